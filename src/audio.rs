@@ -43,6 +43,86 @@ pub struct AudioDescription {
     pub bits_per_channel: u32,
 }
 
+/// Reason [AudioFile::open_for_reading] failed.
+#[derive(Debug)]
+pub enum OpenError {
+    /// The file doesn't exist or couldn't be read.
+    FileNotFound,
+    /// The file isn't in a container format we recognize.
+    UnsupportedFileType,
+    /// The container format is recognized, but not the format of the audio
+    /// data within it.
+    UnsupportedDataFormat,
+}
+
+/// Whether [decode_to_pcm16] supports an audio format.
+pub fn can_decode_to_pcm16(desc: &AudioDescription) -> bool {
+    let channels_ok = desc.channels_per_frame == 1 || desc.channels_per_frame == 2;
+    let format_ok = match desc.format {
+        AudioFormat::AppleIma4 => true,
+        AudioFormat::LinearPcm { is_float, .. } => matches!(
+            (is_float, desc.bits_per_channel),
+            (false, 8 | 16) | (true, 32)
+        ),
+    };
+    channels_ok && format_ok
+}
+
+/// Decode whole packets of audio data to interleaved 16-bit samples.
+pub fn decode_to_pcm16(desc: &AudioDescription, data: &[u8]) -> Vec<i16> {
+    match desc.format {
+        AudioFormat::AppleIma4 => {
+            // Each packet contains a 34-byte block for each channel in turn.
+            let channels = desc.channels_per_frame as usize;
+            let mut samples = Vec::with_capacity((data.len() / 34) * 64);
+            for packet in data.chunks_exact(34 * channels) {
+                let blocks: Vec<[i16; 64]> = packet
+                    .chunks_exact(34)
+                    .map(|block| decode_ima4(block.try_into().unwrap()))
+                    .collect();
+                for i in 0..64 {
+                    samples.extend(blocks.iter().map(|block| block[i]));
+                }
+            }
+            samples
+        }
+        AudioFormat::LinearPcm {
+            is_float,
+            is_little_endian,
+        } => match (is_float, desc.bits_per_channel) {
+            // 8-bit samples are unsigned, see AudioFile::read_bytes().
+            (false, 8) => data
+                .iter()
+                .map(|&sample| (i16::from(sample) - 128) << 8)
+                .collect(),
+            (false, 16) => data
+                .chunks_exact(2)
+                .map(|sample| {
+                    let sample = sample.try_into().unwrap();
+                    if is_little_endian {
+                        i16::from_le_bytes(sample)
+                    } else {
+                        i16::from_be_bytes(sample)
+                    }
+                })
+                .collect(),
+            (true, 32) => data
+                .chunks_exact(4)
+                .map(|sample| {
+                    let sample = sample.try_into().unwrap();
+                    let sample = if is_little_endian {
+                        f32::from_le_bytes(sample)
+                    } else {
+                        f32::from_be_bytes(sample)
+                    };
+                    (sample.clamp(-1.0, 1.0) * 32767.0) as i16
+                })
+                .collect(),
+            _ => unreachable!(), // rejected by can_decode_to_pcm16()
+        },
+    }
+}
+
 pub struct AudioFile(AudioFileInner);
 enum AudioFileInner {
     Wave(hound::WavReader<Cursor<Vec<u8>>>),
@@ -51,9 +131,11 @@ enum AudioFileInner {
 }
 
 impl AudioFile {
-    pub fn open_for_reading<P: AsRef<GuestPath>>(path: P, fs: &Fs) -> Result<Self, ()> {
+    pub fn open_for_reading<P: AsRef<GuestPath>>(path: P, fs: &Fs) -> Result<Self, OpenError> {
         // TODO: it would be better not to load the whole file at once
-        let bytes = fs.read(path.as_ref())?;
+        let bytes = fs
+            .read(path.as_ref())
+            .map_err(|_| OpenError::FileNotFound)?;
 
         // Both WavReader::new() and CafPacketReader::new() consume the reader
         // (in this case, a Cursor) passed to them. This is a bit annoying
@@ -64,9 +146,37 @@ impl AudioFile {
 
         if hound::WavReader::new(Cursor::new(&bytes)).is_ok() {
             let reader = hound::WavReader::new(Cursor::new(bytes)).unwrap();
+            let spec = reader.spec();
+            // See audio_description().
+            if !matches!(spec.bits_per_sample, 8 | 16)
+                || spec.sample_format != hound::SampleFormat::Int
+            {
+                log!(
+                    "Audio file at path {:?} has unsupported WAVE format: {:?}",
+                    path.as_ref(),
+                    spec
+                );
+                return Err(OpenError::UnsupportedDataFormat);
+            }
             Ok(AudioFile(AudioFileInner::Wave(reader)))
         } else if caf::CafPacketReader::new(Cursor::new(&bytes), vec![]).is_ok() {
             let reader = caf::CafPacketReader::new(Cursor::new(bytes), vec![]).unwrap();
+            let desc = &reader.audio_desc;
+            // See audio_description().
+            let supported = match desc.format_id {
+                caf::FormatType::LinearPcm => (desc.format_flags & !3) == 0,
+                caf::FormatType::AppleIma4 => desc.format_flags == 0,
+                _ => false,
+            };
+            // Variable packet sizes aren't implemented yet.
+            if !supported || desc.bytes_per_packet == 0 {
+                log!(
+                    "Audio file at path {:?} has unsupported CAF format: {:?}",
+                    path.as_ref(),
+                    desc.format_id
+                );
+                return Err(OpenError::UnsupportedDataFormat);
+            }
             Ok(AudioFile(AudioFileInner::Caf(reader)))
         // TODO: Real MP3 container handling. Currently we are immediately
         // decoding the entire file to PCM and acting as if it's a PCM file,
@@ -79,7 +189,7 @@ impl AudioFile {
                 "Could not decode audio file at path {:?}, likely an unimplemented file format.",
                 path.as_ref()
             );
-            Err(())
+            Err(OpenError::UnsupportedFileType)
         }
     }
 
@@ -221,6 +331,15 @@ impl AudioFile {
         self.packet_size_fixed() // variable size not implemented
     }
 
+    /// Read the whole file and decode it to interleaved 16-bit samples. Check
+    /// [can_decode_to_pcm16] first.
+    pub fn read_all_to_pcm16(&mut self) -> Result<Vec<i16>, ()> {
+        let mut data = vec![0u8; self.byte_count().try_into().unwrap()];
+        let bytes_read = self.read_bytes(0, &mut data)?;
+        data.truncate(bytes_read);
+        Ok(decode_to_pcm16(&self.audio_description(), &data))
+    }
+
     /// Read `buffer.len()` bytes of audio data from byte offset `offset`.
     /// Returns the number of bytes read.
     pub fn read_bytes(&mut self, offset: u64, buffer: &mut [u8]) -> Result<usize, ()> {
@@ -69,6 +69,14 @@ extern "C" {
     pub fn alcGetString(device: *mut ALCdevice, param: ALCenum) -> *const ALCchar;
 }
 
+// === alext.h ===
+
+// ALC_SOFT_pause_device
+extern "C" {
+    pub fn alcDevicePauseSOFT(device: *mut ALCdevice);
+    pub fn alcDeviceResumeSOFT(device: *mut ALCdevice);
+}
+
 // === al.h ===
 
 #[allow(dead_code)]
@@ -93,8 +101,17 @@ use al_types::*;
 
 pub const AL_NO_ERROR: ALenum = 0;
 
+pub const AL_POSITION: ALenum = 0x1004;
+pub const AL_DIRECTION: ALenum = 0x1005;
+pub const AL_VELOCITY: ALenum = 0x1006;
+pub const AL_LOOPING: ALenum = 0x1007;
+pub const AL_BUFFER: ALenum = 0x1009;
+pub const AL_GAIN: ALenum = 0x100A;
+
 pub const AL_MAX_GAIN: ALenum = 0x100E;
 
+pub const AL_ORIENTATION: ALenum = 0x100F;
+
 pub const AL_SOURCE_STATE: ALenum = 0x1010;
 
 pub const AL_INITIAL: ALenum = 0x1011;
@@ -105,16 +122,28 @@ pub const AL_STOPPED: ALenum = 0x1014;
 pub const AL_BUFFERS_QUEUED: ALenum = 0x1015;
 pub const AL_BUFFERS_PROCESSED: ALenum = 0x1016;
 
+pub const AL_SEC_OFFSET: ALenum = 0x1024;
+
 pub const AL_FORMAT_MONO8: ALenum = 0x1100;
 pub const AL_FORMAT_MONO16: ALenum = 0x1101;
 pub const AL_FORMAT_STEREO8: ALenum = 0x1102;
 pub const AL_FORMAT_STEREO16: ALenum = 0x1103;
 
+pub const AL_FREQUENCY: ALenum = 0x2001;
+pub const AL_BITS: ALenum = 0x2002;
+pub const AL_CHANNELS: ALenum = 0x2003;
+pub const AL_SIZE: ALenum = 0x2004;
+
 extern "C" {
     pub fn alGetError() -> ALenum;
 
     pub fn alDistanceModel(value: ALenum);
 
+    pub fn alGetFloat(param: ALenum) -> ALfloat;
+    pub fn alGetFloatv(param: ALenum, values: *mut ALfloat);
+    pub fn alGetInteger(param: ALenum) -> ALint;
+    pub fn alGetIntegerv(param: ALenum, values: *mut ALint);
+
     pub fn alGetEnumValue(enumName: *const ALchar) -> ALenum;
 
     pub fn alIsBuffer(buffer: ALuint) -> ALboolean;
@@ -184,6 +213,11 @@ extern "C" {
     pub fn alSourceStop(source: ALuint);
     pub fn alSourceRewind(source: ALuint);
 
+    pub fn alSourcePlayv(n: ALsizei, sources: *const ALuint);
+    pub fn alSourcePausev(n: ALsizei, sources: *const ALuint);
+    pub fn alSourceStopv(n: ALsizei, sources: *const ALuint);
+    pub fn alSourceRewindv(n: ALsizei, sources: *const ALuint);
+
     pub fn alSourceQueueBuffers(source: ALuint, nb: ALsizei, buffers: *const ALuint);
     pub fn alSourceUnqueueBuffers(source: ALuint, nb: ALsizei, buffers: *mut ALuint);
 
@@ -198,6 +232,10 @@ extern "C" {
         samplerate: ALsizei,
     );
 
+    pub fn alGetBufferf(buffer: ALuint, param: ALenum, value: *mut ALfloat);
+    pub fn alGetBufferi(buffer: ALuint, param: ALenum, value: *mut ALint);
+
     pub fn alDopplerFactor(dopplerFactor: ALfloat);
     pub fn alDopplerVelocity(dopplerVelocity: ALfloat);
+    pub fn alSpeedOfSound(value: ALfloat);
 }
@@ -95,7 +95,7 @@ pub use crate::export_c_func; // #[macro_export] is weird...
 pub enum HostConstant {
     NSString(&'static str),
     NullPtr,
-    Custom(fn(&mut Mem) -> ConstVoidPtr),
+    Custom(fn(&mut Environment) -> ConstVoidPtr),
 }
 
 /// Type for lists of constants exported by host implementations of frameworks.
@@ -431,7 +431,7 @@ impl Dyld {
                     let null_ptr_ptr = env.mem.alloc_and_write(null_ptr);
                     null_ptr_ptr.cast().cast_const()
                 }
-                HostConstant::Custom(f) => f(&mut env.mem),
+                HostConstant::Custom(f) => f(env),
             };
             env.mem.write(symbol_ptr_ptr, symbol_ptr.cast());
         }
@@ -6,7 +6,9 @@
 //! Separate module just for the constant lists, since this will probably be a
 //! very long and frequently-updated list.
 
-use crate::frameworks::{core_foundation, core_graphics, foundation, media_player, opengles};
+use crate::frameworks::{
+    core_foundation, core_graphics, foundation, media_player, opengles, uikit,
+};
 use crate::libc;
 
 /// All the lists of constants that the linker should search through.
@@ -18,9 +20,16 @@ pub const CONSTANT_LISTS: &[super::ConstantExports] = &[
     core_graphics::cg_affine_transform::CONSTANTS,
     core_graphics::cg_color_space::CONSTANTS,
     core_graphics::cg_geometry::CONSTANTS,
+    foundation::ns_error::CONSTANTS,
     foundation::ns_exception::CONSTANTS,
+    foundation::ns_file_manager::CONSTANTS,
     foundation::ns_locale::CONSTANTS,
     foundation::ns_run_loop::CONSTANTS,
+    foundation::ns_xml_parser::CONSTANTS,
     media_player::movie_player::CONSTANTS,
     opengles::eagl::CONSTANTS,
+    uikit::ui_application::CONSTANTS,
+    uikit::ui_device::CONSTANTS,
+    uikit::ui_image_picker_controller::CONSTANTS,
+    uikit::ui_pasteboard::CONSTANTS,
 ];
@@ -8,6 +8,7 @@
 //! Unlike its siblings, this module should be considered private and only used
 //! via the re-exports one level up.
 
+mod clock;
 mod mutex;
 
 use crate::abi::GuestRet;
@@ -20,6 +21,7 @@ use crate::{
 use std::net::TcpListener;
 use std::time::{Duration, Instant};
 
+pub use clock::Clock;
 pub use mutex::{MutexId, MutexType, PTHREAD_MUTEX_DEFAULT};
 
 /// Index into the [Vec] of threads. Thread 0 is always the main thread.
@@ -75,8 +77,9 @@ impl Thread {
 /// The struct containing the entire emulator state. Methods are provided for
 /// execution and management of threads.
 pub struct Environment {
-    /// Reference point for various timing functions.
-    pub startup_time: Instant,
+    /// The emulated clock, which all guest-visible timing functions should
+    /// use.
+    pub clock: Clock,
     pub bundle: bundle::Bundle,
     pub fs: fs::Fs,
     /// The window is only absent when running in headless mode.
@@ -141,7 +144,7 @@ impl Environment {
         options: options::Options,
         env_for_salvage: Option<Environment>,
     ) -> Result<Environment, String> {
-        let startup_time = Instant::now();
+        let clock = Clock::new();
 
         // Extract things to salvage from the old environment, and then drop it.
         // This needs to be done before creating a new window, because SDL2 only
@@ -254,7 +257,7 @@ impl Environment {
         };
 
         let mut env = Environment {
-            startup_time,
+            clock,
             bundle,
             fs,
             window,
@@ -349,7 +352,7 @@ impl Environment {
         let bundle = bundle::Bundle::new_fake_bundle();
         let fs = fs::Fs::new_fake_fs();
 
-        let startup_time = Instant::now();
+        let clock = Clock::new();
 
         let icon = None;
         let launch_image = None;
@@ -387,7 +390,7 @@ impl Environment {
         };
 
         let mut env = Environment {
-            startup_time,
+            clock,
             bundle,
             fs,
             window,
@@ -754,10 +757,15 @@ impl Environment {
         // GDB doesn't seem to manage to produce a useful stack trace, so
         // let's print our own.
         self.stack_trace();
-        self.gdb_server
-            .as_mut()
-            .unwrap()
-            .wait_for_debugger(reason, &mut self.cpu, &mut self.mem)
+        // The app shouldn't see time pass while it's stopped in the debugger.
+        self.clock.pause();
+        let step = self.gdb_server.as_mut().unwrap().wait_for_debugger(
+            reason,
+            &mut self.cpu,
+            &mut self.mem,
+        );
+        self.clock.resume();
+        step
     }
 
     #[inline(always)]
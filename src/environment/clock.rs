@@ -0,0 +1,135 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! The emulated clock.
+//!
+//! All guest-visible time (`time()`, `gettimeofday()`, `mach_absolute_time()`,
+//! `NSDate`, etc) should come from here rather than directly from the host, so
+//! that it can be frozen while the emulator isn't running the app (e.g. while
+//! the debugger is attached). Otherwise, the app would see time jump forward
+//! when it resumes, and game timers would misbehave.
+
+use std::time::{Duration, Instant, SystemTime};
+
+pub struct Clock {
+    /// The host time at which the clock was last resumed, or [None] if it is
+    /// currently paused.
+    running_since: Option<Instant>,
+    /// The emulated time elapsed up until `running_since` (or until now, if
+    /// paused).
+    elapsed_before: Duration,
+    /// The host's wall-clock time at startup. The emulated wall-clock time is
+    /// this plus the elapsed emulated time.
+    startup_system_time: SystemTime,
+}
+
+impl Clock {
+    /// Create a new clock, starting at zero and running.
+    pub fn new() -> Clock {
+        Clock {
+            running_since: Some(Instant::now()),
+            elapsed_before: Duration::ZERO,
+            startup_system_time: SystemTime::now(),
+        }
+    }
+
+    /// Monotonic emulated time since startup. This never goes backwards.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed_before
+            + self
+                .running_since
+                .map_or(Duration::ZERO, |since| Instant::now().duration_since(since))
+    }
+
+    /// Emulated wall-clock time.
+    pub fn system_time(&self) -> SystemTime {
+        self.startup_system_time + self.elapsed()
+    }
+
+    /// Get the host time at which [Self::elapsed] will reach `elapsed`,
+    /// assuming the clock keeps running. This is for host code that needs to
+    /// sleep until an emulated time, e.g. the next `NSTimer` firing.
+    pub fn host_instant_at(&self, elapsed: Duration) -> Instant {
+        Instant::now() + elapsed.saturating_sub(self.elapsed())
+    }
+
+    /// Whether the clock is currently stopped by [Self::pause].
+    #[allow(dead_code)]
+    pub fn is_paused(&self) -> bool {
+        self.running_since.is_none()
+    }
+
+    /// Stop the clock. Does nothing if it is already paused.
+    pub fn pause(&mut self) {
+        if let Some(since) = self.running_since.take() {
+            self.elapsed_before += Instant::now().duration_since(since);
+        }
+    }
+
+    /// Restart the clock after [Self::pause]. Does nothing if it is already
+    /// running.
+    pub fn resume(&mut self) {
+        if self.running_since.is_none() {
+            self.running_since = Some(Instant::now());
+        }
+    }
+
+    /// Move the clock forward without waiting, e.g. to keep frame pacing
+    /// consistent while paused.
+    #[allow(dead_code)]
+    pub fn advance(&mut self, by: Duration) {
+        self.elapsed_before += by;
+    }
+
+    /// Set the elapsed emulated time directly, e.g. to restore it from saved
+    /// state. Note that this can make [Self::elapsed] go backwards, so it
+    /// shouldn't be used while the app is running.
+    #[allow(dead_code)]
+    pub fn set_elapsed(&mut self, elapsed: Duration) {
+        self.elapsed_before = elapsed;
+        if self.running_since.is_some() {
+            self.running_since = Some(Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pause_freezes_time() {
+        let mut clock = Clock::new();
+        assert!(!clock.is_paused());
+        clock.pause();
+        assert!(clock.is_paused());
+        let frozen = clock.elapsed();
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(clock.elapsed(), frozen);
+        assert_eq!(clock.system_time(), clock.startup_system_time + frozen);
+
+        clock.resume();
+        assert!(!clock.is_paused());
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(clock.elapsed() > frozen);
+    }
+
+    #[test]
+    fn advance_and_set_elapsed() {
+        let mut clock = Clock::new();
+        clock.pause();
+        clock.set_elapsed(Duration::from_secs(100));
+        assert_eq!(clock.elapsed(), Duration::from_secs(100));
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(clock.elapsed(), Duration::from_millis(101_500));
+        clock.set_elapsed(Duration::from_secs(5));
+        assert_eq!(clock.elapsed(), Duration::from_secs(5));
+
+        clock.resume();
+        clock.set_elapsed(Duration::from_secs(7));
+        assert!(clock.elapsed() >= Duration::from_secs(7));
+        assert!(clock.elapsed() < Duration::from_secs(8));
+    }
+}
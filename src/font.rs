@@ -15,6 +15,7 @@
 
 use crate::paths;
 use rusttype::{Point, Scale};
+use std::borrow::Cow;
 use std::io::Read;
 
 pub struct Font {
@@ -33,6 +34,24 @@ pub enum WrapMode {
     Char,
 }
 
+/// What to do with text that doesn't fit within a maximum height.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Overflow {
+    /// Lines that don't fit are left out.
+    Omit,
+    /// The rest of the paragraph goes on the last line without wrapping, for
+    /// the caller to clip.
+    Clip,
+    /// The last line is shortened to fit with an ellipsis at its start.
+    TruncateHead,
+    /// The last line is shortened to fit with an ellipsis in its middle.
+    TruncateMiddle,
+    /// The last line is shortened to fit with an ellipsis at its end.
+    TruncateTail,
+}
+
+const ELLIPSIS: &str = "\u{2026}";
+
 fn scale(font_size: f32) -> Scale {
     // iPhone OS's interpretation of font size is slightly different, reason
     // unknown. This is not the same as the Windows pt vs Mac pt issue.
@@ -40,6 +59,22 @@ fn scale(font_size: f32) -> Scale {
     Scale::uniform(font_size * 1.125)
 }
 
+/// Vertical metrics of a font at a particular size, see [Font::metrics].
+#[derive(Copy, Clone, Debug)]
+pub struct FontMetrics {
+    /// Distance from the baseline to the top of the tallest glyphs (positive).
+    pub ascent: f32,
+    /// Distance from the baseline to the bottom of the lowest glyphs
+    /// (negative).
+    pub descent: f32,
+    /// Gap between the descent of one line and the ascent of the next.
+    pub line_gap: f32,
+    /// Height of capital letters above the baseline.
+    pub cap_height: f32,
+    /// Height of lowercase letters above the baseline.
+    pub x_height: f32,
+}
+
 /// Helper for [Font::draw], used for the `draw_glyph` callback.
 pub struct RasterGlyph<'a> {
     origin: (f32, f32),
@@ -107,6 +142,30 @@ impl Font {
         (v_metrics.ascent - v_metrics.descent, v_metrics.line_gap)
     }
 
+    /// Get the font's metrics at a given font size. These are consistent with
+    /// [Font::calculate_text_size] and [Font::draw]: each line of text takes
+    /// up `ascent - descent`, plus `line_gap` between lines.
+    pub fn metrics(&self, font_size: f32) -> FontMetrics {
+        let scale = scale(font_size);
+        let v_metrics = self.font.v_metrics(scale);
+        // The OS/2 table has these, but RustType doesn't expose it, so measure
+        // the glyphs instead. RustType's y axis points down.
+        let glyph_height = |c: char| {
+            self.font
+                .glyph(c)
+                .scaled(scale)
+                .exact_bounding_box()
+                .map_or(0.0, |bounds| -bounds.min.y)
+        };
+        FontMetrics {
+            ascent: v_metrics.ascent,
+            descent: v_metrics.descent,
+            line_gap: v_metrics.line_gap,
+            cap_height: glyph_height('H'),
+            x_height: glyph_height('x'),
+        }
+    }
+
     /// Calculate the width of a line. This does not handle newlines!
     fn calculate_line_width(&self, font_size: f32, line: &str) -> f32 {
         let mut line_x_min: f32 = 0.0;
@@ -257,18 +316,105 @@ impl Font {
         lines
     }
 
+    /// Shorten a line so it fits in a width, marking where text was removed
+    /// with an ellipsis. The ellipsis is added even if nothing needs removing.
+    fn truncate_line(
+        &self,
+        font_size: f32,
+        line: &str,
+        width: Option<f32>,
+        overflow: Overflow,
+    ) -> (f32, String) {
+        let chars: Vec<char> = line.chars().collect();
+        let mut candidate = String::new();
+        for keep in (0..=chars.len()).rev() {
+            let (head, tail) = match overflow {
+                Overflow::TruncateHead => (0, keep),
+                Overflow::TruncateMiddle => (keep - keep / 2, keep / 2),
+                Overflow::TruncateTail => (keep, 0),
+                Overflow::Omit | Overflow::Clip => unreachable!(),
+            };
+            candidate.clear();
+            candidate.extend(&chars[..head]);
+            // Whitespace next to the ellipsis looks odd.
+            candidate.truncate(candidate.trim_end().len());
+            candidate.push_str(ELLIPSIS);
+            let tail: String = chars[chars.len() - tail..].iter().collect();
+            candidate.push_str(tail.trim_start());
+            let candidate_width = self.calculate_line_width(font_size, &candidate);
+            if keep == 0 || width.map_or(true, |width| candidate_width <= width) {
+                return (candidate_width, candidate);
+            }
+        }
+        unreachable!()
+    }
+
+    /// Break text into lines with known widths, then apply the maximum height,
+    /// if any.
+    fn fit_lines<'a>(
+        &self,
+        font_size: f32,
+        text: &'a str,
+        wrap: Option<(f32, WrapMode)>,
+        fit: Option<(f32, Overflow)>,
+    ) -> Vec<(f32, Cow<'a, str>)> {
+        let mut lines: Vec<(f32, Cow<'a, str>)> = self
+            .break_lines(font_size, text, wrap)
+            .into_iter()
+            .map(|(width, line)| (width, Cow::Borrowed(line)))
+            .collect();
+
+        let Some((max_height, overflow)) = fit else {
+            return lines;
+        };
+        let (line_height, line_gap) = self.line_height_and_gap(font_size);
+        // At least one line is always shown, even if it doesn't fit.
+        let max_lines = ((max_height + line_gap) / (line_height + line_gap)).floor();
+        let max_lines = (max_lines as usize).max(1);
+        if lines.len() <= max_lines {
+            return lines;
+        }
+
+        lines.truncate(max_lines);
+        if overflow == Overflow::Omit {
+            return lines;
+        }
+
+        // Lines are slices of the text, so this finds where the last line
+        // starts within it.
+        let Cow::Borrowed(last_line) = lines.last().unwrap().1 else {
+            unreachable!();
+        };
+        let offset = last_line.as_ptr() as usize - text.as_ptr() as usize;
+        let rest_of_paragraph = text[offset..].lines().next().unwrap_or("");
+        let last = if overflow == Overflow::Clip {
+            let width = self.calculate_line_width(font_size, rest_of_paragraph);
+            (width, Cow::Borrowed(rest_of_paragraph))
+        } else {
+            let width = wrap.map(|(width, _)| width);
+            let (width, line) = self.truncate_line(font_size, rest_of_paragraph, width, overflow);
+            (width, Cow::Owned(line))
+        };
+        *lines.last_mut().unwrap() = last;
+        lines
+    }
+
     /// Calculate the on-screen width and height of text with a given font size.
+    ///
+    /// `wrap` is the width to wrap lines to and how to do it, and `fit` is the
+    /// maximum height and how to shorten text that exceeds it.
     pub fn calculate_text_size(
         &self,
         font_size: f32,
         text: &str,
         wrap: Option<(f32, WrapMode)>,
+        fit: Option<(f32, Overflow)>,
     ) -> (f32, f32) {
-        let lines = self.break_lines(font_size, text, wrap);
+        let lines = self.fit_lines(font_size, text, wrap, fit);
 
         let width = lines
             .iter()
-            .fold(0f32, |widest, &(line_width, _line)| widest.max(line_width));
+            .fold(0f32, |widest, &(line_width, _)| widest.max(line_width));
         let (line_height, line_gap) = self.line_height_and_gap(font_size);
         let height =
             line_height * (lines.len() as f32) + line_gap * (lines.len().saturating_sub(1) as f32);
@@ -278,19 +424,23 @@ impl Font {
 
     /// Draw text. Calls the provided callback for each glyph that is to be
     /// drawn. Assumes y starts at the bottom-left corner and points upwards.
+    ///
+    /// `wrap` and `fit` are as for [Font::calculate_text_size].
+    #[allow(clippy::too_many_arguments)]
     pub fn draw<F: FnMut(RasterGlyph)>(
         &self,
         font_size: f32,
         text: &str,
         origin: (f32, f32),
         wrap: Option<(f32, WrapMode)>,
+        fit: Option<(f32, Overflow)>,
         alignment: TextAlignment,
         mut draw_glyph: F,
     ) {
         // TODO: This code has gone through a rather traumatic series of y sign
         //       flips and might benefit from refactoring for clarity?
 
-        let lines = self.break_lines(font_size, text, wrap);
+        let lines = self.fit_lines(font_size, text, wrap, fit);
 
         let mut line_y = self.font.v_metrics(scale(font_size)).ascent;
         let (line_height, line_gap) = self.line_height_and_gap(font_size);
@@ -314,7 +464,7 @@ impl Font {
                 TextAlignment::Right => -line_width,
             };
             for glyph in self.font.layout(
-                line_text,
+                &line_text,
                 scale(font_size),
                 Point {
                     x: origin.0 + line_x_offset,
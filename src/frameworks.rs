@@ -21,6 +21,7 @@
 #![allow(clippy::too_many_arguments)] // It's not our fault!
 
 pub mod audio_toolbox;
+pub mod av_foundation;
 pub mod carbon_core;
 pub mod core_animation;
 pub mod core_audio_types;
@@ -37,6 +38,7 @@ pub mod uikit;
 #[derive(Default)]
 pub struct State {
     audio_toolbox: audio_toolbox::State,
+    av_foundation: av_foundation::State,
     core_animation: core_animation::State,
     foundation: foundation::State,
     media_player: media_player::State,
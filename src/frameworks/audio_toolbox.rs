@@ -25,9 +25,12 @@ pub mod audio_file;
 pub mod audio_queue;
 pub mod audio_services;
 pub mod audio_session;
+pub mod ext_audio_file;
 
 #[derive(Default)]
 pub struct State {
     audio_file: audio_file::State,
     audio_queue: audio_queue::State,
+    audio_services: audio_services::State,
+    ext_audio_file: ext_audio_file::State,
 }
@@ -42,6 +42,8 @@ unsafe impl SafeRead for OpaqueAudioFileID {}
 type AudioFileID = MutPtr<OpaqueAudioFileID>;
 
 const kAudioFileFileNotFoundError: OSStatus = -43;
+const kAudioFileUnsupportedFileTypeError: OSStatus = fourcc(b"typ?") as _;
+const kAudioFileUnsupportedDataFormatError: OSStatus = fourcc(b"fmt?") as _;
 const kAudioFileBadPropertySizeError: OSStatus = fourcc(b"!siz") as _;
 const kAudioFileUnsupportedProperty: OSStatus = fourcc(b"pty?") as _;
 
@@ -73,15 +75,25 @@ fn AudioFileOpenURL(
 
     // The hint is optional and is supposed to only be used for certain file
     // formats that can't be uniquely identified, which we don't support so far.
-    assert!(in_file_type_hint == 0);
+    // Apps nonetheless often pass e.g. kAudioFileCAFType, so it's ignored.
+    if in_file_type_hint != 0 {
+        log_dbg!(
+            "AudioFileOpenURL() ignoring file type hint {}",
+            debug_fourcc(in_file_type_hint)
+        );
+    }
 
     let path = to_rust_path(env, in_file_ref);
-    let Ok(audio_file) = audio::AudioFile::open_for_reading(path, &env.fs) else {
-        log!(
-            "Warning: AudioFileOpenURL() for path {:?} failed",
-            in_file_ref
-        );
-        return kAudioFileFileNotFoundError;
+    let audio_file = match audio::AudioFile::open_for_reading(path, &env.fs) {
+        Ok(audio_file) => audio_file,
+        Err(err) => {
+            log!(
+                "Warning: AudioFileOpenURL() for path {:?} failed: {:?}",
+                in_file_ref,
+                err
+            );
+            return open_error_to_status(err);
+        }
     };
 
     let host_object = AudioFileHostObject { audio_file };
@@ -102,13 +114,98 @@ fn AudioFileOpenURL(
     0 // success
 }
 
-fn property_size(property_id: AudioFilePropertyID) -> GuestUSize {
+pub(super) fn open_error_to_status(err: audio::OpenError) -> OSStatus {
+    match err {
+        audio::OpenError::FileNotFound => kAudioFileFileNotFoundError,
+        audio::OpenError::UnsupportedFileType => kAudioFileUnsupportedFileTypeError,
+        audio::OpenError::UnsupportedDataFormat => kAudioFileUnsupportedDataFormatError,
+    }
+}
+
+/// Get the `AudioStreamBasicDescription` describing a file's data format.
+pub(super) fn data_format(audio_file: &audio::AudioFile) -> AudioStreamBasicDescription {
+    let audio::AudioDescription {
+        sample_rate,
+        format,
+        bytes_per_packet,
+        frames_per_packet,
+        channels_per_frame,
+        bits_per_channel,
+    } = audio_file.audio_description();
+
+    match format {
+        audio::AudioFormat::LinearPcm {
+            is_float,
+            is_little_endian,
+        } => {
+            let is_packed = (bits_per_channel * channels_per_frame * frames_per_packet)
+                == (bytes_per_packet * 8);
+            let format_flags = (u32::from(is_float) * kAudioFormatFlagIsFloat)
+                | (u32::from((!is_float) && matches!(bits_per_channel, 16 | 24))
+                    * kAudioFormatFlagIsSignedInteger)
+                | (u32::from(is_packed) * kAudioFormatFlagIsPacked)
+                | (u32::from(!is_little_endian) * kAudioFormatFlagIsBigEndian);
+            AudioStreamBasicDescription {
+                sample_rate,
+                format_id: kAudioFormatLinearPCM,
+                format_flags,
+                bytes_per_packet,
+                frames_per_packet,
+                bytes_per_frame: bytes_per_packet / frames_per_packet,
+                channels_per_frame,
+                bits_per_channel,
+                _reserved: 0,
+            }
+        }
+        audio::AudioFormat::AppleIma4 => {
+            AudioStreamBasicDescription {
+                sample_rate,
+                format_id: kAudioFormatAppleIMA4,
+                format_flags: 0,
+                bytes_per_packet,
+                frames_per_packet,
+                bytes_per_frame: 0, // compressed
+                channels_per_frame,
+                bits_per_channel,
+                _reserved: 0,
+            }
+        }
+    }
+}
+
+/// Open and decode a whole audio file to interleaved 16-bit samples, for APIs
+/// that play sounds without streaming them. Returns the samples, the number of
+/// channels and the sample rate, or a status code and error description.
+pub(crate) fn decode_file_to_pcm16(
+    env: &mut Environment,
+    url: CFURLRef,
+) -> Result<(Vec<i16>, u32, f64), (OSStatus, String)> {
+    let path = to_rust_path(env, url);
+    let mut audio_file = audio::AudioFile::open_for_reading(&path, &env.fs).map_err(|err| {
+        let description = format!("Couldn't open audio file {:?}: {:?}", path, err);
+        (open_error_to_status(err), description)
+    })?;
+
+    let desc = audio_file.audio_description();
+    if !audio::can_decode_to_pcm16(&desc) {
+        let description = format!("Can't decode audio file {:?}: {:?}", path, desc);
+        return Err((kAudioFileUnsupportedDataFormatError, description));
+    }
+
+    let samples = audio_file.read_all_to_pcm16().map_err(|_| {
+        let description = format!("Couldn't read audio file {:?}", path);
+        (kAudioFileUnsupportedDataFormatError, description)
+    })?;
+    Ok((samples, desc.channels_per_frame, desc.sample_rate))
+}
+
+fn property_size(property_id: AudioFilePropertyID) -> Option<GuestUSize> {
     match property_id {
-        kAudioFilePropertyDataFormat => guest_size_of::<AudioStreamBasicDescription>(),
-        kAudioFilePropertyAudioDataByteCount => guest_size_of::<u64>(),
-        kAudioFilePropertyAudioDataPacketCount => guest_size_of::<u64>(),
-        kAudioFilePropertyPacketSizeUpperBound => guest_size_of::<u32>(),
-        _ => unimplemented!("Unimplemented property ID: {}", debug_fourcc(property_id)),
+        kAudioFilePropertyDataFormat => Some(guest_size_of::<AudioStreamBasicDescription>()),
+        kAudioFilePropertyAudioDataByteCount => Some(guest_size_of::<u64>()),
+        kAudioFilePropertyAudioDataPacketCount => Some(guest_size_of::<u64>()),
+        kAudioFilePropertyPacketSizeUpperBound => Some(guest_size_of::<u32>()),
+        _ => None,
     }
 }
 
@@ -135,8 +232,15 @@ fn AudioFileGetPropertyInfo(
         }
         return kAudioFileUnsupportedProperty;
     }
+    let Some(size) = property_size(in_property_id) else {
+        log!(
+            "Warning: AudioFileGetPropertyInfo() for unimplemented property ID {}",
+            debug_fourcc(in_property_id)
+        );
+        return kAudioFileUnsupportedProperty;
+    };
     if !out_data_size.is_null() {
-        env.mem.write(out_data_size, property_size(in_property_id));
+        env.mem.write(out_data_size, size);
     }
     if !is_writable.is_null() {
         env.mem.write(is_writable, 0); // TODO: probably not always correct
@@ -153,7 +257,13 @@ fn AudioFileGetProperty(
 ) -> OSStatus {
     return_if_null!(in_audio_file);
 
-    let required_size = property_size(in_property_id);
+    let Some(required_size) = property_size(in_property_id) else {
+        log!(
+            "Warning: AudioFileGetProperty() for unimplemented property ID {}",
+            debug_fourcc(in_property_id)
+        );
+        return kAudioFileUnsupportedProperty;
+    };
     if env.mem.read(io_data_size) != required_size {
         log!("Warning: AudioFileGetProperty() failed");
         return kAudioFileBadPropertySizeError;
@@ -166,53 +276,7 @@ fn AudioFileGetProperty(
 
     match in_property_id {
         kAudioFilePropertyDataFormat => {
-            let audio::AudioDescription {
-                sample_rate,
-                format,
-                bytes_per_packet,
-                frames_per_packet,
-                channels_per_frame,
-                bits_per_channel,
-            } = host_object.audio_file.audio_description();
-
-            let desc: AudioStreamBasicDescription = match format {
-                audio::AudioFormat::LinearPcm {
-                    is_float,
-                    is_little_endian,
-                } => {
-                    let is_packed = (bits_per_channel * channels_per_frame * frames_per_packet)
-                        == (bytes_per_packet * 8);
-                    let format_flags = (u32::from(is_float) * kAudioFormatFlagIsFloat)
-                        | (u32::from((!is_float) && matches!(bits_per_channel, 16 | 24))
-                            * kAudioFormatFlagIsSignedInteger)
-                        | (u32::from(is_packed) * kAudioFormatFlagIsPacked)
-                        | (u32::from(!is_little_endian) * kAudioFormatFlagIsBigEndian);
-                    AudioStreamBasicDescription {
-                        sample_rate,
-                        format_id: kAudioFormatLinearPCM,
-                        format_flags,
-                        bytes_per_packet,
-                        frames_per_packet,
-                        bytes_per_frame: bytes_per_packet / frames_per_packet,
-                        channels_per_frame,
-                        bits_per_channel,
-                        _reserved: 0,
-                    }
-                }
-                audio::AudioFormat::AppleIma4 => {
-                    AudioStreamBasicDescription {
-                        sample_rate,
-                        format_id: kAudioFormatAppleIMA4,
-                        format_flags: 0,
-                        bytes_per_packet,
-                        frames_per_packet,
-                        bytes_per_frame: 0, // compressed
-                        channels_per_frame,
-                        bits_per_channel,
-                        _reserved: 0,
-                    }
-                }
-            };
+            let desc = data_format(&host_object.audio_file);
             env.mem.write(out_property_data.cast(), desc);
         }
         kAudioFilePropertyAudioDataByteCount => {
@@ -17,8 +17,8 @@ use crate::dyld::{export_c_func, FunctionExports};
 use crate::frameworks::carbon_core::OSStatus;
 use crate::frameworks::core_audio_types::{
     debug_fourcc, fourcc, kAudioFormatAppleIMA4, kAudioFormatFlagIsBigEndian,
-    kAudioFormatFlagIsFloat, kAudioFormatFlagIsPacked, kAudioFormatLinearPCM,
-    AudioStreamBasicDescription,
+    kAudioFormatFlagIsFloat, kAudioFormatFlagIsPacked, kAudioFormatFlagIsSignedInteger,
+    kAudioFormatLinearPCM, AudioStreamBasicDescription,
 };
 use crate::frameworks::core_foundation::cf_run_loop::{
     kCFRunLoopCommonModes, CFRunLoopGetMain, CFRunLoopMode, CFRunLoopRef,
@@ -63,8 +63,29 @@ impl State {
     }
 }
 
+/// Make the internal OpenAL context current, for other frameworks that are
+/// built on Audio Toolbox's audio playback (e.g. `AVAudioPlayer`).
+pub(crate) fn make_internal_al_context_current(env: &mut Environment) -> ContextManager {
+    State::get(&mut env.framework_state).make_al_context_current()
+}
+
+/// Pause or resume playback on the internal OpenAL device, if it has been
+/// created, e.g. while the app is suspended.
+pub(crate) fn set_internal_al_device_paused(env: &mut Environment, paused: bool) {
+    let state = State::get(&mut env.framework_state);
+    if let Some((device, _context)) = state.al_device_and_context {
+        unsafe {
+            if paused {
+                al::alcDevicePauseSOFT(device);
+            } else {
+                al::alcDeviceResumeSOFT(device);
+            }
+        }
+    }
+}
+
 #[must_use]
-struct ContextManager(*mut ALCcontext);
+pub(crate) struct ContextManager(*mut ALCcontext);
 impl ContextManager {
     pub fn make_active(new_context: *mut ALCcontext) -> ContextManager {
         let old_context = unsafe { al::alcGetCurrentContext() };
@@ -433,6 +454,7 @@ fn is_supported_audio_format(format: &AudioStreamBasicDescription) -> bool {
     let &AudioStreamBasicDescription {
         format_id,
         format_flags,
+        bytes_per_frame,
         channels_per_frame,
         bits_per_channel,
         ..
@@ -441,10 +463,13 @@ fn is_supported_audio_format(format: &AudioStreamBasicDescription) -> bool {
         kAudioFormatAppleIMA4 => (channels_per_frame == 1) || (channels_per_frame == 2),
         kAudioFormatLinearPCM => {
             // TODO: support more PCM formats
+            // Apps don't always set kAudioFormatFlagIsPacked, but the samples
+            // are effectively packed if they fill the whole frame.
+            let is_packed = (format_flags & kAudioFormatFlagIsPacked) != 0
+                || bytes_per_frame == channels_per_frame * (bits_per_channel / 8);
             (channels_per_frame == 1 || channels_per_frame == 2)
                 && (bits_per_channel == 8 || bits_per_channel == 16)
-                && (format_flags & kAudioFormatFlagIsPacked) != 0
-                && (format_flags & kAudioFormatFlagIsBigEndian) == 0
+                && is_packed
                 && (format_flags & kAudioFormatFlagIsFloat) == 0
         }
         _ => false,
@@ -515,7 +540,22 @@ fn decode_buffer(
                 (2, 16) => al::AL_FORMAT_STEREO16,
                 _ => unreachable!(),
             };
-            (f, format.sample_rate as ALsizei, data_slice.to_owned())
+            let mut data = data_slice.to_owned();
+            if format.bits_per_channel == 8 {
+                // OpenAL's 8-bit formats are unsigned.
+                if (format.format_flags & kAudioFormatFlagIsSignedInteger) != 0 {
+                    for sample in data.iter_mut() {
+                        *sample ^= 0x80;
+                    }
+                }
+            } else if (format.format_flags & kAudioFormatFlagIsBigEndian) != 0 {
+                // OpenAL's 16-bit formats use the host's byte order, which is
+                // little-endian on all the hosts we support.
+                for sample in data.chunks_exact_mut(2) {
+                    sample.swap(0, 1);
+                }
+            }
+            (f, format.sample_rate as ALsizei, data)
         }
         _ => unreachable!(),
     }
@@ -797,6 +837,17 @@ fn AudioQueuePause(env: &mut Environment, in_aq: AudioQueueRef) -> OSStatus {
     0 // success
 }
 
+fn AudioQueueFlush(env: &mut Environment, in_aq: AudioQueueRef) -> OSStatus {
+    return_if_null!(in_aq);
+
+    // Every enqueued buffer is given to OpenAL in full eventually, and there's
+    // no decoder state that could hold back the end of the audio, so there's
+    // nothing to flush.
+    log_dbg!("AudioQueueFlush({:?})", in_aq);
+
+    0 // success
+}
+
 fn finish_stopping_audio_queue(env: &mut Environment, in_aq: AudioQueueRef) {
     // OpenAL stop is not done here because it would be redundant in the case
     // of an asynchronous stop, where the audio queue stopping is triggered by
@@ -965,6 +1016,7 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(AudioQueuePrime(_, _, _)),
     export_c_func!(AudioQueueStart(_, _)),
     export_c_func!(AudioQueuePause(_)),
+    export_c_func!(AudioQueueFlush(_)),
     export_c_func!(AudioQueueStop(_, _)),
     export_c_func!(AudioQueueReset(_)),
     export_c_func!(AudioQueueFreeBuffer(_, _)),
@@ -4,19 +4,64 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 //! `AudioServices.h` (Audio Services)
+//!
+//! System sounds are decoded when they're created and played with the same
+//! internal OpenAL context as audio queues. Vibration is simulated with
+//! controller rumble, if there's a controller that supports it.
 
+use super::audio_file::decode_file_to_pcm16;
+use super::audio_queue::make_internal_al_context_current;
+use crate::abi::{CallFromHost, GuestFunction};
+use crate::audio::openal as al;
+use crate::audio::openal::al_types::*;
 use crate::dyld::{export_c_func, FunctionExports};
 use crate::frameworks::carbon_core::OSStatus;
 use crate::frameworks::core_audio_types::fourcc;
+use crate::frameworks::core_foundation::cf_run_loop::{CFRunLoopMode, CFRunLoopRef};
+use crate::frameworks::core_foundation::cf_url::CFURLRef;
 use crate::mem::{MutPtr, MutVoidPtr};
 use crate::Environment;
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Default)]
+pub struct State {
+    system_sounds: HashMap<SystemSoundID, SystemSound>,
+    next_system_sound_id: Option<SystemSoundID>,
+}
+impl State {
+    fn get(framework_state: &mut crate::frameworks::State) -> &mut Self {
+        &mut framework_state.audio_toolbox.audio_services
+    }
+}
+
+struct SystemSound {
+    al_source: ALuint,
+    al_buffer: ALuint,
+    /// Completion routine and its user data.
+    completion: Option<(AudioServicesSystemSoundCompletionProc, MutVoidPtr)>,
+    /// Whether the sound was playing when last checked, so that the end of
+    /// playback can be noticed.
+    is_playing: bool,
+}
 
 /// Usually a FourCC.
 type AudioServicesPropertyID = u32;
 type SystemSoundID = u32;
 
+type AudioServicesSystemSoundCompletionProc = GuestFunction;
+
 const kAudioServicesUnsupportedPropertyError: OSStatus = fourcc(b"pty?") as _;
+const kAudioServicesSystemSoundUnspecifiedError: OSStatus = -1500;
+
 const kSystemSoundID_Vibrate: SystemSoundID = 0x00000FFF;
+/// IDs of sounds created by apps seem to start here, above all the predefined
+/// IDs.
+const FIRST_SYSTEM_SOUND_ID: SystemSoundID = 0x00001001;
+
+/// How long to rumble for when vibrating. The real vibration lasts roughly
+/// this long.
+const VIBRATION_DURATION: Duration = Duration::from_millis(400);
 
 fn AudioServicesGetProperty(
     _env: &mut Environment,
@@ -35,13 +80,225 @@ fn AudioServicesGetProperty(
     }
 }
 
-fn AudioServicesPlaySystemSound(_env: &mut Environment, in_system_sound_id: SystemSoundID) {
-    assert_eq!(in_system_sound_id, kSystemSoundID_Vibrate);
-    log!("TODO: vibration (AudioServicesPlaySystemSound)");
-    // TODO: implement other system sounds
+fn AudioServicesCreateSystemSoundID(
+    env: &mut Environment,
+    in_file_url: CFURLRef,
+    out_system_sound_id: MutPtr<SystemSoundID>,
+) -> OSStatus {
+    return_if_null!(in_file_url);
+
+    let (samples, channels, sample_rate) = match decode_file_to_pcm16(env, in_file_url) {
+        Ok(decoded) => decoded,
+        Err((status, description)) => {
+            log!(
+                "Warning: AudioServicesCreateSystemSoundID() failed: {}",
+                description
+            );
+            return status;
+        }
+    };
+
+    let al_format = if channels == 1 {
+        al::AL_FORMAT_MONO16
+    } else {
+        al::AL_FORMAT_STEREO16
+    };
+    let _context_manager = make_internal_al_context_current(env);
+    let mut al_source = 0;
+    let mut al_buffer = 0;
+    unsafe {
+        al::alGenSources(1, &mut al_source);
+        al::alGenBuffers(1, &mut al_buffer);
+        al::alBufferData(
+            al_buffer,
+            al_format,
+            samples.as_ptr() as *const ALvoid,
+            (samples.len() * 2).try_into().unwrap(),
+            sample_rate as ALsizei,
+        );
+        al::alSourcei(al_source, al::AL_BUFFER, al_buffer as ALint);
+        assert!(al::alGetError() == 0);
+    }
+
+    let state = State::get(&mut env.framework_state);
+    let system_sound_id = state.next_system_sound_id.unwrap_or(FIRST_SYSTEM_SOUND_ID);
+    state.next_system_sound_id = Some(system_sound_id + 1);
+    state.system_sounds.insert(
+        system_sound_id,
+        SystemSound {
+            al_source,
+            al_buffer,
+            completion: None,
+            is_playing: false,
+        },
+    );
+
+    env.mem.write(out_system_sound_id, system_sound_id);
+
+    log_dbg!(
+        "AudioServicesCreateSystemSoundID() created system sound {} for URL {:?}",
+        system_sound_id,
+        in_file_url
+    );
+
+    0 // success
+}
+
+fn AudioServicesDisposeSystemSoundID(
+    env: &mut Environment,
+    in_system_sound_id: SystemSoundID,
+) -> OSStatus {
+    let Some(system_sound) = State::get(&mut env.framework_state)
+        .system_sounds
+        .remove(&in_system_sound_id)
+    else {
+        return kAudioServicesSystemSoundUnspecifiedError;
+    };
+
+    let _context_manager = make_internal_al_context_current(env);
+    unsafe {
+        al::alSourceStop(system_sound.al_source);
+        al::alSourcei(system_sound.al_source, al::AL_BUFFER, 0);
+        al::alDeleteSources(1, &system_sound.al_source);
+        al::alDeleteBuffers(1, &system_sound.al_buffer);
+        assert!(al::alGetError() == 0);
+    }
+
+    log_dbg!(
+        "AudioServicesDisposeSystemSoundID() disposed of system sound {}",
+        in_system_sound_id
+    );
+
+    0 // success
+}
+
+fn AudioServicesPlaySystemSound(env: &mut Environment, in_system_sound_id: SystemSoundID) {
+    if in_system_sound_id == kSystemSoundID_Vibrate {
+        log_dbg!("AudioServicesPlaySystemSound() vibrating");
+        if let Some(window) = env.window.as_mut() {
+            window.rumble(VIBRATION_DURATION);
+        }
+        return;
+    }
+
+    let Some(system_sound) = State::get(&mut env.framework_state)
+        .system_sounds
+        .get_mut(&in_system_sound_id)
+    else {
+        // TODO: implement the built-in system sounds
+        log!(
+            "TODO: AudioServicesPlaySystemSound() for unknown system sound {}",
+            in_system_sound_id
+        );
+        return;
+    };
+    // Playing a sound that's already playing restarts it.
+    system_sound.is_playing = true;
+    let al_source = system_sound.al_source;
+
+    let _context_manager = make_internal_al_context_current(env);
+    unsafe {
+        al::alSourcePlay(al_source);
+        assert!(al::alGetError() == 0);
+    }
+}
+
+fn AudioServicesPlayAlertSound(env: &mut Environment, in_system_sound_id: SystemSoundID) {
+    // Depending on the user's settings, a real device might also vibrate.
+    // That would probably be more annoying than helpful here.
+    AudioServicesPlaySystemSound(env, in_system_sound_id);
+}
+
+fn AudioServicesAddSystemSoundCompletion(
+    env: &mut Environment,
+    in_system_sound_id: SystemSoundID,
+    in_run_loop: CFRunLoopRef,
+    in_run_loop_mode: CFRunLoopMode,
+    in_completion_routine: AudioServicesSystemSoundCompletionProc,
+    in_client_data: MutVoidPtr,
+) -> OSStatus {
+    // TODO: respect the run loop and mode. The completion routine is always
+    // called from the main thread's run loop currently.
+    if !in_run_loop.is_null() || !in_run_loop_mode.is_null() {
+        log_dbg!(
+            "AudioServicesAddSystemSoundCompletion() ignoring run loop {:?} and mode {:?}",
+            in_run_loop,
+            in_run_loop_mode
+        );
+    }
+
+    let Some(system_sound) = State::get(&mut env.framework_state)
+        .system_sounds
+        .get_mut(&in_system_sound_id)
+    else {
+        return kAudioServicesSystemSoundUnspecifiedError;
+    };
+    system_sound.completion = Some((in_completion_routine, in_client_data));
+
+    0 // success
+}
+
+fn AudioServicesRemoveSystemSoundCompletion(
+    env: &mut Environment,
+    in_system_sound_id: SystemSoundID,
+) {
+    if let Some(system_sound) = State::get(&mut env.framework_state)
+        .system_sounds
+        .get_mut(&in_system_sound_id)
+    {
+        system_sound.completion = None;
+    }
+}
+
+/// For use by `NSRunLoop`: check whether system sounds have finished playing,
+/// and call their completion routines if so.
+pub fn handle_system_sounds(env: &mut Environment) {
+    let state = State::get(&mut env.framework_state);
+    let playing: Vec<(SystemSoundID, ALuint)> = state
+        .system_sounds
+        .iter()
+        .filter(|(_, system_sound)| system_sound.is_playing)
+        .map(|(&id, system_sound)| (id, system_sound.al_source))
+        .collect();
+
+    for (system_sound_id, al_source) in playing {
+        let mut al_source_state = 0;
+        {
+            let _context_manager = make_internal_al_context_current(env);
+            unsafe {
+                al::alGetSourcei(al_source, al::AL_SOURCE_STATE, &mut al_source_state);
+                assert!(al::alGetError() == 0);
+            }
+        }
+        if al_source_state == al::AL_PLAYING {
+            continue;
+        }
+
+        // An earlier completion routine might have disposed of the sound.
+        let Some(system_sound) = State::get(&mut env.framework_state)
+            .system_sounds
+            .get_mut(&system_sound_id)
+        else {
+            continue;
+        };
+        system_sound.is_playing = false;
+        if let Some((completion_routine, client_data)) = system_sound.completion {
+            log_dbg!(
+                "System sound {} finished playing, calling completion routine {:?}",
+                system_sound_id,
+                completion_routine
+            );
+            let () = completion_routine.call_from_host(env, (system_sound_id, client_data));
+        }
+    }
 }
 
 pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(AudioServicesGetProperty(_, _, _, _, _)),
+    export_c_func!(AudioServicesCreateSystemSoundID(_, _)),
+    export_c_func!(AudioServicesDisposeSystemSoundID(_)),
     export_c_func!(AudioServicesPlaySystemSound(_)),
+    export_c_func!(AudioServicesPlayAlertSound(_)),
+    export_c_func!(AudioServicesAddSystemSoundCompletion(_, _, _, _, _)),
+    export_c_func!(AudioServicesRemoveSystemSoundCompletion(_)),
 ];
@@ -0,0 +1,483 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `ExtendedAudioFile.h` (Extended Audio File Services)
+//!
+//! This uses the same file format support as Audio File Services. The only
+//! conversion implemented so far is decoding to interleaved 16-bit integer
+//! linear PCM with the file's sample rate, which is what apps usually ask for
+//! when loading sounds into OpenAL.
+
+use super::audio_file::{data_format, kAudioFileUnsupportedDataFormatError, open_error_to_status};
+use crate::audio; // Keep this module namespaced to avoid confusion
+use crate::dyld::{export_c_func, FunctionExports};
+use crate::frameworks::carbon_core::OSStatus;
+use crate::frameworks::core_audio_types::{
+    debug_fourcc, fourcc, kAudioFormatFlagIsBigEndian, kAudioFormatFlagIsFloat,
+    kAudioFormatFlagIsNonInterleaved, kAudioFormatFlagIsSignedInteger, kAudioFormatLinearPCM,
+    AudioBufferList, AudioStreamBasicDescription,
+};
+use crate::frameworks::core_foundation::cf_url::CFURLRef;
+use crate::frameworks::foundation::ns_url::to_rust_path;
+use crate::mem::{guest_size_of, ConstVoidPtr, GuestUSize, MutPtr, MutVoidPtr, SafeRead};
+use crate::Environment;
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub struct State {
+    ext_audio_files: HashMap<ExtAudioFileRef, ExtAudioFileHostObject>,
+}
+impl State {
+    fn get(framework_state: &mut crate::frameworks::State) -> &mut Self {
+        &mut framework_state.audio_toolbox.ext_audio_file
+    }
+}
+
+struct ExtAudioFileHostObject {
+    audio_file: audio::AudioFile,
+    /// Format of the data returned by [ExtAudioFileRead]. `None` means it's
+    /// the same as the file's data format.
+    client_format: Option<AudioStreamBasicDescription>,
+    /// Frame offset at which the next read starts.
+    position: u64,
+}
+impl ExtAudioFileHostObject {
+    fn frame_count(&self) -> u64 {
+        let frames_per_packet = self.audio_file.audio_description().frames_per_packet;
+        self.audio_file.packet_count() * u64::from(frames_per_packet)
+    }
+}
+
+#[repr(C, packed)]
+struct OpaqueExtAudioFile {
+    _filler: u8,
+}
+unsafe impl SafeRead for OpaqueExtAudioFile {}
+
+type ExtAudioFileRef = MutPtr<OpaqueExtAudioFile>;
+
+const kExtAudioFileError_InvalidProperty: OSStatus = fourcc(b"prop") as _;
+const kExtAudioFileError_InvalidPropertySize: OSStatus = fourcc(b"psiz") as _;
+const kExtAudioFileError_NonPCMClientFormat: OSStatus = fourcc(b"nclf") as _;
+const kExtAudioFileError_InvalidSeek: OSStatus = fourcc(b"seek") as _;
+
+/// Usually a FourCC.
+type ExtAudioFilePropertyID = u32;
+const kExtAudioFileProperty_FileDataFormat: ExtAudioFilePropertyID = fourcc(b"ffmt");
+const kExtAudioFileProperty_ClientDataFormat: ExtAudioFilePropertyID = fourcc(b"cfmt");
+const kExtAudioFileProperty_FileLengthFrames: ExtAudioFilePropertyID = fourcc(b"#frm");
+
+/// Whether we can convert a file's audio data to a client data format.
+fn is_supported_client_format(
+    client_format: &AudioStreamBasicDescription,
+    file_format: &AudioStreamBasicDescription,
+) -> bool {
+    let &AudioStreamBasicDescription {
+        sample_rate,
+        format_id,
+        format_flags,
+        bytes_per_packet,
+        frames_per_packet,
+        bytes_per_frame,
+        channels_per_frame,
+        bits_per_channel,
+        ..
+    } = client_format;
+    let file_sample_rate = file_format.sample_rate;
+
+    // TODO: support resampling and more sample formats
+    format_id == kAudioFormatLinearPCM
+        && (format_flags & kAudioFormatFlagIsSignedInteger) != 0
+        && (format_flags
+            & (kAudioFormatFlagIsFloat
+                | kAudioFormatFlagIsBigEndian
+                | kAudioFormatFlagIsNonInterleaved))
+            == 0
+        && bits_per_channel == 16
+        && (channels_per_frame == 1 || channels_per_frame == 2)
+        && bytes_per_frame == channels_per_frame * 2
+        && frames_per_packet == 1
+        && bytes_per_packet == bytes_per_frame
+        && sample_rate == file_sample_rate
+}
+
+fn ExtAudioFileOpenURL(
+    env: &mut Environment,
+    in_url: CFURLRef,
+    out_ext_audio_file: MutPtr<ExtAudioFileRef>,
+) -> OSStatus {
+    return_if_null!(in_url);
+
+    let path = to_rust_path(env, in_url);
+    let audio_file = match audio::AudioFile::open_for_reading(path, &env.fs) {
+        Ok(audio_file) => audio_file,
+        Err(err) => {
+            log!(
+                "Warning: ExtAudioFileOpenURL() for path {:?} failed: {:?}",
+                in_url,
+                err
+            );
+            return open_error_to_status(err);
+        }
+    };
+
+    let desc = audio_file.audio_description();
+    if !audio::can_decode_to_pcm16(&desc) {
+        log!(
+            "Warning: ExtAudioFileOpenURL() for path {:?} failed, can't decode {:?}",
+            in_url,
+            desc
+        );
+        return kAudioFileUnsupportedDataFormatError;
+    }
+
+    let host_object = ExtAudioFileHostObject {
+        audio_file,
+        client_format: None,
+        position: 0,
+    };
+
+    let guest_ext_audio_file = env.mem.alloc_and_write(OpaqueExtAudioFile { _filler: 0 });
+    State::get(&mut env.framework_state)
+        .ext_audio_files
+        .insert(guest_ext_audio_file, host_object);
+
+    env.mem.write(out_ext_audio_file, guest_ext_audio_file);
+
+    log_dbg!(
+        "ExtAudioFileOpenURL() opened path {:?}, new extended audio file handle: {:?}",
+        in_url,
+        guest_ext_audio_file
+    );
+
+    0 // success
+}
+
+fn property_size(property_id: ExtAudioFilePropertyID) -> Option<GuestUSize> {
+    match property_id {
+        kExtAudioFileProperty_FileDataFormat => {
+            Some(guest_size_of::<AudioStreamBasicDescription>())
+        }
+        kExtAudioFileProperty_ClientDataFormat => {
+            Some(guest_size_of::<AudioStreamBasicDescription>())
+        }
+        kExtAudioFileProperty_FileLengthFrames => Some(guest_size_of::<i64>()),
+        _ => None,
+    }
+}
+
+fn ExtAudioFileGetPropertyInfo(
+    env: &mut Environment,
+    in_ext_audio_file: ExtAudioFileRef,
+    in_property_id: ExtAudioFilePropertyID,
+    out_size: MutPtr<u32>,
+    out_writable: MutPtr<bool>,
+) -> OSStatus {
+    return_if_null!(in_ext_audio_file);
+
+    let Some(size) = property_size(in_property_id) else {
+        log!(
+            "Warning: ExtAudioFileGetPropertyInfo() for unimplemented property ID {}",
+            debug_fourcc(in_property_id)
+        );
+        return kExtAudioFileError_InvalidProperty;
+    };
+    if !out_size.is_null() {
+        env.mem.write(out_size, size);
+    }
+    if !out_writable.is_null() {
+        let writable = in_property_id == kExtAudioFileProperty_ClientDataFormat;
+        env.mem.write(out_writable, writable);
+    }
+    0 // success
+}
+
+fn ExtAudioFileGetProperty(
+    env: &mut Environment,
+    in_ext_audio_file: ExtAudioFileRef,
+    in_property_id: ExtAudioFilePropertyID,
+    io_property_data_size: MutPtr<u32>,
+    out_property_data: MutVoidPtr,
+) -> OSStatus {
+    return_if_null!(in_ext_audio_file);
+
+    let Some(required_size) = property_size(in_property_id) else {
+        log!(
+            "Warning: ExtAudioFileGetProperty() for unimplemented property ID {}",
+            debug_fourcc(in_property_id)
+        );
+        return kExtAudioFileError_InvalidProperty;
+    };
+    if env.mem.read(io_property_data_size) != required_size {
+        log!("Warning: ExtAudioFileGetProperty() failed");
+        return kExtAudioFileError_InvalidPropertySize;
+    }
+
+    let host_object = State::get(&mut env.framework_state)
+        .ext_audio_files
+        .get_mut(&in_ext_audio_file)
+        .unwrap();
+
+    match in_property_id {
+        kExtAudioFileProperty_FileDataFormat => {
+            let desc = data_format(&host_object.audio_file);
+            env.mem.write(out_property_data.cast(), desc);
+        }
+        kExtAudioFileProperty_ClientDataFormat => {
+            let desc = host_object
+                .client_format
+                .unwrap_or_else(|| data_format(&host_object.audio_file));
+            env.mem.write(out_property_data.cast(), desc);
+        }
+        kExtAudioFileProperty_FileLengthFrames => {
+            let frame_count: i64 = host_object.frame_count().try_into().unwrap();
+            env.mem.write(out_property_data.cast(), frame_count);
+        }
+        _ => unreachable!(),
+    }
+
+    0 // success
+}
+
+fn ExtAudioFileSetProperty(
+    env: &mut Environment,
+    in_ext_audio_file: ExtAudioFileRef,
+    in_property_id: ExtAudioFilePropertyID,
+    in_property_data_size: u32,
+    in_property_data: ConstVoidPtr,
+) -> OSStatus {
+    return_if_null!(in_ext_audio_file);
+
+    if in_property_id != kExtAudioFileProperty_ClientDataFormat {
+        log!(
+            "Warning: ExtAudioFileSetProperty() for unimplemented property ID {}",
+            debug_fourcc(in_property_id)
+        );
+        return kExtAudioFileError_InvalidProperty;
+    }
+    if in_property_data_size != guest_size_of::<AudioStreamBasicDescription>() {
+        log!("Warning: ExtAudioFileSetProperty() failed");
+        return kExtAudioFileError_InvalidPropertySize;
+    }
+
+    let client_format: AudioStreamBasicDescription = env.mem.read(in_property_data.cast());
+
+    let host_object = State::get(&mut env.framework_state)
+        .ext_audio_files
+        .get_mut(&in_ext_audio_file)
+        .unwrap();
+
+    if client_format.format_id != kAudioFormatLinearPCM {
+        log!(
+            "Warning: ExtAudioFileSetProperty() with non-PCM client format {:?}",
+            client_format
+        );
+        return kExtAudioFileError_NonPCMClientFormat;
+    }
+    let file_format = data_format(&host_object.audio_file);
+    if !is_supported_client_format(&client_format, &file_format) {
+        log!(
+            "TODO: ExtAudioFileSetProperty() conversion from {:?} to client format {:?}",
+            file_format,
+            client_format
+        );
+        return kAudioFileUnsupportedDataFormatError;
+    }
+
+    log_dbg!(
+        "ExtAudioFileSetProperty() set client format of {:?} to {:?}",
+        in_ext_audio_file,
+        client_format
+    );
+    host_object.client_format = Some(client_format);
+
+    0 // success
+}
+
+fn ExtAudioFileRead(
+    env: &mut Environment,
+    in_ext_audio_file: ExtAudioFileRef,
+    io_number_frames: MutPtr<u32>,
+    io_data: MutPtr<AudioBufferList>,
+) -> OSStatus {
+    return_if_null!(in_ext_audio_file);
+
+    let AudioBufferList {
+        number_buffers,
+        buffers: [mut buffer],
+    } = env.mem.read(io_data);
+    // Non-interleaved client formats are rejected by ExtAudioFileSetProperty().
+    assert!(number_buffers == 1);
+
+    let host_object = State::get(&mut env.framework_state)
+        .ext_audio_files
+        .get_mut(&in_ext_audio_file)
+        .unwrap();
+
+    let file_desc = host_object.audio_file.audio_description();
+    let (bytes_per_frame, out_channels) = match host_object.client_format {
+        Some(client_format) => (
+            client_format.bytes_per_frame,
+            client_format.channels_per_frame,
+        ),
+        None => {
+            if !matches!(file_desc.format, audio::AudioFormat::LinearPcm { .. }) {
+                log!("Warning: ExtAudioFileRead() for compressed file without client format");
+                return kExtAudioFileError_NonPCMClientFormat;
+            }
+            (file_desc.bytes_per_packet, file_desc.channels_per_frame)
+        }
+    };
+
+    let frames_remaining = host_object.frame_count() - host_object.position;
+    let frame_count = u64::from(env.mem.read(io_number_frames))
+        .min(u64::from(buffer.data_byte_size / bytes_per_frame))
+        .min(frames_remaining);
+
+    let byte_count: GuestUSize = if host_object.client_format.is_none() {
+        // Linear PCM files can be read as-is.
+        let out_slice = env.mem.bytes_at_mut(
+            buffer.data.cast(),
+            (frame_count * u64::from(bytes_per_frame))
+                .try_into()
+                .unwrap(),
+        );
+        host_object
+            .audio_file
+            .read_bytes(host_object.position * u64::from(bytes_per_frame), out_slice)
+            .unwrap() // TODO: handle seek error?
+            .try_into()
+            .unwrap()
+    } else {
+        let frames_per_packet = u64::from(file_desc.frames_per_packet);
+        let packet_size = u64::from(host_object.audio_file.packet_size_fixed());
+        let first_packet = host_object.position / frames_per_packet;
+        let end_packet = (host_object.position + frame_count).div_ceil(frames_per_packet);
+
+        let mut data = vec![0u8; ((end_packet - first_packet) * packet_size) as usize];
+        let bytes_read = host_object
+            .audio_file
+            .read_bytes(first_packet * packet_size, &mut data)
+            .unwrap(); // TODO: handle seek error?
+        data.truncate(bytes_read);
+        let samples = audio::decode_to_pcm16(&file_desc, &data);
+
+        let in_channels = file_desc.channels_per_frame as usize;
+        let skip = (host_object.position - first_packet * frames_per_packet) as usize;
+        let mut out_pcm = Vec::<u8>::with_capacity((frame_count * 2) as usize * in_channels);
+        for frame in samples
+            .chunks_exact(in_channels)
+            .skip(skip)
+            .take(frame_count as usize)
+        {
+            match (in_channels, out_channels) {
+                (1, 2) => {
+                    out_pcm.extend_from_slice(&frame[0].to_le_bytes());
+                    out_pcm.extend_from_slice(&frame[0].to_le_bytes());
+                }
+                (2, 1) => {
+                    let mixed = (i32::from(frame[0]) + i32::from(frame[1])) / 2;
+                    out_pcm.extend_from_slice(&(mixed as i16).to_le_bytes());
+                }
+                _ => {
+                    for sample in frame {
+                        out_pcm.extend_from_slice(&sample.to_le_bytes());
+                    }
+                }
+            }
+        }
+
+        let byte_count: GuestUSize = out_pcm.len().try_into().unwrap();
+        env.mem
+            .bytes_at_mut(buffer.data.cast(), byte_count)
+            .copy_from_slice(&out_pcm);
+        byte_count
+    };
+
+    let frames_read = byte_count / bytes_per_frame;
+    host_object.position += u64::from(frames_read);
+
+    buffer.data_byte_size = byte_count;
+    env.mem.write(
+        io_data,
+        AudioBufferList {
+            number_buffers,
+            buffers: [buffer],
+        },
+    );
+    env.mem.write(io_number_frames, frames_read);
+
+    0 // success
+}
+
+fn ExtAudioFileSeek(
+    env: &mut Environment,
+    in_ext_audio_file: ExtAudioFileRef,
+    in_frame_offset: i64,
+) -> OSStatus {
+    return_if_null!(in_ext_audio_file);
+
+    let host_object = State::get(&mut env.framework_state)
+        .ext_audio_files
+        .get_mut(&in_ext_audio_file)
+        .unwrap();
+
+    match u64::try_from(in_frame_offset) {
+        Ok(position) if position <= host_object.frame_count() => {
+            host_object.position = position;
+            0 // success
+        }
+        _ => {
+            log!(
+                "Warning: ExtAudioFileSeek() to invalid offset {}",
+                in_frame_offset
+            );
+            kExtAudioFileError_InvalidSeek
+        }
+    }
+}
+
+fn ExtAudioFileTell(
+    env: &mut Environment,
+    in_ext_audio_file: ExtAudioFileRef,
+    out_frame_offset: MutPtr<i64>,
+) -> OSStatus {
+    return_if_null!(in_ext_audio_file);
+
+    let position = State::get(&mut env.framework_state)
+        .ext_audio_files
+        .get(&in_ext_audio_file)
+        .unwrap()
+        .position;
+    env.mem
+        .write(out_frame_offset, position.try_into().unwrap());
+    0 // success
+}
+
+fn ExtAudioFileDispose(env: &mut Environment, in_ext_audio_file: ExtAudioFileRef) -> OSStatus {
+    return_if_null!(in_ext_audio_file);
+
+    let _host_object = State::get(&mut env.framework_state)
+        .ext_audio_files
+        .remove(&in_ext_audio_file)
+        .unwrap();
+    env.mem.free(in_ext_audio_file.cast());
+    log_dbg!(
+        "ExtAudioFileDispose() destroyed extended audio file handle: {:?}",
+        in_ext_audio_file
+    );
+    0 // success
+}
+
+pub const FUNCTIONS: FunctionExports = &[
+    export_c_func!(ExtAudioFileOpenURL(_, _)),
+    export_c_func!(ExtAudioFileGetPropertyInfo(_, _, _, _)),
+    export_c_func!(ExtAudioFileGetProperty(_, _, _, _)),
+    export_c_func!(ExtAudioFileSetProperty(_, _, _, _)),
+    export_c_func!(ExtAudioFileRead(_, _, _)),
+    export_c_func!(ExtAudioFileSeek(_, _)),
+    export_c_func!(ExtAudioFileTell(_, _)),
+    export_c_func!(ExtAudioFileDispose(_)),
+];
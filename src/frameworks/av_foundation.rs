@@ -0,0 +1,19 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! The AV Foundation framework.
+
+pub mod av_audio_player;
+
+#[derive(Default)]
+pub struct State {
+    av_audio_player: av_audio_player::State,
+}
+
+/// For use by `NSRunLoop`: check audio players' status, call delegates if
+/// necessary.
+pub fn handle_players(env: &mut crate::Environment) {
+    av_audio_player::handle_players(env);
+}
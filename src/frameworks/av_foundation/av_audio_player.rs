@@ -0,0 +1,383 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `AVAudioPlayer`.
+//!
+//! The whole file is decoded up-front into a single OpenAL buffer, which is
+//! played using Audio Toolbox's internal OpenAL context. Finite looping is done
+//! by queueing the buffer once per repetition, so there are no gaps.
+
+use crate::audio::openal as al;
+use crate::audio::openal::al_types::*;
+use crate::frameworks::audio_toolbox::audio_file::decode_file_to_pcm16;
+use crate::frameworks::audio_toolbox::audio_queue::make_internal_al_context_current;
+use crate::frameworks::foundation::ns_error::{set_error, NSOSStatusErrorDomain};
+use crate::frameworks::foundation::{NSInteger, NSTimeInterval, NSUInteger};
+use crate::mem::MutPtr;
+use crate::objc::{
+    id, msg, nil, objc_classes, release, retain, ClassExports, HostObject, NSZonePtr,
+};
+use crate::Environment;
+
+#[derive(Default)]
+pub struct State {
+    /// Players that are currently playing. These are weak references, players
+    /// remove themselves when they stop playing or are deallocated.
+    playing: Vec<id>,
+}
+impl State {
+    fn get(env: &mut Environment) -> &mut Self {
+        &mut env.framework_state.av_foundation.av_audio_player
+    }
+}
+
+#[derive(Default)]
+struct AVAudioPlayerHostObject {
+    /// `NSURL*`
+    url: id,
+    /// Weak reference
+    delegate: id,
+    number_of_loops: NSInteger,
+    volume: f32,
+    /// Duration of the sound in seconds.
+    duration: NSTimeInterval,
+    channels: NSUInteger,
+    al_source: ALuint,
+    al_buffer: ALuint,
+    /// Playback position in seconds, used while the OpenAL source is neither
+    /// playing nor paused.
+    stopped_time: NSTimeInterval,
+    is_playing: bool,
+}
+impl HostObject for AVAudioPlayerHostObject {}
+
+fn al_source_state(env: &mut Environment, al_source: ALuint) -> ALenum {
+    let _context_manager = make_internal_al_context_current(env);
+    let mut al_source_state = 0;
+    unsafe {
+        al::alGetSourcei(al_source, al::AL_SOURCE_STATE, &mut al_source_state);
+        assert!(al::alGetError() == 0);
+    }
+    al_source_state
+}
+
+/// Get the playback position within the whole OpenAL buffer queue, which
+/// contains one copy of the sound per repetition.
+fn al_source_offset(env: &mut Environment, al_source: ALuint) -> NSTimeInterval {
+    let _context_manager = make_internal_al_context_current(env);
+    let mut offset = 0.0;
+    unsafe {
+        al::alGetSourcef(al_source, al::AL_SEC_OFFSET, &mut offset);
+        assert!(al::alGetError() == 0);
+    }
+    offset.into()
+}
+
+fn stop_tracking(env: &mut Environment, player: id) {
+    State::get(env).playing.retain(|&other| other != player);
+    env.objc
+        .borrow_mut::<AVAudioPlayerHostObject>(player)
+        .is_playing = false;
+}
+
+fn delegate_responds_to(env: &mut Environment, this: id, selector: &str) -> Option<id> {
+    let delegate = env.objc.borrow::<AVAudioPlayerHostObject>(this).delegate;
+    if delegate != nil
+        && env
+            .objc
+            .object_has_method_named(&env.mem, delegate, selector)
+    {
+        Some(delegate)
+    } else {
+        None
+    }
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation AVAudioPlayer: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(AVAudioPlayerHostObject {
+        volume: 1.0,
+        ..Default::default()
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (id)initWithContentsOfURL:(id)url // NSURL*
+                      error:(MutPtr<id>)error { // NSError**
+    let (samples, channels, sample_rate) = match decode_file_to_pcm16(env, url) {
+        Ok(decoded) => decoded,
+        Err((status, description)) => {
+            log!(
+                "Warning: [(AVAudioPlayer*){:?} initWithContentsOfURL:{:?}] failed: {}",
+                this,
+                url,
+                description,
+            );
+            set_error(env, error, NSOSStatusErrorDomain, status.into(), description);
+            release(env, this);
+            return nil;
+        }
+    };
+
+    let al_format = if channels == 1 {
+        al::AL_FORMAT_MONO16
+    } else {
+        al::AL_FORMAT_STEREO16
+    };
+    let (al_source, al_buffer) = {
+        let _context_manager = make_internal_al_context_current(env);
+        let mut al_source = 0;
+        let mut al_buffer = 0;
+        unsafe {
+            al::alGenSources(1, &mut al_source);
+            al::alGenBuffers(1, &mut al_buffer);
+            al::alBufferData(
+                al_buffer,
+                al_format,
+                samples.as_ptr() as *const ALvoid,
+                (samples.len() * 2).try_into().unwrap(),
+                sample_rate as ALsizei,
+            );
+            assert!(al::alGetError() == 0);
+        }
+        (al_source, al_buffer)
+    };
+
+    retain(env, url);
+    let host_object = env.objc.borrow_mut::<AVAudioPlayerHostObject>(this);
+    host_object.url = url;
+    host_object.duration = (samples.len() / channels as usize) as f64 / sample_rate;
+    host_object.channels = channels;
+    host_object.al_source = al_source;
+    host_object.al_buffer = al_buffer;
+
+    log_dbg!(
+        "[(AVAudioPlayer*){:?} initWithContentsOfURL:{:?}] decoded {} seconds of audio",
+        this,
+        url,
+        host_object.duration
+    );
+
+    this
+}
+
+- (())dealloc {
+    stop_tracking(env, this);
+    let &AVAudioPlayerHostObject {
+        url,
+        al_source,
+        al_buffer,
+        ..
+    } = env.objc.borrow(this);
+    // The OpenAL objects don't exist if the initializer failed.
+    if al_source != 0 {
+        let _context_manager = make_internal_al_context_current(env);
+        unsafe {
+            al::alSourceStop(al_source);
+            al::alSourcei(al_source, al::AL_BUFFER, 0);
+            al::alDeleteSources(1, &al_source);
+            al::alDeleteBuffers(1, &al_buffer);
+            assert!(al::alGetError() == 0);
+        }
+    }
+    release(env, url);
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (id)url {
+    env.objc.borrow::<AVAudioPlayerHostObject>(this).url
+}
+
+- (id)delegate {
+    env.objc.borrow::<AVAudioPlayerHostObject>(this).delegate
+}
+- (())setDelegate:(id)delegate {
+    env.objc.borrow_mut::<AVAudioPlayerHostObject>(this).delegate = delegate;
+}
+
+- (bool)prepareToPlay {
+    // The file was already decoded by the initializer.
+    true
+}
+
+- (bool)play {
+    let &AVAudioPlayerHostObject {
+        number_of_loops,
+        volume,
+        al_source,
+        al_buffer,
+        stopped_time,
+        is_playing,
+        ..
+    } = env.objc.borrow(this);
+
+    let al_source_state = al_source_state(env, al_source);
+    {
+        let _context_manager = make_internal_al_context_current(env);
+        unsafe {
+            if al_source_state == al::AL_PAUSED {
+                al::alSourcePlay(al_source);
+            } else if al_source_state != al::AL_PLAYING {
+                al::alSourcei(al_source, al::AL_BUFFER, 0);
+                if number_of_loops < 0 {
+                    // Loop indefinitely.
+                    al::alSourceQueueBuffers(al_source, 1, &al_buffer);
+                    al::alSourcei(al_source, al::AL_LOOPING, 1);
+                } else {
+                    let buffers = vec![al_buffer; (number_of_loops + 1) as usize];
+                    al::alSourceQueueBuffers(
+                        al_source,
+                        buffers.len().try_into().unwrap(),
+                        buffers.as_ptr(),
+                    );
+                    al::alSourcei(al_source, al::AL_LOOPING, 0);
+                }
+                al::alSourcef(al_source, al::AL_GAIN, volume);
+                al::alSourcePlay(al_source);
+                if stopped_time > 0.0 {
+                    al::alSourcef(al_source, al::AL_SEC_OFFSET, stopped_time as f32);
+                }
+            }
+            assert!(al::alGetError() == 0);
+        }
+    }
+
+    if !is_playing {
+        env.objc.borrow_mut::<AVAudioPlayerHostObject>(this).is_playing = true;
+        State::get(env).playing.push(this);
+    }
+    true
+}
+
+- (())pause {
+    let al_source = env.objc.borrow::<AVAudioPlayerHostObject>(this).al_source;
+    if al_source_state(env, al_source) == al::AL_PLAYING {
+        let _context_manager = make_internal_al_context_current(env);
+        unsafe {
+            al::alSourcePause(al_source);
+            assert!(al::alGetError() == 0);
+        }
+    }
+    stop_tracking(env, this);
+}
+
+- (())stop {
+    // Unlike when playback finishes, stopping doesn't reset the current time.
+    let current_time: NSTimeInterval = msg![env; this currentTime];
+    let al_source = env.objc.borrow::<AVAudioPlayerHostObject>(this).al_source;
+    {
+        let _context_manager = make_internal_al_context_current(env);
+        unsafe {
+            al::alSourceStop(al_source);
+            assert!(al::alGetError() == 0);
+        }
+    }
+    env.objc.borrow_mut::<AVAudioPlayerHostObject>(this).stopped_time = current_time;
+    stop_tracking(env, this);
+}
+
+- (bool)isPlaying {
+    env.objc.borrow::<AVAudioPlayerHostObject>(this).is_playing
+}
+
+- (NSTimeInterval)duration {
+    env.objc.borrow::<AVAudioPlayerHostObject>(this).duration
+}
+
+- (NSUInteger)numberOfChannels {
+    env.objc.borrow::<AVAudioPlayerHostObject>(this).channels
+}
+
+- (NSTimeInterval)currentTime {
+    let &AVAudioPlayerHostObject {
+        duration,
+        al_source,
+        stopped_time,
+        ..
+    } = env.objc.borrow(this);
+    match al_source_state(env, al_source) {
+        al::AL_PLAYING | al::AL_PAUSED if duration > 0.0 => {
+            al_source_offset(env, al_source) % duration
+        }
+        _ => stopped_time,
+    }
+}
+- (())setCurrentTime:(NSTimeInterval)time {
+    let &AVAudioPlayerHostObject {
+        duration,
+        al_source,
+        ..
+    } = env.objc.borrow(this);
+    let time = time.clamp(0.0, duration);
+    match al_source_state(env, al_source) {
+        al::AL_PLAYING | al::AL_PAUSED if duration > 0.0 => {
+            // Stay within the current repetition.
+            let repetition = (al_source_offset(env, al_source) / duration).floor();
+            let offset = repetition * duration + time;
+            let _context_manager = make_internal_al_context_current(env);
+            unsafe {
+                al::alSourcef(al_source, al::AL_SEC_OFFSET, offset as f32);
+                assert!(al::alGetError() == 0);
+            }
+        }
+        _ => {
+            env.objc.borrow_mut::<AVAudioPlayerHostObject>(this).stopped_time = time;
+        }
+    }
+}
+
+- (NSInteger)numberOfLoops {
+    env.objc.borrow::<AVAudioPlayerHostObject>(this).number_of_loops
+}
+- (())setNumberOfLoops:(NSInteger)number_of_loops {
+    // This takes effect the next time playback starts from being stopped.
+    env.objc.borrow_mut::<AVAudioPlayerHostObject>(this).number_of_loops = number_of_loops;
+}
+
+- (f32)volume {
+    env.objc.borrow::<AVAudioPlayerHostObject>(this).volume
+}
+- (())setVolume:(f32)volume {
+    let host_object = env.objc.borrow_mut::<AVAudioPlayerHostObject>(this);
+    host_object.volume = volume;
+    let al_source = host_object.al_source;
+    let _context_manager = make_internal_al_context_current(env);
+    unsafe {
+        al::alSourcef(al_source, al::AL_GAIN, volume);
+        assert!(al::alGetError() == 0);
+    }
+}
+
+@end
+
+};
+
+/// For use by `NSRunLoop` via [super::handle_players]: check whether playing
+/// audio players have finished, and tell their delegates if so.
+pub(super) fn handle_players(env: &mut Environment) {
+    for player in State::get(env).playing.clone() {
+        let al_source = env.objc.borrow::<AVAudioPlayerHostObject>(player).al_source;
+        if al_source_state(env, al_source) != al::AL_STOPPED {
+            continue;
+        }
+
+        log_dbg!("AVAudioPlayer {:?} finished playing", player);
+        stop_tracking(env, player);
+        env.objc
+            .borrow_mut::<AVAudioPlayerHostObject>(player)
+            .stopped_time = 0.0;
+
+        if let Some(delegate) =
+            delegate_responds_to(env, player, "audioPlayerDidFinishPlaying:successfully:")
+        {
+            () = msg![env; delegate audioPlayerDidFinishPlaying:player successfully:true];
+        }
+    }
+}
@@ -8,6 +8,10 @@
 //! Useful resources:
 //! - Apple's [Core Animation Programming Guide](https://developer.apple.com/library/archive/documentation/Cocoa/Conceptual/CoreAnimation_guide/Introduction/Introduction.html)
 
+use crate::dyld::{export_c_func, FunctionExports};
+use crate::frameworks::core_foundation::time::CFTimeInterval;
+use crate::Environment;
+
 pub mod ca_eagl_layer;
 pub mod ca_layer;
 
@@ -18,3 +22,11 @@ pub use composition::recomposite_if_necessary;
 pub struct State {
     composition: composition::State,
 }
+
+/// `CACurrentMediaTime()`: the same clock as `mach_absolute_time()`, but in
+/// seconds.
+fn CACurrentMediaTime(env: &mut Environment) -> CFTimeInterval {
+    env.clock.elapsed().as_secs_f64()
+}
+
+pub const FUNCTIONS: FunctionExports = &[export_c_func!(CACurrentMediaTime())];
@@ -6,6 +6,9 @@
 //! `CALayer`.
 
 use crate::frameworks::core_foundation::{CFRelease, CFRetain};
+use crate::frameworks::core_graphics::cg_affine_transform::{
+    CGAffineTransform, CGAffineTransformIdentity,
+};
 use crate::frameworks::core_graphics::cg_bitmap_context::{
     CGBitmapContextCreate, CGBitmapContextGetHeight, CGBitmapContextGetWidth,
 };
@@ -31,6 +34,8 @@ pub(super) struct CALayerHostObject {
     pub(super) bounds: CGRect,
     pub(super) position: CGPoint,
     pub(super) anchor_point: CGPoint,
+    /// Applied around the anchor point.
+    pub(super) affine_transform: CGAffineTransform,
     pub(super) hidden: bool,
     pub(super) opaque: bool,
     pub(super) opacity: f32,
@@ -48,6 +53,10 @@ pub(super) struct CALayerHostObject {
     pub(super) gles_texture: Option<crate::gles::gles11_raw::types::GLuint>,
     /// Internal state for compositor
     pub(super) gles_texture_is_up_to_date: bool,
+    /// Internal state for compositor (pattern `backgroundColor`)
+    pub(super) pattern_texture: Option<crate::gles::gles11_raw::types::GLuint>,
+    /// Internal state for compositor (pattern `backgroundColor`)
+    pub(super) pattern_texture_is_up_to_date: bool,
 }
 impl HostObject for CALayerHostObject {}
 
@@ -68,6 +77,7 @@ pub const CLASSES: ClassExports = objc_classes! {
         },
         position: CGPoint { x: 0.0, y: 0.0 },
         anchor_point: CGPoint { x: 0.5, y: 0.5 },
+        affine_transform: CGAffineTransformIdentity,
         hidden: false,
         opaque: false,
         opacity: 1.0,
@@ -79,6 +89,8 @@ pub const CLASSES: ClassExports = objc_classes! {
         cg_context: None,
         gles_texture: None,
         gles_texture_is_up_to_date: false,
+        pattern_texture: None,
+        pattern_texture_is_up_to_date: false,
     });
     env.objc.alloc_object(this, host_object, &mut env.mem)
 }
@@ -181,6 +193,14 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.objc.borrow_mut::<CALayerHostObject>(this).anchor_point = anchor_point;
 }
 
+// TODO: CATransform3D and the transform property
+- (CGAffineTransform)affineTransform {
+    env.objc.borrow::<CALayerHostObject>(this).affine_transform
+}
+- (())setAffineTransform:(CGAffineTransform)transform {
+    env.objc.borrow_mut::<CALayerHostObject>(this).affine_transform = transform;
+}
+
 - (CGRect)frame {
     let &CALayerHostObject {
         bounds,
@@ -241,6 +261,9 @@ pub const CLASSES: ClassExports = objc_classes! {
 - (())setBackgroundColor:(id)new_color {
     let host_obj = env.objc.borrow_mut::<CALayerHostObject>(this);
     let old_color = std::mem::replace(&mut host_obj.background_color, new_color);
+    if old_color != new_color {
+        host_obj.pattern_texture_is_up_to_date = false;
+    }
     if new_color != nil {
         CFRetain(env, new_color); // CFRetain doesn't like nil
     }
@@ -302,19 +302,57 @@ unsafe fn composite_layer_recursive(
     let absolute_frame = {
         let position = host_obj.position;
         let anchor_point = host_obj.anchor_point;
-        CGRect {
+        let transform = host_obj.affine_transform;
+        // Frame relative to the position, which the transform is applied to.
+        let frame = CGRect {
             origin: CGPoint {
-                x: origin.x + position.x - bounds.size.width * anchor_point.x,
-                y: origin.y + position.y - bounds.size.height * anchor_point.y,
+                x: -bounds.size.width * anchor_point.x,
+                y: -bounds.size.height * anchor_point.y,
             },
             size: bounds.size,
+        };
+        // TODO: Only scaling and translation can be drawn correctly. The
+        // bounding box is used for other transforms, and the transform isn't
+        // applied to sublayers.
+        let frame = if transform.is_identity() {
+            frame
+        } else {
+            transform.apply_to_rect(frame)
+        };
+        CGRect {
+            origin: CGPoint {
+                x: origin.x + position.x + frame.origin.x,
+                y: origin.y + position.y + frame.origin.y,
+            },
+            size: frame.size,
         }
     };
     let absolute_frame_clipped = clip_rects(clip_to, absolute_frame);
 
     // Draw background color, if any
+    let pattern = if host_obj.background_color == nil {
+        None
+    } else {
+        ui_color::get_pattern(objc, host_obj.background_color)
+    };
     let have_background = if host_obj.background_color == nil {
         false
+    } else if let Some(pattern) = pattern {
+        if opacity == 0.0 {
+            false
+        } else {
+            composite_pattern(
+                gles,
+                objc,
+                layer,
+                pattern,
+                absolute_frame_clipped,
+                opacity,
+                scale_hack,
+                fb_height,
+            );
+            true
+        }
     } else {
         let (r, g, b, a) = ui_color::get_rgba(objc, host_obj.background_color);
         // TODO: fully support alpha transparency for backgrounds
@@ -452,6 +490,75 @@ unsafe fn composite_layer_recursive(
     objc.borrow_mut::<CALayerHostObject>(layer).sublayers = sublayers;
 }
 
+/// Draw a pattern color (`colorWithPatternImage:`) background by tiling the
+/// image, starting from the top-left corner of the layer.
+unsafe fn composite_pattern(
+    gles: &mut dyn GLES,
+    objc: &mut ObjC,
+    layer: id,
+    pattern: id, // CGImageRef
+    absolute_frame_clipped: CGRect,
+    opacity: CGFloat,
+    scale_hack: u32,
+    fb_height: u32,
+) {
+    let host_obj = objc.borrow_mut::<CALayerHostObject>(layer);
+    let texture = if let Some(texture) = host_obj.pattern_texture {
+        texture
+    } else {
+        assert!(!host_obj.pattern_texture_is_up_to_date);
+        let mut texture = 0;
+        gles.GenTextures(1, &mut texture);
+        host_obj.pattern_texture = Some(texture);
+        texture
+    };
+    let need_update = !host_obj.pattern_texture_is_up_to_date;
+    host_obj.pattern_texture_is_up_to_date = true;
+    let size = host_obj.bounds.size;
+
+    gles.BindTexture(gles11::TEXTURE_2D, texture);
+    let image = cg_image::borrow_image(objc, pattern);
+    let (image_width, image_height) = image.dimensions();
+    if need_update {
+        upload_rgba8_pixels(gles, image.pixels(), image.dimensions());
+        gles.TexParameteri(
+            gles11::TEXTURE_2D,
+            gles11::TEXTURE_WRAP_S,
+            gles11::REPEAT as _,
+        );
+        gles.TexParameteri(
+            gles11::TEXTURE_2D,
+            gles11::TEXTURE_WRAP_T,
+            gles11::REPEAT as _,
+        );
+    }
+
+    gles.Color4f(opacity, opacity, opacity, opacity);
+    gles.Enable(gles11::BLEND);
+    gles.BlendFunc(gles11::ONE, gles11::ONE_MINUS_SRC_ALPHA);
+
+    let (x, y, w, h) = gl_rect_from_cg_rect(absolute_frame_clipped, scale_hack, fb_height);
+    gles.Scissor(x, y, w, h);
+    gles.Viewport(x, y, w, h);
+
+    gles.BindBuffer(gles11::ARRAY_BUFFER, 0);
+    let vertices: [f32; 12] = [
+        -1.0, -1.0, -1.0, 1.0, 1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0,
+    ];
+    gles.EnableClientState(gles11::VERTEX_ARRAY);
+    gles.VertexPointer(2, gles11::FLOAT, 0, vertices.as_ptr() as *const GLvoid);
+
+    // Images have top-to-bottom row order, so the bottom of the layer is at the
+    // highest V co-ordinate.
+    let u = size.width / image_width as f32;
+    let v = size.height / image_height as f32;
+    let tex_coords: [f32; 12] = [0.0, v, 0.0, 0.0, u, v, u, v, 0.0, 0.0, u, 0.0];
+    gles.EnableClientState(gles11::TEXTURE_COORD_ARRAY);
+    gles.TexCoordPointer(2, gles11::FLOAT, 0, tex_coords.as_ptr() as *const GLvoid);
+    gles.Enable(gles11::TEXTURE_2D);
+    gles.DrawArrays(gles11::TRIANGLES, 0, 6);
+}
+
 unsafe fn upload_rgba8_pixels(gles: &mut dyn GLES, pixels: &[u8], dimensions: (u32, u32)) {
     gles.TexImage2D(
         gles11::TEXTURE_2D,
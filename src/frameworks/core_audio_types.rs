@@ -5,7 +5,7 @@
  */
 //! The Core Audio Types framework. (Yes, it's not part of Core Audio?)
 
-use crate::mem::SafeRead;
+use crate::mem::{MutVoidPtr, SafeRead};
 
 // The audio frameworks love FourCC's, and we currently don't need these
 // anywhere else, so this is as good a place to put this as any.
@@ -89,3 +89,23 @@ pub const kAudioFormatFlagIsFloat: AudioFormatFlags = 1 << 0;
 pub const kAudioFormatFlagIsBigEndian: AudioFormatFlags = 1 << 1;
 pub const kAudioFormatFlagIsSignedInteger: AudioFormatFlags = 1 << 2;
 pub const kAudioFormatFlagIsPacked: AudioFormatFlags = 1 << 3;
+pub const kAudioFormatFlagIsNonInterleaved: AudioFormatFlags = 1 << 5;
+
+#[derive(Copy, Clone)]
+#[repr(C, packed)]
+pub struct AudioBuffer {
+    pub number_channels: u32,
+    pub data_byte_size: u32,
+    pub data: MutVoidPtr,
+}
+unsafe impl SafeRead for AudioBuffer {}
+
+/// This is a variable-size struct: `number_buffers` is followed by that many
+/// [AudioBuffer]s, but only the first one is declared here.
+#[derive(Copy, Clone)]
+#[repr(C, packed)]
+pub struct AudioBufferList {
+    pub number_buffers: u32,
+    pub buffers: [AudioBuffer; 1],
+}
+unsafe impl SafeRead for AudioBufferList {}
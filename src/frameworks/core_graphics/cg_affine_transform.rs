@@ -85,8 +85,9 @@ pub const CGAffineTransformIdentity: CGAffineTransform = CGAffineTransform {
 
 pub const CONSTANTS: ConstantExports = &[(
     "_CGAffineTransformIdentity",
-    HostConstant::Custom(|mem| {
-        mem.alloc_and_write(CGAffineTransformIdentity)
+    HostConstant::Custom(|env| {
+        env.mem
+            .alloc_and_write(CGAffineTransformIdentity)
             .cast()
             .cast_const()
     }),
@@ -101,9 +102,17 @@ impl CGAffineTransform {
         self == CGAffineTransformIdentity
     }
     pub fn make_rotation(angle: CGFloat) -> Self {
-        Matrix::<3>::from(&Matrix::<2>::z_rotation(angle))
-            .try_into()
-            .unwrap()
+        // Matrix::z_rotation() rotates the opposite way to what CG does in
+        // this representation, so the matrix is spelled out instead.
+        let (sin, cos) = angle.sin_cos();
+        CGAffineTransform {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            tx: 0.0,
+            ty: 0.0,
+        }
     }
     pub fn make_scale(x: CGFloat, y: CGFloat) -> Self {
         Matrix::<3>::from(&Matrix::<2>::scale_2d(x, y))
@@ -283,6 +292,102 @@ pub fn CGRectApplyAffineTransform(
     transform.apply_to_rect(rect)
 }
 
+#[cfg(test)]
+#[test]
+fn test_transforms() {
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_close(a: CGPoint, b: CGPoint) {
+        assert!(
+            (a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4,
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+    let point = CGPoint { x: 3.0, y: 4.0 };
+
+    // Positive angles rotate counterclockwise, from the x axis to the y axis.
+    assert_close(
+        CGAffineTransform::make_rotation(FRAC_PI_2).apply_to_point(CGPoint { x: 1.0, y: 0.0 }),
+        CGPoint { x: 0.0, y: 1.0 },
+    );
+    assert_close(
+        CGAffineTransform::make_scale(2.0, -1.0).apply_to_point(point),
+        CGPoint { x: 6.0, y: -4.0 },
+    );
+    assert_close(
+        CGAffineTransform::make_translation(10.0, 20.0).apply_to_point(point),
+        CGPoint { x: 13.0, y: 24.0 },
+    );
+
+    // Concatenation applies the first transform first.
+    let scale = CGAffineTransform::make_scale(2.0, 2.0);
+    let translation = CGAffineTransform::make_translation(10.0, 0.0);
+    assert_close(
+        translation.concat(scale).apply_to_point(point),
+        CGPoint { x: 26.0, y: 8.0 },
+    );
+    assert_close(
+        scale.concat(translation).apply_to_point(point),
+        CGPoint { x: 16.0, y: 8.0 },
+    );
+    // The other operations apply the new transform before the existing one,
+    // like the CTM functions do.
+    assert_close(
+        scale.translate(10.0, 0.0).apply_to_point(point),
+        CGPoint { x: 26.0, y: 8.0 },
+    );
+    assert_close(
+        translation.rotate(FRAC_PI_2).apply_to_point(point),
+        CGPoint { x: 6.0, y: 3.0 },
+    );
+
+    // Inverting undoes the transform.
+    let transforms = [
+        CGAffineTransformIdentity,
+        CGAffineTransform::make_rotation(1.0),
+        scale.rotate(-2.5).translate(-7.0, 0.5),
+        CGAffineTransform {
+            a: 2.0,
+            b: 1.0,
+            c: -0.5,
+            d: 3.0,
+            tx: 5.0,
+            ty: -7.0,
+        },
+    ];
+    for transform in transforms {
+        let transformed = transform.apply_to_point(point);
+        assert_close(transform.invert().apply_to_point(transformed), point);
+        assert_close(
+            transform.concat(transform.invert()).apply_to_point(point),
+            point,
+        );
+    }
+    // Non-invertible transforms are returned unchanged.
+    let singular = CGAffineTransform::make_scale(0.0, 1.0);
+    assert_eq!(singular.invert(), singular);
+
+    // Rectangles become the bounding box of their transformed corners.
+    let rect = CGRect {
+        origin: CGPoint { x: 1.0, y: 2.0 },
+        size: CGSize {
+            width: 3.0,
+            height: 4.0,
+        },
+    };
+    let rotated = CGAffineTransform::make_rotation(FRAC_PI_2).apply_to_rect(rect);
+    assert_close(rotated.origin, CGPoint { x: -6.0, y: 1.0 });
+    assert_close(
+        CGPoint {
+            x: rotated.size.width,
+            y: rotated.size.height,
+        },
+        CGPoint { x: 4.0, y: 3.0 },
+    );
+}
+
 pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(CGAffineTransformIsIdentity(_)),
     export_c_func!(CGAffineTransformEqualToTransform(_, _)),
@@ -9,7 +9,11 @@ use super::cg_affine_transform::{CGAffineTransform, CGAffineTransformIdentity};
 use super::cg_color_space::{
     kCGColorSpaceGenericGray, kCGColorSpaceGenericRGB, CGColorSpaceHostObject, CGColorSpaceRef,
 };
-use super::cg_context::{CGContextHostObject, CGContextRef, CGContextSubclass};
+use super::cg_context::{
+    kCGLineCapButt, kCGLineCapRound, kCGLineCapSquare, kCGLineJoinBevel, kCGLineJoinMiter,
+    kCGLineJoinRound, kCGTextFill, CGContextHostObject, CGContextRef, CGContextSubclass, CGLineCap,
+    CGLineJoin, Subpath,
+};
 use super::cg_image::{
     self, kCGBitmapAlphaInfoMask, kCGBitmapByteOrderMask, kCGImageAlphaFirst, kCGImageAlphaLast,
     kCGImageAlphaNone, kCGImageAlphaNoneSkipFirst, kCGImageAlphaNoneSkipLast, kCGImageAlphaOnly,
@@ -20,7 +24,7 @@ use super::{CGFloat, CGPoint, CGRect};
 use crate::dyld::{export_c_func, FunctionExports};
 use crate::image::{gamma_decode, gamma_encode, Image};
 use crate::mem::{GuestUSize, Mem, MutVoidPtr};
-use crate::objc::ObjC;
+use crate::objc::{nil, ObjC};
 use crate::Environment;
 
 #[derive(Copy, Clone)]
@@ -82,7 +86,17 @@ pub fn CGBitmapContextCreate(
         }),
         // TODO: is this the correct default?
         rgb_fill_color: (0.0, 0.0, 0.0, 0.0),
+        rgb_stroke_color: (0.0, 0.0, 0.0, 1.0),
         transform: CGAffineTransformIdentity,
+        line_width: 1.0,
+        line_cap: kCGLineCapButt,
+        line_join: kCGLineJoinMiter,
+        clip: None,
+        path: Vec::new(),
+        saved_gstates: Vec::new(),
+        font: nil,
+        text_drawing_mode: kCGTextFill,
+        text_matrix: CGAffineTransformIdentity,
     };
     let isa = env
         .objc
@@ -110,23 +124,26 @@ pub fn CGBitmapContextGetHeight(env: &mut Environment, context: CGContextRef) ->
 }
 
 pub fn CGBitmapContextCreateImage(env: &mut Environment, context: CGContextRef) -> CGImageRef {
-    // TODO: Image::from_pixel_vec() should not exist, and this function should
-    // support any bitmap format.
+    // TODO: Image::from_pixel_vec() should not exist.
     let host_obj = env.objc.borrow::<CGContextHostObject>(context);
     let CGContextSubclass::CGBitmapContext(bitmap_data) = host_obj.subclass;
-    assert!(
-        bitmap_data.bits_per_component == 8
-            && bitmap_data.bytes_per_row == bitmap_data.width * 4
-            && bitmap_data.color_space == kCGColorSpaceGenericRGB
-            && bitmap_data.alpha_info == kCGImageAlphaPremultipliedLast
+    let bytes = env.mem.bytes_at(
+        bitmap_data.data.cast(),
+        bitmap_data.bytes_per_row * bitmap_data.height,
     );
-    let pixels = env
-        .mem
-        .bytes_at(
-            bitmap_data.data.cast(),
-            bitmap_data.bytes_per_row * bitmap_data.height,
-        )
-        .to_vec();
+    // The context's layout was validated when it was created, and it always
+    // has the default byte order.
+    let pixels = cg_image::convert_to_rgba8(
+        bytes,
+        bitmap_data.width,
+        bitmap_data.height,
+        bitmap_data.bits_per_component,
+        bytes_per_pixel(&bitmap_data) * 8,
+        bitmap_data.bytes_per_row,
+        Some(bitmap_data.color_space),
+        bitmap_data.alpha_info,
+    )
+    .unwrap();
     cg_image::from_image(
         env,
         Image::from_pixel_vec(pixels, (bitmap_data.width, bitmap_data.height)),
@@ -322,7 +339,8 @@ fn put_pixel(
                 blend_premultiplied(bg_pixel, pixel)
             }
             kCGImageAlphaOnly => (pixel.0, pixel.1, pixel.2, blend_alpha(bg_pixel.3, pixel.3)),
-            _ => pixel,
+            // The background is opaque, but the new pixel might not be.
+            _ => blend_straight(bg_pixel, pixel),
         }
     } else {
         pixel
@@ -351,7 +369,9 @@ fn put_pixel(
 pub struct CGBitmapContextDrawer<'a> {
     bitmap_info: CGBitmapContextData,
     rgb_fill_color: (CGFloat, CGFloat, CGFloat, CGFloat),
+    rgb_stroke_color: (CGFloat, CGFloat, CGFloat, CGFloat),
     transform: CGAffineTransform,
+    clip: Option<CGRect>,
     pixels: &'a mut [u8],
 }
 impl CGBitmapContextDrawer<'_> {
@@ -363,7 +383,10 @@ impl CGBitmapContextDrawer<'_> {
         let &CGContextHostObject {
             subclass: CGContextSubclass::CGBitmapContext(bitmap_info),
             rgb_fill_color,
+            rgb_stroke_color,
             transform,
+            clip,
+            ..
         } = objc.borrow(context);
 
         let pixels = get_pixels(&bitmap_info, mem);
@@ -371,11 +394,20 @@ impl CGBitmapContextDrawer<'_> {
         CGBitmapContextDrawer {
             bitmap_info,
             rgb_fill_color,
+            rgb_stroke_color,
             transform,
+            clip,
             pixels,
         }
     }
 
+    /// Concatenate a transform with the context's current transform, for this
+    /// drawer only. This is like `CGContextConcatCTM` but doesn't affect the
+    /// context.
+    pub fn concat_transform(&mut self, transform: CGAffineTransform) {
+        self.transform = transform.concat(self.transform);
+    }
+
     pub fn width(&self) -> GuestUSize {
         self.bitmap_info.width
     }
@@ -385,31 +417,81 @@ impl CGBitmapContextDrawer<'_> {
     /// Get the current fill color. The returned color is linear RGB, not sRGB.
     /// It has premultiplied alpha if the context does.
     pub fn rgb_fill_color(&self) -> (CGFloat, CGFloat, CGFloat, CGFloat) {
+        self.convert_color(self.rgb_fill_color)
+    }
+    /// Get the current stroke color, in the same representation as
+    /// [Self::rgb_fill_color].
+    pub fn rgb_stroke_color(&self) -> (CGFloat, CGFloat, CGFloat, CGFloat) {
+        self.convert_color(self.rgb_stroke_color)
+    }
+    fn convert_color(
+        &self,
+        color: (CGFloat, CGFloat, CGFloat, CGFloat),
+    ) -> (CGFloat, CGFloat, CGFloat, CGFloat) {
         let multiply_by = match self.bitmap_info.alpha_info {
-            kCGImageAlphaPremultipliedLast | kCGImageAlphaPremultipliedFirst => {
-                self.rgb_fill_color.3
-            }
+            kCGImageAlphaPremultipliedLast | kCGImageAlphaPremultipliedFirst => color.3,
             _ => 1.0,
         };
         // Multiplying before decoding matches the Simulator's output.
         (
-            gamma_decode(self.rgb_fill_color.0 * multiply_by),
-            gamma_decode(self.rgb_fill_color.1 * multiply_by),
-            gamma_decode(self.rgb_fill_color.2 * multiply_by),
-            self.rgb_fill_color.3, // alpha is always linear
+            gamma_decode(color.0 * multiply_by),
+            gamma_decode(color.1 * multiply_by),
+            gamma_decode(color.2 * multiply_by),
+            color.3, // alpha is always linear
         )
     }
+    /// Convert a color with premultiplied alpha, like the ones [Image]
+    /// provides, to the representation the context uses.
+    pub fn color_from_premultiplied(
+        &self,
+        color: (CGFloat, CGFloat, CGFloat, CGFloat),
+    ) -> (CGFloat, CGFloat, CGFloat, CGFloat) {
+        let (r, g, b, a) = color;
+        match self.bitmap_info.alpha_info {
+            kCGImageAlphaPremultipliedLast | kCGImageAlphaPremultipliedFirst => color,
+            _ if a == 0.0 => (0.0, 0.0, 0.0, 0.0),
+            _ => (r / a, g / a, b / a, a),
+        }
+    }
     /// Set the pixel at `coords` to `color`. `color` must be linear RGB, not
     /// sRGB! Note that `coords` are absolute: you must do transformation
-    /// yourself.
+    /// yourself. Pixels outside the clipping area are left unchanged.
     pub fn put_pixel(
         &mut self,
         coords: (i32, i32),
         color: (CGFloat, CGFloat, CGFloat, CGFloat),
         blend: bool,
     ) {
+        if let Some(clip) = self.clip {
+            let (x, y) = (coords.0 as CGFloat + 0.5, coords.1 as CGFloat + 0.5);
+            if x < clip.origin.x
+                || y < clip.origin.y
+                || x >= clip.origin.x + clip.size.width
+                || y >= clip.origin.y + clip.size.height
+            {
+                return;
+            }
+        }
         put_pixel(&self.bitmap_info, self.pixels, coords, color, blend)
     }
+    /// Blend `color` into the pixel at `coords`, for a pixel that is only
+    /// partly covered (`coverage` is between 0 and 1) by what is being drawn.
+    /// Otherwise like [Self::put_pixel].
+    pub fn blend_pixel_with_coverage(
+        &mut self,
+        coords: (i32, i32),
+        color: (CGFloat, CGFloat, CGFloat, CGFloat),
+        coverage: CGFloat,
+    ) {
+        let (r, g, b, a) = color;
+        let color = match self.bitmap_info.alpha_info {
+            kCGImageAlphaPremultipliedLast | kCGImageAlphaPremultipliedFirst => {
+                (r * coverage, g * coverage, b * coverage, a * coverage)
+            }
+            _ => (r, g, b, a * coverage),
+        };
+        self.put_pixel(coords, color, /* blend: */ true)
+    }
 
     /// Takes a [CGRect] and applies the current transform to it, and iterates
     /// over the transformed, clipped, absolute integer pixel co-ordinates in
@@ -478,7 +560,9 @@ fn test_iter_transformed_pixels() {
                 alpha_info: 0,
             },
             rgb_fill_color: (0.0, 0.0, 0.0, 0.0),
+            rgb_stroke_color: (0.0, 0.0, 0.0, 0.0),
             transform,
+            clip: None,
             pixels: &mut [],
         }
     }
@@ -567,6 +651,360 @@ pub(super) fn fill_rect(env: &mut Environment, context: CGContextRef, rect: CGRe
     }
 }
 
+/// How many scanlines are sampled per row of pixels when filling a path, for
+/// vertical anti-aliasing. Horizontal coverage is computed exactly.
+const FILL_SUBSAMPLES: usize = 4;
+
+/// Fill polygons (each implicitly closed) given in device space, with the
+/// nonzero winding rule or the even-odd rule. Overlapping polygons are
+/// combined according to the rule, rather than drawn on top of each other.
+fn fill_polygons(
+    drawer: &mut CGBitmapContextDrawer,
+    polygons: &[Vec<CGPoint>],
+    even_odd: bool,
+    color: (CGFloat, CGFloat, CGFloat, CGFloat),
+) {
+    // Each edge is (lower point, upper point, winding direction).
+    let mut edges: Vec<(CGPoint, CGPoint, i32)> = Vec::new();
+    for polygon in polygons {
+        for (i, &a) in polygon.iter().enumerate() {
+            let b = polygon[(i + 1) % polygon.len()];
+            if a.y < b.y {
+                edges.push((a, b, 1));
+            } else if a.y > b.y {
+                edges.push((b, a, -1));
+            }
+        }
+    }
+    if edges.is_empty() {
+        return;
+    }
+
+    let min_y = edges.iter().map(|e| e.0.y).fold(f32::INFINITY, f32::min);
+    let max_y = edges
+        .iter()
+        .map(|e| e.1.y)
+        .fold(f32::NEG_INFINITY, f32::max);
+    let y_start = min_y.floor().max(0.0) as GuestUSize;
+    let y_end = (max_y.ceil().min(drawer.height() as f32)).max(0.0) as GuestUSize;
+    let width = drawer.width() as usize;
+
+    let mut coverage = vec![0.0f32; width];
+    let mut crossings: Vec<(f32, i32)> = Vec::new();
+    for y in y_start..y_end {
+        coverage.fill(0.0);
+        for sample in 0..FILL_SUBSAMPLES {
+            let sample_y = y as f32 + (sample as f32 + 0.5) / FILL_SUBSAMPLES as f32;
+            crossings.clear();
+            for &(lower, upper, direction) in &edges {
+                if lower.y <= sample_y && sample_y < upper.y {
+                    let t = (sample_y - lower.y) / (upper.y - lower.y);
+                    crossings.push((lower.x + (upper.x - lower.x) * t, direction));
+                }
+            }
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut winding = 0;
+            for pair in crossings.windows(2) {
+                winding += pair[0].1;
+                let inside = if even_odd {
+                    winding % 2 != 0
+                } else {
+                    winding != 0
+                };
+                if !inside {
+                    continue;
+                }
+                let span_start = pair[0].0.clamp(0.0, width as f32);
+                let span_end = pair[1].0.clamp(0.0, width as f32);
+                let first_pixel = span_start.floor() as usize;
+                let end_pixel = (span_end.ceil() as usize).min(width);
+                for (x, pixel_coverage) in coverage
+                    .iter_mut()
+                    .enumerate()
+                    .take(end_pixel)
+                    .skip(first_pixel)
+                {
+                    let overlap = span_end.min(x as f32 + 1.0) - span_start.max(x as f32);
+                    *pixel_coverage += overlap.max(0.0) / FILL_SUBSAMPLES as f32;
+                }
+            }
+        }
+        for (x, &pixel_coverage) in coverage.iter().enumerate() {
+            if pixel_coverage > 0.0 {
+                drawer.blend_pixel_with_coverage(
+                    (x as i32, y as i32),
+                    color,
+                    pixel_coverage.min(1.0),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_fill_polygons() {
+    fn fill(polygons: &[Vec<CGPoint>], even_odd: bool) -> Vec<u8> {
+        let mut pixels = vec![0u8; 4 * 4];
+        let mut drawer = CGBitmapContextDrawer {
+            bitmap_info: CGBitmapContextData {
+                data: crate::mem::Ptr::null(),
+                data_is_owned: false,
+                width: 4,
+                height: 4,
+                bits_per_component: 8,
+                bytes_per_row: 4,
+                color_space: kCGColorSpaceGenericGray,
+                alpha_info: kCGImageAlphaNone,
+            },
+            rgb_fill_color: (0.0, 0.0, 0.0, 0.0),
+            rgb_stroke_color: (0.0, 0.0, 0.0, 0.0),
+            transform: CGAffineTransformIdentity,
+            clip: None,
+            pixels: &mut pixels,
+        };
+        fill_polygons(&mut drawer, polygons, even_odd, (1.0, 1.0, 1.0, 1.0));
+        pixels
+    }
+    fn square(from: f32, to: f32) -> Vec<CGPoint> {
+        vec![
+            CGPoint { x: from, y: from },
+            CGPoint { x: to, y: from },
+            CGPoint { x: to, y: to },
+            CGPoint { x: from, y: to },
+        ]
+    }
+
+    // Two squares with the same orientation, one inside the other.
+    let squares = [square(0.0, 4.0), square(1.0, 3.0)];
+    assert_eq!(fill(&squares, /* even_odd: */ false), vec![255; 16]);
+    #[rustfmt::skip]
+    assert_eq!(fill(&squares, /* even_odd: */ true), vec![
+        255, 255, 255, 255,
+        255,   0,   0, 255,
+        255,   0,   0, 255,
+        255, 255, 255, 255,
+    ]);
+
+    // Partly covered pixels are blended according to their coverage, which
+    // here is a half at the edges and a quarter at the corners. (This is
+    // in linear RGB, so it's brighter once encoded.)
+    #[rustfmt::skip]
+    assert_eq!(fill(&[square(0.5, 3.5)], /* even_odd: */ false), vec![
+        135, 186, 186, 135,
+        186, 255, 255, 186,
+        186, 255, 255, 186,
+        135, 186, 186, 135,
+    ]);
+}
+
+/// Implementation of `CGContextFillPath` (`even_odd` == [false]) and
+/// `CGContextEOFillPath` (`even_odd` == [true]) for `CGBitmapContext`. Open
+/// subpaths are implicitly closed.
+pub(super) fn fill_path(
+    env: &mut Environment,
+    context: CGContextRef,
+    path: &[Subpath],
+    even_odd: bool,
+) {
+    let mut drawer = CGBitmapContextDrawer::new(&env.objc, &mut env.mem, context);
+    let color = drawer.rgb_fill_color();
+    let polygons: Vec<Vec<CGPoint>> = path
+        .iter()
+        .filter(|subpath| subpath.points.len() >= 3)
+        .map(|subpath| subpath.points.clone())
+        .collect();
+    fill_polygons(&mut drawer, &polygons, even_odd, color);
+}
+
+/// Polygon approximating a circle, for round caps and joins.
+fn circle_polygon(center: CGPoint, radius: CGFloat) -> Vec<CGPoint> {
+    let segments = ((radius * std::f32::consts::TAU / 2.0).ceil() as usize).clamp(8, 256);
+    (0..segments)
+        .map(|i| {
+            let angle = std::f32::consts::TAU * (i as f32 / segments as f32);
+            CGPoint {
+                x: center.x + radius * angle.cos(),
+                y: center.y + radius * angle.sin(),
+            }
+        })
+        .collect()
+}
+
+/// Build the outline of a stroked subpath, as polygons that all have the same
+/// orientation, so that filling them with the nonzero winding rule gives their
+/// union. `half_width` is in device space.
+fn stroke_outline(
+    subpath: &Subpath,
+    half_width: CGFloat,
+    cap: CGLineCap,
+    join: CGLineJoin,
+    polygons: &mut Vec<Vec<CGPoint>>,
+) {
+    // CG's default miter limit.
+    const MITER_LIMIT: CGFloat = 10.0;
+
+    let mut points = subpath.points.clone();
+    points.dedup();
+    let closed = subpath.closed && points.len() >= 3;
+    if closed && points.first() == points.last() {
+        points.pop();
+    }
+
+    if points.len() == 1 {
+        // A zero-length subpath is only visible with round or square caps.
+        let p = points[0];
+        match cap {
+            kCGLineCapRound => polygons.push(circle_polygon(p, half_width)),
+            kCGLineCapSquare => polygons.push(vec![
+                CGPoint {
+                    x: p.x - half_width,
+                    y: p.y - half_width,
+                },
+                CGPoint {
+                    x: p.x + half_width,
+                    y: p.y - half_width,
+                },
+                CGPoint {
+                    x: p.x + half_width,
+                    y: p.y + half_width,
+                },
+                CGPoint {
+                    x: p.x - half_width,
+                    y: p.y + half_width,
+                },
+            ]),
+            _ => (),
+        }
+        return;
+    }
+
+    let offset = |p: CGPoint, (dx, dy): (CGFloat, CGFloat), scale: CGFloat| CGPoint {
+        x: p.x + dx * scale,
+        y: p.y + dy * scale,
+    };
+    let direction = |a: CGPoint, b: CGPoint| {
+        let length = (b.x - a.x).hypot(b.y - a.y);
+        ((b.x - a.x) / length, (b.y - a.y) / length)
+    };
+
+    let segment_count = if closed {
+        points.len()
+    } else {
+        points.len() - 1
+    };
+    let segments: Vec<(CGPoint, CGPoint, (CGFloat, CGFloat))> = (0..segment_count)
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            (a, b, direction(a, b))
+        })
+        .collect();
+
+    let mut outline_polygons = Vec::new();
+    for (i, &(mut a, mut b, d)) in segments.iter().enumerate() {
+        if !closed && cap == kCGLineCapSquare {
+            if i == 0 {
+                a = offset(a, d, -half_width);
+            }
+            if i == segment_count - 1 {
+                b = offset(b, d, half_width);
+            }
+        }
+        let normal = (-d.1, d.0);
+        outline_polygons.push(vec![
+            offset(a, normal, half_width),
+            offset(b, normal, half_width),
+            offset(b, normal, -half_width),
+            offset(a, normal, -half_width),
+        ]);
+    }
+
+    let join_count = if closed {
+        segment_count
+    } else {
+        segment_count - 1
+    };
+    for i in 0..join_count {
+        let (_, p, d0) = segments[i];
+        let (_, _, d1) = segments[(i + 1) % segment_count];
+        if join == kCGLineJoinRound {
+            outline_polygons.push(circle_polygon(p, half_width));
+            continue;
+        }
+        // The gap to fill is on the outside of the turn.
+        let cross = d0.0 * d1.1 - d0.1 * d1.0;
+        if cross == 0.0 {
+            continue;
+        }
+        let side = if cross > 0.0 { -1.0 } else { 1.0 };
+        let n0 = (-d0.1 * side, d0.0 * side);
+        let n1 = (-d1.1 * side, d1.0 * side);
+        let outer0 = offset(p, n0, half_width);
+        let outer1 = offset(p, n1, half_width);
+        // The miter length relative to the line width is 1/cos(θ/2), where θ
+        // is the angle between the normals.
+        let cos_half_angle = ((1.0 + n0.0 * n1.0 + n0.1 * n1.1) / 2.0).sqrt();
+        if join == kCGLineJoinMiter && cos_half_angle > 1.0 / MITER_LIMIT {
+            let bisector = direction(p, offset(p, (n0.0 + n1.0, n0.1 + n1.1), 1.0));
+            let tip = offset(p, bisector, half_width / cos_half_angle);
+            outline_polygons.push(vec![p, outer0, tip, outer1]);
+        } else {
+            assert!(join == kCGLineJoinMiter || join == kCGLineJoinBevel);
+            outline_polygons.push(vec![p, outer0, outer1]);
+        }
+    }
+
+    if !closed && cap == kCGLineCapRound {
+        outline_polygons.push(circle_polygon(points[0], half_width));
+        outline_polygons.push(circle_polygon(*points.last().unwrap(), half_width));
+    }
+
+    for mut polygon in outline_polygons {
+        let doubled_area: CGFloat = (0..polygon.len())
+            .map(|i| {
+                let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+                a.x * b.y - b.x * a.y
+            })
+            .sum();
+        if doubled_area < 0.0 {
+            polygon.reverse();
+        }
+        polygons.push(polygon);
+    }
+}
+
+/// Implementation of `CGContextStrokePath` for `CGBitmapContext`.
+pub(super) fn stroke_path(env: &mut Environment, context: CGContextRef, path: &[Subpath]) {
+    let &CGContextHostObject {
+        transform,
+        line_width,
+        line_cap,
+        line_join,
+        ..
+    } = env.objc.borrow(context);
+    // TODO: non-uniform scaling should make the line width vary with the
+    // direction of the line.
+    let scale = (transform.a * transform.d - transform.b * transform.c)
+        .abs()
+        .sqrt();
+    let half_width = if line_width > 0.0 {
+        line_width * scale / 2.0
+    } else {
+        // A width of zero means the thinnest line that can be drawn.
+        0.5
+    };
+
+    let mut polygons = Vec::new();
+    for subpath in path {
+        stroke_outline(subpath, half_width, line_cap, line_join, &mut polygons);
+    }
+
+    let mut drawer = CGBitmapContextDrawer::new(&env.objc, &mut env.mem, context);
+    let color = drawer.rgb_stroke_color();
+    fill_polygons(&mut drawer, &polygons, /* even_odd: */ false, color);
+}
+
 /// Implementation of `CGContextDrawImage` for `CGBitmapContext`.
 pub(super) fn draw_image(
     env: &mut Environment,
@@ -604,8 +1042,8 @@ pub(super) fn draw_image(
         let texel_x = (image_width as f32 * texel_x) as i32;
         // Image is in top-to-bottom order, but the bitmap is bottom-to-top
         let texel_y = (image_height as f32 * (1.0 - texel_y)) as i32;
-        // FIXME: might need alpha format conversion here
         if let Some(color) = image.get_pixel((texel_x, texel_y)) {
+            let color = drawer.color_from_premultiplied(color);
             drawer.put_pixel((x, y), color, /* blend: */ true)
         }
     }
@@ -0,0 +1,118 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `CGColor.h`
+//!
+//! In our implementation, `CGColorRef` is the same type as `UIColor*`, which
+//! is what the `CGColor` property of a `UIColor` returns (and it seems like
+//! UIKit itself maybe did this once). See also the remarks in ui_view.rs.
+
+use super::cg_color_space::{
+    kCGColorSpaceModelMonochrome, kCGColorSpaceModelRGB, CGColorSpaceCreateDeviceRGB,
+    CGColorSpaceGetModel, CGColorSpaceRef,
+};
+use super::CGFloat;
+use crate::dyld::{export_c_func, FunctionExports};
+use crate::frameworks::core_foundation::{CFRelease, CFRetain, CFTypeRef};
+use crate::frameworks::foundation::NSUInteger;
+use crate::frameworks::uikit::ui_color;
+use crate::mem::ConstPtr;
+use crate::objc::{id, msg, msg_class, nil};
+use crate::Environment;
+
+pub type CGColorRef = CFTypeRef;
+
+pub fn CGColorRelease(env: &mut Environment, c: CGColorRef) {
+    if !c.is_null() {
+        CFRelease(env, c);
+    }
+}
+pub fn CGColorRetain(env: &mut Environment, c: CGColorRef) -> CGColorRef {
+    if !c.is_null() {
+        CFRetain(env, c)
+    } else {
+        c
+    }
+}
+
+fn CGColorCreate(
+    env: &mut Environment,
+    space: CGColorSpaceRef,
+    components: ConstPtr<CGFloat>,
+) -> CGColorRef {
+    let (r, g, b, a) = match CGColorSpaceGetModel(env, space) {
+        kCGColorSpaceModelMonochrome => {
+            let w = env.mem.read(components);
+            let a = env.mem.read(components + 1);
+            (w, w, w, a)
+        }
+        kCGColorSpaceModelRGB => (
+            env.mem.read(components),
+            env.mem.read(components + 1),
+            env.mem.read(components + 2),
+            env.mem.read(components + 3),
+        ),
+        _ => unimplemented!(),
+    };
+    let color: id = msg_class![env; UIColor alloc];
+    msg![env; color initWithRed:r green:g blue:b alpha:a]
+}
+
+fn CGColorCreateCopy(env: &mut Environment, color: CGColorRef) -> CGColorRef {
+    // Colors are immutable.
+    CGColorRetain(env, color)
+}
+
+fn CGColorCreateCopyWithAlpha(
+    env: &mut Environment,
+    color: CGColorRef,
+    alpha: CGFloat,
+) -> CGColorRef {
+    let new: id = msg![env; color colorWithAlphaComponent:alpha];
+    CGColorRetain(env, new)
+}
+
+fn CGColorEqualToColor(env: &mut Environment, a: CGColorRef, b: CGColorRef) -> bool {
+    if a == b {
+        return true;
+    }
+    if a == nil || b == nil {
+        return false;
+    }
+    ui_color::get_rgba(&env.objc, a) == ui_color::get_rgba(&env.objc, b)
+        && ui_color::get_pattern(&env.objc, a) == ui_color::get_pattern(&env.objc, b)
+}
+
+fn CGColorGetAlpha(env: &mut Environment, color: CGColorRef) -> CGFloat {
+    ui_color::get_rgba(&env.objc, color).3
+}
+
+fn CGColorGetNumberOfComponents(_env: &mut Environment, _color: CGColorRef) -> NSUInteger {
+    4 // always RGBA, see CGColorGetColorSpace
+}
+
+fn CGColorGetComponents(env: &mut Environment, color: CGColorRef) -> ConstPtr<CGFloat> {
+    ui_color::get_components_ptr(env, color)
+}
+
+fn CGColorGetColorSpace(env: &mut Environment, _color: CGColorRef) -> CGColorSpaceRef {
+    // No "Get" function should return a new object, but we don't keep a color
+    // space around, so this leaks.
+    // TODO: should return kCGColorSpaceModelPattern for pattern colors
+    CGColorSpaceCreateDeviceRGB(env)
+}
+
+pub const FUNCTIONS: FunctionExports = &[
+    export_c_func!(CGColorRetain(_)),
+    export_c_func!(CGColorRelease(_)),
+    export_c_func!(CGColorCreate(_, _)),
+    export_c_func!(CGColorCreateCopy(_)),
+    export_c_func!(CGColorCreateCopyWithAlpha(_, _)),
+    export_c_func!(CGColorEqualToColor(_, _)),
+    export_c_func!(CGColorGetAlpha(_)),
+    export_c_func!(CGColorGetNumberOfComponents(_)),
+    export_c_func!(CGColorGetComponents(_)),
+    export_c_func!(CGColorGetColorSpace(_)),
+];
@@ -5,12 +5,16 @@
  */
 //! `CGContext.h`
 
-use super::cg_affine_transform::CGAffineTransform;
+use super::cg_affine_transform::{CGAffineTransform, CGAffineTransformIdentity};
+use super::cg_color::CGColorRef;
 use super::cg_image::CGImageRef;
-use super::{cg_bitmap_context, CGFloat, CGRect};
+use super::{cg_bitmap_context, CGFloat, CGPoint, CGRect, CGSize};
 use crate::dyld::{export_c_func, FunctionExports};
 use crate::frameworks::core_foundation::{CFRelease, CFRetain, CFTypeRef};
-use crate::objc::{objc_classes, ClassExports, HostObject};
+use crate::frameworks::foundation::ns_string;
+use crate::frameworks::uikit::{ui_color, ui_font};
+use crate::mem::{ConstPtr, GuestUSize};
+use crate::objc::{id, msg_class, nil, objc_classes, release, retain, ClassExports, HostObject};
 use crate::Environment;
 
 pub const CLASSES: ClassExports = objc_classes! {
@@ -28,6 +32,8 @@ pub const CLASSES: ClassExports = objc_classes! {
     if bitmap_data.data_is_owned {
         env.mem.free(bitmap_data.data);
     }
+    let font = host_obj.font;
+    release(env, font);
 
     env.objc.dealloc_object(this, &mut env.mem)
 }
@@ -39,17 +45,90 @@ pub const CLASSES: ClassExports = objc_classes! {
 pub(super) struct CGContextHostObject {
     pub(super) subclass: CGContextSubclass,
     pub(super) rgb_fill_color: (CGFloat, CGFloat, CGFloat, CGFloat),
+    pub(super) rgb_stroke_color: (CGFloat, CGFloat, CGFloat, CGFloat),
     /// Current transform.
     pub(super) transform: CGAffineTransform,
+    pub(super) line_width: CGFloat,
+    pub(super) line_cap: CGLineCap,
+    pub(super) line_join: CGLineJoin,
+    /// Clipping rectangle in device space, if any.
+    pub(super) clip: Option<CGRect>,
+    /// Current path. This is consumed by drawing it.
+    pub(super) path: Vec<Subpath>,
+    /// Stack for `CGContextSaveGState` and `CGContextRestoreGState`.
+    pub(super) saved_gstates: Vec<SavedGState>,
+    /// `UIFont*` selected with `CGContextSelectFont`, or nil. The bundled
+    /// fonts are shared with `UIFont`, so this is simply one of those.
+    pub(super) font: id,
+    pub(super) text_drawing_mode: CGTextDrawingMode,
+    /// Transform from text space to user space. Its translation is the text
+    /// position.
+    pub(super) text_matrix: CGAffineTransform,
 }
 impl HostObject for CGContextHostObject {}
 
+/// The parts of a context's state that `CGContextSaveGState` saves. Notably,
+/// this doesn't include the current path or the text matrix.
+/// TODO: The selected font should be saved too.
+#[derive(Clone, Copy)]
+pub(super) struct SavedGState {
+    rgb_fill_color: (CGFloat, CGFloat, CGFloat, CGFloat),
+    rgb_stroke_color: (CGFloat, CGFloat, CGFloat, CGFloat),
+    transform: CGAffineTransform,
+    line_width: CGFloat,
+    line_cap: CGLineCap,
+    line_join: CGLineJoin,
+    clip: Option<CGRect>,
+    text_drawing_mode: CGTextDrawingMode,
+}
+
+/// Part of a context's current path. Curves and arcs are flattened into line
+/// segments when they are added, and the points are in device space, because
+/// the transform that applies to a point is the one that was current when it
+/// was added.
+#[derive(Clone, Debug)]
+pub(super) struct Subpath {
+    pub(super) points: Vec<CGPoint>,
+    pub(super) closed: bool,
+}
+
 pub(super) enum CGContextSubclass {
     CGBitmapContext(cg_bitmap_context::CGBitmapContextData),
 }
 
 pub type CGContextRef = CFTypeRef;
 
+pub type CGLineCap = i32;
+pub const kCGLineCapButt: CGLineCap = 0;
+pub const kCGLineCapRound: CGLineCap = 1;
+pub const kCGLineCapSquare: CGLineCap = 2;
+
+pub type CGLineJoin = i32;
+pub const kCGLineJoinMiter: CGLineJoin = 0;
+pub const kCGLineJoinRound: CGLineJoin = 1;
+pub const kCGLineJoinBevel: CGLineJoin = 2;
+
+pub type CGPathDrawingMode = i32;
+pub const kCGPathFill: CGPathDrawingMode = 0;
+pub const kCGPathEOFill: CGPathDrawingMode = 1;
+pub const kCGPathStroke: CGPathDrawingMode = 2;
+pub const kCGPathFillStroke: CGPathDrawingMode = 3;
+pub const kCGPathEOFillStroke: CGPathDrawingMode = 4;
+
+pub type CGTextDrawingMode = i32;
+pub const kCGTextFill: CGTextDrawingMode = 0;
+pub const kCGTextStroke: CGTextDrawingMode = 1;
+pub const kCGTextFillStroke: CGTextDrawingMode = 2;
+pub const kCGTextInvisible: CGTextDrawingMode = 3;
+pub const kCGTextFillClip: CGTextDrawingMode = 4;
+pub const kCGTextStrokeClip: CGTextDrawingMode = 5;
+pub const kCGTextFillStrokeClip: CGTextDrawingMode = 6;
+pub const kCGTextClip: CGTextDrawingMode = 7;
+
+pub type CGTextEncoding = i32;
+pub const kCGEncodingFontSpecific: CGTextEncoding = 0;
+pub const kCGEncodingMacRoman: CGTextEncoding = 1;
+
 pub fn CGContextRelease(env: &mut Environment, c: CGContextRef) {
     if !c.is_null() {
         CFRelease(env, c);
@@ -63,6 +142,82 @@ pub fn CGContextRetain(env: &mut Environment, c: CGContextRef) -> CGContextRef {
     }
 }
 
+pub fn CGContextSaveGState(env: &mut Environment, context: CGContextRef) {
+    let host_obj = env.objc.borrow_mut::<CGContextHostObject>(context);
+    let gstate = SavedGState {
+        rgb_fill_color: host_obj.rgb_fill_color,
+        rgb_stroke_color: host_obj.rgb_stroke_color,
+        transform: host_obj.transform,
+        line_width: host_obj.line_width,
+        line_cap: host_obj.line_cap,
+        line_join: host_obj.line_join,
+        clip: host_obj.clip,
+        text_drawing_mode: host_obj.text_drawing_mode,
+    };
+    host_obj.saved_gstates.push(gstate);
+}
+pub fn CGContextRestoreGState(env: &mut Environment, context: CGContextRef) {
+    let host_obj = env.objc.borrow_mut::<CGContextHostObject>(context);
+    let Some(gstate) = host_obj.saved_gstates.pop() else {
+        log!("Warning: CGContextRestoreGState() called without a saved state, ignoring");
+        return;
+    };
+    let SavedGState {
+        rgb_fill_color,
+        rgb_stroke_color,
+        transform,
+        line_width,
+        line_cap,
+        line_join,
+        clip,
+        text_drawing_mode,
+    } = gstate;
+    host_obj.rgb_fill_color = rgb_fill_color;
+    host_obj.rgb_stroke_color = rgb_stroke_color;
+    host_obj.transform = transform;
+    host_obj.line_width = line_width;
+    host_obj.line_cap = line_cap;
+    host_obj.line_join = line_join;
+    host_obj.clip = clip;
+    host_obj.text_drawing_mode = text_drawing_mode;
+}
+
+pub fn CGContextClipToRect(env: &mut Environment, context: CGContextRef, rect: CGRect) {
+    let host_obj = env.objc.borrow_mut::<CGContextHostObject>(context);
+    // TODO: A rotated rectangle should not be clipped to as its bounding box.
+    let rect = host_obj.transform.apply_to_rect(rect);
+    let clip = if let Some(clip) = host_obj.clip {
+        // Clipping can only ever make the clipping area smaller.
+        let x = clip.origin.x.max(rect.origin.x);
+        let y = clip.origin.y.max(rect.origin.y);
+        let x_end = (clip.origin.x + clip.size.width).min(rect.origin.x + rect.size.width);
+        let y_end = (clip.origin.y + clip.size.height).min(rect.origin.y + rect.size.height);
+        CGRect {
+            origin: CGPoint { x, y },
+            size: CGSize {
+                width: (x_end - x).max(0.0),
+                height: (y_end - y).max(0.0),
+            },
+        }
+    } else {
+        rect
+    };
+    host_obj.clip = Some(clip);
+}
+
+pub fn CGContextGetClipBoundingBox(env: &mut Environment, context: CGContextRef) -> CGRect {
+    let (width, height, _) = cg_bitmap_context::get_data(&env.objc, context);
+    let host_obj = env.objc.borrow::<CGContextHostObject>(context);
+    let clip = host_obj.clip.unwrap_or(CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: CGSize {
+            width: width as CGFloat,
+            height: height as CGFloat,
+        },
+    });
+    host_obj.transform.invert().apply_to_rect(clip)
+}
+
 pub fn CGContextSetRGBFillColor(
     env: &mut Environment,
     context: CGContextRef,
@@ -89,6 +244,312 @@ fn CGContextSetGrayFillColor(
         .rgb_fill_color = color;
 }
 
+pub fn CGContextSetFillColorWithColor(
+    env: &mut Environment,
+    context: CGContextRef,
+    color: CGColorRef,
+) {
+    // TODO: support pattern colors
+    let color = ui_color::get_rgba(&env.objc, color);
+    env.objc
+        .borrow_mut::<CGContextHostObject>(context)
+        .rgb_fill_color = color;
+}
+
+pub fn CGContextSetRGBStrokeColor(
+    env: &mut Environment,
+    context: CGContextRef,
+    red: CGFloat,
+    green: CGFloat,
+    blue: CGFloat,
+    alpha: CGFloat,
+) {
+    let color = (red, green, blue, alpha);
+    env.objc
+        .borrow_mut::<CGContextHostObject>(context)
+        .rgb_stroke_color = color;
+}
+
+fn CGContextSetGrayStrokeColor(
+    env: &mut Environment,
+    context: CGContextRef,
+    gray: CGFloat,
+    alpha: CGFloat,
+) {
+    let color = (gray, gray, gray, alpha);
+    env.objc
+        .borrow_mut::<CGContextHostObject>(context)
+        .rgb_stroke_color = color;
+}
+
+pub fn CGContextSetStrokeColorWithColor(
+    env: &mut Environment,
+    context: CGContextRef,
+    color: CGColorRef,
+) {
+    // TODO: support pattern colors
+    let color = ui_color::get_rgba(&env.objc, color);
+    env.objc
+        .borrow_mut::<CGContextHostObject>(context)
+        .rgb_stroke_color = color;
+}
+
+pub fn CGContextSetLineWidth(env: &mut Environment, context: CGContextRef, width: CGFloat) {
+    env.objc
+        .borrow_mut::<CGContextHostObject>(context)
+        .line_width = width;
+}
+pub fn CGContextSetLineCap(env: &mut Environment, context: CGContextRef, cap: CGLineCap) {
+    assert!((kCGLineCapButt..=kCGLineCapSquare).contains(&cap));
+    env.objc.borrow_mut::<CGContextHostObject>(context).line_cap = cap;
+}
+pub fn CGContextSetLineJoin(env: &mut Environment, context: CGContextRef, join: CGLineJoin) {
+    assert!((kCGLineJoinMiter..=kCGLineJoinBevel).contains(&join));
+    env.objc
+        .borrow_mut::<CGContextHostObject>(context)
+        .line_join = join;
+}
+
+/// The current point of a path, in device space.
+fn current_point(path: &[Subpath]) -> Option<CGPoint> {
+    let subpath = path.last()?;
+    if subpath.closed {
+        subpath.points.first().copied()
+    } else {
+        subpath.points.last().copied()
+    }
+}
+
+/// Add a line from the current point to a point in device space.
+fn line_to_device_point(path: &mut Vec<Subpath>, point: CGPoint) {
+    match path.last_mut() {
+        Some(subpath) if !subpath.closed => subpath.points.push(point),
+        Some(subpath) => {
+            // Closing a subpath moves the current point back to its start, and
+            // anything added afterwards is a new subpath.
+            let start = subpath.points[0];
+            path.push(Subpath {
+                points: vec![start, point],
+                closed: false,
+            });
+        }
+        None => {
+            log!("Warning: adding a line to a path without a current point");
+            path.push(Subpath {
+                points: vec![point],
+                closed: false,
+            });
+        }
+    }
+}
+
+/// How many line segments to flatten a curve into, given the length of its
+/// control polygon or its arc length in device space.
+fn segment_count_for_length(length: CGFloat) -> usize {
+    // A segment every two pixels or so is indistinguishable from a curve.
+    ((length / 2.0).ceil() as usize).clamp(1, 256)
+}
+
+pub fn CGContextBeginPath(env: &mut Environment, context: CGContextRef) {
+    env.objc
+        .borrow_mut::<CGContextHostObject>(context)
+        .path
+        .clear();
+}
+
+pub fn CGContextMoveToPoint(env: &mut Environment, context: CGContextRef, x: CGFloat, y: CGFloat) {
+    let host_obj = env.objc.borrow_mut::<CGContextHostObject>(context);
+    let point = host_obj.transform.apply_to_point(CGPoint { x, y });
+    host_obj.path.push(Subpath {
+        points: vec![point],
+        closed: false,
+    });
+}
+
+pub fn CGContextAddLineToPoint(
+    env: &mut Environment,
+    context: CGContextRef,
+    x: CGFloat,
+    y: CGFloat,
+) {
+    let host_obj = env.objc.borrow_mut::<CGContextHostObject>(context);
+    let point = host_obj.transform.apply_to_point(CGPoint { x, y });
+    line_to_device_point(&mut host_obj.path, point);
+}
+
+pub fn CGContextAddCurveToPoint(
+    env: &mut Environment,
+    context: CGContextRef,
+    cp1x: CGFloat,
+    cp1y: CGFloat,
+    cp2x: CGFloat,
+    cp2y: CGFloat,
+    x: CGFloat,
+    y: CGFloat,
+) {
+    let host_obj = env.objc.borrow_mut::<CGContextHostObject>(context);
+    let Some(p0) = current_point(&host_obj.path) else {
+        log!("Warning: CGContextAddCurveToPoint() called without a current point, ignoring");
+        return;
+    };
+    // Transforming the control points is equivalent to transforming the curve,
+    // since the transform is affine.
+    let transform = host_obj.transform;
+    let p1 = transform.apply_to_point(CGPoint { x: cp1x, y: cp1y });
+    let p2 = transform.apply_to_point(CGPoint { x: cp2x, y: cp2y });
+    let p3 = transform.apply_to_point(CGPoint { x, y });
+
+    let distance = |a: CGPoint, b: CGPoint| (b.x - a.x).hypot(b.y - a.y);
+    let length = distance(p0, p1) + distance(p1, p2) + distance(p2, p3);
+    let segments = segment_count_for_length(length);
+    for i in 1..=segments {
+        let t = i as CGFloat / segments as CGFloat;
+        let u = 1.0 - t;
+        let (w0, w1, w2, w3) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+        let point = CGPoint {
+            x: w0 * p0.x + w1 * p1.x + w2 * p2.x + w3 * p3.x,
+            y: w0 * p0.y + w1 * p1.y + w2 * p2.y + w3 * p3.y,
+        };
+        line_to_device_point(&mut host_obj.path, point);
+    }
+}
+
+fn CGContextAddQuadCurveToPoint(
+    env: &mut Environment,
+    context: CGContextRef,
+    cpx: CGFloat,
+    cpy: CGFloat,
+    x: CGFloat,
+    y: CGFloat,
+) {
+    let host_obj = env.objc.borrow::<CGContextHostObject>(context);
+    let Some(p0) = current_point(&host_obj.path) else {
+        log!("Warning: CGContextAddQuadCurveToPoint() called without a current point, ignoring");
+        return;
+    };
+    // The start point has to be brought back into user space to express the
+    // curve as an equivalent cubic one.
+    let p0 = host_obj.transform.invert().apply_to_point(p0);
+    CGContextAddCurveToPoint(
+        env,
+        context,
+        p0.x + (cpx - p0.x) * (2.0 / 3.0),
+        p0.y + (cpy - p0.y) * (2.0 / 3.0),
+        x + (cpx - x) * (2.0 / 3.0),
+        y + (cpy - y) * (2.0 / 3.0),
+        x,
+        y,
+    );
+}
+
+pub fn CGContextAddArc(
+    env: &mut Environment,
+    context: CGContextRef,
+    x: CGFloat,
+    y: CGFloat,
+    radius: CGFloat,
+    start_angle: CGFloat,
+    end_angle: CGFloat,
+    clockwise: i32,
+) {
+    use std::f32::consts::TAU;
+
+    // Angles increase counterclockwise in CG's (unflipped) co-ordinate system.
+    let sweep = if clockwise != 0 {
+        -(start_angle - end_angle).rem_euclid(TAU)
+    } else {
+        (end_angle - start_angle).rem_euclid(TAU)
+    };
+    // A difference that's a multiple of 2π is a full circle.
+    let sweep = if sweep == 0.0 && start_angle != end_angle {
+        if clockwise != 0 {
+            -TAU
+        } else {
+            TAU
+        }
+    } else {
+        sweep
+    };
+
+    let host_obj = env.objc.borrow_mut::<CGContextHostObject>(context);
+    let transform = host_obj.transform;
+    let scale = (transform.a * transform.d - transform.b * transform.c)
+        .abs()
+        .sqrt();
+    let segments = segment_count_for_length(sweep.abs() * radius * scale);
+
+    for i in 0..=segments {
+        let angle = start_angle + sweep * (i as CGFloat / segments as CGFloat);
+        let point = transform.apply_to_point(CGPoint {
+            x: x + radius * angle.cos(),
+            y: y + radius * angle.sin(),
+        });
+        // The arc is connected to the current point by a line, if there is
+        // one.
+        if i == 0 && current_point(&host_obj.path).is_none() {
+            host_obj.path.push(Subpath {
+                points: vec![point],
+                closed: false,
+            });
+        } else {
+            line_to_device_point(&mut host_obj.path, point);
+        }
+    }
+}
+
+pub fn CGContextAddRect(env: &mut Environment, context: CGContextRef, rect: CGRect) {
+    let (x, y, size) = (rect.origin.x, rect.origin.y, rect.size);
+    CGContextMoveToPoint(env, context, x, y);
+    CGContextAddLineToPoint(env, context, x + size.width, y);
+    CGContextAddLineToPoint(env, context, x + size.width, y + size.height);
+    CGContextAddLineToPoint(env, context, x, y + size.height);
+    CGContextClosePath(env, context);
+}
+
+pub fn CGContextClosePath(env: &mut Environment, context: CGContextRef) {
+    let host_obj = env.objc.borrow_mut::<CGContextHostObject>(context);
+    if let Some(subpath) = host_obj.path.last_mut() {
+        subpath.closed = true;
+    }
+}
+
+pub fn CGContextDrawPath(env: &mut Environment, context: CGContextRef, mode: CGPathDrawingMode) {
+    let path = std::mem::take(&mut env.objc.borrow_mut::<CGContextHostObject>(context).path);
+    match mode {
+        kCGPathFill | kCGPathFillStroke => {
+            cg_bitmap_context::fill_path(env, context, &path, /* even_odd: */ false)
+        }
+        kCGPathEOFill | kCGPathEOFillStroke => {
+            cg_bitmap_context::fill_path(env, context, &path, /* even_odd: */ true)
+        }
+        kCGPathStroke => (),
+        _ => unimplemented!("CGContextDrawPath() mode {}", mode),
+    }
+    if matches!(
+        mode,
+        kCGPathStroke | kCGPathFillStroke | kCGPathEOFillStroke
+    ) {
+        cg_bitmap_context::stroke_path(env, context, &path);
+    }
+}
+pub fn CGContextFillPath(env: &mut Environment, context: CGContextRef) {
+    CGContextDrawPath(env, context, kCGPathFill);
+}
+pub fn CGContextEOFillPath(env: &mut Environment, context: CGContextRef) {
+    CGContextDrawPath(env, context, kCGPathEOFill);
+}
+pub fn CGContextStrokePath(env: &mut Environment, context: CGContextRef) {
+    CGContextDrawPath(env, context, kCGPathStroke);
+}
+
+pub fn CGContextStrokeRect(env: &mut Environment, context: CGContextRef, rect: CGRect) {
+    // This doesn't affect the current path.
+    let path = std::mem::take(&mut env.objc.borrow_mut::<CGContextHostObject>(context).path);
+    CGContextAddRect(env, context, rect);
+    CGContextStrokePath(env, context);
+    env.objc.borrow_mut::<CGContextHostObject>(context).path = path;
+}
+
 pub fn CGContextFillRect(env: &mut Environment, context: CGContextRef, rect: CGRect) {
     cg_bitmap_context::fill_rect(env, context, rect, /* clear: */ false);
 }
@@ -132,6 +593,152 @@ pub fn CGContextTranslateCTM(
     host_obj.transform = host_obj.transform.translate(tx, ty);
 }
 
+fn CGContextSelectFont(
+    env: &mut Environment,
+    context: CGContextRef,
+    name: ConstPtr<u8>,
+    size: CGFloat,
+    text_encoding: CGTextEncoding,
+) {
+    if text_encoding != kCGEncodingFontSpecific && text_encoding != kCGEncodingMacRoman {
+        log!(
+            "Warning: CGContextSelectFont() with unknown text encoding {}",
+            text_encoding
+        );
+    }
+    let name = env.mem.cstr_at_utf8(name).unwrap().to_string();
+    log_dbg!("CGContextSelectFont({:?}, {:?})", name, size);
+    let name = ns_string::from_rust_string(env, name);
+    // UIFont falls back to a similar font for names it doesn't know.
+    let font: id = msg_class![env; UIFont fontWithName:name size:size];
+    release(env, name);
+
+    retain(env, font);
+    let old_font = std::mem::replace(
+        &mut env.objc.borrow_mut::<CGContextHostObject>(context).font,
+        font,
+    );
+    release(env, old_font);
+}
+
+pub fn CGContextSetTextDrawingMode(
+    env: &mut Environment,
+    context: CGContextRef,
+    mode: CGTextDrawingMode,
+) {
+    assert!((kCGTextFill..=kCGTextClip).contains(&mode));
+    env.objc
+        .borrow_mut::<CGContextHostObject>(context)
+        .text_drawing_mode = mode;
+}
+
+pub fn CGContextSetTextMatrix(
+    env: &mut Environment,
+    context: CGContextRef,
+    transform: CGAffineTransform,
+) {
+    env.objc
+        .borrow_mut::<CGContextHostObject>(context)
+        .text_matrix = transform;
+}
+pub fn CGContextGetTextMatrix(env: &mut Environment, context: CGContextRef) -> CGAffineTransform {
+    env.objc.borrow::<CGContextHostObject>(context).text_matrix
+}
+
+pub fn CGContextSetTextPosition(
+    env: &mut Environment,
+    context: CGContextRef,
+    x: CGFloat,
+    y: CGFloat,
+) {
+    let host_obj = env.objc.borrow_mut::<CGContextHostObject>(context);
+    host_obj.text_matrix.tx = x;
+    host_obj.text_matrix.ty = y;
+}
+pub fn CGContextGetTextPosition(env: &mut Environment, context: CGContextRef) -> CGPoint {
+    let text_matrix = env.objc.borrow::<CGContextHostObject>(context).text_matrix;
+    CGPoint {
+        x: text_matrix.tx,
+        y: text_matrix.ty,
+    }
+}
+
+pub fn CGContextShowText(
+    env: &mut Environment,
+    context: CGContextRef,
+    string: ConstPtr<u8>,
+    length: GuestUSize,
+) {
+    // TODO: Decode Mac OS Roman properly. Only ASCII is the same in it and
+    // Unicode.
+    let bytes = env.mem.bytes_at(string, length);
+    if !bytes.is_ascii() {
+        log!("TODO: CGContextShowText() with non-ASCII text {:?}", bytes);
+    }
+    let text: String = bytes
+        .iter()
+        .map(|&byte| {
+            if byte.is_ascii() {
+                byte as char
+            } else {
+                char::REPLACEMENT_CHARACTER
+            }
+        })
+        .collect();
+    log_dbg!("CGContextShowText({:?})", text);
+
+    let &CGContextHostObject {
+        font,
+        text_drawing_mode,
+        text_matrix,
+        ..
+    } = env.objc.borrow(context);
+    if font == nil {
+        log!("Warning: CGContextShowText() called with no font selected, ignoring");
+        return;
+    }
+
+    let use_stroke_color = match text_drawing_mode {
+        kCGTextFill | kCGTextFillStroke => Some(false),
+        kCGTextStroke => Some(true),
+        kCGTextInvisible => None,
+        _ => {
+            // TODO: clipping to text
+            log!(
+                "TODO: CGContextShowText() with drawing mode {}, not clipping",
+                text_drawing_mode
+            );
+            match text_drawing_mode {
+                kCGTextFillClip | kCGTextFillStrokeClip => Some(false),
+                kCGTextStrokeClip => Some(true),
+                _ => None,
+            }
+        }
+    };
+    // TODO: Strokes are drawn like fills, because the glyphs are rasterized
+    // without access to their outlines.
+    if let Some(use_stroke_color) = use_stroke_color {
+        ui_font::draw_cg_text(env, font, context, &text, text_matrix, use_stroke_color);
+    }
+
+    // The text position is advanced in text space.
+    let width = ui_font::cg_text_width(env, font, &text);
+    let host_obj = env.objc.borrow_mut::<CGContextHostObject>(context);
+    host_obj.text_matrix = host_obj.text_matrix.translate(width, 0.0);
+}
+
+pub fn CGContextShowTextAtPoint(
+    env: &mut Environment,
+    context: CGContextRef,
+    x: CGFloat,
+    y: CGFloat,
+    string: ConstPtr<u8>,
+    length: GuestUSize,
+) {
+    CGContextSetTextPosition(env, context, x, y);
+    CGContextShowText(env, context, string, length);
+}
+
 fn CGContextDrawImage(
     env: &mut Environment,
     context: CGContextRef,
@@ -144,8 +751,32 @@ fn CGContextDrawImage(
 pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(CGContextRetain(_)),
     export_c_func!(CGContextRelease(_)),
+    export_c_func!(CGContextSaveGState(_)),
+    export_c_func!(CGContextRestoreGState(_)),
+    export_c_func!(CGContextClipToRect(_, _)),
+    export_c_func!(CGContextGetClipBoundingBox(_)),
     export_c_func!(CGContextSetRGBFillColor(_, _, _, _, _)),
     export_c_func!(CGContextSetGrayFillColor(_, _, _)),
+    export_c_func!(CGContextSetFillColorWithColor(_, _)),
+    export_c_func!(CGContextSetRGBStrokeColor(_, _, _, _, _)),
+    export_c_func!(CGContextSetGrayStrokeColor(_, _, _)),
+    export_c_func!(CGContextSetStrokeColorWithColor(_, _)),
+    export_c_func!(CGContextSetLineWidth(_, _)),
+    export_c_func!(CGContextSetLineCap(_, _)),
+    export_c_func!(CGContextSetLineJoin(_, _)),
+    export_c_func!(CGContextBeginPath(_)),
+    export_c_func!(CGContextMoveToPoint(_, _, _)),
+    export_c_func!(CGContextAddLineToPoint(_, _, _)),
+    export_c_func!(CGContextAddCurveToPoint(_, _, _, _, _, _, _)),
+    export_c_func!(CGContextAddQuadCurveToPoint(_, _, _, _, _)),
+    export_c_func!(CGContextAddArc(_, _, _, _, _, _, _)),
+    export_c_func!(CGContextAddRect(_, _)),
+    export_c_func!(CGContextClosePath(_)),
+    export_c_func!(CGContextDrawPath(_, _)),
+    export_c_func!(CGContextFillPath(_)),
+    export_c_func!(CGContextEOFillPath(_)),
+    export_c_func!(CGContextStrokePath(_)),
+    export_c_func!(CGContextStrokeRect(_, _)),
     export_c_func!(CGContextFillRect(_, _)),
     export_c_func!(CGContextClearRect(_, _)),
     export_c_func!(CGContextConcatCTM(_, _)),
@@ -153,5 +784,13 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(CGContextRotateCTM(_, _)),
     export_c_func!(CGContextScaleCTM(_, _, _)),
     export_c_func!(CGContextTranslateCTM(_, _, _)),
+    export_c_func!(CGContextSelectFont(_, _, _, _)),
+    export_c_func!(CGContextSetTextDrawingMode(_, _)),
+    export_c_func!(CGContextSetTextMatrix(_, _)),
+    export_c_func!(CGContextGetTextMatrix(_)),
+    export_c_func!(CGContextSetTextPosition(_, _, _)),
+    export_c_func!(CGContextGetTextPosition(_)),
+    export_c_func!(CGContextShowText(_, _, _)),
+    export_c_func!(CGContextShowTextAtPoint(_, _, _, _, _)),
     export_c_func!(CGContextDrawImage(_, _, _)),
 ];
@@ -5,7 +5,10 @@
  */
 //! `CGImage.h`
 
-use super::cg_color_space::{kCGColorSpaceGenericRGB, CGColorSpaceCreateWithName, CGColorSpaceRef};
+use super::cg_color_space::{
+    kCGColorSpaceGenericGray, kCGColorSpaceGenericRGB, CGColorSpaceCreateWithName,
+    CGColorSpaceHostObject, CGColorSpaceRef,
+};
 use super::cg_data_provider::{self, CGDataProviderRef};
 use super::CGFloat;
 use crate::dyld::{export_c_func, FunctionExports};
@@ -29,17 +32,15 @@ pub const kCGImageAlphaOnly: CGImageAlphaInfo = 7;
 pub type CGImageByteOrderInfo = u32;
 pub const kCGImageByteOrderMask: CGImageByteOrderInfo = 0x7000;
 pub const kCGImageByteOrderDefault: CGImageByteOrderInfo = 0 << 12;
-#[allow(dead_code)]
 pub const kCGImageByteOrder16Little: CGImageByteOrderInfo = 1 << 12;
-#[allow(dead_code)]
 pub const kCGImageByteOrder32Little: CGImageByteOrderInfo = 2 << 12;
-#[allow(dead_code)]
 pub const kCGImageByteOrder16Big: CGImageByteOrderInfo = 3 << 12;
 pub const kCGImageByteOrder32Big: CGImageByteOrderInfo = 4 << 12;
 
 pub type CGBitmapInfo = u32;
 pub const kCGBitmapAlphaInfoMask: CGBitmapInfo = 0x1F; // huh, it's not 0x7?
 pub const kCGBitmapByteOrderMask: CGBitmapInfo = kCGImageByteOrderMask;
+pub const kCGBitmapFloatComponents: CGBitmapInfo = 1 << 8;
 // TODO: other stuff in this enum (for now, always assert the rest is 0)
 
 pub const CLASSES: ClassExports = objc_classes! {
@@ -93,7 +94,242 @@ pub fn borrow_image_mut(objc: &mut ObjC, image: CGImageRef) -> &mut Image {
     &mut objc.borrow_mut::<CGImageHostObject>(image).image
 }
 
-// TODO: More create methods.
+/// Convert pixel data in one of the layouts `CGImageCreate` and
+/// `CGBitmapContextCreate` accept to the layout of [Image] (8-bit sRGB RGBA with
+/// premultiplied alpha, rows in top-to-bottom order). The color space can be
+/// [None] for alpha-only images.
+pub(super) fn convert_to_rgba8(
+    data: &[u8],
+    width: GuestUSize,
+    height: GuestUSize,
+    bits_per_component: GuestUSize,
+    bits_per_pixel: GuestUSize,
+    bytes_per_row: GuestUSize,
+    color_space: Option<&str>,
+    bitmap_info: CGBitmapInfo,
+) -> Result<Vec<u8>, String> {
+    if bits_per_component != 8 || (bitmap_info & kCGBitmapFloatComponents) != 0 {
+        // TODO: support other bit depths (e.g. 5-bit RGB) and floats
+        return Err(format!(
+            "Unsupported components: {} bits, bitmap info {:#x}",
+            bits_per_component, bitmap_info
+        ));
+    }
+
+    let alpha_info = bitmap_info & kCGBitmapAlphaInfoMask;
+    let color_components: usize = match (color_space, alpha_info) {
+        (_, kCGImageAlphaOnly) => 0,
+        (Some(kCGColorSpaceGenericRGB), _) => 3,
+        (Some(kCGColorSpaceGenericGray), _) => 1,
+        (Some(color_space), _) => return Err(format!("Unsupported color space {}", color_space)),
+        (None, _) => return Err("No color space for an image with color components".into()),
+    };
+    // Offsets are for the components in big-endian order, i.e. the order the
+    // names of the alpha info constants describe.
+    let (components, color_offset, alpha_offset) = match alpha_info {
+        kCGImageAlphaNone => (color_components, 0, None),
+        kCGImageAlphaPremultipliedLast | kCGImageAlphaLast => {
+            (color_components + 1, 0, Some(color_components))
+        }
+        kCGImageAlphaPremultipliedFirst | kCGImageAlphaFirst => (color_components + 1, 1, Some(0)),
+        kCGImageAlphaNoneSkipLast => (color_components + 1, 0, None),
+        kCGImageAlphaNoneSkipFirst => (color_components + 1, 1, None),
+        kCGImageAlphaOnly => (1, 0, Some(0)),
+        _ => return Err(format!("Unknown alpha info {}", alpha_info)),
+    };
+    let premultiply = matches!(alpha_info, kCGImageAlphaLast | kCGImageAlphaFirst);
+    if bits_per_pixel as usize != components * 8 {
+        return Err(format!(
+            "{} bits per pixel doesn't match {} components",
+            bits_per_pixel, components
+        ));
+    }
+
+    // Little-endian byte orders mean that each pixel, read as a 16-bit or
+    // 32-bit integer, is byte-swapped in memory relative to big-endian order.
+    let swap_size = match bitmap_info & kCGBitmapByteOrderMask {
+        kCGImageByteOrderDefault | kCGImageByteOrder16Big | kCGImageByteOrder32Big => None,
+        kCGImageByteOrder16Little => Some(2),
+        kCGImageByteOrder32Little => Some(4),
+        byte_order => return Err(format!("Unknown byte order {:#x}", byte_order)),
+    };
+    if swap_size.is_some_and(|swap_size| swap_size != components) {
+        return Err(format!(
+            "Byte order {:#x} doesn't match {} bits per pixel",
+            bitmap_info & kCGBitmapByteOrderMask,
+            bits_per_pixel
+        ));
+    }
+
+    let (width, height) = (width as usize, height as usize);
+    let bytes_per_row = bytes_per_row as usize;
+    if width != 0 && height != 0 {
+        let min_size = bytes_per_row * (height - 1) + width * components;
+        if bytes_per_row < width * components || data.len() < min_size {
+            return Err(format!(
+                "Not enough data for {}x{} image with {} bytes per row: {} bytes",
+                width,
+                height,
+                bytes_per_row,
+                data.len()
+            ));
+        }
+    }
+
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let mut pixel = [0u8; 4];
+            let start = y * bytes_per_row + x * components;
+            pixel[..components].copy_from_slice(&data[start..][..components]);
+            if swap_size.is_some() {
+                pixel[..components].reverse();
+            }
+
+            let (r, g, b) = match color_components {
+                3 => (
+                    pixel[color_offset],
+                    pixel[color_offset + 1],
+                    pixel[color_offset + 2],
+                ),
+                1 => (
+                    pixel[color_offset],
+                    pixel[color_offset],
+                    pixel[color_offset],
+                ),
+                _ => (0, 0, 0),
+            };
+            let a = alpha_offset.map_or(255, |alpha_offset| pixel[alpha_offset]);
+            let (r, g, b) = if premultiply {
+                let multiply = |c: u8| ((c as u16 * a as u16 + 127) / 255) as u8;
+                (multiply(r), multiply(g), multiply(b))
+            } else {
+                (r, g, b)
+            };
+            pixels.extend_from_slice(&[r, g, b, a]);
+        }
+    }
+    Ok(pixels)
+}
+
+#[cfg(test)]
+#[test]
+fn test_convert_to_rgba8() {
+    let convert = |data: &[u8], bits_per_pixel, color_space, bitmap_info| {
+        let width = (data.len() * 8) as GuestUSize / bits_per_pixel;
+        convert_to_rgba8(
+            data,
+            width,
+            1,
+            8,
+            bits_per_pixel,
+            data.len() as GuestUSize,
+            color_space,
+            bitmap_info,
+        )
+    };
+    let rgb = Some(kCGColorSpaceGenericRGB);
+    let gray = Some(kCGColorSpaceGenericGray);
+
+    // Premultiplied RGBA is the native layout.
+    let rgba = [10, 20, 30, 128, 1, 2, 3, 255];
+    assert_eq!(
+        convert(&rgba, 32, rgb, kCGImageAlphaPremultipliedLast),
+        Ok(rgba.to_vec())
+    );
+    // BGRA as used on little-endian hardware.
+    assert_eq!(
+        convert(
+            &[30, 20, 10, 128],
+            32,
+            rgb,
+            kCGImageAlphaPremultipliedFirst | kCGImageByteOrder32Little
+        ),
+        Ok(vec![10, 20, 30, 128])
+    );
+    // Non-premultiplied alpha is premultiplied.
+    assert_eq!(
+        convert(&[128, 200, 100, 50], 32, rgb, kCGImageAlphaFirst),
+        Ok(vec![100, 50, 25, 128])
+    );
+    // Skipped components are ignored and the result is opaque.
+    assert_eq!(
+        convert(&[99, 10, 20, 30], 32, rgb, kCGImageAlphaNoneSkipFirst),
+        Ok(vec![10, 20, 30, 255])
+    );
+    assert_eq!(
+        convert(&[10, 20, 30], 24, rgb, kCGImageAlphaNone),
+        Ok(vec![10, 20, 30, 255])
+    );
+    assert_eq!(
+        convert(&[200, 100], 16, gray, kCGImageAlphaPremultipliedLast),
+        Ok(vec![200, 200, 200, 100])
+    );
+    assert_eq!(
+        convert(&[100], 8, gray, kCGImageAlphaOnly),
+        Ok(vec![0, 0, 0, 100])
+    );
+    // Masks don't need a color space.
+    assert_eq!(
+        convert(&[100], 8, None, kCGImageAlphaOnly),
+        Ok(vec![0, 0, 0, 100])
+    );
+    // Layouts that don't make sense are rejected.
+    assert!(convert(&[10, 20, 30], 24, None, kCGImageAlphaNone).is_err());
+    assert!(convert(&[10, 20, 30], 24, rgb, kCGImageAlphaPremultipliedLast).is_err());
+    assert!(convert(
+        &[10, 20, 30],
+        24,
+        rgb,
+        kCGImageAlphaNone | kCGImageByteOrder32Little
+    )
+    .is_err());
+}
+
+fn CGImageCreate(
+    env: &mut Environment,
+    width: GuestUSize,
+    height: GuestUSize,
+    bits_per_component: GuestUSize,
+    bits_per_pixel: GuestUSize,
+    bytes_per_row: GuestUSize,
+    color_space: CGColorSpaceRef,
+    bitmap_info: CGBitmapInfo,
+    provider: CGDataProviderRef,
+    decode: ConstPtr<CGFloat>,
+    _should_interpolate: bool, // TODO
+    _intent: i32,              // TODO (should be CGColorRenderingIntent)
+) -> CGImageRef {
+    assert!(decode.is_null()); // TODO
+
+    // Masks (kCGImageAlphaOnly) don't need a color space.
+    let color_space = if color_space.is_null() {
+        None
+    } else {
+        Some(env.objc.borrow::<CGColorSpaceHostObject>(color_space).name)
+    };
+    let bytes = cg_data_provider::borrow_bytes(env, provider);
+    // The data is copied, so unlike on a real device, the data provider isn't
+    // retained.
+    let pixels = match convert_to_rgba8(
+        bytes,
+        width,
+        height,
+        bits_per_component,
+        bits_per_pixel,
+        bytes_per_row,
+        color_space,
+        bitmap_info,
+    ) {
+        Ok(pixels) => pixels,
+        Err(reason) => {
+            log!("Warning: CGImageCreate() failed: {}", reason);
+            return nil;
+        }
+    };
+
+    from_image(env, Image::from_pixel_vec(pixels, (width, height)))
+}
 
 fn CGImageCreateWithPNGDataProvider(
     env: &mut Environment,
@@ -113,6 +349,17 @@ fn CGImageCreateWithPNGDataProvider(
     from_image(env, image)
 }
 
+fn CGImageCreateWithJPEGDataProvider(
+    env: &mut Environment,
+    source: CGDataProviderRef,
+    decode: ConstPtr<CGFloat>,
+    should_interpolate: bool,
+    intent: i32,
+) -> CGImageRef {
+    // Image::from_bytes() detects the format itself.
+    CGImageCreateWithPNGDataProvider(env, source, decode, should_interpolate, intent)
+}
+
 fn CGImageGetAlphaInfo(_env: &mut Environment, _image: CGImageRef) -> CGImageAlphaInfo {
     // our Image type always returns premultiplied RGBA
     // (the premultiplied part must match what the real UIImage does, but
@@ -167,7 +414,9 @@ fn CGImageGetBitsPerComponent(_: &mut Environment, _: CGImageRef) -> GuestUSize
 pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(CGImageRelease(_)),
     export_c_func!(CGImageRetain(_)),
+    export_c_func!(CGImageCreate(_, _, _, _, _, _, _, _, _, _, _)),
     export_c_func!(CGImageCreateWithPNGDataProvider(_, _, _, _)),
+    export_c_func!(CGImageCreateWithJPEGDataProvider(_, _, _, _)),
     export_c_func!(CGImageGetAlphaInfo(_)),
     export_c_func!(CGImageGetColorSpace(_)),
     export_c_func!(CGImageGetWidth(_)),
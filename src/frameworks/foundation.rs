@@ -21,37 +21,51 @@ pub mod ns_date;
 pub mod ns_date_formatter;
 pub mod ns_dictionary;
 pub mod ns_enumerator;
+pub mod ns_error;
 pub mod ns_exception;
 pub mod ns_file_manager;
+pub mod ns_index_path;
+pub mod ns_keyed_archiver;
 pub mod ns_keyed_unarchiver;
 pub mod ns_locale;
 pub mod ns_log;
 pub mod ns_notification;
 pub mod ns_notification_center;
 pub mod ns_null;
+pub mod ns_number_formatter;
 pub mod ns_objc_runtime;
 pub mod ns_object;
+pub mod ns_operation;
+pub mod ns_predicate;
 pub mod ns_process_info;
 pub mod ns_property_list_serialization;
+pub mod ns_regular_expression;
 pub mod ns_run_loop;
+pub mod ns_scanner;
 pub mod ns_set;
 pub mod ns_string;
 pub mod ns_thread;
 pub mod ns_timer;
 pub mod ns_url;
+pub mod ns_url_connection;
+pub mod ns_url_request;
+pub mod ns_url_response;
 pub mod ns_user_defaults;
 pub mod ns_value;
+pub mod ns_xml_parser;
 
 #[derive(Default)]
 pub struct State {
     ns_autorelease_pool: ns_autorelease_pool::State,
     ns_bundle: ns_bundle::State,
+    ns_character_set: ns_character_set::State,
     ns_file_manager: ns_file_manager::State,
     ns_locale: ns_locale::State,
     ns_notification_center: ns_notification_center::State,
     ns_null: ns_null::State,
     ns_run_loop: ns_run_loop::State,
     ns_string: ns_string::State,
+    ns_thread: ns_thread::State,
     ns_user_defaults: ns_user_defaults::State,
 }
 
@@ -81,6 +95,8 @@ impl crate::abi::GuestArg for NSRange {
     }
 }
 
+pub const NSNotFound: NSInteger = NSInteger::MAX;
+
 pub type NSComparisonResult = NSInteger;
 pub const NSOrderedAscending: NSComparisonResult = -1;
 pub const NSOrderedSame: NSComparisonResult = 0;
@@ -5,12 +5,17 @@
  */
 //! The `NSArray` class cluster, including `NSMutableArray`.
 
-use super::ns_property_list_serialization::deserialize_plist_from_file;
-use super::{ns_keyed_unarchiver, ns_string, ns_url, NSUInteger};
+use super::ns_property_list_serialization::{deserialize_plist_from_file, serialize_plist_to_file};
+use super::{
+    ns_keyed_unarchiver, ns_string, ns_url, NSComparisonResult, NSNotFound, NSOrderedSame,
+    NSUInteger,
+};
+use crate::abi::{CallFromHost, GuestFunction, GuestRet};
 use crate::fs::GuestPath;
+use crate::mem::{MutPtr, MutVoidPtr};
 use crate::objc::{
-    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, ClassExports, HostObject,
-    NSZonePtr,
+    autorelease, get_block_invoke, id, msg, msg_class, msg_send, nil, objc_classes, release,
+    retain, ClassExports, HostObject, NSZonePtr, SEL,
 };
 use crate::Environment;
 
@@ -25,6 +30,138 @@ struct ArrayHostObject {
 }
 impl HostObject for ArrayHostObject {}
 
+/// The different ways the guest can specify how to order two objects.
+#[derive(Copy, Clone)]
+pub(super) enum Comparator {
+    /// `- (NSComparisonResult)compare:(id)other`, sent to the first object.
+    Selector(SEL),
+    /// `NSInteger (*)(id, id, void *)` and its context argument.
+    Function(GuestFunction, MutVoidPtr),
+    /// `NSComparator`, i.e. `NSComparisonResult (^)(id, id)`.
+    Block(id),
+}
+impl Comparator {
+    fn compare(self, env: &mut Environment, a: id, b: id) -> NSComparisonResult {
+        match self {
+            Comparator::Selector(sel) => msg_send(env, (a, sel, b)),
+            Comparator::Function(function, context) => {
+                function.call_from_host(env, (a, b, context))
+            }
+            Comparator::Block(block) => {
+                let invoke = get_block_invoke(&env.mem, block);
+                invoke.call_from_host(env, (block, a, b))
+            }
+        }
+    }
+}
+
+/// Sort objects using a guest comparison.
+fn sort_objects(env: &mut Environment, objects: Vec<id>, comparator: Comparator) -> Vec<id> {
+    sort_by_object(env, objects, comparator, |object| object)
+}
+
+/// Sort items by an object associated with each one, using a guest comparison.
+///
+/// This is a merge sort. Rust's `sort_by` is not used because it may panic if
+/// the comparison isn't a total order, which guest code can't be trusted to
+/// provide. Note that the comparison runs guest code, which might modify the
+/// array being sorted, so the objects are sorted in a copy.
+pub(super) fn sort_by_object<T: Copy>(
+    env: &mut Environment,
+    items: Vec<T>,
+    comparator: Comparator,
+    get_object: impl Fn(T) -> id,
+) -> Vec<T> {
+    let len = items.len();
+    let mut src = items;
+    let mut dst = Vec::with_capacity(len);
+    let mut width = 1;
+    while width < len {
+        dst.clear();
+        for start in (0..len).step_by(width * 2) {
+            let mid = (start + width).min(len);
+            let end = (start + width * 2).min(len);
+            let (mut i, mut j) = (start, mid);
+            while i < mid && j < end {
+                // Anything greater than NSOrderedSame is treated as
+                // NSOrderedDescending, since function comparators often just
+                // return a difference.
+                let (a, b) = (get_object(src[i]), get_object(src[j]));
+                if comparator.compare(env, a, b) > NSOrderedSame {
+                    dst.push(src[j]);
+                    j += 1;
+                } else {
+                    dst.push(src[i]);
+                    i += 1;
+                }
+            }
+            dst.extend_from_slice(&src[i..mid]);
+            dst.extend_from_slice(&src[j..end]);
+        }
+        std::mem::swap(&mut src, &mut dst);
+        width *= 2;
+    }
+    src
+}
+
+/// Get the objects of any kind of array, without retaining them.
+pub(super) fn objects_of_array(env: &mut Environment, array: id) -> Vec<id> {
+    let count: NSUInteger = msg![env; array count];
+    (0..count)
+        .map(|i| msg![env; array objectAtIndex:i])
+        .collect()
+}
+
+/// Call a block taking `(id obj, NSUInteger idx, BOOL *stop)` for each object
+/// in an array, until `handle_result` returns [true] for a result of the block
+/// or the block sets the stop flag. Returns the index for which
+/// `handle_result` returned [true], if any. Setting the stop flag doesn't count
+/// as a match by itself.
+fn enumerate_objects_with_block<R: GuestRet>(
+    env: &mut Environment,
+    array: id,
+    block: id,
+    mut handle_result: impl FnMut(R) -> bool,
+) -> Option<NSUInteger> {
+    // Enumeration is over a snapshot, so mutation by the block can't break it.
+    let objects = objects_of_array(env, array);
+    let invoke = get_block_invoke(&env.mem, block);
+    let stop: MutPtr<bool> = env.mem.alloc_and_write(false);
+    let mut found = None;
+    for (index, object) in objects.into_iter().enumerate() {
+        let index: NSUInteger = index.try_into().unwrap();
+        let result: R = invoke.call_from_host(env, (block, object, index, stop));
+        let passed = handle_result(result);
+        let stopped = env.mem.read(stop);
+        if passed {
+            found = Some(index);
+        }
+        if passed || stopped {
+            break;
+        }
+    }
+    env.mem.free(stop.cast());
+    found
+}
+
+/// Shared implementation of the `sortedArrayUsing...` methods.
+fn sorted_array(env: &mut Environment, array: id, comparator: Comparator) -> id {
+    let objects = objects_of_array(env, array);
+    let objects = sort_objects(env, objects, comparator);
+    for &object in &objects {
+        retain(env, object);
+    }
+    let new = from_vec(env, objects);
+    autorelease(env, new)
+}
+
+/// Shared implementation of the `sortUsing...` methods.
+fn sort_array_in_place(env: &mut Environment, array: id, comparator: Comparator) {
+    let objects = env.objc.borrow::<ArrayHostObject>(array).array.clone();
+    let objects = sort_objects(env, objects, comparator);
+    env.objc.borrow_mut::<ArrayHostObject>(array).array = objects;
+}
+
 pub const CLASSES: ClassExports = objc_classes! {
 
 (env, this, _cmd);
@@ -90,6 +227,11 @@ pub const CLASSES: ClassExports = objc_classes! {
     deserialize_plist_from_file(env, &path, /* array_expected: */ true)
 }
 
+- (bool)writeToFile:(id)path // NSString*
+         atomically:(bool)atomically {
+    serialize_plist_to_file(env, this, path, atomically)
+}
+
 // NSCopying implementation
 - (id)copyWithZone:(NSZonePtr)_zone {
     // TODO: override this once we have NSMutableArray!
@@ -104,6 +246,49 @@ pub const CLASSES: ClassExports = objc_classes! {
     msg![env; this objectAtIndex: (size - 1)]
 }
 
+- (id)filteredArrayUsingPredicate:(id)predicate { // NSPredicate*
+    let mut objects = Vec::new();
+    for object in objects_of_array(env, this) {
+        if msg![env; predicate evaluateWithObject:object] {
+            objects.push(retain(env, object));
+        }
+    }
+    let new = from_vec(env, objects);
+    autorelease(env, new)
+}
+
+- (())enumerateObjectsUsingBlock:(id)block { // void (^)(id, NSUInteger, BOOL*)
+    enumerate_objects_with_block(env, this, block, |()| false);
+}
+
+- (NSUInteger)indexOfObjectPassingTest:(id)predicate { // BOOL (^)(id, NSUInteger, BOOL*)
+    enumerate_objects_with_block(env, this, predicate, |passed: bool| passed)
+        .unwrap_or(NSNotFound as NSUInteger)
+}
+
+- (())makeObjectsPerformSelector:(SEL)selector {
+    for object in objects_of_array(env, this) {
+        let _: () = msg_send(env, (object, selector));
+    }
+}
+- (())makeObjectsPerformSelector:(SEL)selector
+                      withObject:(id)argument {
+    for object in objects_of_array(env, this) {
+        let _: () = msg_send(env, (object, selector, argument));
+    }
+}
+
+- (id)sortedArrayUsingSelector:(SEL)comparator {
+    sorted_array(env, this, Comparator::Selector(comparator))
+}
+- (id)sortedArrayUsingFunction:(GuestFunction)comparator // NSInteger(*)(id, id, void*)
+                       context:(MutVoidPtr)context {
+    sorted_array(env, this, Comparator::Function(comparator, context))
+}
+- (id)sortedArrayUsingComparator:(id)comparator { // NSComparator
+    sorted_array(env, this, Comparator::Block(comparator))
+}
+
 @end
 
 // NSMutableArray is an abstract class. A subclass must provide everything
@@ -271,6 +456,17 @@ pub const CLASSES: ClassExports = objc_classes! {
     release(env, object)
 }
 
+- (())sortUsingSelector:(SEL)comparator {
+    sort_array_in_place(env, this, Comparator::Selector(comparator))
+}
+- (())sortUsingFunction:(GuestFunction)comparator // NSInteger(*)(id, id, void*)
+                context:(MutVoidPtr)context {
+    sort_array_in_place(env, this, Comparator::Function(comparator, context))
+}
+- (())sortUsingComparator:(id)comparator { // NSComparator
+    sort_array_in_place(env, this, Comparator::Block(comparator))
+}
+
 @end
 
 // Special variant for use by CFArray with NULL callbacks: objects aren't
@@ -10,11 +10,11 @@ use crate::bundle::Bundle;
 use crate::frameworks::core_foundation::cf_bundle::{
     CFBundleCopyBundleLocalizations, CFBundleCopyPreferredLocalizationsFromArray,
 };
+use crate::fs::GuestPath;
 use crate::objc::{
     autorelease, id, msg, msg_class, nil, objc_classes, release, ClassExports, HostObject,
 };
 use crate::Environment;
-use std::collections::HashSet;
 
 // Should be ISO 639-1 (or ISO 639-2) compliant
 // TODO: complete this list or use some crate for mapping
@@ -128,41 +128,43 @@ pub const CLASSES: ClassExports = objc_classes! {
 - (id)pathForResource:(id)name // NSString*
                ofType:(id)extension // NSString*
           inDirectory:(id)directory { // NSString*
-    assert!(name != nil); // TODO
-
     // TODO: cache result of lookups
 
-    let path = path_for_resource_helper(env, this, name, nil, directory, extension);
+    // Non-localized resources take precedence.
+    let path = path_for_resource_helper(env, this, name, None, directory, extension);
     if path != nil {
         return path
     }
 
-    // Try preferred languages in order of preference
+    // Try preferred languages in order of preference, then the development
+    // language, and as a last resort, English.
+    let mut lang_codes = Vec::new();
     let langs: id = msg_class![env; NSLocale preferredLanguages];
     let lang_count: NSUInteger = msg![env; langs count];
-    let mut unknown_codes = HashSet::new();
     for i in 0..lang_count {
         let lang_code: id = msg![env; langs objectAtIndex:i];
-        let lang_code = ns_string::to_rust_string(env, lang_code); // TODO: avoid copy
-        if let Some(&(_, lproj)) = LANG_ID_TO_LANG_PROJ.iter().find(|&&(code, _)| code == lang_code) {
-            let lproj: id = ns_string::get_static_str(env, lproj);
-            let localized_path = path_for_resource_helper(env, this, name, lproj, directory, extension);
-            if localized_path != nil {
-                return localized_path;
-            }
-        } else {
-            unknown_codes.insert(lang_code);
+        lang_codes.push(ns_string::to_rust_string(env, lang_code).into_owned());
+    }
+    let info_dictionary: id = msg![env; this infoDictionary];
+    if info_dictionary != nil {
+        let key = ns_string::get_static_str(env, "CFBundleDevelopmentRegion");
+        let region: id = msg![env; info_dictionary objectForKey:key];
+        if region != nil {
+            lang_codes.push(ns_string::to_rust_string(env, region).into_owned());
         }
     }
+    lang_codes.push("en".to_string());
 
-    // As a last resort, fallback to English
-    // TODO: fallback to a development language (CFBundleDevelopmentRegion from
-    // Info.plist)
-    if !unknown_codes.is_empty() {
-        log!("TODO: language codes {:?} aren't mapped to a language name, falling back to English", unknown_codes);
+    for lang_code in lang_codes {
+        for lproj in lproj_names_for_language(&lang_code) {
+            let lproj = Some(lproj.as_str());
+            let path = path_for_resource_helper(env, this, name, lproj, directory, extension);
+            if path != nil {
+                return path;
+            }
+        }
     }
-    let lproj: id = ns_string::get_static_str(env, "English.lproj");
-    path_for_resource_helper(env, this, name, lproj, directory, extension)
+    nil
 }
 - (id)pathForResource:(id)name // NSString*
                ofType:(id)extension { // NSString*
@@ -174,6 +176,9 @@ pub const CLASSES: ClassExports = objc_classes! {
    let path_string: id = msg![env; this pathForResource:name
                                                  ofType:extension
                                             inDirectory:subpath];
+   if path_string == nil {
+       return nil;
+   }
    let path_url: id = msg_class![env; NSURL alloc];
    let path_url: id = msg![env; path_url initFileURLWithPath:path_string];
    autorelease(env, path_url)
@@ -201,6 +206,15 @@ pub const CLASSES: ClassExports = objc_classes! {
     dict
 }
 
+- (id)objectForInfoDictionaryKey:(id)key { // NSString*
+    // TODO: Use localized values from InfoPlist.strings if available.
+    let info_dictionary: id = msg![env; this infoDictionary];
+    if info_dictionary == nil {
+        return nil;
+    }
+    msg![env; info_dictionary objectForKey:key]
+}
+
 - (id)localizations {
     let localizations = CFBundleCopyBundleLocalizations(env, this);
     autorelease(env, localizations)
@@ -212,21 +226,71 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 };
 
+/// Get the names of the `.lproj` directories that might contain resources for
+/// a language, in order of preference. Both the modern (e.g. `en.lproj`) and
+/// legacy (e.g. `English.lproj`) naming schemes are supported.
+fn lproj_names_for_language(lang_code: &str) -> Vec<String> {
+    let mut names = vec![format!("{}.lproj", lang_code)];
+    // A code with a region (e.g. `en_GB` or `en-GB`) falls back to the plain
+    // language.
+    let base_code = lang_code.split(['_', '-']).next().unwrap();
+    if base_code != lang_code {
+        names.push(format!("{}.lproj", base_code));
+    }
+    if let Some(&(_, lproj)) = LANG_ID_TO_LANG_PROJ
+        .iter()
+        .find(|&&(code, _)| code == base_code)
+    {
+        names.push(lproj.to_string());
+    }
+    names
+}
+
 fn path_for_resource_helper(
     env: &mut Environment,
     bundle: id,
     name: id,
-    lproj: id,
+    lproj: Option<&str>,
     directory: id,
     extension: id,
 ) -> id {
     let mut path: id = msg![env; bundle resourcePath];
-    if lproj != nil {
+    if let Some(lproj) = lproj {
+        let lproj = ns_string::from_rust_string(env, lproj.to_string());
         path = msg![env; path stringByAppendingPathComponent:lproj];
+        release(env, lproj);
     }
     if directory != nil {
         path = msg![env; path stringByAppendingPathComponent:directory];
     }
+
+    // With no name, the first file with the right extension is returned.
+    if name == nil {
+        let path_str = ns_string::to_rust_string(env, path);
+        let extension = if extension == nil {
+            String::new()
+        } else {
+            ns_string::to_rust_string(env, extension).into_owned()
+        };
+        let Ok(names) = env.fs.enumerate(GuestPath::new(&path_str)) else {
+            return nil;
+        };
+        let mut names: Vec<&str> = names
+            .filter(|name| {
+                name.rsplit_once('.')
+                    .map_or(extension.is_empty(), |(_, ext)| ext == extension)
+            })
+            .collect();
+        names.sort();
+        let Some(&first) = names.first() else {
+            return nil;
+        };
+        let first = ns_string::from_rust_string(env, first.to_string());
+        let res: id = msg![env; path stringByAppendingPathComponent:first];
+        release(env, first);
+        return res;
+    }
+
     path = msg![env; path stringByAppendingPathComponent:name];
     if extension != nil {
         path = msg![env; path stringByAppendingPathExtension:extension];
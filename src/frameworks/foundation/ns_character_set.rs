@@ -7,16 +7,204 @@
 
 use super::{ns_string, unichar};
 use crate::objc::{
-    autorelease, id, msg, msg_class, objc_classes, retain, ClassExports, HostObject, NSZonePtr,
+    autorelease, id, msg, msg_class, objc_classes, retain, ClassExports, HostObject, NSZonePtr, SEL,
 };
-use std::collections::HashSet;
+use crate::Environment;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+#[derive(Default)]
+pub struct State {
+    /// The shared instances returned by methods like `letterCharacterSet`,
+    /// keyed by the method's selector. Some of these are slow to build.
+    standard_sets: HashMap<SEL, id>,
+}
+
+const MAX_SCALAR: u32 = char::MAX as u32;
 
 /// Belongs to _touchHLE_NSCharacterSet
 struct CharacterSetHostObject {
-    set: HashSet<unichar>,
+    /// Sorted list of non-overlapping, non-adjacent ranges of Unicode scalar
+    /// values. Lone surrogates are stored as their code unit value.
+    ranges: Vec<RangeInclusive<u32>>,
 }
 impl HostObject for CharacterSetHostObject {}
 
+/// Sort and merge ranges so they are suitable for [CharacterSetHostObject].
+fn normalize_ranges(mut ranges: Vec<RangeInclusive<u32>>) -> Vec<RangeInclusive<u32>> {
+    ranges.sort_by_key(|range| *range.start());
+    let mut merged: Vec<RangeInclusive<u32>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        if range.is_empty() {
+            continue;
+        }
+        match merged.last_mut() {
+            Some(last) if *range.start() <= last.end().saturating_add(1) => {
+                if range.end() > last.end() {
+                    *last = *last.start()..=*range.end();
+                }
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+fn ranges_contain(ranges: &[RangeInclusive<u32>], c: u32) -> bool {
+    ranges
+        .binary_search_by(|range| {
+            if *range.end() < c {
+                std::cmp::Ordering::Less
+            } else if *range.start() > c {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// Complement of a normalized range list, over all Unicode scalar values.
+fn invert_ranges(ranges: &[RangeInclusive<u32>]) -> Vec<RangeInclusive<u32>> {
+    let mut inverted = Vec::with_capacity(ranges.len() + 1);
+    let mut next_start = 0;
+    for range in ranges {
+        if *range.start() > next_start {
+            inverted.push(next_start..=(range.start() - 1));
+        }
+        next_start = range.end() + 1;
+    }
+    if next_start <= MAX_SCALAR {
+        inverted.push(next_start..=MAX_SCALAR);
+    }
+    inverted
+}
+
+/// Build a range list from a predicate over Unicode scalar values.
+fn ranges_from_predicate(pred: impl Fn(char) -> bool) -> Vec<RangeInclusive<u32>> {
+    let mut ranges = Vec::new();
+    let mut current: Option<RangeInclusive<u32>> = None;
+    for c in (0..=MAX_SCALAR).filter_map(char::from_u32) {
+        if !pred(c) {
+            continue;
+        }
+        let c = c as u32;
+        current = match current {
+            Some(range) if *range.end() + 1 == c => Some(*range.start()..=c),
+            Some(range) => {
+                ranges.push(range);
+                Some(c..=c)
+            }
+            None => Some(c..=c),
+        };
+    }
+    ranges.extend(current);
+    ranges
+}
+
+/// Unicode General Category Zs, plus U+0009.
+const WHITESPACE: &[RangeInclusive<u32>] = &[
+    0x0009..=0x0009,
+    0x0020..=0x0020,
+    0x00A0..=0x00A0,
+    0x1680..=0x1680,
+    0x2000..=0x200A,
+    0x202F..=0x202F,
+    0x205F..=0x205F,
+    0x3000..=0x3000,
+];
+
+/// U+000A ~ U+000D, U+0085, U+2028 and U+2029.
+const NEWLINES: &[RangeInclusive<u32>] = &[0x000A..=0x000D, 0x0085..=0x0085, 0x2028..=0x2029];
+
+/// The zero digit of each block of Unicode General Category Nd in the BMP.
+/// Each one is followed by the other nine digits.
+/// TODO: digits outside the BMP
+const DECIMAL_DIGIT_ZEROES: &[u32] = &[
+    0x0030, 0x0660, 0x06F0, 0x07C0, 0x0966, 0x09E6, 0x0A66, 0x0AE6, 0x0B66, 0x0BE6, 0x0C66, 0x0CE6,
+    0x0D66, 0x0DE6, 0x0E50, 0x0ED0, 0x0F20, 0x1040, 0x1090, 0x17E0, 0x1810, 0x1946, 0x19D0, 0x1A80,
+    0x1A90, 0x1B50, 0x1BB0, 0x1C40, 0x1C50, 0xA620, 0xA8D0, 0xA900, 0xA9D0, 0xA9F0, 0xAA50, 0xABF0,
+    0xFF10,
+];
+
+/// Unicode General Category P* for the scripts apps are most likely to use.
+/// TODO: punctuation in other scripts
+const PUNCTUATION: &[RangeInclusive<u32>] = &[
+    // ASCII. Note that some characters, e.g. `$` and `+`, are symbols instead.
+    0x0021..=0x0023,
+    0x0025..=0x002A,
+    0x002C..=0x002F,
+    0x003A..=0x003B,
+    0x003F..=0x0040,
+    0x005B..=0x005D,
+    0x005F..=0x005F,
+    0x007B..=0x007B,
+    0x007D..=0x007D,
+    // Latin-1 Supplement
+    0x00A1..=0x00A1,
+    0x00A7..=0x00A7,
+    0x00AB..=0x00AB,
+    0x00B6..=0x00B7,
+    0x00BB..=0x00BB,
+    0x00BF..=0x00BF,
+    // General Punctuation
+    0x2010..=0x2027,
+    0x2030..=0x2043,
+    0x2045..=0x2051,
+    0x2053..=0x205E,
+    // CJK Symbols and Punctuation
+    0x3001..=0x3003,
+    0x3008..=0x3011,
+    0x3014..=0x301F,
+    // Halfwidth and Fullwidth Forms
+    0xFF01..=0xFF03,
+    0xFF05..=0xFF0A,
+    0xFF0C..=0xFF0F,
+    0xFF1A..=0xFF1B,
+    0xFF1F..=0xFF20,
+    0xFF3B..=0xFF3D,
+    0xFF3F..=0xFF3F,
+    0xFF5B..=0xFF5B,
+    0xFF5D..=0xFF5D,
+    0xFF5F..=0xFF65,
+];
+
+/// Create a new autoreleased character set with the given membership.
+fn new_with_ranges(env: &mut Environment, class: id, ranges: Vec<RangeInclusive<u32>>) -> id {
+    let new: id = msg![env; class alloc];
+    env.objc.borrow_mut::<CharacterSetHostObject>(new).ranges = normalize_ranges(ranges);
+    autorelease(env, new)
+}
+
+/// Get the shared instance of one of the predefined character sets, building
+/// it the first time.
+fn get_standard_set(
+    env: &mut Environment,
+    class: id,
+    sel: SEL,
+    build_ranges: impl FnOnce() -> Vec<RangeInclusive<u32>>,
+) -> id {
+    if let Some(&existing) = env
+        .framework_state
+        .foundation
+        .ns_character_set
+        .standard_sets
+        .get(&sel)
+    {
+        existing
+    } else {
+        let new: id = msg![env; class alloc];
+        env.objc.borrow_mut::<CharacterSetHostObject>(new).ranges =
+            normalize_ranges(build_ranges());
+        env.framework_state
+            .foundation
+            .ns_character_set
+            .standard_sets
+            .insert(sel, new);
+        new
+    }
+}
+
 pub const CLASSES: ClassExports = objc_classes! {
 
 (env, this, _cmd);
@@ -37,15 +225,49 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 // This doesn't have a corresponding init method for some reason.
 + (id)characterSetWithCharactersInString:(id)string { // NSString*
-    let mut set = HashSet::new();
-    ns_string::for_each_code_unit(env, string, |_idx, c| { set.insert(c); });
+    let mut code_units = Vec::new();
+    ns_string::for_each_code_unit(env, string, |_idx, c| code_units.push(c));
+    let ranges = char::decode_utf16(code_units)
+        .map(|c| {
+            let c = c.map_or_else(|err| err.unpaired_surrogate().into(), u32::from);
+            c..=c
+        })
+        .collect();
+    new_with_ranges(env, this, ranges)
+}
+
++ (id)whitespaceCharacterSet {
+    get_standard_set(env, this, _cmd, || WHITESPACE.to_vec())
+}
+
++ (id)whitespaceAndNewlineCharacterSet {
+    get_standard_set(env, this, _cmd, || [WHITESPACE, NEWLINES].concat())
+}
 
-    let new: id = msg![env; this alloc];
-    env.objc.borrow_mut::<CharacterSetHostObject>(new).set = set;
++ (id)newlineCharacterSet {
+    get_standard_set(env, this, _cmd, || NEWLINES.to_vec())
+}
 
-    autorelease(env, new);
++ (id)decimalDigitCharacterSet {
+    get_standard_set(env, this, _cmd, || {
+        DECIMAL_DIGIT_ZEROES.iter().map(|&zero| zero..=(zero + 9)).collect()
+    })
+}
 
-    new
++ (id)letterCharacterSet {
+    // TODO: This should be Unicode General Categories L* and M*, but the
+    // Alphabetic property is a close approximation.
+    get_standard_set(env, this, _cmd, || ranges_from_predicate(char::is_alphabetic))
+}
+
++ (id)alphanumericCharacterSet {
+    // TODO: This should be Unicode General Categories L*, M* and N*, but the
+    // Alphabetic and Numeric properties are a close approximation.
+    get_standard_set(env, this, _cmd, || ranges_from_predicate(char::is_alphanumeric))
+}
+
++ (id)punctuationCharacterSet {
+    get_standard_set(env, this, _cmd, || PUNCTUATION.to_vec())
 }
 
 // NSCopying implementation
@@ -62,7 +284,7 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 + (id)allocWithZone:(NSZonePtr)_zone {
     let host_object = Box::new(CharacterSetHostObject {
-        set: HashSet::new(),
+        ranges: Vec::new(),
     });
     env.objc.alloc_object(this, host_object, &mut env.mem)
 }
@@ -70,9 +292,41 @@ pub const CLASSES: ClassExports = objc_classes! {
 // TODO: initWithCoder:
 
 - (bool)characterIsMember:(unichar)code_unit {
-    env.objc.borrow::<CharacterSetHostObject>(this).set.contains(&code_unit)
+    let ranges = &env.objc.borrow::<CharacterSetHostObject>(this).ranges;
+    ranges_contain(ranges, code_unit.into())
+}
+
+- (bool)longCharacterIsMember:(u32)scalar {
+    let ranges = &env.objc.borrow::<CharacterSetHostObject>(this).ranges;
+    ranges_contain(ranges, scalar)
+}
+
+- (id)invertedSet {
+    let ranges = invert_ranges(&env.objc.borrow::<CharacterSetHostObject>(this).ranges);
+    let class = env.objc.get_known_class("_touchHLE_NSCharacterSet", &mut env.mem);
+    new_with_ranges(env, class, ranges)
 }
 
 @end
 
 };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_list() {
+        let ranges = normalize_ranges(vec![5..=7, 0..=1, 2..=2, 6..=9, 20..=20]);
+        assert_eq!(ranges, vec![0..=2, 5..=9, 20..=20]);
+        assert!(ranges_contain(&ranges, 0));
+        assert!(ranges_contain(&ranges, 7));
+        assert!(!ranges_contain(&ranges, 3));
+        assert!(!ranges_contain(&ranges, 21));
+
+        let inverted = invert_ranges(&ranges);
+        assert_eq!(inverted, vec![3..=4, 10..=19, 21..=MAX_SCALAR]);
+        assert_eq!(invert_ranges(&inverted), ranges);
+        assert_eq!(invert_ranges(&[]), vec![0..=MAX_SCALAR]);
+    }
+}
@@ -5,15 +5,24 @@
  */
 //! `NSData` and `NSMutableData`.
 
+use super::ns_error::{
+    set_error, NSCocoaErrorDomain, NSFileReadNoSuchFileError, NSFileReadUnknownError,
+    NSFileWriteUnknownError,
+};
 use super::ns_string::to_rust_string;
 use super::{NSRange, NSUInteger};
-use crate::fs::GuestPath;
+use crate::fs::{GuestPath, GuestPathBuf};
 use crate::mem::{ConstVoidPtr, MutPtr, MutVoidPtr, Ptr};
 use crate::objc::{
     autorelease, id, msg, nil, objc_classes, release, retain, ClassExports, HostObject, NSZonePtr,
 };
 use crate::{msg_class, Environment};
 
+pub type NSDataReadingOptions = NSUInteger;
+
+pub type NSDataWritingOptions = NSUInteger;
+pub const NSDataWritingAtomic: NSDataWritingOptions = 1;
+
 struct NSDataHostObject {
     bytes: MutVoidPtr,
     length: NSUInteger,
@@ -49,9 +58,17 @@ pub const CLASSES: ClassExports = objc_classes! {
     autorelease(env, new)
 }
 
-+ (id)dataWithContentsOfFile:(id)path{
++ (id)dataWithContentsOfFile:(id)path { // NSString*
     let new: id = msg![env; this alloc];
-    let new: id = msg![env; new initWithContentsOfFile: path];
+    let new: id = msg![env; new initWithContentsOfFile:path];
+    autorelease(env, new)
+}
+
++ (id)dataWithContentsOfFile:(id)path // NSString*
+                     options:(NSDataReadingOptions)options
+                       error:(MutPtr<id>)error { // NSError**
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithContentsOfFile:path options:options error:error];
     autorelease(env, new)
 }
 
@@ -88,10 +105,23 @@ pub const CLASSES: ClassExports = objc_classes! {
     nil
 }
 
-- (id)initWithContentsOfFile:(id)path {
+- (id)initWithContentsOfFile:(id)path { // NSString*
+    msg![env; this initWithContentsOfFile:path options:0u32 error:(MutPtr::<id>::null())]
+}
+
+- (id)initWithContentsOfFile:(id)path // NSString*
+                     options:(NSDataReadingOptions)_options
+                       error:(MutPtr<id>)error { // NSError**
     let path = to_rust_string(env, path);
     log_dbg!("[(NSData*){:?} initWithContentsOfFile:{:?}]", this, path);
-    let Ok(bytes) = env.fs.read(GuestPath::new(&path)) else {
+    let path = GuestPath::new(&path);
+    let Ok(bytes) = env.fs.read(path) else {
+        let (code, description) = if env.fs.exists(path) {
+            (NSFileReadUnknownError, format!("Couldn't read file {:?}", path))
+        } else {
+            (NSFileReadNoSuchFileError, format!("No such file {:?}", path))
+        };
+        set_error(env, error, NSCocoaErrorDomain, code, description);
         release(env, this);
         return nil;
     };
@@ -106,11 +136,29 @@ pub const CLASSES: ClassExports = objc_classes! {
     this
 }
 
-// FIXME: writes should be atomic
 - (bool)writeToFile:(id)path // NSString*
-         atomically:(bool)_use_aux_file {
+         atomically:(bool)use_aux_file {
+    let options = if use_aux_file { NSDataWritingAtomic } else { 0 };
+    msg![env; this writeToFile:path options:options error:(MutPtr::<id>::null())]
+}
+
+- (bool)writeToFile:(id)path // NSString*
+            options:(NSDataWritingOptions)options
+              error:(MutPtr<id>)error { // NSError**
     let file = to_rust_string(env, path);
-    log_dbg!("[(NSData*){:?} writeToFile:{:?} atomically:_]", this, file);
+    log_dbg!("[(NSData*){:?} writeToFile:{:?} options:{:#x}]", this, file, options);
+    let path = GuestPath::new(&file);
+    // Write to a temporary file and then move it into place, so that the
+    // destination never contains a partially-written file.
+    let temp_path = if options & NSDataWritingAtomic != 0 {
+        Some(match path.parent_and_file_name() {
+            Some((dir, file_name)) => dir.join(format!(".{}.touchHLE-temp", file_name)),
+            None => GuestPathBuf::from(format!(".{}.touchHLE-temp", file)),
+        })
+    } else {
+        None
+    };
+
     let host_object = env.objc.borrow::<NSDataHostObject>(this);
     // Mem::bytes_at() panics when the pointer is NULL, but NSData's pointer can
     // be NULL if the length is 0.
@@ -119,7 +167,16 @@ pub const CLASSES: ClassExports = objc_classes! {
     } else {
         env.mem.bytes_at(host_object.bytes.cast(), host_object.length)
     };
-    env.fs.write(GuestPath::new(&file), slice).is_ok()
+    let success = if let Some(temp_path) = temp_path {
+        env.fs.write(&temp_path, slice).is_ok() && env.fs.rename(&temp_path, path).is_ok()
+    } else {
+        env.fs.write(path, slice).is_ok()
+    };
+    if !success {
+        let description = format!("Couldn't write file {:?}", path);
+        set_error(env, error, NSCocoaErrorDomain, NSFileWriteUnknownError, description);
+    }
+    success
 }
 
 - (())dealloc {
@@ -163,6 +220,13 @@ pub const CLASSES: ClassExports = objc_classes! {
     );
 }
 
+- (())getBytes:(MutPtr<u8>)buffer length:(NSUInteger)length {
+    let data_length: NSUInteger = msg![env; this length];
+    let length = length.min(data_length);
+    let range = NSRange { location: 0, length };
+    msg![env; this getBytes:buffer range:range]
+}
+
 - (())getBytes:(MutPtr<u8>)buffer {
     let &NSDataHostObject { bytes, length, .. } = env.objc.borrow(this);
     env.mem.memmove(
@@ -172,6 +236,21 @@ pub const CLASSES: ClassExports = objc_classes! {
     );
 }
 
+- (id)subdataWithRange:(NSRange)range {
+    let NSRange { location, length } = range;
+    let data_length: NSUInteger = msg![env; this length];
+    // TODO: throw NSRangeException if out-of-range instead of panic?
+    assert!(location.checked_add(length).map_or(false, |end| end <= data_length));
+    let new: id = msg_class![env; NSData alloc];
+    if length == 0 {
+        let new: id = msg![env; new init];
+        return autorelease(env, new);
+    }
+    let bytes: ConstVoidPtr = msg![env; this bytes];
+    let new: id = msg![env; new initWithBytes:((bytes + location).cast_mut()) length:length];
+    autorelease(env, new)
+}
+
 @end
 
 @implementation NSMutableData: NSData
@@ -183,10 +262,18 @@ pub const CLASSES: ClassExports = objc_classes! {
     msg![env; new initWithBytes:bytes length:length]
 }
 
+- (MutVoidPtr)mutableBytes {
+    env.objc.borrow::<NSDataHostObject>(this).bytes
+}
+
 - (())increaseLengthBy:(NSUInteger)add_len {
     let &NSDataHostObject { bytes, length, .. } = env.objc.borrow(this);
     let new_len = length + add_len;
-    let new_bytes = env.mem.realloc(bytes, new_len);
+    let new_bytes = if bytes.is_null() {
+        env.mem.alloc(new_len)
+    } else {
+        env.mem.realloc(bytes, new_len)
+    };
     let host = env.objc.borrow_mut::<NSDataHostObject>(this);
     host.length = new_len;
     host.bytes = new_bytes;
@@ -202,3 +289,17 @@ pub fn to_rust_slice(env: &mut Environment, data: id) -> &[u8] {
     env.mem
         .bytes_at(borrowed_data.bytes.cast(), borrowed_data.length)
 }
+
+/// Shortcut for host code: create a new `NSData` with a copy of some bytes.
+pub fn from_vec(env: &mut Environment, bytes: Vec<u8>) -> id {
+    let new: id = msg_class![env; NSData alloc];
+    let length: NSUInteger = bytes.len().try_into().unwrap();
+    if length == 0 {
+        return msg![env; new init];
+    }
+    let alloc = env.mem.alloc(length);
+    env.mem
+        .bytes_at_mut(alloc.cast(), length)
+        .copy_from_slice(&bytes);
+    msg![env; new initWithBytesNoCopy:alloc length:length]
+}
@@ -5,10 +5,17 @@
  */
 //! `NSDate`.
 
-use super::NSTimeInterval;
+use super::{
+    ns_string, NSComparisonResult, NSOrderedAscending, NSOrderedDescending, NSOrderedSame,
+    NSTimeInterval, NSUInteger,
+};
 use crate::frameworks::core_foundation::time::apple_epoch;
-use crate::objc::{autorelease, id, objc_classes, ClassExports, HostObject};
-
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, retain, Class, ClassExports, HostObject,
+    NSZonePtr,
+};
+use crate::Environment;
+use std::cmp::Ordering;
 use std::time::SystemTime;
 
 struct NSDateHostObject {
@@ -16,40 +23,180 @@ struct NSDateHostObject {
 }
 impl HostObject for NSDateHostObject {}
 
+/// The current time, as a time interval since the reference date. This uses the
+/// same clock as `time()` etc, so intervals are consistent with those.
+fn now_since_reference_date(env: &mut Environment) -> NSTimeInterval {
+    // "Date objects are immutable, representing an invariant time interval
+    // relative to an absolute reference date (00:00:00 UTC on 1 January 2001)."
+    env.clock
+        .system_time()
+        .duration_since(apple_epoch())
+        .unwrap()
+        .as_secs_f64()
+}
+
+/// The Unix epoch (1 January 1970), as a time interval since the reference
+/// date. This is negative.
+fn unix_epoch_since_reference_date() -> NSTimeInterval {
+    -apple_epoch()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs_f64()
+}
+
 pub const CLASSES: ClassExports = objc_classes! {
 
 (env, this, _cmd);
 
 @implementation NSDate: NSObject
 
-+ (id)date {
-    // "Date objects are immutable, representing an invariant time interval
-    // relative to an absolute reference date (00:00:00 UTC on 1 January 2001)."
-    let time_interval = SystemTime::now()
-        .duration_since(apple_epoch())
-        .unwrap()
-        .as_secs_f64();
++ (id)allocWithZone:(NSZonePtr)_zone {
     let host_object = Box::new(NSDateHostObject {
-        time_interval
+        time_interval: 0.0,
     });
-    let new = env.objc.alloc_object(this, host_object, &mut env.mem);
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
++ (id)dateWithTimeIntervalSinceReferenceDate:(NSTimeInterval)time_interval {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithTimeIntervalSinceReferenceDate:time_interval];
+    autorelease(env, new)
+}
+
++ (id)date {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new init];
 
     log_dbg!("[(NSDate*){:?} date]: New date {:?}", this, new);
 
     autorelease(env, new)
 }
 
++ (id)dateWithTimeIntervalSinceNow:(NSTimeInterval)time_interval {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithTimeIntervalSinceNow:time_interval];
+    autorelease(env, new)
+}
+
++ (id)dateWithTimeIntervalSince1970:(NSTimeInterval)time_interval {
+    let time_interval = time_interval + unix_epoch_since_reference_date();
+    msg![env; this dateWithTimeIntervalSinceReferenceDate:time_interval]
+}
+
++ (id)distantPast {
+    // Same value as on macOS.
+    msg![env; this dateWithTimeIntervalSinceReferenceDate:(-63114076800.0f64)]
+}
++ (id)distantFuture {
+    // Same value as on macOS.
+    msg![env; this dateWithTimeIntervalSinceReferenceDate:63113904000.0f64]
+}
+
+- (id)init {
+    let time_interval = now_since_reference_date(env);
+    msg![env; this initWithTimeIntervalSinceReferenceDate:time_interval]
+}
+
+- (id)initWithTimeIntervalSinceReferenceDate:(NSTimeInterval)time_interval {
+    env.objc.borrow_mut::<NSDateHostObject>(this).time_interval = time_interval;
+    this
+}
+
+- (id)initWithTimeIntervalSinceNow:(NSTimeInterval)time_interval {
+    let time_interval = now_since_reference_date(env) + time_interval;
+    msg![env; this initWithTimeIntervalSinceReferenceDate:time_interval]
+}
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    let key = ns_string::get_static_str(env, "NS.time");
+    let time_interval: NSTimeInterval = msg![env; coder decodeDoubleForKey:key];
+    msg![env; this initWithTimeIntervalSinceReferenceDate:time_interval]
+}
+
+// NSCopying implementation
+- (id)copyWithZone:(NSZonePtr)_zone {
+    // Dates are immutable.
+    retain(env, this)
+}
+
+- (NSTimeInterval)timeIntervalSinceReferenceDate {
+    env.objc.borrow::<NSDateHostObject>(this).time_interval
+}
+
+- (NSTimeInterval)timeIntervalSince1970 {
+    let time_interval: NSTimeInterval = msg![env; this timeIntervalSinceReferenceDate];
+    time_interval - unix_epoch_since_reference_date()
+}
+
+- (NSTimeInterval)timeIntervalSinceNow {
+    let time_interval: NSTimeInterval = msg![env; this timeIntervalSinceReferenceDate];
+    time_interval - now_since_reference_date(env)
+}
+
 - (NSTimeInterval)timeIntervalSinceDate:(id)anotherDate {
     assert!(!anotherDate.is_null());
-    let host_object = env.objc.borrow::<NSDateHostObject>(this);
-    let another_date_host_object = env.objc.borrow::<NSDateHostObject>(anotherDate);
-    let result = another_date_host_object.time_interval - host_object.time_interval;
+    let this_interval: NSTimeInterval = msg![env; this timeIntervalSinceReferenceDate];
+    let other_interval: NSTimeInterval = msg![env; anotherDate timeIntervalSinceReferenceDate];
+    let result = this_interval - other_interval;
     log_dbg!("[(NSDate*){:?} timeIntervalSinceDate:{:?}]: result {} seconds", this, anotherDate, result);
     result
 }
 
-- (NSTimeInterval)timeIntervalSinceReferenceDate {
-    env.objc.borrow::<NSDateHostObject>(this).time_interval
+- (id)dateByAddingTimeInterval:(NSTimeInterval)seconds {
+    let time_interval: NSTimeInterval = msg![env; this timeIntervalSinceReferenceDate];
+    let class: Class = msg![env; this class];
+    msg![env; class dateWithTimeIntervalSinceReferenceDate:(time_interval + seconds)]
+}
+// Older name for dateByAddingTimeInterval:
+- (id)addTimeInterval:(NSTimeInterval)seconds {
+    msg![env; this dateByAddingTimeInterval:seconds]
+}
+
+- (NSComparisonResult)compare:(id)other { // NSDate*
+    let this_interval: NSTimeInterval = msg![env; this timeIntervalSinceReferenceDate];
+    let other_interval: NSTimeInterval = msg![env; other timeIntervalSinceReferenceDate];
+    // TODO: NaN?
+    match this_interval.partial_cmp(&other_interval) {
+        Some(Ordering::Less) => NSOrderedAscending,
+        Some(Ordering::Greater) => NSOrderedDescending,
+        _ => NSOrderedSame,
+    }
+}
+
+- (id)earlierDate:(id)other { // NSDate*
+    // The receiver is returned if they are equal.
+    let order: NSComparisonResult = msg![env; this compare:other];
+    if order == NSOrderedDescending { other } else { this }
+}
+
+- (id)laterDate:(id)other { // NSDate*
+    // The receiver is returned if they are equal.
+    let order: NSComparisonResult = msg![env; this compare:other];
+    if order == NSOrderedAscending { other } else { this }
+}
+
+- (bool)isEqualToDate:(id)other { // NSDate*
+    if other == nil {
+        return false;
+    }
+    let order: NSComparisonResult = msg![env; this compare:other];
+    order == NSOrderedSame
+}
+
+- (NSUInteger)hash {
+    let time_interval: NSTimeInterval = msg![env; this timeIntervalSinceReferenceDate];
+    super::hash_helper(&time_interval.to_bits())
+}
+- (bool)isEqualTo:(id)other {
+    if this == other {
+        return true;
+    }
+    let class: Class = msg_class![env; NSDate class];
+    if !msg![env; other isKindOfClass:class] {
+        return false;
+    }
+    msg![env; this isEqualToDate:other]
 }
 
 @end
@@ -9,15 +9,391 @@
 //! - Apple's [Introduction to Data Formatting Programming Guide For Cocoa](https://developer.apple.com/library/archive/documentation/Cocoa/Conceptual/DataFormatting/DataFormatting.html)
 //! - [Unicode Technical Standard #35](https://unicode.org/reports/tr35/tr35-10.html#Date_Format_Patterns)
 
-use crate::frameworks::core_foundation::time::CFAbsoluteTimeGetGregorianDate;
-use crate::frameworks::foundation::{ns_string, NSTimeInterval};
-use crate::objc::{id, msg, nil, objc_classes, ClassExports, HostObject, NSZonePtr};
+use crate::frameworks::core_foundation::time::apple_epoch;
+use crate::frameworks::foundation::{ns_locale, ns_string, NSInteger, NSTimeInterval};
+use crate::libc::time::{
+    calendar_date_to_timestamp, time_names_for_language, time_t, timestamp_to_calendar_date,
+    TimeNames,
+};
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, ClassExports, HostObject,
+    NSZonePtr,
+};
+use crate::Environment;
+use std::time::SystemTime;
 
 struct NSDateFormatterHostObject {
+    /// `NSString*`
     date_format: Option<id>,
+    /// `NSLocale*`, or `nil` for the current locale.
+    locale: id,
+    /// `NSTimeZone*`, or `nil` for the default time zone (which is always GMT
+    /// in touchHLE currently, see `localtime_r()`).
+    time_zone: id,
 }
 impl HostObject for NSDateFormatterHostObject {}
 
+/// Part of a date format pattern.
+#[derive(Debug, PartialEq)]
+enum DateFormatToken {
+    Literal(String),
+    /// A pattern letter and how many times it is repeated, e.g. `yyyy` is
+    /// `Field('y', 4)`.
+    Field(char, usize),
+}
+
+fn tokenize_date_format(format: &str) -> Vec<DateFormatToken> {
+    let mut tokens = Vec::new();
+    let mut literal = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            'A'..='Z' | 'a'..='z' => {
+                let mut count = 1;
+                while chars.next_if_eq(&c).is_some() {
+                    count += 1;
+                }
+                if !literal.is_empty() {
+                    tokens.push(DateFormatToken::Literal(std::mem::take(&mut literal)));
+                }
+                tokens.push(DateFormatToken::Field(c, count));
+            }
+            // Text can be quoted with '', and '' on its own is a literal '.
+            '\'' if chars.next_if_eq(&'\'').is_some() => literal.push('\''),
+            '\'' => loop {
+                match chars.next() {
+                    None => break,
+                    Some('\'') if chars.next_if_eq(&'\'').is_some() => literal.push('\''),
+                    Some('\'') => break,
+                    Some(c) => literal.push(c),
+                }
+            },
+            _ => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        tokens.push(DateFormatToken::Literal(literal));
+    }
+    tokens
+}
+
+/// Whether a field is written as text (or a time zone) rather than a number.
+fn is_text_field(letter: char, count: usize) -> bool {
+    matches!(
+        (letter, count),
+        ('M', 3..) | ('E', _) | ('a', _) | ('z', _) | ('Z', _)
+    )
+}
+
+/// Format an offset from GMT in seconds as e.g. `+0100`, or `+01:00` if
+/// `colon` is set.
+fn format_gmt_offset(gmt_offset: time_t, colon: bool) -> String {
+    let sign = if gmt_offset < 0 { '-' } else { '+' };
+    let minutes = gmt_offset.abs() / 60;
+    let separator = if colon { ":" } else { "" };
+    format!(
+        "{}{:02}{}{:02}",
+        sign,
+        minutes / 60,
+        separator,
+        minutes % 60
+    )
+}
+
+/// Parse an offset from GMT like `+0100` or `+01:00`, returning it in seconds
+/// and the rest of the string.
+fn parse_gmt_offset(string: &str) -> Option<(time_t, &str)> {
+    let sign = match string.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let rest = &string[1..];
+    let hours: time_t = rest.get(..2)?.parse().ok()?;
+    let rest = rest[2..].strip_prefix(':').unwrap_or(&rest[2..]);
+    let minutes: time_t = rest.get(..2)?.parse().ok()?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some((sign * (hours * 3600 + minutes * 60), &rest[2..]))
+}
+
+/// Format a time (in seconds since the Unix epoch) as it is in a time zone
+/// `gmt_offset` seconds ahead of GMT.
+fn format_date(
+    tokens: &[DateFormatToken],
+    time: f64,
+    gmt_offset: time_t,
+    names: &TimeNames,
+) -> String {
+    let fraction = time - time.floor();
+    let tm = timestamp_to_calendar_date(time.floor() as time_t + gmt_offset);
+    let year = tm.tm_year + 1900;
+    let month = tm.tm_mon as usize;
+    let hour = tm.tm_hour;
+    let hour_12 = if hour % 12 == 0 { 12 } else { hour % 12 };
+
+    let mut result = String::new();
+    for token in tokens {
+        let &DateFormatToken::Field(letter, count) = token else {
+            let DateFormatToken::Literal(literal) = token else {
+                unreachable!()
+            };
+            result.push_str(literal);
+            continue;
+        };
+        let formatted = match (letter, count) {
+            // Two-digit year is a special case, it gets truncated.
+            ('y', 2) => format!("{:02}", year.rem_euclid(100)),
+            ('y', _) => format!("{:0count$}", year),
+            ('M', 1..=2) => format!("{:0count$}", month + 1),
+            ('M', 3) => names.months_abbr[month].to_string(),
+            ('M', _) => names.months[month].to_string(),
+            ('d', _) => format!("{:0count$}", tm.tm_mday),
+            ('H', _) => format!("{:0count$}", hour),
+            ('h', _) => format!("{:0count$}", hour_12),
+            ('m', _) => format!("{:0count$}", tm.tm_min),
+            ('s', _) => format!("{:0count$}", tm.tm_sec),
+            ('E', 1..=3) => names.weekdays_abbr[tm.tm_wday as usize].to_string(),
+            ('E', _) => names.weekdays[tm.tm_wday as usize].to_string(),
+            // Fractional seconds are truncated, not rounded.
+            ('S', _) => format!(
+                "{:0count$}",
+                (fraction * 10f64.powi(count as i32)).floor() as u64
+            ),
+            // TODO: localize
+            ('a', _) => if hour < 12 { "AM" } else { "PM" }.to_string(),
+            ('Z', 1..=3) => format_gmt_offset(gmt_offset, false),
+            ('Z', 5) if gmt_offset == 0 => "Z".to_string(),
+            ('Z', 5) => format_gmt_offset(gmt_offset, true),
+            // TODO: time zone names (e.g. "PST") for `z`
+            ('Z', _) | ('z', _) if gmt_offset == 0 => "GMT".to_string(),
+            ('Z', _) | ('z', _) => format!("GMT{}", format_gmt_offset(gmt_offset, true)),
+            _ => {
+                log!(
+                    "TODO: Date format pattern {:?}, writing it literally",
+                    token
+                );
+                letter.to_string().repeat(count)
+            }
+        };
+        result.push_str(&formatted);
+    }
+    result
+}
+
+/// Parse a string using a date format pattern, returning a timestamp. Times
+/// are assumed to be `gmt_offset` seconds ahead of GMT unless the string
+/// specifies a time zone. Returns [None] if the string doesn't match the
+/// pattern or isn't a valid date.
+fn parse_date(
+    tokens: &[DateFormatToken],
+    string: &str,
+    gmt_offset: time_t,
+    names: &TimeNames,
+) -> Option<time_t> {
+    let mut gmt_offset = gmt_offset;
+    // Fields that aren't in the pattern default to 1970-01-01 00:00:00.
+    let mut year: i32 = 1970;
+    let mut month: i32 = 1;
+    let mut day: i32 = 1;
+    let mut hour: i32 = 0;
+    let mut minute: i32 = 0;
+    let mut second: i32 = 0;
+    let mut is_pm: Option<bool> = None;
+    let mut uses_12_hour = false;
+
+    let mut rest = string;
+    for (i, token) in tokens.iter().enumerate() {
+        let &DateFormatToken::Field(letter, count) = token else {
+            let DateFormatToken::Literal(literal) = token else {
+                unreachable!()
+            };
+            rest = rest.strip_prefix(literal.as_str())?;
+            continue;
+        };
+
+        if letter == 'z' || letter == 'Z' {
+            // Accept any of the forms the time zone can be formatted as.
+            rest = if let Some(after) = rest.strip_prefix('Z') {
+                gmt_offset = 0;
+                after
+            } else {
+                let after = rest
+                    .strip_prefix("GMT")
+                    .or_else(|| rest.strip_prefix("UTC"));
+                match parse_gmt_offset(after.unwrap_or(rest)) {
+                    Some((offset, after)) => {
+                        gmt_offset = offset;
+                        after
+                    }
+                    None => {
+                        gmt_offset = 0;
+                        after?
+                    }
+                }
+            };
+            continue;
+        }
+
+        if is_text_field(letter, count) {
+            let candidates: &[&str] = match (letter, count) {
+                ('M', 3) => &names.months_abbr,
+                ('M', _) => &names.months,
+                ('E', 1..=3) => &names.weekdays_abbr,
+                ('E', _) => &names.weekdays,
+                ('a', _) => &["AM", "PM"],
+                _ => unreachable!(),
+            };
+            let (index, name) = candidates.iter().enumerate().find(|(_, name)| {
+                rest.get(..name.len())
+                    .map_or(false, |prefix| prefix.eq_ignore_ascii_case(name))
+            })?;
+            rest = &rest[name.len()..];
+            match letter {
+                'M' => month = index as i32 + 1,
+                'a' => is_pm = Some(index == 1),
+                // The day of the week is redundant.
+                _ => (),
+            }
+            continue;
+        }
+
+        // If the next field is also numeric, there is nothing to separate
+        // them, so this one must have exactly the specified width.
+        let next_is_numeric = matches!(
+            tokens.get(i + 1),
+            Some(&DateFormatToken::Field(next_letter, next_count))
+                if !is_text_field(next_letter, next_count)
+        );
+        let max_digits = if next_is_numeric || count == 2 {
+            count
+        } else if letter == 'y' {
+            count.max(4)
+        } else {
+            count.max(2)
+        };
+        if !matches!(letter, 'y' | 'M' | 'd' | 'H' | 'h' | 'm' | 's' | 'S') {
+            log!(
+                "TODO: Date format pattern {:?}, can't parse {:?}",
+                token,
+                string
+            );
+            return None;
+        }
+        let digit_count = rest
+            .bytes()
+            .take(max_digits)
+            .take_while(u8::is_ascii_digit)
+            .count();
+        if digit_count == 0 || (next_is_numeric && digit_count != count) {
+            return None;
+        }
+        let value: i32 = rest[..digit_count].parse().ok()?;
+        rest = &rest[digit_count..];
+        match letter {
+            // Two-digit years are assumed to be in the 21st century.
+            'y' if count == 2 => year = 2000 + value,
+            'y' => year = value,
+            'M' => month = value,
+            'd' => day = value,
+            'H' => hour = value,
+            'h' => {
+                hour = value;
+                uses_12_hour = true;
+            }
+            'm' => minute = value,
+            's' => second = value,
+            // Sub-second precision isn't kept.
+            'S' => (),
+            _ => unreachable!(),
+        }
+    }
+    if !rest.is_empty() {
+        return None;
+    }
+
+    if uses_12_hour {
+        if !(1..=12).contains(&hour) {
+            return None;
+        }
+        hour %= 12;
+        if is_pm == Some(true) {
+            hour += 12;
+        }
+    } else if let Some(is_pm) = is_pm {
+        if is_pm && hour < 12 {
+            hour += 12;
+        }
+    }
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || !(0..=23).contains(&hour)
+        || !(0..=59).contains(&minute)
+        || !(0..=60).contains(&second)
+    {
+        return None;
+    }
+
+    let mut tm = timestamp_to_calendar_date(0);
+    tm.tm_year = year - 1900;
+    tm.tm_mon = month - 1;
+    tm.tm_mday = day;
+    tm.tm_hour = hour;
+    tm.tm_min = minute;
+    tm.tm_sec = second;
+    let timestamp = calendar_date_to_timestamp(tm)?;
+    // Reject days that don't exist in that month, e.g. 30 February.
+    if timestamp_to_calendar_date(timestamp).tm_mday != day {
+        return None;
+    }
+    Some(timestamp - gmt_offset)
+}
+
+/// Seconds between the Unix epoch and the reference date used by `NSDate`.
+fn apple_epoch_offset() -> f64 {
+    apple_epoch()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs_f64()
+}
+
+/// Get the time zone's offset from GMT in seconds.
+fn time_zone_offset(env: &mut Environment, formatter: id) -> time_t {
+    let time_zone = env
+        .objc
+        .borrow::<NSDateFormatterHostObject>(formatter)
+        .time_zone;
+    if time_zone == nil {
+        0
+    } else {
+        let offset: NSInteger = msg![env; time_zone secondsFromGMT];
+        offset
+    }
+}
+
+/// Get the tokenized format and localized names for a formatter.
+fn format_and_names(
+    env: &mut Environment,
+    formatter: id,
+) -> (Vec<DateFormatToken>, &'static TimeNames) {
+    let &NSDateFormatterHostObject {
+        date_format,
+        locale,
+        ..
+    } = env.objc.borrow(formatter);
+    let format = match date_format {
+        Some(date_format) => ns_string::to_rust_string(env, date_format),
+        None => "".into(),
+    };
+    log_dbg!("date_format: {:?}", format);
+    let language = ns_locale::language_code_for_locale(env, locale);
+    (
+        tokenize_date_format(&format),
+        time_names_for_language(&language),
+    )
+}
+
 pub const CLASSES: ClassExports = objc_classes! {
 
 (env, this, _cmd);
@@ -27,49 +403,160 @@ pub const CLASSES: ClassExports = objc_classes! {
 + (id)allocWithZone:(NSZonePtr)_zone {
     let host_object = Box::new(NSDateFormatterHostObject {
         date_format: None,
+        locale: nil,
+        time_zone: nil,
     });
     env.objc.alloc_object(this, host_object, &mut env.mem)
 }
 
+- (())dealloc {
+    let &NSDateFormatterHostObject {
+        date_format,
+        locale,
+        time_zone,
+    } = env.objc.borrow(this);
+    if let Some(date_format) = date_format {
+        release(env, date_format);
+    }
+    release(env, locale);
+    release(env, time_zone);
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (id)dateFormat {
+    env.objc.borrow::<NSDateFormatterHostObject>(this).date_format.unwrap_or(nil)
+}
 - (())setDateFormat:(id)format { // NSString *
     let date_format: id = msg![env; format copy];
-    env.objc.borrow_mut::<NSDateFormatterHostObject>(this).date_format = Some(date_format);
+    let host_object = env.objc.borrow_mut::<NSDateFormatterHostObject>(this);
+    let old_format = std::mem::replace(&mut host_object.date_format, Some(date_format));
+    if let Some(old_format) = old_format {
+        release(env, old_format);
+    }
 }
 
-- (id)stringFromDate:(id)date {
-    let &NSDateFormatterHostObject {
-        date_format
-    } = env.objc.borrow(this);
-    let mut format = ns_string::to_rust_string(env, date_format.unwrap()).to_string().clone();
-    log_dbg!("date_format before: {:?}", format);
+- (id)locale {
+    let locale = env.objc.borrow::<NSDateFormatterHostObject>(this).locale;
+    if locale == nil {
+        msg_class![env; NSLocale currentLocale]
+    } else {
+        locale
+    }
+}
+- (())setLocale:(id)locale { // NSLocale*
+    retain(env, locale);
+    let host_object = env.objc.borrow_mut::<NSDateFormatterHostObject>(this);
+    let old_locale = std::mem::replace(&mut host_object.locale, locale);
+    release(env, old_locale);
+}
 
+- (id)timeZone {
+    env.objc.borrow::<NSDateFormatterHostObject>(this).time_zone
+}
+- (())setTimeZone:(id)time_zone { // NSTimeZone*
+    retain(env, time_zone);
+    let host_object = env.objc.borrow_mut::<NSDateFormatterHostObject>(this);
+    let old_time_zone = std::mem::replace(&mut host_object.time_zone, time_zone);
+    release(env, old_time_zone);
+}
+
+- (id)stringFromDate:(id)date { // NSDate*
+    let (tokens, names) = format_and_names(env, this);
     let ti: NSTimeInterval = msg![env; date timeIntervalSinceReferenceDate];
-    let greg_date = CFAbsoluteTimeGetGregorianDate(env, ti, nil);
-    let year = greg_date.year;
-    let month = greg_date.month;
-    let day = greg_date.day;
-    let hour = greg_date.hours;
-    let minute = greg_date.minutes;
-    let second = greg_date.seconds;
-
-    format = format.replace("yyyy", format!("{:04}", year).as_str());
-    format = format.replace("MM", format!("{:02}", month).as_str());
-    format = format.replace("dd", format!("{}", day).as_str());
-    format = format.replace("HH", format!("{}", hour).as_str());
-    format = format.replace("mm", format!("{:02}", minute).as_str());
-    format = format.replace("ss", format!("{:02}", second).as_str());
-
-    for c in format.chars() {
-        match c {
-            'A'..='Z' | 'a'..='z' => unimplemented!("date string contains unsubstituted format patterns"),
-            _ => {}
-        }
-    }
-    log_dbg!("date_format after: {:?}", format);
+    let gmt_offset = time_zone_offset(env, this);
+    let string = format_date(&tokens, ti + apple_epoch_offset(), gmt_offset, names);
+    let string = ns_string::from_rust_string(env, string);
+    autorelease(env, string)
+}
 
-    ns_string::from_rust_string(env, format)
+- (id)dateFromString:(id)string { // NSString*
+    if string == nil {
+        return nil;
+    }
+    let (tokens, names) = format_and_names(env, this);
+    let string = ns_string::to_rust_string(env, string);
+    let gmt_offset = time_zone_offset(env, this);
+    let Some(timestamp) = parse_date(&tokens, &string, gmt_offset, names) else {
+        return nil;
+    };
+    let ti: NSTimeInterval = f64::from(timestamp) - apple_epoch_offset();
+    msg_class![env; NSDate dateWithTimeIntervalSinceReferenceDate:ti]
 }
 
 @end
 
 };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_date_format() {
+        use DateFormatToken::*;
+        assert_eq!(
+            tokenize_date_format("yyyy-MM-dd 'at' h:mm a ''"),
+            vec![
+                Field('y', 4),
+                Literal("-".to_string()),
+                Field('M', 2),
+                Literal("-".to_string()),
+                Field('d', 2),
+                Literal(" at ".to_string()),
+                Field('h', 1),
+                Literal(":".to_string()),
+                Field('m', 2),
+                Literal(" ".to_string()),
+                Field('a', 1),
+                Literal(" '".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_and_parse_date() {
+        let names = time_names_for_language("en");
+        // 2009-07-04 15:06:07 UTC, a Saturday
+        let timestamp = 1246719967;
+
+        let tokens = tokenize_date_format("EEEE, d MMMM yyyy HH:mm:ss");
+        let string = format_date(&tokens, timestamp as f64, 0, names);
+        assert_eq!(string, "Saturday, 4 July 2009 15:06:07");
+        assert_eq!(parse_date(&tokens, &string, 0, names), Some(timestamp));
+
+        let tokens = tokenize_date_format("yyyyMMdd hh:mm:ss a");
+        let string = format_date(&tokens, timestamp as f64, 0, names);
+        assert_eq!(string, "20090704 03:06:07 PM");
+        assert_eq!(parse_date(&tokens, &string, 0, names), Some(timestamp));
+
+        let tokens = tokenize_date_format("yyyy-MM-dd");
+        assert_eq!(parse_date(&tokens, "2009-02-30", 0, names), None);
+        assert_eq!(parse_date(&tokens, "2009-13-01", 0, names), None);
+        assert_eq!(parse_date(&tokens, "2009-07-04x", 0, names), None);
+        assert_eq!(parse_date(&tokens, "2009/07/04", 0, names), None);
+        assert_eq!(parse_date(&tokens, "", 0, names), None);
+
+        let tokens = tokenize_date_format("yyyy-MM-dd'T'HH:mm:ss.SSSZ");
+        let string = format_date(&tokens, timestamp as f64 + 0.25, 3600, names);
+        assert_eq!(string, "2009-07-04T16:06:07.250+0100");
+        assert_eq!(parse_date(&tokens, &string, 0, names), Some(timestamp));
+        assert_eq!(
+            parse_date(&tokens, "2009-07-04T10:06:07.000-0500", 0, names),
+            Some(timestamp)
+        );
+        let tokens = tokenize_date_format("yyyy-MM-dd HH:mm z");
+        assert_eq!(
+            format_date(&tokens, timestamp as f64, 0, names),
+            "2009-07-04 15:06 GMT"
+        );
+        assert_eq!(
+            parse_date(&tokens, "2009-07-04 16:06 GMT+01:00", 0, names),
+            Some(timestamp - 7)
+        );
+
+        // Unsupported pattern letters don't panic.
+        let tokens = tokenize_date_format("G yyyy");
+        assert_eq!(format_date(&tokens, timestamp as f64, 0, names), "G 2009");
+        assert_eq!(parse_date(&tokens, "AD 2009", 0, names), None);
+    }
+}
@@ -5,13 +5,15 @@
  */
 //! The `NSDictionary` class cluster, including `NSMutableDictionary`.
 
-use super::ns_property_list_serialization::deserialize_plist_from_file;
-use super::{ns_string, ns_url, NSUInteger};
-use crate::abi::VaList;
+use super::ns_array::{self, sort_by_object, Comparator};
+use super::ns_property_list_serialization::{deserialize_plist_from_file, serialize_plist_to_file};
+use super::{ns_keyed_unarchiver, ns_string, ns_url, NSUInteger};
+use crate::abi::{CallFromHost, VaList};
 use crate::fs::GuestPath;
+use crate::mem::MutPtr;
 use crate::objc::{
-    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, ClassExports, HostObject,
-    NSZonePtr,
+    autorelease, get_block_invoke, id, msg, msg_class, nil, objc_classes, release, retain,
+    ClassExports, HostObject, NSZonePtr,
 };
 use crate::Environment;
 use std::collections::HashMap;
@@ -30,6 +32,9 @@ pub(super) struct DictionaryHostObject {
     /// where the keys have the same hash value.
     map: HashMap<Hash, Vec<(id, id)>>,
     pub(super) count: NSUInteger,
+    /// Incremented on every change, so that mutation during enumeration can
+    /// be detected.
+    mutations: u32,
 }
 impl HostObject for DictionaryHostObject {}
 impl DictionaryHostObject {
@@ -55,6 +60,7 @@ impl DictionaryHostObject {
 
         let value = retain(env, value);
 
+        self.mutations = self.mutations.wrapping_add(1);
         let Some(collisions) = self.map.get_mut(&hash) else {
             self.map.insert(hash, vec![(key, value)]);
             self.count += 1;
@@ -62,6 +68,7 @@ impl DictionaryHostObject {
         };
         for &mut (candidate_key, ref mut existing_value) in collisions.iter_mut() {
             if candidate_key == key || msg![env; candidate_key isEqualTo:key] {
+                release(env, key);
                 release(env, *existing_value);
                 *existing_value = value;
                 return;
@@ -70,6 +77,30 @@ impl DictionaryHostObject {
         collisions.push((key, value));
         self.count += 1;
     }
+    pub(super) fn remove(&mut self, env: &mut Environment, key: id) {
+        let hash: Hash = msg![env; key hash];
+        let Some(collisions) = self.map.get_mut(&hash) else {
+            return;
+        };
+        let mut found = None;
+        for (i, &(candidate_key, _)) in collisions.iter().enumerate() {
+            if candidate_key == key || msg![env; candidate_key isEqualTo:key] {
+                found = Some(i);
+                break;
+            }
+        }
+        let Some(i) = found else {
+            return;
+        };
+        let (existing_key, existing_value) = collisions.swap_remove(i);
+        if collisions.is_empty() {
+            self.map.remove(&hash);
+        }
+        self.count -= 1;
+        self.mutations = self.mutations.wrapping_add(1);
+        release(env, existing_key);
+        release(env, existing_value);
+    }
     pub(super) fn release(&mut self, env: &mut Environment) {
         for collisions in self.map.values() {
             for &(key, value) in collisions {
@@ -79,10 +110,72 @@ impl DictionaryHostObject {
         }
     }
     pub(super) fn iter_keys(&self) -> impl Iterator<Item = id> + '_ {
-        self.map.values().flatten().map(|&(key, _value)| key)
+        self.iter_keys_and_values().map(|(key, _value)| key)
+    }
+    /// Iterate over the key-value pairs. The order is arbitrary, but it is the
+    /// same for every iteration until the dictionary is next mutated, so e.g.
+    /// `allKeys` and `allValues` correspond.
+    pub(super) fn iter_keys_and_values(&self) -> impl Iterator<Item = (id, id)> + '_ {
+        self.map.values().flatten().copied()
     }
 }
 
+/// Call a block taking `(id key, id obj, BOOL *stop)` for each entry in a
+/// dictionary, until it sets the stop flag.
+fn enumerate_keys_and_objects_with_block(env: &mut Environment, dict: id, block: id) {
+    let host_object = env.objc.borrow::<DictionaryHostObject>(dict);
+    let mutations = host_object.mutations;
+    let pairs: Vec<(id, id)> = host_object.iter_keys_and_values().collect();
+
+    let invoke = get_block_invoke(&env.mem, block);
+    let stop: MutPtr<bool> = env.mem.alloc_and_write(false);
+    for (key, value) in pairs {
+        let _: () = invoke.call_from_host(env, (block, key, value, stop));
+        if env.objc.borrow::<DictionaryHostObject>(dict).mutations != mutations {
+            // TODO: raise NSGenericException
+            panic!(
+                "*** Collection {:?} was mutated while being enumerated.",
+                dict
+            );
+        }
+        if env.mem.read(stop) {
+            break;
+        }
+    }
+    env.mem.free(stop.cast());
+}
+
+/// Shared implementation of `allKeys` and `allValues`.
+fn all_keys_or_values(env: &mut Environment, dict: id, keys: bool) -> id {
+    let objects: Vec<id> = env
+        .objc
+        .borrow::<DictionaryHostObject>(dict)
+        .iter_keys_and_values()
+        .map(|(key, value)| if keys { key } else { value })
+        .collect();
+    for &object in &objects {
+        retain(env, object);
+    }
+    let array = ns_array::from_vec(env, objects);
+    autorelease(env, array)
+}
+
+/// Implementation of `keysSortedByValueUsingComparator:`.
+fn keys_sorted_by_value(env: &mut Environment, dict: id, comparator: Comparator) -> id {
+    let pairs: Vec<(id, id)> = env
+        .objc
+        .borrow::<DictionaryHostObject>(dict)
+        .iter_keys_and_values()
+        .collect();
+    let pairs = sort_by_object(env, pairs, comparator, |(_key, value)| value);
+    let keys: Vec<id> = pairs
+        .into_iter()
+        .map(|(key, _value)| retain(env, key))
+        .collect();
+    let array = ns_array::from_vec(env, keys);
+    autorelease(env, array)
+}
+
 /// Helper to enable sharing `dictionaryWithObjectsAndKeys:` and
 /// `initWithObjectsAndKeys:`' implementations without vararg passthrough.
 pub fn init_with_objects_and_keys(
@@ -180,6 +273,11 @@ pub const CLASSES: ClassExports = objc_classes! {
     deserialize_plist_from_file(env, &path, /* array_expected: */ false)
 }
 
+- (bool)writeToFile:(id)path // NSString*
+         atomically:(bool)atomically {
+    serialize_plist_to_file(env, this, path, atomically)
+}
+
 // NSCopying implementation
 - (id)copyWithZone:(NSZonePtr)_zone {
     // TODO: override this once we have NSMutableString!
@@ -209,6 +307,18 @@ pub const CLASSES: ClassExports = objc_classes! {
     init_with_objects_and_keys(env, this, first_object, dots.start())
 }
 
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    // FIXME: What if it's not an NSKeyedUnarchiver?
+    let pairs = ns_keyed_unarchiver::decode_current_dictionary(env, coder);
+    let mut host_object = <DictionaryHostObject as Default>::default();
+    for (key, object) in pairs {
+        host_object.insert(env, key, object, /* copy_key: */ true);
+    }
+    *env.objc.borrow_mut(this) = host_object;
+    this
+}
+
 - (id)init {
     *env.objc.borrow_mut(this) = <DictionaryHostObject as Default>::default();
     this
@@ -226,6 +336,130 @@ pub const CLASSES: ClassExports = objc_classes! {
     res
 }
 
+- (id)allKeys {
+    all_keys_or_values(env, this, /* keys: */ true)
+}
+- (id)allValues {
+    all_keys_or_values(env, this, /* keys: */ false)
+}
+
+- (())enumerateKeysAndObjectsUsingBlock:(id)block { // void (^)(id, id, BOOL*)
+    enumerate_keys_and_objects_with_block(env, this, block)
+}
+
+- (id)keysSortedByValueUsingComparator:(id)comparator { // NSComparator
+    keys_sorted_by_value(env, this, Comparator::Block(comparator))
+}
+
+@end
+
+// NSMutableDictionary is an abstract class. A subclass must provide everything
+// NSDictionary provides, plus:
+// - (void)setObject:(id)object forKey:(id)key;
+// - (void)removeObjectForKey:(id)key;
+// Note that it inherits from NSDictionary, so we must ensure we override any
+// default methods that would be inappropriate for mutability.
+@implementation NSMutableDictionary: NSDictionary
+
++ (id)allocWithZone:(NSZonePtr)zone {
+    // NSMutableDictionary might be subclassed by something which needs
+    // allocWithZone: to have the normal behaviour. Unimplemented: call
+    // superclass alloc then.
+    assert!(this == env.objc.get_known_class("NSMutableDictionary", &mut env.mem));
+    msg_class![env; _touchHLE_NSMutableDictionary allocWithZone:zone]
+}
+
+// NSCopying implementation
+- (id)copyWithZone:(NSZonePtr)_zone {
+    let pairs: Vec<(id, id)> = env
+        .objc
+        .borrow::<DictionaryHostObject>(this)
+        .iter_keys_and_values()
+        .collect();
+    dict_from_keys_and_objects(env, &pairs)
+}
+
+@end
+
+// Our private subclass that is the single implementation of
+// NSMutableDictionary for the time being.
+@implementation _touchHLE_NSMutableDictionary: NSMutableDictionary
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::<DictionaryHostObject>::default();
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (())dealloc {
+    std::mem::take(env.objc.borrow_mut::<DictionaryHostObject>(this)).release(env);
+
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (id)initWithObjectsAndKeys:(id)first_object, ...dots {
+    init_with_objects_and_keys(env, this, first_object, dots.start())
+}
+
+// NSCoding implementation
+- (id)initWithCoder:(id)coder {
+    // FIXME: What if it's not an NSKeyedUnarchiver?
+    let pairs = ns_keyed_unarchiver::decode_current_dictionary(env, coder);
+    let mut host_object = <DictionaryHostObject as Default>::default();
+    for (key, object) in pairs {
+        host_object.insert(env, key, object, /* copy_key: */ true);
+    }
+    *env.objc.borrow_mut(this) = host_object;
+    this
+}
+
+- (id)init {
+    *env.objc.borrow_mut(this) = <DictionaryHostObject as Default>::default();
+    this
+}
+
+- (id)initWithCapacity:(NSUInteger)_capacity {
+    msg![env; this init]
+}
+
+- (NSUInteger)count {
+    env.objc.borrow::<DictionaryHostObject>(this).count
+}
+- (id)objectForKey:(id)key {
+    let host_obj: DictionaryHostObject = std::mem::take(env.objc.borrow_mut(this));
+    let res = host_obj.lookup(env, key);
+    *env.objc.borrow_mut(this) = host_obj;
+    res
+}
+
+- (id)allKeys {
+    all_keys_or_values(env, this, /* keys: */ true)
+}
+- (id)allValues {
+    all_keys_or_values(env, this, /* keys: */ false)
+}
+
+- (())enumerateKeysAndObjectsUsingBlock:(id)block { // void (^)(id, id, BOOL*)
+    enumerate_keys_and_objects_with_block(env, this, block)
+}
+
+- (id)keysSortedByValueUsingComparator:(id)comparator { // NSComparator
+    keys_sorted_by_value(env, this, Comparator::Block(comparator))
+}
+
+- (())setObject:(id)object
+         forKey:(id)key {
+    assert!(object != nil && key != nil); // TODO: raise proper exception
+    let mut host_obj: DictionaryHostObject = std::mem::take(env.objc.borrow_mut(this));
+    host_obj.insert(env, key, object, /* copy_key: */ true);
+    *env.objc.borrow_mut(this) = host_obj;
+}
+
+- (())removeObjectForKey:(id)key {
+    let mut host_obj: DictionaryHostObject = std::mem::take(env.objc.borrow_mut(this));
+    host_obj.remove(env, key);
+    *env.objc.borrow_mut(this) = host_obj;
+}
+
 @end
 
 };
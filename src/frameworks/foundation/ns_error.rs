@@ -0,0 +1,187 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSError`.
+
+use super::ns_dictionary::dict_from_keys_and_objects;
+use super::{ns_string, NSInteger};
+use crate::dyld::{ConstantExports, HostConstant};
+use crate::mem::MutPtr;
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, ClassExports, HostObject,
+    NSZonePtr,
+};
+use crate::Environment;
+
+pub const NSCocoaErrorDomain: &str = "NSCocoaErrorDomain";
+pub const NSLocalizedDescriptionKey: &str = "NSLocalizedDescriptionKey";
+pub const NSOSStatusErrorDomain: &str = "NSOSStatusErrorDomain";
+pub const NSURLErrorDomain: &str = "NSURLErrorDomain";
+
+pub const CONSTANTS: ConstantExports = &[
+    (
+        "_NSCocoaErrorDomain",
+        HostConstant::NSString(NSCocoaErrorDomain),
+    ),
+    (
+        "_NSOSStatusErrorDomain",
+        HostConstant::NSString(NSOSStatusErrorDomain),
+    ),
+    (
+        "_NSPOSIXErrorDomain",
+        HostConstant::NSString("NSPOSIXErrorDomain"),
+    ),
+    (
+        "_NSURLErrorDomain",
+        HostConstant::NSString(NSURLErrorDomain),
+    ),
+    (
+        "_NSLocalizedDescriptionKey",
+        HostConstant::NSString(NSLocalizedDescriptionKey),
+    ),
+];
+
+// Some codes in NSCocoaErrorDomain.
+pub const NSFileNoSuchFileError: NSInteger = 4;
+pub const NSFileReadUnknownError: NSInteger = 256;
+pub const NSFileReadCorruptFileError: NSInteger = 259;
+pub const NSFileReadNoSuchFileError: NSInteger = 260;
+pub const NSFileWriteUnknownError: NSInteger = 512;
+pub const NSPropertyListReadCorruptError: NSInteger = 3840;
+pub const NSPropertyListWriteStreamError: NSInteger = 3851;
+
+struct NSErrorHostObject {
+    /// `NSString*`
+    domain: id,
+    code: NSInteger,
+    /// `NSDictionary*`, may be `nil`
+    user_info: id,
+}
+impl HostObject for NSErrorHostObject {}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSError: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSErrorHostObject {
+        domain: nil,
+        code: 0,
+        user_info: nil,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
++ (id)errorWithDomain:(id)domain // NSString*
+                 code:(NSInteger)code
+             userInfo:(id)user_info { // NSDictionary*
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithDomain:domain code:code userInfo:user_info];
+    autorelease(env, new)
+}
+
+- (id)initWithDomain:(id)domain // NSString*
+                code:(NSInteger)code
+            userInfo:(id)user_info { // NSDictionary*
+    let domain: id = msg![env; domain copy];
+    retain(env, user_info);
+    *env.objc.borrow_mut(this) = NSErrorHostObject {
+        domain,
+        code,
+        user_info,
+    };
+    this
+}
+
+- (())dealloc {
+    let &NSErrorHostObject {
+        domain, user_info, ..
+    } = env.objc.borrow(this);
+    release(env, domain);
+    release(env, user_info);
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (id)domain {
+    env.objc.borrow::<NSErrorHostObject>(this).domain
+}
+- (NSInteger)code {
+    env.objc.borrow::<NSErrorHostObject>(this).code
+}
+- (id)userInfo {
+    env.objc.borrow::<NSErrorHostObject>(this).user_info
+}
+
+- (id)localizedDescription {
+    let &NSErrorHostObject {
+        domain,
+        code,
+        user_info,
+    } = env.objc.borrow(this);
+    if user_info != nil {
+        let key = ns_string::get_static_str(env, NSLocalizedDescriptionKey);
+        let description: id = msg![env; user_info objectForKey:key];
+        if description != nil {
+            return description;
+        }
+    }
+    let domain = ns_string::to_rust_string(env, domain);
+    let description = format!(
+        "The operation couldn’t be completed. ({} error {}.)",
+        domain,
+        code
+    );
+    let description = ns_string::from_rust_string(env, description);
+    autorelease(env, description)
+}
+
+- (id)description {
+    let description: id = msg![env; this localizedDescription];
+    let description = ns_string::to_rust_string(env, description);
+    let &NSErrorHostObject { domain, code, .. } = env.objc.borrow(this);
+    let domain = ns_string::to_rust_string(env, domain);
+    let description = format!("Error Domain={} Code={} \"{}\"", domain, code, description);
+    let description = ns_string::from_rust_string(env, description);
+    autorelease(env, description)
+}
+
+@end
+
+};
+
+/// Create an autoreleased `NSError` with the given localized description.
+pub fn new_error(
+    env: &mut Environment,
+    domain: &'static str,
+    code: NSInteger,
+    description: String,
+) -> id {
+    let domain = ns_string::get_static_str(env, domain);
+    let key = ns_string::get_static_str(env, NSLocalizedDescriptionKey);
+    let description = ns_string::from_rust_string(env, description);
+    let user_info = dict_from_keys_and_objects(env, &[(key, description)]);
+    release(env, description);
+    let error: id = msg_class![env; NSError errorWithDomain:domain code:code userInfo:user_info];
+    release(env, user_info);
+    error
+}
+
+/// Helper for methods with an `NSError**` out-parameter: if the pointer is
+/// non-null, create an error and write it there.
+pub fn set_error(
+    env: &mut Environment,
+    out: MutPtr<id>,
+    domain: &'static str,
+    code: NSInteger,
+    description: String,
+) {
+    log_dbg!("Returning error: {}", description);
+    if !out.is_null() {
+        let error = new_error(env, domain, code, description);
+        env.mem.write(out, error);
+    }
+}
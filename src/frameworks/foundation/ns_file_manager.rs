@@ -5,14 +5,47 @@
  */
 //! `NSFileManager` etc.
 
-use super::{ns_array, ns_string, NSUInteger};
-use crate::dyld::{export_c_func, FunctionExports};
+use super::ns_dictionary::dict_from_keys_and_objects;
+use super::ns_error::{
+    set_error, NSCocoaErrorDomain, NSFileNoSuchFileError, NSFileReadNoSuchFileError,
+    NSFileWriteUnknownError,
+};
+use super::{ns_array, ns_string, NSTimeInterval, NSUInteger};
+use crate::dyld::{export_c_func, ConstantExports, FunctionExports, HostConstant};
+use crate::frameworks::core_foundation::time::apple_epoch;
 use crate::fs::{GuestPath, GuestPathBuf};
 use crate::mem::MutPtr;
 use crate::objc::{
     autorelease, id, msg, msg_class, nil, objc_classes, release, ClassExports, HostObject,
 };
 use crate::Environment;
+use std::time::SystemTime;
+
+pub const NSFileSize: &str = "NSFileSize";
+pub const NSFileModificationDate: &str = "NSFileModificationDate";
+pub const NSFileType: &str = "NSFileType";
+pub const NSFileTypeRegular: &str = "NSFileTypeRegular";
+pub const NSFileTypeDirectory: &str = "NSFileTypeDirectory";
+
+pub const CONSTANTS: ConstantExports = &[
+    ("_NSFileSize", HostConstant::NSString(NSFileSize)),
+    (
+        "_NSFileModificationDate",
+        HostConstant::NSString(NSFileModificationDate),
+    ),
+    ("_NSFileType", HostConstant::NSString(NSFileType)),
+    (
+        "_NSFileTypeRegular",
+        HostConstant::NSString(NSFileTypeRegular),
+    ),
+    (
+        "_NSFileTypeDirectory",
+        HostConstant::NSString(NSFileTypeDirectory),
+    ),
+];
+
+type NSDirectoryEnumerationOptions = NSUInteger;
+const NSDirectoryEnumerationSkipsHiddenFiles: NSDirectoryEnumerationOptions = 1 << 2;
 
 type NSSearchPathDirectory = NSUInteger;
 const NSApplicationDirectory: NSSearchPathDirectory = 1;
@@ -65,6 +98,43 @@ struct NSDirectoryEnumeratorHostObject {
 }
 impl HostObject for NSDirectoryEnumeratorHostObject {}
 
+/// Get the names of the files/directories in a directory, sorted so the order
+/// is deterministic, optionally excluding hidden ones (names beginning with
+/// `.`).
+fn directory_contents(
+    env: &Environment,
+    path: &GuestPath,
+    skip_hidden: bool,
+) -> Option<Vec<String>> {
+    let mut names: Vec<String> = env
+        .fs
+        .enumerate(path)
+        .ok()?
+        .filter(|name| !(skip_hidden && name.starts_with('.')))
+        .map(String::from)
+        .collect();
+    names.sort();
+    Some(names)
+}
+
+/// Remove a file, or a directory and all its contents.
+fn remove_recursive(env: &mut Environment, path: &GuestPath) -> Result<(), ()> {
+    if env.fs.is_dir(path) {
+        for name in directory_contents(env, path, /* skip_hidden: */ false).unwrap() {
+            remove_recursive(env, &path.join(name))?;
+        }
+    }
+    env.fs.remove(path)
+}
+
+/// Seconds since the `NSDate` reference date, for file modification times.
+fn time_since_reference_date(time: SystemTime) -> NSTimeInterval {
+    match time.duration_since(apple_epoch()) {
+        Ok(after) => after.as_secs_f64(),
+        Err(before) => -before.duration().as_secs_f64(),
+    }
+}
+
 pub const CLASSES: ClassExports = objc_classes! {
 
 (env, this, _cmd);
@@ -96,8 +166,9 @@ pub const CLASSES: ClassExports = objc_classes! {
     let path = ns_string::to_rust_string(env, path); // TODO: avoid copy
     let guest_path = GuestPath::new(&path);
     let res_exists = env.fs.exists(guest_path);
-    if !is_dir.is_null() {
-        let res_is_dir = !env.fs.is_file(guest_path);
+    // The isDirectory: result is undefined if the file doesn't exist.
+    if res_exists && !is_dir.is_null() {
+        let res_is_dir = env.fs.is_dir(guest_path);
         env.mem.write(is_dir, res_is_dir);
         log_dbg!("fileExistsAtPath:{:?} isDirectory:{:?} => {}", path, res_is_dir, res_exists);
     } else {
@@ -128,20 +199,101 @@ pub const CLASSES: ClassExports = objc_classes! {
     }
 }
 
+- (bool)createDirectoryAtPath:(id)path // NSString*
+  withIntermediateDirectories:(bool)intermediates
+                   attributes:(id)attributes // NSDictionary*
+                        error:(MutPtr<id>)error { // NSError**
+    if attributes != nil {
+        log!("TODO: ignoring attributes for createDirectoryAtPath:");
+    }
+    let path = ns_string::to_rust_string(env, path); // TODO: avoid copy
+    let path = GuestPath::new(&path);
+
+    let result = if intermediates {
+        // Create each missing ancestor in turn. Existing directories are not
+        // an error in this mode.
+        let mut ancestors = vec![path];
+        while let Some(parent) = ancestors.last().unwrap().parent() {
+            if parent.as_str().is_empty() || env.fs.exists(parent) {
+                break;
+            }
+            ancestors.push(parent);
+        }
+        ancestors.into_iter().rev().try_for_each(|dir| {
+            if env.fs.is_dir(dir) {
+                Ok(())
+            } else {
+                env.fs.create_dir(dir)
+            }
+        })
+    } else {
+        env.fs.create_dir(path)
+    };
+    log_dbg!(
+        "createDirectoryAtPath:{:?} withIntermediateDirectories:{} => {:?}",
+        path,
+        intermediates,
+        result
+    );
+    if result.is_err() {
+        let description = format!("Couldn't create directory {:?}", path);
+        set_error(env, error, NSCocoaErrorDomain, NSFileWriteUnknownError, description);
+    }
+    result.is_ok()
+}
+
 - (bool)removeItemAtPath:(id)path // NSString*
                    error:(MutPtr<id>)error { // NSError**
     let path = ns_string::to_rust_string(env, path); // TODO: avoid copy
-    match env.fs.remove(GuestPath::new(&path)) {
+    let path = GuestPath::new(&path);
+    if !env.fs.exists(path) {
+        let description = format!("No such file {:?}", path);
+        set_error(env, error, NSCocoaErrorDomain, NSFileNoSuchFileError, description);
+        return false;
+    }
+    match remove_recursive(env, path) {
         Ok(()) => true,
         Err(()) => {
-            if !error.is_null() {
-                todo!(); // TODO: create an NSError if requested
-            }
+            let description = format!("Couldn't remove {:?}", path);
+            set_error(env, error, NSCocoaErrorDomain, NSFileWriteUnknownError, description);
             false
         }
     }
 }
 
+- (id)attributesOfItemAtPath:(id)path // NSString*
+                       error:(MutPtr<id>)error { // NSError**
+    let path = ns_string::to_rust_string(env, path); // TODO: avoid copy
+    let Ok(metadata) = env.fs.metadata(GuestPath::new(&path)) else {
+        let description = format!("No such file {:?}", path);
+        set_error(env, error, NSCocoaErrorDomain, NSFileReadNoSuchFileError, description);
+        return nil;
+    };
+    log_dbg!("attributesOfItemAtPath:{:?} => {:?}", path, metadata);
+
+    let size: id = msg_class![env; NSNumber numberWithUnsignedLongLong:(metadata.len)];
+    // Files inside the app bundle have no modification time, so use an
+    // arbitrary but consistent one.
+    let modified = metadata.modified.map_or(0.0, time_since_reference_date);
+    let modified: id = msg_class![env; NSDate dateWithTimeIntervalSinceReferenceDate:modified];
+    let file_type = if metadata.is_dir {
+        NSFileTypeDirectory
+    } else {
+        NSFileTypeRegular
+    };
+    let file_type = ns_string::get_static_str(env, file_type);
+
+    let size_key = ns_string::get_static_str(env, NSFileSize);
+    let modified_key = ns_string::get_static_str(env, NSFileModificationDate);
+    let file_type_key = ns_string::get_static_str(env, NSFileType);
+    let dict = dict_from_keys_and_objects(env, &[
+        (size_key, size),
+        (modified_key, modified),
+        (file_type_key, file_type),
+    ]);
+    autorelease(env, dict)
+}
+
 - (id)enumeratorAtPath:(id)path { // NSString*
     let path = ns_string::to_rust_string(env, path); // TODO: avoid copy
     let Ok(paths) = env.fs.enumerate_recursive(GuestPath::new(&path)) else {
@@ -156,29 +308,51 @@ pub const CLASSES: ClassExports = objc_classes! {
 }
 
 - (id)directoryContentsAtPath:(id)path /* NSString* */ { // NSArray*
+    msg![env; this contentsOfDirectoryAtPath:path error:(MutPtr::<id>::null())]
+}
+
+- (id)contentsOfDirectoryAtPath:(id)path /* NSString* */
+                          error:(MutPtr<id>)error { // NSError**
     let path = ns_string::to_rust_string(env, path); // TODO: avoid copy
-    let Ok(paths) = env.fs.enumerate(GuestPath::new(&path)) else {
+    let path = GuestPath::new(&path);
+    let Some(names) = directory_contents(env, path, /* skip_hidden: */ false) else {
+        let description = format!("No such directory {:?}", path);
+        set_error(env, error, NSCocoaErrorDomain, NSFileReadNoSuchFileError, description);
         return nil;
     };
-    let paths: Vec<GuestPathBuf> = paths
-        .map(|path| GuestPathBuf::from(GuestPath::new(path)))
-        .collect();
-    log_dbg!("directoryContentsAtPath {}: {:?}", path, paths);
-    let path_strings = paths
-        .iter()
-        .map(|name| ns_string::from_rust_string(env, name.as_str().to_string()))
+    log_dbg!("contentsOfDirectoryAtPath {:?}: {:?}", path, names);
+    let name_strings = names
+        .into_iter()
+        .map(|name| ns_string::from_rust_string(env, name))
         .collect();
-    let res = ns_array::from_vec(env, path_strings);
+    let res = ns_array::from_vec(env, name_strings);
     autorelease(env, res)
 }
 
-- (id)contentsOfDirectoryAtPath:(id)path /* NSString* */
-                          error:(MutPtr<id>)error { // NSError**
-    let contents: id = msg![env; this directoryContentsAtPath:path];
-    if contents == nil && !error.is_null() {
-        todo!(); // TODO: create an NSError if requested
-    }
-    contents
+- (id)contentsOfDirectoryAtURL:(id)url // NSURL*
+    includingPropertiesForKeys:(id)_keys // NSArray*
+                       options:(NSDirectoryEnumerationOptions)options
+                         error:(MutPtr<id>)error { // NSError**
+    let path = super::ns_url::to_rust_path(env, url);
+    let skip_hidden = options & NSDirectoryEnumerationSkipsHiddenFiles != 0;
+    let Some(names) = directory_contents(env, &path, skip_hidden) else {
+        let description = format!("No such directory {:?}", path);
+        set_error(env, error, NSCocoaErrorDomain, NSFileReadNoSuchFileError, description);
+        return nil;
+    };
+    let urls = names
+        .into_iter()
+        .map(|name| {
+            let is_dir = env.fs.is_dir(&path.join(&name));
+            let full_path = ns_string::from_rust_string(env, String::from(path.join(name)));
+            let url: id = msg_class![env; NSURL alloc];
+            let url: id = msg![env; url initFileURLWithPath:full_path isDirectory:is_dir];
+            release(env, full_path);
+            url
+        })
+        .collect();
+    let res = ns_array::from_vec(env, urls);
+    autorelease(env, res)
 }
 
 - (bool)copyItemAtPath:(id)src // NSString*
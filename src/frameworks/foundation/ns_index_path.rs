@@ -0,0 +1,157 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSIndexPath`.
+
+use super::{NSComparisonResult, NSOrderedAscending, NSOrderedDescending, NSOrderedSame};
+use super::{NSInteger, NSNotFound, NSUInteger};
+use crate::mem::{ConstPtr, MutPtr};
+use crate::objc::{
+    autorelease, id, msg, msg_class, objc_classes, retain, Class, ClassExports, HostObject,
+    NSZonePtr,
+};
+use crate::Environment;
+
+struct NSIndexPathHostObject {
+    indexes: Vec<NSUInteger>,
+}
+impl HostObject for NSIndexPathHostObject {}
+
+/// Shortcut for host code, e.g. UIKit's table view: get a new autoreleased
+/// `NSIndexPath*` for a row in a section.
+pub fn index_path_for_row(env: &mut Environment, section: NSUInteger, row: NSUInteger) -> id {
+    let new: id = msg_class![env; NSIndexPath alloc];
+    *env.objc.borrow_mut(new) = NSIndexPathHostObject {
+        indexes: vec![section, row],
+    };
+    autorelease(env, new)
+}
+
+/// Shortcut for host code: get the section and row of an `NSIndexPath*` as
+/// used by UIKit's table view.
+pub fn section_and_row(env: &Environment, index_path: id) -> (NSUInteger, NSUInteger) {
+    let indexes = &env.objc.borrow::<NSIndexPathHostObject>(index_path).indexes;
+    assert!(indexes.len() == 2);
+    (indexes[0], indexes[1])
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSIndexPath: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSIndexPathHostObject {
+        indexes: Vec::new(),
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
++ (id)indexPathWithIndex:(NSUInteger)index {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithIndex:index];
+    autorelease(env, new)
+}
+
++ (id)indexPathWithIndexes:(ConstPtr<NSUInteger>)indexes
+                    length:(NSUInteger)length {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithIndexes:indexes length:length];
+    autorelease(env, new)
+}
+
+// These come from a category in UIKit (UITableView).
++ (id)indexPathForRow:(NSInteger)row
+            inSection:(NSInteger)section {
+    index_path_for_row(env, section.try_into().unwrap(), row.try_into().unwrap())
+}
+- (NSInteger)section {
+    let (section, _row) = section_and_row(env, this);
+    section.try_into().unwrap()
+}
+- (NSInteger)row {
+    let (_section, row) = section_and_row(env, this);
+    row.try_into().unwrap()
+}
+
+- (id)initWithIndex:(NSUInteger)index {
+    env.objc.borrow_mut::<NSIndexPathHostObject>(this).indexes = vec![index];
+    this
+}
+
+- (id)initWithIndexes:(ConstPtr<NSUInteger>)indexes
+               length:(NSUInteger)length {
+    let indexes = (0..length).map(|i| env.mem.read(indexes + i)).collect();
+    env.objc.borrow_mut::<NSIndexPathHostObject>(this).indexes = indexes;
+    this
+}
+
+- (id)indexPathByAddingIndex:(NSUInteger)index {
+    let mut indexes = env.objc.borrow::<NSIndexPathHostObject>(this).indexes.clone();
+    indexes.push(index);
+    let class: Class = msg![env; this class];
+    let new: id = msg![env; class alloc];
+    env.objc.borrow_mut::<NSIndexPathHostObject>(new).indexes = indexes;
+    autorelease(env, new)
+}
+
+- (NSUInteger)length {
+    let indexes = &env.objc.borrow::<NSIndexPathHostObject>(this).indexes;
+    indexes.len().try_into().unwrap()
+}
+
+- (NSUInteger)indexAtPosition:(NSUInteger)position {
+    let indexes = &env.objc.borrow::<NSIndexPathHostObject>(this).indexes;
+    // Apple's documentation says NSNotFound is returned if the position is out
+    // of range.
+    indexes
+        .get(position as usize)
+        .copied()
+        .unwrap_or(NSNotFound as NSUInteger)
+}
+
+- (())getIndexes:(MutPtr<NSUInteger>)indexes {
+    let host_indexes = env.objc.borrow::<NSIndexPathHostObject>(this).indexes.clone();
+    for (i, index) in host_indexes.into_iter().enumerate() {
+        env.mem.write(indexes + i.try_into().unwrap(), index);
+    }
+}
+
+- (NSComparisonResult)compare:(id)other { // NSIndexPath*
+    let a = &env.objc.borrow::<NSIndexPathHostObject>(this).indexes;
+    let b = &env.objc.borrow::<NSIndexPathHostObject>(other).indexes;
+    match a.cmp(b) {
+        std::cmp::Ordering::Less => NSOrderedAscending,
+        std::cmp::Ordering::Equal => NSOrderedSame,
+        std::cmp::Ordering::Greater => NSOrderedDescending,
+    }
+}
+
+- (NSUInteger)hash {
+    super::hash_helper(&env.objc.borrow::<NSIndexPathHostObject>(this).indexes)
+}
+- (bool)isEqual:(id)other {
+    if this == other {
+        return true;
+    }
+    let class: Class = msg_class![env; NSIndexPath class];
+    if !msg![env; other isKindOfClass:class] {
+        return false;
+    }
+    let a = &env.objc.borrow::<NSIndexPathHostObject>(this).indexes;
+    let b = &env.objc.borrow::<NSIndexPathHostObject>(other).indexes;
+    a == b
+}
+
+// NSCopying implementation
+- (id)copyWithZone:(NSZonePtr)_zone {
+    // Index paths are immutable.
+    retain(env, this)
+}
+
+@end
+
+};
@@ -0,0 +1,365 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSKeyedArchiver` and serialization of its object graph format.
+//!
+//! See [super::ns_keyed_unarchiver] for the other direction. The output is a
+//! binary plist in the same format Apple's implementation produces.
+
+use super::ns_array::objects_of_array;
+use super::ns_dictionary::DictionaryHostObject;
+use super::ns_property_list_serialization::data_to_vec;
+use super::ns_string::to_rust_string;
+use super::ns_value::NSNumberHostObject;
+use super::{NSInteger, NSTimeInterval, NSUInteger};
+use crate::frameworks::core_graphics::{CGPoint, CGRect, CGSize};
+use crate::frameworks::uikit::ui_geometry::{
+    NSStringFromCGPoint, NSStringFromCGRect, NSStringFromCGSize,
+};
+use crate::mem::MutVoidPtr;
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, Class, ClassExports,
+    HostObject, NSZonePtr,
+};
+use crate::Environment;
+use plist::{Dictionary, Uid, Value};
+use std::collections::HashMap;
+
+/// The key used for the root object by the convenience methods.
+const ROOT_KEY: &str = "root";
+
+struct NSKeyedArchiverHostObject {
+    /// `NSMutableData*` that receives the archive when encoding is finished.
+    data: id,
+    /// The `$objects` array. The first item is always `$null`, which is what
+    /// uid 0 (i.e. `nil`) refers to.
+    objects: Vec<Value>,
+    /// Objects that have already been archived (or are being archived), and
+    /// their uids. The objects are retained so their ids can't be reused.
+    object_uids: HashMap<id, Uid>,
+    /// Class names that have already been archived, and their uids.
+    class_uids: HashMap<String, Uid>,
+    /// The `$top` dictionary, which top-level objects are encoded into.
+    top: Dictionary,
+    /// The dictionary for the object currently being encoded, if any.
+    current: Option<Dictionary>,
+    finished: bool,
+}
+impl HostObject for NSKeyedArchiverHostObject {}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSKeyedArchiver: NSCoder
+
++ (id)allocWithZone:(NSZonePtr)_zone { // struct _NSZone*
+    let archiver = Box::new(NSKeyedArchiverHostObject {
+        data: nil,
+        objects: vec![Value::String("$null".to_string())],
+        object_uids: HashMap::new(),
+        class_uids: HashMap::new(),
+        top: Dictionary::new(),
+        current: None,
+        finished: false,
+    });
+    env.objc.alloc_object(this, archiver, &mut env.mem)
+}
+
++ (id)archivedDataWithRootObject:(id)object {
+    let data: id = msg_class![env; NSMutableData new];
+    let archiver: id = msg![env; this alloc];
+    let archiver: id = msg![env; archiver initForWritingWithMutableData:data];
+    encode_object(env, archiver, object, ROOT_KEY);
+    () = msg![env; archiver finishEncoding];
+    release(env, archiver);
+    autorelease(env, data)
+}
+
++ (bool)archiveRootObject:(id)object
+                   toFile:(id)path { // NSString*
+    let data: id = msg![env; this archivedDataWithRootObject:object];
+    msg![env; data writeToFile:path atomically:true]
+}
+
+- (id)initForWritingWithMutableData:(id)data { // NSMutableData*
+    let host_obj = env.objc.borrow_mut::<NSKeyedArchiverHostObject>(this);
+    assert!(host_obj.data == nil);
+    host_obj.data = data;
+    retain(env, data);
+    this
+}
+
+- (())dealloc {
+    let host_obj = env.objc.borrow_mut::<NSKeyedArchiverHostObject>(this);
+    if !host_obj.finished {
+        log!("Warning: NSKeyedArchiver {:?} deallocated without finishEncoding", this);
+    }
+    let data = host_obj.data;
+    let object_uids = std::mem::take(&mut host_obj.object_uids);
+
+    for object in object_uids.into_keys() {
+        release(env, object);
+    }
+    release(env, data);
+
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (bool)allowsKeyedCoding {
+    true
+}
+
+- (())finishEncoding {
+    let host_obj = env.objc.borrow_mut::<NSKeyedArchiverHostObject>(this);
+    assert!(!host_obj.finished && host_obj.current.is_none());
+    host_obj.finished = true;
+
+    let mut plist = Dictionary::new();
+    plist.insert("$archiver".to_string(), Value::String("NSKeyedArchiver".to_string()));
+    plist.insert(
+        "$objects".to_string(),
+        Value::Array(std::mem::take(&mut host_obj.objects))
+    );
+    plist.insert("$top".to_string(), Value::Dictionary(std::mem::take(&mut host_obj.top)));
+    plist.insert("$version".to_string(), Value::Integer(100000.into()));
+    let data = host_obj.data;
+
+    let mut bytes = Vec::new();
+    Value::Dictionary(plist).to_writer_binary(&mut bytes).unwrap();
+
+    // Append the archive to the data object.
+    let old_length: NSUInteger = msg![env; data length];
+    let length: NSUInteger = bytes.len().try_into().unwrap();
+    () = msg![env; data increaseLengthBy:length];
+    let data_bytes: MutVoidPtr = msg![env; data mutableBytes];
+    env.mem
+        .bytes_at_mut(data_bytes.cast::<u8>() + old_length, length)
+        .copy_from_slice(&bytes);
+}
+
+// These methods are called by objects in their encodeWithCoder: method, or by
+// the code that initiates the archival to provide top-level objects. They are
+// all from the NSCoder abstract class.
+
+- (())encodeObject:(id)object
+            forKey:(id)key { // NSString*
+    let key = to_rust_string(env, key).into_owned();
+    encode_object(env, this, object, &key);
+}
+
+- (())encodeConditionalObject:(id)object
+                       forKey:(id)key { // NSString*
+    // TODO: Only encode the object if it is encoded unconditionally elsewhere.
+    let key = to_rust_string(env, key).into_owned();
+    encode_object(env, this, object, &key);
+}
+
+- (())encodeBool:(bool)value
+          forKey:(id)key { // NSString*
+    encode_value(env, this, key, Value::Boolean(value));
+}
+
+- (())encodeDouble:(f64)value
+            forKey:(id)key { // NSString*
+    encode_value(env, this, key, Value::Real(value));
+}
+
+- (())encodeFloat:(f32)value
+           forKey:(id)key { // NSString*
+    encode_value(env, this, key, Value::Real(value.into()));
+}
+
+- (())encodeInteger:(NSInteger)value
+             forKey:(id)key { // NSString*
+    encode_value(env, this, key, Value::Integer(value.into()));
+}
+
+- (())encodeInt:(i32)value
+         forKey:(id)key { // NSString*
+    encode_value(env, this, key, Value::Integer(value.into()));
+}
+
+- (())encodeInt32:(i32)value
+           forKey:(id)key { // NSString*
+    encode_value(env, this, key, Value::Integer(value.into()));
+}
+
+- (())encodeInt64:(i64)value
+           forKey:(id)key { // NSString*
+    encode_value(env, this, key, Value::Integer(value.into()));
+}
+
+// These come from a category in UIKit's UIGeometry.h
+- (())encodeCGPoint:(CGPoint)point
+             forKey:(id)key { // NSString*
+    let string = NSStringFromCGPoint(env, point);
+    msg![env; this encodeObject:string forKey:key]
+}
+- (())encodeCGSize:(CGSize)size
+            forKey:(id)key { // NSString*
+    let string = NSStringFromCGSize(env, size);
+    msg![env; this encodeObject:string forKey:key]
+}
+- (())encodeCGRect:(CGRect)rect
+            forKey:(id)key { // NSString*
+    let string = NSStringFromCGRect(env, rect);
+    msg![env; this encodeObject:string forKey:key]
+}
+
+@end
+
+};
+
+fn borrow_host_obj(env: &mut Environment, archiver: id) -> &mut NSKeyedArchiverHostObject {
+    env.objc.borrow_mut(archiver)
+}
+
+/// Put a value in the scope of the object currently being encoded.
+fn encode_value_for_rust_key(env: &mut Environment, archiver: id, key: &str, value: Value) {
+    let host_obj = borrow_host_obj(env, archiver);
+    assert!(!host_obj.finished);
+    host_obj
+        .current
+        .as_mut()
+        .unwrap_or(&mut host_obj.top)
+        .insert(key.to_string(), value);
+}
+
+fn encode_value(env: &mut Environment, archiver: id, key: id, value: Value) {
+    let key = to_rust_string(env, key).into_owned();
+    encode_value_for_rust_key(env, archiver, &key, value);
+}
+
+fn encode_object(env: &mut Environment, archiver: id, object: id, key: &str) {
+    let uid = archive_object(env, archiver, object);
+    encode_value_for_rust_key(env, archiver, key, Value::Uid(uid));
+}
+
+/// Get the uid for the description of a class, archiving it if necessary.
+///
+/// Our private classes aren't included, so that the archive can be read by
+/// Apple's implementation, e.g. `_touchHLE_NSMutableArray` is archived as
+/// `NSMutableArray`.
+fn archive_class(env: &mut Environment, archiver: id, class: Class) -> Uid {
+    let mut class_names = Vec::new();
+    let mut class = class;
+    while class != nil {
+        let name = env.objc.get_class_name(class);
+        if !name.starts_with("_touchHLE_") {
+            class_names.push(name.to_string());
+        }
+        class = env.objc.get_superclass(class);
+    }
+
+    let host_obj = borrow_host_obj(env, archiver);
+    if let Some(&uid) = host_obj.class_uids.get(&class_names[0]) {
+        return uid;
+    }
+    let uid = Uid::new(host_obj.objects.len().try_into().unwrap());
+    host_obj.class_uids.insert(class_names[0].clone(), uid);
+    let mut class_dict = Dictionary::new();
+    class_dict.insert(
+        "$classname".to_string(),
+        Value::String(class_names[0].clone()),
+    );
+    class_dict.insert(
+        "$classes".to_string(),
+        Value::Array(class_names.into_iter().map(Value::String).collect()),
+    );
+    host_obj.objects.push(Value::Dictionary(class_dict));
+    uid
+}
+
+/// The core of the implementation: archive an object and get its uid.
+///
+/// This is recursive in practice: the `encodeWithCoder:` messages sent by this
+/// function will be received by objects which will then send
+/// `encodeXXX:forKey:` messages back to the archiver, which will then call
+/// this function (and so on). Objects are given their uid before they are
+/// encoded, so cycles in the object graph are preserved.
+fn archive_object(env: &mut Environment, archiver: id, object: id) -> Uid {
+    if object == nil {
+        return Uid::new(0);
+    }
+    if let Some(&uid) = borrow_host_obj(env, archiver).object_uids.get(&object) {
+        return uid;
+    }
+
+    let string_class: Class = msg_class![env; NSString class];
+    let number_class: Class = msg_class![env; NSNumber class];
+    let data_class: Class = msg_class![env; NSData class];
+    let date_class: Class = msg_class![env; NSDate class];
+    let array_class: Class = msg_class![env; NSArray class];
+    let dictionary_class: Class = msg_class![env; NSDictionary class];
+
+    // Strings, numbers and data are stored directly as plist values.
+    let simple_value = if msg![env; object isKindOfClass:string_class] {
+        Some(Value::String(to_rust_string(env, object).into_owned()))
+    } else if msg![env; object isKindOfClass:number_class] {
+        Some(match *env.objc.borrow(object) {
+            NSNumberHostObject::Bool(value) => Value::Boolean(value),
+            NSNumberHostObject::UnsignedLongLong(value) => Value::Integer(value.into()),
+            NSNumberHostObject::LongLong(value) => Value::Integer(value.into()),
+            NSNumberHostObject::Float(value) => Value::Real(value.into()),
+            NSNumberHostObject::Double(value) => Value::Real(value),
+        })
+    } else if msg![env; object isKindOfClass:data_class] {
+        Some(Value::Data(data_to_vec(env, object)))
+    } else {
+        None
+    };
+
+    retain(env, object);
+    let host_obj = borrow_host_obj(env, archiver);
+    let uid = Uid::new(host_obj.objects.len().try_into().unwrap());
+    host_obj.object_uids.insert(object, uid);
+    if let Some(value) = simple_value {
+        host_obj.objects.push(value);
+        return uid;
+    }
+    // Placeholder until the object has been encoded.
+    host_obj.objects.push(Value::Boolean(false));
+    let old_current = host_obj.current.replace(Dictionary::new());
+
+    // Our collection and date classes don't implement NSCoding, so they are
+    // encoded here, in the same way Apple's implementation encodes them.
+    if msg![env; object isKindOfClass:array_class] {
+        let objects = objects_of_array(env, object);
+        let uids = objects
+            .into_iter()
+            .map(|item| Value::Uid(archive_object(env, archiver, item)))
+            .collect();
+        encode_value_for_rust_key(env, archiver, "NS.objects", Value::Array(uids));
+    } else if msg![env; object isKindOfClass:dictionary_class] {
+        let pairs: Vec<(id, id)> = env
+            .objc
+            .borrow::<DictionaryHostObject>(object)
+            .iter_keys_and_values()
+            .collect();
+        let mut keys = Vec::with_capacity(pairs.len());
+        let mut values = Vec::with_capacity(pairs.len());
+        for (key, value) in pairs {
+            keys.push(Value::Uid(archive_object(env, archiver, key)));
+            values.push(Value::Uid(archive_object(env, archiver, value)));
+        }
+        encode_value_for_rust_key(env, archiver, "NS.keys", Value::Array(keys));
+        encode_value_for_rust_key(env, archiver, "NS.objects", Value::Array(values));
+    } else if msg![env; object isKindOfClass:date_class] {
+        let time_interval: NSTimeInterval = msg![env; object timeIntervalSinceReferenceDate];
+        encode_value_for_rust_key(env, archiver, "NS.time", Value::Real(time_interval));
+    } else {
+        () = msg![env; object encodeWithCoder:archiver];
+    }
+
+    let class: Class = msg![env; object class];
+    let class_uid = archive_class(env, archiver, class);
+
+    let host_obj = borrow_host_obj(env, archiver);
+    let mut dict = std::mem::replace(&mut host_obj.current, old_current).unwrap();
+    dict.insert("$class".to_string(), Value::Uid(class_uid));
+    host_obj.objects[uid.get() as usize] = Value::Dictionary(dict);
+    uid
+}
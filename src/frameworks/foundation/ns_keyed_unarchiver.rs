@@ -5,20 +5,24 @@
  */
 //! `NSKeyedUnarchiver` and deserialization of its object graph format.
 //!
+//! See [super::ns_keyed_archiver] for the other direction.
+//!
 //! Resources:
 //! - You can get a good intuitive grasp of how the format works just by staring
 //!   at a pretty-print of a simple nib file from something that can parse
 //!   plists, e.g. `plutil -p` or `println!("{:#?}", plist::Value::...);`.
 //! - Apple's [Archives and Serializations Programming Guide](https://developer.apple.com/library/archive/documentation/Cocoa/Conceptual/Archiving/Articles/archives.html)
 
-use super::ns_string::{from_rust_string, to_rust_string};
+use super::ns_property_list_serialization::{data_to_vec, deserialize_plist};
+use super::ns_string::{get_static_str, to_rust_string};
 use crate::frameworks::core_graphics::{CGPoint, CGRect, CGSize};
 use crate::frameworks::foundation::NSInteger;
 use crate::frameworks::uikit::ui_geometry::{
     CGPointFromString, CGRectFromString, CGSizeFromString,
 };
 use crate::objc::{
-    autorelease, id, msg, nil, objc_classes, release, retain, ClassExports, HostObject, NSZonePtr,
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, ClassExports, HostObject,
+    NSZonePtr,
 };
 use crate::Environment;
 use plist::{Dictionary, Uid, Value};
@@ -47,8 +51,28 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.objc.alloc_object(this, unarchiver, &mut env.mem)
 }
 
-// TODO: real init methods. This is currently only initialized by the shortcut
-// function below.
++ (id)unarchiveObjectWithData:(id)data { // NSData*
+    let unarchiver: id = msg![env; this alloc];
+    let unarchiver: id = msg![env; unarchiver initForReadingWithData:data];
+    let key = get_static_str(env, "root");
+    let object: id = msg![env; unarchiver decodeObjectForKey:key];
+    release(env, unarchiver);
+    object
+}
+
++ (id)unarchiveObjectWithFile:(id)path { // NSString*
+    let data: id = msg_class![env; NSData dataWithContentsOfFile:path];
+    if data == nil {
+        return nil;
+    }
+    msg![env; this unarchiveObjectWithData:data]
+}
+
+- (id)initForReadingWithData:(id)data { // NSData*
+    let bytes = data_to_vec(env, data);
+    init_for_reading_with_data(env, this, &bytes);
+    this
+}
 
 - (())dealloc {
     let host_obj = borrow_host_obj(env, this);
@@ -134,6 +158,18 @@ pub const CLASSES: ClassExports = objc_classes! {
     autorelease(env, object)
 }
 
+- (bool)containsValueForKey:(id)key { // NSString*
+    get_value_to_decode_for_key(env, this, key).is_some()
+}
+
+- (bool)allowsKeyedCoding {
+    true
+}
+
+- (())finishDecoding {
+    // Nothing to do.
+}
+
 // TODO: add more decode methods
 
 // These come from a category in UIKit's UIGeometry.h
@@ -203,6 +239,11 @@ pub fn init_for_reading_with_data(env: &mut Environment, unarchiver: id, data: &
 /// The object returned is retained only by the archiver. Remember to retain and
 /// possibly autorelease it as appropriate.
 fn unarchive_key(env: &mut Environment, unarchiver: id, key: Uid) -> id {
+    // Uid 0 is always "$null".
+    if key.get() == 0 {
+        return nil;
+    }
+
     let host_obj = borrow_host_obj(env, unarchiver);
     if let Some(existing) = host_obj.already_unarchived[key.get() as usize] {
         return existing;
@@ -237,21 +278,38 @@ fn unarchive_key(env: &mut Environment, unarchiver: id, key: Uid) -> id {
                 host_obj.already_unarchived[class_key.get() as usize] = Some(class);
             };
 
+            let new_object: id = msg![env; class alloc];
+
+            // The object is recorded before it is initialized, so that any
+            // references back to it (cycles in the object graph) resolve to
+            // the same object.
             let host_obj = borrow_host_obj(env, unarchiver); // reborrow
+            host_obj.already_unarchived[key.get() as usize] = Some(new_object);
             let old_current_key = host_obj.current_key;
             host_obj.current_key = Some(key);
 
-            let new_object: id = msg![env; class alloc];
-            let new_object: id = msg![env; new_object initWithCoder:unarchiver];
+            let initialized_object: id = msg![env; new_object initWithCoder:unarchiver];
 
             let host_obj = borrow_host_obj(env, unarchiver); // reborrow
             host_obj.current_key = old_current_key;
 
-            new_object
+            if initialized_object != new_object {
+                log_dbg!(
+                    "initWithCoder: on {:?} returned a different object {:?}",
+                    new_object,
+                    initialized_object
+                );
+            }
+            initialized_object
         }
-        Value::String(s) => {
-            let s = s.to_string();
-            from_rust_string(env, s)
+        // Strings, numbers and data are stored directly.
+        Value::String(_)
+        | Value::Integer(_)
+        | Value::Real(_)
+        | Value::Boolean(_)
+        | Value::Data(_) => {
+            let item = item.clone();
+            deserialize_plist(env, &item)
         }
         _ => unimplemented!("Unarchive: {:#?}", item),
     };
@@ -285,3 +343,34 @@ pub fn decode_current_array(env: &mut Environment, unarchiver: id) -> Vec<id> {
         })
         .collect()
 }
+
+/// Shortcut for use by `[_touchHLE_NSDictionary initWithCoder:]`.
+///
+/// The keys and objects are retained only by the unarchiver.
+pub fn decode_current_dictionary(env: &mut Environment, unarchiver: id) -> Vec<(id, id)> {
+    let (keys, objects): (Vec<Uid>, Vec<Uid>) = {
+        let host_obj = borrow_host_obj(env, unarchiver);
+        let objects = host_obj.plist["$objects"].as_array().unwrap();
+        let item = &objects[host_obj.current_key.unwrap().get() as usize];
+        let item = item.as_dictionary().unwrap();
+        let get_uids = |name| -> Vec<Uid> {
+            item[name]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|value| value.as_uid().copied().unwrap())
+                .collect()
+        };
+        (get_uids("NS.keys"), get_uids("NS.objects"))
+    };
+    assert!(keys.len() == objects.len());
+
+    keys.into_iter()
+        .zip(objects)
+        .map(|(key, object)| {
+            let key = unarchive_key(env, unarchiver, key);
+            let object = unarchive_key(env, unarchiver, object);
+            (key, object)
+        })
+        .collect()
+}
@@ -5,19 +5,48 @@
  */
 //! `NSLocale`.
 
-use super::{ns_array, ns_string};
+use super::{ns_array, ns_string, NSUInteger};
 use crate::dyld::{ConstantExports, HostConstant};
-use crate::objc::{id, objc_classes, ClassExports, HostObject};
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, retain, ClassExports, HostObject, NSZonePtr,
+};
 use crate::options::Options;
 use crate::Environment;
 use std::ffi::CStr;
 
-const NSLocaleCountryCode: &str = "NSLocaleCountryCode";
+pub const NSLocaleIdentifier: &str = "NSLocaleIdentifier";
+pub const NSLocaleLanguageCode: &str = "NSLocaleLanguageCode";
+pub const NSLocaleCountryCode: &str = "NSLocaleCountryCode";
+pub const NSLocaleDecimalSeparator: &str = "NSLocaleDecimalSeparator";
+pub const NSLocaleGroupingSeparator: &str = "NSLocaleGroupingSeparator";
+pub const NSLocaleCurrencySymbol: &str = "NSLocaleCurrencySymbol";
 
-pub const CONSTANTS: ConstantExports = &[(
-    "_NSLocaleCountryCode",
-    HostConstant::NSString(NSLocaleCountryCode),
-)];
+pub const CONSTANTS: ConstantExports = &[
+    (
+        "_NSLocaleIdentifier",
+        HostConstant::NSString(NSLocaleIdentifier),
+    ),
+    (
+        "_NSLocaleLanguageCode",
+        HostConstant::NSString(NSLocaleLanguageCode),
+    ),
+    (
+        "_NSLocaleCountryCode",
+        HostConstant::NSString(NSLocaleCountryCode),
+    ),
+    (
+        "_NSLocaleDecimalSeparator",
+        HostConstant::NSString(NSLocaleDecimalSeparator),
+    ),
+    (
+        "_NSLocaleGroupingSeparator",
+        HostConstant::NSString(NSLocaleGroupingSeparator),
+    ),
+    (
+        "_NSLocaleCurrencySymbol",
+        HostConstant::NSString(NSLocaleCurrencySymbol),
+    ),
+];
 
 #[derive(Default)]
 pub struct State {
@@ -107,10 +136,118 @@ fn get_preferred_countries() -> Vec<String> {
     }
 }
 
+/// The parts of a locale's number formatting rules that touchHLE knows about.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct NumberConventions {
+    pub(super) decimal_separator: String,
+    pub(super) grouping_separator: String,
+    pub(super) currency_symbol: String,
+    /// Whether the currency symbol goes after the number (with a space).
+    pub(super) currency_symbol_after: bool,
+}
+
+/// Look up the number formatting rules for a country. This is a small table
+/// covering common App Store regions, not a full CLDR database.
+pub(super) fn conventions_for_country(country_code: &str) -> NumberConventions {
+    let (decimal_separator, grouping_separator) = match country_code {
+        "DE" | "AT" | "IT" | "ES" | "NL" | "BE" | "BR" | "DK" | "ID" | "TR" | "AR" => (",", "."),
+        // U+00A0 NO-BREAK SPACE
+        "FR" | "RU" | "PL" | "CZ" | "SE" | "NO" | "FI" | "PT" | "UA" => (",", "\u{A0}"),
+        "CH" => (".", "'"),
+        _ => (".", ","),
+    };
+    let (currency_symbol, currency_symbol_after) = match country_code {
+        "US" | "CA" | "AU" | "NZ" | "HK" | "SG" | "MX" => ("$", false),
+        "GB" => ("£", false),
+        "JP" => ("¥", false),
+        "CN" => ("￥", false),
+        "KR" => ("₩", false),
+        "IN" => ("₹", false),
+        "BR" => ("R$", false),
+        "CH" => ("CHF", false),
+        "IE" => ("€", false),
+        "DE" | "AT" | "FR" | "IT" | "ES" | "NL" | "BE" | "FI" | "PT" => ("€", true),
+        "SE" | "NO" | "DK" => ("kr", true),
+        // U+00A4 CURRENCY SIGN, used when the currency is unknown.
+        _ => ("¤", false),
+    };
+    NumberConventions {
+        decimal_separator: decimal_separator.to_string(),
+        grouping_separator: grouping_separator.to_string(),
+        currency_symbol: currency_symbol.to_string(),
+        currency_symbol_after,
+    }
+}
+
+/// Split a locale identifier like `en_US`, `en-US` or `zh-Hans_CN` into its
+/// language code and, if present, its country code.
+fn parse_locale_identifier(identifier: &str) -> (String, Option<String>) {
+    let mut parts = identifier.split(['_', '-']);
+    let language = parts.next().unwrap().to_ascii_lowercase();
+    // Scripts (four letters) and variants are ignored.
+    let country = parts
+        .find(|part| {
+            part.len() == 2 || (part.len() == 3 && part.bytes().all(|c| c.is_ascii_digit()))
+        })
+        .map(|part| part.to_ascii_uppercase());
+    (language, country)
+}
+
 struct NSLocaleHostObject {
-    country_code: id,
+    language_code: String,
+    country_code: Option<String>,
 }
 impl HostObject for NSLocaleHostObject {}
+impl NSLocaleHostObject {
+    fn identifier(&self) -> String {
+        match self.country_code {
+            Some(ref country_code) => format!("{}_{}", self.language_code, country_code),
+            None => self.language_code.clone(),
+        }
+    }
+}
+
+/// Borrow the host object of an `NSLocale*`, or of the current locale if it is
+/// `nil`. All of touchHLE's locale-dependent code should go through this, so
+/// that the current locale is the single source of truth.
+fn borrow_locale(env: &mut Environment, locale: id) -> &NSLocaleHostObject {
+    let locale = if locale == nil {
+        msg_class![env; NSLocale currentLocale]
+    } else {
+        locale
+    };
+    env.objc.borrow(locale)
+}
+
+/// Get the language code (e.g. `"en"`, without any script or region suffix)
+/// to use for localizing things according to an `NSLocale*` (or `nil` for the
+/// current locale).
+pub fn language_code_for_locale(env: &mut Environment, locale: id) -> String {
+    borrow_locale(env, locale).language_code.clone()
+}
+
+/// Get the country code (e.g. `"US"`) of an `NSLocale*` (or `nil` for the
+/// current locale), if it has one.
+pub fn country_code_for_locale(env: &mut Environment, locale: id) -> Option<String> {
+    borrow_locale(env, locale).country_code.clone()
+}
+
+/// Get the number formatting rules for an `NSLocale*` (or `nil` for the
+/// current locale).
+pub(super) fn number_conventions_for_locale(
+    env: &mut Environment,
+    locale: id,
+) -> NumberConventions {
+    let country_code = country_code_for_locale(env, locale);
+    conventions_for_country(country_code.as_deref().unwrap_or(""))
+}
+
+/// Get the language code of the current locale, for host code that needs to
+/// localize things itself. This is consistent with `+[NSLocale currentLocale]`
+/// and `+[NSLocale preferredLanguages]`.
+pub fn current_language_code(env: &mut Environment) -> String {
+    language_code_for_locale(env, nil)
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -134,36 +271,101 @@ pub const CLASSES: ClassExports = objc_classes! {
     }
 }
 
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSLocaleHostObject {
+        language_code: String::new(),
+        country_code: None,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
 + (id)currentLocale {
     if let Some(locale) = State::get(env).current_locale {
-        locale
-    } else {
-        let countries = get_preferred_countries();
-        let country_code = ns_string::from_rust_string(env, countries[0].clone());
-        let host_object = NSLocaleHostObject {
-            country_code
-        };
-        let new_locale = env.objc.alloc_object(
-            this,
-            Box::new(host_object),
-            &mut env.mem
-        );
-        State::get(env).current_locale = Some(new_locale);
-        new_locale
+        return locale;
     }
+
+    // The language comes from the user's most preferred language, so that the
+    // current locale agrees with preferredLanguages.
+    let languages: id = msg![env; this preferredLanguages];
+    let count: NSUInteger = msg![env; languages count];
+    let language_code = if count == 0 {
+        "en".to_string()
+    } else {
+        let language: id = msg![env; languages objectAtIndex:0u32];
+        let language = ns_string::to_rust_string(env, language);
+        parse_locale_identifier(&language).0
+    };
+    let countries = get_preferred_countries();
+    let host_object = NSLocaleHostObject {
+        language_code,
+        country_code: Some(countries[0].to_ascii_uppercase()),
+    };
+    log_dbg!("Current locale: {:?}", host_object.identifier());
+    let new_locale = env.objc.alloc_object(
+        this,
+        Box::new(host_object),
+        &mut env.mem
+    );
+    State::get(env).current_locale = Some(new_locale);
+    new_locale
+}
+
++ (id)autoupdatingCurrentLocale {
+    msg![env; this currentLocale]
+}
+
++ (id)localeWithLocaleIdentifier:(id)identifier { // NSString*
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithLocaleIdentifier:identifier];
+    autorelease(env, new)
+}
+
+- (id)initWithLocaleIdentifier:(id)identifier { // NSString*
+    let identifier = ns_string::to_rust_string(env, identifier);
+    let (language_code, country_code) = parse_locale_identifier(&identifier);
+    let host_object = env.objc.borrow_mut::<NSLocaleHostObject>(this);
+    host_object.language_code = language_code;
+    host_object.country_code = country_code;
+    this
 }
 
-// TODO: constructors, more accessors
+// NSCopying implementation
+- (id)copyWithZone:(NSZonePtr)_zone {
+    // Locales are immutable.
+    retain(env, this)
+}
+
+- (id)localeIdentifier {
+    let identifier = env.objc.borrow::<NSLocaleHostObject>(this).identifier();
+    let identifier = ns_string::from_rust_string(env, identifier);
+    autorelease(env, identifier)
+}
 
 - (id)objectForKey:(id)key {
     let key_str: &str = &ns_string::to_rust_string(env, key);
-    match key_str {
-        NSLocaleCountryCode => {
-            let &NSLocaleHostObject { country_code } = env.objc.borrow(this);
-            country_code
+    let value = match key_str {
+        NSLocaleIdentifier => Some(env.objc.borrow::<NSLocaleHostObject>(this).identifier()),
+        NSLocaleLanguageCode => Some(language_code_for_locale(env, this)),
+        NSLocaleCountryCode => country_code_for_locale(env, this),
+        NSLocaleDecimalSeparator => {
+            Some(number_conventions_for_locale(env, this).decimal_separator)
         },
-        _ => unimplemented!()
-    }
+        NSLocaleGroupingSeparator => {
+            Some(number_conventions_for_locale(env, this).grouping_separator)
+        },
+        NSLocaleCurrencySymbol => {
+            Some(number_conventions_for_locale(env, this).currency_symbol)
+        },
+        _ => {
+            log!("TODO: [(NSLocale*){:?} objectForKey:{:?}] => nil", this, key_str);
+            None
+        }
+    };
+    let Some(value) = value else {
+        return nil;
+    };
+    let value = ns_string::from_rust_string(env, value);
+    autorelease(env, value)
 }
 
 @end
@@ -28,7 +28,9 @@ struct Observer {
 }
 
 struct NSNotificationCenterHostObject {
-    observers: HashMap<Cow<'static, str>, Vec<Observer>>,
+    /// Observers by notification name. Observers registered with a `nil` name
+    /// receive all notifications and are under [None].
+    observers: HashMap<Option<Cow<'static, str>>, Vec<Observer>>,
 }
 impl HostObject for NSNotificationCenterHostObject {}
 
@@ -69,9 +71,8 @@ pub const CLASSES: ClassExports = objc_classes! {
          selector:(SEL)selector
              name:(NSNotificationName)name
            object:(id)object {
-    // TODO: handle case where name is nil
     // Usually a static string, so no real copy will happen
-    let name = ns_string::to_rust_string(env, name);
+    let name = (name != nil).then(|| ns_string::to_rust_string(env, name));
 
     log_dbg!(
         "[(NSNotificationCenter*){:?} addObserver:{:?} selector:{:?} name:{:?} object:{:?}",
@@ -93,14 +94,20 @@ pub const CLASSES: ClassExports = objc_classes! {
     });
 }
 
+- (())removeObserver:(id)observer {
+    msg![env; this removeObserver:observer name:nil object:nil]
+}
+
 - (())removeObserver:(id)observer
                 name:(NSNotificationName)name
               object:(id)object {
-    assert!(observer != nil); // TODO
+    if observer == nil {
+        return;
+    }
 
-    // TODO: handle case where name is nil
+    // A nil name or object means any name or object.
     // Usually a static string, so no real copy will happen
-    let name = ns_string::to_rust_string(env, name);
+    let name = (name != nil).then(|| ns_string::to_rust_string(env, name));
 
     log_dbg!(
         "[(NSNotificationCenter*){:?} removeObserver:{:?} name:{:?} object:{:?}",
@@ -111,20 +118,25 @@ pub const CLASSES: ClassExports = objc_classes! {
     );
 
     let host_obj = env.objc.borrow_mut::<NSNotificationCenterHostObject>(this);
-    let Some(observers) = host_obj.observers.get_mut(&name) else {
-        return;
-    };
 
     // TODO: is this the correct behaviour, can an observer be registered
     // several times?
     let mut removed_observers = Vec::new();
 
-    let mut i = 0;
-    while i < observers.len() {
-        if observers[i].observer == observer && (object == nil || object == observers[i].object) {
-            removed_observers.push(observers.swap_remove(i));
-        } else {
-            i += 1;
+    for (observers_name, observers) in host_obj.observers.iter_mut() {
+        if name.is_some() && name != *observers_name {
+            continue;
+        }
+        let mut i = 0;
+        while i < observers.len() {
+            if observers[i].observer == observer
+                && (object == nil || object == observers[i].object)
+            {
+                // Not swap_remove(), because observers are notified in order.
+                removed_observers.push(observers.remove(i));
+            } else {
+                i += 1;
+            }
         }
     }
 
@@ -149,10 +161,13 @@ pub const CLASSES: ClassExports = objc_classes! {
 
     log_dbg!("Notification is a {:?} posted by {:?}", name, notification_poster);
 
+    // Observers for this name and for all names are both notified. Copies are
+    // taken since observers may add or remove observers when notified.
     let host_obj = env.objc.borrow_mut::<NSNotificationCenterHostObject>(this);
-    let Some(observers) = host_obj.observers.get(&name).cloned() else {
-        return;
-    };
+    let mut observers = host_obj.observers.get(&Some(name)).cloned().unwrap_or_default();
+    if let Some(any_name_observers) = host_obj.observers.get(&None) {
+        observers.extend_from_slice(any_name_observers);
+    }
     for Observer { observer, selector, object } in observers {
         // The object argument is a filter for which notification sources the
         // observer is interested in.
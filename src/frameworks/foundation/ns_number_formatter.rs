@@ -0,0 +1,402 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSNumberFormatter`.
+//!
+//! Resources:
+//! - Apple's [Introduction to Data Formatting Programming Guide For Cocoa](https://developer.apple.com/library/archive/documentation/Cocoa/Conceptual/DataFormatting/DataFormatting.html)
+
+use super::ns_locale::{self, NumberConventions};
+use super::ns_string::{from_rust_string, to_rust_string};
+use super::NSUInteger;
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, ClassExports, HostObject,
+    NSZonePtr,
+};
+use crate::Environment;
+
+pub type NSNumberFormatterStyle = NSUInteger;
+pub const NSNumberFormatterNoStyle: NSNumberFormatterStyle = 0;
+pub const NSNumberFormatterDecimalStyle: NSNumberFormatterStyle = 1;
+pub const NSNumberFormatterCurrencyStyle: NSNumberFormatterStyle = 2;
+pub const NSNumberFormatterPercentStyle: NSNumberFormatterStyle = 3;
+pub const NSNumberFormatterScientificStyle: NSNumberFormatterStyle = 4;
+pub const NSNumberFormatterSpellOutStyle: NSNumberFormatterStyle = 5;
+
+/// Default (minimum, maximum) fraction digits and grouping for a style. These
+/// match what Cocoa uses for most locales.
+fn style_defaults(style: NSNumberFormatterStyle) -> (NSUInteger, NSUInteger, bool) {
+    match style {
+        NSNumberFormatterNoStyle => (0, 0, false),
+        NSNumberFormatterDecimalStyle => (0, 3, true),
+        NSNumberFormatterCurrencyStyle => (2, 2, true),
+        NSNumberFormatterPercentStyle => (0, 0, true),
+        // Unsupported styles are formatted like the decimal style, see
+        // setNumberStyle:.
+        _ => (0, 3, true),
+    }
+}
+
+/// Settings resolved from the formatter's properties and locale.
+struct FormatSettings {
+    style: NSNumberFormatterStyle,
+    conventions: NumberConventions,
+    uses_grouping_separator: bool,
+    minimum_fraction_digits: NSUInteger,
+    maximum_fraction_digits: NSUInteger,
+}
+
+fn format_number(value: f64, settings: &FormatSettings) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+
+    let value = if settings.style == NSNumberFormatterPercentStyle {
+        value * 100.0
+    } else {
+        value
+    };
+
+    let digits = if value.is_infinite() {
+        "∞".to_string()
+    } else {
+        let max = settings.maximum_fraction_digits as usize;
+        let min = (settings.minimum_fraction_digits as usize).min(max);
+        let rounded = format!("{:.*}", max, value.abs());
+        let (integer_part, fraction_part) = match rounded.split_once('.') {
+            Some((integer_part, fraction_part)) => (integer_part, fraction_part),
+            None => (rounded.as_str(), ""),
+        };
+        // Trailing zeros can be dropped down to the minimum.
+        let mut fraction_part = fraction_part.trim_end_matches('0');
+        if fraction_part.len() < min {
+            fraction_part = &rounded[integer_part.len() + 1..][..min];
+        }
+
+        let mut digits = String::new();
+        let integer_len = integer_part.len();
+        for (i, c) in integer_part.chars().enumerate() {
+            if settings.uses_grouping_separator && i != 0 && (integer_len - i) % 3 == 0 {
+                digits.push_str(&settings.conventions.grouping_separator);
+            }
+            digits.push(c);
+        }
+        if !fraction_part.is_empty() {
+            digits.push_str(&settings.conventions.decimal_separator);
+            digits.push_str(fraction_part);
+        }
+        digits
+    };
+
+    // Negative zero after rounding is displayed without a sign.
+    let is_nonzero = value.is_infinite() || digits.chars().any(|c| ('1'..='9').contains(&c));
+    let sign = if value < 0.0 && is_nonzero { "-" } else { "" };
+    let symbol = &settings.conventions.currency_symbol;
+    match settings.style {
+        NSNumberFormatterCurrencyStyle if settings.conventions.currency_symbol_after => {
+            format!("{}{}\u{A0}{}", sign, digits, symbol)
+        }
+        NSNumberFormatterCurrencyStyle => format!("{}{}{}", sign, symbol, digits),
+        NSNumberFormatterPercentStyle => format!("{}{}%", sign, digits),
+        _ => format!("{}{}", sign, digits),
+    }
+}
+
+/// Parse a number formatted according to `settings`. Returns the value and
+/// whether it should be treated as an integer.
+fn parse_number(string: &str, settings: &FormatSettings) -> Option<(f64, bool)> {
+    let conventions = &settings.conventions;
+    let mut string = string.trim().to_string();
+    if settings.style == NSNumberFormatterCurrencyStyle {
+        string = string.replace(&conventions.currency_symbol, "");
+    } else if settings.style == NSNumberFormatterPercentStyle {
+        string = string.strip_suffix('%')?.to_string();
+    }
+    if settings.uses_grouping_separator {
+        string = string.replace(&conventions.grouping_separator, "");
+    }
+    let string = string.trim();
+
+    let (negative, string) = match string.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, string),
+    };
+    let (integer_part, fraction_part) = match string.split_once(&conventions.decimal_separator) {
+        Some((integer_part, fraction_part)) => (integer_part, Some(fraction_part)),
+        None => (string, None),
+    };
+    let is_valid_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if integer_part.is_empty() && fraction_part.map_or(true, |f| f.is_empty()) {
+        return None;
+    }
+    if !is_valid_digits(integer_part) || !fraction_part.map_or(true, is_valid_digits) {
+        return None;
+    }
+
+    let mut value: f64 = match fraction_part {
+        Some(fraction_part) => format!("0{}.{}0", integer_part, fraction_part)
+            .parse()
+            .ok()?,
+        None => format!("0{}", integer_part).parse().ok()?,
+    };
+    if negative {
+        value = -value;
+    }
+    if settings.style == NSNumberFormatterPercentStyle {
+        value /= 100.0;
+    }
+    Some((
+        value,
+        value.fract() == 0.0 && value.abs() < (1u64 << 53) as f64,
+    ))
+}
+
+struct NSNumberFormatterHostObject {
+    number_style: NSNumberFormatterStyle,
+    /// `NSLocale*`, or `nil` for the current locale.
+    locale: id,
+    uses_grouping_separator: Option<bool>,
+    minimum_fraction_digits: Option<NSUInteger>,
+    maximum_fraction_digits: Option<NSUInteger>,
+    grouping_separator: Option<String>,
+    decimal_separator: Option<String>,
+    currency_symbol: Option<String>,
+}
+impl HostObject for NSNumberFormatterHostObject {}
+
+fn format_settings(env: &mut Environment, formatter: id) -> FormatSettings {
+    let locale = env
+        .objc
+        .borrow::<NSNumberFormatterHostObject>(formatter)
+        .locale;
+    let mut conventions = ns_locale::number_conventions_for_locale(env, locale);
+
+    let host_object = env.objc.borrow::<NSNumberFormatterHostObject>(formatter);
+    if let Some(ref separator) = host_object.grouping_separator {
+        conventions.grouping_separator = separator.clone();
+    }
+    if let Some(ref separator) = host_object.decimal_separator {
+        conventions.decimal_separator = separator.clone();
+    }
+    if let Some(ref symbol) = host_object.currency_symbol {
+        conventions.currency_symbol = symbol.clone();
+    }
+    let (default_min, default_max, default_grouping) = style_defaults(host_object.number_style);
+    FormatSettings {
+        style: host_object.number_style,
+        conventions,
+        uses_grouping_separator: host_object
+            .uses_grouping_separator
+            .unwrap_or(default_grouping),
+        minimum_fraction_digits: host_object.minimum_fraction_digits.unwrap_or(default_min),
+        maximum_fraction_digits: host_object.maximum_fraction_digits.unwrap_or(default_max),
+    }
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSNumberFormatter: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSNumberFormatterHostObject {
+        number_style: NSNumberFormatterNoStyle,
+        locale: nil,
+        uses_grouping_separator: None,
+        minimum_fraction_digits: None,
+        maximum_fraction_digits: None,
+        grouping_separator: None,
+        decimal_separator: None,
+        currency_symbol: None,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (())dealloc {
+    let locale = env.objc.borrow::<NSNumberFormatterHostObject>(this).locale;
+    release(env, locale);
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (NSNumberFormatterStyle)numberStyle {
+    env.objc.borrow::<NSNumberFormatterHostObject>(this).number_style
+}
+- (())setNumberStyle:(NSNumberFormatterStyle)style {
+    if matches!(style, NSNumberFormatterScientificStyle | NSNumberFormatterSpellOutStyle) {
+        log!("TODO: NSNumberFormatterStyle {}, using the decimal style instead", style);
+    } else if style > NSNumberFormatterSpellOutStyle {
+        log!(
+            "Warning: unknown NSNumberFormatterStyle {}, using the decimal style instead",
+            style
+        );
+    }
+    let host_object = env.objc.borrow_mut::<NSNumberFormatterHostObject>(this);
+    host_object.number_style = style;
+    // Changing the style resets the style-dependent properties.
+    host_object.uses_grouping_separator = None;
+    host_object.minimum_fraction_digits = None;
+    host_object.maximum_fraction_digits = None;
+}
+
+- (id)locale {
+    let locale = env.objc.borrow::<NSNumberFormatterHostObject>(this).locale;
+    if locale == nil {
+        msg_class![env; NSLocale currentLocale]
+    } else {
+        locale
+    }
+}
+- (())setLocale:(id)locale { // NSLocale*
+    retain(env, locale);
+    let host_object = env.objc.borrow_mut::<NSNumberFormatterHostObject>(this);
+    let old_locale = std::mem::replace(&mut host_object.locale, locale);
+    release(env, old_locale);
+}
+
+- (bool)usesGroupingSeparator {
+    format_settings(env, this).uses_grouping_separator
+}
+- (())setUsesGroupingSeparator:(bool)uses_grouping_separator {
+    env.objc.borrow_mut::<NSNumberFormatterHostObject>(this).uses_grouping_separator =
+        Some(uses_grouping_separator);
+}
+
+- (NSUInteger)minimumFractionDigits {
+    format_settings(env, this).minimum_fraction_digits
+}
+- (())setMinimumFractionDigits:(NSUInteger)digits {
+    let host_object = env.objc.borrow_mut::<NSNumberFormatterHostObject>(this);
+    host_object.minimum_fraction_digits = Some(digits);
+    // The maximum can't be less than the minimum.
+    if host_object.maximum_fraction_digits.map_or(false, |max| max < digits) {
+        host_object.maximum_fraction_digits = Some(digits);
+    }
+}
+
+- (NSUInteger)maximumFractionDigits {
+    format_settings(env, this).maximum_fraction_digits
+}
+- (())setMaximumFractionDigits:(NSUInteger)digits {
+    let host_object = env.objc.borrow_mut::<NSNumberFormatterHostObject>(this);
+    host_object.maximum_fraction_digits = Some(digits);
+    // The minimum can't be more than the maximum.
+    if host_object.minimum_fraction_digits.map_or(false, |min| min > digits) {
+        host_object.minimum_fraction_digits = Some(digits);
+    }
+}
+
+- (id)groupingSeparator {
+    let separator = format_settings(env, this).conventions.grouping_separator;
+    let separator = from_rust_string(env, separator);
+    autorelease(env, separator)
+}
+- (())setGroupingSeparator:(id)separator { // NSString*
+    let separator = to_rust_string(env, separator).into_owned();
+    env.objc.borrow_mut::<NSNumberFormatterHostObject>(this).grouping_separator = Some(separator);
+}
+
+- (id)decimalSeparator {
+    let separator = format_settings(env, this).conventions.decimal_separator;
+    let separator = from_rust_string(env, separator);
+    autorelease(env, separator)
+}
+- (())setDecimalSeparator:(id)separator { // NSString*
+    let separator = to_rust_string(env, separator).into_owned();
+    env.objc.borrow_mut::<NSNumberFormatterHostObject>(this).decimal_separator = Some(separator);
+}
+
+- (id)currencySymbol {
+    let symbol = format_settings(env, this).conventions.currency_symbol;
+    let symbol = from_rust_string(env, symbol);
+    autorelease(env, symbol)
+}
+- (())setCurrencySymbol:(id)symbol { // NSString*
+    let symbol = to_rust_string(env, symbol).into_owned();
+    env.objc.borrow_mut::<NSNumberFormatterHostObject>(this).currency_symbol = Some(symbol);
+}
+
+- (id)stringFromNumber:(id)number { // NSNumber*
+    if number == nil {
+        return nil;
+    }
+    let value: f64 = msg![env; number doubleValue];
+    let settings = format_settings(env, this);
+    let string = format_number(value, &settings);
+    let string = from_rust_string(env, string);
+    autorelease(env, string)
+}
+
+- (id)numberFromString:(id)string { // NSString*
+    if string == nil {
+        return nil;
+    }
+    let settings = format_settings(env, this);
+    let string = to_rust_string(env, string);
+    match parse_number(&string, &settings) {
+        Some((value, true)) => msg_class![env; NSNumber numberWithLongLong:(value as i64)],
+        Some((value, false)) => msg_class![env; NSNumber numberWithDouble:value],
+        None => nil,
+    }
+}
+
+@end
+
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(style: NSNumberFormatterStyle, country_code: &str) -> FormatSettings {
+        let (minimum_fraction_digits, maximum_fraction_digits, uses_grouping_separator) =
+            style_defaults(style);
+        FormatSettings {
+            style,
+            conventions: ns_locale::conventions_for_country(country_code),
+            uses_grouping_separator,
+            minimum_fraction_digits,
+            maximum_fraction_digits,
+        }
+    }
+
+    #[test]
+    fn test_format_number() {
+        let decimal = settings(NSNumberFormatterDecimalStyle, "US");
+        assert_eq!(format_number(0.0, &decimal), "0");
+        assert_eq!(format_number(123.0, &decimal), "123");
+        assert_eq!(format_number(1234.0, &decimal), "1,234");
+        assert_eq!(format_number(1234567.0, &decimal), "1,234,567");
+        assert_eq!(format_number(-1234567.5, &decimal), "-1,234,567.5");
+        assert_eq!(format_number(0.12345, &decimal), "0.123");
+
+        let currency = settings(NSNumberFormatterCurrencyStyle, "US");
+        assert_eq!(format_number(1234.5, &currency), "$1,234.50");
+        assert_eq!(format_number(-3.0, &currency), "-$3.00");
+
+        let currency = settings(NSNumberFormatterCurrencyStyle, "DE");
+        assert_eq!(format_number(1234.5, &currency), "1.234,50\u{A0}€");
+
+        let percent = settings(NSNumberFormatterPercentStyle, "US");
+        assert_eq!(format_number(0.25, &percent), "25%");
+
+        let none = settings(NSNumberFormatterNoStyle, "US");
+        assert_eq!(format_number(1234.4, &none), "1234");
+    }
+
+    #[test]
+    fn test_parse_number() {
+        let decimal = settings(NSNumberFormatterDecimalStyle, "US");
+        assert_eq!(parse_number("1,234,567", &decimal), Some((1234567.0, true)));
+        assert_eq!(parse_number("-12.5", &decimal), Some((-12.5, false)));
+        assert_eq!(parse_number(".5", &decimal), Some((0.5, false)));
+        assert_eq!(parse_number("abc", &decimal), None);
+        assert_eq!(parse_number("", &decimal), None);
+        assert_eq!(parse_number("1.2.3", &decimal), None);
+
+        let currency = settings(NSNumberFormatterCurrencyStyle, "DE");
+        assert_eq!(parse_number("1.234,50 €", &currency), Some((1234.5, false)));
+    }
+}
@@ -0,0 +1,363 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSOperation`, its subclasses, and `NSOperationQueue`.
+//!
+//! Operations are run on worker threads created with `NSThread`. Waiting for
+//! operations is done by polling, since there's no host-side condition
+//! variable primitive yet.
+
+use super::ns_array;
+use super::NSInteger;
+use crate::abi::CallFromHost;
+use crate::objc::{
+    autorelease, copy_block, get_block_invoke, id, msg, msg_class, msg_send, nil, objc_classes,
+    release, release_block, retain, ClassExports, HostObject, NSZonePtr, SEL,
+};
+use crate::Environment;
+use std::time::Duration;
+
+pub const NSOperationQueueDefaultMaxConcurrentOperationCount: NSInteger = -1;
+
+/// How long to sleep for between checks while waiting for operations.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+enum OperationWork {
+    /// Plain `NSOperation` (or a guest subclass overriding `main`).
+    None,
+    /// `NSInvocationOperation`
+    Invocation {
+        target: id,
+        selector: SEL,
+        object: id,
+    },
+    /// `NSBlockOperation`. The blocks are copies owned by the operation.
+    Blocks(Vec<id>),
+}
+
+struct OperationHostObject {
+    work: OperationWork,
+    /// `NSOperation*`s that must finish before this one is ready. They are
+    /// retained.
+    dependencies: Vec<id>,
+    executing: bool,
+    finished: bool,
+    cancelled: bool,
+}
+impl HostObject for OperationHostObject {}
+
+struct OperationQueueHostObject {
+    /// Operations that haven't finished yet. They are retained.
+    operations: Vec<id>,
+    max_concurrent_operation_count: NSInteger,
+    /// Number of worker threads currently running.
+    workers: NSInteger,
+}
+impl HostObject for OperationQueueHostObject {}
+
+/// Wait until `is_done` returns `true`, letting other threads run meanwhile.
+fn wait_until(env: &mut Environment, mut is_done: impl FnMut(&mut Environment) -> bool) {
+    while !is_done(env) {
+        env.sleep(POLL_INTERVAL, /* tail_call: */ false);
+    }
+}
+
+/// Start a new worker thread for a queue if it is allowed more of them.
+fn start_worker_if_needed(env: &mut Environment, queue: id) {
+    let host_obj = env.objc.borrow_mut::<OperationQueueHostObject>(queue);
+    let max = host_obj.max_concurrent_operation_count;
+    let pending: NSInteger = host_obj.operations.len().try_into().unwrap();
+    if host_obj.workers >= pending || (max >= 0 && host_obj.workers >= max) {
+        return;
+    }
+    host_obj.workers += 1;
+    let selector = env
+        .objc
+        .lookup_selector("_touchHLE_runOperations:")
+        .unwrap();
+    () = msg_class![env; NSThread detachNewThreadSelector:selector toTarget:queue withObject:nil];
+}
+
+/// Find an operation in a queue that is ready to start, and mark it as started
+/// so no other worker takes it.
+fn take_ready_operation(env: &mut Environment, queue: id) -> Option<id> {
+    let operations = env
+        .objc
+        .borrow::<OperationQueueHostObject>(queue)
+        .operations
+        .clone();
+    for operation in operations {
+        let host_obj = env.objc.borrow::<OperationHostObject>(operation);
+        if host_obj.executing || host_obj.finished {
+            continue;
+        }
+        if msg![env; operation isReady] {
+            env.objc
+                .borrow_mut::<OperationHostObject>(operation)
+                .executing = true;
+            return Some(operation);
+        }
+    }
+    None
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSOperation: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(OperationHostObject {
+        work: OperationWork::None,
+        dependencies: Vec::new(),
+        executing: false,
+        finished: false,
+        cancelled: false,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (())dealloc {
+    let host_obj = env.objc.borrow_mut::<OperationHostObject>(this);
+    let work = std::mem::replace(&mut host_obj.work, OperationWork::None);
+    let dependencies = std::mem::take(&mut host_obj.dependencies);
+    match work {
+        OperationWork::None => (),
+        OperationWork::Invocation { target, object, .. } => {
+            release(env, target);
+            release(env, object);
+        }
+        OperationWork::Blocks(blocks) => {
+            for block in blocks {
+                release_block(&mut env.mem, block);
+            }
+        }
+    }
+    for dependency in dependencies {
+        release(env, dependency);
+    }
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (())start {
+    if !msg![env; this isCancelled] {
+        env.objc.borrow_mut::<OperationHostObject>(this).executing = true;
+        () = msg![env; this main];
+    }
+    let host_obj = env.objc.borrow_mut::<OperationHostObject>(this);
+    host_obj.executing = false;
+    host_obj.finished = true;
+}
+
+- (())main {
+    // Subclasses override this.
+}
+
+- (bool)isReady {
+    let dependencies = env.objc.borrow::<OperationHostObject>(this).dependencies.clone();
+    dependencies.into_iter().all(|dependency| msg![env; dependency isFinished])
+}
+- (bool)isExecuting {
+    let host_obj = env.objc.borrow::<OperationHostObject>(this);
+    host_obj.executing && !host_obj.finished
+}
+- (bool)isFinished {
+    env.objc.borrow::<OperationHostObject>(this).finished
+}
+- (bool)isCancelled {
+    env.objc.borrow::<OperationHostObject>(this).cancelled
+}
+- (bool)isConcurrent {
+    false
+}
+
+- (())cancel {
+    env.objc.borrow_mut::<OperationHostObject>(this).cancelled = true;
+}
+
+- (())addDependency:(id)operation { // NSOperation*
+    retain(env, operation);
+    env.objc.borrow_mut::<OperationHostObject>(this).dependencies.push(operation);
+}
+- (())removeDependency:(id)operation { // NSOperation*
+    let dependencies = &mut env.objc.borrow_mut::<OperationHostObject>(this).dependencies;
+    if let Some(index) = dependencies.iter().position(|&d| d == operation) {
+        dependencies.remove(index);
+        release(env, operation);
+    }
+}
+- (id)dependencies {
+    let dependencies = env.objc.borrow::<OperationHostObject>(this).dependencies.clone();
+    for &dependency in &dependencies {
+        retain(env, dependency);
+    }
+    let array = ns_array::from_vec(env, dependencies);
+    autorelease(env, array)
+}
+
+- (())waitUntilFinished {
+    wait_until(env, |env| msg![env; this isFinished]);
+}
+
+@end
+
+@implementation NSInvocationOperation: NSOperation
+
+- (id)initWithTarget:(id)target
+            selector:(SEL)selector
+              object:(id)object {
+    retain(env, target);
+    retain(env, object);
+    env.objc.borrow_mut::<OperationHostObject>(this).work = OperationWork::Invocation {
+        target,
+        selector,
+        object,
+    };
+    this
+}
+
+- (())main {
+    let &OperationWork::Invocation {
+        target,
+        selector,
+        object,
+    } = &env.objc.borrow::<OperationHostObject>(this).work else {
+        panic!();
+    };
+    // TODO: store the result, for the result method
+    () = msg_send(env, (target, selector, object));
+}
+
+@end
+
+@implementation NSBlockOperation: NSOperation
+
++ (id)blockOperationWithBlock:(id)block { // void (^)(void)
+    let new: id = msg![env; this new];
+    () = msg![env; new addExecutionBlock:block];
+    autorelease(env, new)
+}
+
+- (())addExecutionBlock:(id)block { // void (^)(void)
+    let block = copy_block(&mut env.mem, block);
+    let host_obj = env.objc.borrow_mut::<OperationHostObject>(this);
+    match host_obj.work {
+        OperationWork::None => host_obj.work = OperationWork::Blocks(vec![block]),
+        OperationWork::Blocks(ref mut blocks) => blocks.push(block),
+        OperationWork::Invocation { .. } => unreachable!(),
+    }
+}
+
+- (())main {
+    let blocks = match env.objc.borrow::<OperationHostObject>(this).work {
+        OperationWork::Blocks(ref blocks) => blocks.clone(),
+        _ => Vec::new(),
+    };
+    for block in blocks {
+        let invoke = get_block_invoke(&env.mem, block);
+        let _: () = invoke.call_from_host(env, (block,));
+    }
+}
+
+@end
+
+@implementation NSOperationQueue: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(OperationQueueHostObject {
+        operations: Vec::new(),
+        max_concurrent_operation_count: NSOperationQueueDefaultMaxConcurrentOperationCount,
+        workers: 0,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (())dealloc {
+    // Worker threads retain the queue, so there can't be any left.
+    let operations = std::mem::take(
+        &mut env.objc.borrow_mut::<OperationQueueHostObject>(this).operations
+    );
+    for operation in operations {
+        release(env, operation);
+    }
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (())addOperation:(id)operation { // NSOperation*
+    retain(env, operation);
+    env.objc.borrow_mut::<OperationQueueHostObject>(this).operations.push(operation);
+    start_worker_if_needed(env, this);
+}
+
+- (())addOperationWithBlock:(id)block { // void (^)(void)
+    let operation: id = msg_class![env; NSBlockOperation blockOperationWithBlock:block];
+    msg![env; this addOperation:operation]
+}
+
+- (NSInteger)maxConcurrentOperationCount {
+    env.objc.borrow::<OperationQueueHostObject>(this).max_concurrent_operation_count
+}
+- (())setMaxConcurrentOperationCount:(NSInteger)count {
+    let host_obj = env.objc.borrow_mut::<OperationQueueHostObject>(this);
+    host_obj.max_concurrent_operation_count = count;
+    start_worker_if_needed(env, this);
+}
+
+- (id)operations {
+    let operations = env.objc.borrow::<OperationQueueHostObject>(this).operations.clone();
+    for &operation in &operations {
+        retain(env, operation);
+    }
+    let array = ns_array::from_vec(env, operations);
+    autorelease(env, array)
+}
+- (NSInteger)operationCount {
+    let count = env.objc.borrow::<OperationQueueHostObject>(this).operations.len();
+    count.try_into().unwrap()
+}
+
+- (())cancelAllOperations {
+    let operations = env.objc.borrow::<OperationQueueHostObject>(this).operations.clone();
+    for operation in operations {
+        () = msg![env; operation cancel];
+    }
+}
+
+- (())waitUntilAllOperationsAreFinished {
+    wait_until(env, |env| {
+        env.objc.borrow::<OperationQueueHostObject>(this).operations.is_empty()
+    });
+}
+
+// Body of a worker thread: run operations until there are none left.
+- (())_touchHLE_runOperations:(id)_object {
+    loop {
+        if let Some(operation) = take_ready_operation(env, this) {
+            () = msg![env; operation start];
+            let host_obj = env.objc.borrow_mut::<OperationQueueHostObject>(this);
+            let operations = &mut host_obj.operations;
+            let index = operations.iter().position(|&o| o == operation).unwrap();
+            operations.remove(index);
+            release(env, operation);
+            continue;
+        }
+        let operations = env.objc.borrow::<OperationQueueHostObject>(this).operations.clone();
+        let all_started = operations.into_iter().all(|operation| {
+            let host_obj = env.objc.borrow::<OperationHostObject>(operation);
+            host_obj.executing || host_obj.finished
+        });
+        if all_started {
+            env.objc.borrow_mut::<OperationQueueHostObject>(this).workers -= 1;
+            break;
+        }
+        // Some operations are waiting on dependencies.
+        env.sleep(POLL_INTERVAL, /* tail_call: */ false);
+    }
+}
+
+@end
+
+};
@@ -0,0 +1,86 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSPredicate`.
+//!
+//! Only constant and block-based predicates are supported so far. Predicate
+//! format strings (`predicateWithFormat:`) aren't parsed yet.
+
+use crate::abi::CallFromHost;
+use crate::objc::{
+    autorelease, copy_block, get_block_invoke, id, msg, nil, objc_classes, release_block, retain,
+    ClassExports, HostObject, NSZonePtr,
+};
+
+enum Predicate {
+    /// `predicateWithValue:`
+    Value(bool),
+    /// `predicateWithBlock:`. The block is a copy owned by the predicate.
+    Block(id),
+}
+
+struct PredicateHostObject {
+    predicate: Predicate,
+}
+impl HostObject for PredicateHostObject {}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSPredicate: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(PredicateHostObject {
+        predicate: Predicate::Value(false),
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
++ (id)predicateWithValue:(bool)value {
+    let new: id = msg![env; this new];
+    env.objc.borrow_mut::<PredicateHostObject>(new).predicate = Predicate::Value(value);
+    autorelease(env, new)
+}
+
++ (id)predicateWithBlock:(id)block { // BOOL (^)(id, NSDictionary*)
+    let block = copy_block(&mut env.mem, block);
+    let new: id = msg![env; this new];
+    env.objc.borrow_mut::<PredicateHostObject>(new).predicate = Predicate::Block(block);
+    autorelease(env, new)
+}
+
+- (())dealloc {
+    let host_obj = env.objc.borrow_mut::<PredicateHostObject>(this);
+    if let Predicate::Block(block) = host_obj.predicate {
+        release_block(&mut env.mem, block);
+    }
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+// NSCopying implementation
+- (id)copyWithZone:(NSZonePtr)_zone {
+    // Predicates are immutable.
+    retain(env, this)
+}
+
+- (bool)evaluateWithObject:(id)object {
+    msg![env; this evaluateWithObject:object substitutionVariables:nil]
+}
+
+- (bool)evaluateWithObject:(id)object
+     substitutionVariables:(id)bindings { // NSDictionary*
+    match env.objc.borrow::<PredicateHostObject>(this).predicate {
+        Predicate::Value(value) => value,
+        Predicate::Block(block) => {
+            let invoke = get_block_invoke(&env.mem, block);
+            invoke.call_from_host(env, (block, object, bindings))
+        }
+    }
+}
+
+@end
+
+};
@@ -1,15 +1,29 @@
 //! `NSPropertyListSerialization`.
 
-use super::{ns_array, ns_dictionary, ns_string, NSUInteger};
+use super::ns_dictionary::DictionaryHostObject;
+use super::ns_error::{
+    set_error, NSCocoaErrorDomain, NSPropertyListReadCorruptError, NSPropertyListWriteStreamError,
+};
+use super::ns_value::NSNumberHostObject;
+use super::{ns_array, ns_dictionary, ns_string, NSTimeInterval, NSUInteger};
+use crate::frameworks::core_foundation::time::apple_epoch;
 use crate::fs::GuestPath;
-use crate::mem::MutPtr;
-use crate::objc::{id, msg, msg_class, nil, release};
+use crate::mem::{ConstVoidPtr, MutPtr};
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, Class, ClassExports,
+};
 use crate::Environment;
-use plist::Value;
+use plist::{Dictionary, Value};
 use std::io::Cursor;
+use std::time::{Duration, SystemTime};
 
-// TODO: Implement reading of property lists other than Info.plist.
-// [NSDictionary contentsOfFile:] and [NSArray contentsOfFile:] in particular.
+pub type NSPropertyListFormat = NSUInteger;
+pub const NSPropertyListOpenStepFormat: NSPropertyListFormat = 1;
+pub const NSPropertyListXMLFormat_v1_0: NSPropertyListFormat = 100;
+pub const NSPropertyListBinaryFormat_v1_0: NSPropertyListFormat = 200;
+
+pub type NSPropertyListMutabilityOptions = NSUInteger;
+pub const NSPropertyListImmutable: NSPropertyListMutabilityOptions = 0;
 
 /// Internals of `initWithContentsOfFile:` on `NSArray` and `NSDictionary`.
 /// Returns `nil` on failure.
@@ -41,7 +55,96 @@ pub(super) fn deserialize_plist_from_file(
     deserialize_plist(env, &root)
 }
 
-fn deserialize_plist(env: &mut Environment, value: &Value) -> id {
+/// Internals of `writeToFile:atomically:` on `NSArray` and `NSDictionary`.
+pub(super) fn serialize_plist_to_file(
+    env: &mut Environment,
+    object: id,
+    path: id, // NSString*
+    atomically: bool,
+) -> bool {
+    let Ok(bytes) = serialize_plist_to_bytes(env, object, NSPropertyListXMLFormat_v1_0) else {
+        return false;
+    };
+    // Going via NSData means there's only one implementation of file writing.
+    let data = data_from_bytes(env, &bytes);
+    let success: bool = msg![env; data writeToFile:path atomically:atomically];
+    release(env, data);
+    success
+}
+
+/// Parse a property list in either the binary or the XML format. On success,
+/// returns the object (owned by the caller) and the format it was in.
+fn deserialize_plist_from_bytes(
+    env: &mut Environment,
+    bytes: &[u8],
+) -> Result<(id, NSPropertyListFormat), String> {
+    if bytes.starts_with(b"bplist") {
+        let root = Value::from_reader(Cursor::new(bytes))
+            .map_err(|e| format!("Couldn't parse binary property list: {}", e))?;
+        Ok((
+            deserialize_plist(env, &root),
+            NSPropertyListBinaryFormat_v1_0,
+        ))
+    } else {
+        // TODO: OpenStep format
+        let root = Value::from_reader_xml(Cursor::new(bytes))
+            .map_err(|e| format!("Couldn't parse XML property list: {}", e))?;
+        Ok((deserialize_plist(env, &root), NSPropertyListXMLFormat_v1_0))
+    }
+}
+
+/// Serialize a property list in the binary or XML format.
+fn serialize_plist_to_bytes(
+    env: &mut Environment,
+    object: id,
+    format: NSPropertyListFormat,
+) -> Result<Vec<u8>, String> {
+    let Some(root) = serialize_plist(env, object) else {
+        return Err("Object is not a property list".to_string());
+    };
+    let mut bytes = Vec::new();
+    let result = match format {
+        NSPropertyListXMLFormat_v1_0 => root.to_writer_xml(&mut bytes),
+        NSPropertyListBinaryFormat_v1_0 => root.to_writer_binary(&mut bytes),
+        NSPropertyListOpenStepFormat => {
+            return Err("The OpenStep format can't be written".to_string());
+        }
+        _ => return Err(format!("Unknown property list format {}", format)),
+    };
+    result.map_err(|e| format!("Couldn't write property list: {}", e))?;
+    Ok(bytes)
+}
+
+/// Create an `NSData` (owned by the caller) containing a copy of `bytes`.
+fn data_from_bytes(env: &mut Environment, bytes: &[u8]) -> id {
+    let length: NSUInteger = bytes.len().try_into().unwrap();
+    let alloc: MutPtr<u8> = env.mem.alloc(length).cast();
+    env.mem.bytes_at_mut(alloc, length).copy_from_slice(bytes);
+    let data: id = msg_class![env; NSData alloc];
+    msg![env; data initWithBytesNoCopy:alloc length:length]
+}
+
+fn system_time_to_reference_date(time: SystemTime) -> NSTimeInterval {
+    match time.duration_since(apple_epoch()) {
+        Ok(after) => after.as_secs_f64(),
+        Err(before) => -before.duration().as_secs_f64(),
+    }
+}
+
+/// Returns [None] if the time interval is NaN, infinite, or otherwise can't be
+/// represented as a [SystemTime].
+fn reference_date_to_system_time(time_interval: NSTimeInterval) -> Option<SystemTime> {
+    let offset = Duration::try_from_secs_f64(time_interval.abs()).ok()?;
+    if time_interval >= 0.0 {
+        apple_epoch().checked_add(offset)
+    } else {
+        apple_epoch().checked_sub(offset)
+    }
+}
+
+/// Convert a plist value to the corresponding Objective-C object. The result
+/// is owned by the caller (not autoreleased).
+pub(super) fn deserialize_plist(env: &mut Environment, value: &Value) -> id {
     match value {
         Value::Array(array) => {
             let array = array
@@ -75,15 +178,11 @@ fn deserialize_plist(env: &mut Environment, value: &Value) -> id {
             let b: bool = *b;
             msg![env; number initWithBool:b]
         }
-        Value::Data(d) => {
-            let length: NSUInteger = d.len().try_into().unwrap();
-            let alloc: MutPtr<u8> = env.mem.alloc(length).cast();
-            env.mem.bytes_at_mut(alloc, length).copy_from_slice(d);
-            let data: id = msg_class![env; NSData alloc];
-            msg![env; data initWithBytesNoCopy:alloc length:length]
-        }
-        Value::Date(_) => {
-            todo!("deserialize plist value: {:?}", value); // TODO
+        Value::Data(d) => data_from_bytes(env, d),
+        Value::Date(d) => {
+            let date: id = msg_class![env; NSDate alloc];
+            let time_interval = system_time_to_reference_date((*d).into());
+            msg![env; date initWithTimeIntervalSinceReferenceDate:time_interval]
         }
         Value::Integer(int) => {
             let number: id = msg_class![env; NSNumber alloc];
@@ -114,3 +213,179 @@ fn deserialize_plist(env: &mut Environment, value: &Value) -> id {
         }
     }
 }
+
+/// Convert an Objective-C object to a plist value. Returns [None] if the object
+/// or something it contains is not one of the property list types.
+pub(super) fn serialize_plist(env: &mut Environment, object: id) -> Option<Value> {
+    if object == nil {
+        return None;
+    }
+
+    let string_class: Class = msg_class![env; NSString class];
+    let number_class: Class = msg_class![env; NSNumber class];
+    let data_class: Class = msg_class![env; NSData class];
+    let date_class: Class = msg_class![env; NSDate class];
+    let array_class: Class = msg_class![env; NSArray class];
+    let dictionary_class: Class = msg_class![env; NSDictionary class];
+
+    if msg![env; object isKindOfClass:string_class] {
+        let string = ns_string::to_rust_string(env, object);
+        Some(Value::String(string.into_owned()))
+    } else if msg![env; object isKindOfClass:number_class] {
+        Some(match *env.objc.borrow(object) {
+            NSNumberHostObject::Bool(value) => Value::Boolean(value),
+            NSNumberHostObject::UnsignedLongLong(value) => Value::Integer(value.into()),
+            NSNumberHostObject::LongLong(value) => Value::Integer(value.into()),
+            NSNumberHostObject::Float(value) => Value::Real(value.into()),
+            NSNumberHostObject::Double(value) => Value::Real(value),
+        })
+    } else if msg![env; object isKindOfClass:data_class] {
+        Some(Value::Data(data_to_vec(env, object)))
+    } else if msg![env; object isKindOfClass:date_class] {
+        let time_interval: NSTimeInterval = msg![env; object timeIntervalSinceReferenceDate];
+        let Some(time) = reference_date_to_system_time(time_interval) else {
+            log!("Can't serialize date {} to a plist.", time_interval);
+            return None;
+        };
+        Some(Value::Date(time.into()))
+    } else if msg![env; object isKindOfClass:array_class] {
+        let count: NSUInteger = msg![env; object count];
+        let mut array = Vec::with_capacity(count as usize);
+        for i in 0..count {
+            let item: id = msg![env; object objectAtIndex:i];
+            array.push(serialize_plist(env, item)?);
+        }
+        Some(Value::Array(array))
+    } else if msg![env; object isKindOfClass:dictionary_class] {
+        let keys: Vec<id> = env
+            .objc
+            .borrow::<DictionaryHostObject>(object)
+            .iter_keys()
+            .collect();
+        let mut dict = Dictionary::new();
+        for key in keys {
+            if !msg![env; key isKindOfClass:string_class] {
+                log!("Plist dictionary keys must be strings, got {:?}.", key);
+                return None;
+            }
+            let value: id = msg![env; object objectForKey:key];
+            let value = serialize_plist(env, value)?;
+            dict.insert(ns_string::to_rust_string(env, key).into_owned(), value);
+        }
+        Some(Value::Dictionary(dict))
+    } else {
+        log!("Object {:?} is not a property list type.", object);
+        None
+    }
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSPropertyListSerialization: NSObject
+
++ (id)propertyListFromData:(id)data // NSData*
+          mutabilityOption:(NSPropertyListMutabilityOptions)option
+                    format:(MutPtr<NSPropertyListFormat>)format
+          errorDescription:(MutPtr<id>)error_string { // NSString**
+    if option != NSPropertyListImmutable {
+        log!("TODO: ignoring mutability option {} for property list", option);
+    }
+    let bytes = data_to_vec(env, data);
+    match deserialize_plist_from_bytes(env, &bytes) {
+        Ok((plist, actual_format)) => {
+            if !format.is_null() {
+                env.mem.write(format, actual_format);
+            }
+            autorelease(env, plist)
+        }
+        Err(description) => {
+            log_dbg!("propertyListFromData: failed: {}", description);
+            if !error_string.is_null() {
+                // The caller is responsible for releasing this string.
+                let description = ns_string::from_rust_string(env, description);
+                env.mem.write(error_string, description);
+            }
+            nil
+        }
+    }
+}
+
++ (id)propertyListWithData:(id)data // NSData*
+                   options:(NSPropertyListMutabilityOptions)options
+                    format:(MutPtr<NSPropertyListFormat>)format
+                     error:(MutPtr<id>)error { // NSError**
+    if options != NSPropertyListImmutable {
+        log!("TODO: ignoring mutability option {} for property list", options);
+    }
+    let bytes = data_to_vec(env, data);
+    match deserialize_plist_from_bytes(env, &bytes) {
+        Ok((plist, actual_format)) => {
+            if !format.is_null() {
+                env.mem.write(format, actual_format);
+            }
+            autorelease(env, plist)
+        }
+        Err(description) => {
+            let code = NSPropertyListReadCorruptError;
+            set_error(env, error, NSCocoaErrorDomain, code, description);
+            nil
+        }
+    }
+}
+
++ (id)dataFromPropertyList:(id)plist
+                    format:(NSPropertyListFormat)format
+          errorDescription:(MutPtr<id>)error_string { // NSString**
+    match serialize_plist_to_bytes(env, plist, format) {
+        Ok(bytes) => {
+            let data = data_from_bytes(env, &bytes);
+            autorelease(env, data)
+        }
+        Err(description) => {
+            log_dbg!("dataFromPropertyList: failed: {}", description);
+            if !error_string.is_null() {
+                // The caller is responsible for releasing this string.
+                let description = ns_string::from_rust_string(env, description);
+                env.mem.write(error_string, description);
+            }
+            nil
+        }
+    }
+}
+
++ (id)dataWithPropertyList:(id)plist
+                    format:(NSPropertyListFormat)format
+                   options:(NSUInteger)_options // reserved
+                     error:(MutPtr<id>)error { // NSError**
+    match serialize_plist_to_bytes(env, plist, format) {
+        Ok(bytes) => {
+            let data = data_from_bytes(env, &bytes);
+            autorelease(env, data)
+        }
+        Err(description) => {
+            let code = NSPropertyListWriteStreamError;
+            set_error(env, error, NSCocoaErrorDomain, code, description);
+            nil
+        }
+    }
+}
+
++ (bool)propertyList:(id)plist
+    isValidForFormat:(NSPropertyListFormat)format {
+    format != NSPropertyListOpenStepFormat && serialize_plist(env, plist).is_some()
+}
+
+@end
+
+};
+
+pub(super) fn data_to_vec(env: &mut Environment, data: id) -> Vec<u8> {
+    let bytes: ConstVoidPtr = msg![env; data bytes];
+    let length: NSUInteger = msg![env; data length];
+    if length == 0 {
+        return Vec::new();
+    }
+    env.mem.bytes_at(bytes.cast(), length).to_vec()
+}
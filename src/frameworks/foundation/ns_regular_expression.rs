@@ -0,0 +1,409 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSRegularExpression` and `NSTextCheckingResult`.
+//!
+//! This uses the `regex` crate, whose syntax is close to, but not the same as,
+//! the ICU syntax Apple uses. Notably, backreferences and lookaround are not
+//! supported.
+
+use super::ns_error::{set_error, NSCocoaErrorDomain};
+use super::ns_string::{for_each_code_unit, from_rust_string, to_rust_string};
+use super::{ns_array, NSInteger, NSNotFound, NSRange, NSUInteger};
+use crate::mem::MutPtr;
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, ClassExports, HostObject,
+    NSZonePtr,
+};
+use crate::Environment;
+use regex::{Captures, Regex, RegexBuilder};
+
+pub type NSRegularExpressionOptions = NSUInteger;
+pub const NSRegularExpressionCaseInsensitive: NSRegularExpressionOptions = 1 << 0;
+pub const NSRegularExpressionAllowCommentsAndWhitespace: NSRegularExpressionOptions = 1 << 1;
+pub const NSRegularExpressionIgnoreMetacharacters: NSRegularExpressionOptions = 1 << 2;
+pub const NSRegularExpressionDotMatchesLineSeparators: NSRegularExpressionOptions = 1 << 3;
+pub const NSRegularExpressionAnchorsMatchLines: NSRegularExpressionOptions = 1 << 4;
+
+pub type NSMatchingOptions = NSUInteger;
+
+pub type NSTextCheckingType = u64;
+pub const NSTextCheckingTypeRegularExpression: NSTextCheckingType = 1 << 10;
+
+/// Error code in NSCocoaErrorDomain for invalid patterns.
+const NSFormattingError: NSInteger = 2048;
+
+struct RegularExpressionHostObject {
+    regex: Option<Regex>,
+    /// `NSString*`
+    pattern: id,
+    options: NSRegularExpressionOptions,
+}
+impl HostObject for RegularExpressionHostObject {}
+
+struct TextCheckingResultHostObject {
+    /// `NSRegularExpression*`
+    regular_expression: id,
+    /// The range of the whole match followed by the range of each capture
+    /// group, as (location, length) in UTF-16 code units. Groups that didn't
+    /// participate in the match have a location of `NSNotFound`.
+    ranges: Vec<(NSUInteger, NSUInteger)>,
+}
+impl HostObject for TextCheckingResultHostObject {}
+
+/// A copy of part of an `NSString` that can be searched, and the information
+/// needed to map byte offsets in it back to UTF-16 offsets in the original.
+struct SearchText {
+    text: String,
+    /// UTF-16 offset in the original string for each byte offset in `text`.
+    /// Only the entries at character boundaries are meaningful.
+    utf16_offsets: Vec<NSUInteger>,
+}
+impl SearchText {
+    fn new(env: &mut Environment, string: id, range: NSRange) -> SearchText {
+        let NSRange { location, length } = range;
+        let mut code_units = Vec::new();
+        for_each_code_unit(env, string, |_idx, c| code_units.push(c));
+        let end = location.checked_add(length).unwrap();
+        // TODO: raise NSRangeException
+        assert!(end as usize <= code_units.len());
+
+        let mut text = String::new();
+        let mut utf16_offsets = Vec::new();
+        let mut offset = location;
+        for c in char::decode_utf16(code_units[location as usize..end as usize].iter().copied()) {
+            // Lone surrogates are replaced with U+FFFD, which is also a single
+            // code unit, so offsets are preserved.
+            let c = c.unwrap_or(char::REPLACEMENT_CHARACTER);
+            for _ in 0..c.len_utf8() {
+                utf16_offsets.push(offset);
+            }
+            text.push(c);
+            offset += c.len_utf16() as NSUInteger;
+        }
+        utf16_offsets.push(offset);
+        SearchText {
+            text,
+            utf16_offsets,
+        }
+    }
+
+    fn ranges_of_captures(&self, captures: &Captures) -> Vec<(NSUInteger, NSUInteger)> {
+        captures
+            .iter()
+            .map(|group| match group {
+                Some(group) => {
+                    let start = self.utf16_offsets[group.start()];
+                    let end = self.utf16_offsets[group.end()];
+                    (start, end - start)
+                }
+                None => (NSNotFound as NSUInteger, 0),
+            })
+            .collect()
+    }
+}
+
+fn borrow_regex(env: &mut Environment, regular_expression: id) -> Regex {
+    // Cloning a Regex is cheap, and this avoids holding a borrow of the host
+    // object while creating other objects.
+    env.objc
+        .borrow::<RegularExpressionHostObject>(regular_expression)
+        .regex
+        .clone()
+        .unwrap()
+}
+
+/// Create an autoreleased `NSTextCheckingResult`.
+fn new_result(
+    env: &mut Environment,
+    regular_expression: id,
+    ranges: Vec<(NSUInteger, NSUInteger)>,
+) -> id {
+    retain(env, regular_expression);
+    let host_object = Box::new(TextCheckingResultHostObject {
+        regular_expression,
+        ranges,
+    });
+    let class = env
+        .objc
+        .get_known_class("NSTextCheckingResult", &mut env.mem);
+    let result = env.objc.alloc_object(class, host_object, &mut env.mem);
+    autorelease(env, result)
+}
+
+/// Expand a replacement template, where `$n` refers to capture group `n` and
+/// `\` escapes the next character.
+fn expand_template(template: &str, captures: &Captures) -> String {
+    let mut result = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next()),
+            '$' if matches!(chars.peek(), Some(c) if c.is_ascii_digit()) => {
+                // Like ICU, take as many digits as still form a valid
+                // group number.
+                let mut group = 0;
+                while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                    let next_group = group * 10 + digit as usize;
+                    if group != 0 && next_group >= captures.len() {
+                        break;
+                    }
+                    group = next_group;
+                    chars.next();
+                }
+                if let Some(group) = captures.get(group) {
+                    result.push_str(group.as_str());
+                }
+            }
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSRegularExpression: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(RegularExpressionHostObject {
+        regex: None,
+        pattern: nil,
+        options: 0,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
++ (id)regularExpressionWithPattern:(id)pattern // NSString*
+                           options:(NSRegularExpressionOptions)options
+                             error:(MutPtr<id>)error { // NSError**
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithPattern:pattern options:options error:error];
+    autorelease(env, new)
+}
+
++ (id)escapedPatternForString:(id)string { // NSString*
+    let string = to_rust_string(env, string);
+    let escaped = regex::escape(&string);
+    let escaped = from_rust_string(env, escaped);
+    autorelease(env, escaped)
+}
+
+- (id)initWithPattern:(id)pattern // NSString*
+              options:(NSRegularExpressionOptions)options
+                error:(MutPtr<id>)error { // NSError**
+    let pattern_string = to_rust_string(env, pattern);
+    let pattern_string = if options & NSRegularExpressionIgnoreMetacharacters != 0 {
+        regex::escape(&pattern_string)
+    } else {
+        pattern_string.into_owned()
+    };
+    let known_options = NSRegularExpressionCaseInsensitive
+        | NSRegularExpressionAllowCommentsAndWhitespace
+        | NSRegularExpressionIgnoreMetacharacters
+        | NSRegularExpressionDotMatchesLineSeparators
+        | NSRegularExpressionAnchorsMatchLines;
+    if options & !known_options != 0 {
+        log!("TODO: ignoring NSRegularExpression options {:#x}", options & !known_options);
+    }
+    let regex = RegexBuilder::new(&pattern_string)
+        .case_insensitive(options & NSRegularExpressionCaseInsensitive != 0)
+        .ignore_whitespace(options & NSRegularExpressionAllowCommentsAndWhitespace != 0)
+        .dot_matches_new_line(options & NSRegularExpressionDotMatchesLineSeparators != 0)
+        .multi_line(options & NSRegularExpressionAnchorsMatchLines != 0)
+        .build();
+    let regex = match regex {
+        Ok(regex) => regex,
+        Err(err) => {
+            let description = format!("Invalid regular expression {:?}: {}", pattern_string, err);
+            set_error(env, error, NSCocoaErrorDomain, NSFormattingError, description);
+            release(env, this);
+            return nil;
+        }
+    };
+    let pattern: id = msg![env; pattern copy];
+    *env.objc.borrow_mut(this) = RegularExpressionHostObject {
+        regex: Some(regex),
+        pattern,
+        options,
+    };
+    this
+}
+
+- (())dealloc {
+    let pattern = env.objc.borrow::<RegularExpressionHostObject>(this).pattern;
+    release(env, pattern);
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+// NSCopying implementation
+- (id)copyWithZone:(NSZonePtr)_zone {
+    // Regular expressions are immutable.
+    retain(env, this)
+}
+
+- (id)pattern {
+    env.objc.borrow::<RegularExpressionHostObject>(this).pattern
+}
+- (NSRegularExpressionOptions)options {
+    env.objc.borrow::<RegularExpressionHostObject>(this).options
+}
+- (NSUInteger)numberOfCaptureGroups {
+    let regex = borrow_regex(env, this);
+    (regex.captures_len() - 1).try_into().unwrap()
+}
+
+- (id)matchesInString:(id)string // NSString*
+              options:(NSMatchingOptions)options
+                range:(NSRange)range {
+    if options != 0 {
+        log!("TODO: ignoring NSMatchingOptions {:#x}", options);
+    }
+    let regex = borrow_regex(env, this);
+    let text = SearchText::new(env, string, range);
+    let results = regex
+        .captures_iter(&text.text)
+        .map(|captures| {
+            let result = new_result(env, this, text.ranges_of_captures(&captures));
+            retain(env, result)
+        })
+        .collect();
+    let results = ns_array::from_vec(env, results);
+    autorelease(env, results)
+}
+
+- (id)firstMatchInString:(id)string // NSString*
+                 options:(NSMatchingOptions)options
+                   range:(NSRange)range {
+    if options != 0 {
+        log!("TODO: ignoring NSMatchingOptions {:#x}", options);
+    }
+    let regex = borrow_regex(env, this);
+    let text = SearchText::new(env, string, range);
+    match regex.captures(&text.text) {
+        Some(captures) => new_result(env, this, text.ranges_of_captures(&captures)),
+        None => nil,
+    }
+}
+
+- (NSRange)rangeOfFirstMatchInString:(id)string // NSString*
+                             options:(NSMatchingOptions)options
+                               range:(NSRange)range {
+    let result: id = msg![env; this firstMatchInString:string options:options range:range];
+    if result == nil {
+        return NSRange {
+            location: NSNotFound as NSUInteger,
+            length: 0,
+        };
+    }
+    msg![env; result range]
+}
+
+- (NSUInteger)numberOfMatchesInString:(id)string // NSString*
+                              options:(NSMatchingOptions)options
+                                range:(NSRange)range {
+    if options != 0 {
+        log!("TODO: ignoring NSMatchingOptions {:#x}", options);
+    }
+    let regex = borrow_regex(env, this);
+    let text = SearchText::new(env, string, range);
+    regex.find_iter(&text.text).count().try_into().unwrap()
+}
+
+- (id)stringByReplacingMatchesInString:(id)string // NSString*
+                               options:(NSMatchingOptions)options
+                                 range:(NSRange)range
+                          withTemplate:(id)template { // NSString*
+    if options != 0 {
+        log!("TODO: ignoring NSMatchingOptions {:#x}", options);
+    }
+    let regex = borrow_regex(env, this);
+    let template = to_rust_string(env, template);
+
+    // Only the part of the string within the range is searched, but the rest
+    // is kept in the result.
+    let whole_length: NSUInteger = msg![env; string length];
+    let before = SearchText::new(env, string, NSRange {
+        location: 0,
+        length: range.location,
+    });
+    let text = SearchText::new(env, string, NSRange {
+        location: range.location,
+        length: range.length,
+    });
+    let end = range.location + range.length;
+    let after = SearchText::new(env, string, NSRange {
+        location: end,
+        length: whole_length - end,
+    });
+
+    let replaced = regex.replace_all(&text.text, |captures: &Captures| {
+        expand_template(&template, captures)
+    });
+    let result = format!("{}{}{}", before.text, replaced, after.text);
+    let result = from_rust_string(env, result);
+    autorelease(env, result)
+}
+
+@end
+
+@implementation NSTextCheckingResult: NSObject
+
+// These are only created by host code, using new_result().
+
+- (())dealloc {
+    let regular_expression = env
+        .objc
+        .borrow::<TextCheckingResultHostObject>(this)
+        .regular_expression;
+    release(env, regular_expression);
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (NSTextCheckingType)resultType {
+    NSTextCheckingTypeRegularExpression
+}
+
+- (id)regularExpression {
+    env.objc.borrow::<TextCheckingResultHostObject>(this).regular_expression
+}
+
+- (NSRange)range {
+    msg![env; this rangeAtIndex:0u32]
+}
+
+- (NSUInteger)numberOfRanges {
+    let ranges = &env.objc.borrow::<TextCheckingResultHostObject>(this).ranges;
+    ranges.len().try_into().unwrap()
+}
+
+- (NSRange)rangeAtIndex:(NSUInteger)index {
+    let ranges = &env.objc.borrow::<TextCheckingResultHostObject>(this).ranges;
+    // TODO: raise NSRangeException
+    let (location, length) = ranges[index as usize];
+    NSRange { location, length }
+}
+
+@end
+
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_template() {
+        let regex = Regex::new(r"(\w+)@(\w+)").unwrap();
+        let captures = regex.captures("user@example").unwrap();
+        assert_eq!(expand_template("$2 at $1", &captures), "example at user");
+        assert_eq!(expand_template(r"\$1 costs $$", &captures), "$1 costs $$");
+        assert_eq!(expand_template("$0!", &captures), "user@example!");
+        // $12 refers to group 1 followed by "2", since there is no group 12.
+        assert_eq!(expand_template("$12", &captures), "user2");
+    }
+}
@@ -8,13 +8,14 @@
 //! Resources:
 //! - Apple's [Threading Programming Guide](https://developer.apple.com/library/archive/documentation/Cocoa/Conceptual/Multithreading/Introduction/Introduction.html)
 
-use super::{ns_string, ns_timer};
+use super::{ns_string, ns_timer, ns_url_connection};
 use crate::dyld::{ConstantExports, HostConstant};
 use crate::frameworks::audio_toolbox::audio_queue::{handle_audio_queue, AudioQueueRef};
+use crate::frameworks::audio_toolbox::audio_services::handle_system_sounds;
 use crate::frameworks::core_foundation::cf_run_loop::{
     kCFRunLoopCommonModes, kCFRunLoopDefaultMode, CFRunLoopRef,
 };
-use crate::frameworks::{core_animation, media_player, uikit};
+use crate::frameworks::{av_foundation, core_animation, media_player, uikit};
 use crate::objc::{id, msg, objc_classes, release, retain, ClassExports, HostObject};
 use crate::Environment;
 use std::time::{Duration, Instant};
@@ -48,6 +49,9 @@ struct NSRunLoopHostObject {
     /// Strong references to `NSTimer*` in no particular order. Timers are owned
     /// by the run loop. The timer must remove itself when invalidated.
     timers: Vec<id>,
+    /// Strong references to `NSURLConnection*` in no particular order. The
+    /// connection must remove itself when it finishes or is cancelled.
+    url_connections: Vec<id>,
 }
 impl HostObject for NSRunLoopHostObject {}
 
@@ -64,6 +68,7 @@ pub const CLASSES: ClassExports = objc_classes! {
         let host_object = Box::new(NSRunLoopHostObject {
             audio_queues: Vec::new(),
             timers: Vec::new(),
+            url_connections: Vec::new(),
         });
         let new = env.objc.alloc_static_object(this, host_object, &mut env.mem);
         env.framework_state.foundation.ns_run_loop.main_thread_run_loop = Some(new);
@@ -153,6 +158,31 @@ pub(super) fn remove_timer(env: &mut Environment, run_loop: id, timer: id) {
     }
 }
 
+/// For use by NSURLConnection.
+pub(super) fn add_url_connection(env: &mut Environment, run_loop: id, connection: id) {
+    retain(env, connection);
+    let connections = &mut env
+        .objc
+        .borrow_mut::<NSRunLoopHostObject>(run_loop)
+        .url_connections;
+    assert!(!connections.contains(&connection));
+    connections.push(connection);
+}
+
+/// For use by NSURLConnection so it can remove itself once it's done.
+pub(super) fn remove_url_connection(env: &mut Environment, run_loop: id, connection: id) {
+    let connections = &mut env
+        .objc
+        .borrow_mut::<NSRunLoopHostObject>(run_loop)
+        .url_connections;
+    let connection_idx = connections
+        .iter()
+        .position(|&item| item == connection)
+        .unwrap();
+    connections.swap_remove(connection_idx);
+    release(env, connection);
+}
+
 /// Run the run loop for just a single iteration. This is a special mode just
 /// for the app picker, since we don't have `runMode:beforeDate:` or
 /// `runUntilDate:` yet. (TODO: implement those to replace this.)
@@ -171,6 +201,7 @@ fn run_run_loop(env: &mut Environment, run_loop: id, single_iteration: bool) {
     // environment or to lock the object. Re-used each iteration for efficiency.
     let mut timers_tmp = Vec::new();
     let mut audio_queues_tmp = Vec::new();
+    let mut url_connections_tmp = Vec::new();
 
     fn limit_sleep_time(current: &mut Option<Instant>, new: Option<Instant>) {
         if let Some(new) = new {
@@ -211,8 +242,23 @@ fn run_run_loop(env: &mut Environment, run_loop: id, single_iteration: bool) {
             handle_audio_queue(env, audio_queue);
         }
 
+        handle_system_sounds(env);
+
+        assert!(url_connections_tmp.is_empty());
+        url_connections_tmp.extend_from_slice(
+            &env.objc
+                .borrow::<NSRunLoopHostObject>(run_loop)
+                .url_connections,
+        );
+
+        for connection in url_connections_tmp.drain(..) {
+            ns_url_connection::handle_connection(env, connection);
+        }
+
         media_player::handle_players(env);
 
+        av_foundation::handle_players(env);
+
         // Unfortunately, touchHLE has to poll for certain things repeatedly;
         // it can't just wait until the next event appears.
         //
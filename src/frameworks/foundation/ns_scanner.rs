@@ -0,0 +1,345 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSScanner`.
+
+use super::ns_string::{fold_case, for_each_code_unit, from_rust_string};
+use super::{unichar, NSInteger, NSUInteger};
+use crate::mem::MutPtr;
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, ClassExports, HostObject,
+    NSZonePtr,
+};
+use crate::Environment;
+
+struct NSScannerHostObject {
+    /// `NSString*`
+    string: id,
+    /// Copy of the string's contents, to avoid having to message the string
+    /// for every character.
+    code_units: Vec<unichar>,
+    scan_location: NSUInteger,
+    /// `NSCharacterSet*`, may be `nil`.
+    characters_to_be_skipped: id,
+    case_sensitive: bool,
+}
+impl HostObject for NSScannerHostObject {}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSScanner: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSScannerHostObject {
+        string: nil,
+        code_units: Vec::new(),
+        scan_location: 0,
+        characters_to_be_skipped: nil,
+        case_sensitive: false,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
++ (id)scannerWithString:(id)string { // NSString*
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithString:string];
+    autorelease(env, new)
+}
+
+- (id)initWithString:(id)string { // NSString*
+    let string: id = msg![env; string copy];
+    let mut code_units = Vec::new();
+    for_each_code_unit(env, string, |_idx, c| code_units.push(c));
+    let skip_set: id = msg_class![env; NSCharacterSet whitespaceAndNewlineCharacterSet];
+    retain(env, skip_set);
+
+    let host_object = env.objc.borrow_mut::<NSScannerHostObject>(this);
+    host_object.string = string;
+    host_object.code_units = code_units;
+    host_object.characters_to_be_skipped = skip_set;
+    this
+}
+
+- (())dealloc {
+    let &NSScannerHostObject {
+        string,
+        characters_to_be_skipped,
+        ..
+    } = env.objc.borrow(this);
+    release(env, string);
+    release(env, characters_to_be_skipped);
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (id)string {
+    env.objc.borrow::<NSScannerHostObject>(this).string
+}
+
+- (NSUInteger)scanLocation {
+    env.objc.borrow::<NSScannerHostObject>(this).scan_location
+}
+- (())setScanLocation:(NSUInteger)location {
+    let host_object = env.objc.borrow_mut::<NSScannerHostObject>(this);
+    // TODO: raise NSRangeException
+    assert!(location as usize <= host_object.code_units.len());
+    host_object.scan_location = location;
+}
+
+- (id)charactersToBeSkipped {
+    env.objc.borrow::<NSScannerHostObject>(this).characters_to_be_skipped
+}
+- (())setCharactersToBeSkipped:(id)set { // NSCharacterSet*
+    retain(env, set);
+    let host_object = env.objc.borrow_mut::<NSScannerHostObject>(this);
+    let old_set = std::mem::replace(&mut host_object.characters_to_be_skipped, set);
+    release(env, old_set);
+}
+
+- (bool)caseSensitive {
+    env.objc.borrow::<NSScannerHostObject>(this).case_sensitive
+}
+- (())setCaseSensitive:(bool)case_sensitive {
+    env.objc.borrow_mut::<NSScannerHostObject>(this).case_sensitive = case_sensitive;
+}
+
+- (bool)isAtEnd {
+    // Skipped characters at the end don't count.
+    let location = skip_characters(env, this);
+    location == env.objc.borrow::<NSScannerHostObject>(this).code_units.len()
+}
+
+- (bool)scanInt:(MutPtr<i32>)result {
+    let Some((value, end)) = scan_integer(env, this) else {
+        return false;
+    };
+    if !result.is_null() {
+        env.mem.write(result, value.clamp(i32::MIN.into(), i32::MAX.into()) as i32);
+    }
+    set_location(env, this, end);
+    true
+}
+
+- (bool)scanInteger:(MutPtr<NSInteger>)result {
+    let Some((value, end)) = scan_integer(env, this) else {
+        return false;
+    };
+    if !result.is_null() {
+        let value = value.clamp(NSInteger::MIN.into(), NSInteger::MAX.into());
+        env.mem.write(result, value as NSInteger);
+    }
+    set_location(env, this, end);
+    true
+}
+
+- (bool)scanFloat:(MutPtr<f32>)result {
+    let Some((value, end)) = scan_decimal(env, this) else {
+        return false;
+    };
+    if !result.is_null() {
+        env.mem.write(result, value as f32);
+    }
+    set_location(env, this, end);
+    true
+}
+
+- (bool)scanDouble:(MutPtr<f64>)result {
+    let Some((value, end)) = scan_decimal(env, this) else {
+        return false;
+    };
+    if !result.is_null() {
+        env.mem.write(result, value);
+    }
+    set_location(env, this, end);
+    true
+}
+
+- (bool)scanString:(id)string // NSString*
+        intoString:(MutPtr<id>)result { // NSString**
+    let mut expected = Vec::new();
+    for_each_code_unit(env, string, |_idx, c| expected.push(c));
+
+    let start = skip_characters(env, this);
+    let host_object = env.objc.borrow::<NSScannerHostObject>(this);
+    let Some(candidate) = host_object.code_units.get(start..start + expected.len()) else {
+        return false;
+    };
+    let matches = if host_object.case_sensitive {
+        candidate == expected
+    } else {
+        candidate
+            .iter()
+            .zip(expected.iter())
+            .all(|(&a, &b)| fold_case(a) == fold_case(b))
+    };
+    if !matches {
+        return false;
+    }
+    let end = start + expected.len();
+    write_substring_result(env, this, start, end, result);
+    set_location(env, this, end);
+    true
+}
+
+- (bool)scanCharactersFromSet:(id)set // NSCharacterSet*
+                   intoString:(MutPtr<id>)result { // NSString**
+    let start = skip_characters(env, this);
+    let end = scan_while(env, this, start, set, true);
+    if end == start {
+        return false;
+    }
+    write_substring_result(env, this, start, end, result);
+    set_location(env, this, end);
+    true
+}
+
+- (bool)scanUpToCharactersFromSet:(id)set // NSCharacterSet*
+                       intoString:(MutPtr<id>)result { // NSString**
+    let start = skip_characters(env, this);
+    let end = scan_while(env, this, start, set, false);
+    if end == start {
+        return false;
+    }
+    write_substring_result(env, this, start, end, result);
+    set_location(env, this, end);
+    true
+}
+
+@end
+
+};
+
+fn set_location(env: &mut Environment, scanner: id, location: usize) {
+    env.objc
+        .borrow_mut::<NSScannerHostObject>(scanner)
+        .scan_location = location.try_into().unwrap();
+}
+
+/// Find the first index at or after `start` where the character is (if
+/// `member` is `true`) or is not (if `member` is `false`) in `set`.
+fn scan_while(env: &mut Environment, scanner: id, start: usize, set: id, member: bool) -> usize {
+    let mut location = start;
+    loop {
+        let host_object = env.objc.borrow::<NSScannerHostObject>(scanner);
+        let Some(&c) = host_object.code_units.get(location) else {
+            break;
+        };
+        let is_member: bool = msg![env; set characterIsMember:c];
+        if is_member != member {
+            break;
+        }
+        location += 1;
+    }
+    location
+}
+
+/// Get the index at which the next scan should start, i.e. the scan location
+/// after skipping any `charactersToBeSkipped`. The scan location itself is
+/// not updated, because a failed scan must not advance it.
+fn skip_characters(env: &mut Environment, scanner: id) -> usize {
+    let host_object = env.objc.borrow::<NSScannerHostObject>(scanner);
+    let start = host_object.scan_location as usize;
+    let skip_set = host_object.characters_to_be_skipped;
+    if skip_set == nil {
+        return start;
+    }
+    scan_while(env, scanner, start, skip_set, true)
+}
+
+fn write_substring_result(
+    env: &mut Environment,
+    scanner: id,
+    start: usize,
+    end: usize,
+    result: MutPtr<id>,
+) {
+    if result.is_null() {
+        return;
+    }
+    let host_object = env.objc.borrow::<NSScannerHostObject>(scanner);
+    let substring = String::from_utf16_lossy(&host_object.code_units[start..end]);
+    let substring = from_rust_string(env, substring);
+    let substring = autorelease(env, substring);
+    env.mem.write(result, substring);
+}
+
+/// Collect the ASCII characters starting at `start` for which `pred` is true.
+fn take_ascii(code_units: &[unichar], start: usize, pred: impl Fn(u8) -> bool) -> usize {
+    code_units[start..]
+        .iter()
+        .take_while(|&&c| c < 0x80 && pred(c as u8))
+        .count()
+}
+
+/// Scan an optionally-signed decimal integer. Returns the value (not yet
+/// clamped to the caller's type) and the index just after it.
+fn scan_integer(env: &mut Environment, scanner: id) -> Option<(i64, usize)> {
+    let start = skip_characters(env, scanner);
+    let code_units = &env.objc.borrow::<NSScannerHostObject>(scanner).code_units;
+
+    let mut end = start;
+    let negative = match code_units.get(end) {
+        Some(&c) if c == b'-'.into() => {
+            end += 1;
+            true
+        }
+        Some(&c) if c == b'+'.into() => {
+            end += 1;
+            false
+        }
+        _ => false,
+    };
+    let digit_count = take_ascii(code_units, end, |c| c.is_ascii_digit());
+    if digit_count == 0 {
+        return None;
+    }
+
+    // Out-of-range values saturate, so there's no need to keep track of more
+    // than what fits in an i64.
+    let mut value: i64 = 0;
+    for &c in &code_units[end..end + digit_count] {
+        let digit = i64::from(c - u16::from(b'0'));
+        value = value.saturating_mul(10).saturating_add(digit);
+    }
+    if negative {
+        value = -value;
+    }
+    Some((value, end + digit_count))
+}
+
+/// Scan a decimal floating-point number, with an optional sign, fractional
+/// part and exponent. Returns the value and the index just after it.
+fn scan_decimal(env: &mut Environment, scanner: id) -> Option<(f64, usize)> {
+    let start = skip_characters(env, scanner);
+    let code_units = &env.objc.borrow::<NSScannerHostObject>(scanner).code_units;
+
+    let mut end = start;
+    end += take_ascii(code_units, end, |c| c == b'-' || c == b'+').min(1);
+    let integer_digits = take_ascii(code_units, end, |c| c.is_ascii_digit());
+    end += integer_digits;
+    let mut fraction_digits = 0;
+    if code_units.get(end) == Some(&b'.'.into()) {
+        fraction_digits = take_ascii(code_units, end + 1, |c| c.is_ascii_digit());
+        if integer_digits != 0 || fraction_digits != 0 {
+            end += 1 + fraction_digits;
+        }
+    }
+    if integer_digits == 0 && fraction_digits == 0 {
+        return None;
+    }
+    // The exponent is only consumed if it is well-formed.
+    if matches!(code_units.get(end), Some(&c) if c == b'e'.into() || c == b'E'.into()) {
+        let mut exponent_end = end + 1;
+        exponent_end += take_ascii(code_units, exponent_end, |c| c == b'-' || c == b'+').min(1);
+        let exponent_digits = take_ascii(code_units, exponent_end, |c| c.is_ascii_digit());
+        if exponent_digits != 0 {
+            end = exponent_end + exponent_digits;
+        }
+    }
+
+    let text = String::from_utf16(&code_units[start..end]).unwrap();
+    Some((text.parse().unwrap(), end))
+}
@@ -12,7 +12,8 @@ mod path_algorithms;
 
 use super::ns_array;
 use super::{
-    NSComparisonResult, NSOrderedAscending, NSOrderedDescending, NSOrderedSame, NSUInteger,
+    NSComparisonResult, NSInteger, NSOrderedAscending, NSOrderedDescending, NSOrderedSame, NSRange,
+    NSUInteger,
 };
 use crate::abi::VaList;
 use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
@@ -23,8 +24,8 @@ use crate::fs::GuestPath;
 use crate::mach_o::MachO;
 use crate::mem::{guest_size_of, ConstPtr, Mem, MutPtr, Ptr, SafeRead};
 use crate::objc::{
-    autorelease, id, msg, msg_class, nil, objc_classes, retain, Class, ClassExports, HostObject,
-    NSZonePtr, ObjC,
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, Class, ClassExports,
+    HostObject, NSZonePtr, ObjC,
 };
 use crate::Environment;
 use std::borrow::Cow;
@@ -41,7 +42,10 @@ pub const NSUTF16BigEndianStringEncoding: NSUInteger = 0x90000100;
 pub const NSUTF16LittleEndianStringEncoding: NSUInteger = 0x94000100;
 
 pub type NSStringCompareOptions = NSUInteger;
+pub const NSCaseInsensitiveSearch: NSUInteger = 1;
 pub const NSLiteralSearch: NSUInteger = 2;
+pub const NSBackwardsSearch: NSUInteger = 4;
+pub const NSAnchoredSearch: NSUInteger = 8;
 pub const NSNumericSearch: NSUInteger = 64;
 
 /// Encodings that C strings (null-terminated byte strings) can use.
@@ -238,6 +242,80 @@ fn from_rust_ordering(ordering: std::cmp::Ordering) -> NSComparisonResult {
     }
 }
 
+/// Simple case folding of a UTF-16 code unit, for [NSCaseInsensitiveSearch].
+/// Characters whose lowercase form isn't a single BMP character are unchanged.
+pub(super) fn fold_case(c: u16) -> u16 {
+    let Some(c_char) = char::from_u32(c.into()) else {
+        return c;
+    };
+    let mut lower = c_char.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(lower), None) => u16::try_from(u32::from(lower)).unwrap_or(c),
+        _ => c,
+    }
+}
+
+/// Find the first (or with [NSBackwardsSearch], last) occurrence of `needle`
+/// within `haystack[range]`, returning the index of its start. This operates
+/// on UTF-16 code units, like Cocoa's ranges and indices do.
+///
+/// [NSCaseInsensitiveSearch] and [NSAnchoredSearch] are also supported. Other
+/// options are ignored.
+fn find_code_units(
+    haystack: &[u16],
+    needle: &[u16],
+    range: std::ops::Range<usize>,
+    options: NSStringCompareOptions,
+) -> Option<usize> {
+    if needle.is_empty() || needle.len() > range.len() {
+        return None;
+    }
+    let matches_at = |start: usize| {
+        let candidate = &haystack[start..start + needle.len()];
+        if options & NSCaseInsensitiveSearch != 0 {
+            candidate
+                .iter()
+                .zip(needle)
+                .all(|(&a, &b)| fold_case(a) == fold_case(b))
+        } else {
+            candidate == needle
+        }
+    };
+    let first = range.start;
+    let last = range.end - needle.len();
+    let anchored = options & NSAnchoredSearch != 0;
+    if options & NSBackwardsSearch != 0 {
+        if anchored {
+            matches_at(last).then_some(last)
+        } else {
+            (first..=last).rev().find(|&start| matches_at(start))
+        }
+    } else if anchored {
+        matches_at(first).then_some(first)
+    } else {
+        (first..=last).find(|&start| matches_at(start))
+    }
+}
+
+/// Helper for the `componentsSeparatedBy...` methods: create an autoreleased
+/// `NSArray` of new `NSString`s.
+fn components_to_array(env: &mut Environment, components: Vec<Utf16String>) -> id {
+    // TODO: For a foreign subclass of NSString, do we have to return that
+    // subclass? The signature implies this isn't the case and it's probably not
+    // worth the effort, but it's an interesting question.
+    let class = env.objc.get_known_class("_touchHLE_NSString", &mut env.mem);
+
+    let component_ns_strings = components
+        .into_iter()
+        .map(|utf16| {
+            let host_object = Box::new(StringHostObject::Utf16(utf16));
+            env.objc.alloc_object(class, host_object, &mut env.mem)
+        })
+        .collect();
+    let array = ns_array::from_vec(env, component_ns_strings);
+    autorelease(env, array)
+}
+
 pub const CLASSES: ClassExports = objc_classes! {
 
 (env, this, _cmd);
@@ -256,6 +334,12 @@ pub const CLASSES: ClassExports = objc_classes! {
     msg_class![env; _touchHLE_NSString allocWithZone:zone]
 }
 
++ (id)string {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new init];
+    autorelease(env, new)
+}
+
 + (id)stringWithString:(id)string { // NSString*
     let new: id = msg![env; this alloc];
     let new: id = msg![env; new initWithString:string];
@@ -294,8 +378,10 @@ pub const CLASSES: ClassExports = objc_classes! {
 + (id)stringWithFormat:(id)format, // NSString*
                        ...args {
     let res = with_format(env, format, args.start());
-    let res = from_rust_string(env, res);
-    autorelease(env, res)
+    // Not using from_rust_string, so that this works for NSMutableString too.
+    let new: id = msg![env; this alloc];
+    *env.objc.borrow_mut(new) = StringHostObject::Utf8(res.into());
+    autorelease(env, new)
 }
 
 // These are the two methods that have to be overridden by subclasses, so these
@@ -417,10 +503,16 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 // NSCopying implementation
 - (id)copyWithZone:(NSZonePtr)_zone {
-    // TODO: override this once we have NSMutableString!
+    // NSMutableString overrides this.
     retain(env, this)
 }
 
+// NSMutableCopying implementation
+- (id)mutableCopyWithZone:(NSZonePtr)_zone {
+    let new: id = msg_class![env; NSMutableString alloc];
+    msg![env; new initWithString:this]
+}
+
 - (bool)getCString:(MutPtr<u8>)buffer
          maxLength:(NSUInteger)buffer_size
           encoding:(NSStringEncoding)encoding {
@@ -465,8 +557,12 @@ pub const CLASSES: ClassExports = objc_classes! {
     let sep_iter = env.objc.borrow::<StringHostObject>(separator)
         .iter_code_units();
 
-    // TODO: zero-length separator support
-    assert!(sep_iter.clone().next().is_some());
+    // A zero-length separator never matches.
+    if sep_iter.clone().next().is_none() {
+        let component = msg![env; this copy];
+        let array = ns_array::from_vec(env, vec![component]);
+        return autorelease(env, array);
+    }
 
     let mut components = Vec::<Utf16String>::new();
     let mut current_component: Utf16String = Vec::new();
@@ -485,17 +581,27 @@ pub const CLASSES: ClassExports = objc_classes! {
     }
     components.push(current_component);
 
-    // TODO: For a foreign subclass of NSString, do we have to return that
-    // subclass? The signature implies this isn't the case and it's probably not
-    // worth the effort, but it's an interesting question.
-    let class = env.objc.get_known_class("_touchHLE_NSString", &mut env.mem);
+    components_to_array(env, components)
+}
 
-    let component_ns_strings = components.drain(..).map(|utf16| {
-        let host_object = Box::new(StringHostObject::Utf16(utf16));
-        env.objc.alloc_object(class, host_object, &mut env.mem)
-    }).collect();
-    let array = ns_array::from_vec(env, component_ns_strings);
-    autorelease(env, array)
+- (id)componentsSeparatedByCharactersInSet:(id)set { // NSCharacterSet*
+    let mut code_units: Utf16String = Vec::new();
+    for_each_code_unit(env, this, |_idx, c| code_units.push(c));
+
+    // Like componentsSeparatedByString:, adjacent separators produce empty
+    // components.
+    let mut components = Vec::<Utf16String>::new();
+    let mut current_component: Utf16String = Vec::new();
+    for c in code_units {
+        if msg![env; set characterIsMember:c] {
+            components.push(std::mem::take(&mut current_component));
+        } else {
+            current_component.push(c);
+        }
+    }
+    components.push(current_component);
+
+    components_to_array(env, components)
 }
 
 - (ConstPtr<u8>)cStringUsingEncoding:(NSStringEncoding)encoding {
@@ -564,34 +670,111 @@ pub const CLASSES: ClassExports = objc_classes! {
     autorelease(env, res)
 }
 
+- (NSRange)rangeOfString:(id)search_string { // NSString*
+    msg![env; this rangeOfString:search_string options:0u32]
+}
+
+- (NSRange)rangeOfString:(id)search_string // NSString*
+                 options:(NSStringCompareOptions)options {
+    let length: NSUInteger = msg![env; this length];
+    let range = NSRange { location: 0, length };
+    msg![env; this rangeOfString:search_string options:options range:range]
+}
+
+- (NSRange)rangeOfString:(id)search_string // NSString*
+                 options:(NSStringCompareOptions)options
+                   range:(NSRange)range {
+    // TODO: support foreign subclasses (perhaps via a helper function that
+    // copies the string first)
+    let mut code_units: Utf16String = Vec::new();
+    for_each_code_unit(env, this, |_idx, c| code_units.push(c));
+    let mut search_code_units: Utf16String = Vec::new();
+    for_each_code_unit(env, search_string, |_idx, c| search_code_units.push(c));
+
+    let NSRange { location, length } = range;
+    let search_start = location as usize;
+    let search_end = search_start + length as usize;
+    assert!(search_end <= code_units.len()); // TODO: raise exception
+
+    // An empty search string is never found.
+    match find_code_units(
+        &code_units,
+        &search_code_units,
+        search_start..search_end,
+        options,
+    ) {
+        Some(start) => NSRange {
+            location: start.try_into().unwrap(),
+            length: search_code_units.len().try_into().unwrap(),
+        },
+        None => NSRange {
+            location: NSNotFound as NSUInteger,
+            length: 0,
+        },
+    }
+}
+
 - (id)stringByReplacingOccurrencesOfString:(id)target // NSString*
                                 withString:(id)replacement { // NSString*
+    let length: NSUInteger = msg![env; this length];
+    let range = NSRange { location: 0, length };
+    msg![env; this stringByReplacingOccurrencesOfString:target
+                                             withString:replacement
+                                                options:0u32
+                                                  range:range]
+}
+
+- (id)stringByReplacingOccurrencesOfString:(id)target // NSString*
+                                withString:(id)replacement // NSString*
+                                   options:(NSStringCompareOptions)options
+                                     range:(NSRange)range {
     // TODO: support foreign subclasses (perhaps via a helper function that
     // copies the string first)
-    let mut main_iter = env.objc.borrow::<StringHostObject>(this)
-        .iter_code_units();
-    let target_iter = env.objc.borrow::<StringHostObject>(target)
-        .iter_code_units();
-    let replacement_iter = env.objc.borrow::<StringHostObject>(replacement)
-        .iter_code_units();
-
-    // TODO: zero-length target support?
-    assert!(target_iter.clone().next().is_some());
+    let mut code_units: Utf16String = Vec::new();
+    for_each_code_unit(env, this, |_idx, c| code_units.push(c));
+    let mut target_code_units: Utf16String = Vec::new();
+    for_each_code_unit(env, target, |_idx, c| target_code_units.push(c));
+    let mut replacement_code_units: Utf16String = Vec::new();
+    for_each_code_unit(env, replacement, |_idx, c| replacement_code_units.push(c));
+
+    let NSRange { location, length } = range;
+    let search_start = location as usize;
+    let search_end = search_start + length as usize;
+    assert!(search_end <= code_units.len()); // TODO: raise exception
+
+    if target_code_units.is_empty() {
+        let this = retain(env, this);
+        return autorelease(env, this);
+    }
 
-    let mut result: Utf16String = Vec::new();
-    loop {
-        if let Some(new_main_iter) = main_iter.strip_prefix(&target_iter) {
-            // matched target, replace it
-            result.extend(replacement_iter.clone());
-            main_iter = new_main_iter;
+    // Find all the non-overlapping occurrences. When searching backwards,
+    // which occurrences are found can differ from a forwards search, e.g.
+    // replacing "aa" in "aaa".
+    let mut occurrences = Vec::new();
+    let mut remaining = search_start..search_end;
+    while let Some(start) = find_code_units(
+        &code_units,
+        &target_code_units,
+        remaining.clone(),
+        options,
+    ) {
+        occurrences.push(start);
+        if options & NSBackwardsSearch != 0 {
+            remaining.end = start;
         } else {
-            // no match, copy as normal
-            match main_iter.next() {
-                Some(cur) => result.push(cur),
-                None => break,
-            }
+            remaining.start = start + target_code_units.len();
         }
     }
+    occurrences.sort_unstable();
+
+    let mut result: Utf16String = Vec::with_capacity(code_units.len());
+    let mut copied_up_to = 0;
+    for start in occurrences {
+        result.extend_from_slice(&code_units[copied_up_to..start]);
+        result.extend_from_slice(&replacement_code_units);
+        copied_up_to = start + target_code_units.len();
+    }
+    result.extend_from_slice(&code_units[copied_up_to..]);
 
     // TODO: For a foreign subclass of NSString, do we have to return that
     // subclass? The signature implies this isn't the case and it's probably not
@@ -709,7 +892,6 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 // These come from a category in UIKit (UIStringDrawing).
 // TODO: Implement categories so we can completely move the code to UIFont.
-// TODO: More `sizeWithFont:` variants
 - (CGSize)sizeWithFont:(id)font { // UIFont*
     // TODO: avoid copy
     let text = to_rust_string(env, this);
@@ -729,6 +911,28 @@ pub const CLASSES: ClassExports = objc_classes! {
     ui_font::size_with_font(env, font, &text, Some((size, line_break_mode)))
 }
 
+- (CGSize)sizeWithFont:(id)font // UIFont*
+              forWidth:(CGFloat)width
+         lineBreakMode:(UILineBreakMode)line_break_mode {
+    // TODO: avoid copy
+    let text = to_rust_string(env, this);
+    ui_font::size_with_font_for_width(env, font, &text, width, line_break_mode)
+}
+- (CGSize)sizeWithFont:(id)font // UIFont*
+           minFontSize:(CGFloat)min_font_size
+        actualFontSize:(MutPtr<CGFloat>)actual_font_size
+              forWidth:(CGFloat)width
+         lineBreakMode:(UILineBreakMode)line_break_mode {
+    // TODO: avoid copy
+    let text = to_rust_string(env, this);
+    let font = ui_font::shrink_to_fit(env, font, &text, min_font_size, width);
+    if !actual_font_size.is_null() {
+        let size: CGFloat = msg![env; font pointSize];
+        env.mem.write(actual_font_size, size);
+    }
+    ui_font::size_with_font_for_width(env, font, &text, width, line_break_mode)
+}
+
 - (CGSize)drawAtPoint:(CGPoint)point
              withFont:(id)font { // UIFont*
     // TODO: avoid copy
@@ -744,6 +948,37 @@ pub const CLASSES: ClassExports = objc_classes! {
     let text = to_rust_string(env, this);
     ui_font::draw_at_point(env, font, &text, point, Some((width, line_break_mode)))
 }
+- (CGSize)drawAtPoint:(CGPoint)point
+             forWidth:(CGFloat)width
+             withFont:(id)font // UIFont*
+             fontSize:(CGFloat)font_size
+        lineBreakMode:(UILineBreakMode)line_break_mode
+   baselineAdjustment:(NSInteger)_baseline_adjustment { // UIBaselineAdjustment
+    // The baseline adjustment only matters when the font size differs from
+    // the size the text is laid out for, which isn't the case here.
+    let font: id = msg![env; font fontWithSize:font_size];
+    msg![env; this drawAtPoint:point
+                      forWidth:width
+                      withFont:font
+                 lineBreakMode:line_break_mode]
+}
+- (CGSize)drawAtPoint:(CGPoint)point
+             forWidth:(CGFloat)width
+             withFont:(id)font // UIFont*
+          minFontSize:(CGFloat)min_font_size
+       actualFontSize:(MutPtr<CGFloat>)actual_font_size
+        lineBreakMode:(UILineBreakMode)line_break_mode
+   baselineAdjustment:(NSInteger)_baseline_adjustment { // UIBaselineAdjustment
+    // TODO: baseline adjustment for shrunk text
+    // TODO: avoid copy
+    let text = to_rust_string(env, this);
+    let font = ui_font::shrink_to_fit(env, font, &text, min_font_size, width);
+    if !actual_font_size.is_null() {
+        let size: CGFloat = msg![env; font pointSize];
+        env.mem.write(actual_font_size, size);
+    }
+    ui_font::draw_at_point(env, font, &text, point, Some((width, line_break_mode)))
+}
 
 - (CGSize)drawInRect:(CGRect)rect
             withFont:(id)font { // UIFont*
@@ -791,6 +1026,67 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 @end
 
+// NSMutableString is an abstract class. A subclass must provide everything
+// NSString provides, plus:
+// - (void)replaceCharactersInRange:(NSRange)range withString:(NSString*)string;
+// Note that it inherits from NSString, so we must ensure we override any
+// default methods that would be inappropriate for mutability.
+@implementation NSMutableString: NSString
+
++ (id)allocWithZone:(NSZonePtr)zone {
+    // NSMutableString might be subclassed by something which needs
+    // allocWithZone: to have the normal behaviour. Unimplemented: call
+    // superclass alloc then.
+    assert!(this == env.objc.get_known_class("NSMutableString", &mut env.mem));
+    msg_class![env; _touchHLE_NSMutableString allocWithZone:zone]
+}
+
++ (id)stringWithCapacity:(NSUInteger)capacity {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithCapacity:capacity];
+    autorelease(env, new)
+}
+
+// NSCopying implementation
+- (id)copyWithZone:(NSZonePtr)_zone {
+    // The copy must be immutable.
+    let new: id = msg_class![env; NSString alloc];
+    msg![env; new initWithString:this]
+}
+
+- (())appendString:(id)string { // NSString*
+    let length: NSUInteger = msg![env; this length];
+    let range = NSRange { location: length, length: 0 };
+    msg![env; this replaceCharactersInRange:range withString:string]
+}
+
+- (())appendFormat:(id)format, // NSString*
+                   ...args {
+    let res = with_format(env, format, args.start());
+    let res = from_rust_string(env, res);
+    msg![env; this appendString:res];
+    release(env, res);
+}
+
+- (())insertString:(id)string // NSString*
+           atIndex:(NSUInteger)index {
+    let range = NSRange { location: index, length: 0 };
+    msg![env; this replaceCharactersInRange:range withString:string]
+}
+
+- (())deleteCharactersInRange:(NSRange)range {
+    let empty = get_static_str(env, "");
+    msg![env; this replaceCharactersInRange:range withString:empty]
+}
+
+- (())setString:(id)string { // NSString*
+    let length: NSUInteger = msg![env; this length];
+    let range = NSRange { location: 0, length };
+    msg![env; this replaceCharactersInRange:range withString:string]
+}
+
+@end
+
 // Our private subclass that is the single implementation of NSString for the
 // time being.
 @implementation _touchHLE_NSString: NSString
@@ -880,6 +1176,68 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 @end
 
+// Our private subclass that is the single implementation of NSMutableString
+// for the time being. It uses the same host object as _touchHLE_NSString, so
+// that NSString methods which access it directly also work for this class.
+@implementation _touchHLE_NSMutableString: NSMutableString
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(StringHostObject::Utf16(Vec::new()));
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+// TODO: more init methods
+
+- (id)initWithCapacity:(NSUInteger)capacity {
+    *env.objc.borrow_mut(this) = StringHostObject::Utf16(Vec::with_capacity(capacity as usize));
+    this
+}
+
+- (id)initWithString:(id)string { // NSString*
+    let mut code_units = Vec::new();
+    for_each_code_unit(env, string, |_, c| code_units.push(c));
+    *env.objc.borrow_mut(this) = StringHostObject::Utf16(code_units);
+    this
+}
+
+- (id)initWithUTF8String:(ConstPtr<u8>)utf8_string {
+    let string = env.mem.cstr_at_utf8(utf8_string).unwrap().to_owned();
+    *env.objc.borrow_mut(this) = StringHostObject::Utf8(string.into());
+    this
+}
+
+- (id)initWithFormat:(id)format, // NSString*
+                     ...args {
+    let res = with_format(env, format, args.start());
+    *env.objc.borrow_mut(this) = StringHostObject::Utf8(res.into());
+    this
+}
+
+- (id)initWithFormat:(id)format // NSString*
+           arguments:(VaList)args {
+    let res = with_format(env, format, args);
+    *env.objc.borrow_mut(this) = StringHostObject::Utf8(res.into());
+    this
+}
+
+- (())replaceCharactersInRange:(NSRange)range
+                    withString:(id)string { // NSString*
+    // The replacement must be copied first, in case it is this string.
+    let mut replacement = Vec::new();
+    for_each_code_unit(env, string, |_, c| replacement.push(c));
+
+    let NSRange { location, length } = range;
+    let start = location as usize;
+    let end = start + length as usize;
+    let (code_units, _) = env.objc
+        .borrow_mut::<StringHostObject>(this)
+        .convert_to_utf16_inplace();
+    assert!(end <= code_units.len()); // TODO: raise NSRangeException
+    code_units.splice(start..end, replacement);
+}
+
+@end
+
 // Specialised subclass for static-lifetime strings.
 // See `get_static_str`.
 @implementation _touchHLE_NSString_Static: _touchHLE_NSString
@@ -15,19 +15,49 @@ use crate::libc::pthread::thread::{
 use crate::mem::{guest_size_of, MutPtr};
 use crate::msg;
 use crate::objc::{
-    id, msg_send, nil, objc_classes, release, retain, Class, ClassExports, HostObject, NSZonePtr,
-    SEL,
+    id, msg_class, msg_send, nil, objc_classes, release, retain, Class, ClassExports, HostObject,
+    NSZonePtr, SEL,
 };
-use crate::Environment;
+use crate::{Environment, ThreadId};
+use std::collections::HashMap;
 use std::time::Duration;
 
+#[derive(Default)]
+pub struct State {
+    /// The `NSThread*` for each thread that has one. These are owned by the
+    /// map, and are created on demand for threads that weren't created by
+    /// `NSThread`.
+    threads: HashMap<ThreadId, id>,
+    is_multi_threaded: bool,
+}
+impl State {
+    fn get(env: &mut Environment) -> &mut State {
+        &mut env.framework_state.foundation.ns_thread
+    }
+}
+
 struct NSThreadHostObject {
     target: id,
     selector: Option<SEL>,
     object: id,
+    /// `NSMutableDictionary*`, created on first use.
+    thread_dictionary: id,
+    priority: f64,
 }
 impl HostObject for NSThreadHostObject {}
 
+/// Get the `NSThread*` for the current thread, creating one if necessary.
+fn current_thread(env: &mut Environment) -> id {
+    let current_thread = env.current_thread;
+    if let Some(&thread) = State::get(env).threads.get(&current_thread) {
+        return thread;
+    }
+    let thread: id = msg_class![env; NSThread alloc];
+    let thread: id = msg![env; thread init];
+    State::get(env).threads.insert(current_thread, thread);
+    thread
+}
+
 pub const CLASSES: ClassExports = objc_classes! {
 
 (env, this, _cmd);
@@ -39,25 +69,42 @@ pub const CLASSES: ClassExports = objc_classes! {
         target: nil,
         selector: None,
         object: nil,
+        thread_dictionary: nil,
+        priority: 0.5,
     });
     env.objc.alloc_object(this, host_object, &mut env.mem)
 }
 
 + (f64)threadPriority {
-    log!("TODO: [NSThread threadPriority] (not implemented yet)");
-    1.0
+    let thread = current_thread(env);
+    msg![env; thread threadPriority]
 }
 
 + (bool)setThreadPriority:(f64)priority {
-    log!("TODO: [NSThread setThreadPriority:{:?}] (ignored)", priority);
-    true
+    let thread = current_thread(env);
+    msg![env; thread setThreadPriority:priority]
 }
 
 + (id)currentThread {
-    // Simple hack to make the `setThreadPriority:` work as an instance method
-    // (it's both a class and an instance method). Must be replaced if we ever
-    // need to support other methods.
-    this
+    current_thread(env)
+}
+
++ (id)mainThread {
+    if env.current_thread == 0 {
+        return current_thread(env);
+    }
+    // The main thread's NSThread is always created before any other thread
+    // is started by NSThread, but not necessarily by pthreads.
+    let main_thread = State::get(env).threads.get(&0).copied();
+    main_thread.expect("TODO: create main thread's NSThread from another thread")
+}
+
++ (bool)isMainThread {
+    env.current_thread == 0
+}
+
++ (bool)isMultiThreaded {
+    State::get(env).is_multi_threaded
 }
 
 + (())sleepForTimeInterval:(NSTimeInterval)ti {
@@ -72,9 +119,10 @@ pub const CLASSES: ClassExports = objc_classes! {
         target,
         selector: Some(selector),
         object,
+        thread_dictionary: nil,
+        priority: 0.5,
     });
     let this = env.objc.alloc_object(this, host_object, &mut env.mem);
-    retain(env, this);
 
     retain(env, target);
     retain(env, object);
@@ -91,13 +139,52 @@ pub const CLASSES: ClassExports = objc_classes! {
     pthread_attr_setdetachstate(env, attr, PTHREAD_CREATE_DETACHED);
     let thread_ptr: MutPtr<pthread_t> = env.mem.alloc(guest_size_of::<pthread_t>()).cast();
 
-    pthread_create(env, thread_ptr, attr.cast_const(), gf, this.cast());
-
+    // Make sure the main thread has an NSThread, so that mainThread works
+    // from the new thread.
+    if env.current_thread == 0 {
+        current_thread(env);
+    }
     // TODO: post NSWillBecomeMultiThreadedNotification
+    State::get(env).is_multi_threaded = true;
+
+    pthread_create(env, thread_ptr, attr.cast_const(), gf, this.cast());
 }
 
 // TODO: construction etc
 
+- (())dealloc {
+    let &NSThreadHostObject {
+        thread_dictionary, ..
+    } = env.objc.borrow(this);
+    release(env, thread_dictionary);
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (id)threadDictionary {
+    let thread_dictionary = env.objc.borrow::<NSThreadHostObject>(this).thread_dictionary;
+    if thread_dictionary != nil {
+        return thread_dictionary;
+    }
+    let thread_dictionary: id = msg_class![env; NSMutableDictionary new];
+    env.objc.borrow_mut::<NSThreadHostObject>(this).thread_dictionary = thread_dictionary;
+    thread_dictionary
+}
+
+- (f64)threadPriority {
+    env.objc.borrow::<NSThreadHostObject>(this).priority
+}
+
+- (bool)setThreadPriority:(f64)priority {
+    log!("TODO: [(NSThread*){:?} setThreadPriority:{:?}] (ignored)", this, priority);
+    env.objc.borrow_mut::<NSThreadHostObject>(this).priority = priority;
+    true
+}
+
+- (bool)isMainThread {
+    let main_thread = State::get(env).threads.get(&0).copied();
+    main_thread == Some(this)
+}
+
 @end
 
 };
@@ -112,16 +199,28 @@ pub fn _touchHLE_NSThreadInvocationHelper(env: &mut Environment, ns_thread_obj:
     );
     assert_eq!(class, env.objc.get_known_class("NSThread", &mut env.mem));
 
+    let current_thread = env.current_thread;
+    // The NSThread object (already retained for us) is now owned by the map.
+    State::get(env)
+        .threads
+        .insert(current_thread, ns_thread_obj);
+
     let &NSThreadHostObject {
         target,
         selector,
         object,
+        ..
     } = env.objc.borrow(ns_thread_obj);
+
+    // Cocoa provides an autorelease pool for the new thread.
+    let pool: id = msg_class![env; NSAutoreleasePool new];
     () = msg_send(env, (target, selector.unwrap(), object));
+    release(env, pool);
 
     release(env, object);
     release(env, target);
 
+    State::get(env).threads.remove(&current_thread);
     release(env, ns_thread_obj);
 
     // TODO: NSThread exit
@@ -19,13 +19,16 @@ struct NSTimerHostObject {
     ns_interval: NSTimeInterval,
     /// Copy of `ns_interval` in Rust's type for time intervals. Keep in sync!
     rust_interval: Duration,
-    /// Strong reference
+    /// Strong reference, released when the timer is invalidated. Like in
+    /// Cocoa, this means a valid timer never fires on a deallocated object.
     target: id,
     selector: SEL,
-    /// Strong reference
+    /// Strong reference, released when the timer is invalidated.
     user_info: id,
     repeats: bool,
-    due_by: Option<Instant>,
+    /// Emulated time (see [crate::environment::Clock::elapsed]) at which the
+    /// timer should next fire, or [None] if it has been invalidated.
+    due_by: Option<Duration>,
     /// Weak reference
     run_loop: id,
 }
@@ -56,7 +59,7 @@ pub const CLASSES: ClassExports = objc_classes! {
         selector,
         user_info,
         repeats,
-        due_by: Some(Instant::now().checked_add(rust_interval).unwrap()),
+        due_by: Some(env.clock.elapsed().checked_add(rust_interval).unwrap()),
         run_loop: nil,
     });
     let new = env.objc.alloc_object(this, host_object, &mut env.mem);
@@ -119,10 +122,12 @@ pub const CLASSES: ClassExports = objc_classes! {
 }
 
 -(())invalidate {
-    // Timer might already be invalid, don't try to remove it twice.
-    if env.objc.borrow_mut::<NSTimerHostObject>(this).due_by.take().is_some() {
-        let run_loop: id = msg_class![env; NSRunLoop currentRunLoop];
-        ns_run_loop::remove_timer(env, run_loop, this);
+    invalidate(env, this);
+}
+
+- (())fire {
+    if env.objc.borrow::<NSTimerHostObject>(this).due_by.is_some() {
+        fire(env, this);
     }
 }
 
@@ -140,16 +145,70 @@ pub(super) fn set_run_loop(env: &mut Environment, timer: id, run_loop: id) {
     host_object.run_loop = run_loop;
 }
 
+/// Stop the timer from firing again, remove it from its run loop (if it is
+/// still in one) and release its target and user info.
+fn invalidate(env: &mut Environment, timer: id) {
+    let host_object = env.objc.borrow_mut::<NSTimerHostObject>(timer);
+    // Timer might already be invalid, don't try to remove it twice.
+    let was_valid = host_object.due_by.take().is_some();
+    let run_loop = host_object.run_loop;
+    let target = std::mem::replace(&mut host_object.target, nil);
+    let user_info = std::mem::replace(&mut host_object.user_info, nil);
+
+    // The timer might not be scheduled, e.g. if it was created with
+    // `timerWithTimeInterval:` and never added to a run loop.
+    if was_valid && run_loop != nil {
+        ns_run_loop::remove_timer(env, run_loop, timer);
+    }
+    release(env, target);
+    release(env, user_info);
+}
+
+/// Send the timer's message to its target. Non-repeating timers are
+/// invalidated afterwards.
+fn fire(env: &mut Environment, timer: id) {
+    let &NSTimerHostObject {
+        target,
+        selector,
+        repeats,
+        ..
+    } = env.objc.borrow(timer);
+
+    log_dbg!(
+        "Timer {:?} fired, sending {:?} message to {:?}",
+        timer,
+        selector.as_str(&env.mem),
+        target
+    );
+
+    // Timer may be released when it's invalidated, so we need to retain it so
+    // it's still around to pass to the timer target. The target is retained
+    // in case the timer is invalidated during the message send.
+    retain(env, timer);
+    retain(env, target);
+
+    let pool: id = msg_class![env; NSAutoreleasePool new];
+
+    // Signature should be `- (void)timerDidFire:(NSTimer *)which`.
+    let _: () = msg_send(env, (target, selector, timer));
+
+    if !repeats {
+        invalidate(env, timer);
+    }
+
+    release(env, target);
+    release(env, timer);
+    release(env, pool);
+}
+
 /// For use by `NSRunLoop`: check if a timer is due to fire and fire it if
 /// necessary.
 ///
-/// Returns the next firing time, if any.
+/// Returns the next firing time (as a host time, for sleeping), if any.
 pub(super) fn handle_timer(env: &mut Environment, timer: id) -> Option<Instant> {
     let &NSTimerHostObject {
         ns_interval,
         rust_interval,
-        target,
-        selector,
         repeats,
         due_by,
         run_loop,
@@ -159,16 +218,16 @@ pub(super) fn handle_timer(env: &mut Environment, timer: id) -> Option<Instant>
     // invalidated timers should have already been removed from the run loop
     let due_by = due_by.unwrap();
 
-    let now = Instant::now();
+    let now = env.clock.elapsed();
 
     if due_by > now {
-        return Some(due_by);
+        return Some(env.clock.host_instant_at(due_by));
     }
 
-    let overdue_by = now.duration_since(due_by);
+    let overdue_by = now - due_by;
 
-    // Timer may be released when it's invalidated, so we need to retain it so
-    // it's still around to pass to the timer target.
+    // The timer is released when it's removed from the run loop, so we need to
+    // retain it so it's still around after firing.
     retain(env, timer);
 
     // Advancing the timer before sending its message seems like a good idea
@@ -196,25 +255,16 @@ pub(super) fn handle_timer(env: &mut Environment, timer: id) -> Option<Instant>
         let advance_by = rust_interval.checked_mul(advance_by).unwrap();
         Some(due_by.checked_add(advance_by).unwrap())
     } else {
+        // The target and user info are kept until after firing, see fire().
         ns_run_loop::remove_timer(env, run_loop, timer);
         None
     };
     env.objc.borrow_mut::<NSTimerHostObject>(timer).due_by = new_due_by;
 
-    log_dbg!(
-        "Timer {:?} fired, sending {:?} message to {:?}",
-        timer,
-        selector.as_str(&env.mem),
-        target
-    );
-
-    let pool: id = msg_class![env; NSAutoreleasePool new];
-
-    // Signature should be `- (void)timerDidFire:(NSTimer *)which`.
-    let _: () = msg_send(env, (target, selector, timer));
+    fire(env, timer);
 
+    // The timer may have been invalidated during firing.
+    let due_by = env.objc.borrow::<NSTimerHostObject>(timer).due_by;
     release(env, timer);
-    release(env, pool);
-
-    new_due_by
+    due_by.map(|due_by| env.clock.host_instant_at(due_by))
 }
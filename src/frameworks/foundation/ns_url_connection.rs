@@ -0,0 +1,400 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSURLConnection`.
+//!
+//! Only HTTP and HTTPS are supported. Requests are performed on the host with
+//! `ureq`, either blocking the guest thread (synchronous requests) or on a
+//! host thread whose result is picked up by the run loop (asynchronous
+//! requests). The whole response is downloaded before the delegate is told
+//! about it.
+//!
+//! Network access is disabled unless the `--network-access` option is used.
+//! Otherwise, requests fail as if there were no internet connection.
+
+use super::ns_error::{new_error, set_error, NSURLErrorDomain};
+use super::ns_run_loop::{add_url_connection, remove_url_connection};
+use super::ns_url_request::{request_info, RequestInfo};
+use super::ns_url_response::new_http_response;
+use super::{ns_data, NSInteger};
+use crate::mem::MutPtr;
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, ClassExports, HostObject,
+    NSZonePtr,
+};
+use crate::Environment;
+use std::io::Read;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::time::Duration;
+
+// Some codes in NSURLErrorDomain.
+pub const NSURLErrorUnknown: NSInteger = -1;
+pub const NSURLErrorBadURL: NSInteger = -1000;
+pub const NSURLErrorTimedOut: NSInteger = -1001;
+pub const NSURLErrorUnsupportedURL: NSInteger = -1002;
+pub const NSURLErrorCannotFindHost: NSInteger = -1003;
+pub const NSURLErrorCannotConnectToHost: NSInteger = -1004;
+pub const NSURLErrorNetworkConnectionLost: NSInteger = -1005;
+pub const NSURLErrorNotConnectedToInternet: NSInteger = -1009;
+
+/// The parts of an HTTP response that are passed back to the guest.
+struct HttpResponse {
+    url: String,
+    status_code: NSInteger,
+    header_fields: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+/// An `NSURLErrorDomain` error code and description.
+type HttpError = (NSInteger, String);
+
+type HttpResult = Result<HttpResponse, HttpError>;
+
+/// Perform an HTTP request on the host. This blocks until the whole response
+/// has been received.
+fn perform_request(request: RequestInfo) -> HttpResult {
+    let scheme = request.url.split_once(':').map(|(scheme, _)| scheme);
+    let scheme = scheme.unwrap_or("").to_ascii_lowercase();
+    if scheme != "http" && scheme != "https" {
+        return Err((
+            NSURLErrorUnsupportedURL,
+            format!("Unsupported URL {:?}", request.url),
+        ));
+    }
+
+    let mut agent = ureq::AgentBuilder::new();
+    if request.timeout_interval > 0.0 {
+        agent = agent.timeout(Duration::from_secs_f64(request.timeout_interval));
+    }
+    let agent = agent.build();
+
+    let mut http_request = agent.request(&request.method, &request.url);
+    for (name, value) in &request.header_fields {
+        http_request = http_request.set(name, value);
+    }
+    let result = if request.body.is_empty() {
+        http_request.call()
+    } else {
+        http_request.send_bytes(&request.body)
+    };
+    let response = match result {
+        // Error status codes are not errors for NSURLConnection.
+        Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+        Err(err) => {
+            let code = match err.kind() {
+                ureq::ErrorKind::InvalidUrl => NSURLErrorBadURL,
+                ureq::ErrorKind::UnknownScheme => NSURLErrorUnsupportedURL,
+                ureq::ErrorKind::Dns => NSURLErrorCannotFindHost,
+                ureq::ErrorKind::ConnectionFailed => NSURLErrorCannotConnectToHost,
+                ureq::ErrorKind::Io => NSURLErrorNetworkConnectionLost,
+                _ => NSURLErrorUnknown,
+            };
+            return Err((
+                code,
+                format!("Request to {:?} failed: {}", request.url, err),
+            ));
+        }
+    };
+
+    let url = response.get_url().to_string();
+    let status_code = response.status().into();
+    let header_fields = response
+        .headers_names()
+        .into_iter()
+        .flat_map(|name| {
+            response
+                .all(&name)
+                .into_iter()
+                .map(|value| (name.clone(), value.to_string()))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let mut body = Vec::new();
+    if let Err(err) = response.into_reader().read_to_end(&mut body) {
+        let code = if err.kind() == std::io::ErrorKind::TimedOut {
+            NSURLErrorTimedOut
+        } else {
+            NSURLErrorNetworkConnectionLost
+        };
+        return Err((
+            code,
+            format!("Request to {:?} failed: {}", request.url, err),
+        ));
+    }
+
+    Ok(HttpResponse {
+        url,
+        status_code,
+        header_fields,
+        body,
+    })
+}
+
+/// Get the details of a request, or an error if network access is disabled.
+fn prepare_request(env: &mut Environment, request: id) -> Result<RequestInfo, HttpError> {
+    let request = request_info(env, request);
+    log_dbg!("{} request to {:?}", request.method, request.url);
+    if !env.options.network_access {
+        log!(
+            "Blocked network request to {:?}. Use the --network-access option to allow it.",
+            request.url
+        );
+        return Err((
+            NSURLErrorNotConnectedToInternet,
+            "Network access is disabled".to_string(),
+        ));
+    }
+    Ok(request)
+}
+
+enum ConnectionState {
+    NotStarted,
+    /// The request is being performed on a host thread.
+    Loading(Receiver<HttpResult>),
+    /// The request finished, failed or was cancelled.
+    Done,
+}
+
+struct NSURLConnectionHostObject {
+    /// `NSURLRequest*`
+    request: id,
+    /// Strong reference, released once the connection is done.
+    delegate: id,
+    /// The `NSRunLoop*` the delegate is called from. Weak reference.
+    run_loop: id,
+    state: ConnectionState,
+}
+impl HostObject for NSURLConnectionHostObject {}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSURLConnection: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSURLConnectionHostObject {
+        request: nil,
+        delegate: nil,
+        run_loop: nil,
+        state: ConnectionState::NotStarted,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
++ (bool)canHandleRequest:(id)request { // NSURLRequest*
+    let url = request_info(env, request).url;
+    url.starts_with("http:") || url.starts_with("https:")
+}
+
++ (id)sendSynchronousRequest:(id)request // NSURLRequest*
+           returningResponse:(MutPtr<id>)response // NSURLResponse**
+                       error:(MutPtr<id>)error { // NSError**
+    let result = prepare_request(env, request).and_then(perform_request);
+    match result {
+        Ok(HttpResponse {
+            url,
+            status_code,
+            header_fields,
+            body,
+        }) => {
+            if !response.is_null() {
+                let new_response = new_http_response(env, &url, status_code, &header_fields);
+                let new_response = autorelease(env, new_response);
+                env.mem.write(response, new_response);
+            }
+            let data = ns_data::from_vec(env, body);
+            autorelease(env, data)
+        }
+        Err((code, description)) => {
+            if !response.is_null() {
+                env.mem.write(response, nil);
+            }
+            set_error(env, error, NSURLErrorDomain, code, description);
+            nil
+        }
+    }
+}
+
++ (id)connectionWithRequest:(id)request // NSURLRequest*
+                   delegate:(id)delegate {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithRequest:request delegate:delegate];
+    autorelease(env, new)
+}
+
+- (id)initWithRequest:(id)request // NSURLRequest*
+             delegate:(id)delegate {
+    msg![env; this initWithRequest:request delegate:delegate startImmediately:true]
+}
+
+- (id)initWithRequest:(id)request // NSURLRequest*
+             delegate:(id)delegate
+     startImmediately:(bool)start_immediately {
+    let request: id = msg![env; request copy];
+    retain(env, delegate);
+    let host_object = env.objc.borrow_mut::<NSURLConnectionHostObject>(this);
+    host_object.request = request;
+    host_object.delegate = delegate;
+    if start_immediately {
+        () = msg![env; this start];
+    }
+    this
+}
+
+- (())dealloc {
+    let &NSURLConnectionHostObject {
+        request, delegate, ..
+    } = env.objc.borrow(this);
+    release(env, request);
+    release(env, delegate);
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (())scheduleInRunLoop:(id)run_loop // NSRunLoop*
+                forMode:(id)_mode { // NSRunLoopMode
+    // TODO: handle run loop modes
+    let host_object = env.objc.borrow_mut::<NSURLConnectionHostObject>(this);
+    assert!(matches!(host_object.state, ConnectionState::NotStarted));
+    host_object.run_loop = run_loop;
+}
+
+- (())start {
+    let host_object = env.objc.borrow::<NSURLConnectionHostObject>(this);
+    if !matches!(host_object.state, ConnectionState::NotStarted) {
+        return;
+    }
+    let request = host_object.request;
+    let run_loop = host_object.run_loop;
+    let run_loop = if run_loop == nil {
+        msg_class![env; NSRunLoop currentRunLoop]
+    } else {
+        run_loop
+    };
+
+    let (sender, receiver) = channel();
+    match prepare_request(env, request) {
+        Ok(request) => {
+            std::thread::spawn(move || {
+                // If the connection was cancelled, nobody is listening anymore.
+                let _ = sender.send(perform_request(request));
+            });
+        }
+        // Report the error from the run loop, like other results.
+        Err(err) => sender.send(Err(err)).unwrap(),
+    }
+
+    let host_object = env.objc.borrow_mut::<NSURLConnectionHostObject>(this);
+    host_object.run_loop = run_loop;
+    host_object.state = ConnectionState::Loading(receiver);
+    add_url_connection(env, run_loop, this);
+}
+
+- (())cancel {
+    let host_object = env.objc.borrow_mut::<NSURLConnectionHostObject>(this);
+    let state = std::mem::replace(&mut host_object.state, ConnectionState::Done);
+    if let ConnectionState::Loading(_) = state {
+        finish(env, this);
+    }
+}
+
+@end
+
+};
+
+/// Stop tracking a connection: remove it from its run loop and release its
+/// delegate.
+fn finish(env: &mut Environment, connection: id) {
+    let host_object = env.objc.borrow_mut::<NSURLConnectionHostObject>(connection);
+    let delegate = std::mem::replace(&mut host_object.delegate, nil);
+    let run_loop = host_object.run_loop;
+    remove_url_connection(env, run_loop, connection);
+    release(env, delegate);
+}
+
+/// For use by `NSRunLoop`: check if an asynchronous request has finished, and
+/// if so, notify the delegate.
+pub(super) fn handle_connection(env: &mut Environment, connection: id) {
+    let host_object = env.objc.borrow::<NSURLConnectionHostObject>(connection);
+    let ConnectionState::Loading(ref receiver) = host_object.state else {
+        return;
+    };
+    let result = match receiver.try_recv() {
+        Ok(result) => result,
+        Err(TryRecvError::Empty) => return,
+        Err(TryRecvError::Disconnected) => Err((
+            NSURLErrorUnknown,
+            "Request thread exited unexpectedly".to_string(),
+        )),
+    };
+    let delegate = host_object.delegate;
+    env.objc
+        .borrow_mut::<NSURLConnectionHostObject>(connection)
+        .state = ConnectionState::Done;
+
+    // The delegate methods can release the connection and the delegate.
+    retain(env, connection);
+    retain(env, delegate);
+    finish(env, connection);
+    if delegate == nil {
+        release(env, connection);
+        return;
+    }
+
+    match result {
+        Ok(HttpResponse {
+            url,
+            status_code,
+            header_fields,
+            body,
+        }) => {
+            log_dbg!(
+                "Connection {:?} received response {} with {} bytes",
+                connection,
+                status_code,
+                body.len()
+            );
+            if env.objc.object_has_method_named(
+                &env.mem,
+                delegate,
+                "connection:didReceiveResponse:",
+            ) {
+                let response = new_http_response(env, &url, status_code, &header_fields);
+                () = msg![env; delegate connection:connection didReceiveResponse:response];
+                release(env, response);
+            }
+            if !body.is_empty()
+                && env.objc.object_has_method_named(
+                    &env.mem,
+                    delegate,
+                    "connection:didReceiveData:",
+                )
+            {
+                let data = ns_data::from_vec(env, body);
+                () = msg![env; delegate connection:connection didReceiveData:data];
+                release(env, data);
+            }
+            if env
+                .objc
+                .object_has_method_named(&env.mem, delegate, "connectionDidFinishLoading:")
+            {
+                () = msg![env; delegate connectionDidFinishLoading:connection];
+            }
+        }
+        Err((code, description)) => {
+            log_dbg!("Connection {:?} failed: {}", connection, description);
+            if env
+                .objc
+                .object_has_method_named(&env.mem, delegate, "connection:didFailWithError:")
+            {
+                let error = new_error(env, NSURLErrorDomain, code, description);
+                () = msg![env; delegate connection:connection didFailWithError:error];
+            }
+        }
+    }
+
+    release(env, delegate);
+    release(env, connection);
+}
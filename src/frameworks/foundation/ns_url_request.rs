@@ -0,0 +1,275 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSURLRequest` and `NSMutableURLRequest`.
+
+use super::ns_dictionary::dict_from_keys_and_objects;
+use super::ns_string::{from_rust_string, to_rust_string};
+use super::{ns_data, NSTimeInterval, NSUInteger};
+use crate::objc::{
+    autorelease, id, msg, nil, objc_classes, release, retain, ClassExports, HostObject, NSZonePtr,
+};
+use crate::Environment;
+
+pub type NSURLRequestCachePolicy = NSUInteger;
+pub const NSURLRequestUseProtocolCachePolicy: NSURLRequestCachePolicy = 0;
+
+const DEFAULT_TIMEOUT_INTERVAL: NSTimeInterval = 60.0;
+
+#[derive(Clone)]
+struct NSURLRequestHostObject {
+    /// `NSURL*`
+    url: id,
+    cache_policy: NSURLRequestCachePolicy,
+    timeout_interval: NSTimeInterval,
+    http_method: String,
+    /// Header names and values, in the order they were set.
+    http_header_fields: Vec<(String, String)>,
+    /// `NSData*`
+    http_body: id,
+}
+impl HostObject for NSURLRequestHostObject {}
+
+/// What host code needs to know to perform a request.
+pub struct RequestInfo {
+    pub url: String,
+    pub method: String,
+    pub header_fields: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub timeout_interval: NSTimeInterval,
+}
+
+/// Shortcut for host code: get the details of an `NSURLRequest`.
+pub fn request_info(env: &mut Environment, request: id) -> RequestInfo {
+    let host_object = env.objc.borrow::<NSURLRequestHostObject>(request).clone();
+    let url = if host_object.url == nil {
+        String::new()
+    } else {
+        let url: id = msg![env; (host_object.url) absoluteString];
+        to_rust_string(env, url).into_owned()
+    };
+    let body = if host_object.http_body == nil {
+        Vec::new()
+    } else {
+        let length: NSUInteger = msg![env; (host_object.http_body) length];
+        if length == 0 {
+            Vec::new()
+        } else {
+            ns_data::to_rust_slice(env, host_object.http_body).to_vec()
+        }
+    };
+    RequestInfo {
+        url,
+        method: host_object.http_method,
+        header_fields: host_object.http_header_fields,
+        body,
+        timeout_interval: host_object.timeout_interval,
+    }
+}
+
+fn find_header_field(fields: &[(String, String)], name: &str) -> Option<usize> {
+    // Header field names are case-insensitive.
+    fields
+        .iter()
+        .position(|(field_name, _)| field_name.eq_ignore_ascii_case(name))
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSURLRequest: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSURLRequestHostObject {
+        url: nil,
+        cache_policy: NSURLRequestUseProtocolCachePolicy,
+        timeout_interval: DEFAULT_TIMEOUT_INTERVAL,
+        http_method: "GET".to_string(),
+        http_header_fields: Vec::new(),
+        http_body: nil,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
++ (id)requestWithURL:(id)url { // NSURL*
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithURL:url];
+    autorelease(env, new)
+}
+
++ (id)requestWithURL:(id)url // NSURL*
+         cachePolicy:(NSURLRequestCachePolicy)cache_policy
+     timeoutInterval:(NSTimeInterval)timeout_interval {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithURL:url
+                                cachePolicy:cache_policy
+                            timeoutInterval:timeout_interval];
+    autorelease(env, new)
+}
+
+- (id)initWithURL:(id)url { // NSURL*
+    msg![env; this initWithURL:url
+                   cachePolicy:NSURLRequestUseProtocolCachePolicy
+               timeoutInterval:DEFAULT_TIMEOUT_INTERVAL]
+}
+
+- (id)initWithURL:(id)url // NSURL*
+      cachePolicy:(NSURLRequestCachePolicy)cache_policy
+  timeoutInterval:(NSTimeInterval)timeout_interval {
+    let url: id = msg![env; url copy];
+    let host_object = env.objc.borrow_mut::<NSURLRequestHostObject>(this);
+    host_object.url = url;
+    host_object.cache_policy = cache_policy;
+    host_object.timeout_interval = timeout_interval;
+    this
+}
+
+- (())dealloc {
+    let &NSURLRequestHostObject { url, http_body, .. } = env.objc.borrow(this);
+    release(env, url);
+    release(env, http_body);
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+// NSCopying implementation
+- (id)copyWithZone:(NSZonePtr)_zone {
+    retain(env, this)
+}
+
+// NSMutableCopying implementation
+- (id)mutableCopyWithZone:(NSZonePtr)_zone {
+    let host_object = env.objc.borrow::<NSURLRequestHostObject>(this).clone();
+    retain(env, host_object.url);
+    retain(env, host_object.http_body);
+    let class = env.objc.get_known_class("NSMutableURLRequest", &mut env.mem);
+    env.objc.alloc_object(class, Box::new(host_object), &mut env.mem)
+}
+
+- (id)URL {
+    env.objc.borrow::<NSURLRequestHostObject>(this).url
+}
+- (NSURLRequestCachePolicy)cachePolicy {
+    env.objc.borrow::<NSURLRequestHostObject>(this).cache_policy
+}
+- (NSTimeInterval)timeoutInterval {
+    env.objc.borrow::<NSURLRequestHostObject>(this).timeout_interval
+}
+
+- (id)HTTPMethod {
+    let method = env.objc.borrow::<NSURLRequestHostObject>(this).http_method.clone();
+    let method = from_rust_string(env, method);
+    autorelease(env, method)
+}
+- (id)HTTPBody {
+    env.objc.borrow::<NSURLRequestHostObject>(this).http_body
+}
+
+- (id)allHTTPHeaderFields {
+    let fields = env.objc.borrow::<NSURLRequestHostObject>(this).http_header_fields.clone();
+    let keys_and_objects: Vec<(id, id)> = fields
+        .into_iter()
+        .map(|(name, value)| (from_rust_string(env, name), from_rust_string(env, value)))
+        .collect();
+    let dict = dict_from_keys_and_objects(env, &keys_and_objects);
+    for (key, object) in keys_and_objects {
+        release(env, key);
+        release(env, object);
+    }
+    autorelease(env, dict)
+}
+- (id)valueForHTTPHeaderField:(id)field { // NSString*
+    let field = to_rust_string(env, field);
+    let fields = &env.objc.borrow::<NSURLRequestHostObject>(this).http_header_fields;
+    let Some(index) = find_header_field(fields, &field) else {
+        return nil;
+    };
+    let value = fields[index].1.clone();
+    let value = from_rust_string(env, value);
+    autorelease(env, value)
+}
+
+@end
+
+@implementation NSMutableURLRequest: NSURLRequest
+
+// NSCopying implementation
+- (id)copyWithZone:(NSZonePtr)_zone {
+    let host_object = env.objc.borrow::<NSURLRequestHostObject>(this).clone();
+    retain(env, host_object.url);
+    retain(env, host_object.http_body);
+    let class = env.objc.get_known_class("NSURLRequest", &mut env.mem);
+    env.objc.alloc_object(class, Box::new(host_object), &mut env.mem)
+}
+
+- (())setURL:(id)url { // NSURL*
+    let url: id = msg![env; url copy];
+    let host_object = env.objc.borrow_mut::<NSURLRequestHostObject>(this);
+    let old_url = std::mem::replace(&mut host_object.url, url);
+    release(env, old_url);
+}
+- (())setCachePolicy:(NSURLRequestCachePolicy)cache_policy {
+    env.objc.borrow_mut::<NSURLRequestHostObject>(this).cache_policy = cache_policy;
+}
+- (())setTimeoutInterval:(NSTimeInterval)timeout_interval {
+    env.objc.borrow_mut::<NSURLRequestHostObject>(this).timeout_interval = timeout_interval;
+}
+
+- (())setHTTPMethod:(id)method { // NSString*
+    let method = to_rust_string(env, method).into_owned();
+    env.objc.borrow_mut::<NSURLRequestHostObject>(this).http_method = method;
+}
+- (())setHTTPBody:(id)body { // NSData*
+    let body: id = msg![env; body copy];
+    let host_object = env.objc.borrow_mut::<NSURLRequestHostObject>(this);
+    let old_body = std::mem::replace(&mut host_object.http_body, body);
+    release(env, old_body);
+}
+
+- (())setValue:(id)value // NSString*
+forHTTPHeaderField:(id)field { // NSString*
+    let field = to_rust_string(env, field).into_owned();
+    let value = (value != nil).then(|| to_rust_string(env, value).into_owned());
+    let fields = &mut env.objc.borrow_mut::<NSURLRequestHostObject>(this).http_header_fields;
+    match (find_header_field(fields, &field), value) {
+        (Some(index), Some(value)) => fields[index].1 = value,
+        (Some(index), None) => {
+            fields.remove(index);
+        }
+        (None, Some(value)) => fields.push((field, value)),
+        (None, None) => (),
+    }
+}
+- (())addValue:(id)value // NSString*
+forHTTPHeaderField:(id)field { // NSString*
+    let field = to_rust_string(env, field).into_owned();
+    let value = to_rust_string(env, value).into_owned();
+    let fields = &mut env.objc.borrow_mut::<NSURLRequestHostObject>(this).http_header_fields;
+    match find_header_field(fields, &field) {
+        Some(index) => {
+            let existing = &mut fields[index].1;
+            existing.push(',');
+            existing.push_str(&value);
+        }
+        None => fields.push((field, value)),
+    }
+}
+- (())setAllHTTPHeaderFields:(id)fields { // NSDictionary*
+    let keys: id = msg![env; fields allKeys];
+    let count: NSUInteger = msg![env; keys count];
+    let mut new_fields = Vec::new();
+    for i in 0..count {
+        let key: id = msg![env; keys objectAtIndex:i];
+        let value: id = msg![env; fields objectForKey:key];
+        let key = to_rust_string(env, key).into_owned();
+        let value = to_rust_string(env, value).into_owned();
+        new_fields.push((key, value));
+    }
+    env.objc.borrow_mut::<NSURLRequestHostObject>(this).http_header_fields = new_fields;
+}
+
+@end
+
+};
@@ -0,0 +1,172 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSURLResponse` and `NSHTTPURLResponse`.
+
+use super::ns_dictionary::dict_from_keys_and_objects;
+use super::ns_string::from_rust_string;
+use super::NSInteger;
+use crate::objc::{
+    autorelease, id, msg, msg_class, nil, objc_classes, release, ClassExports, HostObject,
+    NSZonePtr,
+};
+use crate::Environment;
+
+/// `NSURLResponseUnknownLength`
+const NSURLResponseUnknownLength: i64 = -1;
+
+struct NSURLResponseHostObject {
+    /// `NSURL*`
+    url: id,
+    /// `NSString*`
+    mime_type: id,
+    expected_content_length: i64,
+    /// `NSString*`
+    text_encoding_name: id,
+    /// Only meaningful for `NSHTTPURLResponse`.
+    status_code: NSInteger,
+    /// `NSDictionary*`, only meaningful for `NSHTTPURLResponse`.
+    all_header_fields: id,
+}
+impl HostObject for NSURLResponseHostObject {}
+
+/// Shortcut for host code: create a new `NSHTTPURLResponse` from the parts of
+/// an HTTP response.
+pub fn new_http_response(
+    env: &mut Environment,
+    url: &str,
+    status_code: NSInteger,
+    header_fields: &[(String, String)],
+) -> id {
+    let find_field = |name: &str| {
+        header_fields
+            .iter()
+            .find(|(field_name, _)| field_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    };
+    let expected_content_length = find_field("Content-Length")
+        .and_then(|length| length.trim().parse().ok())
+        .unwrap_or(NSURLResponseUnknownLength);
+    // e.g. "text/html; charset=utf-8"
+    let content_type = find_field("Content-Type").unwrap_or("");
+    let mut content_type_parts = content_type.split(';').map(str::trim);
+    let mime_type = content_type_parts
+        .next()
+        .filter(|mime_type| !mime_type.is_empty())
+        .map(|mime_type| mime_type.to_ascii_lowercase());
+    let text_encoding_name = content_type_parts
+        .filter_map(|part| part.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+        .map(|(_, value)| value.trim().trim_matches('"').to_string());
+
+    let url = from_rust_string(env, url.to_string());
+    let ns_url: id = msg_class![env; NSURL alloc];
+    let ns_url: id = msg![env; ns_url initWithString:url];
+    release(env, url);
+    let mime_type = mime_type.map_or(nil, |mime_type| from_rust_string(env, mime_type));
+    let text_encoding_name = text_encoding_name.map_or(nil, |text_encoding_name| {
+        from_rust_string(env, text_encoding_name)
+    });
+
+    let keys_and_objects: Vec<(id, id)> = header_fields
+        .iter()
+        .map(|(name, value)| {
+            (
+                from_rust_string(env, name.clone()),
+                from_rust_string(env, value.clone()),
+            )
+        })
+        .collect();
+    let all_header_fields = dict_from_keys_and_objects(env, &keys_and_objects);
+    for (key, object) in keys_and_objects {
+        release(env, key);
+        release(env, object);
+    }
+
+    let host_object = Box::new(NSURLResponseHostObject {
+        url: ns_url,
+        mime_type,
+        expected_content_length,
+        text_encoding_name,
+        status_code,
+        all_header_fields,
+    });
+    let class = env.objc.get_known_class("NSHTTPURLResponse", &mut env.mem);
+    env.objc.alloc_object(class, host_object, &mut env.mem)
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSURLResponse: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSURLResponseHostObject {
+        url: nil,
+        mime_type: nil,
+        expected_content_length: NSURLResponseUnknownLength,
+        text_encoding_name: nil,
+        status_code: 0,
+        all_header_fields: nil,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (())dealloc {
+    let &NSURLResponseHostObject {
+        url,
+        mime_type,
+        text_encoding_name,
+        all_header_fields,
+        ..
+    } = env.objc.borrow(this);
+    release(env, url);
+    release(env, mime_type);
+    release(env, text_encoding_name);
+    release(env, all_header_fields);
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (id)URL {
+    env.objc.borrow::<NSURLResponseHostObject>(this).url
+}
+- (id)MIMEType {
+    env.objc.borrow::<NSURLResponseHostObject>(this).mime_type
+}
+- (i64)expectedContentLength {
+    env.objc.borrow::<NSURLResponseHostObject>(this).expected_content_length
+}
+- (id)textEncodingName {
+    env.objc.borrow::<NSURLResponseHostObject>(this).text_encoding_name
+}
+
+@end
+
+@implementation NSHTTPURLResponse: NSURLResponse
+
++ (id)localizedStringForStatusCode:(NSInteger)status_code {
+    let reason = match status_code {
+        100..=199 => "informational",
+        200..=299 => "success",
+        300..=399 => "redirected",
+        400..=499 => "client error",
+        500..=599 => "server error",
+        _ => "unknown",
+    };
+    let reason = from_rust_string(env, reason.to_string());
+    autorelease(env, reason)
+}
+
+- (NSInteger)statusCode {
+    env.objc.borrow::<NSURLResponseHostObject>(this).status_code
+}
+- (id)allHeaderFields {
+    env.objc.borrow::<NSURLResponseHostObject>(this).all_header_fields
+}
+
+@end
+
+};
@@ -8,14 +8,21 @@
 //! References:
 //! - Apple's [Preferences and Settings Programming Guide](https://developer.apple.com/library/archive/documentation/Cocoa/Conceptual/UserDefaults/AboutPreferenceDomains/AboutPreferenceDomains.html).
 
-use super::ns_dictionary::dict_from_keys_and_objects;
-use super::ns_string;
-use crate::objc::{id, msg_class, objc_classes, ClassExports};
+use super::ns_dictionary::DictionaryHostObject;
+use super::ns_property_list_serialization::{deserialize_plist, serialize_plist};
+use super::{ns_string, NSInteger};
+use crate::fs::GuestPathBuf;
+use crate::objc::{
+    id, msg, msg_class, nil, objc_classes, release, Class, ClassExports, HostObject, NSZonePtr,
+};
 use crate::Environment;
+use plist::{Dictionary, Value};
+use std::collections::HashMap;
+use std::io::Cursor;
 
 #[derive(Default)]
 pub struct State {
-    /// `NSDictionary*`
+    /// `NSUserDefaults*`
     standard_defaults: Option<id>,
 }
 impl State {
@@ -24,26 +31,260 @@ impl State {
     }
 }
 
+struct UserDefaultsHostObject {
+    /// Values set by the app, which are persisted. The values are owned
+    /// immutable copies.
+    values: HashMap<String, id>,
+    /// Values from `registerDefaults:`, which are not persisted. The values
+    /// are owned.
+    registered: HashMap<String, id>,
+    /// Whether `values` has changed since it was last written to disk.
+    dirty: bool,
+}
+impl HostObject for UserDefaultsHostObject {}
+
+/// Guest path of the plist file the app's defaults are stored in.
+fn defaults_path(env: &Environment) -> GuestPathBuf {
+    env.fs
+        .home_directory()
+        .join("Library/Preferences")
+        .join(format!("{}.plist", env.bundle.bundle_identifier()))
+}
+
+/// Read the persisted defaults. Returns an empty map if there are none.
+fn load_defaults(env: &mut Environment) -> HashMap<String, id> {
+    let path = defaults_path(env);
+    let Ok(bytes) = env.fs.read(&path) else {
+        log_dbg!("No user defaults found at {:?}.", path);
+        return HashMap::new();
+    };
+    let Ok(Value::Dictionary(dict)) = Value::from_reader(Cursor::new(bytes)) else {
+        log!("Couldn't parse user defaults at {:?}, ignoring.", path);
+        return HashMap::new();
+    };
+    log_dbg!("Loaded user defaults from {:?}.", path);
+    dict.iter()
+        .map(|(key, value)| (key.clone(), deserialize_plist(env, value)))
+        .collect()
+}
+
+/// Write the persisted defaults to disk.
+fn save_defaults(env: &mut Environment, defaults: id) -> bool {
+    let values: Vec<(String, id)> = env
+        .objc
+        .borrow::<UserDefaultsHostObject>(defaults)
+        .values
+        .iter()
+        .map(|(key, &value)| (key.clone(), value))
+        .collect();
+    let mut dict = Dictionary::new();
+    for (key, value) in values {
+        // setObject:forKey: has already checked this.
+        dict.insert(key, serialize_plist(env, value).unwrap());
+    }
+
+    let mut bytes = Vec::new();
+    Value::Dictionary(dict)
+        .to_writer_binary(&mut bytes)
+        .unwrap();
+    let path = defaults_path(env);
+    if env.fs.write(&path, &bytes).is_err() {
+        log!("Couldn't write user defaults to {:?}!", path);
+        return false;
+    }
+    log_dbg!("Wrote user defaults to {:?}.", path);
+    true
+}
+
+/// Shared implementation of `setObject:forKey:` and `removeObjectForKey:`.
+/// `value` must already be an owned reference, or `nil`.
+fn set_value(env: &mut Environment, defaults: id, key: id, value: id) {
+    let key = ns_string::to_rust_string(env, key).into_owned();
+    let host_object = env.objc.borrow_mut::<UserDefaultsHostObject>(defaults);
+    host_object.dirty = true;
+    let old = if value == nil {
+        host_object.values.remove(&key)
+    } else {
+        host_object.values.insert(key, value)
+    };
+    if let Some(old) = old {
+        release(env, old);
+    }
+}
+
 pub const CLASSES: ClassExports = objc_classes! {
 
 (env, this, _cmd);
 
 @implementation NSUserDefaults: NSObject
 
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(UserDefaultsHostObject {
+        values: HashMap::new(),
+        registered: HashMap::new(),
+        dirty: false,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
 + (id)standardUserDefaults {
     if let Some(existing) = State::get(env).standard_defaults {
-        existing
+        return existing;
+    }
+
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new init];
+
+    // TODO: Are there other default keys we need to set?
+    let langs_value: id = msg_class![env; NSLocale preferredLanguages];
+    let langs_value: id = msg![env; langs_value copy];
+    env.objc
+        .borrow_mut::<UserDefaultsHostObject>(new)
+        .registered
+        .insert("AppleLanguages".to_string(), langs_value);
+
+    let values = load_defaults(env);
+    env.objc.borrow_mut::<UserDefaultsHostObject>(new).values = values;
+
+    State::get(env).standard_defaults = Some(new);
+    new
+}
+
+- (())dealloc {
+    let host_object = env.objc.borrow_mut::<UserDefaultsHostObject>(this);
+    let values = std::mem::take(&mut host_object.values);
+    let registered = std::mem::take(&mut host_object.registered);
+    for value in values.into_values().chain(registered.into_values()) {
+        release(env, value);
+    }
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (id)objectForKey:(id)key { // NSString*
+    let key = ns_string::to_rust_string(env, key);
+    let host_object = env.objc.borrow::<UserDefaultsHostObject>(this);
+    host_object
+        .values
+        .get(&*key)
+        .or_else(|| host_object.registered.get(&*key))
+        .copied()
+        .unwrap_or(nil)
+}
+
+- (())setObject:(id)value
+         forKey:(id)key { // NSString*
+    if value == nil {
+        set_value(env, this, key, nil);
+        return;
+    }
+    if serialize_plist(env, value).is_none() {
+        // TODO: raise NSInvalidArgumentException, like Apple's implementation.
+        // Ignoring the call is the closest we can get without exceptions.
+        log!(
+            "Attempt to insert non-property list object {:?} for key {:?} into NSUserDefaults, ignoring.",
+            value,
+            key
+        );
+        return;
+    }
+    let value: id = msg![env; value copy];
+    set_value(env, this, key, value);
+}
+
+- (())removeObjectForKey:(id)key { // NSString*
+    set_value(env, this, key, nil);
+}
+
+- (())registerDefaults:(id)dict { // NSDictionary*
+    let keys: Vec<id> = env
+        .objc
+        .borrow::<DictionaryHostObject>(dict)
+        .iter_keys()
+        .collect();
+    for key in keys {
+        let value: id = msg![env; dict objectForKey:key];
+        let value: id = msg![env; value copy];
+        let key = ns_string::to_rust_string(env, key).into_owned();
+        let host_object = env.objc.borrow_mut::<UserDefaultsHostObject>(this);
+        if let Some(old) = host_object.registered.insert(key, value) {
+            release(env, old);
+        }
+    }
+}
+
+- (id)stringForKey:(id)key { // NSString*
+    let value: id = msg![env; this objectForKey:key];
+    let string_class: Class = msg_class![env; NSString class];
+    if value != nil && msg![env; value isKindOfClass:string_class] {
+        value
     } else {
-        // TODO: Are there other default keys we need to set?
-        let langs_value: id = msg_class![env; NSLocale preferredLanguages];
-        let langs_key: id = ns_string::get_static_str(env, "AppleLanguages");
-        let new = dict_from_keys_and_objects(env, &[(langs_key, langs_value)]);
-        State::get(env).standard_defaults = Some(new);
-        new
+        nil
     }
 }
 
-// TODO: plist methods etc
+// TODO: These should also accept NSString values.
+- (bool)boolForKey:(id)key { // NSString*
+    let value: id = msg![env; this objectForKey:key];
+    value != nil && msg![env; value boolValue]
+}
+- (NSInteger)integerForKey:(id)key { // NSString*
+    let value: id = msg![env; this objectForKey:key];
+    if value == nil {
+        return 0;
+    }
+    msg![env; value integerValue]
+}
+- (f32)floatForKey:(id)key { // NSString*
+    let value: id = msg![env; this objectForKey:key];
+    if value == nil {
+        return 0.0;
+    }
+    msg![env; value floatValue]
+}
+- (f64)doubleForKey:(id)key { // NSString*
+    let value: id = msg![env; this objectForKey:key];
+    if value == nil {
+        return 0.0;
+    }
+    msg![env; value doubleValue]
+}
+
+- (())setBool:(bool)value
+       forKey:(id)key { // NSString*
+    let number: id = msg_class![env; NSNumber alloc];
+    let number: id = msg![env; number initWithBool:value];
+    set_value(env, this, key, number);
+}
+- (())setInteger:(NSInteger)value
+          forKey:(id)key { // NSString*
+    let value: i64 = value.into();
+    let number: id = msg_class![env; NSNumber alloc];
+    let number: id = msg![env; number initWithLongLong:value];
+    set_value(env, this, key, number);
+}
+- (())setFloat:(f32)value
+        forKey:(id)key { // NSString*
+    let number: id = msg_class![env; NSNumber alloc];
+    let number: id = msg![env; number initWithFloat:value];
+    set_value(env, this, key, number);
+}
+- (())setDouble:(f64)value
+         forKey:(id)key { // NSString*
+    let number: id = msg_class![env; NSNumber alloc];
+    let number: id = msg![env; number initWithDouble:value];
+    set_value(env, this, key, number);
+}
+
+- (bool)synchronize {
+    if !env.objc.borrow::<UserDefaultsHostObject>(this).dirty {
+        return true;
+    }
+    let success = save_defaults(env, this);
+    if success {
+        env.objc.borrow_mut::<UserDefaultsHostObject>(this).dirty = false;
+    }
+    success
+}
 
 @end
 
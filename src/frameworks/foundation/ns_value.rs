@@ -5,14 +5,133 @@
  */
 //! The `NSValue` class cluster, including `NSNumber`.
 
-use super::NSUInteger;
+use super::{NSInteger, NSUInteger};
+use crate::frameworks::core_graphics::cg_affine_transform::CGAffineTransform;
+use crate::frameworks::core_graphics::{CGPoint, CGRect, CGSize};
 use crate::frameworks::foundation::ns_string::from_rust_string;
+use crate::mem::{guest_size_of, ConstPtr, ConstVoidPtr, GuestUSize, MutPtr, MutVoidPtr, SafeRead};
 use crate::objc::{
-    autorelease, id, msg, msg_class, objc_classes, retain, Class, ClassExports, HostObject,
-    NSZonePtr,
+    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, Class, ClassExports,
+    HostObject, NSZonePtr,
 };
+use crate::Environment;
 
-enum NSNumberHostObject {
+/// Belongs to _touchHLE_NSValue
+struct NSValueHostObject {
+    /// Copy of the value. Allocated in guest memory so it can be easily
+    /// copied to and from guest pointers.
+    bytes: MutVoidPtr,
+    size: GuestUSize,
+    /// Copy of the `objCType` string. Null-terminated.
+    objc_type: MutPtr<u8>,
+}
+impl HostObject for NSValueHostObject {}
+
+/// Get the size and alignment of a type from its Objective-C type encoding,
+/// and the remainder of the encoding after it. Returns [None] if the encoding
+/// is malformed or uses a type that isn't supported.
+///
+/// Alignments follow the iPhone OS ARM ABI, where no type needs more than
+/// 4-byte alignment.
+fn objc_type_size_and_align(encoding: &[u8]) -> Option<(GuestUSize, GuestUSize, &[u8])> {
+    let (&first, rest) = encoding.split_first()?;
+    let align_of = |size: GuestUSize| size.clamp(1, 4);
+    let align_up = |size: GuestUSize, align: GuestUSize| size.div_ceil(align).checked_mul(align);
+    Some(match first {
+        // Type qualifiers
+        b'r' | b'n' | b'N' | b'o' | b'O' | b'R' | b'V' => return objc_type_size_and_align(rest),
+        b'c' | b'C' | b'B' => (1, 1, rest),
+        b's' | b'S' => (2, 2, rest),
+        b'i' | b'I' | b'l' | b'L' | b'f' | b'*' | b'@' | b'#' | b':' => (4, 4, rest),
+        b'q' | b'Q' | b'd' => (8, 4, rest),
+        b'v' => (0, 1, rest),
+        b'^' => {
+            let (_, _, rest) = objc_type_size_and_align(rest)?;
+            (4, 4, rest)
+        }
+        b'[' => {
+            let digits = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+            let count: GuestUSize = std::str::from_utf8(&rest[..digits]).ok()?.parse().ok()?;
+            let (size, align, rest) = objc_type_size_and_align(&rest[digits..])?;
+            let rest = rest.strip_prefix(b"]")?;
+            (size.checked_mul(count)?, align, rest)
+        }
+        b'{' | b'(' => {
+            let is_union = first == b'(';
+            let end = if is_union { b')' } else { b'}' };
+            // Skip the name, if there are member types.
+            let i = rest.iter().position(|&c| c == b'=' || c == end)?;
+            let mut rest = if rest[i] == b'=' {
+                &rest[i + 1..]
+            } else {
+                &rest[i..]
+            };
+            let (mut size, mut align) = (0, 1);
+            while rest.first() != Some(&end) {
+                let (member_size, member_align, new_rest) = objc_type_size_and_align(rest)?;
+                rest = new_rest;
+                align = align.max(member_align);
+                if is_union {
+                    size = size.max(member_size);
+                } else {
+                    size = align_up(size, member_align)?.checked_add(member_size)?;
+                }
+            }
+            (align_up(size, align)?, align_of(align), &rest[1..])
+        }
+        _ => return None,
+    })
+}
+
+/// Get the size of a type from its Objective-C type encoding, or [None] (with
+/// a warning logged) if it isn't supported.
+fn objc_type_size(encoding: &[u8]) -> Option<GuestUSize> {
+    let Some((size, _align, rest)) = objc_type_size_and_align(encoding) else {
+        log!(
+            "Warning: unsupported or malformed Objective-C type encoding {:?}",
+            std::str::from_utf8(encoding)
+        );
+        return None;
+    };
+    if !rest.is_empty() {
+        log!(
+            "Warning: ignoring trailing {:?} in Objective-C type encoding",
+            std::str::from_utf8(rest)
+        );
+    }
+    Some(size)
+}
+
+/// Create a new autoreleased `NSValue` containing a copy of a host value.
+fn value_with<T: SafeRead>(env: &mut Environment, class: Class, value: T, objc_type: &str) -> id {
+    let bytes = env.mem.alloc_and_write(value);
+    let objc_type = env.mem.alloc_and_write_cstr(objc_type.as_bytes());
+    let new: id = msg![env; class valueWithBytes:(bytes.cast_void().cast_const())
+                                    objCType:(objc_type.cast_const())];
+    env.mem.free(bytes.cast());
+    env.mem.free(objc_type.cast());
+    new
+}
+
+/// Read a host value from an `NSValue`, checking its type.
+fn value_of<T: SafeRead>(env: &mut Environment, value: id, objc_type: &str) -> T {
+    let actual_type: ConstPtr<u8> = msg![env; value objCType];
+    let actual_type = env.mem.cstr_at_utf8(actual_type).unwrap();
+    if actual_type != objc_type {
+        log!(
+            "Warning: reading NSValue of type {:?} as {:?}",
+            actual_type,
+            objc_type
+        );
+    }
+    let buffer: MutPtr<T> = env.mem.alloc(guest_size_of::<T>()).cast();
+    () = msg![env; value getValue:(buffer.cast_void())];
+    let result = env.mem.read(buffer);
+    env.mem.free(buffer.cast());
+    result
+}
+
+pub(super) enum NSNumberHostObject {
     Bool(bool),
     UnsignedLongLong(u64),
     LongLong(i64),
@@ -25,15 +144,149 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 (env, this, _cmd);
 
-// NSValue is an abstract class. None of the things it should provide are
-// implemented here yet (TODO).
+// NSValue is an abstract class. A subclass must provide:
+// - (void)getValue:(void*)buffer;
+// - (const char*)objCType;
+// We can pick whichever subclass we want for the various alloc methods.
+// For the time being, that will always be _touchHLE_NSValue.
 @implementation NSValue: NSObject
 
++ (id)allocWithZone:(NSZonePtr)zone {
+    // NSValue might be subclassed by something which needs allocWithZone:
+    // to have the normal behaviour. Unimplemented: call superclass alloc then.
+    assert!(this == env.objc.get_known_class("NSValue", &mut env.mem));
+    msg_class![env; _touchHLE_NSValue allocWithZone:zone]
+}
+
++ (id)valueWithBytes:(ConstVoidPtr)bytes
+            objCType:(ConstPtr<u8>)objc_type {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithBytes:bytes objCType:objc_type];
+    autorelease(env, new)
+}
++ (id)value:(ConstVoidPtr)bytes
+withObjCType:(ConstPtr<u8>)objc_type {
+    msg![env; this valueWithBytes:bytes objCType:objc_type]
+}
+
++ (id)valueWithPointer:(ConstVoidPtr)pointer {
+    value_with(env, this, pointer, "^v")
+}
++ (id)valueWithNonretainedObject:(id)object {
+    value_with(env, this, object, "@")
+}
+
+// These come from a category in UIKit's UIGeometry.h
++ (id)valueWithCGPoint:(CGPoint)point {
+    value_with(env, this, point, "{CGPoint=ff}")
+}
++ (id)valueWithCGSize:(CGSize)size {
+    value_with(env, this, size, "{CGSize=ff}")
+}
++ (id)valueWithCGRect:(CGRect)rect {
+    value_with(env, this, rect, "{CGRect={CGPoint=ff}{CGSize=ff}}")
+}
++ (id)valueWithCGAffineTransform:(CGAffineTransform)transform {
+    value_with(env, this, transform, "{CGAffineTransform=ffffff}")
+}
+
 // NSCopying implementation
 - (id)copyWithZone:(NSZonePtr)_zone {
     retain(env, this)
 }
 
+- (ConstVoidPtr)pointerValue {
+    value_of(env, this, "^v")
+}
+- (id)nonretainedObjectValue {
+    value_of(env, this, "@")
+}
+
+- (CGPoint)CGPointValue {
+    value_of(env, this, "{CGPoint=ff}")
+}
+- (CGSize)CGSizeValue {
+    value_of(env, this, "{CGSize=ff}")
+}
+- (CGRect)CGRectValue {
+    value_of(env, this, "{CGRect={CGPoint=ff}{CGSize=ff}}")
+}
+- (CGAffineTransform)CGAffineTransformValue {
+    value_of(env, this, "{CGAffineTransform=ffffff}")
+}
+
+@end
+
+// Our private subclass that is the single implementation of NSValue for the
+// time being.
+@implementation _touchHLE_NSValue: NSValue
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSValueHostObject {
+        bytes: MutVoidPtr::null(),
+        size: 0,
+        objc_type: MutPtr::null(),
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (id)initWithBytes:(ConstVoidPtr)bytes
+           objCType:(ConstPtr<u8>)objc_type {
+    let Some(size) = objc_type_size(env.mem.cstr_at(objc_type)) else {
+        release(env, this);
+        return nil;
+    };
+    let objc_type = env.mem.cstr_at(objc_type).to_vec();
+    let objc_type = env.mem.alloc_and_write_cstr(&objc_type);
+    let new_bytes = env.mem.alloc(size);
+    env.mem.memmove(new_bytes, bytes, size);
+    *env.objc.borrow_mut(this) = NSValueHostObject {
+        bytes: new_bytes,
+        size,
+        objc_type,
+    };
+    this
+}
+
+- (())dealloc {
+    let &NSValueHostObject { bytes, objc_type, .. } = env.objc.borrow(this);
+    env.mem.free(bytes);
+    env.mem.free(objc_type.cast());
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (())getValue:(MutVoidPtr)buffer {
+    let &NSValueHostObject { bytes, size, .. } = env.objc.borrow(this);
+    env.mem.memmove(buffer, bytes.cast_const(), size);
+}
+
+- (ConstPtr<u8>)objCType {
+    env.objc.borrow::<NSValueHostObject>(this).objc_type.cast_const()
+}
+
+- (bool)isEqualToValue:(id)other { // NSValue*
+    if this == other {
+        return true;
+    }
+    let class: Class = msg_class![env; NSValue class];
+    if other.is_null() || !msg![env; other isKindOfClass:class] {
+        return false;
+    }
+    let &NSValueHostObject { bytes, size, objc_type } = env.objc.borrow(this);
+    let other_type: ConstPtr<u8> = msg![env; other objCType];
+    if env.mem.cstr_at(objc_type) != env.mem.cstr_at(other_type) {
+        return false;
+    }
+    let other_bytes = env.mem.alloc(size);
+    () = msg![env; other getValue:other_bytes];
+    let equal = env.mem.bytes_at(bytes.cast(), size) == env.mem.bytes_at(other_bytes.cast(), size);
+    env.mem.free(other_bytes);
+    equal
+}
+- (bool)isEqualTo:(id)other {
+    msg![env; this isEqualToValue:other]
+}
+
 @end
 
 // NSNumber is not an abstract class.
@@ -143,8 +396,80 @@ pub const CLASSES: ClassExports = objc_classes! {
     a == b
 }
 
-// TODO: accessors etc
+- (bool)boolValue {
+    match *env.objc.borrow(this) {
+        NSNumberHostObject::Bool(value) => value,
+        NSNumberHostObject::UnsignedLongLong(value) => value != 0,
+        NSNumberHostObject::LongLong(value) => value != 0,
+        NSNumberHostObject::Float(value) => value != 0.0,
+        NSNumberHostObject::Double(value) => value != 0.0,
+    }
+}
+
+- (i64)longLongValue {
+    match *env.objc.borrow(this) {
+        NSNumberHostObject::Bool(value) => value as i64,
+        NSNumberHostObject::UnsignedLongLong(value) => value as i64,
+        NSNumberHostObject::LongLong(value) => value,
+        NSNumberHostObject::Float(value) => value as i64,
+        NSNumberHostObject::Double(value) => value as i64,
+    }
+}
+- (i32)intValue {
+    let value: i64 = msg![env; this longLongValue];
+    value as i32
+}
+- (NSInteger)integerValue {
+    let value: i64 = msg![env; this longLongValue];
+    value as NSInteger
+}
+
+- (f32)floatValue {
+    let value: f64 = msg![env; this doubleValue];
+    value as f32
+}
+- (f64)doubleValue {
+    match *env.objc.borrow(this) {
+        NSNumberHostObject::Bool(value) => value as i32 as f64,
+        NSNumberHostObject::UnsignedLongLong(value) => value as f64,
+        NSNumberHostObject::LongLong(value) => value as f64,
+        NSNumberHostObject::Float(value) => value.into(),
+        NSNumberHostObject::Double(value) => value,
+    }
+}
+
+// TODO: more accessors etc
 
 @end
 
 };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_objc_type_size() {
+        assert_eq!(objc_type_size(b"c"), Some(1));
+        assert_eq!(objc_type_size(b"^v"), Some(4));
+        assert_eq!(objc_type_size(b"rd"), Some(8));
+        assert_eq!(objc_type_size(b"{CGPoint=ff}"), Some(8));
+        assert_eq!(
+            objc_type_size(b"{CGRect={CGPoint=ff}{CGSize=ff}}"),
+            Some(16)
+        );
+        assert_eq!(objc_type_size(b"{CGAffineTransform=ffffff}"), Some(24));
+        assert_eq!(objc_type_size(b"{?=cic}"), Some(12));
+        assert_eq!(objc_type_size(b"{?=cd}"), Some(12));
+        assert_eq!(objc_type_size(b"[3s]"), Some(6));
+        assert_eq!(objc_type_size(b"(?=ci)"), Some(4));
+        assert_eq!(objc_type_size(b"{_NSRange=II}"), Some(8));
+        assert_eq!(objc_type_size(b""), None);
+        assert_eq!(objc_type_size(b"x"), None);
+        assert_eq!(objc_type_size(b"^"), None);
+        assert_eq!(objc_type_size(b"[s]"), None);
+        assert_eq!(objc_type_size(b"[3s"), None);
+        assert_eq!(objc_type_size(b"{CGPoint=ff"), None);
+        assert_eq!(objc_type_size(b"[4294967295i]"), None);
+    }
+}
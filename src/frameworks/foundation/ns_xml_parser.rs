@@ -0,0 +1,396 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `NSXMLParser`.
+//!
+//! This is implemented on top of `quick-xml`, which is a streaming parser like
+//! the libxml2 SAX interface Apple's implementation uses. DTDs, external
+//! entities and processing instructions are ignored.
+
+use super::ns_dictionary::dict_from_keys_and_objects;
+use super::ns_error::new_error;
+use super::ns_string::from_rust_string;
+use super::{ns_data, ns_url, NSInteger, NSUInteger};
+use crate::dyld::{ConstantExports, HostConstant};
+use crate::objc::{
+    id, msg, nil, objc_classes, release, retain, ClassExports, HostObject, NSZonePtr,
+};
+use crate::Environment;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::ResolveResult;
+use quick_xml::NsReader;
+
+pub const NSXMLParserErrorDomain: &str = "NSXMLParserErrorDomain";
+
+pub const CONSTANTS: ConstantExports = &[(
+    "_NSXMLParserErrorDomain",
+    HostConstant::NSString(NSXMLParserErrorDomain),
+)];
+
+// Some codes in NSXMLParserErrorDomain.
+pub const NSXMLParserInternalError: NSInteger = 1;
+pub const NSXMLParserPrematureDocumentEndError: NSInteger = 5;
+pub const NSXMLParserTagNameMismatchError: NSInteger = 76;
+pub const NSXMLParserDelegateAbortedParseError: NSInteger = 512;
+
+struct NSXMLParserHostObject {
+    /// The document to parse.
+    data: Vec<u8>,
+    /// Weak reference.
+    delegate: id,
+    should_process_namespaces: bool,
+    should_report_namespace_prefixes: bool,
+    should_resolve_external_entities: bool,
+    /// Set by `abortParsing`.
+    aborted: bool,
+    /// `NSError*`
+    parser_error: id,
+}
+impl HostObject for NSXMLParserHostObject {}
+
+/// Which optional delegate methods are implemented. This is looked up once
+/// per parse.
+struct DelegateMethods {
+    did_start_document: bool,
+    did_end_document: bool,
+    did_start_element: bool,
+    did_end_element: bool,
+    found_characters: bool,
+    found_cdata: bool,
+    parse_error_occurred: bool,
+}
+impl DelegateMethods {
+    fn new(env: &mut Environment, delegate: id) -> DelegateMethods {
+        let has = |name: &str| {
+            delegate != nil && env.objc.object_has_method_named(&env.mem, delegate, name)
+        };
+        DelegateMethods {
+            did_start_document: has("parserDidStartDocument:"),
+            did_end_document: has("parserDidEndDocument:"),
+            did_start_element: has("parser:didStartElement:namespaceURI:qualifiedName:attributes:"),
+            did_end_element: has("parser:didEndElement:namespaceURI:qualifiedName:"),
+            found_characters: has("parser:foundCharacters:"),
+            found_cdata: has("parser:foundCDATA:"),
+            parse_error_occurred: has("parser:parseErrorOccurred:"),
+        }
+    }
+}
+
+/// Element names to pass to the delegate: the element name, the namespace URI
+/// and the qualified name. The latter two are only provided when processing
+/// namespaces.
+fn element_names(
+    env: &mut Environment,
+    process_namespaces: bool,
+    namespace_uri: &str,
+    qualified_name: &[u8],
+    local_name: &[u8],
+) -> (id, id, id) {
+    let qualified_name = String::from_utf8_lossy(qualified_name).into_owned();
+    if !process_namespaces {
+        return (from_rust_string(env, qualified_name), nil, nil);
+    }
+    let local_name = String::from_utf8_lossy(local_name).into_owned();
+    (
+        from_rust_string(env, local_name),
+        from_rust_string(env, namespace_uri.to_string()),
+        from_rust_string(env, qualified_name),
+    )
+}
+
+/// Create a new `NSDictionary*` of the attributes of an element.
+fn attributes_dict(
+    env: &mut Environment,
+    element: &BytesStart,
+    report_namespace_prefixes: bool,
+) -> Result<id, quick_xml::Error> {
+    let mut keys_and_objects = Vec::new();
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(quick_xml::Error::from)?;
+        let key = attribute.key.as_ref();
+        // Namespace declarations aren't attributes as far as NSXMLParser is
+        // concerned, unless the prefixes are being reported.
+        if !report_namespace_prefixes && (key == b"xmlns" || key.starts_with(b"xmlns:")) {
+            continue;
+        }
+        let key = String::from_utf8_lossy(key).into_owned();
+        let value = attribute.unescape_value()?.into_owned();
+        keys_and_objects.push((from_rust_string(env, key), from_rust_string(env, value)));
+    }
+    let dict = dict_from_keys_and_objects(env, &keys_and_objects);
+    for (key, object) in keys_and_objects {
+        release(env, key);
+        release(env, object);
+    }
+    Ok(dict)
+}
+
+/// Parse the document, calling the delegate methods. Returns an error code and
+/// description if parsing fails.
+fn parse(env: &mut Environment, parser: id) -> Result<(), (NSInteger, String)> {
+    let host_object = env.objc.borrow::<NSXMLParserHostObject>(parser);
+    let data = host_object.data.clone();
+    let delegate = host_object.delegate;
+    let process_namespaces = host_object.should_process_namespaces;
+    let report_namespace_prefixes = host_object.should_report_namespace_prefixes;
+    if host_object.should_resolve_external_entities {
+        log!(
+            "TODO: NSXMLParser {:?} can't resolve external entities",
+            parser
+        );
+    }
+    let methods = DelegateMethods::new(env, delegate);
+
+    let is_aborted =
+        |env: &mut Environment| env.objc.borrow::<NSXMLParserHostObject>(parser).aborted;
+    let aborted_error = || {
+        (
+            NSXMLParserDelegateAbortedParseError,
+            "Parsing was aborted by the delegate".to_string(),
+        )
+    };
+    let xml_error = |position: usize, err: quick_xml::Error| {
+        let code = match err {
+            quick_xml::Error::EndEventMismatch { .. } => NSXMLParserTagNameMismatchError,
+            quick_xml::Error::UnexpectedEof(_) => NSXMLParserPrematureDocumentEndError,
+            _ => NSXMLParserInternalError,
+        };
+        let description = format!("XML error at byte {}: {}", position, err);
+        (code, description)
+    };
+
+    let mut reader = NsReader::from_reader(&data[..]);
+    // Whitespace is reported with parser:foundCharacters: like other text.
+    reader.trim_text(false);
+    reader.check_end_names(true);
+
+    if methods.did_start_document {
+        () = msg![env; delegate parserDidStartDocument:parser];
+    }
+
+    let mut depth = 0usize;
+    let mut buf = Vec::new();
+    loop {
+        if is_aborted(env) {
+            return Err(aborted_error());
+        }
+        buf.clear();
+        let (namespace_uri, event) = match reader.read_resolved_event_into(&mut buf) {
+            Ok((ResolveResult::Bound(namespace), event)) => (
+                String::from_utf8_lossy(namespace.as_ref()).into_owned(),
+                event,
+            ),
+            Ok((_, event)) => (String::new(), event),
+            Err(err) => return Err(xml_error(reader.buffer_position(), err)),
+        };
+        match event {
+            Event::Start(ref element) | Event::Empty(ref element) => {
+                let is_empty = matches!(event, Event::Empty(_));
+                if !is_empty {
+                    depth += 1;
+                }
+                let names = element_names(
+                    env,
+                    process_namespaces,
+                    &namespace_uri,
+                    element.name().as_ref(),
+                    element.local_name().as_ref(),
+                );
+                let (element_name, namespace_uri, qualified_name) = names;
+                if methods.did_start_element {
+                    let attributes = attributes_dict(env, element, report_namespace_prefixes)
+                        .map_err(|err| xml_error(reader.buffer_position(), err))?;
+                    () = msg![env; delegate parser:parser
+                                   didStartElement:element_name
+                                      namespaceURI:namespace_uri
+                                     qualifiedName:qualified_name
+                                        attributes:attributes];
+                    release(env, attributes);
+                }
+                if is_empty && methods.did_end_element && !is_aborted(env) {
+                    () = msg![env; delegate parser:parser
+                                     didEndElement:element_name
+                                      namespaceURI:namespace_uri
+                                     qualifiedName:qualified_name];
+                }
+                release(env, element_name);
+                release(env, namespace_uri);
+                release(env, qualified_name);
+            }
+            Event::End(ref element) => {
+                depth -= 1;
+                if methods.did_end_element {
+                    let (element_name, namespace_uri, qualified_name) = element_names(
+                        env,
+                        process_namespaces,
+                        &namespace_uri,
+                        element.name().as_ref(),
+                        element.local_name().as_ref(),
+                    );
+                    () = msg![env; delegate parser:parser
+                                     didEndElement:element_name
+                                      namespaceURI:namespace_uri
+                                     qualifiedName:qualified_name];
+                    release(env, element_name);
+                    release(env, namespace_uri);
+                    release(env, qualified_name);
+                }
+            }
+            Event::Text(ref text) => {
+                // Text outside the root element can only be whitespace.
+                if depth > 0 && methods.found_characters {
+                    let text = text
+                        .unescape()
+                        .map_err(|err| xml_error(reader.buffer_position(), err))?
+                        .into_owned();
+                    let string = from_rust_string(env, text);
+                    () = msg![env; delegate parser:parser foundCharacters:string];
+                    release(env, string);
+                }
+            }
+            Event::CData(ref cdata) => {
+                if methods.found_cdata {
+                    let data = ns_data::from_vec(env, cdata.to_vec());
+                    () = msg![env; delegate parser:parser foundCDATA:data];
+                    release(env, data);
+                } else if methods.found_characters {
+                    let text = String::from_utf8_lossy(cdata).into_owned();
+                    let string = from_rust_string(env, text);
+                    () = msg![env; delegate parser:parser foundCharacters:string];
+                    release(env, string);
+                }
+            }
+            Event::Eof => break,
+            // XML declaration, comments, processing instructions and DTD.
+            _ => (),
+        }
+    }
+
+    if depth > 0 {
+        return Err((
+            NSXMLParserPrematureDocumentEndError,
+            "Document ended before all elements were closed".to_string(),
+        ));
+    }
+
+    if methods.did_end_document {
+        () = msg![env; delegate parserDidEndDocument:parser];
+    }
+    Ok(())
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation NSXMLParser: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(NSXMLParserHostObject {
+        data: Vec::new(),
+        delegate: nil,
+        should_process_namespaces: false,
+        should_report_namespace_prefixes: false,
+        should_resolve_external_entities: false,
+        aborted: false,
+        parser_error: nil,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (id)initWithData:(id)data { // NSData*
+    let length: NSUInteger = msg![env; data length];
+    let data = if length == 0 {
+        Vec::new()
+    } else {
+        ns_data::to_rust_slice(env, data).to_vec()
+    };
+    env.objc.borrow_mut::<NSXMLParserHostObject>(this).data = data;
+    this
+}
+
+- (id)initWithContentsOfURL:(id)url { // NSURL*
+    let path = ns_url::to_rust_path(env, url);
+    let Ok(data) = env.fs.read(&path) else {
+        log!("NSXMLParser: couldn't read {:?}", path);
+        release(env, this);
+        return nil;
+    };
+    env.objc.borrow_mut::<NSXMLParserHostObject>(this).data = data;
+    this
+}
+
+- (())dealloc {
+    let parser_error = env.objc.borrow::<NSXMLParserHostObject>(this).parser_error;
+    release(env, parser_error);
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (id)delegate {
+    env.objc.borrow::<NSXMLParserHostObject>(this).delegate
+}
+- (())setDelegate:(id)delegate {
+    env.objc.borrow_mut::<NSXMLParserHostObject>(this).delegate = delegate;
+}
+
+- (bool)shouldProcessNamespaces {
+    env.objc.borrow::<NSXMLParserHostObject>(this).should_process_namespaces
+}
+- (())setShouldProcessNamespaces:(bool)value {
+    env.objc.borrow_mut::<NSXMLParserHostObject>(this).should_process_namespaces = value;
+}
+- (bool)shouldReportNamespacePrefixes {
+    env.objc.borrow::<NSXMLParserHostObject>(this).should_report_namespace_prefixes
+}
+- (())setShouldReportNamespacePrefixes:(bool)value {
+    env.objc.borrow_mut::<NSXMLParserHostObject>(this).should_report_namespace_prefixes = value;
+}
+- (bool)shouldResolveExternalEntities {
+    env.objc.borrow::<NSXMLParserHostObject>(this).should_resolve_external_entities
+}
+- (())setShouldResolveExternalEntities:(bool)value {
+    env.objc.borrow_mut::<NSXMLParserHostObject>(this).should_resolve_external_entities = value;
+}
+
+- (bool)parse {
+    log_dbg!("[(NSXMLParser*){:?} parse]", this);
+    // The delegate might release the parser during parsing.
+    retain(env, this);
+    env.objc.borrow_mut::<NSXMLParserHostObject>(this).aborted = false;
+    let result = parse(env, this);
+    let success = match result {
+        Ok(()) => true,
+        Err((code, description)) => {
+            log_dbg!("NSXMLParser {:?} failed: {}", this, description);
+            let error = new_error(env, NSXMLParserErrorDomain, code, description);
+            retain(env, error);
+            let host_object = env.objc.borrow_mut::<NSXMLParserHostObject>(this);
+            let old_error = std::mem::replace(&mut host_object.parser_error, error);
+            let delegate = host_object.delegate;
+            release(env, old_error);
+            // The delegate isn't told about the error it caused by aborting.
+            if code != NSXMLParserDelegateAbortedParseError
+                && DelegateMethods::new(env, delegate).parse_error_occurred
+            {
+                () = msg![env; delegate parser:this parseErrorOccurred:error];
+            }
+            false
+        }
+    };
+    release(env, this);
+    success
+}
+
+- (())abortParsing {
+    env.objc.borrow_mut::<NSXMLParserHostObject>(this).aborted = true;
+}
+
+- (id)parserError {
+    env.objc.borrow::<NSXMLParserHostObject>(this).parser_error
+}
+
+@end
+
+};
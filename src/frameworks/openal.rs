@@ -44,6 +44,20 @@ struct GuestALCcontext {
 }
 impl SafeWrite for GuestALCcontext {}
 
+/// Pause or resume playback on all the devices the app has opened, e.g. while
+/// it is suspended.
+pub fn set_devices_paused(env: &mut Environment, paused: bool) {
+    for &host_device in State::get(env).devices.values() {
+        unsafe {
+            if paused {
+                al::alcDevicePauseSOFT(host_device);
+            } else {
+                al::alcDeviceResumeSOFT(host_device);
+            }
+        }
+    }
+}
+
 // === alc.h ===
 
 fn alcOpenDevice(env: &mut Environment, devicename: ConstPtr<u8>) -> MutPtr<GuestALCdevice> {
@@ -222,6 +236,30 @@ fn alDistanceModel(_env: &mut Environment, value: ALenum) {
     unsafe { al::alDistanceModel(value) };
 }
 
+fn alGetFloat(_env: &mut Environment, param: ALenum) -> ALfloat {
+    unsafe { al::alGetFloat(param) }
+}
+fn alGetFloatv(env: &mut Environment, param: ALenum, values: MutPtr<ALfloat>) {
+    // All the state queried this way is a single value.
+    unsafe { al::alGetFloatv(param, env.mem.ptr_at_mut(values, 1)) };
+}
+fn alGetInteger(_env: &mut Environment, param: ALenum) -> ALint {
+    unsafe { al::alGetInteger(param) }
+}
+fn alGetIntegerv(env: &mut Environment, param: ALenum, values: MutPtr<ALint>) {
+    unsafe { al::alGetIntegerv(param, env.mem.ptr_at_mut(values, 1)) };
+}
+
+/// Number of values for a vector listener or source parameter, so the whole
+/// vector can be bounds-checked.
+fn vector_param_count(param: ALenum) -> GuestUSize {
+    match param {
+        al::AL_ORIENTATION => 6, // "at" and "up" vectors
+        al::AL_POSITION | al::AL_VELOCITY | al::AL_DIRECTION => 3,
+        _ => 1,
+    }
+}
+
 fn alGetEnumValue(env: &mut Environment, enumName: ConstPtr<u8>) -> ALenum {
     let s = env.mem.cstr_at_utf8(enumName).unwrap();
     let ss = CString::new(s).unwrap();
@@ -242,8 +280,7 @@ fn alListenerf(_env: &mut Environment, param: ALenum, value: ALfloat) {
     unsafe { al::alListenerf(param, value) };
 }
 fn alListenerfv(env: &mut Environment, param: ALenum, values: ConstPtr<ALfloat>) {
-    // we assume that at least 1 parameter should be passed
-    let values = env.mem.ptr_at(values, 1);
+    let values = env.mem.ptr_at(values, vector_param_count(param));
     unsafe { al::alListenerfv(param, values) };
 }
 fn alListener3f(
@@ -270,7 +307,7 @@ fn alListener3i(
     unsafe { al::alListener3i(param, value1, value2, value3) };
 }
 fn alListeneriv(env: &mut Environment, param: ALenum, values: ConstPtr<ALint>) {
-    let values = env.mem.ptr_at(values, 3); // upper bound
+    let values = env.mem.ptr_at(values, vector_param_count(param));
     unsafe { al::alListeneriv(param, values) };
 }
 
@@ -292,7 +329,7 @@ fn alGetListener3f(
     env.mem.write(value3, values[2]);
 }
 fn alGetListenerfv(env: &mut Environment, param: ALenum, values: MutPtr<ALfloat>) {
-    let values = env.mem.ptr_at_mut(values, 3); // upper bound
+    let values = env.mem.ptr_at_mut(values, vector_param_count(param));
     unsafe { al::alGetListenerfv(param, values) };
 }
 fn alGetListeneri(env: &mut Environment, param: ALenum, value: MutPtr<ALint>) {
@@ -313,7 +350,7 @@ fn alGetListener3i(
     env.mem.write(value3, values[2]);
 }
 fn alGetListeneriv(env: &mut Environment, param: ALenum, values: MutPtr<ALint>) {
-    let values = env.mem.ptr_at_mut(values, 3); // upper bound
+    let values = env.mem.ptr_at_mut(values, vector_param_count(param));
     unsafe { al::alGetListeneriv(param, values) };
 }
 
@@ -332,8 +369,7 @@ fn alSourcef(_env: &mut Environment, source: ALuint, param: ALenum, value: ALflo
     unsafe { al::alSourcef(source, param, value) };
 }
 fn alSourcefv(env: &mut Environment, source: ALuint, param: ALenum, values: ConstPtr<ALfloat>) {
-    // we assume that at least 1 parameter should be passed
-    let values = env.mem.ptr_at(values, 1);
+    let values = env.mem.ptr_at(values, vector_param_count(param));
     unsafe { al::alSourcefv(source, param, values) };
 }
 fn alSource3f(
@@ -360,7 +396,7 @@ fn alSource3i(
     unsafe { al::alSource3i(source, param, value1, value2, value3) };
 }
 fn alSourceiv(env: &mut Environment, source: ALuint, param: ALenum, values: ConstPtr<ALint>) {
-    let values = env.mem.ptr_at(values, 3); // upper bound
+    let values = env.mem.ptr_at(values, vector_param_count(param));
     unsafe { al::alSourceiv(source, param, values) };
 }
 
@@ -390,7 +426,7 @@ fn alGetSource3f(
     env.mem.write(value3, values[2]);
 }
 fn alGetSourcefv(env: &mut Environment, source: ALuint, param: ALenum, values: MutPtr<ALfloat>) {
-    let values = env.mem.ptr_at_mut(values, 3); // upper bound
+    let values = env.mem.ptr_at_mut(values, vector_param_count(param));
     unsafe { al::alGetSourcefv(source, param, values) };
 }
 fn alGetSourcei(env: &mut Environment, source: ALuint, param: ALenum, value: MutPtr<ALint>) {
@@ -419,7 +455,7 @@ fn alGetSource3i(
     env.mem.write(value3, values[2]);
 }
 fn alGetSourceiv(env: &mut Environment, source: ALuint, param: ALenum, values: MutPtr<ALint>) {
-    let values = env.mem.ptr_at_mut(values, 3); // upper bound
+    let values = env.mem.ptr_at_mut(values, vector_param_count(param));
     unsafe { al::alGetSourceiv(source, param, values) };
 }
 
@@ -435,6 +471,32 @@ fn alSourceStop(_env: &mut Environment, source: ALuint) {
 fn alSourceRewind(_env: &mut Environment, source: ALuint) {
     unsafe { al::alSourceRewind(source) };
 }
+/// Get a host pointer to the array of `n` sources passed to one of the
+/// `alSource*v` functions. If `n` is negative, this returns a null pointer: the
+/// AL spec makes that an `AL_INVALID_VALUE` error, which OpenAL Soft reports
+/// and returns from without reading the array.
+fn sources_ptr(env: &Environment, n: ALsizei, sources: ConstPtr<ALuint>) -> *const ALuint {
+    match GuestUSize::try_from(n) {
+        Ok(n_usize) => env.mem.ptr_at(sources, n_usize),
+        Err(_) => std::ptr::null(),
+    }
+}
+fn alSourcePlayv(env: &mut Environment, n: ALsizei, sources: ConstPtr<ALuint>) {
+    let sources = sources_ptr(env, n, sources);
+    unsafe { al::alSourcePlayv(n, sources) };
+}
+fn alSourcePausev(env: &mut Environment, n: ALsizei, sources: ConstPtr<ALuint>) {
+    let sources = sources_ptr(env, n, sources);
+    unsafe { al::alSourcePausev(n, sources) };
+}
+fn alSourceStopv(env: &mut Environment, n: ALsizei, sources: ConstPtr<ALuint>) {
+    let sources = sources_ptr(env, n, sources);
+    unsafe { al::alSourceStopv(n, sources) };
+}
+fn alSourceRewindv(env: &mut Environment, n: ALsizei, sources: ConstPtr<ALuint>) {
+    let sources = sources_ptr(env, n, sources);
+    unsafe { al::alSourceRewindv(n, sources) };
+}
 
 fn alSourceQueueBuffers(
     env: &mut Environment,
@@ -442,6 +504,9 @@ fn alSourceQueueBuffers(
     nb: ALsizei,
     buffers: ConstPtr<ALuint>,
 ) {
+    // OpenAL Soft's mixer keeps track of which queued buffers it has played
+    // past, so AL_BUFFERS_PROCESSED and alSourceUnqueueBuffers work for
+    // streaming without any help from us.
     let nb_usize: GuestUSize = nb.try_into().unwrap();
     let buffers = env.mem.ptr_at(buffers, nb_usize);
     unsafe { al::alSourceQueueBuffers(source, nb, buffers) }
@@ -516,6 +581,15 @@ fn alBufferData(
     };
 }
 
+fn alGetBufferf(env: &mut Environment, buffer: ALuint, param: ALenum, value: MutPtr<ALfloat>) {
+    unsafe { al::alGetBufferf(buffer, param, env.mem.ptr_at_mut(value, 1)) };
+}
+fn alGetBufferi(env: &mut Environment, buffer: ALuint, param: ALenum, value: MutPtr<ALint>) {
+    // Streaming code often uses AL_SIZE etc to work out how much audio has
+    // been played, so these need to be right.
+    unsafe { al::alGetBufferi(buffer, param, env.mem.ptr_at_mut(value, 1)) };
+}
+
 /// This is an Apple extension that treats the data passed as a static buffer
 /// rather than a temporary one, which means it never has to be copied.
 /// OpenAL Soft doesn't support this, so we pass through to `alBufferData`
@@ -555,6 +629,10 @@ fn alDopplerVelocity(env: &mut Environment, value: ALfloat) {
     unsafe { al::alDopplerVelocity(value) };
 }
 
+fn alSpeedOfSound(_env: &mut Environment, value: ALfloat) {
+    unsafe { al::alSpeedOfSound(value) };
+}
+
 // TODO: more functions
 
 // Note: For some reasons Wolf3d registers many OpenAl functions, but actually
@@ -583,12 +661,6 @@ fn alcIsExtensionPresent(
 ) -> ALCboolean {
     0
 }
-fn alGetBufferf(_env: &mut Environment, _buffer: ALuint, _param: ALenum, _value: MutPtr<ALfloat>) {
-    todo!();
-}
-fn alGetBufferi(_env: &mut Environment, _buffer: ALuint, _param: ALenum, _value: MutPtr<ALint>) {
-    todo!();
-}
 fn alEnable(_env: &mut Environment, _capability: ALenum) {
     todo!();
 }
@@ -607,18 +679,6 @@ fn alGetDouble(_env: &mut Environment, _param: ALenum) -> ALdouble {
 fn alGetDoublev(_env: &mut Environment, _param: ALenum, _values: MutPtr<ALdouble>) {
     todo!();
 }
-fn alGetFloat(_env: &mut Environment, _param: ALenum) -> ALfloat {
-    todo!();
-}
-fn alGetFloatv(_env: &mut Environment, _param: ALenum, _values: MutPtr<ALfloat>) {
-    todo!();
-}
-fn alGetInteger(_env: &mut Environment, _param: ALenum) -> ALint {
-    todo!();
-}
-fn alGetIntegerv(_env: &mut Environment, _param: ALenum, _values: MutPtr<ALint>) {
-    todo!();
-}
 fn alGetProcAddress(_env: &mut Environment, _funcName: ConstPtr<u8>) -> MutVoidPtr {
     todo!();
 }
@@ -631,18 +691,6 @@ fn alIsExtensionPresent(_env: &mut Environment, _extName: ConstPtr<u8>) -> ALboo
 fn alIsEnabled(_env: &mut Environment, _capability: ALenum) -> ALboolean {
     todo!();
 }
-fn alSourcePlayv(_env: &mut Environment, _nsources: ALsizei, _sources: ConstPtr<ALuint>) {
-    todo!();
-}
-fn alSourcePausev(_env: &mut Environment, _nsources: ALsizei, _sources: ConstPtr<ALuint>) {
-    todo!();
-}
-fn alSourceStopv(_env: &mut Environment, _nsources: ALsizei, _sources: ConstPtr<ALuint>) {
-    todo!();
-}
-fn alSourceRewindv(_env: &mut Environment, _nsources: ALsizei, _sources: ConstPtr<ALuint>) {
-    todo!();
-}
 
 pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(alcOpenDevice(_)),
@@ -706,6 +754,7 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(alDisable(_)),
     export_c_func!(alDopplerFactor(_)),
     export_c_func!(alDopplerVelocity(_)),
+    export_c_func!(alSpeedOfSound(_)),
     export_c_func!(alGetBoolean(_)),
     export_c_func!(alGetBooleanv(_, _)),
     export_c_func!(alGetDouble(_)),
@@ -12,9 +12,9 @@
 pub mod eagl;
 mod gles_guest;
 
-use crate::mem::ConstPtr;
+use crate::mem::{ConstPtr, MutVoidPtr};
 pub use gles_guest::FUNCTIONS;
-use touchHLE_gl_bindings::gles11::types::GLenum;
+use touchHLE_gl_bindings::gles11::types::{GLenum, GLuint};
 
 #[derive(Default)]
 pub struct State {
@@ -22,7 +22,15 @@ pub struct State {
     current_ctxs: std::collections::HashMap<crate::ThreadId, Option<crate::objc::id>>,
     /// Which thread's EAGLContext is currently active
     current_ctx_thread: Option<crate::ThreadId>,
-    strings_cache: std::collections::HashMap<GLenum, ConstPtr<u8>>,
+    /// Strings returned by `glGetString`, by whether they are for OpenGL ES 2.0
+    /// and the name.
+    strings_cache: std::collections::HashMap<(bool, GLenum), ConstPtr<u8>>,
+    /// Guest memory standing in for buffers mapped with `glMapBufferOES`, by
+    /// EAGLContext and buffer name.
+    mapped_buffers: std::collections::HashMap<(crate::objc::id, GLuint), MutVoidPtr>,
+    /// Errors detected by touchHLE itself rather than the host OpenGL ES
+    /// implementation, by EAGLContext. The next `glGetError` returns them.
+    pending_errors: std::collections::HashMap<crate::objc::id, GLenum>,
 }
 impl State {
     fn current_ctx_for_thread(&mut self, thread: crate::ThreadId) -> &mut Option<crate::objc::id> {
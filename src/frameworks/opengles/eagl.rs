@@ -13,9 +13,10 @@ use crate::frameworks::foundation::ns_string::get_static_str;
 use crate::frameworks::foundation::NSUInteger;
 use crate::gles::gles11_raw as gles11; // constants only
 use crate::gles::gles11_raw::types::*;
+use crate::gles::gles20_raw as gles20; // constants only
 use crate::gles::present::{present_frame, FpsCounter};
-use crate::gles::{create_gles1_ctx, gles1_on_gl2, GLES};
-use crate::objc::{id, msg, nil, objc_classes, release, retain, ClassExports, HostObject};
+use crate::gles::{create_gles1_ctx, create_gles2_ctx, gles1_on_gl2, GLES};
+use crate::objc::{id, msg, nil, objc_classes, release, retain, ClassExports, HostObject, ObjC};
 use crate::options::Options;
 use crate::window::Window;
 use std::collections::HashMap;
@@ -50,12 +51,12 @@ pub const CONSTANTS: ConstantExports = &[
 
 type EAGLRenderingAPI = u32;
 const kEAGLRenderingAPIOpenGLES1: EAGLRenderingAPI = 1;
-#[allow(dead_code)]
 const kEAGLRenderingAPIOpenGLES2: EAGLRenderingAPI = 2;
 #[allow(dead_code)]
 const kEAGLRenderingAPIOpenGLES3: EAGLRenderingAPI = 3;
 
 pub(super) struct EAGLContextHostObject {
+    api: EAGLRenderingAPI,
     pub(super) gles_ctx: Option<Box<dyn GLES>>,
     /// Mapping of OpenGL ES renderbuffer names to `EAGLDrawable` instances
     /// (always `CAEAGLLayer*`). Retains the instance so it won't dangle.
@@ -65,6 +66,11 @@ pub(super) struct EAGLContextHostObject {
 }
 impl HostObject for EAGLContextHostObject {}
 
+/// Whether an `EAGLContext` was created for OpenGL ES 2.0 rather than 1.1.
+pub(super) fn is_gles2_context(objc: &ObjC, context: id) -> bool {
+    objc.borrow::<EAGLContextHostObject>(context).api == kEAGLRenderingAPIOpenGLES2
+}
+
 pub const CLASSES: ClassExports = objc_classes! {
 
 (env, this, _cmd);
@@ -73,6 +79,7 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 + (id)alloc {
     let host_object = Box::new(EAGLContextHostObject {
+        api: kEAGLRenderingAPIOpenGLES1,
         gles_ctx: None,
         renderbuffer_drawable_bindings: HashMap::new(),
         fps_counter: None,
@@ -111,31 +118,40 @@ pub const CLASSES: ClassExports = objc_classes! {
 }
 
 - (id)initWithAPI:(EAGLRenderingAPI)api {
-    assert!(api == kEAGLRenderingAPIOpenGLES1);
-
     let window = env.window.as_mut().expect("OpenGL ES is not supported in headless mode");
-    let gles1_ctx = create_gles1_ctx(window, &env.options);
+    let gles_ctx = match api {
+        kEAGLRenderingAPIOpenGLES1 => create_gles1_ctx(window, &env.options),
+        kEAGLRenderingAPIOpenGLES2 => create_gles2_ctx(window, &env.options),
+        _ => unimplemented!("EAGLRenderingAPI {}", api),
+    };
 
     // Make the context current so we can get driver info from it.
     // initWithAPI: is not supposed to make the new context current (the app
     // must call setCurrentContext: for that), so we need to hide this from the
     // app. Setting current_ctx_thread to None should cause sync_context to
     // switch back to the right context if the app makes an OpenGL ES call.
-    gles1_ctx.make_current(window);
+    gles_ctx.make_current(window);
     env.framework_state.opengles.current_ctx_thread = None;
-    log!("Driver info: {}", unsafe { gles1_ctx.driver_description() });
+    log!("Driver info: {}", unsafe { gles_ctx.driver_description() });
 
-    env.objc.borrow_mut::<EAGLContextHostObject>(this).gles_ctx = Some(gles1_ctx);
+    let host_obj = env.objc.borrow_mut::<EAGLContextHostObject>(this);
+    host_obj.api = api;
+    host_obj.gles_ctx = Some(gles_ctx);
 
     this
 }
 
+- (EAGLRenderingAPI)API {
+    env.objc.borrow::<EAGLContextHostObject>(this).api
+}
+
 - (())dealloc {
     let host_obj = env.objc.borrow_mut::<EAGLContextHostObject>(this);
     let bindings = std::mem::take(&mut host_obj.renderbuffer_drawable_bindings);
     for (_renderbuffer, drawable) in bindings {
         release(env, drawable);
     }
+    env.framework_state.opengles.pending_errors.remove(&this);
     env.objc.dealloc_object(this, &mut env.mem);
 }
 
@@ -236,10 +252,12 @@ pub const CLASSES: ClassExports = objc_classes! {
             drawable,
             renderbuffer,
         );
+        let api = env.objc.borrow::<EAGLContextHostObject>(this).api;
+        let is_gles2 = api == kEAGLRenderingAPIOpenGLES2;
         // re-borrow
         let gles = super::sync_context(&mut env.framework_state.opengles, &mut env.objc, env.window.as_mut().unwrap(), env.current_thread);
         unsafe {
-            present_renderbuffer(gles, env.window.as_mut().unwrap());
+            present_renderbuffer(gles, env.window.as_mut().unwrap(), is_gles2);
         }
     } else {
         if fullscreen_layer != nil {
@@ -459,8 +477,9 @@ unsafe fn read_renderbuffer(gles: &mut dyn GLES, mut pixel_buffer: Vec<u8>) -> (
 /// [present_frame], trying to avoid noticeably modifying OpenGL ES state while
 /// doing so. The front and back buffers are then swapped.
 ///
-/// The provided context must be current.
-unsafe fn present_renderbuffer(gles: &mut dyn GLES, window: &mut Window) {
+/// The provided context must be current. `is_gles2` must be set if it is an
+/// OpenGL ES 2.0 context, so its extra state can be backed up too.
+unsafe fn present_renderbuffer(gles: &mut dyn GLES, window: &mut Window, is_gles2: bool) {
     // We can't directly copy the content of the renderbuffer to the default
     // framebuffer (the window), but if we attach it to a framebuffer object, we
     // can use glCopyTexImage2D() to copy it to a texture, which we can then
@@ -536,6 +555,26 @@ unsafe fn present_renderbuffer(gles: &mut dyn GLES, window: &mut Window) {
         }
         old_capabilities
     };
+    // The app's shader program and generic vertex attribute arrays would be
+    // used instead of the fixed-function pipeline and the arrays above.
+    let old_program_and_attrib_arrays = if is_gles2 {
+        let old_program: GLuint = get_int(gles, gles20::CURRENT_PROGRAM) as _;
+        gles.UseProgram(0);
+        let max_attribs: GLuint = get_int(gles, gles20::MAX_VERTEX_ATTRIBS) as _;
+        let old_attrib_arrays: Vec<GLuint> = (0..max_attribs)
+            .filter(|&index| {
+                let mut is_enabled = 0;
+                gles.GetVertexAttribiv(index, gles20::VERTEX_ATTRIB_ARRAY_ENABLED, &mut is_enabled);
+                is_enabled != 0
+            })
+            .collect();
+        for &index in &old_attrib_arrays {
+            gles.DisableVertexAttribArray(index);
+        }
+        Some((old_program, old_attrib_arrays))
+    } else {
+        None
+    };
     let old_matrix_mode: GLenum = get_int(gles, gles11::MATRIX_MODE) as _;
     for mode in [gles11::MODELVIEW, gles11::PROJECTION, gles11::TEXTURE] {
         gles.MatrixMode(mode);
@@ -601,6 +640,12 @@ unsafe fn present_renderbuffer(gles: &mut dyn GLES, window: &mut Window) {
         gles.PopMatrix();
     }
     gles.MatrixMode(old_matrix_mode);
+    if let Some((old_program, old_attrib_arrays)) = old_program_and_attrib_arrays {
+        gles.UseProgram(old_program);
+        for index in old_attrib_arrays {
+            gles.EnableVertexAttribArray(index);
+        }
+    }
     gles.Color4f(old_color[0], old_color[1], old_color[2], old_color[3]);
     gles.Viewport(
         old_viewport.0,
@@ -12,16 +12,21 @@
 //! depending on the value of `pname`, using the upper bound (4 in this case)
 //! every time is never going to cause a problem in practice.
 
+use super::eagl::is_gles2_context;
 use crate::dyld::{export_c_func, FunctionExports};
 use crate::gles::gles11_raw as gles11; // constants only
+use crate::gles::gles20_raw as gles20; // constants only
 use crate::gles::GLES;
-use crate::mem::{ConstPtr, ConstVoidPtr, GuestISize, GuestUSize, Mem, MutPtr};
+use crate::mem::{
+    ConstPtr, ConstVoidPtr, GuestISize, GuestUSize, Mem, MutPtr, MutVoidPtr, SafeRead,
+};
+use crate::objc::id;
 use crate::Environment;
 
 // These types are the same size in guest code (32-bit) and host code (64-bit).
 use crate::gles::gles11_raw::types::{
-    GLbitfield, GLboolean, GLclampf, GLclampx, GLenum, GLfixed, GLfloat, GLint, GLsizei, GLubyte,
-    GLuint, GLvoid,
+    GLbitfield, GLboolean, GLchar, GLclampf, GLclampx, GLenum, GLfixed, GLfloat, GLint, GLsizei,
+    GLubyte, GLuint, GLvoid,
 };
 // These types have different sizes, so some care is needed.
 use crate::gles::gles11_raw::types::{GLintptr as HostGLintptr, GLsizeiptr as HostGLsizeiptr};
@@ -66,7 +71,22 @@ fn panic_on_gl_errors(gles: &mut dyn GLES) {
 }
 
 // Generic state manipulation
+/// Record an error detected by touchHLE itself, for [glGetError] to return.
+/// As in OpenGL ES, only the first error is kept until it has been returned.
+fn set_gl_error(env: &mut Environment, error: GLenum) {
+    let ctx = current_ctx(env);
+    env.framework_state
+        .opengles
+        .pending_errors
+        .entry(ctx)
+        .or_insert(error);
+}
 fn glGetError(env: &mut Environment) -> GLenum {
+    let ctx = current_ctx(env);
+    if let Some(err) = env.framework_state.opengles.pending_errors.remove(&ctx) {
+        log!("Warning: glGetError() returned {:#x}", err);
+        return err;
+    }
     with_ctx_and_mem(env, |gles, _mem| {
         let err = unsafe { gles.GetError() };
         if err != 0 {
@@ -130,35 +150,59 @@ fn glFlush(env: &mut Environment) {
     with_ctx_and_mem(env, |gles, _mem| unsafe { gles.Flush() })
 }
 fn glGetString(env: &mut Environment, name: GLenum) -> ConstPtr<GLubyte> {
-    let res = if let Some(&str) = env.framework_state.opengles.strings_cache.get(&name) {
+    let ctx = current_ctx(env);
+    let is_gles2 = is_gles2_context(&env.objc, ctx);
+    let key = (is_gles2, name);
+    let res = if let Some(&str) = env.framework_state.opengles.strings_cache.get(&key) {
         str
     } else {
-        let new_str = with_ctx_and_mem(env, |_gles, mem| {
-            // Those values are extracted from the iPod touch 2nd gen, iOS 4.2.1
-            let s: &[u8] = match name {
-                gles11::VENDOR => {
-                    b"Imagination Technologies"
-                }
-                gles11::RENDERER => {
-                    b"PowerVR MBXLite with VGPLite"
-                }
-                gles11::VERSION => {
-                    b"OpenGL ES-CM 1.1 (76)"
-                }
-                gles11::EXTENSIONS => {
-                    b"GL_APPLE_framebuffer_multisample GL_APPLE_texture_max_level GL_EXT_discard_framebuffer GL_EXT_texture_filter_anisotropic GL_EXT_texture_lod_bias GL_IMG_read_format GL_IMG_texture_compression_pvrtc GL_IMG_texture_format_BGRA8888 GL_OES_blend_subtract GL_OES_compressed_paletted_texture GL_OES_depth24 GL_OES_draw_texture GL_OES_framebuffer_object GL_OES_mapbuffer GL_OES_matrix_palette GL_OES_point_size_array GL_OES_point_sprite GL_OES_read_format GL_OES_rgb8_rgba8 GL_OES_texture_mirrored_repeat GL_OES_vertex_array_object "
-                }
-                _ => unreachable!(),
-            };
-            mem.alloc_and_write_cstr(s).cast_const()
-        });
+        // The vendor, renderer and version are those of the iPod touch 2nd gen
+        // (iOS 4.2.1) for OpenGL ES 1.1, and the iPhone 3GS for OpenGL ES 2.0,
+        // so that apps take the code paths they would on a real device.
+        // The extensions are only those that touchHLE actually implements, on
+        // any host, so they may differ a little from a real device.
+        let s: &str = match (is_gles2, name) {
+            (_, gles11::VENDOR) => "Imagination Technologies",
+            (false, gles11::RENDERER) => "PowerVR MBXLite with VGPLite",
+            (true, gles11::RENDERER) => "PowerVR SGX 535",
+            (false, gles11::VERSION) => "OpenGL ES-CM 1.1 (76)",
+            (true, gles11::VERSION) => "OpenGL ES 2.0 IMGSGX535-63.24",
+            (true, gles20::SHADING_LANGUAGE_VERSION) => "OpenGL ES GLSL ES 1.00",
+            (false, gles11::EXTENSIONS) => concat!(
+                "GL_EXT_texture_filter_anisotropic ",
+                "GL_EXT_texture_lod_bias ",
+                "GL_IMG_texture_compression_pvrtc ",
+                "GL_OES_compressed_ETC1_RGB8_texture ",
+                "GL_OES_compressed_paletted_texture ",
+                "GL_OES_depth24 ",
+                "GL_OES_framebuffer_object ",
+                "GL_OES_mapbuffer ",
+                "GL_OES_rgb8_rgba8 ",
+                "GL_OES_texture_mirrored_repeat ",
+            ),
+            (true, gles11::EXTENSIONS) => concat!(
+                "GL_EXT_texture_filter_anisotropic ",
+                "GL_IMG_texture_compression_pvrtc ",
+                "GL_OES_compressed_ETC1_RGB8_texture ",
+                "GL_OES_depth24 ",
+                "GL_OES_mapbuffer ",
+                "GL_OES_rgb8_rgba8 ",
+                "GL_OES_standard_derivatives ",
+            ),
+            _ => {
+                log!("Warning: glGetString({:#x}) => NULL, unknown name", name);
+                set_gl_error(env, gles11::INVALID_ENUM);
+                return ConstPtr::null();
+            }
+        };
+        let new_str = env.mem.alloc_and_write_cstr(s.as_bytes()).cast_const();
         env.framework_state
             .opengles
             .strings_cache
-            .insert(name, new_str);
+            .insert(key, new_str);
         new_str
     };
-    log_dbg!("glGetString({}) => {:?}", name, res);
+    log_dbg!("glGetString({:#x}) => {:?}", name, res);
     res
 }
 
@@ -317,8 +361,17 @@ fn glGenBuffers(env: &mut Environment, n: GLsizei, buffers: MutPtr<GLuint>) {
     })
 }
 fn glDeleteBuffers(env: &mut Environment, n: GLsizei, buffers: ConstPtr<GLuint>) {
+    // Deleting a buffer unmaps it.
+    let n_usize: GuestUSize = n.try_into().unwrap();
+    let ctx = current_ctx(env);
+    for i in 0..n_usize {
+        let buffer = env.mem.read(buffers + i);
+        let mapped_buffers = &mut env.framework_state.opengles.mapped_buffers;
+        if let Some(guest_ptr) = mapped_buffers.remove(&(ctx, buffer)) {
+            env.mem.free(guest_ptr);
+        }
+    }
     with_ctx_and_mem(env, |gles, mem| {
-        let n_usize: GuestUSize = n.try_into().unwrap();
         let buffers = mem.ptr_at(buffers, n_usize);
         unsafe { gles.DeleteBuffers(n, buffers) }
     })
@@ -361,6 +414,28 @@ fn glBufferSubData(
         gles.BufferSubData(target, offset as HostGLintptr, size as HostGLsizeiptr, data)
     })
 }
+fn glIsBuffer(env: &mut Environment, buffer: GLuint) -> GLboolean {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.IsBuffer(buffer) })
+}
+fn glGetBufferParameteriv(
+    env: &mut Environment,
+    target: GLenum,
+    pname: GLenum,
+    params: MutPtr<GLint>,
+) {
+    match pname {
+        // The host buffer is never left mapped, see glMapBufferOES.
+        gles11::BUFFER_MAPPED_OES => {
+            let is_mapped = is_buffer_mapped(env, target);
+            env.mem.write(params, is_mapped as GLint)
+        }
+        gles11::BUFFER_ACCESS_OES => env.mem.write(params, gles11::WRITE_ONLY_OES as GLint),
+        _ => with_ctx_and_mem(env, |gles, mem| {
+            let params = mem.ptr_at_mut(params, 1);
+            unsafe { gles.GetBufferParameteriv(target, pname, params) }
+        }),
+    }
+}
 
 // Non-pointers
 fn glColor4f(env: &mut Environment, red: GLfloat, green: GLfloat, blue: GLfloat, alpha: GLfloat) {
@@ -640,6 +715,32 @@ fn glTranslatex(env: &mut Environment, x: GLfixed, y: GLfixed, z: GLfixed) {
 fn glPixelStorei(env: &mut Environment, pname: GLenum, param: GLint) {
     with_ctx_and_mem(env, |gles, _mem| unsafe { gles.PixelStorei(pname, param) })
 }
+fn glReadPixels(
+    env: &mut Environment,
+    x: GLint,
+    y: GLint,
+    width: GLsizei,
+    height: GLsizei,
+    format: GLenum,
+    type_: GLenum,
+    pixels: MutVoidPtr,
+) {
+    // The app's framebuffers are real framebuffers in the host context, with
+    // the same bottom-left origin as on an iPhone OS device, so the rows come
+    // out bottom-to-top just like the app expects and no flipping is needed.
+    with_ctx_and_mem(env, |gles, mem| unsafe {
+        if width <= 0 || height <= 0 {
+            // Nothing to read, but the host should still generate any errors.
+            gles.ReadPixels(x, y, width, height, format, type_, std::ptr::null_mut());
+            return;
+        }
+        let mut alignment = 0;
+        gles.GetIntegerv(gles11::PACK_ALIGNMENT, &mut alignment);
+        let size = image_size_with_alignment(width, height, format, type_, alignment);
+        let pixels = mem.ptr_at_mut(pixels.cast::<u8>(), size).cast::<GLvoid>();
+        gles.ReadPixels(x, y, width, height, format, type_, pixels)
+    })
+}
 fn glGenTextures(env: &mut Environment, n: GLsizei, textures: MutPtr<GLuint>) {
     with_ctx_and_mem(env, |gles, mem| {
         let n_usize: GuestUSize = n.try_into().unwrap();
@@ -730,8 +831,8 @@ fn glTexParameterxv(
         gles.TexParameterxv(target, pname, params)
     })
 }
-fn image_size_estimate(pixel_count: GuestUSize, format: GLenum, type_: GLenum) -> GuestUSize {
-    let bytes_per_pixel: GuestUSize = match type_ {
+fn bytes_per_pixel(format: GLenum, type_: GLenum) -> GuestUSize {
+    match type_ {
         gles11::UNSIGNED_BYTE => match format {
             gles11::ALPHA | gles11::LUMINANCE => 1,
             gles11::LUMINANCE_ALPHA => 2,
@@ -743,9 +844,33 @@ fn image_size_estimate(pixel_count: GuestUSize, format: GLenum, type_: GLenum) -
         | gles11::UNSIGNED_SHORT_4_4_4_4
         | gles11::UNSIGNED_SHORT_5_5_5_1 => 2,
         _ => panic!("Unexpected type {:#x}", type_),
-    };
+    }
+}
+fn image_size_estimate(pixel_count: GuestUSize, format: GLenum, type_: GLenum) -> GuestUSize {
     // This is approximate, it doesn't account for alignment.
-    pixel_count.checked_mul(bytes_per_pixel).unwrap()
+    pixel_count
+        .checked_mul(bytes_per_pixel(format, type_))
+        .unwrap()
+}
+/// Exact size of an image, where each row starts at a multiple of `alignment`
+/// (from `GL_PACK_ALIGNMENT` or `GL_UNPACK_ALIGNMENT`).
+fn image_size_with_alignment(
+    width: GLsizei,
+    height: GLsizei,
+    format: GLenum,
+    type_: GLenum,
+    alignment: GLint,
+) -> GuestUSize {
+    let width: GuestUSize = width.try_into().unwrap();
+    let height: GuestUSize = height.try_into().unwrap();
+    let alignment: GuestUSize = alignment.try_into().unwrap();
+    let row_size = width.checked_mul(bytes_per_pixel(format, type_)).unwrap();
+    let row_stride = row_size.next_multiple_of(alignment);
+    // The last row doesn't have padding.
+    row_stride
+        .checked_mul(height - 1)
+        .and_then(|size| size.checked_add(row_size))
+        .unwrap()
 }
 fn glTexImage2D(
     env: &mut Environment,
@@ -978,9 +1103,17 @@ fn glGetRenderbufferParameterivOES(
     })
 }
 fn glCheckFramebufferStatusOES(env: &mut Environment, target: GLenum) -> GLenum {
-    with_ctx_and_mem(env, |gles, _mem| unsafe {
+    let status = with_ctx_and_mem(env, |gles, _mem| unsafe {
         gles.CheckFramebufferStatusOES(target)
-    })
+    });
+    if status != gles11::FRAMEBUFFER_COMPLETE_OES {
+        log!(
+            "Warning: glCheckFramebufferStatus({:#x}) returned {:#x}, rendering to this framebuffer will fail",
+            target,
+            status
+        );
+    }
+    status
 }
 fn glDeleteFramebuffersOES(env: &mut Environment, n: GLsizei, framebuffers: ConstPtr<GLuint>) {
     with_ctx_and_mem(env, |gles, mem| {
@@ -1000,6 +1133,675 @@ fn glGenerateMipmapOES(env: &mut Environment, target: GLenum) {
     with_ctx_and_mem(env, |gles, _mem| unsafe { gles.GenerateMipmapOES(target) })
 }
 
+// OES_mapbuffer
+
+fn current_ctx(env: &mut Environment) -> id {
+    env.framework_state
+        .opengles
+        .current_ctx_for_thread(env.current_thread)
+        .unwrap()
+}
+/// Get the key for the buffer bound to `target` in
+/// [super::State::mapped_buffers].
+fn mapped_buffer_key(env: &mut Environment, target: GLenum) -> (id, GLuint) {
+    let binding = match target {
+        gles11::ARRAY_BUFFER => gles11::ARRAY_BUFFER_BINDING,
+        gles11::ELEMENT_ARRAY_BUFFER => gles11::ELEMENT_ARRAY_BUFFER_BINDING,
+        _ => panic!("Unexpected buffer target {:#x}", target),
+    };
+    let buffer = with_ctx_and_mem(env, |gles, _mem| {
+        let mut buffer = 0;
+        unsafe { gles.GetIntegerv(binding, &mut buffer) };
+        buffer as GLuint
+    });
+    (current_ctx(env), buffer)
+}
+fn is_buffer_mapped(env: &mut Environment, target: GLenum) -> bool {
+    let key = mapped_buffer_key(env, target);
+    env.framework_state
+        .opengles
+        .mapped_buffers
+        .contains_key(&key)
+}
+fn get_buffer_size(gles: &mut dyn GLES, target: GLenum) -> GuestUSize {
+    let mut size = 0;
+    unsafe { gles.GetBufferParameteriv(target, gles11::BUFFER_SIZE, &mut size) };
+    size.try_into().unwrap()
+}
+
+fn glMapBufferOES(env: &mut Environment, target: GLenum, access: GLenum) -> MutVoidPtr {
+    let key = mapped_buffer_key(env, target);
+    // TODO: GL_INVALID_OPERATION for these
+    assert!(key.1 != 0);
+    assert!(!env
+        .framework_state
+        .opengles
+        .mapped_buffers
+        .contains_key(&key));
+
+    // The host's mapping of the buffer isn't in guest memory, so the app gets
+    // a copy, which glUnmapBufferOES writes back. The host buffer is only
+    // mapped briefly to make it.
+    let size = with_ctx_and_mem(env, |gles, _mem| get_buffer_size(gles, target));
+    let guest_ptr = env.mem.alloc(size);
+    with_ctx_and_mem(env, |gles, mem| unsafe {
+        let host_ptr: *const u8 = gles.MapBufferOES(target, access).cast_const().cast();
+        assert!(!host_ptr.is_null());
+        mem.bytes_at_mut(guest_ptr.cast(), size)
+            .copy_from_slice(std::slice::from_raw_parts(host_ptr, size as usize));
+        gles.UnmapBufferOES(target);
+    });
+    env.framework_state
+        .opengles
+        .mapped_buffers
+        .insert(key, guest_ptr);
+    guest_ptr
+}
+fn glUnmapBufferOES(env: &mut Environment, target: GLenum) -> GLboolean {
+    let key = mapped_buffer_key(env, target);
+    let Some(guest_ptr) = env.framework_state.opengles.mapped_buffers.remove(&key) else {
+        // TODO: GL_INVALID_OPERATION
+        log!(
+            "Warning: glUnmapBufferOES({:#x}) called for a buffer that isn't mapped",
+            target
+        );
+        return gles11::FALSE;
+    };
+    with_ctx_and_mem(env, |gles, mem| {
+        let size = get_buffer_size(gles, target);
+        let data = mem.ptr_at(guest_ptr.cast::<u8>(), size).cast();
+        unsafe { gles.BufferSubData(target, 0, size as HostGLsizeiptr, data) }
+    });
+    env.mem.free(guest_ptr);
+    gles11::TRUE
+}
+fn glGetBufferPointervOES(
+    env: &mut Environment,
+    target: GLenum,
+    pname: GLenum,
+    params: MutPtr<MutVoidPtr>,
+) {
+    assert!(pname == gles11::BUFFER_MAP_POINTER_OES);
+    let key = mapped_buffer_key(env, target);
+    let guest_ptr = env
+        .framework_state
+        .opengles
+        .mapped_buffers
+        .get(&key)
+        .copied()
+        .unwrap_or(MutVoidPtr::null());
+    env.mem.write(params, guest_ptr);
+}
+
+// OpenGL ES 2.0 names for OES_framebuffer_object functions. The constants have
+// the same values too.
+fn glGenFramebuffers(env: &mut Environment, n: GLsizei, framebuffers: MutPtr<GLuint>) {
+    glGenFramebuffersOES(env, n, framebuffers)
+}
+fn glGenRenderbuffers(env: &mut Environment, n: GLsizei, renderbuffers: MutPtr<GLuint>) {
+    glGenRenderbuffersOES(env, n, renderbuffers)
+}
+fn glBindFramebuffer(env: &mut Environment, target: GLenum, framebuffer: GLuint) {
+    glBindFramebufferOES(env, target, framebuffer)
+}
+fn glBindRenderbuffer(env: &mut Environment, target: GLenum, renderbuffer: GLuint) {
+    glBindRenderbufferOES(env, target, renderbuffer)
+}
+fn glRenderbufferStorage(
+    env: &mut Environment,
+    target: GLenum,
+    internalformat: GLenum,
+    width: GLsizei,
+    height: GLsizei,
+) {
+    glRenderbufferStorageOES(env, target, internalformat, width, height)
+}
+fn glFramebufferRenderbuffer(
+    env: &mut Environment,
+    target: GLenum,
+    attachment: GLenum,
+    renderbuffertarget: GLenum,
+    renderbuffer: GLuint,
+) {
+    glFramebufferRenderbufferOES(env, target, attachment, renderbuffertarget, renderbuffer)
+}
+fn glFramebufferTexture2D(
+    env: &mut Environment,
+    target: GLenum,
+    attachment: GLenum,
+    textarget: GLenum,
+    texture: GLuint,
+    level: i32,
+) {
+    glFramebufferTexture2DOES(env, target, attachment, textarget, texture, level)
+}
+fn glGetRenderbufferParameteriv(
+    env: &mut Environment,
+    target: GLenum,
+    pname: GLenum,
+    params: MutPtr<GLint>,
+) {
+    glGetRenderbufferParameterivOES(env, target, pname, params)
+}
+fn glCheckFramebufferStatus(env: &mut Environment, target: GLenum) -> GLenum {
+    glCheckFramebufferStatusOES(env, target)
+}
+fn glDeleteFramebuffers(env: &mut Environment, n: GLsizei, framebuffers: ConstPtr<GLuint>) {
+    glDeleteFramebuffersOES(env, n, framebuffers)
+}
+fn glDeleteRenderbuffers(env: &mut Environment, n: GLsizei, renderbuffers: ConstPtr<GLuint>) {
+    glDeleteRenderbuffersOES(env, n, renderbuffers)
+}
+fn glGenerateMipmap(env: &mut Environment, target: GLenum) {
+    glGenerateMipmapOES(env, target)
+}
+
+// OpenGL ES 2.0
+
+/// Get a host pointer for a null-terminated string parameter.
+fn cstr_ptr(mem: &Mem, string: ConstPtr<u8>) -> *const GLchar {
+    // The terminator follows the string in guest memory.
+    mem.cstr_at(string).as_ptr().cast()
+}
+
+/// Like [Mem::ptr_at_mut], but allows null pointers, which some functions
+/// accept for parameters the app isn't interested in.
+fn ptr_at_mut_or_null<T: SafeRead>(mem: &mut Mem, ptr: MutPtr<T>, count: GuestUSize) -> *mut T {
+    if ptr.is_null() {
+        std::ptr::null_mut()
+    } else {
+        mem.ptr_at_mut(ptr, count)
+    }
+}
+
+// Shaders
+fn glCreateShader(env: &mut Environment, type_: GLenum) -> GLuint {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.CreateShader(type_) })
+}
+fn glDeleteShader(env: &mut Environment, shader: GLuint) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.DeleteShader(shader) })
+}
+fn glIsShader(env: &mut Environment, shader: GLuint) -> GLboolean {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.IsShader(shader) })
+}
+fn glShaderSource(
+    env: &mut Environment,
+    shader: GLuint,
+    count: GLsizei,
+    string: ConstPtr<ConstPtr<u8>>,
+    length: ConstPtr<GLint>,
+) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let count_usize: GuestUSize = count.try_into().unwrap();
+        let strings: Vec<*const GLchar> = (0..count_usize)
+            .map(|i| {
+                let string = mem.read(string + i);
+                // Strings with a length needn't be null-terminated.
+                if !length.is_null() && mem.read(length + i) >= 0 {
+                    let len: GuestUSize = mem.read(length + i).try_into().unwrap();
+                    mem.ptr_at(string, len).cast()
+                } else {
+                    cstr_ptr(mem, string)
+                }
+            })
+            .collect();
+        let length = if length.is_null() {
+            std::ptr::null()
+        } else {
+            mem.ptr_at(length, count_usize)
+        };
+        unsafe { gles.ShaderSource(shader, count, strings.as_ptr(), length) }
+    })
+}
+fn glCompileShader(env: &mut Environment, shader: GLuint) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.CompileShader(shader) })
+}
+fn glGetShaderiv(env: &mut Environment, shader: GLuint, pname: GLenum, params: MutPtr<GLint>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let params = mem.ptr_at_mut(params, 1);
+        unsafe { gles.GetShaderiv(shader, pname, params) }
+    })
+}
+fn glGetShaderInfoLog(
+    env: &mut Environment,
+    shader: GLuint,
+    bufsize: GLsizei,
+    length: MutPtr<GLsizei>,
+    infolog: MutPtr<u8>,
+) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let bufsize_usize: GuestUSize = bufsize.try_into().unwrap();
+        let length = ptr_at_mut_or_null(mem, length, 1);
+        let infolog = mem.ptr_at_mut(infolog, bufsize_usize).cast();
+        unsafe { gles.GetShaderInfoLog(shader, bufsize, length, infolog) }
+    })
+}
+fn glGetShaderSource(
+    env: &mut Environment,
+    shader: GLuint,
+    bufsize: GLsizei,
+    length: MutPtr<GLsizei>,
+    source: MutPtr<u8>,
+) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let bufsize_usize: GuestUSize = bufsize.try_into().unwrap();
+        let length = ptr_at_mut_or_null(mem, length, 1);
+        let source = mem.ptr_at_mut(source, bufsize_usize).cast();
+        unsafe { gles.GetShaderSource(shader, bufsize, length, source) }
+    })
+}
+fn glGetShaderPrecisionFormat(
+    env: &mut Environment,
+    shadertype: GLenum,
+    precisiontype: GLenum,
+    range: MutPtr<GLint>,
+    precision: MutPtr<GLint>,
+) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let range = mem.ptr_at_mut(range, 2);
+        let precision = mem.ptr_at_mut(precision, 1);
+        unsafe { gles.GetShaderPrecisionFormat(shadertype, precisiontype, range, precision) }
+    })
+}
+fn glReleaseShaderCompiler(env: &mut Environment) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.ReleaseShaderCompiler() })
+}
+
+// Programs
+fn glCreateProgram(env: &mut Environment) -> GLuint {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.CreateProgram() })
+}
+fn glDeleteProgram(env: &mut Environment, program: GLuint) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.DeleteProgram(program) })
+}
+fn glIsProgram(env: &mut Environment, program: GLuint) -> GLboolean {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.IsProgram(program) })
+}
+fn glAttachShader(env: &mut Environment, program: GLuint, shader: GLuint) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe {
+        gles.AttachShader(program, shader)
+    })
+}
+fn glDetachShader(env: &mut Environment, program: GLuint, shader: GLuint) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe {
+        gles.DetachShader(program, shader)
+    })
+}
+fn glGetAttachedShaders(
+    env: &mut Environment,
+    program: GLuint,
+    maxcount: GLsizei,
+    count: MutPtr<GLsizei>,
+    shaders: MutPtr<GLuint>,
+) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let maxcount_usize: GuestUSize = maxcount.try_into().unwrap();
+        let count = ptr_at_mut_or_null(mem, count, 1);
+        let shaders = mem.ptr_at_mut(shaders, maxcount_usize);
+        unsafe { gles.GetAttachedShaders(program, maxcount, count, shaders) }
+    })
+}
+fn glLinkProgram(env: &mut Environment, program: GLuint) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.LinkProgram(program) })
+}
+fn glValidateProgram(env: &mut Environment, program: GLuint) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.ValidateProgram(program) })
+}
+fn glUseProgram(env: &mut Environment, program: GLuint) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.UseProgram(program) })
+}
+fn glGetProgramiv(env: &mut Environment, program: GLuint, pname: GLenum, params: MutPtr<GLint>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let params = mem.ptr_at_mut(params, 1);
+        unsafe { gles.GetProgramiv(program, pname, params) }
+    })
+}
+fn glGetProgramInfoLog(
+    env: &mut Environment,
+    program: GLuint,
+    bufsize: GLsizei,
+    length: MutPtr<GLsizei>,
+    infolog: MutPtr<u8>,
+) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let bufsize_usize: GuestUSize = bufsize.try_into().unwrap();
+        let length = ptr_at_mut_or_null(mem, length, 1);
+        let infolog = mem.ptr_at_mut(infolog, bufsize_usize).cast();
+        unsafe { gles.GetProgramInfoLog(program, bufsize, length, infolog) }
+    })
+}
+
+// Vertex attributes
+fn glBindAttribLocation(env: &mut Environment, program: GLuint, index: GLuint, name: ConstPtr<u8>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let name = cstr_ptr(mem, name);
+        unsafe { gles.BindAttribLocation(program, index, name) }
+    })
+}
+fn glGetAttribLocation(env: &mut Environment, program: GLuint, name: ConstPtr<u8>) -> GLint {
+    with_ctx_and_mem(env, |gles, mem| {
+        let name = cstr_ptr(mem, name);
+        unsafe { gles.GetAttribLocation(program, name) }
+    })
+}
+fn glGetActiveAttrib(
+    env: &mut Environment,
+    program: GLuint,
+    index: GLuint,
+    bufsize: GLsizei,
+    length: MutPtr<GLsizei>,
+    size: MutPtr<GLint>,
+    type_: MutPtr<GLenum>,
+    name: MutPtr<u8>,
+) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let bufsize_usize: GuestUSize = bufsize.try_into().unwrap();
+        let length = ptr_at_mut_or_null(mem, length, 1);
+        let size = mem.ptr_at_mut(size, 1);
+        let type_ = mem.ptr_at_mut(type_, 1);
+        let name = mem.ptr_at_mut(name, bufsize_usize).cast();
+        unsafe { gles.GetActiveAttrib(program, index, bufsize, length, size, type_, name) }
+    })
+}
+fn glEnableVertexAttribArray(env: &mut Environment, index: GLuint) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe {
+        gles.EnableVertexAttribArray(index)
+    })
+}
+fn glDisableVertexAttribArray(env: &mut Environment, index: GLuint) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe {
+        gles.DisableVertexAttribArray(index)
+    })
+}
+fn glGetVertexAttribfv(
+    env: &mut Environment,
+    index: GLuint,
+    pname: GLenum,
+    params: MutPtr<GLfloat>,
+) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let params = mem.ptr_at_mut(params, 4);
+        unsafe { gles.GetVertexAttribfv(index, pname, params) }
+    })
+}
+fn glGetVertexAttribiv(env: &mut Environment, index: GLuint, pname: GLenum, params: MutPtr<GLint>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let params = mem.ptr_at_mut(params, 4);
+        unsafe { gles.GetVertexAttribiv(index, pname, params) }
+    })
+}
+fn glGetVertexAttribPointerv(
+    env: &mut Environment,
+    index: GLuint,
+    pname: GLenum,
+    pointer: MutPtr<MutVoidPtr>,
+) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let mut buffer_binding = 0;
+        unsafe {
+            gles.GetVertexAttribiv(
+                index,
+                gles20::VERTEX_ATTRIB_ARRAY_BUFFER_BINDING,
+                &mut buffer_binding,
+            )
+        };
+        let mut host_pointer = std::ptr::null_mut();
+        unsafe { gles.GetVertexAttribPointerv(index, pname, &mut host_pointer) };
+        let guest_pointer = if buffer_binding != 0 || host_pointer.is_null() {
+            // An offset into the bound buffer (or NULL) needs no translation.
+            MutVoidPtr::from_bits(host_pointer as usize as u32)
+        } else {
+            // This is a client-side array, so the pointer is one that
+            // translate_pointer_or_offset() got from guest memory.
+            mem.guest_ptr_for_host_ptr(host_pointer.cast_const())
+                .unwrap()
+                .cast_mut()
+        };
+        mem.write(pointer, guest_pointer);
+    })
+}
+fn glVertexAttribPointer(
+    env: &mut Environment,
+    index: GLuint,
+    size: GLint,
+    type_: GLenum,
+    normalized: GLboolean,
+    stride: GLsizei,
+    pointer: ConstVoidPtr,
+) {
+    with_ctx_and_mem(env, |gles, mem| unsafe {
+        let pointer = translate_pointer_or_offset(gles, mem, pointer, gles11::ARRAY_BUFFER_BINDING);
+        gles.VertexAttribPointer(index, size, type_, normalized, stride, pointer)
+    })
+}
+fn glVertexAttrib1f(env: &mut Environment, index: GLuint, x: GLfloat) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.VertexAttrib1f(index, x) })
+}
+fn glVertexAttrib2f(env: &mut Environment, index: GLuint, x: GLfloat, y: GLfloat) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe {
+        gles.VertexAttrib2f(index, x, y)
+    })
+}
+fn glVertexAttrib3f(env: &mut Environment, index: GLuint, x: GLfloat, y: GLfloat, z: GLfloat) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe {
+        gles.VertexAttrib3f(index, x, y, z)
+    })
+}
+fn glVertexAttrib4f(
+    env: &mut Environment,
+    index: GLuint,
+    x: GLfloat,
+    y: GLfloat,
+    z: GLfloat,
+    w: GLfloat,
+) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe {
+        gles.VertexAttrib4f(index, x, y, z, w)
+    })
+}
+fn glVertexAttrib1fv(env: &mut Environment, index: GLuint, values: ConstPtr<GLfloat>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let values = mem.ptr_at(values, 1);
+        unsafe { gles.VertexAttrib1fv(index, values) }
+    })
+}
+fn glVertexAttrib2fv(env: &mut Environment, index: GLuint, values: ConstPtr<GLfloat>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let values = mem.ptr_at(values, 2);
+        unsafe { gles.VertexAttrib2fv(index, values) }
+    })
+}
+fn glVertexAttrib3fv(env: &mut Environment, index: GLuint, values: ConstPtr<GLfloat>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let values = mem.ptr_at(values, 3);
+        unsafe { gles.VertexAttrib3fv(index, values) }
+    })
+}
+fn glVertexAttrib4fv(env: &mut Environment, index: GLuint, values: ConstPtr<GLfloat>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let values = mem.ptr_at(values, 4);
+        unsafe { gles.VertexAttrib4fv(index, values) }
+    })
+}
+
+// Uniforms
+fn glGetUniformLocation(env: &mut Environment, program: GLuint, name: ConstPtr<u8>) -> GLint {
+    with_ctx_and_mem(env, |gles, mem| {
+        let name = cstr_ptr(mem, name);
+        unsafe { gles.GetUniformLocation(program, name) }
+    })
+}
+fn glGetActiveUniform(
+    env: &mut Environment,
+    program: GLuint,
+    index: GLuint,
+    bufsize: GLsizei,
+    length: MutPtr<GLsizei>,
+    size: MutPtr<GLint>,
+    type_: MutPtr<GLenum>,
+    name: MutPtr<u8>,
+) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let bufsize_usize: GuestUSize = bufsize.try_into().unwrap();
+        let length = ptr_at_mut_or_null(mem, length, 1);
+        let size = mem.ptr_at_mut(size, 1);
+        let type_ = mem.ptr_at_mut(type_, 1);
+        let name = mem.ptr_at_mut(name, bufsize_usize).cast();
+        unsafe { gles.GetActiveUniform(program, index, bufsize, length, size, type_, name) }
+    })
+}
+fn glGetUniformfv(
+    env: &mut Environment,
+    program: GLuint,
+    location: GLint,
+    params: MutPtr<GLfloat>,
+) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let params = mem.ptr_at_mut(params, 16);
+        unsafe { gles.GetUniformfv(program, location, params) }
+    })
+}
+fn glGetUniformiv(env: &mut Environment, program: GLuint, location: GLint, params: MutPtr<GLint>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let params = mem.ptr_at_mut(params, 16);
+        unsafe { gles.GetUniformiv(program, location, params) }
+    })
+}
+fn glUniform1f(env: &mut Environment, location: GLint, x: GLfloat) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.Uniform1f(location, x) })
+}
+fn glUniform2f(env: &mut Environment, location: GLint, x: GLfloat, y: GLfloat) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.Uniform2f(location, x, y) })
+}
+fn glUniform3f(env: &mut Environment, location: GLint, x: GLfloat, y: GLfloat, z: GLfloat) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe {
+        gles.Uniform3f(location, x, y, z)
+    })
+}
+fn glUniform4f(
+    env: &mut Environment,
+    location: GLint,
+    x: GLfloat,
+    y: GLfloat,
+    z: GLfloat,
+    w: GLfloat,
+) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe {
+        gles.Uniform4f(location, x, y, z, w)
+    })
+}
+fn glUniform1i(env: &mut Environment, location: GLint, x: GLint) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.Uniform1i(location, x) })
+}
+fn glUniform2i(env: &mut Environment, location: GLint, x: GLint, y: GLint) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe { gles.Uniform2i(location, x, y) })
+}
+fn glUniform3i(env: &mut Environment, location: GLint, x: GLint, y: GLint, z: GLint) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe {
+        gles.Uniform3i(location, x, y, z)
+    })
+}
+fn glUniform4i(env: &mut Environment, location: GLint, x: GLint, y: GLint, z: GLint, w: GLint) {
+    with_ctx_and_mem(env, |gles, _mem| unsafe {
+        gles.Uniform4i(location, x, y, z, w)
+    })
+}
+fn glUniform1fv(env: &mut Environment, location: GLint, count: GLsizei, values: ConstPtr<GLfloat>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let count_usize: GuestUSize = count.try_into().unwrap();
+        let values = mem.ptr_at(values, count_usize);
+        unsafe { gles.Uniform1fv(location, count, values) }
+    })
+}
+fn glUniform2fv(env: &mut Environment, location: GLint, count: GLsizei, values: ConstPtr<GLfloat>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let count_usize: GuestUSize = count.try_into().unwrap();
+        let values = mem.ptr_at(values, count_usize * 2);
+        unsafe { gles.Uniform2fv(location, count, values) }
+    })
+}
+fn glUniform3fv(env: &mut Environment, location: GLint, count: GLsizei, values: ConstPtr<GLfloat>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let count_usize: GuestUSize = count.try_into().unwrap();
+        let values = mem.ptr_at(values, count_usize * 3);
+        unsafe { gles.Uniform3fv(location, count, values) }
+    })
+}
+fn glUniform4fv(env: &mut Environment, location: GLint, count: GLsizei, values: ConstPtr<GLfloat>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let count_usize: GuestUSize = count.try_into().unwrap();
+        let values = mem.ptr_at(values, count_usize * 4);
+        unsafe { gles.Uniform4fv(location, count, values) }
+    })
+}
+fn glUniform1iv(env: &mut Environment, location: GLint, count: GLsizei, values: ConstPtr<GLint>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let count_usize: GuestUSize = count.try_into().unwrap();
+        let values = mem.ptr_at(values, count_usize);
+        unsafe { gles.Uniform1iv(location, count, values) }
+    })
+}
+fn glUniform2iv(env: &mut Environment, location: GLint, count: GLsizei, values: ConstPtr<GLint>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let count_usize: GuestUSize = count.try_into().unwrap();
+        let values = mem.ptr_at(values, count_usize * 2);
+        unsafe { gles.Uniform2iv(location, count, values) }
+    })
+}
+fn glUniform3iv(env: &mut Environment, location: GLint, count: GLsizei, values: ConstPtr<GLint>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let count_usize: GuestUSize = count.try_into().unwrap();
+        let values = mem.ptr_at(values, count_usize * 3);
+        unsafe { gles.Uniform3iv(location, count, values) }
+    })
+}
+fn glUniform4iv(env: &mut Environment, location: GLint, count: GLsizei, values: ConstPtr<GLint>) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let count_usize: GuestUSize = count.try_into().unwrap();
+        let values = mem.ptr_at(values, count_usize * 4);
+        unsafe { gles.Uniform4iv(location, count, values) }
+    })
+}
+fn glUniformMatrix2fv(
+    env: &mut Environment,
+    location: GLint,
+    count: GLsizei,
+    transpose: GLboolean,
+    value: ConstPtr<GLfloat>,
+) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let count_usize: GuestUSize = count.try_into().unwrap();
+        let value = mem.ptr_at(value, count_usize * 4);
+        unsafe { gles.UniformMatrix2fv(location, count, transpose, value) }
+    })
+}
+fn glUniformMatrix3fv(
+    env: &mut Environment,
+    location: GLint,
+    count: GLsizei,
+    transpose: GLboolean,
+    value: ConstPtr<GLfloat>,
+) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let count_usize: GuestUSize = count.try_into().unwrap();
+        let value = mem.ptr_at(value, count_usize * 9);
+        unsafe { gles.UniformMatrix3fv(location, count, transpose, value) }
+    })
+}
+fn glUniformMatrix4fv(
+    env: &mut Environment,
+    location: GLint,
+    count: GLsizei,
+    transpose: GLboolean,
+    value: ConstPtr<GLfloat>,
+) {
+    with_ctx_and_mem(env, |gles, mem| {
+        let count_usize: GuestUSize = count.try_into().unwrap();
+        let value = mem.ptr_at(value, count_usize * 16);
+        unsafe { gles.UniformMatrix4fv(location, count, transpose, value) }
+    })
+}
+
 pub const FUNCTIONS: FunctionExports = &[
     // Generic state manipulation
     export_c_func!(glGetError()),
@@ -1052,6 +1854,8 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(glBindBuffer(_, _)),
     export_c_func!(glBufferData(_, _, _, _)),
     export_c_func!(glBufferSubData(_, _, _, _)),
+    export_c_func!(glIsBuffer(_)),
+    export_c_func!(glGetBufferParameteriv(_, _, _)),
     // Non-pointers
     export_c_func!(glColor4f(_, _, _, _)),
     export_c_func!(glColor4x(_, _, _, _)),
@@ -1094,6 +1898,7 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(glTranslatex(_, _, _)),
     // Textures
     export_c_func!(glPixelStorei(_, _)),
+    export_c_func!(glReadPixels(_, _, _, _, _, _, _)),
     export_c_func!(glGenTextures(_, _)),
     export_c_func!(glDeleteTextures(_, _)),
     export_c_func!(glActiveTexture(_)),
@@ -1128,4 +1933,86 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(glDeleteFramebuffersOES(_, _)),
     export_c_func!(glDeleteRenderbuffersOES(_, _)),
     export_c_func!(glGenerateMipmapOES(_)),
+    // OES_mapbuffer
+    export_c_func!(glMapBufferOES(_, _)),
+    export_c_func!(glUnmapBufferOES(_)),
+    export_c_func!(glGetBufferPointervOES(_, _, _)),
+    // OpenGL ES 2.0 framebuffer objects
+    export_c_func!(glGenFramebuffers(_, _)),
+    export_c_func!(glGenRenderbuffers(_, _)),
+    export_c_func!(glBindFramebuffer(_, _)),
+    export_c_func!(glBindRenderbuffer(_, _)),
+    export_c_func!(glRenderbufferStorage(_, _, _, _)),
+    export_c_func!(glFramebufferRenderbuffer(_, _, _, _)),
+    export_c_func!(glFramebufferTexture2D(_, _, _, _, _)),
+    export_c_func!(glGetRenderbufferParameteriv(_, _, _)),
+    export_c_func!(glCheckFramebufferStatus(_)),
+    export_c_func!(glDeleteFramebuffers(_, _)),
+    export_c_func!(glDeleteRenderbuffers(_, _)),
+    export_c_func!(glGenerateMipmap(_)),
+    // OpenGL ES 2.0 shaders
+    export_c_func!(glCreateShader(_)),
+    export_c_func!(glDeleteShader(_)),
+    export_c_func!(glIsShader(_)),
+    export_c_func!(glShaderSource(_, _, _, _)),
+    export_c_func!(glCompileShader(_)),
+    export_c_func!(glGetShaderiv(_, _, _)),
+    export_c_func!(glGetShaderInfoLog(_, _, _, _)),
+    export_c_func!(glGetShaderSource(_, _, _, _)),
+    export_c_func!(glGetShaderPrecisionFormat(_, _, _, _)),
+    export_c_func!(glReleaseShaderCompiler()),
+    // OpenGL ES 2.0 programs
+    export_c_func!(glCreateProgram()),
+    export_c_func!(glDeleteProgram(_)),
+    export_c_func!(glIsProgram(_)),
+    export_c_func!(glAttachShader(_, _)),
+    export_c_func!(glDetachShader(_, _)),
+    export_c_func!(glGetAttachedShaders(_, _, _, _)),
+    export_c_func!(glLinkProgram(_)),
+    export_c_func!(glValidateProgram(_)),
+    export_c_func!(glUseProgram(_)),
+    export_c_func!(glGetProgramiv(_, _, _)),
+    export_c_func!(glGetProgramInfoLog(_, _, _, _)),
+    // OpenGL ES 2.0 vertex attributes
+    export_c_func!(glBindAttribLocation(_, _, _)),
+    export_c_func!(glGetAttribLocation(_, _)),
+    export_c_func!(glGetActiveAttrib(_, _, _, _, _, _, _)),
+    export_c_func!(glEnableVertexAttribArray(_)),
+    export_c_func!(glDisableVertexAttribArray(_)),
+    export_c_func!(glGetVertexAttribfv(_, _, _)),
+    export_c_func!(glGetVertexAttribiv(_, _, _)),
+    export_c_func!(glGetVertexAttribPointerv(_, _, _)),
+    export_c_func!(glVertexAttribPointer(_, _, _, _, _, _)),
+    export_c_func!(glVertexAttrib1f(_, _)),
+    export_c_func!(glVertexAttrib2f(_, _, _)),
+    export_c_func!(glVertexAttrib3f(_, _, _, _)),
+    export_c_func!(glVertexAttrib4f(_, _, _, _, _)),
+    export_c_func!(glVertexAttrib1fv(_, _)),
+    export_c_func!(glVertexAttrib2fv(_, _)),
+    export_c_func!(glVertexAttrib3fv(_, _)),
+    export_c_func!(glVertexAttrib4fv(_, _)),
+    // OpenGL ES 2.0 uniforms
+    export_c_func!(glGetUniformLocation(_, _)),
+    export_c_func!(glGetActiveUniform(_, _, _, _, _, _, _)),
+    export_c_func!(glGetUniformfv(_, _, _)),
+    export_c_func!(glGetUniformiv(_, _, _)),
+    export_c_func!(glUniform1f(_, _)),
+    export_c_func!(glUniform2f(_, _, _)),
+    export_c_func!(glUniform3f(_, _, _, _)),
+    export_c_func!(glUniform4f(_, _, _, _, _)),
+    export_c_func!(glUniform1i(_, _)),
+    export_c_func!(glUniform2i(_, _, _)),
+    export_c_func!(glUniform3i(_, _, _, _)),
+    export_c_func!(glUniform4i(_, _, _, _, _)),
+    export_c_func!(glUniform1fv(_, _, _)),
+    export_c_func!(glUniform2fv(_, _, _)),
+    export_c_func!(glUniform3fv(_, _, _)),
+    export_c_func!(glUniform4fv(_, _, _)),
+    export_c_func!(glUniform1iv(_, _, _)),
+    export_c_func!(glUniform2iv(_, _, _)),
+    export_c_func!(glUniform3iv(_, _, _)),
+    export_c_func!(glUniform4iv(_, _, _)),
+    export_c_func!(glUniformMatrix2fv(_, _, _, _)),
+    export_c_func!(glUniformMatrix3fv(_, _, _, _)),
+    export_c_func!(glUniformMatrix4fv(_, _, _, _)),
 ];
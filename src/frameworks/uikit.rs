@@ -10,20 +10,21 @@
 //! will probably take a lot of shortcuts.
 
 use crate::Environment;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 pub mod ui_accelerometer;
-pub mod ui_activity_indicator_view;
 pub mod ui_application;
 pub mod ui_color;
 pub mod ui_device;
 pub mod ui_event;
 pub mod ui_font;
 pub mod ui_geometry;
+pub mod ui_gesture_recognizer;
 pub mod ui_graphics;
 pub mod ui_image;
 pub mod ui_image_picker_controller;
 pub mod ui_nib;
+pub mod ui_pasteboard;
 pub mod ui_responder;
 pub mod ui_screen;
 pub mod ui_touch;
@@ -38,11 +39,69 @@ pub struct State {
     ui_device: ui_device::State,
     ui_font: ui_font::State,
     ui_graphics: ui_graphics::State,
+    ui_pasteboard: ui_pasteboard::State,
+    ui_responder: ui_responder::State,
     ui_screen: ui_screen::State,
     ui_touch: ui_touch::State,
     pub ui_view: ui_view::State,
 }
 
+/// Send the app to the background and stop running it until the user brings it
+/// back, either by pressing F11 or, if `resume_on_focus` is set, by focusing
+/// the window again. This is like how iOS suspends background apps.
+fn suspend(env: &mut Environment, resume_on_focus: bool) {
+    ui_application::enter_background(env);
+
+    // The app shouldn't see time pass or hear its audio while it's suspended.
+    env.clock.pause();
+    set_audio_paused(env, true);
+    wait_for_resume(env, resume_on_focus);
+    set_audio_paused(env, false);
+    env.clock.resume();
+
+    ui_application::enter_foreground(env);
+}
+
+fn set_audio_paused(env: &mut Environment, paused: bool) {
+    super::openal::set_devices_paused(env, paused);
+    super::audio_toolbox::audio_queue::set_internal_al_device_paused(env, paused);
+}
+
+fn wait_for_resume(env: &mut Environment, resume_on_focus: bool) {
+    use crate::window::Event;
+
+    loop {
+        // Events still have to be polled regularly, like NSRunLoop does, so
+        // that the host OS doesn't consider touchHLE unresponsive.
+        std::thread::sleep(Duration::from_secs_f64(1.0 / 60.0));
+        let window = env.window.as_mut().unwrap();
+        window.poll_for_events(&env.options);
+
+        while let Some(event) = env.window.as_mut().unwrap().pop_event() {
+            match event {
+                Event::Quit => {
+                    echo!("User requested quit, exiting.");
+                    ui_application::exit(env);
+                }
+                Event::AppWillResignActive | Event::AppWillTerminate => {
+                    log!("Handling {:?} event while suspended: exiting.", event);
+                    ui_application::exit(env);
+                }
+                Event::TogglePause => {
+                    echo!("F11 pressed, resuming app.");
+                    return;
+                }
+                Event::FocusGained if resume_on_focus => {
+                    log!("Window regained focus, resuming app.");
+                    return;
+                }
+                // A suspended app doesn't get any input.
+                _ => (),
+            }
+        }
+    }
+}
+
 /// For use by `NSRunLoop`: handles any events that have queued up.
 ///
 /// Returns the next time this function must be called, if any, e.g. the next
@@ -87,6 +146,10 @@ pub fn handle_events(env: &mut Environment) -> Option<Instant> {
                 log!("Handling app-will-terminate event.");
                 ui_application::exit(env);
             }
+            Event::Escape => ui_view::ui_alert_view::handle_escape(env),
+            Event::TextInput(text_input_event) => {
+                ui_view::ui_control::ui_text_field::handle_text_input(env, text_input_event)
+            }
             Event::EnterDebugger => {
                 if env.is_debugging_enabled() {
                     log!("Handling EnterDebugger event: entering debugger.");
@@ -96,8 +159,39 @@ pub fn handle_events(env: &mut Environment) -> Option<Instant> {
                     log!("Ignoring EnterDebugger event: no debugger connected.");
                 }
             }
+            Event::FocusLost => {
+                if env.options.background_on_focus_loss {
+                    log!("Window lost focus, suspending app.");
+                    suspend(env, /* resume_on_focus: */ true);
+                }
+            }
+            Event::TogglePause => {
+                echo!("F11 pressed, suspending app. Press F11 again to resume it.");
+                suspend(env, /* resume_on_focus: */ false);
+            }
+            // This only matters while the app is suspended.
+            Event::FocusGained => (),
         }
     }
 
-    ui_accelerometer::handle_accelerometer(env)
+    ui_device::handle_orientation_change(env);
+
+    let animations_due = ui_view::run_animations(env);
+    let activity_indicators_due =
+        ui_view::ui_activity_indicator_view::animate_activity_indicators(env);
+    let progress_views_due = ui_view::ui_progress_view::animate_progress_views(env);
+
+    ui_view::layout_views_if_needed(env);
+
+    let accelerometer_due = ui_accelerometer::handle_accelerometer(env);
+
+    [
+        animations_due,
+        activity_indicators_due,
+        progress_views_due,
+        accelerometer_due,
+    ]
+    .into_iter()
+    .flatten()
+    .min()
 }
@@ -6,22 +6,68 @@
 //! `UIApplication` and `UIApplicationMain`.
 
 use super::ui_device::*;
-use crate::dyld::{export_c_func, FunctionExports};
-use crate::frameworks::foundation::{ns_array, ns_string};
+use crate::dyld::{export_c_func, ConstantExports, FunctionExports, HostConstant};
+use crate::frameworks::foundation::{ns_array, ns_string, NSInteger};
 use crate::frameworks::uikit::ui_nib::load_main_nib_file;
 use crate::mem::MutPtr;
 use crate::objc::{
-    autorelease, id, msg, msg_class, nil, objc_classes, release, retain, ClassExports, HostObject,
-    NSZonePtr,
+    autorelease, id, msg, msg_class, msg_send, nil, objc_classes, release, retain, ClassExports,
+    HostObject, NSZonePtr,
 };
 use crate::window::DeviceOrientation;
 use crate::Environment;
 
+pub const UIApplicationDidFinishLaunchingNotification: &str =
+    "UIApplicationDidFinishLaunchingNotification";
+pub const UIApplicationDidBecomeActiveNotification: &str =
+    "UIApplicationDidBecomeActiveNotification";
+pub const UIApplicationWillResignActiveNotification: &str =
+    "UIApplicationWillResignActiveNotification";
+pub const UIApplicationDidEnterBackgroundNotification: &str =
+    "UIApplicationDidEnterBackgroundNotification";
+pub const UIApplicationWillEnterForegroundNotification: &str =
+    "UIApplicationWillEnterForegroundNotification";
+pub const UIApplicationWillTerminateNotification: &str = "UIApplicationWillTerminateNotification";
+
+/// `NSNotificationName` values.
+pub const CONSTANTS: ConstantExports = &[
+    (
+        "_UIApplicationDidFinishLaunchingNotification",
+        HostConstant::NSString(UIApplicationDidFinishLaunchingNotification),
+    ),
+    (
+        "_UIApplicationDidBecomeActiveNotification",
+        HostConstant::NSString(UIApplicationDidBecomeActiveNotification),
+    ),
+    (
+        "_UIApplicationWillResignActiveNotification",
+        HostConstant::NSString(UIApplicationWillResignActiveNotification),
+    ),
+    (
+        "_UIApplicationDidEnterBackgroundNotification",
+        HostConstant::NSString(UIApplicationDidEnterBackgroundNotification),
+    ),
+    (
+        "_UIApplicationWillEnterForegroundNotification",
+        HostConstant::NSString(UIApplicationWillEnterForegroundNotification),
+    ),
+    (
+        "_UIApplicationWillTerminateNotification",
+        HostConstant::NSString(UIApplicationWillTerminateNotification),
+    ),
+];
+
+pub type UIApplicationState = NSInteger;
+pub const UIApplicationStateActive: UIApplicationState = 0;
+pub const UIApplicationStateInactive: UIApplicationState = 1;
+pub const UIApplicationStateBackground: UIApplicationState = 2;
+
 #[derive(Default)]
 pub struct State {
     /// [UIApplication sharedApplication]
     shared_application: Option<id>,
     pub(super) status_bar_hidden: bool,
+    application_state: UIApplicationState,
 }
 
 struct UIApplicationHostObject {
@@ -142,6 +188,10 @@ pub const CLASSES: ClassExports = objc_classes! {
     log!("TODO: ignoring endIgnoringInteractionEvents");
 }
 
+- (UIApplicationState)applicationState {
+    env.framework_state.uikit.ui_application.application_state
+}
+
 - (id)windows {
     log!("TODO: UIApplication's windows getter is returning only visible windows");
     let visible_windows: Vec<id> = (*env
@@ -175,6 +225,8 @@ pub(super) fn UIApplicationMain(
     // It's not clear what granularity this should happen with, but this
     // granularity has already caught several bugs. :)
 
+    env.framework_state.uikit.ui_application.application_state = UIApplicationStateInactive;
+
     let ui_application = {
         let pool: id = msg_class![env; NSAutoreleasePool new];
 
@@ -233,6 +285,11 @@ pub(super) fn UIApplicationMain(
         ) {
             () = msg![env; delegate applicationDidFinishLaunching:ui_application];
         }
+        post_notification(
+            env,
+            ui_application,
+            UIApplicationDidFinishLaunchingNotification,
+        );
 
         let _: () = msg![env; pool drain];
     }
@@ -246,20 +303,13 @@ pub(super) fn UIApplicationMain(
 
     // Send applicationDidBecomeActive now that the application is ready to
     // become active.
-    {
-        let pool: id = msg_class![env; NSAutoreleasePool new];
-        let delegate: id = msg![env; ui_application delegate];
-        if env
-            .objc
-            .object_has_method_named(&env.mem, delegate, "applicationDidBecomeActive:")
-        {
-            () = msg![env; delegate applicationDidBecomeActive:ui_application];
-        }
-        let _: () = msg![env; pool drain];
-    }
-
-    // FIXME: There are more messages we should send.
-    // TODO: Send UIApplicationDidFinishLaunchingNotification?
+    env.framework_state.uikit.ui_application.application_state = UIApplicationStateActive;
+    notify_delegate(
+        env,
+        ui_application,
+        "applicationDidBecomeActive:",
+        UIApplicationDidBecomeActiveNotification,
+    );
 
     // TODO: It might be nicer to return from this function (even though it's
     // conceptually noreturn) and set some global flag that changes how the
@@ -270,35 +320,110 @@ pub(super) fn UIApplicationMain(
     let _: () = msg![env; run_loop run];
 }
 
+fn post_notification(env: &mut Environment, ui_application: id, name: &'static str) {
+    let name = ns_string::get_static_str(env, name);
+    let center: id = msg_class![env; NSNotificationCenter defaultCenter];
+    let _: () = msg![env; center postNotificationName:name object:ui_application];
+}
+
+/// Send a `UIApplicationDelegate` message, if the delegate implements it, and
+/// then post the matching notification, like iOS does.
+fn notify_delegate(
+    env: &mut Environment,
+    ui_application: id,
+    selector: &str,
+    notification: &'static str,
+) {
+    let pool: id = msg_class![env; NSAutoreleasePool new];
+    let delegate: id = msg![env; ui_application delegate];
+    if env
+        .objc
+        .object_has_method_named(&env.mem, delegate, selector)
+    {
+        let selector = env.objc.lookup_selector(selector).unwrap();
+        let _: () = msg_send(env, (delegate, selector, ui_application));
+    }
+    post_notification(env, ui_application, notification);
+    let _: () = msg![env; pool drain];
+}
+
+/// Tell the app it's going into the background, e.g. because the window lost
+/// focus or the user paused it. Does nothing if it's already there.
+///
+/// iOS suspends apps in the background, so the caller should stop running the
+/// app until [enter_foreground] is called.
+pub(super) fn enter_background(env: &mut Environment) {
+    let state = &mut env.framework_state.uikit.ui_application;
+    if state.application_state == UIApplicationStateBackground {
+        return;
+    }
+    let ui_application = state.shared_application.unwrap();
+
+    state.application_state = UIApplicationStateInactive;
+    notify_delegate(
+        env,
+        ui_application,
+        "applicationWillResignActive:",
+        UIApplicationWillResignActiveNotification,
+    );
+
+    env.framework_state.uikit.ui_application.application_state = UIApplicationStateBackground;
+    notify_delegate(
+        env,
+        ui_application,
+        "applicationDidEnterBackground:",
+        UIApplicationDidEnterBackgroundNotification,
+    );
+}
+
+/// Tell the app it's returning from the background (see [enter_background]).
+/// Does nothing if it's not in the background.
+pub(super) fn enter_foreground(env: &mut Environment) {
+    let state = &mut env.framework_state.uikit.ui_application;
+    if state.application_state != UIApplicationStateBackground {
+        return;
+    }
+    let ui_application = state.shared_application.unwrap();
+
+    state.application_state = UIApplicationStateInactive;
+    notify_delegate(
+        env,
+        ui_application,
+        "applicationWillEnterForeground:",
+        UIApplicationWillEnterForegroundNotification,
+    );
+
+    env.framework_state.uikit.ui_application.application_state = UIApplicationStateActive;
+    notify_delegate(
+        env,
+        ui_application,
+        "applicationDidBecomeActive:",
+        UIApplicationDidBecomeActiveNotification,
+    );
+}
+
 /// Tell the app it's about to quit and then exit.
 pub(super) fn exit(env: &mut Environment) {
     let ui_application: id = msg_class![env; UIApplication sharedApplication];
 
-    // TODO: send notifications also
-
-    {
-        let pool: id = msg_class![env; NSAutoreleasePool new];
-        let delegate: id = msg![env; ui_application delegate];
-        if env
-            .objc
-            .object_has_method_named(&env.mem, delegate, "applicationWillResignActive:")
-        {
-            () = msg![env; delegate applicationWillResignActive:ui_application];
-        }
-        let _: () = msg![env; pool drain];
-    };
+    // An app in the background has already resigned active.
+    let state = &mut env.framework_state.uikit.ui_application;
+    if state.application_state != UIApplicationStateBackground {
+        state.application_state = UIApplicationStateInactive;
+        notify_delegate(
+            env,
+            ui_application,
+            "applicationWillResignActive:",
+            UIApplicationWillResignActiveNotification,
+        );
+    }
 
-    {
-        let pool: id = msg_class![env; NSAutoreleasePool new];
-        let delegate: id = msg![env; ui_application delegate];
-        if env
-            .objc
-            .object_has_method_named(&env.mem, delegate, "applicationWillTerminate:")
-        {
-            () = msg![env; delegate applicationWillTerminate:ui_application];
-        }
-        let _: () = msg![env; pool drain];
-    };
+    notify_delegate(
+        env,
+        ui_application,
+        "applicationWillTerminate:",
+        UIApplicationWillTerminateNotification,
+    );
 
     std::process::exit(0);
 }
@@ -4,11 +4,19 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 //! `UIColor`.
+//!
+//! `CGColorRef` is the same type as `UIColor*` in our implementation, see
+//! [crate::frameworks::core_graphics::cg_color].
 
+use super::ui_graphics::UIGraphicsGetCurrentContext;
+use crate::frameworks::core_graphics::cg_color::CGColorRef;
+use crate::frameworks::core_graphics::cg_context::CGContextSetFillColorWithColor;
+use crate::frameworks::core_graphics::cg_image::{CGImageRef, CGImageRelease, CGImageRetain};
 use crate::frameworks::core_graphics::CGFloat;
-use crate::mem::MutPtr;
+use crate::mem::{ConstPtr, GuestUSize, MutPtr};
 use crate::objc::{
-    autorelease, id, msg, msg_class, objc_classes, ClassExports, HostObject, NSZonePtr, ObjC, SEL,
+    autorelease, id, msg, msg_class, nil, objc_classes, ClassExports, HostObject, NSZonePtr, ObjC,
+    SEL,
 };
 use crate::Environment;
 use std::collections::HashMap;
@@ -40,8 +48,54 @@ fn get_standard_color(
     }
 }
 
+/// Convert hue, saturation and brightness (all in the range 0 to 1) to RGB,
+/// the same way Cocoa does.
+fn hsb_to_rgb(h: CGFloat, s: CGFloat, v: CGFloat) -> (CGFloat, CGFloat, CGFloat) {
+    let h = h.clamp(0.0, 1.0);
+    let s = s.clamp(0.0, 1.0);
+    let v = v.clamp(0.0, 1.0);
+    // A hue of 1.0 is the same as 0.0 (red).
+    let h = if h == 1.0 { 0.0 } else { h * 6.0 };
+    let sector = h.floor();
+    let f = h - sector;
+    let p = v * (1.0 - s);
+    let q = v * (1.0 - s * f);
+    let t = v * (1.0 - s * (1.0 - f));
+    match sector as u32 {
+        0 => (v, t, p),
+        1 => (q, v, p),
+        2 => (p, v, t),
+        3 => (p, q, v),
+        4 => (t, p, v),
+        _ => (v, p, q),
+    }
+}
+
+/// The inverse of [hsb_to_rgb].
+fn rgb_to_hsb(r: CGFloat, g: CGFloat, b: CGFloat) -> (CGFloat, CGFloat, CGFloat) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let v = max;
+    let s = if max == 0.0 { 0.0 } else { delta / max };
+    let h = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    };
+    (h / 6.0, s, v)
+}
+
 struct UIColorHostObject {
     rgba: (CGFloat, CGFloat, CGFloat, CGFloat),
+    /// Image to tile, for `colorWithPatternImage:`. Strong reference.
+    pattern: CGImageRef,
+    /// Guest memory for `CGColorGetComponents`, allocated on demand.
+    components: Option<MutPtr<CGFloat>>,
 }
 impl HostObject for UIColorHostObject {}
 
@@ -54,10 +108,21 @@ pub const CLASSES: ClassExports = objc_classes! {
 + (id)allocWithZone:(NSZonePtr)_zone {
     let host_object = Box::new(UIColorHostObject {
         rgba: (0.0, 0.0, 0.0, 0.0),
+        pattern: nil,
+        components: None,
     });
     env.objc.alloc_object(this, host_object, &mut env.mem)
 }
 
+- (())dealloc {
+    let &UIColorHostObject { pattern, components, .. } = env.objc.borrow(this);
+    CGImageRelease(env, pattern);
+    if let Some(components) = components {
+        env.mem.free(components.cast());
+    }
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
 + (id)colorWithRed:(CGFloat)r
              green:(CGFloat)g
               blue:(CGFloat)b
@@ -73,6 +138,27 @@ pub const CLASSES: ClassExports = objc_classes! {
     autorelease(env, new)
 }
 
++ (id)colorWithHue:(CGFloat)h
+        saturation:(CGFloat)s
+        brightness:(CGFloat)b
+             alpha:(CGFloat)a {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithHue:h saturation:s brightness:b alpha:a];
+    autorelease(env, new)
+}
+
++ (id)colorWithPatternImage:(id)image { // UIImage*
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithPatternImage:image];
+    autorelease(env, new)
+}
+
++ (id)colorWithCGColor:(CGColorRef)cg_color {
+    let new: id = msg![env; this alloc];
+    let new: id = msg![env; new initWithCGColor:cg_color];
+    autorelease(env, new)
+}
+
 + (id)clearColor    { get_standard_color(env, _cmd, 0.0, 0.0, 0.0, 0.0) }
 + (id)blackColor    { get_standard_color(env, _cmd, 0.0, 0.0, 0.0, 1.0) }
 + (id)whiteColor    { get_standard_color(env, _cmd, 1.0, 1.0, 1.0, 1.0) }
@@ -91,10 +177,24 @@ pub const CLASSES: ClassExports = objc_classes! {
 + (id)greenColor    { get_standard_color(env, _cmd, 0.0, 1.0, 0.0, 1.0) }
 + (id)magentaColor  { get_standard_color(env, _cmd, 1.0, 0.0, 1.0, 1.0) }
 + (id)orangeColor   { get_standard_color(env, _cmd, 1.0, 0.5, 0.0, 1.0) }
-+ (id)purpleColor   { get_standard_color(env, _cmd, 0.5, 0.0, 1.5, 1.0) }
++ (id)purpleColor   { get_standard_color(env, _cmd, 0.5, 0.0, 0.5, 1.0) }
 + (id)redColor      { get_standard_color(env, _cmd, 1.0, 0.0, 0.0, 1.0) }
 + (id)yellowColor   { get_standard_color(env, _cmd, 1.0, 1.0, 0.0, 1.0) }
 
++ (id)lightTextColor { get_standard_color(env, _cmd, 1.0, 1.0, 1.0, 0.6) }
++ (id)darkTextColor  { get_standard_color(env, _cmd, 0.0, 0.0, 0.0, 1.0) }
+// The real versions of these two are patterns, but approximating them with
+// their average colors is good enough.
++ (id)groupTableViewBackgroundColor {
+    get_standard_color(env, _cmd, 197.0/255.0, 204.0/255.0, 212.0/255.0, 1.0)
+}
++ (id)scrollViewTexturedBackgroundColor {
+    get_standard_color(env, _cmd, 111.0/255.0, 113.0/255.0, 121.0/255.0, 1.0)
+}
++ (id)viewFlipsideBackgroundColor {
+    get_standard_color(env, _cmd, 31.0/255.0, 33.0/255.0, 36.0/255.0, 1.0)
+}
+
 // TODO: more initializers, set methods, more accessors
 
 - (id)initWithWhite:(CGFloat)w alpha:(CGFloat)a {
@@ -114,6 +214,44 @@ pub const CLASSES: ClassExports = objc_classes! {
     this
 }
 
+- (id)initWithHue:(CGFloat)h
+       saturation:(CGFloat)s
+       brightness:(CGFloat)b
+            alpha:(CGFloat)a {
+    let (r, g, b) = hsb_to_rgb(h, s, b);
+    let a = a.clamp(0.0, 1.0);
+    env.objc.borrow_mut::<UIColorHostObject>(this).rgba = (r, g, b, a);
+    this
+}
+
+- (id)initWithPatternImage:(id)image { // UIImage*
+    let cg_image: CGImageRef = msg![env; image CGImage];
+    CGImageRetain(env, cg_image);
+    let host_obj = env.objc.borrow_mut::<UIColorHostObject>(this);
+    // Used when drawing the pattern isn't supported.
+    host_obj.rgba = (0.5, 0.5, 0.5, 1.0);
+    host_obj.pattern = cg_image;
+    this
+}
+
+- (id)initWithCGColor:(CGColorRef)cg_color {
+    let &UIColorHostObject { rgba, pattern, .. } = env.objc.borrow(cg_color);
+    CGImageRetain(env, pattern);
+    let host_obj = env.objc.borrow_mut::<UIColorHostObject>(this);
+    host_obj.rgba = rgba;
+    host_obj.pattern = pattern;
+    this
+}
+
+- (id)colorWithAlphaComponent:(CGFloat)a {
+    let (r, g, b, _) = env.objc.borrow::<UIColorHostObject>(this).rgba;
+    msg_class![env; UIColor colorWithRed:r green:g blue:b alpha:(a.clamp(0.0, 1.0))]
+}
+
+- (CGColorRef)CGColor {
+    this
+}
+
 - (bool)getRed:(MutPtr<CGFloat>)r
          green:(MutPtr<CGFloat>)g
           blue:(MutPtr<CGFloat>)b
@@ -126,6 +264,41 @@ pub const CLASSES: ClassExports = objc_classes! {
     true
 }
 
+- (bool)getHue:(MutPtr<CGFloat>)h
+    saturation:(MutPtr<CGFloat>)s
+    brightness:(MutPtr<CGFloat>)b
+         alpha:(MutPtr<CGFloat>)a {
+    let (r, g, b_, a_) = env.objc.borrow::<UIColorHostObject>(this).rgba;
+    let (h_, s_, v_) = rgb_to_hsb(r, g, b_);
+    env.mem.write(h, h_);
+    env.mem.write(s, s_);
+    env.mem.write(b, v_);
+    env.mem.write(a, a_);
+    true
+}
+
+- (bool)getWhite:(MutPtr<CGFloat>)w
+           alpha:(MutPtr<CGFloat>)a {
+    let (r, g, b, a_) = env.objc.borrow::<UIColorHostObject>(this).rgba;
+    // Only colors created as grayscale can be converted.
+    if r != g || g != b {
+        return false;
+    }
+    env.mem.write(w, r);
+    env.mem.write(a, a_);
+    true
+}
+
+- (())set {
+    () = msg![env; this setFill];
+}
+- (())setFill {
+    let context = UIGraphicsGetCurrentContext(env);
+    if context != nil {
+        CGContextSetFillColorWithColor(env, context, this);
+    }
+}
+
 @end
 
 };
@@ -135,3 +308,62 @@ pub const CLASSES: ClassExports = objc_classes! {
 pub fn get_rgba(objc: &ObjC, ui_color: id) -> (CGFloat, CGFloat, CGFloat, CGFloat) {
     objc.borrow::<UIColorHostObject>(ui_color).rgba
 }
+
+/// Shortcut for use in Core Animation's compositor: get the image a
+/// `UIColor*` tiles, if it's a pattern color.
+pub fn get_pattern(objc: &ObjC, ui_color: id) -> Option<CGImageRef> {
+    let pattern = objc.borrow::<UIColorHostObject>(ui_color).pattern;
+    (pattern != nil).then_some(pattern)
+}
+
+/// For use by `CGColorGetComponents`: get a pointer to the RGBA components in
+/// guest memory, which lives as long as the color.
+pub(crate) fn get_components_ptr(env: &mut Environment, ui_color: id) -> ConstPtr<CGFloat> {
+    let &UIColorHostObject {
+        rgba, components, ..
+    } = env.objc.borrow(ui_color);
+    if let Some(components) = components {
+        return components.cast_const();
+    }
+    let (r, g, b, a) = rgba;
+    let components: MutPtr<CGFloat> = env.mem.alloc(4 * 4).cast();
+    for (i, component) in [r, g, b, a].into_iter().enumerate() {
+        env.mem.write(components + i as GuestUSize, component);
+    }
+    env.objc
+        .borrow_mut::<UIColorHostObject>(ui_color)
+        .components = Some(components);
+    components.cast_const()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_rgb_eq(a: (CGFloat, CGFloat, CGFloat), b: (CGFloat, CGFloat, CGFloat)) {
+        assert!(
+            (a.0 - b.0).abs() < 1e-6 && (a.1 - b.1).abs() < 1e-6 && (a.2 - b.2).abs() < 1e-6,
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn hsb_to_rgb_primaries() {
+        assert_rgb_eq(hsb_to_rgb(0.0, 1.0, 1.0), (1.0, 0.0, 0.0));
+        assert_rgb_eq(hsb_to_rgb(1.0 / 3.0, 1.0, 1.0), (0.0, 1.0, 0.0));
+        assert_rgb_eq(hsb_to_rgb(2.0 / 3.0, 1.0, 1.0), (0.0, 0.0, 1.0));
+        assert_rgb_eq(hsb_to_rgb(1.0, 1.0, 1.0), (1.0, 0.0, 0.0));
+        assert_rgb_eq(hsb_to_rgb(0.5, 0.0, 0.25), (0.25, 0.25, 0.25));
+        assert_rgb_eq(hsb_to_rgb(1.0 / 12.0, 1.0, 1.0), (1.0, 0.5, 0.0));
+    }
+
+    #[test]
+    fn hsb_round_trip() {
+        for &(r, g, b) in &[(0.2, 0.4, 0.6), (0.9, 0.1, 0.3), (0.5, 0.5, 0.1)] {
+            let (h, s, v) = rgb_to_hsb(r, g, b);
+            assert_rgb_eq(hsb_to_rgb(h, s, v), (r, g, b));
+        }
+    }
+}
@@ -5,9 +5,11 @@
  */
 //! `UIDevice`.
 
-use crate::frameworks::foundation::ns_string;
-use crate::frameworks::foundation::NSInteger;
-use crate::objc::{id, objc_classes, ClassExports, TrivialHostObject};
+use crate::dyld::{ConstantExports, HostConstant};
+use crate::frameworks::foundation::{ns_string, NSInteger};
+use crate::objc::{autorelease, id, msg, msg_class, objc_classes, ClassExports, TrivialHostObject};
+use crate::window::DeviceOrientation;
+use crate::Environment;
 
 pub type UIDeviceOrientation = NSInteger;
 #[allow(dead_code)]
@@ -22,9 +24,49 @@ pub const UIDeviceOrientationFaceUp: UIDeviceOrientation = 5;
 #[allow(dead_code)]
 pub const UIDeviceOrientationFaceDown: UIDeviceOrientation = 6;
 
+type UIDeviceBatteryState = NSInteger;
+const UIDeviceBatteryStateUnknown: UIDeviceBatteryState = 0;
+#[allow(dead_code)]
+const UIDeviceBatteryStateUnplugged: UIDeviceBatteryState = 1;
+#[allow(dead_code)]
+const UIDeviceBatteryStateCharging: UIDeviceBatteryState = 2;
+const UIDeviceBatteryStateFull: UIDeviceBatteryState = 3;
+
+pub const UIDeviceOrientationDidChangeNotification: &str =
+    "UIDeviceOrientationDidChangeNotification";
+
+/// `NSNotificationName` values.
+pub const CONSTANTS: ConstantExports = &[(
+    "_UIDeviceOrientationDidChangeNotification",
+    HostConstant::NSString(UIDeviceOrientationDidChangeNotification),
+)];
+
+/// Real devices report a 40-digit hexadecimal UDID. touchHLE doesn't have a
+/// meaningful one, so it always reports the same made-up value.
+const UNIQUE_IDENTIFIER: &str = "70756368484c4500000000000000000000000000";
+
 #[derive(Default)]
 pub struct State {
     current_device: Option<id>,
+    /// Calls to `beginGeneratingDeviceOrientationNotifications` can be nested,
+    /// so this is a count rather than a boolean.
+    orientation_notifications: u32,
+    /// The orientation last reported by a notification.
+    last_orientation: Option<UIDeviceOrientation>,
+    battery_monitoring_enabled: bool,
+}
+
+fn current_orientation(env: &Environment) -> UIDeviceOrientation {
+    // There's no window in headless mode, but an upright device is a reasonable
+    // thing to report.
+    let Some(window) = env.window.as_ref() else {
+        return UIDeviceOrientationPortrait;
+    };
+    match window.current_rotation() {
+        DeviceOrientation::Portrait => UIDeviceOrientationPortrait,
+        DeviceOrientation::LandscapeLeft => UIDeviceOrientationLandscapeLeft,
+        DeviceOrientation::LandscapeRight => UIDeviceOrientationLandscapeRight,
+    }
 }
 
 pub const CLASSES: ClassExports = objc_classes! {
@@ -48,31 +90,108 @@ pub const CLASSES: ClassExports = objc_classes! {
 }
 
 - (())beginGeneratingDeviceOrientationNotifications {
-    log!("TODO: beginGeneratingDeviceOrientationNotifications");
+    let state = &mut env.framework_state.uikit.ui_device;
+    if state.orientation_notifications == 0 {
+        state.last_orientation = None;
+    }
+    state.orientation_notifications += 1;
 }
 - (())endGeneratingDeviceOrientationNotifications {
-    log!("TODO: endGeneratingDeviceOrientationNotifications");
+    let state = &mut env.framework_state.uikit.ui_device;
+    state.orientation_notifications = state.orientation_notifications.saturating_sub(1);
 }
+- (bool)isGeneratingDeviceOrientationNotifications {
+    env.framework_state.uikit.ui_device.orientation_notifications > 0
+}
+
+- (UIDeviceOrientation)orientation {
+    // Apple's documentation says the orientation is always unknown unless
+    // orientation notifications are being generated.
+    if env.framework_state.uikit.ui_device.orientation_notifications == 0 {
+        UIDeviceOrientationUnknown
+    } else {
+        current_orientation(env)
+    }
+}
+
+// NSString
 - (id)model {
     // TODO: Hardcoded to iPhone for now
     ns_string::get_static_str(env, "iPhone")
 }
-
-// NSString
+- (id)localizedModel {
+    msg![env; this model]
+}
+- (id)name {
+    ns_string::get_static_str(env, "touchHLE")
+}
+- (id)systemName {
+    ns_string::get_static_str(env, "iPhone OS")
+}
 - (id)systemVersion {
-    ns_string::get_static_str(env, "2.0")
+    let version = env.options.system_version.clone();
+    let version = ns_string::from_rust_string(env, version);
+    autorelease(env, version)
 }
 
 - (id)uniqueIdentifier {
-    // Aspen Simulator returns (null) here
-    // TODO: what should be a correct value?
-    ns_string::get_static_str(env, "touchHLEdevice")
+    ns_string::get_static_str(env, UNIQUE_IDENTIFIER)
 }
 
 - (bool)isMultitaskingSupported {
     false
 }
 
+- (bool)isBatteryMonitoringEnabled {
+    env.framework_state.uikit.ui_device.battery_monitoring_enabled
+}
+- (())setBatteryMonitoringEnabled:(bool)enabled {
+    env.framework_state.uikit.ui_device.battery_monitoring_enabled = enabled;
+}
+// The emulated device is always fully charged and plugged in. When battery
+// monitoring is disabled, the real device reports an unknown state.
+- (f32)batteryLevel {
+    if env.framework_state.uikit.ui_device.battery_monitoring_enabled {
+        1.0
+    } else {
+        -1.0
+    }
+}
+- (UIDeviceBatteryState)batteryState {
+    if env.framework_state.uikit.ui_device.battery_monitoring_enabled {
+        UIDeviceBatteryStateFull
+    } else {
+        UIDeviceBatteryStateUnknown
+    }
+}
+
 @end
 
 };
+
+/// For use by `NSRunLoop` via [super::handle_events]: post
+/// `UIDeviceOrientationDidChangeNotification` if the orientation has changed
+/// and the app asked for such notifications.
+pub(super) fn handle_orientation_change(env: &mut Environment) {
+    if env
+        .framework_state
+        .uikit
+        .ui_device
+        .orientation_notifications
+        == 0
+    {
+        return;
+    }
+
+    let orientation = current_orientation(env);
+    let state = &mut env.framework_state.uikit.ui_device;
+    if state.last_orientation == Some(orientation) {
+        return;
+    }
+    state.last_orientation = Some(orientation);
+
+    let device: id = msg_class![env; UIDevice currentDevice];
+    let name = ns_string::get_static_str(env, UIDeviceOrientationDidChangeNotification);
+    let center: id = msg_class![env; NSNotificationCenter defaultCenter];
+    let _: () = msg![env; center postNotificationName:name object:device];
+}
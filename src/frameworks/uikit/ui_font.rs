@@ -6,11 +6,15 @@
 //! `UIFont`.
 
 use super::ui_graphics::UIGraphicsGetCurrentContext;
-use crate::font::{Font, TextAlignment, WrapMode};
+use crate::font::{Font, FontMetrics, Overflow, TextAlignment, WrapMode};
+use crate::frameworks::core_graphics::cg_affine_transform::CGAffineTransform;
 use crate::frameworks::core_graphics::cg_bitmap_context::CGBitmapContextDrawer;
+use crate::frameworks::core_graphics::cg_context::CGContextRef;
 use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
-use crate::frameworks::foundation::NSInteger;
-use crate::objc::{autorelease, id, objc_classes, ClassExports, HostObject};
+use crate::frameworks::foundation::{ns_array, ns_string, NSInteger};
+use crate::objc::{
+    autorelease, id, msg, nil, objc_classes, retain, Class, ClassExports, HostObject,
+};
 use crate::Environment;
 use std::ops::Range;
 
@@ -23,19 +27,82 @@ pub(super) struct State {
     bold_ja: Option<Font>,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq)]
 enum FontKind {
     Regular,
     Bold,
     Italic,
 }
 
+/// The fonts apps can ask for by name: (family name, font name, kind).
+///
+/// touchHLE only bundles the Liberation Sans family (plus Noto Sans JP as a
+/// fallback for Japanese text, see [get_font]), so it is also used for the
+/// fonts it's metrically compatible with. The first family is the system font.
+const FONTS: &[(&str, &str, FontKind)] = &[
+    ("Helvetica", "Helvetica", FontKind::Regular),
+    ("Helvetica", "Helvetica-Bold", FontKind::Bold),
+    ("Helvetica", "Helvetica-Oblique", FontKind::Italic),
+    ("Arial", "ArialMT", FontKind::Regular),
+    ("Arial", "Arial-BoldMT", FontKind::Bold),
+    ("Arial", "Arial-ItalicMT", FontKind::Italic),
+    ("Liberation Sans", "LiberationSans", FontKind::Regular),
+    ("Liberation Sans", "LiberationSans-Bold", FontKind::Bold),
+    ("Liberation Sans", "LiberationSans-Italic", FontKind::Italic),
+];
+
 struct UIFontHostObject {
     size: CGFloat,
     kind: FontKind,
+    /// Index into [FONTS].
+    index: usize,
 }
 impl HostObject for UIFontHostObject {}
 
+/// Create a new (autoreleased) font, loading the font file if necessary.
+fn new_font(env: &mut Environment, class: Class, size: CGFloat, index: usize) -> id {
+    let (_, _, kind) = FONTS[index];
+    let state = &mut env.framework_state.uikit.ui_font;
+    // Cache for later use
+    let (cached, load): (_, fn() -> Font) = match kind {
+        FontKind::Regular => (&mut state.regular, Font::sans_regular),
+        FontKind::Bold => (&mut state.bold, Font::sans_bold),
+        FontKind::Italic => (&mut state.italic, Font::sans_italic),
+    };
+    if cached.is_none() {
+        *cached = Some(load());
+    }
+    let host_object = UIFontHostObject { size, kind, index };
+    let new = env
+        .objc
+        .alloc_object(class, Box::new(host_object), &mut env.mem);
+    autorelease(env, new)
+}
+
+fn system_font_index(kind: FontKind) -> usize {
+    FONTS
+        .iter()
+        .position(|&(_, _, other)| other == kind)
+        .unwrap()
+}
+
+fn get_metrics(env: &mut Environment, font: id) -> FontMetrics {
+    let &UIFontHostObject { size, kind, .. } = env.objc.borrow(font);
+    get_font(&mut env.framework_state.uikit.ui_font, kind, "").metrics(size)
+}
+
+fn new_string_array(env: &mut Environment, strings: Vec<&'static str>) -> id {
+    let strings = strings
+        .into_iter()
+        .map(|string| {
+            let string = ns_string::get_static_str(env, string);
+            retain(env, string)
+        })
+        .collect();
+    let array = ns_array::from_vec(env, strings);
+    autorelease(env, array)
+}
+
 /// Line break mode.
 ///
 /// This is put here for convenience since it's font-related.
@@ -43,12 +110,9 @@ impl HostObject for UIFontHostObject {}
 pub type UILineBreakMode = NSInteger;
 pub const UILineBreakModeWordWrap: UILineBreakMode = 0;
 pub const UILineBreakModeCharacterWrap: UILineBreakMode = 1;
-#[allow(dead_code)]
 pub const UILineBreakModeClip: UILineBreakMode = 2;
-#[allow(dead_code)]
 pub const UILineBreakModeHeadTruncation: UILineBreakMode = 3;
 pub const UILineBreakModeTailTruncation: UILineBreakMode = 4;
-#[allow(dead_code)]
 pub const UILineBreakModeMiddleTruncation: UILineBreakMode = 5;
 
 /// Text alignment.
@@ -67,57 +131,143 @@ pub const CLASSES: ClassExports = objc_classes! {
 @implementation UIFont: NSObject
 
 + (id)systemFontOfSize:(CGFloat)size {
-    // Cache for later use
-    if env.framework_state.uikit.ui_font.regular.is_none() {
-        env.framework_state.uikit.ui_font.regular = Some(Font::sans_regular());
-    }
-    let host_object = UIFontHostObject {
-        size,
-        kind: FontKind::Regular,
-    };
-    let new = env.objc.alloc_object(this, Box::new(host_object), &mut env.mem);
-    autorelease(env, new)
+    new_font(env, this, size, system_font_index(FontKind::Regular))
 }
 + (id)boldSystemFontOfSize:(CGFloat)size {
-    // Cache for later use
-    if env.framework_state.uikit.ui_font.bold.is_none() {
-        env.framework_state.uikit.ui_font.bold = Some(Font::sans_bold());
-    }
-    let host_object = UIFontHostObject {
-        size,
-        kind: FontKind::Bold,
-    };
-    let new = env.objc.alloc_object(this, Box::new(host_object), &mut env.mem);
-    autorelease(env, new)
+    new_font(env, this, size, system_font_index(FontKind::Bold))
 }
 + (id)italicSystemFontOfSize:(CGFloat)size {
-    // Cache for later use
-    if env.framework_state.uikit.ui_font.italic.is_none() {
-        env.framework_state.uikit.ui_font.italic = Some(Font::sans_italic());
+    new_font(env, this, size, system_font_index(FontKind::Italic))
+}
+
++ (CGFloat)systemFontSize {
+    14.0
+}
++ (CGFloat)smallSystemFontSize {
+    12.0
+}
++ (CGFloat)labelFontSize {
+    17.0
+}
++ (CGFloat)buttonFontSize {
+    18.0
+}
+
++ (id)fontWithName:(id)name // NSString*
+              size:(CGFloat)size {
+    if name == nil {
+        return nil;
     }
-    let host_object = UIFontHostObject {
-        size,
-        kind: FontKind::Italic,
-    };
-    let new = env.objc.alloc_object(this, Box::new(host_object), &mut env.mem);
-    autorelease(env, new)
+    let name = ns_string::to_rust_string(env, name);
+    let index = FONTS
+        .iter()
+        .position(|&(_, other_name, _)| other_name.eq_ignore_ascii_case(&name))
+        .unwrap_or_else(|| {
+            // Using a similar-looking font is more helpful than returning nil.
+            let kind = if name.contains("Bold") {
+                FontKind::Bold
+            } else if name.contains("Italic") || name.contains("Oblique") {
+                FontKind::Italic
+            } else {
+                FontKind::Regular
+            };
+            log!(
+                "Warning: No font named {:?}, using {:?} instead",
+                name,
+                FONTS[system_font_index(kind)].1
+            );
+            system_font_index(kind)
+        });
+    new_font(env, this, size, index)
+}
+
++ (id)familyNames {
+    let mut families = Vec::new();
+    for &(family, _, _) in FONTS {
+        if !families.contains(&family) {
+            families.push(family);
+        }
+    }
+    new_string_array(env, families)
+}
++ (id)fontNamesForFamilyName:(id)family_name { // NSString*
+    let family_name = ns_string::to_rust_string(env, family_name);
+    let names = FONTS
+        .iter()
+        .filter(|&&(family, _, _)| family == family_name)
+        .map(|&(_, name, _)| name)
+        .collect();
+    new_string_array(env, names)
+}
+
+- (id)fontWithSize:(CGFloat)size {
+    let index = env.objc.borrow::<UIFontHostObject>(this).index;
+    let class: Class = msg![env; this class];
+    new_font(env, class, size, index)
+}
+
+- (id)familyName {
+    let index = env.objc.borrow::<UIFontHostObject>(this).index;
+    ns_string::get_static_str(env, FONTS[index].0)
+}
+- (id)fontName {
+    let index = env.objc.borrow::<UIFontHostObject>(this).index;
+    ns_string::get_static_str(env, FONTS[index].1)
+}
+
+- (CGFloat)pointSize {
+    env.objc.borrow::<UIFontHostObject>(this).size
+}
+- (CGFloat)ascender {
+    get_metrics(env, this).ascent
+}
+- (CGFloat)descender {
+    get_metrics(env, this).descent
+}
+- (CGFloat)leading {
+    get_metrics(env, this).line_gap
+}
+- (CGFloat)lineHeight {
+    let metrics = get_metrics(env, this);
+    metrics.ascent - metrics.descent + metrics.line_gap
+}
+- (CGFloat)capHeight {
+    get_metrics(env, this).cap_height
+}
+- (CGFloat)xHeight {
+    get_metrics(env, this).x_height
 }
 
 @end
 
 };
 
-fn convert_line_break_mode(ui_mode: UILineBreakMode) -> WrapMode {
+/// Get how to wrap lines and how to handle text that doesn't fit vertically.
+/// All the modes other than character wrapping wrap at words when there's room
+/// for more than one line.
+fn convert_line_break_mode(ui_mode: UILineBreakMode) -> (WrapMode, Overflow) {
     match ui_mode {
-        UILineBreakModeWordWrap => WrapMode::Word,
-        UILineBreakModeCharacterWrap => WrapMode::Char,
-        // TODO: support this properly; fake support is so that UILabel works,
-        // which has this as its default line break mode
-        UILineBreakModeTailTruncation => WrapMode::Word,
+        UILineBreakModeWordWrap => (WrapMode::Word, Overflow::Omit),
+        UILineBreakModeCharacterWrap => (WrapMode::Char, Overflow::Omit),
+        UILineBreakModeClip => (WrapMode::Word, Overflow::Clip),
+        UILineBreakModeHeadTruncation => (WrapMode::Word, Overflow::TruncateHead),
+        UILineBreakModeTailTruncation => (WrapMode::Word, Overflow::TruncateTail),
+        UILineBreakModeMiddleTruncation => (WrapMode::Word, Overflow::TruncateMiddle),
         _ => unimplemented!("TODO: line break mode {}", ui_mode),
     }
 }
 
+/// Get the `wrap` and `fit` parameters for [Font::calculate_text_size] and
+/// [Font::draw] for text constrained to a size. A height of zero means a
+/// single line, since at least one line is always laid out.
+fn wrap_and_fit(
+    size: CGSize,
+    ui_mode: UILineBreakMode,
+) -> (Option<(f32, WrapMode)>, Option<(f32, Overflow)>) {
+    let (wrap_mode, overflow) = convert_line_break_mode(ui_mode);
+    (Some((size.width, wrap_mode)), Some((size.height, overflow)))
+}
+
 #[rustfmt::skip]
 fn get_font<'a>(state: &'a mut State, kind: FontKind, text: &str) -> &'a Font {
     // The default fonts (see font.rs) are the Liberation family, which are a
@@ -172,13 +322,59 @@ pub fn size_with_font(
         text,
     );
 
-    let wrap = constrained.map(|(size, ui_mode)| (size.width, convert_line_break_mode(ui_mode)));
+    let (wrap, fit) =
+        constrained.map_or((None, None), |(size, ui_mode)| wrap_and_fit(size, ui_mode));
 
-    let (width, height) = font.calculate_text_size(host_object.size, text, wrap);
+    let (width, height) = font.calculate_text_size(host_object.size, text, wrap, fit);
 
     CGSize { width, height }
 }
 
+/// Called by `sizeWithFont:forWidth:lineBreakMode:` and similar methods on
+/// `NSString`, which measure a single line of text.
+pub fn size_with_font_for_width(
+    env: &mut Environment,
+    font: id,
+    text: &str,
+    width: CGFloat,
+    line_break_mode: UILineBreakMode,
+) -> CGSize {
+    let size = CGSize { width, height: 0.0 };
+    size_with_font(env, font, text, Some((size, line_break_mode)))
+}
+
+/// For the `minFontSize:` method family on `NSString`: get a copy of `font`
+/// with the largest size, no smaller than `min_size`, at which the text fits
+/// on one line in `width`.
+pub fn shrink_to_fit(
+    env: &mut Environment,
+    font: id,
+    text: &str,
+    min_size: CGFloat,
+    width: CGFloat,
+) -> id {
+    let &UIFontHostObject {
+        size: original_size,
+        kind,
+        ..
+    } = env.objc.borrow(font);
+    let ui_font = &mut env.framework_state.uikit.ui_font;
+    let mut size = original_size;
+    while size > min_size {
+        let (text_width, _) =
+            get_font(ui_font, kind, text).calculate_text_size(size, text, None, None);
+        if text_width <= width {
+            break;
+        }
+        size = (size - 1.0).max(min_size);
+    }
+    if size == original_size {
+        font
+    } else {
+        msg![env; font fontWithSize:size]
+    }
+}
+
 #[inline(always)]
 fn draw_font_glyph(
     drawer: &mut CGBitmapContextDrawer,
@@ -251,11 +447,12 @@ pub fn draw_at_point(
         text,
     );
 
-    let width_and_line_break_mode =
-        width_and_line_break_mode.map(|(width, ui_mode)| (width, convert_line_break_mode(ui_mode)));
-    let clip_x = width_and_line_break_mode.map(|(width, _)| point.x..(point.x + width));
-    let (width, height) =
-        font.calculate_text_size(host_object.size, text, width_and_line_break_mode);
+    // This draws a single line.
+    let (wrap, fit) = width_and_line_break_mode.map_or((None, None), |(width, ui_mode)| {
+        wrap_and_fit(CGSize { width, height: 0.0 }, ui_mode)
+    });
+    let clip_x = wrap.map(|(width, _)| point.x..(point.x + width));
+    let (width, height) = font.calculate_text_size(host_object.size, text, wrap, fit);
 
     let mut drawer = CGBitmapContextDrawer::new(&env.objc, &mut env.mem, context);
     let fill_color = drawer.rgb_fill_color();
@@ -264,7 +461,8 @@ pub fn draw_at_point(
         host_object.size,
         text,
         (point.x, point.y),
-        width_and_line_break_mode,
+        wrap,
+        fit,
         TextAlignment::Left,
         |raster_glyph| {
             draw_font_glyph(
@@ -292,6 +490,7 @@ pub fn draw_in_rect(
     let context = UIGraphicsGetCurrentContext(env);
 
     let text_size = size_with_font(env, font, text, Some((rect.size, line_break_mode)));
+    let (wrap, fit) = wrap_and_fit(rect.size, line_break_mode);
 
     let host_object = env.objc.borrow::<UIFontHostObject>(font);
 
@@ -315,7 +514,8 @@ pub fn draw_in_rect(
         host_object.size,
         text,
         (rect.origin.x + origin_x_offset, rect.origin.y),
-        Some((rect.size.width, convert_line_break_mode(line_break_mode))),
+        wrap,
+        fit,
         alignment,
         |raster_glyph| {
             draw_font_glyph(
@@ -330,3 +530,66 @@ pub fn draw_in_rect(
 
     text_size
 }
+
+/// For the `CGContextShowText` family: get the width of a line of text.
+pub fn cg_text_width(env: &mut Environment, font: id, text: &str) -> CGFloat {
+    let &UIFontHostObject { size, kind, .. } = env.objc.borrow(font);
+    let font = get_font(&mut env.framework_state.uikit.ui_font, kind, text);
+    let (width, _height) = font.calculate_text_size(size, text, None, None);
+    width
+}
+
+/// For the `CGContextShowText` family: draw a line of text with the start of
+/// its baseline at the origin of a text space with y pointing upwards.
+/// `text_to_user` is the transform from that space to the context's user
+/// space. The stroke color is used if `use_stroke_color` is [true], otherwise
+/// the fill color.
+pub fn draw_cg_text(
+    env: &mut Environment,
+    font: id,
+    context: CGContextRef,
+    text: &str,
+    text_to_user: CGAffineTransform,
+    use_stroke_color: bool,
+) {
+    let &UIFontHostObject { size, kind, .. } = env.objc.borrow(font);
+    let font = get_font(&mut env.framework_state.uikit.ui_font, kind, text);
+
+    // Font::draw() lays out lines with y pointing down from their top, and
+    // puts the baseline at the rounded ascent.
+    let ascent = font.metrics(size).ascent.round();
+    let layout_to_text = CGAffineTransform {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: -1.0,
+        tx: 0.0,
+        ty: ascent,
+    };
+
+    let mut drawer = CGBitmapContextDrawer::new(&env.objc, &mut env.mem, context);
+    drawer.concat_transform(layout_to_text.concat(text_to_user));
+    let color = if use_stroke_color {
+        drawer.rgb_stroke_color()
+    } else {
+        drawer.rgb_fill_color()
+    };
+
+    font.draw(
+        size,
+        text,
+        (0.0, 0.0),
+        /* wrap: */ None,
+        /* fit: */ None,
+        TextAlignment::Left,
+        |raster_glyph| {
+            draw_font_glyph(
+                &mut drawer,
+                raster_glyph,
+                color,
+                /* clip_x: */ None,
+                /* clip_y: */ None,
+            )
+        },
+    );
+}
@@ -0,0 +1,805 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UIGestureRecognizer` and its subclasses.
+//!
+//! Touches are delivered to gesture recognizers by [super::ui_touch], before
+//! they are delivered to the views they hit. Recognizers attached to the hit
+//! view and to its superviews get the touch.
+//!
+//! Useful resources:
+//! - Apple's [Event Handling Guide for iOS](https://developer.apple.com/library/archive/documentation/EventHandling/Conceptual/EventHandlingiPhoneOS/GestureRecognizer_basics/GestureRecognizer_basics.html)
+
+use super::ui_event;
+use super::ui_touch::{
+    UITouchHostObject, UITouchPhase, UITouchPhaseBegan, UITouchPhaseCancelled, UITouchPhaseEnded,
+    UITouchPhaseMoved,
+};
+use super::ui_view::UIViewHostObject;
+use crate::frameworks::core_graphics::{CGFloat, CGPoint};
+use crate::frameworks::foundation::{NSInteger, NSTimeInterval, NSUInteger};
+use crate::objc::{
+    id, impl_HostObject_with_superclass, msg, msg_class, msg_send, nil, objc_classes, release,
+    retain, ClassExports, HostObject, NSZonePtr, SEL,
+};
+use crate::Environment;
+
+pub type UIGestureRecognizerState = NSInteger;
+pub const UIGestureRecognizerStatePossible: UIGestureRecognizerState = 0;
+pub const UIGestureRecognizerStateBegan: UIGestureRecognizerState = 1;
+pub const UIGestureRecognizerStateChanged: UIGestureRecognizerState = 2;
+pub const UIGestureRecognizerStateEnded: UIGestureRecognizerState = 3;
+pub const UIGestureRecognizerStateCancelled: UIGestureRecognizerState = 4;
+pub const UIGestureRecognizerStateFailed: UIGestureRecognizerState = 5;
+pub const UIGestureRecognizerStateRecognized: UIGestureRecognizerState =
+    UIGestureRecognizerStateEnded;
+
+/// How far (in points) touches can move before they're no longer a tap, and
+/// must move before they're a pan.
+const MOVEMENT_THRESHOLD: CGFloat = 10.0;
+/// The longest time (in seconds) between the taps of a multi-tap.
+const MAX_TAP_INTERVAL: NSTimeInterval = 0.35;
+
+pub(super) struct UIGestureRecognizerHostObject {
+    /// `UIView*`, weak reference.
+    view: id,
+    /// Weak references to the targets.
+    targets: Vec<(id, SEL)>,
+    /// Weak reference.
+    delegate: id,
+    state: UIGestureRecognizerState,
+    enabled: bool,
+    cancels_touches_in_view: bool,
+    delays_touches_began: bool,
+    delays_touches_ended: bool,
+    /// `UITouch*`s being tracked. Weak references (the touches retain the
+    /// recognizer).
+    touches: Vec<id>,
+    /// Centroid of the touches, in the co-ordinate space of `window`.
+    location: CGPoint,
+    /// `UIWindow*` of the touches, weak reference.
+    window: id,
+}
+impl HostObject for UIGestureRecognizerHostObject {}
+impl Default for UIGestureRecognizerHostObject {
+    fn default() -> Self {
+        UIGestureRecognizerHostObject {
+            view: nil,
+            targets: Vec::new(),
+            delegate: nil,
+            state: UIGestureRecognizerStatePossible,
+            enabled: true,
+            cancels_touches_in_view: true,
+            delays_touches_began: false,
+            delays_touches_ended: true,
+            touches: Vec::new(),
+            location: CGPoint::default(),
+            window: nil,
+        }
+    }
+}
+
+struct UITapGestureRecognizerHostObject {
+    superclass: UIGestureRecognizerHostObject,
+    number_of_taps_required: NSUInteger,
+    number_of_touches_required: NSUInteger,
+    /// Taps completed so far.
+    taps: NSUInteger,
+    /// Most touches down at once during the current tap.
+    touches_in_tap: NSUInteger,
+    last_tap_time: NSTimeInterval,
+    /// Where the current tap started, in window co-ordinates.
+    start_location: CGPoint,
+}
+impl_HostObject_with_superclass!(UITapGestureRecognizerHostObject);
+impl Default for UITapGestureRecognizerHostObject {
+    fn default() -> Self {
+        UITapGestureRecognizerHostObject {
+            superclass: Default::default(),
+            number_of_taps_required: 1,
+            number_of_touches_required: 1,
+            taps: 0,
+            touches_in_tap: 0,
+            last_tap_time: 0.0,
+            start_location: CGPoint::default(),
+        }
+    }
+}
+
+struct UIPanGestureRecognizerHostObject {
+    superclass: UIGestureRecognizerHostObject,
+    minimum_number_of_touches: NSUInteger,
+    maximum_number_of_touches: NSUInteger,
+    /// Where the touches started, in window co-ordinates.
+    start_location: CGPoint,
+    /// The centroid at the last event, in window co-ordinates.
+    last_location: CGPoint,
+    last_time: NSTimeInterval,
+    translation: CGPoint,
+    /// Points per second.
+    velocity: CGPoint,
+}
+impl_HostObject_with_superclass!(UIPanGestureRecognizerHostObject);
+impl Default for UIPanGestureRecognizerHostObject {
+    fn default() -> Self {
+        UIPanGestureRecognizerHostObject {
+            superclass: Default::default(),
+            minimum_number_of_touches: 1,
+            maximum_number_of_touches: NSUInteger::MAX,
+            start_location: CGPoint::default(),
+            last_location: CGPoint::default(),
+            last_time: 0.0,
+            translation: CGPoint::default(),
+            velocity: CGPoint::default(),
+        }
+    }
+}
+
+fn distance(a: CGPoint, b: CGPoint) -> CGFloat {
+    (a.x - b.x).hypot(a.y - b.y)
+}
+
+/// Get the centroid of a recognizer's touches in window co-ordinates, and the
+/// timestamp of the latest one.
+fn centroid_and_time(env: &mut Environment, recognizer: id) -> (CGPoint, NSTimeInterval) {
+    let touches = env
+        .objc
+        .borrow::<UIGestureRecognizerHostObject>(recognizer)
+        .touches
+        .clone();
+    let mut sum = CGPoint::default();
+    let mut time: NSTimeInterval = 0.0;
+    for &touch in &touches {
+        let location: CGPoint = msg![env; touch locationInView:nil];
+        let timestamp: NSTimeInterval = msg![env; touch timestamp];
+        sum.x += location.x;
+        sum.y += location.y;
+        time = time.max(timestamp);
+    }
+    let count = touches.len().max(1) as CGFloat;
+    (
+        CGPoint {
+            x: sum.x / count,
+            y: sum.y / count,
+        },
+        time,
+    )
+}
+
+fn send_actions(env: &mut Environment, recognizer: id) {
+    let targets = env
+        .objc
+        .borrow::<UIGestureRecognizerHostObject>(recognizer)
+        .targets
+        .clone();
+    for (target, action) in targets {
+        if target != nil {
+            () = msg_send(env, (target, action, recognizer));
+        }
+    }
+}
+
+fn delegate_allows(env: &mut Environment, recognizer: id, selector: &str, arg: Option<id>) -> bool {
+    let delegate = env
+        .objc
+        .borrow::<UIGestureRecognizerHostObject>(recognizer)
+        .delegate;
+    if delegate == nil
+        || !env
+            .objc
+            .object_has_method_named(&env.mem, delegate, selector)
+    {
+        return true;
+    }
+    let selector = env.objc.lookup_selector(selector).unwrap();
+    if let Some(arg) = arg {
+        msg_send(env, (delegate, selector, recognizer, arg))
+    } else {
+        msg_send(env, (delegate, selector, recognizer))
+    }
+}
+
+fn can_recognize_simultaneously(env: &mut Environment, a: id, b: id) -> bool {
+    let selector = "gestureRecognizer:shouldRecognizeSimultaneouslyWithGestureRecognizer:";
+    // The default is NO, so a delegate that doesn't implement the method
+    // doesn't allow it.
+    let asks = |env: &mut Environment, recognizer: id| {
+        let delegate = env
+            .objc
+            .borrow::<UIGestureRecognizerHostObject>(recognizer)
+            .delegate;
+        delegate != nil
+            && env
+                .objc
+                .object_has_method_named(&env.mem, delegate, selector)
+    };
+    (asks(env, a) && delegate_allows(env, a, selector, Some(b)))
+        || (asks(env, b) && delegate_allows(env, b, selector, Some(a)))
+}
+
+/// Called when a recognizer leaves the possible state by beginning or being
+/// recognized: other recognizers for the same touches fail, and the touches
+/// may be cancelled for the views.
+fn recognized(env: &mut Environment, recognizer: id) {
+    let host_obj = env.objc.borrow::<UIGestureRecognizerHostObject>(recognizer);
+    let touches = host_obj.touches.clone();
+    let cancels_touches_in_view = host_obj.cancels_touches_in_view;
+
+    for &touch in &touches {
+        let others = env
+            .objc
+            .borrow::<UITouchHostObject>(touch)
+            .gesture_recognizers
+            .clone();
+        for other in others {
+            if other == recognizer {
+                continue;
+            }
+            let state = env
+                .objc
+                .borrow::<UIGestureRecognizerHostObject>(other)
+                .state;
+            if state == UIGestureRecognizerStatePossible
+                && !can_recognize_simultaneously(env, recognizer, other)
+            {
+                env.objc
+                    .borrow_mut::<UIGestureRecognizerHostObject>(other)
+                    .state = UIGestureRecognizerStateFailed;
+            }
+        }
+    }
+
+    if !cancels_touches_in_view {
+        return;
+    }
+    let mut touches_to_cancel = Vec::new();
+    for touch in touches {
+        let touch_host_obj = env.objc.borrow_mut::<UITouchHostObject>(touch);
+        if !touch_host_obj.cancelled_in_view && touch_host_obj.view != nil {
+            touch_host_obj.cancelled_in_view = true;
+            touches_to_cancel.push(touch);
+        }
+    }
+    for touch in touches_to_cancel {
+        let &UITouchHostObject { view, phase, .. } = env.objc.borrow(touch);
+        env.objc.borrow_mut::<UITouchHostObject>(touch).phase = UITouchPhaseCancelled;
+        let set: id = msg_class![env; NSMutableSet new];
+        () = msg![env; set addObject:touch];
+        let event = ui_event::new_event(env, set);
+        log_dbg!(
+            "Gesture recognizer {:?} cancelling touch {:?} in view {:?}",
+            recognizer,
+            touch,
+            view
+        );
+        () = msg![env; view touchesCancelled:set withEvent:event];
+        release(env, event);
+        release(env, set);
+        env.objc.borrow_mut::<UITouchHostObject>(touch).phase = phase;
+    }
+}
+
+/// For use by [super::ui_touch]: get the enabled gesture recognizers for a new
+/// touch in a view, i.e. those of the view and its superviews. The result is
+/// retained.
+pub(super) fn recognizers_for_touch(env: &mut Environment, view: id, touch: id) -> Vec<id> {
+    let mut recognizers = Vec::new();
+    let mut current = view;
+    while current != nil {
+        let candidates = env
+            .objc
+            .borrow::<UIViewHostObject>(current)
+            .gesture_recognizers
+            .clone();
+        current = msg![env; current superview];
+        for recognizer in candidates {
+            if !env
+                .objc
+                .borrow::<UIGestureRecognizerHostObject>(recognizer)
+                .enabled
+            {
+                continue;
+            }
+            if !delegate_allows(
+                env,
+                recognizer,
+                "gestureRecognizer:shouldReceiveTouch:",
+                Some(touch),
+            ) {
+                continue;
+            }
+            recognizers.push(retain(env, recognizer));
+        }
+    }
+    recognizers
+}
+
+/// For use by [super::ui_touch]: deliver touches in a particular phase to the
+/// recognizers they belong to. `phase` is used rather than the touches' own
+/// phase since that may change while delivering.
+pub(super) fn deliver_touches(
+    env: &mut Environment,
+    touches: &[id],
+    event: id, // UIEvent*
+    phase: UITouchPhase,
+) {
+    // recognizer to touches for that recognizer, in a stable order
+    let mut recognizer_touches: Vec<(id, Vec<id>)> = Vec::new();
+    for &touch in touches {
+        let recognizers = env
+            .objc
+            .borrow::<UITouchHostObject>(touch)
+            .gesture_recognizers
+            .clone();
+        for recognizer in recognizers {
+            if let Some((_, touches)) = recognizer_touches
+                .iter_mut()
+                .find(|(other, _)| *other == recognizer)
+            {
+                touches.push(touch);
+            } else {
+                recognizer_touches.push((recognizer, vec![touch]));
+            }
+        }
+    }
+
+    for (recognizer, touches) in recognizer_touches {
+        if phase == UITouchPhaseBegan {
+            let window = env.objc.borrow::<UITouchHostObject>(touches[0]).window;
+            let host_obj = env
+                .objc
+                .borrow_mut::<UIGestureRecognizerHostObject>(recognizer);
+            host_obj.touches.extend_from_slice(&touches);
+            host_obj.window = window;
+        }
+        let (location, _) = centroid_and_time(env, recognizer);
+        let host_obj = env
+            .objc
+            .borrow_mut::<UIGestureRecognizerHostObject>(recognizer);
+        host_obj.location = location;
+        let state = host_obj.state;
+
+        // Recognizers that have finished ignore touches until they're reset.
+        if matches!(
+            state,
+            UIGestureRecognizerStatePossible
+                | UIGestureRecognizerStateBegan
+                | UIGestureRecognizerStateChanged
+        ) {
+            let set: id = msg_class![env; NSMutableSet new];
+            for &touch in &touches {
+                () = msg![env; set addObject:touch];
+            }
+            match phase {
+                UITouchPhaseBegan => {
+                    () = msg![env; recognizer touchesBegan:set withEvent:event];
+                }
+                UITouchPhaseMoved => {
+                    () = msg![env; recognizer touchesMoved:set withEvent:event];
+                }
+                UITouchPhaseEnded => {
+                    () = msg![env; recognizer touchesEnded:set withEvent:event];
+                }
+                _ => unreachable!(),
+            }
+            release(env, set);
+        }
+
+        if phase != UITouchPhaseEnded {
+            continue;
+        }
+        let host_obj = env
+            .objc
+            .borrow_mut::<UIGestureRecognizerHostObject>(recognizer);
+        host_obj.touches.retain(|touch| !touches.contains(touch));
+        if host_obj.touches.is_empty()
+            && matches!(
+                host_obj.state,
+                UIGestureRecognizerStateEnded
+                    | UIGestureRecognizerStateCancelled
+                    | UIGestureRecognizerStateFailed
+            )
+        {
+            host_obj.state = UIGestureRecognizerStatePossible;
+            () = msg![env; recognizer reset];
+        }
+    }
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UIGestureRecognizer: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::<UIGestureRecognizerHostObject>::default();
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (id)initWithTarget:(id)target
+              action:(SEL)action {
+    let this: id = msg![env; this init];
+    if target != nil && !action.is_null() {
+        () = msg![env; this addTarget:target action:action];
+    }
+    this
+}
+
+- (())addTarget:(id)target
+         action:(SEL)action {
+    // The target is a *weak* reference!
+    let targets = &mut env.objc.borrow_mut::<UIGestureRecognizerHostObject>(this).targets;
+    if !targets.contains(&(target, action)) {
+        targets.push((target, action));
+    }
+}
+- (())removeTarget:(id)target
+            action:(SEL)action {
+    // nil and NULL are wildcards.
+    let targets = &mut env.objc.borrow_mut::<UIGestureRecognizerHostObject>(this).targets;
+    targets.retain(|&(other_target, other_action)| {
+        !((target == nil || target == other_target)
+            && (action.is_null() || action == other_action))
+    });
+}
+
+- (id)view {
+    env.objc.borrow::<UIGestureRecognizerHostObject>(this).view
+}
+// Private, for use by UIView's addGestureRecognizer:.
+- (())_touchHLE_setView:(id)view {
+    env.objc.borrow_mut::<UIGestureRecognizerHostObject>(this).view = view;
+}
+
+- (id)delegate {
+    env.objc.borrow::<UIGestureRecognizerHostObject>(this).delegate
+}
+- (())setDelegate:(id)delegate {
+    // The delegate is a *weak* reference!
+    env.objc.borrow_mut::<UIGestureRecognizerHostObject>(this).delegate = delegate;
+}
+
+- (UIGestureRecognizerState)state {
+    env.objc.borrow::<UIGestureRecognizerHostObject>(this).state
+}
+// Officially this is only for subclasses (UIGestureRecognizerSubclass.h).
+- (())setState:(UIGestureRecognizerState)state {
+    let old_state = env.objc.borrow::<UIGestureRecognizerHostObject>(this).state;
+    let begins = old_state == UIGestureRecognizerStatePossible
+        && matches!(state, UIGestureRecognizerStateBegan | UIGestureRecognizerStateRecognized);
+    let state = if begins && !delegate_allows(env, this, "gestureRecognizerShouldBegin:", None) {
+        UIGestureRecognizerStateFailed
+    } else {
+        state
+    };
+    log_dbg!("Gesture recognizer {:?} state {} => {}", this, old_state, state);
+    env.objc.borrow_mut::<UIGestureRecognizerHostObject>(this).state = state;
+
+    if state == UIGestureRecognizerStateFailed {
+        return;
+    }
+    if begins {
+        recognized(env, this);
+    }
+    if matches!(
+        state,
+        UIGestureRecognizerStateBegan
+            | UIGestureRecognizerStateChanged
+            | UIGestureRecognizerStateEnded
+            | UIGestureRecognizerStateCancelled
+    ) {
+        send_actions(env, this);
+    }
+}
+
+- (bool)isEnabled {
+    env.objc.borrow::<UIGestureRecognizerHostObject>(this).enabled
+}
+- (())setEnabled:(bool)enabled {
+    let host_obj = env.objc.borrow_mut::<UIGestureRecognizerHostObject>(this);
+    host_obj.enabled = enabled;
+    let state = host_obj.state;
+    if !enabled && matches!(
+        state,
+        UIGestureRecognizerStateBegan | UIGestureRecognizerStateChanged
+    ) {
+        () = msg![env; this setState:UIGestureRecognizerStateCancelled];
+    }
+}
+
+- (bool)cancelsTouchesInView {
+    env.objc.borrow::<UIGestureRecognizerHostObject>(this).cancels_touches_in_view
+}
+- (())setCancelsTouchesInView:(bool)cancels {
+    env.objc.borrow_mut::<UIGestureRecognizerHostObject>(this).cancels_touches_in_view = cancels;
+}
+// TODO: actually delay touches
+- (bool)delaysTouchesBegan {
+    env.objc.borrow::<UIGestureRecognizerHostObject>(this).delays_touches_began
+}
+- (())setDelaysTouchesBegan:(bool)delays {
+    env.objc.borrow_mut::<UIGestureRecognizerHostObject>(this).delays_touches_began = delays;
+}
+- (bool)delaysTouchesEnded {
+    env.objc.borrow::<UIGestureRecognizerHostObject>(this).delays_touches_ended
+}
+- (())setDelaysTouchesEnded:(bool)delays {
+    env.objc.borrow_mut::<UIGestureRecognizerHostObject>(this).delays_touches_ended = delays;
+}
+
+- (())requireGestureRecognizerToFail:(id)other { // UIGestureRecognizer*
+    log!("TODO: [{:?} requireGestureRecognizerToFail:{:?}] (ignored)", this, other);
+}
+
+- (NSUInteger)numberOfTouches {
+    let touches = &env.objc.borrow::<UIGestureRecognizerHostObject>(this).touches;
+    touches.len().try_into().unwrap()
+}
+
+- (CGPoint)locationInView:(id)view { // UIView*
+    let &UIGestureRecognizerHostObject { location, window, .. } = env.objc.borrow(this);
+    if view == nil || window == nil {
+        location
+    } else {
+        msg![env; view convertPoint:location fromView:window]
+    }
+}
+- (CGPoint)locationOfTouch:(NSUInteger)index
+                    inView:(id)view { // UIView*
+    let touches = &env.objc.borrow::<UIGestureRecognizerHostObject>(this).touches;
+    let touch = touches[index as usize];
+    msg![env; touch locationInView:view]
+}
+
+// Overridden by subclasses.
+- (())reset {
+}
+- (())touchesBegan:(id)_touches // NSSet* of UITouch*
+         withEvent:(id)_event { // UIEvent*
+}
+- (())touchesMoved:(id)_touches // NSSet* of UITouch*
+         withEvent:(id)_event { // UIEvent*
+}
+- (())touchesEnded:(id)_touches // NSSet* of UITouch*
+         withEvent:(id)_event { // UIEvent*
+}
+- (())touchesCancelled:(id)_touches // NSSet* of UITouch*
+             withEvent:(id)_event { // UIEvent*
+}
+- (())ignoreTouch:(id)_touch // UITouch*
+         forEvent:(id)_event { // UIEvent*
+    log!("TODO: [{:?} ignoreTouch:forEvent:] (ignored)", this);
+}
+
+@end
+
+@implementation UITapGestureRecognizer: UIGestureRecognizer
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::<UITapGestureRecognizerHostObject>::default();
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (NSUInteger)numberOfTapsRequired {
+    env.objc.borrow::<UITapGestureRecognizerHostObject>(this).number_of_taps_required
+}
+- (())setNumberOfTapsRequired:(NSUInteger)taps {
+    env.objc.borrow_mut::<UITapGestureRecognizerHostObject>(this).number_of_taps_required = taps;
+}
+- (NSUInteger)numberOfTouchesRequired {
+    env.objc.borrow::<UITapGestureRecognizerHostObject>(this).number_of_touches_required
+}
+- (())setNumberOfTouchesRequired:(NSUInteger)touches {
+    let host_obj = env.objc.borrow_mut::<UITapGestureRecognizerHostObject>(this);
+    host_obj.number_of_touches_required = touches;
+}
+
+- (())reset {
+    let host_obj = env.objc.borrow_mut::<UITapGestureRecognizerHostObject>(this);
+    host_obj.taps = 0;
+    host_obj.touches_in_tap = 0;
+}
+
+- (())touchesBegan:(id)_touches // NSSet* of UITouch*
+         withEvent:(id)_event { // UIEvent*
+    let (location, time) = centroid_and_time(env, this);
+    let host_obj = env.objc.borrow_mut::<UITapGestureRecognizerHostObject>(this);
+    if host_obj.taps > 0 && time - host_obj.last_tap_time > MAX_TAP_INTERVAL {
+        // Too slow for a multi-tap, so this starts over.
+        host_obj.taps = 0;
+    }
+    let touch_count: NSUInteger = host_obj.superclass.touches.len().try_into().unwrap();
+    host_obj.start_location = location;
+    host_obj.touches_in_tap = host_obj.touches_in_tap.max(touch_count);
+    if host_obj.touches_in_tap > host_obj.number_of_touches_required {
+        () = msg![env; this setState:UIGestureRecognizerStateFailed];
+    }
+}
+- (())touchesMoved:(id)_touches // NSSet* of UITouch*
+         withEvent:(id)_event { // UIEvent*
+    let (location, _) = centroid_and_time(env, this);
+    let start_location = env.objc.borrow::<UITapGestureRecognizerHostObject>(this).start_location;
+    if distance(location, start_location) > MOVEMENT_THRESHOLD {
+        () = msg![env; this setState:UIGestureRecognizerStateFailed];
+    }
+}
+- (())touchesEnded:(id)touches // NSSet* of UITouch*
+         withEvent:(id)_event { // UIEvent*
+    let ended_count: NSUInteger = msg![env; touches count];
+    let (_, time) = centroid_and_time(env, this);
+    let host_obj = env.objc.borrow_mut::<UITapGestureRecognizerHostObject>(this);
+    let touch_count: NSUInteger = host_obj.superclass.touches.len().try_into().unwrap();
+    if touch_count > ended_count {
+        // Wait for the other fingers to be lifted.
+        return;
+    }
+    if host_obj.touches_in_tap != host_obj.number_of_touches_required {
+        () = msg![env; this setState:UIGestureRecognizerStateFailed];
+        return;
+    }
+    host_obj.taps += 1;
+    host_obj.touches_in_tap = 0;
+    host_obj.last_tap_time = time;
+    if host_obj.taps >= host_obj.number_of_taps_required {
+        () = msg![env; this setState:UIGestureRecognizerStateRecognized];
+    }
+}
+- (())touchesCancelled:(id)_touches // NSSet* of UITouch*
+             withEvent:(id)_event { // UIEvent*
+    () = msg![env; this setState:UIGestureRecognizerStateFailed];
+}
+
+@end
+
+@implementation UIPanGestureRecognizer: UIGestureRecognizer
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::<UIPanGestureRecognizerHostObject>::default();
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (NSUInteger)minimumNumberOfTouches {
+    env.objc.borrow::<UIPanGestureRecognizerHostObject>(this).minimum_number_of_touches
+}
+- (())setMinimumNumberOfTouches:(NSUInteger)touches {
+    let host_obj = env.objc.borrow_mut::<UIPanGestureRecognizerHostObject>(this);
+    host_obj.minimum_number_of_touches = touches;
+}
+- (NSUInteger)maximumNumberOfTouches {
+    env.objc.borrow::<UIPanGestureRecognizerHostObject>(this).maximum_number_of_touches
+}
+- (())setMaximumNumberOfTouches:(NSUInteger)touches {
+    let host_obj = env.objc.borrow_mut::<UIPanGestureRecognizerHostObject>(this);
+    host_obj.maximum_number_of_touches = touches;
+}
+
+// Translations and velocities are vectors, and views can't be rotated or
+// scaled yet, so they're the same in any view's co-ordinate space.
+- (CGPoint)translationInView:(id)_view { // UIView*
+    env.objc.borrow::<UIPanGestureRecognizerHostObject>(this).translation
+}
+- (())setTranslation:(CGPoint)translation
+              inView:(id)_view { // UIView*
+    let host_obj = env.objc.borrow_mut::<UIPanGestureRecognizerHostObject>(this);
+    host_obj.translation = translation;
+    host_obj.velocity = CGPoint::default();
+}
+- (CGPoint)velocityInView:(id)_view { // UIView*
+    env.objc.borrow::<UIPanGestureRecognizerHostObject>(this).velocity
+}
+
+- (())reset {
+    let host_obj = env.objc.borrow_mut::<UIPanGestureRecognizerHostObject>(this);
+    host_obj.translation = CGPoint::default();
+    host_obj.velocity = CGPoint::default();
+}
+
+- (())touchesBegan:(id)_touches // NSSet* of UITouch*
+         withEvent:(id)_event { // UIEvent*
+    let (location, time) = centroid_and_time(env, this);
+    let host_obj = env.objc.borrow_mut::<UIPanGestureRecognizerHostObject>(this);
+    // The centroid jumps when a finger is added, which mustn't count as
+    // movement.
+    host_obj.last_location = location;
+    host_obj.last_time = time;
+    let touch_count: NSUInteger = host_obj.superclass.touches.len().try_into().unwrap();
+    if host_obj.superclass.state != UIGestureRecognizerStatePossible {
+        return;
+    }
+    host_obj.start_location = location;
+    if touch_count > host_obj.maximum_number_of_touches {
+        () = msg![env; this setState:UIGestureRecognizerStateFailed];
+    }
+}
+- (())touchesMoved:(id)_touches // NSSet* of UITouch*
+         withEvent:(id)_event { // UIEvent*
+    let (location, time) = centroid_and_time(env, this);
+    let host_obj = env.objc.borrow_mut::<UIPanGestureRecognizerHostObject>(this);
+    let delta = CGPoint {
+        x: location.x - host_obj.last_location.x,
+        y: location.y - host_obj.last_location.y,
+    };
+    let dt = (time - host_obj.last_time).max(0.001) as CGFloat;
+    // Smooth the velocity since touch events are unevenly spaced.
+    host_obj.velocity = CGPoint {
+        x: host_obj.velocity.x * 0.2 + (delta.x / dt) * 0.8,
+        y: host_obj.velocity.y * 0.2 + (delta.y / dt) * 0.8,
+    };
+    host_obj.last_location = location;
+    host_obj.last_time = time;
+
+    let touch_count: NSUInteger = host_obj.superclass.touches.len().try_into().unwrap();
+    match host_obj.superclass.state {
+        UIGestureRecognizerStatePossible => {
+            if touch_count >= host_obj.minimum_number_of_touches
+                && distance(location, host_obj.start_location) > MOVEMENT_THRESHOLD
+            {
+                () = msg![env; this setState:UIGestureRecognizerStateBegan];
+            }
+        }
+        UIGestureRecognizerStateBegan | UIGestureRecognizerStateChanged => {
+            host_obj.translation.x += delta.x;
+            host_obj.translation.y += delta.y;
+            () = msg![env; this setState:UIGestureRecognizerStateChanged];
+        }
+        _ => (),
+    }
+}
+- (())touchesEnded:(id)touches // NSSet* of UITouch*
+         withEvent:(id)_event { // UIEvent*
+    let ended_count: NSUInteger = msg![env; touches count];
+    let host_obj = env.objc.borrow::<UIPanGestureRecognizerHostObject>(this);
+    let touch_count: NSUInteger = host_obj.superclass.touches.len().try_into().unwrap();
+    let remaining = touch_count.saturating_sub(ended_count);
+    let state = host_obj.superclass.state;
+    let minimum = host_obj.minimum_number_of_touches;
+
+    if remaining > 0 {
+        // Don't let the centroid jump when a finger is lifted.
+        let remaining_touches: Vec<id> = host_obj
+            .superclass
+            .touches
+            .iter()
+            .copied()
+            .filter(|&touch| env.objc.borrow::<UITouchHostObject>(touch).phase != UITouchPhaseEnded)
+            .collect();
+        let mut sum = CGPoint::default();
+        for &touch in &remaining_touches {
+            let location: CGPoint = msg![env; touch locationInView:nil];
+            sum.x += location.x;
+            sum.y += location.y;
+        }
+        env.objc.borrow_mut::<UIPanGestureRecognizerHostObject>(this).last_location = CGPoint {
+            x: sum.x / remaining_touches.len() as CGFloat,
+            y: sum.y / remaining_touches.len() as CGFloat,
+        };
+    }
+
+    match state {
+        UIGestureRecognizerStatePossible if remaining == 0 => {
+            () = msg![env; this setState:UIGestureRecognizerStateFailed];
+        }
+        UIGestureRecognizerStateBegan | UIGestureRecognizerStateChanged
+            if remaining == 0 || remaining < minimum =>
+        {
+            () = msg![env; this setState:UIGestureRecognizerStateEnded];
+        }
+        _ => (),
+    }
+}
+- (())touchesCancelled:(id)_touches // NSSet* of UITouch*
+             withEvent:(id)_event { // UIEvent*
+    let state = env.objc.borrow::<UIGestureRecognizerHostObject>(this).state;
+    if state == UIGestureRecognizerStatePossible {
+        () = msg![env; this setState:UIGestureRecognizerStateFailed];
+    } else {
+        () = msg![env; this setState:UIGestureRecognizerStateCancelled];
+    }
+}
+
+@end
+
+};
@@ -4,11 +4,421 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 //! `UIImagePickerController`
+//!
+//! The photo library is simulated with a directory on the host (see the
+//! `--photo-library=` option). The picker itself is a simple grid of
+//! thumbnails built from our own UIKit classes. The camera, if enabled with
+//! the `--camera-image=` option, always "takes" the same still image.
 
-use crate::frameworks::foundation::NSInteger;
-use crate::objc::{objc_classes, ClassExports};
+use super::ui_font::UITextAlignmentCenter;
+use super::ui_view::ui_control::ui_button::{UIButtonTypeCustom, UIButtonTypeRoundedRect};
+use super::ui_view::ui_control::{UIControlEventTouchUpInside, UIControlStateNormal};
+use super::ui_view_controller::UIViewControllerHostObject;
+use crate::dyld::{ConstantExports, HostConstant};
+use crate::frameworks::core_graphics::cg_image::{self, CGImageRelease};
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::ns_dictionary::dict_from_keys_and_objects;
+use crate::frameworks::foundation::{ns_string, NSInteger};
+use crate::image::Image;
+use crate::objc::{
+    id, impl_HostObject_with_superclass, msg, msg_class, msg_super, nil, objc_classes, release,
+    ClassExports, NSZonePtr,
+};
+use crate::paths;
+use crate::Environment;
+use std::path::{Path, PathBuf};
 
 type UIImagePickerControllerSourceType = NSInteger;
+const UIImagePickerControllerSourceTypePhotoLibrary: UIImagePickerControllerSourceType = 0;
+const UIImagePickerControllerSourceTypeCamera: UIImagePickerControllerSourceType = 1;
+const UIImagePickerControllerSourceTypeSavedPhotosAlbum: UIImagePickerControllerSourceType = 2;
+
+pub const UIImagePickerControllerMediaType: &str = "UIImagePickerControllerMediaType";
+pub const UIImagePickerControllerOriginalImage: &str = "UIImagePickerControllerOriginalImage";
+
+/// Uniform Type Identifier for images (`kUTTypeImage`).
+const kUTTypeImage: &str = "public.image";
+
+pub const CONSTANTS: ConstantExports = &[
+    (
+        "_UIImagePickerControllerMediaType",
+        HostConstant::NSString(UIImagePickerControllerMediaType),
+    ),
+    (
+        "_UIImagePickerControllerOriginalImage",
+        HostConstant::NSString(UIImagePickerControllerOriginalImage),
+    ),
+];
+
+const THUMBNAIL_COLUMNS: usize = 4;
+const THUMBNAIL_ROWS: usize = 4;
+const THUMBNAILS_PER_PAGE: usize = THUMBNAIL_COLUMNS * THUMBNAIL_ROWS;
+const THUMBNAIL_SIZE: CGFloat = 75.0;
+const THUMBNAIL_GAP: CGFloat = 4.0;
+const BAR_HEIGHT: CGFloat = 44.0;
+
+struct UIImagePickerControllerHostObject {
+    superclass: UIViewControllerHostObject,
+    /// Weak reference.
+    delegate: id,
+    source_type: UIImagePickerControllerSourceType,
+    allows_editing: bool,
+    /// Host paths of the images in the photo library. Found when the view is
+    /// loaded.
+    photos: Vec<PathBuf>,
+    /// Index of the photo library page currently being shown.
+    page_idx: usize,
+    /// `UIButton*`s showing thumbnails, in order. Weak references (the view
+    /// owns them).
+    thumbnail_buttons: Vec<id>,
+    /// `UIImage*`s for each photo on the current page. Strong references.
+    page_images: Vec<id>,
+    /// `UIButton*`s for changing page. Weak references.
+    prev_button: id,
+    next_button: id,
+}
+impl_HostObject_with_superclass!(UIImagePickerControllerHostObject);
+
+/// Get the directory used as the photo library.
+fn photo_library_dir(env: &Environment) -> PathBuf {
+    env.options
+        .photo_library_dir
+        .clone()
+        .unwrap_or_else(|| paths::user_data_base_path().join(paths::PHOTOS_DIR))
+}
+
+/// Get the image that the camera "takes", if there is one.
+fn camera_image_path(env: &Environment) -> Option<PathBuf> {
+    // TODO: support using a webcam on the host?
+    env.options
+        .camera_image
+        .clone()
+        .filter(|path| path.is_file())
+}
+
+/// Find the images in the photo library, in a stable order.
+fn enumerate_photos(env: &Environment) -> Vec<PathBuf> {
+    let dir = photo_library_dir(env);
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log!(
+                "Warning: couldn't read photo library directory {}: {}",
+                dir.display(),
+                e
+            );
+            return Vec::new();
+        }
+    };
+    let mut photos: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|extension| extension.to_str())
+                .map(|extension| extension.to_ascii_lowercase())
+                .is_some_and(|extension| ["png", "jpg", "jpeg"].contains(&extension.as_str()))
+        })
+        .collect();
+    photos.sort();
+    photos
+}
+
+/// Load an image from the host into a new `UIImage*`.
+fn load_photo(env: &mut Environment, path: &Path) -> id {
+    let image = std::fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| Image::from_bytes(&bytes));
+    let image = match image {
+        Ok(image) => image,
+        Err(e) => {
+            log!("Warning: couldn't load photo {}: {}", path.display(), e);
+            return nil;
+        }
+    };
+    let cg_image = cg_image::from_image(env, image);
+    let ui_image: id = msg_class![env; UIImage alloc];
+    let ui_image: id = msg![env; ui_image initWithCGImage:cg_image];
+    CGImageRelease(env, cg_image);
+    ui_image
+}
+
+/// Show a page of the photo library.
+fn show_page(env: &mut Environment, picker: id, page_idx: usize) {
+    let host_obj = env
+        .objc
+        .borrow_mut::<UIImagePickerControllerHostObject>(picker);
+    host_obj.page_idx = page_idx;
+    let old_images = std::mem::take(&mut host_obj.page_images);
+    let start = page_idx * THUMBNAILS_PER_PAGE;
+    let end = (start + THUMBNAILS_PER_PAGE).min(host_obj.photos.len());
+    let photos = host_obj.photos[start..end].to_vec();
+    let has_next_page = end < host_obj.photos.len();
+    let thumbnail_buttons = host_obj.thumbnail_buttons.clone();
+    let prev_button = host_obj.prev_button;
+    let next_button = host_obj.next_button;
+    for image in old_images {
+        release(env, image);
+    }
+
+    let page_images: Vec<id> = photos.iter().map(|path| load_photo(env, path)).collect();
+    for (i, &button) in thumbnail_buttons.iter().enumerate() {
+        let image = page_images.get(i).copied().unwrap_or(nil);
+        () = msg![env; button setImage:image forState:UIControlStateNormal];
+        () = msg![env; button setHidden:(image == nil)];
+    }
+    () = msg![env; prev_button setHidden:(page_idx == 0)];
+    () = msg![env; next_button setHidden:(!has_next_page)];
+
+    env.objc
+        .borrow_mut::<UIImagePickerControllerHostObject>(picker)
+        .page_images = page_images;
+}
+
+fn make_button(
+    env: &mut Environment,
+    picker: id,
+    super_view: id,
+    frame: CGRect,
+    title: &'static str,
+    action: &str,
+) -> id {
+    let button: id = msg_class![env; UIButton buttonWithType:UIButtonTypeRoundedRect];
+    () = msg![env; button setFrame:frame];
+    let title = ns_string::get_static_str(env, title);
+    () = msg![env; button setTitle:title forState:UIControlStateNormal];
+    // FIXME: manually calling layoutSubviews shouldn't be needed?
+    () = msg![env; button layoutSubviews];
+    let action = env.objc.lookup_selector(action).unwrap();
+    () = msg![env; button addTarget:picker
+                             action:action
+                   forControlEvents:UIControlEventTouchUpInside];
+    () = msg![env; super_view addSubview:button];
+    button
+}
+
+/// Add the title bar at the top of the picker's view.
+fn add_title(env: &mut Environment, view: id, frame: CGRect, text: &'static str) {
+    let title_frame = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: CGSize {
+            width: frame.size.width,
+            height: BAR_HEIGHT,
+        },
+    };
+    let title: id = msg_class![env; UILabel alloc];
+    let title: id = msg![env; title initWithFrame:title_frame];
+    let text = ns_string::get_static_str(env, text);
+    () = msg![env; title setText:text];
+    () = msg![env; title setTextAlignment:UITextAlignmentCenter];
+    let font_size: CGFloat = 20.0;
+    let font: id = msg_class![env; UIFont boldSystemFontOfSize:font_size];
+    () = msg![env; title setFont:font];
+    let text_color: id = msg_class![env; UIColor whiteColor];
+    () = msg![env; title setTextColor:text_color];
+    let title_bg_color: id = msg_class![env; UIColor darkGrayColor];
+    () = msg![env; title setBackgroundColor:title_bg_color];
+    () = msg![env; view addSubview:title];
+    release(env, title);
+}
+
+/// Build the contents of the picker's view for the photo library.
+fn load_photo_library_view(env: &mut Environment, picker: id, view: id, frame: CGRect) {
+    let photos = enumerate_photos(env);
+
+    // Thumbnail grid
+    let grid_width = THUMBNAIL_SIZE * (THUMBNAIL_COLUMNS as CGFloat)
+        + THUMBNAIL_GAP * ((THUMBNAIL_COLUMNS - 1) as CGFloat);
+    let grid_origin = CGPoint {
+        x: (frame.size.width - grid_width) / 2.0,
+        y: BAR_HEIGHT + THUMBNAIL_GAP,
+    };
+    let photo_tapped_sel = env.objc.lookup_selector("_touchHLE_photoTapped:").unwrap();
+    let mut thumbnail_buttons = Vec::new();
+    for i in 0..THUMBNAILS_PER_PAGE {
+        let col = i % THUMBNAIL_COLUMNS;
+        let row = i / THUMBNAIL_COLUMNS;
+        let button_frame = CGRect {
+            origin: CGPoint {
+                x: grid_origin.x + (col as CGFloat) * (THUMBNAIL_SIZE + THUMBNAIL_GAP),
+                y: grid_origin.y + (row as CGFloat) * (THUMBNAIL_SIZE + THUMBNAIL_GAP),
+            },
+            size: CGSize {
+                width: THUMBNAIL_SIZE,
+                height: THUMBNAIL_SIZE,
+            },
+        };
+        let button: id = msg_class![env; UIButton buttonWithType:UIButtonTypeCustom];
+        () = msg![env; button setFrame:button_frame];
+        let image_view: id = msg![env; button imageView];
+        let bounds: CGRect = msg![env; button bounds];
+        () = msg![env; image_view setFrame:bounds];
+        () = msg![env; button addTarget:picker
+                                 action:photo_tapped_sel
+                       forControlEvents:UIControlEventTouchUpInside];
+        () = msg![env; view addSubview:button];
+        thumbnail_buttons.push(button);
+    }
+
+    if photos.is_empty() {
+        let label_frame = CGRect {
+            origin: CGPoint {
+                x: 10.0,
+                y: BAR_HEIGHT + 10.0,
+            },
+            size: CGSize {
+                width: frame.size.width - 20.0,
+                height: frame.size.height - BAR_HEIGHT * 2.0 - 20.0,
+            },
+        };
+        let label: id = msg_class![env; UILabel alloc];
+        let label: id = msg![env; label initWithFrame:label_frame];
+        let text = format!(
+            "No photos. Put PNG or JPEG files in the {} directory to make them available here.",
+            photo_library_dir(env).display()
+        );
+        let text = ns_string::from_rust_string(env, text);
+        () = msg![env; label setText:text];
+        release(env, text);
+        () = msg![env; label setTextAlignment:UITextAlignmentCenter];
+        () = msg![env; label setNumberOfLines:0]; // unlimited
+        let text_color: id = msg_class![env; UIColor lightGrayColor];
+        () = msg![env; label setTextColor:text_color];
+        let label_bg_color: id = msg_class![env; UIColor clearColor];
+        () = msg![env; label setBackgroundColor:label_bg_color];
+        () = msg![env; view addSubview:label];
+        release(env, label);
+    }
+
+    // Bottom bar
+    let bar_y = frame.size.height - BAR_HEIGHT + 7.0;
+    let button_size = CGSize {
+        width: 90.0,
+        height: BAR_HEIGHT - 14.0,
+    };
+    let button_frame = |x: CGFloat| CGRect {
+        origin: CGPoint { x, y: bar_y },
+        size: button_size,
+    };
+    let prev_button = make_button(
+        env,
+        picker,
+        view,
+        button_frame(10.0),
+        "Previous",
+        "_touchHLE_previousPage",
+    );
+    make_button(
+        env,
+        picker,
+        view,
+        button_frame((frame.size.width - button_size.width) / 2.0),
+        "Cancel",
+        "_touchHLE_cancel",
+    );
+    let next_button = make_button(
+        env,
+        picker,
+        view,
+        button_frame(frame.size.width - button_size.width - 10.0),
+        "Next",
+        "_touchHLE_nextPage",
+    );
+
+    let host_obj = env
+        .objc
+        .borrow_mut::<UIImagePickerControllerHostObject>(picker);
+    host_obj.photos = photos;
+    host_obj.thumbnail_buttons = thumbnail_buttons;
+    host_obj.prev_button = prev_button;
+    host_obj.next_button = next_button;
+    show_page(env, picker, 0);
+}
+
+/// Build the contents of the picker's view for the camera. There's no live
+/// preview, the "viewfinder" just shows the image that will be "taken".
+fn load_camera_view(env: &mut Environment, picker: id, view: id, frame: CGRect) {
+    let viewfinder_frame = CGRect {
+        origin: CGPoint {
+            x: 0.0,
+            y: BAR_HEIGHT,
+        },
+        size: CGSize {
+            width: frame.size.width,
+            height: frame.size.height - BAR_HEIGHT * 2.0,
+        },
+    };
+    let image = camera_image_path(env).map_or(nil, |path| load_photo(env, &path));
+    let image_view: id = msg_class![env; UIImageView alloc];
+    let image_view: id = msg![env; image_view initWithFrame:viewfinder_frame];
+    () = msg![env; image_view setImage:image];
+    release(env, image);
+    () = msg![env; view addSubview:image_view];
+    release(env, image_view);
+
+    // Bottom bar
+    let bar_y = frame.size.height - BAR_HEIGHT + 7.0;
+    let button_size = CGSize {
+        width: 120.0,
+        height: BAR_HEIGHT - 14.0,
+    };
+    let button_frame = |x: CGFloat| CGRect {
+        origin: CGPoint { x, y: bar_y },
+        size: button_size,
+    };
+    make_button(
+        env,
+        picker,
+        view,
+        button_frame(10.0),
+        "Cancel",
+        "_touchHLE_cancel",
+    );
+    make_button(
+        env,
+        picker,
+        view,
+        button_frame(frame.size.width - button_size.width - 10.0),
+        "Take Photo",
+        "_touchHLE_takePhoto",
+    );
+}
+
+/// Tell the delegate the user picked an image, or dismiss the picker if the
+/// delegate doesn't care.
+fn finish_picking(env: &mut Environment, picker: id, image: id) {
+    let delegate = env
+        .objc
+        .borrow::<UIImagePickerControllerHostObject>(picker)
+        .delegate;
+    if delegate != nil
+        && env.objc.object_has_method_named(
+            &env.mem,
+            delegate,
+            "imagePickerController:didFinishPickingMediaWithInfo:",
+        )
+    {
+        let media_type_key = ns_string::get_static_str(env, UIImagePickerControllerMediaType);
+        let media_type = ns_string::get_static_str(env, kUTTypeImage);
+        let image_key = ns_string::get_static_str(env, UIImagePickerControllerOriginalImage);
+        let info =
+            dict_from_keys_and_objects(env, &[(media_type_key, media_type), (image_key, image)]);
+        () = msg![env; delegate imagePickerController:picker didFinishPickingMediaWithInfo:info];
+        release(env, info);
+    } else if delegate != nil
+        && env.objc.object_has_method_named(
+            &env.mem,
+            delegate,
+            "imagePickerController:didFinishPickingImage:editingInfo:",
+        )
+    {
+        // iPhone OS 2.x API
+        () = msg![env; delegate imagePickerController:picker
+                                didFinishPickingImage:image
+                                          editingInfo:nil];
+    } else {
+        () = msg![env; picker dismissModalViewControllerAnimated:true];
+    }
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -16,12 +426,149 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 // TODO: should extend UINavigationController, which extends
 //       UIViewController.
-@implementation UIImagePickerController
+@implementation UIImagePickerController: UIViewController
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::new(UIImagePickerControllerHostObject {
+        superclass: Default::default(),
+        delegate: nil,
+        source_type: UIImagePickerControllerSourceTypePhotoLibrary,
+        allows_editing: false,
+        photos: Vec::new(),
+        page_idx: 0,
+        thumbnail_buttons: Vec::new(),
+        page_images: Vec::new(),
+        prev_button: nil,
+        next_button: nil,
+    });
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
++ (bool)isSourceTypeAvailable:(UIImagePickerControllerSourceType)type_ {
+    match type_ {
+        UIImagePickerControllerSourceTypePhotoLibrary
+        | UIImagePickerControllerSourceTypeSavedPhotosAlbum => true,
+        UIImagePickerControllerSourceTypeCamera => camera_image_path(env).is_some(),
+        _ => false,
+    }
+}
+
+- (())dealloc {
+    let images = std::mem::take(
+        &mut env.objc.borrow_mut::<UIImagePickerControllerHostObject>(this).page_images
+    );
+    for image in images {
+        release(env, image);
+    }
+    msg_super![env; this dealloc]
+}
+
+- (id)delegate {
+    env.objc.borrow::<UIImagePickerControllerHostObject>(this).delegate
+}
+- (())setDelegate:(id)delegate {
+    // Weak reference
+    env.objc.borrow_mut::<UIImagePickerControllerHostObject>(this).delegate = delegate;
+}
+
+- (UIImagePickerControllerSourceType)sourceType {
+    env.objc.borrow::<UIImagePickerControllerHostObject>(this).source_type
+}
+- (())setSourceType:(UIImagePickerControllerSourceType)type_ {
+    // TODO: raise NSInvalidArgumentException
+    assert!(msg_class![env; UIImagePickerController isSourceTypeAvailable:type_]);
+    env.objc.borrow_mut::<UIImagePickerControllerHostObject>(this).source_type = type_;
+}
+
+- (bool)allowsEditing {
+    env.objc.borrow::<UIImagePickerControllerHostObject>(this).allows_editing
+}
+- (())setAllowsEditing:(bool)allows_editing {
+    // TODO: Actually let the user edit the image.
+    env.objc.borrow_mut::<UIImagePickerControllerHostObject>(this).allows_editing = allows_editing;
+}
+// iPhone OS 2.x name
+- (bool)allowsImageEditing {
+    msg![env; this allowsEditing]
+}
+- (())setAllowsImageEditing:(bool)allows_editing {
+    msg![env; this setAllowsEditing:allows_editing]
+}
+
+- (())loadView {
+    let screen: id = msg_class![env; UIScreen mainScreen];
+    let frame: CGRect = msg![env; screen applicationFrame];
+    let view: id = msg_class![env; UIView alloc];
+    let view: id = msg![env; view initWithFrame:frame];
+    let bg_color: id = msg_class![env; UIColor blackColor];
+    () = msg![env; view setBackgroundColor:bg_color];
+
+    let source_type = env.objc.borrow::<UIImagePickerControllerHostObject>(this).source_type;
+    if source_type == UIImagePickerControllerSourceTypeCamera {
+        add_title(env, view, frame, "Camera");
+        load_camera_view(env, this, view, frame);
+    } else {
+        add_title(env, view, frame, "Photos");
+        load_photo_library_view(env, this, view, frame);
+    }
+
+    () = msg![env; this setView:view];
+    release(env, view);
+}
+
+- (())_touchHLE_photoTapped:(id)sender { // UIButton*
+    let host_obj = env.objc.borrow::<UIImagePickerControllerHostObject>(this);
+    let Some(idx) = host_obj.thumbnail_buttons.iter().position(|&b| b == sender) else {
+        return;
+    };
+    let Some(&image) = host_obj.page_images.get(idx) else {
+        return;
+    };
+    if image == nil {
+        return;
+    }
+    let path = &host_obj.photos[host_obj.page_idx * THUMBNAILS_PER_PAGE + idx];
+    log!("Picked photo {}", path.display());
+    finish_picking(env, this, image);
+}
+
+- (())_touchHLE_takePhoto {
+    let Some(path) = camera_image_path(env) else {
+        return;
+    };
+    let image = load_photo(env, &path);
+    if image == nil {
+        return;
+    }
+    log!("Took photo {}", path.display());
+    finish_picking(env, this, image);
+    release(env, image);
+}
+
+- (())_touchHLE_cancel {
+    let delegate = env.objc.borrow::<UIImagePickerControllerHostObject>(this).delegate;
+    if delegate != nil
+        && env.objc.object_has_method_named(&env.mem, delegate, "imagePickerControllerDidCancel:")
+    {
+        () = msg![env; delegate imagePickerControllerDidCancel:this];
+    } else {
+        () = msg![env; this dismissModalViewControllerAnimated:true];
+    }
+}
+
+- (())_touchHLE_previousPage {
+    let page_idx = env.objc.borrow::<UIImagePickerControllerHostObject>(this).page_idx;
+    if page_idx > 0 {
+        show_page(env, this, page_idx - 1);
+    }
+}
 
-+ (bool)isSourceTypeAvailable:(UIImagePickerControllerSourceType)_type {
-    // For now, simply claim no sources are available.
-    // TODO: support some sources.
-    false
+- (())_touchHLE_nextPage {
+    let host_obj = env.objc.borrow::<UIImagePickerControllerHostObject>(this);
+    let page_idx = host_obj.page_idx;
+    if (page_idx + 1) * THUMBNAILS_PER_PAGE < host_obj.photos.len() {
+        show_page(env, this, page_idx + 1);
+    }
 }
 
 @end
@@ -0,0 +1,316 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UIPasteboard`.
+//!
+//! The general pasteboard's text is the host's clipboard text, so copying and
+//! pasting works between the app and the host. Everything else, e.g. images
+//! and named pasteboards, only lives as long as the app, since SDL's clipboard
+//! API only supports text.
+
+use crate::dyld::{ConstantExports, HostConstant};
+use crate::frameworks::foundation::ns_string::{from_rust_string, to_rust_string};
+use crate::frameworks::foundation::{ns_array, ns_data, NSInteger, NSUInteger};
+use crate::objc::{
+    autorelease, id, msg, nil, objc_classes, release, retain, ClassExports, HostObject, NSZonePtr,
+};
+use crate::Environment;
+use std::collections::HashMap;
+
+pub const UIPasteboardNameGeneral: &str = "com.apple.UIKit.pboard.general";
+pub const UIPasteboardNameFind: &str = "com.apple.UIKit.pboard.find";
+
+pub const CONSTANTS: ConstantExports = &[
+    (
+        "_UIPasteboardNameGeneral",
+        HostConstant::NSString(UIPasteboardNameGeneral),
+    ),
+    (
+        "_UIPasteboardNameFind",
+        HostConstant::NSString(UIPasteboardNameFind),
+    ),
+];
+
+/// Uniform Type Identifiers for text. The first is used for `setString:`.
+const TEXT_TYPES: &[&str] = &[
+    "public.utf8-plain-text",
+    "public.plain-text",
+    "public.text",
+    "public.utf16-plain-text",
+];
+/// Uniform Type Identifier used for `setImage:`.
+const IMAGE_TYPE: &str = "public.png";
+
+#[derive(Default)]
+pub struct State {
+    /// Pasteboards by name. These are strong references.
+    pasteboards: HashMap<String, id>,
+    next_unique_id: u32,
+}
+
+struct UIPasteboardHostObject {
+    name: String,
+    /// Pasteboard type and value pairs. The values are strong references.
+    items: Vec<(String, id)>,
+    change_count: NSInteger,
+    /// Whether text goes to the host clipboard (general pasteboard only).
+    bridged: bool,
+}
+impl HostObject for UIPasteboardHostObject {}
+
+fn is_text_type(pasteboard_type: &str) -> bool {
+    TEXT_TYPES.contains(&pasteboard_type)
+}
+
+fn get_pasteboard(env: &mut Environment, name: &str, create: bool) -> id {
+    if let Some(&existing) = env
+        .framework_state
+        .uikit
+        .ui_pasteboard
+        .pasteboards
+        .get(name)
+    {
+        return existing;
+    }
+    if !create {
+        return nil;
+    }
+    let bridged = name == UIPasteboardNameGeneral && env.window.is_some();
+    let host_object = Box::new(UIPasteboardHostObject {
+        name: name.to_string(),
+        items: Vec::new(),
+        change_count: 0,
+        bridged,
+    });
+    let class = env.objc.get_known_class("UIPasteboard", &mut env.mem);
+    let new = env.objc.alloc_object(class, host_object, &mut env.mem);
+    env.framework_state
+        .uikit
+        .ui_pasteboard
+        .pasteboards
+        .insert(name.to_string(), new);
+    new
+}
+
+/// Replace the pasteboard's contents with a single item.
+fn set_item(env: &mut Environment, pasteboard: id, pasteboard_type: &str, value: id) {
+    let bridged = env
+        .objc
+        .borrow::<UIPasteboardHostObject>(pasteboard)
+        .bridged;
+    let old_items = std::mem::take(
+        &mut env
+            .objc
+            .borrow_mut::<UIPasteboardHostObject>(pasteboard)
+            .items,
+    );
+    for (_, old_value) in old_items {
+        release(env, old_value);
+    }
+
+    if bridged && is_text_type(pasteboard_type) && value != nil {
+        let text = to_rust_string(env, value);
+        env.window_mut().set_clipboard_text(&text);
+    } else {
+        if bridged {
+            // The host clipboard can't hold this, but it mustn't keep the old
+            // text either.
+            env.window_mut().set_clipboard_text("");
+        }
+        if value == nil {
+            return;
+        }
+        retain(env, value);
+        env.objc
+            .borrow_mut::<UIPasteboardHostObject>(pasteboard)
+            .items
+            .push((pasteboard_type.to_string(), value));
+    }
+    env.objc
+        .borrow_mut::<UIPasteboardHostObject>(pasteboard)
+        .change_count += 1;
+}
+
+/// Get the value for a type, if any. The result is autoreleased.
+fn get_item(env: &mut Environment, pasteboard: id, pasteboard_type: &str) -> id {
+    let host_obj = env.objc.borrow::<UIPasteboardHostObject>(pasteboard);
+    if host_obj.bridged && is_text_type(pasteboard_type) {
+        let Some(text) = env.window().get_clipboard_text() else {
+            return nil;
+        };
+        let string = from_rust_string(env, text);
+        return autorelease(env, string);
+    }
+    let value = host_obj
+        .items
+        .iter()
+        .find(|(other_type, _)| other_type == pasteboard_type)
+        .map_or(nil, |&(_, value)| value);
+    retain(env, value);
+    autorelease(env, value)
+}
+
+/// Get the types the pasteboard has values for.
+fn get_types(env: &mut Environment, pasteboard: id) -> Vec<String> {
+    let host_obj = env.objc.borrow::<UIPasteboardHostObject>(pasteboard);
+    let mut types: Vec<String> = host_obj
+        .items
+        .iter()
+        .map(|(pasteboard_type, _)| pasteboard_type.clone())
+        .collect();
+    if host_obj.bridged && env.window().get_clipboard_text().is_some() {
+        types.push(TEXT_TYPES[0].to_string());
+    }
+    types
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UIPasteboard: NSObject
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    // Pasteboards can only be created with the class methods.
+    unimplemented!()
+}
+
++ (id)generalPasteboard {
+    get_pasteboard(env, UIPasteboardNameGeneral, /* create: */ true)
+}
+
++ (id)pasteboardWithName:(id)name // NSString*
+                  create:(bool)create {
+    let name = to_rust_string(env, name);
+    get_pasteboard(env, &name, create)
+}
+
++ (id)pasteboardWithUniqueName {
+    let state = &mut env.framework_state.uikit.ui_pasteboard;
+    let name = format!("touchHLE.unique.{}", state.next_unique_id);
+    state.next_unique_id += 1;
+    get_pasteboard(env, &name, /* create: */ true)
+}
+
++ (())removePasteboardWithName:(id)name { // NSString*
+    let name = to_rust_string(env, name);
+    let pasteboards = &mut env.framework_state.uikit.ui_pasteboard.pasteboards;
+    if let Some(pasteboard) = pasteboards.remove(&*name) {
+        release(env, pasteboard);
+    }
+}
+
+- (())dealloc {
+    let items = std::mem::take(&mut env.objc.borrow_mut::<UIPasteboardHostObject>(this).items);
+    for (_, value) in items {
+        release(env, value);
+    }
+    env.objc.dealloc_object(this, &mut env.mem)
+}
+
+- (id)name {
+    let name = env.objc.borrow::<UIPasteboardHostObject>(this).name.clone();
+    let name = from_rust_string(env, name);
+    autorelease(env, name)
+}
+
+- (bool)isPersistent {
+    false
+}
+- (())setPersistent:(bool)persistent {
+    log_dbg!("[(UIPasteboard*){:?} setPersistent:{}] (ignored)", this, persistent);
+}
+
+- (NSInteger)changeCount {
+    env.objc.borrow::<UIPasteboardHostObject>(this).change_count
+}
+
+- (NSInteger)numberOfItems {
+    if get_types(env, this).is_empty() {
+        0
+    } else {
+        1 // multiple items aren't supported
+    }
+}
+
+- (id)pasteboardTypes {
+    let types = get_types(env, this);
+    let types = types
+        .into_iter()
+        .map(|pasteboard_type| from_rust_string(env, pasteboard_type))
+        .collect();
+    let types = ns_array::from_vec(env, types);
+    autorelease(env, types)
+}
+- (bool)containsPasteboardTypes:(id)types { // NSArray* of NSString*
+    let available = get_types(env, this);
+    let count: NSUInteger = msg![env; types count];
+    for i in 0..count {
+        let pasteboard_type: id = msg![env; types objectAtIndex:i];
+        let pasteboard_type = to_rust_string(env, pasteboard_type);
+        if available.iter().any(|other| *other == pasteboard_type) {
+            return true;
+        }
+    }
+    false
+}
+
+- (id)valueForPasteboardType:(id)pasteboard_type { // NSString*
+    let pasteboard_type = to_rust_string(env, pasteboard_type);
+    get_item(env, this, &pasteboard_type)
+}
+- (())setValue:(id)value
+forPasteboardType:(id)pasteboard_type { // NSString*
+    let pasteboard_type = to_rust_string(env, pasteboard_type);
+    set_item(env, this, &pasteboard_type, value);
+}
+
+- (id)dataForPasteboardType:(id)pasteboard_type { // NSString*
+    let pasteboard_type = to_rust_string(env, pasteboard_type);
+    let value = get_item(env, this, &pasteboard_type);
+    if value != nil && is_text_type(&pasteboard_type) {
+        let text = to_rust_string(env, value);
+        let data = ns_data::from_vec(env, text.into_owned().into_bytes());
+        autorelease(env, data)
+    } else {
+        value
+    }
+}
+- (())setData:(id)data // NSData*
+forPasteboardType:(id)pasteboard_type { // NSString*
+    let pasteboard_type = to_rust_string(env, pasteboard_type);
+    if is_text_type(&pasteboard_type) {
+        let text = String::from_utf8_lossy(ns_data::to_rust_slice(env, data)).into_owned();
+        let string = from_rust_string(env, text);
+        set_item(env, this, &pasteboard_type, string);
+        release(env, string);
+    } else {
+        set_item(env, this, &pasteboard_type, data);
+    }
+}
+
+- (id)string {
+    for pasteboard_type in TEXT_TYPES {
+        let value = get_item(env, this, pasteboard_type);
+        if value != nil {
+            return value;
+        }
+    }
+    nil
+}
+- (())setString:(id)string { // NSString*
+    set_item(env, this, TEXT_TYPES[0], string);
+}
+
+- (id)image {
+    get_item(env, this, IMAGE_TYPE)
+}
+- (())setImage:(id)image { // UIImage*
+    set_item(env, this, IMAGE_TYPE, image);
+}
+
+@end
+
+};
@@ -5,7 +5,13 @@
  */
 //! `UIResponder`.
 
-use crate::objc::{id, objc_classes, ClassExports};
+use crate::objc::{id, msg, nil, objc_classes, ClassExports};
+
+#[derive(Default)]
+pub struct State {
+    /// The current first responder, if any. Weak reference.
+    pub(super) first_responder: id,
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
@@ -15,12 +21,21 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 // TODO: real responder implementation etc
 
-// These methods print debug logs because they are only likely to get called if
-// a subclass didn't override them, which might mean we delivered the event to
-// the wrong object or it is unhandled.
+- (id)nextResponder {
+    // Subclasses override this.
+    nil
+}
+
+// The default implementations pass the touches along the responder chain.
+// They print debug logs if there's nowhere for the touches to go, because that
+// might mean we delivered the event to the wrong object or it is unhandled.
 
 - (())touchesBegan:(id)touches // NSSet* of UITouch*
          withEvent:(id)event { // UIEvent*
+    let next: id = msg![env; this nextResponder];
+    if next != nil {
+        return msg![env; next touchesBegan:touches withEvent:event];
+    }
     log_dbg!(
         "[{:?} touchesBegan:{:?} withEvent:{:?}] (probably unhandled)",
         this,
@@ -31,6 +46,10 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 - (())touchesMoved:(id)touches // NSSet* of UITouch*
          withEvent:(id)event { // UIEvent*
+    let next: id = msg![env; this nextResponder];
+    if next != nil {
+        return msg![env; next touchesMoved:touches withEvent:event];
+    }
     log_dbg!(
         "[{:?} touchesMoved:{:?} withEvent:{:?}] (probably unhandled)",
         this,
@@ -41,6 +60,10 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 - (())touchesEnded:(id)touches // NSSet* of UITouch*
          withEvent:(id)event { // UIEvent*
+    let next: id = msg![env; this nextResponder];
+    if next != nil {
+        return msg![env; next touchesEnded:touches withEvent:event];
+    }
     log_dbg!(
         "[{:?} touchesEnded:{:?} withEvent:{:?}] (probably unhandled)",
         this,
@@ -49,12 +72,56 @@ pub const CLASSES: ClassExports = objc_classes! {
     );
 }
 
-- (bool)becomeFirstResponder {
-    // TODO
+- (())touchesCancelled:(id)touches // NSSet* of UITouch*
+             withEvent:(id)event { // UIEvent*
+    let next: id = msg![env; this nextResponder];
+    if next != nil {
+        return msg![env; next touchesCancelled:touches withEvent:event];
+    }
+    log_dbg!(
+        "[{:?} touchesCancelled:{:?} withEvent:{:?}] (probably unhandled)",
+        this,
+        touches,
+        event,
+    );
+}
+
+// TODO: sending events other than touches through the responder chain
+
+- (bool)canBecomeFirstResponder {
     false
 }
+- (bool)canResignFirstResponder {
+    true
+}
+- (bool)isFirstResponder {
+    env.framework_state.uikit.ui_responder.first_responder == this
+}
+
+- (bool)becomeFirstResponder {
+    let current = env.framework_state.uikit.ui_responder.first_responder;
+    if current == this {
+        return true;
+    }
+    if !msg![env; this canBecomeFirstResponder] {
+        return false;
+    }
+    if current != nil && !msg![env; current resignFirstResponder] {
+        return false;
+    }
+    log_dbg!("New first responder: {:?}", this);
+    env.framework_state.uikit.ui_responder.first_responder = this;
+    true
+}
 - (bool)resignFirstResponder {
-    // TODO
+    if !msg![env; this canResignFirstResponder] {
+        return false;
+    }
+    let first_responder = &mut env.framework_state.uikit.ui_responder.first_responder;
+    if *first_responder == this {
+        log_dbg!("{:?} is no longer the first responder", this);
+        *first_responder = nil;
+    }
     true
 }
 
@@ -6,6 +6,7 @@
 //! `UITouch`.
 
 use super::ui_event;
+use super::ui_gesture_recognizer;
 use crate::frameworks::core_graphics::{CGPoint, CGRect};
 use crate::frameworks::foundation::{NSInteger, NSTimeInterval, NSUInteger};
 use crate::mem::MutVoidPtr;
@@ -23,6 +24,7 @@ pub const UITouchPhaseBegan: UITouchPhase = 0;
 pub const UITouchPhaseMoved: UITouchPhase = 1;
 pub const UITouchPhaseStationary: UITouchPhase = 2;
 pub const UITouchPhaseEnded: UITouchPhase = 3;
+pub const UITouchPhaseCancelled: UITouchPhase = 4;
 
 #[derive(Default)]
 pub struct State {
@@ -40,7 +42,13 @@ pub(super) struct UITouchHostObject {
     /// Relative to the screen
     previous_location: CGPoint,
     timestamp: NSTimeInterval,
-    phase: UITouchPhase,
+    pub(super) phase: UITouchPhase,
+    /// Strong references to the `UIGestureRecognizer`s this touch is delivered
+    /// to.
+    pub(super) gesture_recognizers: Vec<id>,
+    /// Set when a gesture recognizer has cancelled this touch for the view, so
+    /// it will no longer be delivered there.
+    pub(super) cancelled_in_view: bool,
 }
 impl HostObject for UITouchHostObject {}
 
@@ -58,14 +66,22 @@ pub const CLASSES: ClassExports = objc_classes! {
         previous_location: CGPoint { x: 0.0, y: 0.0 },
         timestamp: 0.0,
         phase: UITouchPhaseBegan,
+        gesture_recognizers: Vec::new(),
+        cancelled_in_view: false,
     });
     env.objc.alloc_object(this, host_object, &mut env.mem)
 }
 
 - (())dealloc {
     let &mut UITouchHostObject { view, window, .. } = env.objc.borrow_mut(this);
+    let gesture_recognizers = std::mem::take(
+        &mut env.objc.borrow_mut::<UITouchHostObject>(this).gesture_recognizers
+    );
     release(env, view);
     release(env, window);
+    for recognizer in gesture_recognizers {
+        release(env, recognizer);
+    }
     env.objc.dealloc_object(this, &mut env.mem)
 }
 
@@ -106,6 +122,43 @@ pub const CLASSES: ClassExports = objc_classes! {
 
 };
 
+fn touches_vec(env: &mut Environment, touches: id) -> Vec<id> {
+    let touches_arr: id = msg![env; touches allObjects];
+    let touches_count: NSUInteger = msg![env; touches_arr count];
+    let mut touches_vec = Vec::with_capacity(touches_count as usize);
+    for i in 0..touches_count {
+        let touch: id = msg![env; touches_arr objectAtIndex:i];
+        touches_vec.push(touch);
+    }
+    touches_vec
+}
+
+/// Filter out touches that a gesture recognizer has cancelled for their view.
+/// Returns [None] if there are none left.
+fn uncancelled_touches(env: &mut Environment, touches: id) -> Option<id> {
+    let all_touches = touches_vec(env, touches);
+    let remaining: Vec<id> = all_touches
+        .iter()
+        .copied()
+        .filter(|&touch| {
+            !env.objc
+                .borrow::<UITouchHostObject>(touch)
+                .cancelled_in_view
+        })
+        .collect();
+    if remaining.is_empty() {
+        None
+    } else if remaining.len() == all_touches.len() {
+        Some(touches)
+    } else {
+        let filtered: id = msg_class![env; NSMutableSet new];
+        for touch in remaining {
+            let _: () = msg![env; filtered addObject:touch];
+        }
+        Some(autorelease(env, filtered))
+    }
+}
+
 /// [super::handle_events] will forward touch events to this function.
 pub fn handle_event(env: &mut Environment, event: Event) {
     // before processing anything, we mark all current touches as stationary
@@ -178,6 +231,8 @@ fn handle_touches_down(env: &mut Environment, map: HashMap<FingerId, Coords>) {
             previous_location: location,
             timestamp,
             phase: UITouchPhaseBegan,
+            gesture_recognizers: Vec::new(),
+            cancelled_in_view: false,
         };
         autorelease(env, new_touch);
 
@@ -208,6 +263,9 @@ fn handle_touches_down(env: &mut Environment, map: HashMap<FingerId, Coords>) {
     // view to set of touches for this view
     let mut view_touches: HashMap<id, id> = HashMap::new();
 
+    // touches that got a view, and so might have gesture recognizers
+    let mut hit_touches = Vec::new();
+
     let touches_arr: id = msg![env; touches allObjects];
     let touches_count: NSUInteger = msg![env; touches_arr count];
     for i in 0..touches_count {
@@ -281,9 +339,20 @@ fn handle_touches_down(env: &mut Environment, map: HashMap<FingerId, Coords>) {
             new_touch.view = view;
             new_touch.window = top_window;
         }
+
+        let recognizers = ui_gesture_recognizer::recognizers_for_touch(env, view, touch);
+        env.objc
+            .borrow_mut::<UITouchHostObject>(touch)
+            .gesture_recognizers = recognizers;
+        hit_touches.push(touch);
     }
 
+    ui_gesture_recognizer::deliver_touches(env, &hit_touches, event, UITouchPhaseBegan);
+
     for (view, touches) in view_touches {
+        let Some(touches) = uncancelled_touches(env, touches) else {
+            continue;
+        };
         log_dbg!(
             "Sending [{:?} touchesBegan:{:?} withEvent:{:?}]",
             view,
@@ -349,7 +418,13 @@ fn handle_touches_move(env: &mut Environment, map: HashMap<FingerId, Coords>) {
     let event = ui_event::new_event(env, touches);
     autorelease(env, event);
 
+    let moved_touches = touches_vec(env, touches);
+    ui_gesture_recognizer::deliver_touches(env, &moved_touches, event, UITouchPhaseMoved);
+
     for (view, touches) in view_touches {
+        let Some(touches) = uncancelled_touches(env, touches) else {
+            continue;
+        };
         log_dbg!(
             "Sending [{:?} touchesMoved:{:?} withEvent:{:?}]",
             view,
@@ -423,7 +498,13 @@ fn handle_touches_up(env: &mut Environment, map: HashMap<FingerId, Coords>) {
     let event = ui_event::new_event(env, touches);
     autorelease(env, event);
 
+    let ended_touches = touches_vec(env, touches);
+    ui_gesture_recognizer::deliver_touches(env, &ended_touches, event, UITouchPhaseEnded);
+
     for (view, touches) in view_touches {
+        let Some(touches) = uncancelled_touches(env, touches) else {
+            continue;
+        };
         log_dbg!(
             "Sending [{:?} touchesEnded:{:?} withEvent:{:?}]",
             view,
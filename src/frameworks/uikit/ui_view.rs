@@ -8,28 +8,46 @@
 //! Useful resources:
 //! - Apple's [View Programming Guide for iOS](https://developer.apple.com/library/archive/documentation/WindowsViews/Conceptual/ViewPG_iPhoneOS/Introduction/Introduction.html)
 
+mod animation;
+pub mod ui_activity_indicator_view;
 pub mod ui_alert_view;
 pub mod ui_control;
 pub mod ui_image_view;
 pub mod ui_label;
+pub mod ui_progress_view;
+pub mod ui_scroll_view;
+pub mod ui_table_view_cell;
+pub mod ui_web_view;
 pub mod ui_window;
 
 use super::ui_graphics::{UIGraphicsPopContext, UIGraphicsPushContext};
+use crate::abi::CallFromHost;
 use crate::frameworks::core_graphics::cg_affine_transform::CGAffineTransform;
 use crate::frameworks::core_graphics::cg_context::{CGContextClearRect, CGContextRef};
 use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect};
 use crate::frameworks::foundation::ns_string::get_static_str;
 use crate::frameworks::foundation::{ns_array, NSInteger, NSUInteger};
+use crate::mem::MutVoidPtr;
 use crate::objc::{
-    autorelease, id, msg, nil, objc_classes, release, retain, Class, ClassExports, HostObject,
-    NSZonePtr,
+    autorelease, get_block_invoke, id, msg, nil, objc_classes, release, retain, Class,
+    ClassExports, HostObject, NSZonePtr, SEL,
 };
 use crate::Environment;
+use animation::{Property, UIViewAnimationCurve, UIViewAnimationOptions, Value};
+
+pub(super) use animation::run_animations;
 
 #[derive(Default)]
 pub struct State {
     /// List of views for internal purposes. Non-retaining!
     pub(super) views: Vec<id>,
+    /// Views that have had `setNeedsLayout` called on them since they were
+    /// last laid out. Non-retaining!
+    views_needing_layout: Vec<id>,
+    animation: animation::State,
+    ui_activity_indicator_view: ui_activity_indicator_view::State,
+    ui_alert_view: ui_alert_view::State,
+    ui_progress_view: ui_progress_view::State,
     pub ui_window: ui_window::State,
 }
 
@@ -43,6 +61,9 @@ pub(super) struct UIViewHostObject {
     clears_context_before_drawing: bool,
     user_interaction_enabled: bool,
     multiple_touch_enabled: bool,
+    /// `UIGestureRecognizer`s attached to this view. These are strong
+    /// references.
+    pub(super) gesture_recognizers: Vec<id>,
 }
 impl HostObject for UIViewHostObject {}
 impl Default for UIViewHostObject {
@@ -56,10 +77,19 @@ impl Default for UIViewHostObject {
             clears_context_before_drawing: true,
             user_interaction_enabled: true,
             multiple_touch_enabled: false,
+            gesture_recognizers: Vec::new(),
         }
     }
 }
 
+/// For use by the run loop: call `layoutSubviews` on views that need to be laid
+/// out, like UIKit does before drawing.
+pub(super) fn layout_views_if_needed(env: &mut Environment) {
+    while let Some(view) = env.framework_state.uikit.ui_view.views_needing_layout.pop() {
+        () = msg![env; view layoutSubviews];
+    }
+}
+
 /// Shared parts of `initWithCoder:` and `initWithFrame:`. These can't call
 /// `init`: the subclass may have overridden `init` and will not expect to be
 /// called here.
@@ -96,6 +126,113 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.objc.get_known_class("CALayer", &mut env.mem)
 }
 
+// Animations (see animation.rs)
+
++ (())beginAnimations:(id)animation_id // NSString*
+                context:(MutVoidPtr)context {
+    animation::begin(env, animation_id, context);
+}
++ (())commitAnimations {
+    animation::commit(env);
+}
+// The setters have no effect outside an animation block.
++ (())setAnimationDuration:(f64)duration { // NSTimeInterval
+    if let Some(animation) = animation::pending_mut(env) {
+        animation.duration = duration;
+    }
+}
++ (())setAnimationDelay:(f64)delay { // NSTimeInterval
+    if let Some(animation) = animation::pending_mut(env) {
+        animation.delay = delay;
+    }
+}
++ (())setAnimationCurve:(UIViewAnimationCurve)curve {
+    if let Some(animation) = animation::pending_mut(env) {
+        animation.curve = curve;
+    }
+}
++ (())setAnimationDelegate:(id)delegate {
+    if let Some(animation) = animation::pending_mut(env) {
+        animation.delegate = delegate;
+    }
+}
++ (())setAnimationWillStartSelector:(SEL)selector {
+    if let Some(animation) = animation::pending_mut(env) {
+        animation.will_start_selector = (!selector.is_null()).then_some(selector);
+    }
+}
++ (())setAnimationDidStopSelector:(SEL)selector {
+    if let Some(animation) = animation::pending_mut(env) {
+        animation.did_stop_selector = (!selector.is_null()).then_some(selector);
+    }
+}
++ (())setAnimationRepeatCount:(f32)count {
+    if count > 1.0 {
+        log!("TODO: [UIView setAnimationRepeatCount:{}] (ignored)", count);
+    }
+}
++ (())setAnimationRepeatAutoreverses:(bool)autoreverses {
+    if autoreverses {
+        log!("TODO: [UIView setAnimationRepeatAutoreverses:YES] (ignored)");
+    }
+}
++ (())setAnimationBeginsFromCurrentState:(bool)_begins_from_current_state {
+    // Animations always begin from the current state at the moment.
+}
++ (())setAnimationTransition:(NSInteger)transition // UIViewAnimationTransition
+                     forView:(id)view
+                       cache:(bool)_cache {
+    log!(
+        "TODO: [UIView setAnimationTransition:{} forView:{:?} cache:_] (ignored)",
+        transition,
+        view,
+    );
+}
++ (())setAnimationsEnabled:(bool)enabled {
+    animation::set_animations_enabled(env, enabled);
+}
++ (bool)areAnimationsEnabled {
+    animation::animations_enabled(env)
+}
+
++ (())animateWithDuration:(f64)duration // NSTimeInterval
+               animations:(id)animations { // void (^)(void)
+    let delay: f64 = 0.0;
+    let options: UIViewAnimationOptions = 0;
+    msg![env; this animateWithDuration:duration
+                                 delay:delay
+                               options:options
+                            animations:animations
+                            completion:nil]
+}
++ (())animateWithDuration:(f64)duration // NSTimeInterval
+               animations:(id)animations // void (^)(void)
+               completion:(id)completion { // void (^)(BOOL finished)
+    let delay: f64 = 0.0;
+    let options: UIViewAnimationOptions = 0;
+    msg![env; this animateWithDuration:duration
+                                 delay:delay
+                               options:options
+                            animations:animations
+                            completion:completion]
+}
++ (())animateWithDuration:(f64)duration // NSTimeInterval
+                    delay:(f64)delay // NSTimeInterval
+                  options:(UIViewAnimationOptions)options
+               animations:(id)animations // void (^)(void)
+               completion:(id)completion { // void (^)(BOOL finished)
+    animation::begin(env, nil, MutVoidPtr::null());
+    let animation = animation::pending_mut(env).unwrap();
+    animation.duration = duration;
+    animation.delay = delay;
+    animation.curve = animation::curve_from_options(options);
+    animation::set_completion(env, completion);
+    // TODO: other options (repeating, autoreversing, user interaction)
+    let invoke = get_block_invoke(&env.mem, animations);
+    let _: () = invoke.call_from_host(env, (animations,));
+    animation::commit(env);
+}
+
 // TODO: accessors etc
 
 // initWithCoder: and initWithFrame: are basically UIView's designated
@@ -109,7 +246,10 @@ pub const CLASSES: ClassExports = objc_classes! {
 - (id)initWithFrame:(CGRect)frame {
     let this = init_common(env, this);
 
-    () = msg![env; this setFrame:frame];
+    // A new view shouldn't animate from an empty frame.
+    animation::perform_without_animation(env, |env| {
+        () = msg![env; this setFrame:frame];
+    });
 
     log_dbg!(
         "[(UIView*){:?} initWithFrame:{:?}] => bounds {:?}, center {:?}",
@@ -155,8 +295,10 @@ pub const CLASSES: ClassExports = objc_classes! {
         subview_count,
     );
 
-    () = msg![env; this setBounds:bounds];
-    () = msg![env; this setCenter:center];
+    animation::perform_without_animation(env, |env| {
+        () = msg![env; this setBounds:bounds];
+        () = msg![env; this setCenter:center];
+    });
     () = msg![env; this setHidden:hidden];
     () = msg![env; this setOpaque:opaque];
 
@@ -186,6 +328,24 @@ pub const CLASSES: ClassExports = objc_classes! {
     // On iOS 5.1 and earlier, the default implementation of this method does
     // nothing.
 }
+- (())setNeedsLayout {
+    let views = &mut env.framework_state.uikit.ui_view.views_needing_layout;
+    if !views.contains(&this) {
+        views.push(this);
+    }
+}
+- (())layoutIfNeeded {
+    let views = &mut env.framework_state.uikit.ui_view.views_needing_layout;
+    if let Some(idx) = views.iter().position(|&v| v == this) {
+        views.swap_remove(idx);
+        () = msg![env; this layoutSubviews];
+    }
+}
+
+- (id)nextResponder {
+    // TODO: the view controller should come before the superview, if any
+    env.objc.borrow::<UIViewHostObject>(this).superview
+}
 
 - (id)superview {
     env.objc.borrow::<UIViewHostObject>(this).superview
@@ -223,6 +383,37 @@ pub const CLASSES: ClassExports = objc_classes! {
     }
 }
 
+- (id)gestureRecognizers {
+    let recognizers = env.objc.borrow::<UIViewHostObject>(this).gesture_recognizers.clone();
+    for recognizer in &recognizers {
+        retain(env, *recognizer);
+    }
+    let recognizers = ns_array::from_vec(env, recognizers);
+    autorelease(env, recognizers)
+}
+- (())addGestureRecognizer:(id)recognizer { // UIGestureRecognizer*
+    let recognizers = &env.objc.borrow::<UIViewHostObject>(this).gesture_recognizers;
+    if recognizers.contains(&recognizer) {
+        return;
+    }
+    let old_view: id = msg![env; recognizer view];
+    if old_view != nil {
+        () = msg![env; old_view removeGestureRecognizer:recognizer];
+    }
+    retain(env, recognizer);
+    env.objc.borrow_mut::<UIViewHostObject>(this).gesture_recognizers.push(recognizer);
+    () = msg![env; recognizer _touchHLE_setView:this];
+}
+- (())removeGestureRecognizer:(id)recognizer { // UIGestureRecognizer*
+    let recognizers = &mut env.objc.borrow_mut::<UIViewHostObject>(this).gesture_recognizers;
+    let Some(idx) = recognizers.iter().position(|&other| other == recognizer) else {
+        return;
+    };
+    recognizers.remove(idx);
+    () = msg![env; recognizer _touchHLE_setView:nil];
+    release(env, recognizer);
+}
+
 - (())bringSubviewToFront:(id)subview {
     if subview == nil {
         // This happens in Touch & Go LITE. It's probably due to the ad classes
@@ -274,6 +465,7 @@ pub const CLASSES: ClassExports = objc_classes! {
         clears_context_before_drawing: _,
         user_interaction_enabled: _,
         multiple_touch_enabled: _,
+        gesture_recognizers,
     } = std::mem::take(env.objc.borrow_mut(this));
 
     release(env, layer);
@@ -282,10 +474,18 @@ pub const CLASSES: ClassExports = objc_classes! {
         env.objc.borrow_mut::<UIViewHostObject>(subview).superview = nil;
         release(env, subview);
     }
+    for recognizer in gesture_recognizers {
+        () = msg![env; recognizer _touchHLE_setView:nil];
+        release(env, recognizer);
+    }
 
     env.framework_state.uikit.ui_view.views.swap_remove(
         env.framework_state.uikit.ui_view.views.iter().position(|&v| v == this).unwrap()
     );
+    let views_needing_layout = &mut env.framework_state.uikit.ui_view.views_needing_layout;
+    if let Some(idx) = views_needing_layout.iter().position(|&v| v == this) {
+        views_needing_layout.swap_remove(idx);
+    }
 
     env.objc.dealloc_object(this, &mut env.mem);
 }
@@ -313,12 +513,10 @@ pub const CLASSES: ClassExports = objc_classes! {
 }
 
 - (CGFloat)alpha {
-    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
-    msg![env; layer opacity]
+    animation::model_value(env, this, Property::Opacity).float()
 }
 - (())setAlpha:(CGFloat)alpha {
-    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
-    msg![env; layer setOpacity:alpha]
+    animation::set_animatable(env, this, Property::Opacity, Value::Float(alpha));
 }
 
 // FIXME: CALayer's backgroundColor should be a CGColorRef, which is supposedly
@@ -326,12 +524,10 @@ pub const CLASSES: ClassExports = objc_classes! {
 // them as the same type (and it seems like UIKit itself maybe did this once),
 // but eventually we'll have to do this properly.
 - (id)backgroundColor {
-    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
-    msg![env; layer backgroundColor]
+    animation::model_value(env, this, Property::BackgroundColor).color()
 }
 - (())setBackgroundColor:(id)color { // UIColor*
-    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
-    msg![env; layer setBackgroundColor:color]
+    animation::set_animatable(env, this, Property::BackgroundColor, Value::Color(color));
 }
 
 // TODO: support setNeedsDisplayInRect:
@@ -341,33 +537,53 @@ pub const CLASSES: ClassExports = objc_classes! {
 }
 
 - (CGRect)bounds {
-    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
-    msg![env; layer bounds]
+    animation::model_value(env, this, Property::Bounds).rect()
 }
 - (())setBounds:(CGRect)bounds {
-    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
-    msg![env; layer setBounds:bounds]
+    animation::set_animatable(env, this, Property::Bounds, Value::Rect(bounds));
 }
 - (CGPoint)center {
     // FIXME: what happens if [layer anchorPoint] isn't (0.5, 0.5)?
-    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
-    msg![env; layer position]
+    animation::model_value(env, this, Property::Position).point()
 }
 - (())setCenter:(CGPoint)center {
-    let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
-    msg![env; layer setPosition:center]
+    animation::set_animatable(env, this, Property::Position, Value::Point(center));
 }
+// These match CALayer's frame and setFrame:, but go via the animatable
+// properties.
 - (CGRect)frame {
     let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
-    msg![env; layer frame]
+    let anchor_point: CGPoint = msg![env; layer anchorPoint];
+    let position = animation::model_value(env, this, Property::Position).point();
+    let bounds = animation::model_value(env, this, Property::Bounds).rect();
+    CGRect {
+        origin: CGPoint {
+            x: position.x - bounds.size.width * anchor_point.x,
+            y: position.y - bounds.size.height * anchor_point.y,
+        },
+        size: bounds.size,
+    }
 }
 - (())setFrame:(CGRect)frame {
     let layer = env.objc.borrow::<UIViewHostObject>(this).layer;
-    msg![env; layer setFrame:frame]
+    let anchor_point: CGPoint = msg![env; layer anchorPoint];
+    let position = CGPoint {
+        x: frame.origin.x + frame.size.width * anchor_point.x,
+        y: frame.origin.y + frame.size.height * anchor_point.y,
+    };
+    let bounds = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: frame.size,
+    };
+    animation::set_animatable(env, this, Property::Position, Value::Point(position));
+    animation::set_animatable(env, this, Property::Bounds, Value::Rect(bounds));
+}
+
+- (CGAffineTransform)transform {
+    animation::model_value(env, this, Property::Transform).transform()
 }
-
 - (())setTransform:(CGAffineTransform)transform {
-    log!("TODO: [{:?} setTransform:{:?}]", this, transform);
+    animation::set_animatable(env, this, Property::Transform, Value::Transform(transform));
 }
 
 - (())setContentMode:(NSInteger)content_mode { // should be UIViewContentMode
@@ -0,0 +1,559 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UIView` animations, both the `beginAnimations:context:` and
+//! `commitAnimations` kind and the block-based kind.
+//!
+//! touchHLE's Core Animation doesn't have animations of its own yet, so these
+//! are done by interpolating layer properties on each run loop iteration. This
+//! means the layer holds what Core Animation would call the presentation value
+//! while an animation is running, and `UIView`'s getters must use
+//! [model_value] to get the final value the app set.
+
+use super::UIViewHostObject;
+use crate::abi::CallFromHost;
+use crate::frameworks::core_graphics::cg_affine_transform::CGAffineTransform;
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::{NSInteger, NSUInteger};
+use crate::frameworks::uikit::ui_color;
+use crate::mem::MutVoidPtr;
+use crate::objc::{
+    copy_block, get_block_invoke, id, msg, msg_class, msg_send, nil, release, release_block,
+    retain, SEL,
+};
+use crate::Environment;
+use std::time::{Duration, Instant};
+
+pub type UIViewAnimationCurve = NSInteger;
+pub const UIViewAnimationCurveEaseInOut: UIViewAnimationCurve = 0;
+pub const UIViewAnimationCurveEaseIn: UIViewAnimationCurve = 1;
+pub const UIViewAnimationCurveEaseOut: UIViewAnimationCurve = 2;
+pub const UIViewAnimationCurveLinear: UIViewAnimationCurve = 3;
+
+pub type UIViewAnimationOptions = NSUInteger;
+/// The curve is stored in these bits of [UIViewAnimationOptions], as a
+/// [UIViewAnimationCurve] value shifted left by 16.
+const UIViewAnimationOptionCurveMask: UIViewAnimationOptions = 3 << 16;
+
+/// Default duration for `beginAnimations:context:`.
+const DEFAULT_DURATION: f64 = 0.2;
+/// How often animations are updated.
+const STEP_INTERVAL: f64 = 1.0 / 60.0;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub(super) enum Property {
+    Position,
+    Bounds,
+    Opacity,
+    BackgroundColor,
+    Transform,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(super) enum Value {
+    Point(CGPoint),
+    Rect(CGRect),
+    Float(CGFloat),
+    /// `UIColor*`, possibly nil. Strong reference while part of an animation.
+    Color(id),
+    Transform(CGAffineTransform),
+}
+impl Value {
+    pub(super) fn point(self) -> CGPoint {
+        let Value::Point(point) = self else { panic!() };
+        point
+    }
+    pub(super) fn rect(self) -> CGRect {
+        let Value::Rect(rect) = self else { panic!() };
+        rect
+    }
+    pub(super) fn float(self) -> CGFloat {
+        let Value::Float(float) = self else { panic!() };
+        float
+    }
+    pub(super) fn color(self) -> id {
+        let Value::Color(color) = self else { panic!() };
+        color
+    }
+    pub(super) fn transform(self) -> CGAffineTransform {
+        let Value::Transform(transform) = self else {
+            panic!()
+        };
+        transform
+    }
+}
+
+struct Change {
+    /// `UIView*`, strong reference.
+    view: id,
+    property: Property,
+    from: Value,
+    to: Value,
+}
+
+pub(super) struct Animation {
+    /// `NSString*` passed to `beginAnimations:context:`, strong reference.
+    animation_id: id,
+    context: MutVoidPtr,
+    pub(super) duration: f64,
+    pub(super) delay: f64,
+    pub(super) curve: UIViewAnimationCurve,
+    /// Weak reference.
+    pub(super) delegate: id,
+    pub(super) will_start_selector: Option<SEL>,
+    pub(super) did_stop_selector: Option<SEL>,
+    /// Copied block, for the block-based methods. Possibly nil.
+    completion: id,
+    changes: Vec<Change>,
+    /// Set when the animation is committed.
+    start: Option<Instant>,
+    started: bool,
+    /// Set if another change to one of the animated properties cut the
+    /// animation short.
+    interrupted: bool,
+}
+
+#[derive(Default)]
+pub struct State {
+    /// Animations that have been begun but not yet committed. These can be
+    /// nested, and changes are recorded by the innermost one.
+    pending: Vec<Animation>,
+    running: Vec<Animation>,
+    animations_disabled: bool,
+}
+
+fn layer_for_view(env: &Environment, view: id) -> id {
+    env.objc.borrow::<UIViewHostObject>(view).layer
+}
+
+fn get_value(env: &mut Environment, layer: id, property: Property) -> Value {
+    match property {
+        Property::Position => Value::Point(msg![env; layer position]),
+        Property::Bounds => Value::Rect(msg![env; layer bounds]),
+        Property::Opacity => Value::Float(msg![env; layer opacity]),
+        Property::BackgroundColor => Value::Color(msg![env; layer backgroundColor]),
+        Property::Transform => Value::Transform(msg![env; layer affineTransform]),
+    }
+}
+
+fn set_value(env: &mut Environment, layer: id, value: Value) {
+    match value {
+        Value::Point(position) => msg![env; layer setPosition:position],
+        Value::Rect(bounds) => msg![env; layer setBounds:bounds],
+        Value::Float(opacity) => msg![env; layer setOpacity:opacity],
+        Value::Color(color) => msg![env; layer setBackgroundColor:color],
+        Value::Transform(transform) => msg![env; layer setAffineTransform:transform],
+    }
+}
+
+fn retain_value(env: &mut Environment, value: Value) {
+    if let Value::Color(color) = value {
+        retain(env, color);
+    }
+}
+fn release_value(env: &mut Environment, value: Value) {
+    if let Value::Color(color) = value {
+        release(env, color);
+    }
+}
+
+fn lerp(from: CGFloat, to: CGFloat, t: CGFloat) -> CGFloat {
+    from + (to - from) * t
+}
+
+/// Set the layer property to a value between `from` and `to`.
+fn apply_interpolated(env: &mut Environment, layer: id, from: Value, to: Value, t: CGFloat) {
+    if t >= 1.0 {
+        set_value(env, layer, to);
+        return;
+    }
+    match (from, to) {
+        (Value::Point(from), Value::Point(to)) => {
+            let position = CGPoint {
+                x: lerp(from.x, to.x, t),
+                y: lerp(from.y, to.y, t),
+            };
+            set_value(env, layer, Value::Point(position));
+        }
+        (Value::Rect(from), Value::Rect(to)) => {
+            let bounds = CGRect {
+                origin: CGPoint {
+                    x: lerp(from.origin.x, to.origin.x, t),
+                    y: lerp(from.origin.y, to.origin.y, t),
+                },
+                size: CGSize {
+                    width: lerp(from.size.width, to.size.width, t),
+                    height: lerp(from.size.height, to.size.height, t),
+                },
+            };
+            set_value(env, layer, Value::Rect(bounds));
+        }
+        (Value::Float(from), Value::Float(to)) => {
+            set_value(env, layer, Value::Float(lerp(from, to, t)));
+        }
+        (Value::Color(from), Value::Color(to))
+            if [from, to].iter().any(|&color| {
+                color != nil && ui_color::get_pattern(&env.objc, color).is_some()
+            }) =>
+        {
+            // Patterns can't be interpolated, so the change happens at the end.
+        }
+        (Value::Color(from), Value::Color(to)) => {
+            // A nil color is transparent. Fading from or to it should only
+            // change the alpha.
+            let to_rgba = if to == nil {
+                None
+            } else {
+                Some(ui_color::get_rgba(&env.objc, to))
+            };
+            let from_rgba = if from == nil {
+                None
+            } else {
+                Some(ui_color::get_rgba(&env.objc, from))
+            };
+            let (from_rgba, to_rgba) = match (from_rgba, to_rgba) {
+                (Some(from), Some(to)) => (from, to),
+                (Some((r, g, b, a)), None) => ((r, g, b, a), (r, g, b, 0.0)),
+                (None, Some((r, g, b, a))) => ((r, g, b, 0.0), (r, g, b, a)),
+                (None, None) => return,
+            };
+            let r = lerp(from_rgba.0, to_rgba.0, t);
+            let g = lerp(from_rgba.1, to_rgba.1, t);
+            let b = lerp(from_rgba.2, to_rgba.2, t);
+            let a = lerp(from_rgba.3, to_rgba.3, t);
+            let color: id = msg_class![env; UIColor alloc];
+            let color: id = msg![env; color initWithRed:r green:g blue:b alpha:a];
+            set_value(env, layer, Value::Color(color));
+            release(env, color);
+        }
+        (Value::Transform(from), Value::Transform(to)) => {
+            // Interpolating the matrix components isn't right for rotations,
+            // but the compositor can't draw those properly anyway.
+            let transform = CGAffineTransform {
+                a: lerp(from.a, to.a, t),
+                b: lerp(from.b, to.b, t),
+                c: lerp(from.c, to.c, t),
+                d: lerp(from.d, to.d, t),
+                tx: lerp(from.tx, to.tx, t),
+                ty: lerp(from.ty, to.ty, t),
+            };
+            set_value(env, layer, Value::Transform(transform));
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn apply_curve(curve: UIViewAnimationCurve, t: f64) -> f64 {
+    match curve {
+        UIViewAnimationCurveEaseIn => t * t,
+        UIViewAnimationCurveEaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+        UIViewAnimationCurveLinear => t,
+        // UIViewAnimationCurveEaseInOut, also used for unknown values
+        _ => t * t * (3.0 - 2.0 * t),
+    }
+}
+
+/// Get the curve from a [UIViewAnimationOptions] value.
+pub(super) fn curve_from_options(options: UIViewAnimationOptions) -> UIViewAnimationCurve {
+    ((options & UIViewAnimationOptionCurveMask) >> 16) as UIViewAnimationCurve
+}
+
+/// Implementation of `beginAnimations:context:`.
+pub(super) fn begin(env: &mut Environment, animation_id: id, context: MutVoidPtr) {
+    retain(env, animation_id);
+    env.framework_state
+        .uikit
+        .ui_view
+        .animation
+        .pending
+        .push(Animation {
+            animation_id,
+            context,
+            duration: DEFAULT_DURATION,
+            delay: 0.0,
+            curve: UIViewAnimationCurveEaseInOut,
+            delegate: nil,
+            will_start_selector: None,
+            did_stop_selector: None,
+            completion: nil,
+            changes: Vec::new(),
+            start: None,
+            started: false,
+            interrupted: false,
+        });
+}
+
+/// Get the innermost animation that has been begun but not committed, if any.
+/// The `setAnimation...:` methods configure this one.
+pub(super) fn pending_mut(env: &mut Environment) -> Option<&mut Animation> {
+    env.framework_state
+        .uikit
+        .ui_view
+        .animation
+        .pending
+        .last_mut()
+}
+
+/// Set the completion block of the innermost pending animation. The block is
+/// copied.
+pub(super) fn set_completion(env: &mut Environment, completion: id) {
+    if completion == nil {
+        return;
+    }
+    let completion = copy_block(&mut env.mem, completion);
+    pending_mut(env).unwrap().completion = completion;
+}
+
+/// Implementation of `commitAnimations`.
+pub(super) fn commit(env: &mut Environment) {
+    let state = &mut env.framework_state.uikit.ui_view.animation;
+    let Some(mut animation) = state.pending.pop() else {
+        log!("Warning: commitAnimations called without beginAnimations:context:");
+        return;
+    };
+    // With nothing to animate, the animation finishes straight away.
+    if animation.changes.is_empty() {
+        animation.duration = 0.0;
+        animation.delay = 0.0;
+    }
+    let delay = Duration::from_secs_f64(animation.delay.max(0.0));
+    animation.start = Some(Instant::now() + delay);
+    state.running.push(animation);
+}
+
+pub(super) fn set_animations_enabled(env: &mut Environment, enabled: bool) {
+    env.framework_state
+        .uikit
+        .ui_view
+        .animation
+        .animations_disabled = !enabled;
+}
+pub(super) fn animations_enabled(env: &Environment) -> bool {
+    !env.framework_state
+        .uikit
+        .ui_view
+        .animation
+        .animations_disabled
+}
+
+/// Run some code with animations disabled, like iOS 7's
+/// `performWithoutAnimation:`.
+pub(super) fn perform_without_animation(env: &mut Environment, f: impl FnOnce(&mut Environment)) {
+    let was_disabled = env
+        .framework_state
+        .uikit
+        .ui_view
+        .animation
+        .animations_disabled;
+    env.framework_state
+        .uikit
+        .ui_view
+        .animation
+        .animations_disabled = true;
+    f(env);
+    env.framework_state
+        .uikit
+        .ui_view
+        .animation
+        .animations_disabled = was_disabled;
+}
+
+/// Stop animating a property of a view, leaving it at its current value.
+fn interrupt(env: &mut Environment, view: id, property: Property) {
+    let mut removed = Vec::new();
+    for animation in &mut env.framework_state.uikit.ui_view.animation.running {
+        let old_len = animation.changes.len();
+        let mut i = 0;
+        while i < animation.changes.len() {
+            let change = &animation.changes[i];
+            if change.view == view && change.property == property {
+                removed.push(animation.changes.remove(i));
+            } else {
+                i += 1;
+            }
+        }
+        if animation.changes.len() != old_len {
+            animation.interrupted = true;
+        }
+    }
+    for change in removed {
+        release_change(env, change);
+    }
+}
+
+fn release_change(env: &mut Environment, change: Change) {
+    let Change { view, from, to, .. } = change;
+    release_value(env, from);
+    release_value(env, to);
+    release(env, view);
+}
+
+/// Set an animatable property of a view. If an animation is pending, the
+/// change is animated when it is committed, otherwise it's immediate.
+pub(super) fn set_animatable(env: &mut Environment, view: id, property: Property, to: Value) {
+    let layer = layer_for_view(env, view);
+
+    // The new value supersedes any in-progress animation of the property.
+    interrupt(env, view, property);
+
+    let state = &env.framework_state.uikit.ui_view.animation;
+    if state.animations_disabled || state.pending.is_empty() {
+        set_value(env, layer, to);
+        return;
+    }
+
+    let from = get_value(env, layer, property);
+    retain_value(env, to);
+    let animation = pending_mut(env).unwrap();
+    if let Some(change) = animation
+        .changes
+        .iter_mut()
+        .find(|change| change.view == view && change.property == property)
+    {
+        // Only the last value set within the animation block matters.
+        let old_to = std::mem::replace(&mut change.to, to);
+        release_value(env, old_to);
+        return;
+    }
+    animation.changes.push(Change {
+        view,
+        property,
+        from,
+        to,
+    });
+    retain(env, view);
+    retain_value(env, from);
+}
+
+/// Get the value of an animatable property of a view that the app last set,
+/// which may not yet be the value of the layer's property if it's animating.
+pub(super) fn model_value(env: &mut Environment, view: id, property: Property) -> Value {
+    let state = &env.framework_state.uikit.ui_view.animation;
+    let target = state
+        .running
+        .iter()
+        .chain(state.pending.iter())
+        .flat_map(|animation| animation.changes.iter())
+        .filter(|change| change.view == view && change.property == property)
+        .last()
+        .map(|change| change.to);
+    if let Some(target) = target {
+        target
+    } else {
+        let layer = layer_for_view(env, view);
+        get_value(env, layer, property)
+    }
+}
+
+/// For use by the run loop: update running animations, and tell the app about
+/// any that started or finished.
+///
+/// Returns the next time this function must be called, if any.
+pub(crate) fn run_animations(env: &mut Environment) -> Option<Instant> {
+    let now = Instant::now();
+
+    let mut updates = Vec::new();
+    let mut newly_started = Vec::new();
+    let mut finished = Vec::new();
+
+    let running = &mut env.framework_state.uikit.ui_view.animation.running;
+    if running.is_empty() {
+        return None;
+    }
+    let mut i = 0;
+    while i < running.len() {
+        let animation = &mut running[i];
+        let start = animation.start.unwrap();
+        if now < start {
+            i += 1;
+            continue;
+        }
+        if !animation.started {
+            animation.started = true;
+            if let Some(selector) = animation.will_start_selector {
+                newly_started.push((
+                    animation.delegate,
+                    selector,
+                    animation.animation_id,
+                    animation.context,
+                ));
+            }
+        }
+
+        let t = if animation.duration <= 0.0 {
+            1.0
+        } else {
+            (now.duration_since(start).as_secs_f64() / animation.duration).min(1.0)
+        };
+        let t_curved = apply_curve(animation.curve, t) as CGFloat;
+        for change in &animation.changes {
+            updates.push((change.view, change.from, change.to, t_curved));
+        }
+
+        if t >= 1.0 {
+            finished.push(running.remove(i));
+        } else {
+            i += 1;
+        }
+    }
+
+    for (view, from, to, t) in updates {
+        let layer = layer_for_view(env, view);
+        apply_interpolated(env, layer, from, to, t);
+    }
+
+    for (delegate, selector, animation_id, context) in newly_started {
+        if delegate != nil {
+            () = msg_send(env, (delegate, selector, animation_id, context));
+        }
+    }
+
+    for animation in finished {
+        let Animation {
+            animation_id,
+            context,
+            delegate,
+            did_stop_selector,
+            completion,
+            changes,
+            interrupted,
+            ..
+        } = animation;
+
+        let finished = !interrupted;
+        if let Some(selector) = did_stop_selector.filter(|_| delegate != nil) {
+            let finished_number: id = msg_class![env; NSNumber alloc];
+            let finished_number: id = msg![env; finished_number initWithBool:finished];
+            () = msg_send(
+                env,
+                (delegate, selector, animation_id, finished_number, context),
+            );
+            release(env, finished_number);
+        }
+        if completion != nil {
+            let invoke = get_block_invoke(&env.mem, completion);
+            let _: () = invoke.call_from_host(env, (completion, finished));
+            release_block(&mut env.mem, completion);
+        }
+
+        for change in changes {
+            release_change(env, change);
+        }
+        release(env, animation_id);
+    }
+
+    if env
+        .framework_state
+        .uikit
+        .ui_view
+        .animation
+        .running
+        .is_empty()
+    {
+        None
+    } else {
+        Some(now + Duration::from_secs_f64(STEP_INTERVAL))
+    }
+}
@@ -0,0 +1,287 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UIActivityIndicatorView`.
+//!
+//! The spinner is drawn with `drawRect:` and redrawn each time it advances,
+//! which the run loop drives via [animate_activity_indicators].
+
+use crate::frameworks::core_graphics::cg_context::{
+    CGContextFillRect, CGContextRotateCTM, CGContextSetRGBFillColor, CGContextTranslateCTM,
+};
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::ns_string::get_static_str;
+use crate::frameworks::foundation::NSInteger;
+use crate::frameworks::uikit::ui_color;
+use crate::frameworks::uikit::ui_graphics::UIGraphicsGetCurrentContext;
+use crate::objc::{
+    id, impl_HostObject_with_superclass, msg, msg_super, nil, objc_classes, release, retain,
+    ClassExports, NSZonePtr,
+};
+use crate::Environment;
+use std::time::{Duration, Instant};
+
+pub type UIActivityIndicatorViewStyle = NSInteger;
+pub const UIActivityIndicatorViewStyleWhiteLarge: UIActivityIndicatorViewStyle = 0;
+pub const UIActivityIndicatorViewStyleWhite: UIActivityIndicatorViewStyle = 1;
+pub const UIActivityIndicatorViewStyleGray: UIActivityIndicatorViewStyle = 2;
+
+/// Number of spokes in the spinner. The brightest spoke moves on by one each
+/// step.
+const SPOKE_COUNT: usize = 12;
+/// How long each step of the spinner lasts, in seconds.
+const STEP_INTERVAL: f64 = 1.0 / 12.0;
+
+#[derive(Default)]
+pub struct State {
+    /// Views that are currently animating. These are weak references, views
+    /// remove themselves when they stop animating or are deallocated.
+    animating: Vec<id>,
+}
+
+pub struct UIActivityIndicatorViewHostObject {
+    superclass: super::UIViewHostObject,
+    style: UIActivityIndicatorViewStyle,
+    hides_when_stopped: bool,
+    /// `UIColor*`, or nil for the style's default color.
+    color: id,
+    /// When the current animation started, if the view is animating.
+    animation_start: Option<Instant>,
+    /// Index of the brightest spoke.
+    step: usize,
+}
+impl_HostObject_with_superclass!(UIActivityIndicatorViewHostObject);
+impl Default for UIActivityIndicatorViewHostObject {
+    fn default() -> Self {
+        UIActivityIndicatorViewHostObject {
+            superclass: Default::default(),
+            style: UIActivityIndicatorViewStyleWhite,
+            hides_when_stopped: true,
+            color: nil,
+            animation_start: None,
+            step: 0,
+        }
+    }
+}
+
+fn size_for_style(style: UIActivityIndicatorViewStyle) -> CGFloat {
+    if style == UIActivityIndicatorViewStyleWhiteLarge {
+        37.0
+    } else {
+        20.0
+    }
+}
+
+fn stop_animating(env: &mut Environment, view: id) {
+    let state = &mut env.framework_state.uikit.ui_view.ui_activity_indicator_view;
+    state.animating.retain(|&other| other != view);
+    env.objc
+        .borrow_mut::<UIActivityIndicatorViewHostObject>(view)
+        .animation_start = None;
+}
+
+/// For use by the run loop: advance the spinners of animating views.
+///
+/// Returns the next time this function must be called, if any.
+pub fn animate_activity_indicators(env: &mut Environment) -> Option<Instant> {
+    let now = Instant::now();
+    let mut next_due: Option<Instant> = None;
+
+    let views = env
+        .framework_state
+        .uikit
+        .ui_view
+        .ui_activity_indicator_view
+        .animating
+        .clone();
+    for view in views {
+        let host_obj = env
+            .objc
+            .borrow_mut::<UIActivityIndicatorViewHostObject>(view);
+        let start = host_obj.animation_start.unwrap();
+        let steps = (now.duration_since(start).as_secs_f64() / STEP_INTERVAL) as u64;
+        let step = (steps % SPOKE_COUNT as u64) as usize;
+        let due = start + Duration::from_secs_f64((steps + 1) as f64 * STEP_INTERVAL);
+        next_due = Some(next_due.map_or(due, |other| other.min(due)));
+        if step != host_obj.step {
+            host_obj.step = step;
+            () = msg![env; view setNeedsDisplay];
+        }
+    }
+
+    next_due
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UIActivityIndicatorView: UIView
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::<UIActivityIndicatorViewHostObject>::default();
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (id)initWithActivityIndicatorStyle:(UIActivityIndicatorViewStyle)style {
+    let size = size_for_style(style);
+    let frame = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: CGSize { width: size, height: size },
+    };
+    let this: id = msg![env; this initWithFrame:frame];
+    () = msg![env; this setActivityIndicatorViewStyle:style];
+    this
+}
+
+- (id)initWithFrame:(CGRect)frame {
+    let this: id = msg_super![env; this initWithFrame:frame];
+    // Built-in views don't have user-controlled opaqueness.
+    () = msg_super![env; this setOpaque:false];
+    // Not animating yet, so hidden by default.
+    () = msg![env; this setHidden:true];
+    this
+}
+
+- (id)initWithCoder:(id)coder {
+    let this: id = msg_super![env; this initWithCoder:coder];
+    () = msg_super![env; this setOpaque:false];
+
+    let key_ns_string = get_static_str(env, "UIActivityIndicatorViewStyle");
+    let style: NSInteger = msg![env; coder decodeIntegerForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UIHidesWhenStopped");
+    let hides_when_stopped: bool = msg![env; coder decodeBoolForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UIAnimating");
+    let animating: bool = msg![env; coder decodeBoolForKey:key_ns_string];
+
+    () = msg![env; this setActivityIndicatorViewStyle:style];
+    () = msg![env; this setHidesWhenStopped:hides_when_stopped];
+    if animating {
+        () = msg![env; this startAnimating];
+    }
+    this
+}
+
+- (())dealloc {
+    stop_animating(env, this);
+    let color = env.objc.borrow::<UIActivityIndicatorViewHostObject>(this).color;
+    release(env, color);
+    msg_super![env; this dealloc]
+}
+
+- (UIActivityIndicatorViewStyle)activityIndicatorViewStyle {
+    env.objc.borrow::<UIActivityIndicatorViewHostObject>(this).style
+}
+- (())setActivityIndicatorViewStyle:(UIActivityIndicatorViewStyle)style {
+    env.objc.borrow_mut::<UIActivityIndicatorViewHostObject>(this).style = style;
+    () = msg![env; this setNeedsDisplay];
+}
+
+- (id)color {
+    env.objc.borrow::<UIActivityIndicatorViewHostObject>(this).color
+}
+- (())setColor:(id)color { // UIColor*
+    retain(env, color);
+    let old_color = std::mem::replace(
+        &mut env.objc.borrow_mut::<UIActivityIndicatorViewHostObject>(this).color,
+        color,
+    );
+    release(env, old_color);
+    () = msg![env; this setNeedsDisplay];
+}
+
+- (bool)hidesWhenStopped {
+    env.objc.borrow::<UIActivityIndicatorViewHostObject>(this).hides_when_stopped
+}
+- (())setHidesWhenStopped:(bool)hides_when_stopped {
+    let host_obj = env.objc.borrow_mut::<UIActivityIndicatorViewHostObject>(this);
+    host_obj.hides_when_stopped = hides_when_stopped;
+    let animating = host_obj.animation_start.is_some();
+    if !animating {
+        () = msg![env; this setHidden:hides_when_stopped];
+    }
+}
+
+- (bool)isAnimating {
+    env.objc.borrow::<UIActivityIndicatorViewHostObject>(this).animation_start.is_some()
+}
+
+- (())startAnimating {
+    let host_obj = env.objc.borrow_mut::<UIActivityIndicatorViewHostObject>(this);
+    if host_obj.animation_start.is_some() {
+        return;
+    }
+    host_obj.animation_start = Some(Instant::now());
+    host_obj.step = 0;
+    env.framework_state.uikit.ui_view.ui_activity_indicator_view.animating.push(this);
+    () = msg![env; this setHidden:false];
+    () = msg![env; this setNeedsDisplay];
+}
+- (())stopAnimating {
+    if env.objc.borrow::<UIActivityIndicatorViewHostObject>(this).animation_start.is_none() {
+        return;
+    }
+    stop_animating(env, this);
+    env.objc.borrow_mut::<UIActivityIndicatorViewHostObject>(this).step = 0;
+    if env.objc.borrow::<UIActivityIndicatorViewHostObject>(this).hides_when_stopped {
+        () = msg![env; this setHidden:true];
+    }
+    () = msg![env; this setNeedsDisplay];
+}
+
+- (())setOpaque:(bool)_opaque {
+    // Built-in views don't have user-controlled opaqueness.
+}
+
+- (())drawRect:(CGRect)_rect {
+    let bounds: CGRect = msg![env; this bounds];
+    let context = UIGraphicsGetCurrentContext(env);
+
+    let &UIActivityIndicatorViewHostObject {
+        style,
+        color,
+        step,
+        ..
+    } = env.objc.borrow(this);
+
+    let (r, g, b, a) = if color != nil {
+        ui_color::get_rgba(&env.objc, color)
+    } else if style == UIActivityIndicatorViewStyleGray {
+        (0.5, 0.5, 0.5, 1.0)
+    } else {
+        (1.0, 1.0, 1.0, 1.0)
+    };
+
+    // The spinner keeps the style's size and is centered in the view.
+    let size = size_for_style(style);
+    let outer_radius = size / 2.0;
+    let inner_radius = size / 4.0;
+    let spoke_width = size / 11.0;
+    let spoke = CGRect {
+        origin: CGPoint { x: -spoke_width / 2.0, y: -outer_radius },
+        size: CGSize { width: spoke_width, height: outer_radius - inner_radius },
+    };
+    let center_x = bounds.origin.x + bounds.size.width / 2.0;
+    let center_y = bounds.origin.y + bounds.size.height / 2.0;
+
+    let angle_per_spoke = std::f32::consts::TAU / SPOKE_COUNT as CGFloat;
+    for i in 0..SPOKE_COUNT {
+        // The spokes behind the brightest one fade out.
+        let age = (step + SPOKE_COUNT - i) % SPOKE_COUNT;
+        let fade = 1.0 - 0.8 * (age as CGFloat / (SPOKE_COUNT - 1) as CGFloat);
+        CGContextSetRGBFillColor(env, context, r, g, b, a * fade);
+
+        let angle = angle_per_spoke * i as CGFloat;
+        CGContextTranslateCTM(env, context, center_x, center_y);
+        CGContextRotateCTM(env, context, angle);
+        CGContextFillRect(env, context, spoke);
+        CGContextRotateCTM(env, context, -angle);
+        CGContextTranslateCTM(env, context, -center_x, -center_y);
+    }
+}
+
+@end
+
+};
@@ -4,32 +4,448 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 //! `UIAlertView`.
+//!
+//! The alert is shown in its own window covering the whole screen, so that it
+//! gets all the touches while it's visible.
 
-use crate::frameworks::foundation::ns_string;
-use crate::objc::{id, msg_super, objc_classes, ClassExports};
+use super::ui_control::ui_button::UIButtonTypeRoundedRect;
+use super::ui_control::{UIControlEventTouchUpInside, UIControlStateNormal};
+use super::UIViewHostObject;
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::NSInteger;
+use crate::frameworks::uikit::ui_font::{UILineBreakModeWordWrap, UITextAlignmentCenter};
+use crate::objc::{
+    id, impl_HostObject_with_superclass, msg, msg_class, msg_super, nil, objc_classes, release,
+    retain, ClassExports, NSZonePtr,
+};
+use crate::Environment;
+
+#[derive(Default)]
+pub struct State {
+    /// Alerts currently being shown, in the order they were shown. These are
+    /// weak references, but the alerts retain themselves while visible.
+    visible_alerts: Vec<id>,
+}
+
+const ALERT_WIDTH: CGFloat = 284.0;
+const PADDING: CGFloat = 16.0;
+const BUTTON_HEIGHT: CGFloat = 44.0;
+const BUTTON_GAP: CGFloat = 8.0;
+const TITLE_FONT_SIZE: CGFloat = 18.0;
+const MESSAGE_FONT_SIZE: CGFloat = 16.0;
+
+#[derive(Default)]
+struct UIAlertViewHostObject {
+    superclass: UIViewHostObject,
+    /// Weak reference.
+    delegate: id,
+    /// `NSString*`, strong reference.
+    title: id,
+    /// `NSString*`, strong reference.
+    message: id,
+    /// `NSString*`s in button index order, strong references.
+    button_titles: Vec<id>,
+    cancel_button_index: Option<usize>,
+    /// Strong reference. Only set while the alert is visible.
+    window: id,
+    /// `UIButton*`s in button index order. Weak references (the alert view
+    /// owns them). Only set while the alert is visible.
+    buttons: Vec<id>,
+}
+impl_HostObject_with_superclass!(UIAlertViewHostObject);
+
+fn add_label(
+    env: &mut Environment,
+    alert: id,
+    text: id,
+    font: id,
+    y: CGFloat,
+    width: CGFloat,
+) -> CGFloat {
+    let size: CGSize = msg![env; text sizeWithFont:font
+                                 constrainedToSize:(CGSize {
+                                     width,
+                                     height: 1000.0,
+                                 })
+                                     lineBreakMode:UILineBreakModeWordWrap];
+    let frame = CGRect {
+        origin: CGPoint { x: PADDING, y },
+        size: CGSize {
+            width,
+            height: size.height,
+        },
+    };
+    let label: id = msg_class![env; UILabel alloc];
+    let label: id = msg![env; label initWithFrame:frame];
+    () = msg![env; label setText:text];
+    () = msg![env; label setFont:font];
+    () = msg![env; label setTextAlignment:UITextAlignmentCenter];
+    () = msg![env; label setNumberOfLines:0]; // unlimited
+    let text_color: id = msg_class![env; UIColor whiteColor];
+    () = msg![env; label setTextColor:text_color];
+    let bg_color: id = msg_class![env; UIColor clearColor];
+    () = msg![env; label setBackgroundColor:bg_color];
+    () = msg![env; alert addSubview:label];
+    release(env, label);
+    size.height
+}
+
+/// Lay out the alert's contents and return its height.
+fn lay_out(env: &mut Environment, alert: id) -> CGFloat {
+    let host_obj = env.objc.borrow::<UIAlertViewHostObject>(alert);
+    let title = host_obj.title;
+    let message = host_obj.message;
+    let button_titles = host_obj.button_titles.clone();
+    let cancel_button_index = host_obj.cancel_button_index;
+
+    let content_width = ALERT_WIDTH - PADDING * 2.0;
+    let mut y = PADDING;
+    if title != nil {
+        let font: id = msg_class![env; UIFont boldSystemFontOfSize:TITLE_FONT_SIZE];
+        y += add_label(env, alert, title, font, y, content_width) + BUTTON_GAP;
+    }
+    if message != nil {
+        let font: id = msg_class![env; UIFont systemFontOfSize:MESSAGE_FONT_SIZE];
+        y += add_label(env, alert, message, font, y, content_width) + BUTTON_GAP;
+    }
+    y += BUTTON_GAP;
+
+    // Like on iPhone OS, two buttons go side-by-side with the cancel button on
+    // the left, otherwise they are stacked with the cancel button at the
+    // bottom.
+    let mut order: Vec<usize> = (0..button_titles.len())
+        .filter(|&i| Some(i) != cancel_button_index)
+        .collect();
+    let side_by_side = button_titles.len() == 2;
+    if let Some(cancel_button_index) = cancel_button_index {
+        if side_by_side {
+            order.insert(0, cancel_button_index);
+        } else {
+            order.push(cancel_button_index);
+        }
+    }
+    let button_width = if side_by_side {
+        (content_width - BUTTON_GAP) / 2.0
+    } else {
+        content_width
+    };
+
+    let action = env.objc.lookup_selector("_touchHLE_buttonTapped:").unwrap();
+    let mut buttons = vec![nil; button_titles.len()];
+    for (position, &button_idx) in order.iter().enumerate() {
+        let origin = if side_by_side {
+            CGPoint {
+                x: PADDING + (position as CGFloat) * (button_width + BUTTON_GAP),
+                y,
+            }
+        } else {
+            CGPoint {
+                x: PADDING,
+                y: y + (position as CGFloat) * (BUTTON_HEIGHT + BUTTON_GAP),
+            }
+        };
+        let frame = CGRect {
+            origin,
+            size: CGSize {
+                width: button_width,
+                height: BUTTON_HEIGHT,
+            },
+        };
+        let button: id = msg_class![env; UIButton buttonWithType:UIButtonTypeRoundedRect];
+        () = msg![env; button setFrame:frame];
+        let title = button_titles[button_idx];
+        () = msg![env; button setTitle:title forState:UIControlStateNormal];
+        // FIXME: manually calling layoutSubviews shouldn't be needed?
+        () = msg![env; button layoutSubviews];
+        () = msg![env; button addTarget:alert
+                                 action:action
+                       forControlEvents:UIControlEventTouchUpInside];
+        () = msg![env; alert addSubview:button];
+        buttons[button_idx] = button;
+    }
+    let rows = if side_by_side { 1 } else { order.len() };
+    if rows > 0 {
+        y += (rows as CGFloat) * (BUTTON_HEIGHT + BUTTON_GAP) - BUTTON_GAP;
+    }
+    y += PADDING;
+
+    env.objc.borrow_mut::<UIAlertViewHostObject>(alert).buttons = buttons;
+
+    y
+}
+
+/// For use by the run loop: the user pressed Escape or the Back button, which
+/// is treated like tapping the cancel button of the most recent alert.
+pub fn handle_escape(env: &mut Environment) {
+    let Some(&alert) = env
+        .framework_state
+        .uikit
+        .ui_view
+        .ui_alert_view
+        .visible_alerts
+        .last()
+    else {
+        return;
+    };
+    let cancel_button_index: NSInteger = msg![env; alert cancelButtonIndex];
+    if cancel_button_index == -1 {
+        log!("Alert {:?} has no cancel button, ignoring Escape", alert);
+        return;
+    }
+    log!(
+        "Escape pressed, cancelling alert {:?} (button {})",
+        alert,
+        cancel_button_index
+    );
+    clicked_button(env, alert, cancel_button_index);
+}
+
+fn clicked_button(env: &mut Environment, alert: id, button_idx: NSInteger) {
+    let delegate = env.objc.borrow::<UIAlertViewHostObject>(alert).delegate;
+    if delegate != nil
+        && env
+            .objc
+            .object_has_method_named(&env.mem, delegate, "alertView:clickedButtonAtIndex:")
+    {
+        () = msg![env; delegate alertView:alert clickedButtonAtIndex:button_idx];
+    }
+    () = msg![env; alert dismissWithClickedButtonIndex:button_idx animated:true];
+}
 
 pub const CLASSES: ClassExports = objc_classes! {
 
 (env, this, _cmd);
 
 @implementation UIAlertView: UIView
-- (id)initWithTitle:(id)title
-                      message:(id)message
-                     delegate:(id)delegate
-            cancelButtonTitle:(id)cancelButtonTitle
-            otherButtonTitles:(id)otherButtonTitles {
 
-    log!("TODO: [(UIAlertView*){:?} initWithTitle:{:?} message:{:?} delegate:{:?} cancelButtonTitle:{:?} otherButtonTitles:{:?}]", this, title, message, delegate, cancelButtonTitle, otherButtonTitles);
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::<UIAlertViewHostObject>::default();
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (id)initWithTitle:(id)title // NSString*
+            message:(id)message // NSString*
+           delegate:(id)delegate
+  cancelButtonTitle:(id)cancel_button_title // NSString*
+  otherButtonTitles:(id)first_other_button_title, ...other_button_titles {
+    let this: id = msg![env; this initWithFrame:(<CGRect as Default>::default())];
+
+    () = msg![env; this setTitle:title];
+    () = msg![env; this setMessage:message];
+    () = msg![env; this setDelegate:delegate];
 
-    let msg = ns_string::to_rust_string(env, message);
-    let title = ns_string::to_rust_string(env, title);
-    log!("UIAlertView: title: {:?}, message: {:?}", title, msg);
+    if cancel_button_title != nil {
+        let idx: NSInteger = msg![env; this addButtonWithTitle:cancel_button_title];
+        () = msg![env; this setCancelButtonIndex:idx];
+    }
+    if first_other_button_title != nil {
+        let _: NSInteger = msg![env; this addButtonWithTitle:first_other_button_title];
+        let mut varargs = other_button_titles.start();
+        loop {
+            let next_title: id = varargs.next(env);
+            if next_title == nil {
+                break;
+            }
+            let _: NSInteger = msg![env; this addButtonWithTitle:next_title];
+        }
+    }
 
-    msg_super![env; this init]
+    log_dbg!(
+        "[(UIAlertView*){:?} initWithTitle:{:?} message:{:?} ...]",
+        this,
+        title,
+        message,
+    );
+
+    this
 }
+
+- (())dealloc {
+    let host_obj = env.objc.borrow_mut::<UIAlertViewHostObject>(this);
+    let title = host_obj.title;
+    let message = host_obj.message;
+    let button_titles = std::mem::take(&mut host_obj.button_titles);
+    let window = host_obj.window;
+
+    release(env, title);
+    release(env, message);
+    for button_title in button_titles {
+        release(env, button_title);
+    }
+    release(env, window);
+
+    msg_super![env; this dealloc]
+}
+
+- (id)delegate {
+    env.objc.borrow::<UIAlertViewHostObject>(this).delegate
+}
+- (())setDelegate:(id)delegate {
+    // Weak reference
+    env.objc.borrow_mut::<UIAlertViewHostObject>(this).delegate = delegate;
+}
+
+- (id)title {
+    env.objc.borrow::<UIAlertViewHostObject>(this).title
+}
+- (())setTitle:(id)title { // NSString*
+    let title: id = msg![env; title copy];
+    let host_obj = env.objc.borrow_mut::<UIAlertViewHostObject>(this);
+    let old_title = std::mem::replace(&mut host_obj.title, title);
+    release(env, old_title);
+}
+
+- (id)message {
+    env.objc.borrow::<UIAlertViewHostObject>(this).message
+}
+- (())setMessage:(id)message { // NSString*
+    let message: id = msg![env; message copy];
+    let host_obj = env.objc.borrow_mut::<UIAlertViewHostObject>(this);
+    let old_message = std::mem::replace(&mut host_obj.message, message);
+    release(env, old_message);
+}
+
+- (NSInteger)addButtonWithTitle:(id)title { // NSString*
+    let title: id = msg![env; title copy];
+    let button_titles = &mut env.objc.borrow_mut::<UIAlertViewHostObject>(this).button_titles;
+    button_titles.push(title);
+    (button_titles.len() - 1).try_into().unwrap()
+}
+
+- (NSInteger)numberOfButtons {
+    let button_titles = &env.objc.borrow::<UIAlertViewHostObject>(this).button_titles;
+    button_titles.len().try_into().unwrap()
+}
+
+- (id)buttonTitleAtIndex:(NSInteger)button_idx {
+    let button_titles = &env.objc.borrow::<UIAlertViewHostObject>(this).button_titles;
+    // TODO: raise NSRangeException
+    button_titles[usize::try_from(button_idx).unwrap()]
+}
+
+- (NSInteger)cancelButtonIndex {
+    let cancel_button_index = env.objc.borrow::<UIAlertViewHostObject>(this).cancel_button_index;
+    cancel_button_index.map_or(-1, |idx| idx.try_into().unwrap())
+}
+- (())setCancelButtonIndex:(NSInteger)button_idx {
+    env.objc.borrow_mut::<UIAlertViewHostObject>(this).cancel_button_index =
+        usize::try_from(button_idx).ok();
+}
+
+- (NSInteger)firstOtherButtonIndex {
+    let host_obj = env.objc.borrow::<UIAlertViewHostObject>(this);
+    (0..host_obj.button_titles.len())
+        .find(|&i| Some(i) != host_obj.cancel_button_index)
+        .map_or(-1, |idx| idx.try_into().unwrap())
+}
+
+- (bool)isVisible {
+    env.objc.borrow::<UIAlertViewHostObject>(this).window != nil
+}
+
 - (())show {
-    log!("TODO: [(UIAlertView*){:?} show]", this);
+    if msg![env; this isVisible] {
+        return;
+    }
+
+    // The alert keeps itself alive while it's visible.
+    retain(env, this);
+
+    let height = lay_out(env, this);
+
+    let screen: id = msg_class![env; UIScreen mainScreen];
+    let screen_bounds: CGRect = msg![env; screen bounds];
+    let window: id = msg_class![env; UIWindow alloc];
+    let window: id = msg![env; window initWithFrame:screen_bounds];
+    let dim_color: id = msg_class![env; UIColor colorWithWhite:(0.0 as CGFloat)
+                                                         alpha:(0.5 as CGFloat)];
+    () = msg![env; window setBackgroundColor:dim_color];
+
+    let frame = CGRect {
+        origin: CGPoint {
+            x: (screen_bounds.size.width - ALERT_WIDTH) / 2.0,
+            y: (screen_bounds.size.height - height) / 2.0,
+        },
+        size: CGSize {
+            width: ALERT_WIDTH,
+            height,
+        },
+    };
+    () = msg![env; this setFrame:frame];
+    let bg_color: id = msg_class![env; UIColor colorWithRed:(0.1 as CGFloat)
+                                                      green:(0.15 as CGFloat)
+                                                       blue:(0.35 as CGFloat)
+                                                      alpha:(0.9 as CGFloat)];
+    () = msg![env; this setBackgroundColor:bg_color];
+    () = msg![env; window addSubview:this];
+    () = msg![env; window makeKeyAndVisible];
+
+    env.objc.borrow_mut::<UIAlertViewHostObject>(this).window = window;
+    env.framework_state.uikit.ui_view.ui_alert_view.visible_alerts.push(this);
+
+    let delegate = env.objc.borrow::<UIAlertViewHostObject>(this).delegate;
+    if delegate != nil
+        && env.objc.object_has_method_named(&env.mem, delegate, "didPresentAlertView:")
+    {
+        () = msg![env; delegate didPresentAlertView:this];
+    }
+}
+
+- (())dismissWithClickedButtonIndex:(NSInteger)button_idx
+                           animated:(bool)_animated {
+    if !msg![env; this isVisible] {
+        return;
+    }
+
+    let delegate = env.objc.borrow::<UIAlertViewHostObject>(this).delegate;
+    if delegate != nil
+        && env.objc.object_has_method_named(
+            &env.mem,
+            delegate,
+            "alertView:willDismissWithButtonIndex:",
+        )
+    {
+        () = msg![env; delegate alertView:this willDismissWithButtonIndex:button_idx];
+    }
+
+    let host_obj = env.objc.borrow_mut::<UIAlertViewHostObject>(this);
+    let window = std::mem::take(&mut host_obj.window);
+    host_obj.buttons.clear();
+    let visible_alerts = &mut env.framework_state.uikit.ui_view.ui_alert_view.visible_alerts;
+    visible_alerts.retain(|&alert| alert != this);
+
+    // Remove the buttons and labels so they can be recreated if the alert is
+    // shown again.
+    let subviews = env.objc.borrow::<UIViewHostObject>(this).subviews.clone();
+    for subview in subviews {
+        () = msg![env; subview removeFromSuperview];
+    }
+
+    () = msg![env; this removeFromSuperview];
+    () = msg![env; window setHidden:true];
+    release(env, window);
+
+    if delegate != nil
+        && env.objc.object_has_method_named(
+            &env.mem,
+            delegate,
+            "alertView:didDismissWithButtonIndex:",
+        )
+    {
+        () = msg![env; delegate alertView:this didDismissWithButtonIndex:button_idx];
+    }
+
+    release(env, this);
+}
+
+- (())_touchHLE_buttonTapped:(id)sender { // UIButton*
+    let buttons = &env.objc.borrow::<UIAlertViewHostObject>(this).buttons;
+    let Some(button_idx) = buttons.iter().position(|&button| button == sender) else {
+        return;
+    };
+    clicked_button(env, this, button_idx.try_into().unwrap());
 }
+
 @end
 
 };
@@ -28,6 +28,11 @@ const UIControlEventTouchDragEnter: UIControlEvents = 1 << 4;
 const UIControlEventTouchDragExit: UIControlEvents = 1 << 5;
 pub const UIControlEventTouchUpInside: UIControlEvents = 1 << 6;
 const UIControlEventTouchUpOutside: UIControlEvents = 1 << 7;
+const UIControlEventTouchCancel: UIControlEvents = 1 << 8;
+const UIControlEventEditingDidBegin: UIControlEvents = 1 << 16;
+const UIControlEventEditingChanged: UIControlEvents = 1 << 17;
+const UIControlEventEditingDidEnd: UIControlEvents = 1 << 18;
+const UIControlEventEditingDidEndOnExit: UIControlEvents = 1 << 19;
 
 struct UIControlHostObject {
     superclass: super::UIViewHostObject,
@@ -177,6 +182,10 @@ pub const CLASSES: ClassExports = objc_classes! {
     // tracking property? why here?)
     env.objc.borrow_mut::<UIControlHostObject>(this).tracking = false;
 }
+- (())cancelTrackingWithEvent:(id)_event { // UIEvent*
+    // default implementation, subclasses can override this
+    env.objc.borrow_mut::<UIControlHostObject>(this).tracking = false;
+}
 
 - (())touchesBegan:(id)touches // NSSet* of UITouch*
          withEvent:(id)event { // UIEvent*
@@ -257,6 +266,20 @@ pub const CLASSES: ClassExports = objc_classes! {
         false => UIControlEventTouchUpOutside,
     });
 }
+- (())touchesCancelled:(id)touches // NSSet* of UITouch*
+             withEvent:(id)event { // UIEvent*
+    let touch: id = msg![env; touches anyObject];
+    let tracked_touch = env.objc.borrow::<UIControlHostObject>(this).tracked_touch;
+    if tracked_touch != touch {
+        return;
+    }
+    () = msg![env; this cancelTrackingWithEvent:event];
+    release(env, tracked_touch);
+    env.objc.borrow_mut::<UIControlHostObject>(this).tracked_touch = nil;
+    () = msg![env; this setHighlighted:false];
+
+    send_actions(env, this, event, UIControlEventTouchCancel);
+}
 
 - (())addTarget:(id)target
          action:(SEL)action
@@ -4,44 +4,460 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
 //! `UITextField`.
+//!
+//! Text is typed with the host keyboard (or the on-screen keyboard of the host
+//! OS, where there is one) while the text field is the first responder. There
+//! is no cursor, text is always inserted or deleted at the end.
 
-use crate::frameworks::foundation::{ns_string, NSInteger};
-use crate::objc::{id, objc_classes, ClassExports};
+use super::{
+    send_actions, UIControlEventEditingChanged, UIControlEventEditingDidBegin,
+    UIControlEventEditingDidEnd, UIControlEventEditingDidEndOnExit, UIControlHostObject,
+};
+use crate::frameworks::core_graphics::{CGFloat, CGRect, CGSize};
+use crate::frameworks::foundation::{ns_string, NSInteger, NSRange, NSUInteger};
+use crate::frameworks::uikit::ui_font::UITextAlignment;
+use crate::objc::{
+    id, impl_HostObject_with_superclass, msg, msg_class, msg_super, nil, objc_classes, release,
+    ClassExports, NSZonePtr,
+};
+use crate::window::TextInputEvent;
+use crate::Environment;
 
 type UIKeyboardAppearance = NSInteger;
 type UIKeyboardType = NSInteger;
+const UIKeyboardTypeDefault: UIKeyboardType = 0;
+const UIKeyboardTypeNumberPad: UIKeyboardType = 4;
+const UIKeyboardTypePhonePad: UIKeyboardType = 5;
+const UIKeyboardTypeDecimalPad: UIKeyboardType = 8;
 type UIReturnKeyType = NSInteger;
 type UITextAutocapitalizationType = NSInteger;
 type UITextAutocorrectionType = NSInteger;
 
+type UITextBorderStyle = NSInteger;
+const UITextBorderStyleNone: UITextBorderStyle = 0;
+
+/// Horizontal space between the edge of the text field and its text.
+const TEXT_INSET: CGFloat = 4.0;
+
+struct UITextFieldHostObject {
+    superclass: UIControlHostObject,
+    /// `NSString*`, strong reference. Never [nil].
+    text: id,
+    /// `NSString*`, strong reference.
+    placeholder: id,
+    /// `UILabel*` used to draw the text or placeholder, strong reference.
+    label: id,
+    /// `UIColor*`, strong reference.
+    text_color: id,
+    /// Weak reference.
+    delegate: id,
+    keyboard_type: UIKeyboardType,
+    border_style: UITextBorderStyle,
+    clears_on_begin_editing: bool,
+    secure_text_entry: bool,
+    editing: bool,
+}
+impl_HostObject_with_superclass!(UITextFieldHostObject);
+impl Default for UITextFieldHostObject {
+    fn default() -> Self {
+        UITextFieldHostObject {
+            superclass: Default::default(),
+            text: nil,
+            placeholder: nil,
+            label: nil,
+            text_color: nil,
+            delegate: nil,
+            keyboard_type: UIKeyboardTypeDefault,
+            border_style: UITextBorderStyleNone,
+            clears_on_begin_editing: false,
+            secure_text_entry: false,
+            editing: false,
+        }
+    }
+}
+
+/// Shared part of `initWithFrame:` and `initWithCoder:`.
+fn init_common(env: &mut Environment, this: id) {
+    let empty = ns_string::get_static_str(env, "");
+    let label: id = msg_class![env; UILabel new];
+    let bg_color: id = msg_class![env; UIColor clearColor];
+    () = msg![env; label setBackgroundColor:bg_color];
+    let text_color: id = msg_class![env; UIColor blackColor];
+    let text_color: id = msg![env; text_color retain];
+    () = msg![env; this addSubview:label];
+
+    let host_obj = env.objc.borrow_mut::<UITextFieldHostObject>(this);
+    host_obj.text = empty;
+    host_obj.label = label;
+    host_obj.text_color = text_color;
+
+    update_label(env, this);
+}
+
+/// Update the label to show the current text, or the placeholder if there's
+/// no text.
+fn update_label(env: &mut Environment, this: id) {
+    let &UITextFieldHostObject {
+        text,
+        placeholder,
+        label,
+        text_color,
+        secure_text_entry,
+        ..
+    } = env.objc.borrow(this);
+
+    let text_len: NSUInteger = msg![env; text length];
+    if text_len == 0 && placeholder != nil {
+        () = msg![env; label setText:placeholder];
+        // The real placeholder color is a 70% gray.
+        let placeholder_color: id = msg_class![env; UIColor lightGrayColor];
+        () = msg![env; label setTextColor:placeholder_color];
+    } else if secure_text_entry {
+        let bullets = "\u{2022}".repeat(text_len as usize);
+        let bullets = ns_string::from_rust_string(env, bullets);
+        () = msg![env; label setText:bullets];
+        release(env, bullets);
+        () = msg![env; label setTextColor:text_color];
+    } else {
+        () = msg![env; label setText:text];
+        () = msg![env; label setTextColor:text_color];
+    }
+}
+
+fn delegate_responds_to(env: &mut Environment, this: id, selector: &str) -> Option<id> {
+    let delegate = env.objc.borrow::<UITextFieldHostObject>(this).delegate;
+    if delegate != nil
+        && env
+            .objc
+            .object_has_method_named(&env.mem, delegate, selector)
+    {
+        Some(delegate)
+    } else {
+        None
+    }
+}
+
+/// Can this character be typed with this type of keyboard?
+fn is_allowed_for_keyboard_type(c: char, keyboard_type: UIKeyboardType) -> bool {
+    match keyboard_type {
+        UIKeyboardTypeNumberPad => c.is_ascii_digit(),
+        UIKeyboardTypePhonePad => c.is_ascii_digit() || "+*#".contains(c),
+        UIKeyboardTypeDecimalPad => c.is_ascii_digit() || c == '.',
+        _ => !c.is_control(),
+    }
+}
+
+/// Replace the text in `range` (UTF-16 code units, like `NSString`) with
+/// `replacement`, if the delegate allows it.
+fn replace_characters(env: &mut Environment, this: id, range: NSRange, replacement: &str) {
+    let replacement_ns = ns_string::from_rust_string(env, replacement.to_string());
+    if let Some(delegate) = delegate_responds_to(
+        env,
+        this,
+        "textField:shouldChangeCharactersInRange:replacementString:",
+    ) {
+        let range_copy = NSRange {
+            location: range.location,
+            length: range.length,
+        };
+        let allowed: bool = msg![env; delegate textField:this
+                                shouldChangeCharactersInRange:range_copy
+                                            replacementString:replacement_ns];
+        if !allowed {
+            release(env, replacement_ns);
+            return;
+        }
+    }
+
+    release(env, replacement_ns);
+
+    let text = env.objc.borrow::<UITextFieldHostObject>(this).text;
+    let text = ns_string::to_rust_string(env, text);
+    let mut text_utf16: Vec<u16> = text.encode_utf16().collect();
+    let start = range.location as usize;
+    let end = start + range.length as usize;
+    text_utf16.splice(start..end, replacement.encode_utf16());
+    let new_text = String::from_utf16_lossy(&text_utf16);
+    let new_text = ns_string::from_rust_string(env, new_text);
+    () = msg![env; this setText:new_text];
+    release(env, new_text);
+
+    send_actions(env, this, nil, UIControlEventEditingChanged);
+}
+
+/// For use by the run loop: handle text typed by the user.
+pub fn handle_text_input(env: &mut Environment, event: TextInputEvent) {
+    let first_responder = env.framework_state.uikit.ui_responder.first_responder;
+    let text_field_class = env.objc.get_known_class("UITextField", &mut env.mem);
+    if first_responder == nil || !msg![env; first_responder isKindOfClass:text_field_class] {
+        log_dbg!(
+            "Ignoring text input {:?}, no text field is being edited",
+            event
+        );
+        return;
+    }
+    let this = first_responder;
+
+    let text = env.objc.borrow::<UITextFieldHostObject>(this).text;
+    let text = ns_string::to_rust_string(env, text);
+    let text_len: NSUInteger = text.encode_utf16().count().try_into().unwrap();
+
+    match event {
+        TextInputEvent::Text(new_text) => {
+            let keyboard_type = env.objc.borrow::<UITextFieldHostObject>(this).keyboard_type;
+            let new_text: String = new_text
+                .chars()
+                .filter(|&c| is_allowed_for_keyboard_type(c, keyboard_type))
+                .collect();
+            if new_text.is_empty() {
+                return;
+            }
+            let range = NSRange {
+                location: text_len,
+                length: 0,
+            };
+            replace_characters(env, this, range, &new_text);
+        }
+        TextInputEvent::Backspace => {
+            let Some(last_char) = text.chars().last() else {
+                return;
+            };
+            let last_char_len: NSUInteger = last_char.len_utf16().try_into().unwrap();
+            let range = NSRange {
+                location: text_len - last_char_len,
+                length: last_char_len,
+            };
+            replace_characters(env, this, range, "");
+        }
+        TextInputEvent::Return => {
+            let should_return =
+                if let Some(delegate) = delegate_responds_to(env, this, "textFieldShouldReturn:") {
+                    msg![env; delegate textFieldShouldReturn:this]
+                } else {
+                    true
+                };
+            if should_return {
+                send_actions(env, this, nil, UIControlEventEditingDidEndOnExit);
+            }
+        }
+    }
+}
+
 pub const CLASSES: ClassExports = objc_classes! {
 
 (env, this, _cmd);
 
 @implementation UITextField: UIControl
 
-// TODO: rendering
-// TODO: more properties
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::<UITextFieldHostObject>::default();
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (id)initWithFrame:(CGRect)frame {
+    let this: id = msg_super![env; this initWithFrame:frame];
+    init_common(env, this);
+    this
+}
+
+- (id)initWithCoder:(id)coder {
+    let this: id = msg_super![env; this initWithCoder:coder];
+    init_common(env, this);
+    this
+}
+
+- (())dealloc {
+    let &UITextFieldHostObject {
+        text,
+        placeholder,
+        label,
+        text_color,
+        ..
+    } = env.objc.borrow(this);
+    release(env, text);
+    release(env, placeholder);
+    release(env, label);
+    release(env, text_color);
+
+    msg_super![env; this dealloc]
+}
+
+- (())layoutSubviews {
+    let label = env.objc.borrow::<UITextFieldHostObject>(this).label;
+    let bounds: CGRect = msg![env; this bounds];
+    let mut frame = bounds;
+    frame.origin.x += TEXT_INSET;
+    frame.size = CGSize {
+        width: (bounds.size.width - TEXT_INSET * 2.0).max(0.0),
+        height: bounds.size.height,
+    };
+    () = msg![env; label setFrame:frame];
+}
+
+// FIXME: layoutSubviews should be called automatically when the frame changes
+- (())setFrame:(CGRect)frame {
+    () = msg_super![env; this setFrame:frame];
+    () = msg![env; this layoutSubviews];
+}
 
 - (id)text {
-    // This should be `nil` by default, but Wolf3d crashes otherwise
-    ns_string::get_static_str(env, "")
+    // This should be `nil` by default on the real iPhone OS, but Wolf3d
+    // crashes in that case, so the empty string is used instead.
+    env.objc.borrow::<UITextFieldHostObject>(this).text
+}
+- (())setText:(id)text { // NSString*
+    let text: id = if text == nil {
+        ns_string::get_static_str(env, "")
+    } else {
+        msg![env; text copy]
+    };
+    let host_obj = env.objc.borrow_mut::<UITextFieldHostObject>(this);
+    let old_text = std::mem::replace(&mut host_obj.text, text);
+    release(env, old_text);
+    update_label(env, this);
 }
 
-- (())setText:(id)_text { // NSString*
-    // TODO
+- (id)placeholder {
+    env.objc.borrow::<UITextFieldHostObject>(this).placeholder
 }
-- (())setTextColor:(id)_color { // UIColor*
-    // TODO: implement this once views are actually rendered
+- (())setPlaceholder:(id)placeholder { // NSString*
+    let placeholder: id = msg![env; placeholder copy];
+    let host_obj = env.objc.borrow_mut::<UITextFieldHostObject>(this);
+    let old_placeholder = std::mem::replace(&mut host_obj.placeholder, placeholder);
+    release(env, old_placeholder);
+    update_label(env, this);
 }
 
-- (())setClearsOnBeginEditing:(bool)_clear {
-    // TODO
+- (id)textColor {
+    env.objc.borrow::<UITextFieldHostObject>(this).text_color
+}
+- (())setTextColor:(id)color { // UIColor*
+    let color: id = msg![env; color retain];
+    let host_obj = env.objc.borrow_mut::<UITextFieldHostObject>(this);
+    let old_color = std::mem::replace(&mut host_obj.text_color, color);
+    release(env, old_color);
+    update_label(env, this);
 }
 
+- (id)font {
+    let label = env.objc.borrow::<UITextFieldHostObject>(this).label;
+    msg![env; label font]
+}
+- (())setFont:(id)font { // UIFont*
+    let label = env.objc.borrow::<UITextFieldHostObject>(this).label;
+    msg![env; label setFont:font]
+}
+
+- (UITextAlignment)textAlignment {
+    let label = env.objc.borrow::<UITextFieldHostObject>(this).label;
+    msg![env; label textAlignment]
+}
+- (())setTextAlignment:(UITextAlignment)alignment {
+    let label = env.objc.borrow::<UITextFieldHostObject>(this).label;
+    msg![env; label setTextAlignment:alignment]
+}
+
+- (UITextBorderStyle)borderStyle {
+    env.objc.borrow::<UITextFieldHostObject>(this).border_style
+}
+- (())setBorderStyle:(UITextBorderStyle)style {
+    env.objc.borrow_mut::<UITextFieldHostObject>(this).border_style = style;
+    if style != UITextBorderStyleNone {
+        // TODO: draw the actual border
+        let bg_color: id = msg_class![env; UIColor whiteColor];
+        () = msg![env; this setBackgroundColor:bg_color];
+    }
+}
+
+- (bool)clearsOnBeginEditing {
+    env.objc.borrow::<UITextFieldHostObject>(this).clears_on_begin_editing
+}
+- (())setClearsOnBeginEditing:(bool)clear {
+    env.objc.borrow_mut::<UITextFieldHostObject>(this).clears_on_begin_editing = clear;
+}
+
+- (id)delegate {
+    env.objc.borrow::<UITextFieldHostObject>(this).delegate
+}
 // weak/non-retaining
-- (())setDelegate:(id)_delegate { // something implementing UITextFieldDelegate
-    // TODO
+- (())setDelegate:(id)delegate { // something implementing UITextFieldDelegate
+    env.objc.borrow_mut::<UITextFieldHostObject>(this).delegate = delegate;
+}
+
+- (bool)isEditing {
+    env.objc.borrow::<UITextFieldHostObject>(this).editing
+}
+
+// UIResponder overrides
+
+- (bool)canBecomeFirstResponder {
+    msg![env; this isEnabled]
+}
+
+- (bool)becomeFirstResponder {
+    if msg![env; this isFirstResponder] {
+        return true;
+    }
+    if let Some(delegate) = delegate_responds_to(env, this, "textFieldShouldBeginEditing:") {
+        let should_begin: bool = msg![env; delegate textFieldShouldBeginEditing:this];
+        if !should_begin {
+            return false;
+        }
+    }
+    let became: bool = msg_super![env; this becomeFirstResponder];
+    if !became {
+        return false;
+    }
+
+    let host_obj = env.objc.borrow_mut::<UITextFieldHostObject>(this);
+    host_obj.editing = true;
+    let clears_on_begin_editing = host_obj.clears_on_begin_editing;
+    if clears_on_begin_editing {
+        () = msg![env; this setText:nil];
+    }
+    env.window
+        .as_mut()
+        .expect("UITextField editing not supported in headless mode")
+        .start_text_input();
+
+    send_actions(env, this, nil, UIControlEventEditingDidBegin);
+    if let Some(delegate) = delegate_responds_to(env, this, "textFieldDidBeginEditing:") {
+        () = msg![env; delegate textFieldDidBeginEditing:this];
+    }
+    true
+}
+
+- (bool)resignFirstResponder {
+    if !msg![env; this isFirstResponder] {
+        return msg_super![env; this resignFirstResponder];
+    }
+    if let Some(delegate) = delegate_responds_to(env, this, "textFieldShouldEndEditing:") {
+        let should_end: bool = msg![env; delegate textFieldShouldEndEditing:this];
+        if !should_end {
+            return false;
+        }
+    }
+    let resigned: bool = msg_super![env; this resignFirstResponder];
+    if !resigned {
+        return false;
+    }
+
+    env.objc.borrow_mut::<UITextFieldHostObject>(this).editing = false;
+    if let Some(window) = env.window.as_mut() {
+        window.stop_text_input();
+    }
+
+    send_actions(env, this, nil, UIControlEventEditingDidEnd);
+    if let Some(delegate) = delegate_responds_to(env, this, "textFieldDidEndEditing:") {
+        () = msg![env; delegate textFieldDidEndEditing:this];
+    }
+    true
+}
+
+- (())touchesEnded:(id)touches // NSSet* of UITouch*
+         withEvent:(id)event { // UIEvent*
+    () = msg_super![env; this touchesEnded:touches withEvent:event];
+    let _: bool = msg![env; this becomeFirstResponder];
 }
 
 // UITextInputTraits implementation
@@ -57,8 +473,19 @@ pub const CLASSES: ClassExports = objc_classes! {
 - (())setKeyboardAppearance:(UIKeyboardAppearance)_appearance {
     // TODO
 }
-- (())setKeyboardType:(UIKeyboardType)_type {
-    // TODO
+- (UIKeyboardType)keyboardType {
+    env.objc.borrow::<UITextFieldHostObject>(this).keyboard_type
+}
+- (())setKeyboardType:(UIKeyboardType)type_ {
+    // Only affects which characters can be typed.
+    env.objc.borrow_mut::<UITextFieldHostObject>(this).keyboard_type = type_;
+}
+- (bool)isSecureTextEntry {
+    env.objc.borrow::<UITextFieldHostObject>(this).secure_text_entry
+}
+- (())setSecureTextEntry:(bool)secure {
+    env.objc.borrow_mut::<UITextFieldHostObject>(this).secure_text_entry = secure;
+    update_label(env, this);
 }
 
 @end
@@ -220,7 +220,9 @@ pub const CLASSES: ClassExports = objc_classes! {
     let single_line = number_of_lines == 1;
 
     let calculated_size: CGSize = if single_line {
-        msg![env; text sizeWithFont:font]
+        msg![env; text sizeWithFont:font
+                           forWidth:(bounds.size.width)
+                      lineBreakMode:line_break_mode]
     } else {
         msg![env; text sizeWithFont:font
                   constrainedToSize:(bounds.size)
@@ -255,7 +257,9 @@ pub const CLASSES: ClassExports = objc_classes! {
             y: rect.origin.y
         };
         msg![env; text drawAtPoint:point
-                          withFont:font]
+                          forWidth:(bounds.size.width)
+                          withFont:font
+                     lineBreakMode:line_break_mode]
     } else {
         msg![env; text drawInRect:rect
                          withFont:font
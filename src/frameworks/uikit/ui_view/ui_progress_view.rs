@@ -0,0 +1,289 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UIProgressView`.
+//!
+//! `setProgress:animated:` is animated by the run loop via
+//! [animate_progress_views], since the progress isn't a layer property that
+//! the `UIView` animations in animation.rs could interpolate.
+
+use crate::frameworks::core_graphics::cg_context::{CGContextFillRect, CGContextSetRGBFillColor};
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::ns_string::get_static_str;
+use crate::frameworks::foundation::NSInteger;
+use crate::frameworks::uikit::ui_color;
+use crate::frameworks::uikit::ui_graphics::UIGraphicsGetCurrentContext;
+use crate::objc::{
+    id, impl_HostObject_with_superclass, msg, msg_super, nil, objc_classes, release, retain,
+    ClassExports, NSZonePtr,
+};
+use crate::Environment;
+use std::time::{Duration, Instant};
+
+pub type UIProgressViewStyle = NSInteger;
+pub const UIProgressViewStyleDefault: UIProgressViewStyle = 0;
+pub const UIProgressViewStyleBar: UIProgressViewStyle = 1;
+
+/// Duration of `setProgress:animated:` animations, in seconds.
+const ANIMATION_DURATION: f64 = 0.25;
+/// How often animations are updated.
+const STEP_INTERVAL: f64 = 1.0 / 60.0;
+
+#[derive(Default)]
+pub struct State {
+    /// Views whose progress is animating. These are weak references, views
+    /// remove themselves when the animation ends or they are deallocated.
+    animating: Vec<id>,
+}
+
+#[derive(Default)]
+pub struct UIProgressViewHostObject {
+    superclass: super::UIViewHostObject,
+    style: UIProgressViewStyle,
+    /// The progress the app last set.
+    progress: f32,
+    /// The progress that is drawn. This differs from `progress` while an
+    /// animation is running.
+    displayed_progress: f32,
+    /// Start time and starting progress of the running animation, if any.
+    animation: Option<(Instant, f32)>,
+    /// `UIColor*`, or nil for the style's default color.
+    progress_tint_color: id,
+    /// `UIColor*`, or nil for the style's default color.
+    track_tint_color: id,
+}
+impl_HostObject_with_superclass!(UIProgressViewHostObject);
+
+fn stop_animation(env: &mut Environment, view: id) {
+    let state = &mut env.framework_state.uikit.ui_view.ui_progress_view;
+    state.animating.retain(|&other| other != view);
+    env.objc
+        .borrow_mut::<UIProgressViewHostObject>(view)
+        .animation = None;
+}
+
+/// For use by the run loop: update the progress of views that are animating.
+///
+/// Returns the next time this function must be called, if any.
+pub fn animate_progress_views(env: &mut Environment) -> Option<Instant> {
+    let now = Instant::now();
+
+    let views = env
+        .framework_state
+        .uikit
+        .ui_view
+        .ui_progress_view
+        .animating
+        .clone();
+    if views.is_empty() {
+        return None;
+    }
+    for view in views {
+        let host_obj = env.objc.borrow_mut::<UIProgressViewHostObject>(view);
+        let (start, from) = host_obj.animation.unwrap();
+        let t = (now.duration_since(start).as_secs_f64() / ANIMATION_DURATION).min(1.0);
+        // Ease out
+        let t = (1.0 - (1.0 - t) * (1.0 - t)) as f32;
+        host_obj.displayed_progress = from + (host_obj.progress - from) * t;
+        if t >= 1.0 {
+            stop_animation(env, view);
+        }
+        () = msg![env; view setNeedsDisplay];
+    }
+
+    if env
+        .framework_state
+        .uikit
+        .ui_view
+        .ui_progress_view
+        .animating
+        .is_empty()
+    {
+        None
+    } else {
+        Some(now + Duration::from_secs_f64(STEP_INTERVAL))
+    }
+}
+
+fn rgba_or_default(
+    env: &mut Environment,
+    color: id,
+    default: (CGFloat, CGFloat, CGFloat, CGFloat),
+) -> (CGFloat, CGFloat, CGFloat, CGFloat) {
+    if color != nil {
+        ui_color::get_rgba(&env.objc, color)
+    } else {
+        default
+    }
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UIProgressView: UIView
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::<UIProgressViewHostObject>::default();
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (id)initWithProgressViewStyle:(UIProgressViewStyle)style {
+    let height = if style == UIProgressViewStyleBar { 11.0 } else { 9.0 };
+    let frame = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: CGSize { width: 150.0, height },
+    };
+    let this: id = msg![env; this initWithFrame:frame];
+    () = msg![env; this setProgressViewStyle:style];
+    this
+}
+
+- (id)initWithFrame:(CGRect)frame {
+    let this: id = msg_super![env; this initWithFrame:frame];
+    // Built-in views don't have user-controlled opaqueness.
+    () = msg_super![env; this setOpaque:false];
+    this
+}
+
+- (id)initWithCoder:(id)coder {
+    let this: id = msg_super![env; this initWithCoder:coder];
+    () = msg_super![env; this setOpaque:false];
+
+    let key_ns_string = get_static_str(env, "UIProgressViewStyle");
+    let style: NSInteger = msg![env; coder decodeIntegerForKey:key_ns_string];
+    let key_ns_string = get_static_str(env, "UIProgress");
+    let progress: f32 = msg![env; coder decodeFloatForKey:key_ns_string];
+
+    () = msg![env; this setProgressViewStyle:style];
+    () = msg![env; this setProgress:progress];
+    this
+}
+
+- (())dealloc {
+    stop_animation(env, this);
+    let &UIProgressViewHostObject {
+        progress_tint_color,
+        track_tint_color,
+        ..
+    } = env.objc.borrow(this);
+    release(env, progress_tint_color);
+    release(env, track_tint_color);
+    msg_super![env; this dealloc]
+}
+
+- (UIProgressViewStyle)progressViewStyle {
+    env.objc.borrow::<UIProgressViewHostObject>(this).style
+}
+- (())setProgressViewStyle:(UIProgressViewStyle)style {
+    env.objc.borrow_mut::<UIProgressViewHostObject>(this).style = style;
+    () = msg![env; this setNeedsDisplay];
+}
+
+- (f32)progress {
+    env.objc.borrow::<UIProgressViewHostObject>(this).progress
+}
+- (())setProgress:(f32)progress {
+    msg![env; this setProgress:progress animated:false]
+}
+- (())setProgress:(f32)progress
+         animated:(bool)animated {
+    let progress = progress.clamp(0.0, 1.0);
+    let host_obj = env.objc.borrow_mut::<UIProgressViewHostObject>(this);
+    host_obj.progress = progress;
+    if animated && host_obj.displayed_progress != progress {
+        // Animate from wherever a running animation has got to.
+        let was_animating = host_obj.animation.is_some();
+        host_obj.animation = Some((Instant::now(), host_obj.displayed_progress));
+        if !was_animating {
+            env.framework_state.uikit.ui_view.ui_progress_view.animating.push(this);
+        }
+    } else {
+        host_obj.displayed_progress = progress;
+        stop_animation(env, this);
+        () = msg![env; this setNeedsDisplay];
+    }
+}
+
+- (id)progressTintColor {
+    env.objc.borrow::<UIProgressViewHostObject>(this).progress_tint_color
+}
+- (())setProgressTintColor:(id)color { // UIColor*
+    retain(env, color);
+    let old_color = std::mem::replace(
+        &mut env.objc.borrow_mut::<UIProgressViewHostObject>(this).progress_tint_color,
+        color,
+    );
+    release(env, old_color);
+    () = msg![env; this setNeedsDisplay];
+}
+- (id)trackTintColor {
+    env.objc.borrow::<UIProgressViewHostObject>(this).track_tint_color
+}
+- (())setTrackTintColor:(id)color { // UIColor*
+    retain(env, color);
+    let old_color = std::mem::replace(
+        &mut env.objc.borrow_mut::<UIProgressViewHostObject>(this).track_tint_color,
+        color,
+    );
+    release(env, old_color);
+    () = msg![env; this setNeedsDisplay];
+}
+
+- (())setOpaque:(bool)_opaque {
+    // Built-in views don't have user-controlled opaqueness.
+}
+
+- (())drawRect:(CGRect)_rect {
+    let bounds: CGRect = msg![env; this bounds];
+    let context = UIGraphicsGetCurrentContext(env);
+
+    let &UIProgressViewHostObject {
+        style,
+        displayed_progress,
+        progress_tint_color,
+        track_tint_color,
+        ..
+    } = env.objc.borrow(this);
+
+    let bar = style == UIProgressViewStyleBar;
+    let (border_color, track_default, progress_default) = if bar {
+        ((0.35, 0.35, 0.35, 1.0), (0.6, 0.6, 0.6, 1.0), (0.95, 0.95, 0.95, 1.0))
+    } else {
+        ((0.5, 0.5, 0.5, 1.0), (0.95, 0.95, 0.95, 1.0), (0.16, 0.42, 0.88, 1.0))
+    };
+    let track_color = rgba_or_default(env, track_tint_color, track_default);
+    let progress_color = rgba_or_default(env, progress_tint_color, progress_default);
+
+    let (r, g, b, a) = border_color;
+    CGContextSetRGBFillColor(env, context, r, g, b, a);
+    CGContextFillRect(env, context, bounds);
+
+    let inner = CGRect {
+        origin: CGPoint { x: bounds.origin.x + 1.0, y: bounds.origin.y + 1.0 },
+        size: CGSize {
+            width: (bounds.size.width - 2.0).max(0.0),
+            height: (bounds.size.height - 2.0).max(0.0),
+        },
+    };
+    let (r, g, b, a) = track_color;
+    CGContextSetRGBFillColor(env, context, r, g, b, a);
+    CGContextFillRect(env, context, inner);
+
+    let filled = CGRect {
+        origin: inner.origin,
+        size: CGSize {
+            width: inner.size.width * displayed_progress,
+            height: inner.size.height,
+        },
+    };
+    let (r, g, b, a) = progress_color;
+    CGContextSetRGBFillColor(env, context, r, g, b, a);
+    CGContextFillRect(env, context, filled);
+}
+
+@end
+
+};
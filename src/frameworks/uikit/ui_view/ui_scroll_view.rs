@@ -0,0 +1,534 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UIScrollView`.
+//!
+//! The content offset is the origin of the view's bounds, so scrolling just
+//! means changing the bounds. Touches on subviews that don't handle them
+//! themselves reach the scroll view via the responder chain.
+//!
+//! Missing features include clipping to bounds (not supported by our Core
+//! Animation yet), bouncing, zooming, and scroll indicators.
+
+pub mod ui_table_view;
+
+use super::UIViewHostObject;
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::uikit::ui_geometry::UIEdgeInsets;
+use crate::objc::{
+    id, impl_HostObject_with_superclass, msg, msg_class, msg_send, msg_super, nil, objc_classes,
+    release, retain, ClassExports, NSZonePtr,
+};
+use crate::Environment;
+use std::time::{Duration, Instant};
+
+/// `UIScrollViewDecelerationRateNormal`: the factor the velocity is multiplied
+/// by each millisecond while decelerating.
+const UIScrollViewDecelerationRateNormal: CGFloat = 0.998;
+
+/// Below this speed (points per second), deceleration stops.
+const MIN_VELOCITY: CGFloat = 10.0;
+/// Animation duration for `setContentOffset:animated:` and snapping to pages.
+const ANIMATION_DURATION: Duration = Duration::from_millis(300);
+/// How often the scroll view updates while animating or decelerating.
+const STEP_INTERVAL: f64 = 1.0 / 60.0;
+
+#[derive(Copy, Clone)]
+enum Motion {
+    None,
+    Decelerating {
+        /// Points per second.
+        velocity: CGPoint,
+    },
+    Animating {
+        from: CGPoint,
+        to: CGPoint,
+        started: Instant,
+        /// Paging animations are reported to the delegate as deceleration.
+        is_deceleration: bool,
+    },
+}
+
+struct UIScrollViewHostObject {
+    superclass: UIViewHostObject,
+    /// Weak reference.
+    delegate: id,
+    content_size: CGSize,
+    content_inset: UIEdgeInsets,
+    scroll_enabled: bool,
+    paging_enabled: bool,
+    bounces: bool,
+    deceleration_rate: CGFloat,
+    /// `UITouch*` of the touch currently dragging the content, strong
+    /// reference.
+    tracked_touch: id,
+    /// Last location of the tracked touch, in window co-ordinates (these don't
+    /// change when scrolling).
+    last_touch_location: CGPoint,
+    last_touch_time: Instant,
+    /// Points per second, estimated from the tracked touch's movement.
+    touch_velocity: CGPoint,
+    dragging: bool,
+    motion: Motion,
+    last_step: Instant,
+    /// `NSTimer*` driving animation and deceleration. Weak reference (the run
+    /// loop owns it).
+    timer: id,
+}
+impl_HostObject_with_superclass!(UIScrollViewHostObject);
+impl Default for UIScrollViewHostObject {
+    fn default() -> Self {
+        UIScrollViewHostObject {
+            superclass: Default::default(),
+            delegate: nil,
+            content_size: CGSize::default(),
+            content_inset: UIEdgeInsets::default(),
+            scroll_enabled: true,
+            paging_enabled: false,
+            bounces: true,
+            deceleration_rate: UIScrollViewDecelerationRateNormal,
+            tracked_touch: nil,
+            last_touch_location: CGPoint::default(),
+            last_touch_time: Instant::now(),
+            touch_velocity: CGPoint::default(),
+            dragging: false,
+            motion: Motion::None,
+            last_step: Instant::now(),
+            timer: nil,
+        }
+    }
+}
+
+fn tell_delegate(env: &mut Environment, this: id, selector: &str) {
+    let delegate = env.objc.borrow::<UIScrollViewHostObject>(this).delegate;
+    if delegate != nil
+        && env
+            .objc
+            .object_has_method_named(&env.mem, delegate, selector)
+    {
+        let selector = env.objc.lookup_selector(selector).unwrap();
+        () = msg_send(env, (delegate, selector, this));
+    }
+}
+
+/// The range of content offsets that scrolling by touch can reach.
+fn offset_limits(env: &mut Environment, this: id) -> (CGPoint, CGPoint) {
+    let bounds: CGRect = msg![env; this bounds];
+    let host_obj = env.objc.borrow::<UIScrollViewHostObject>(this);
+    let content_size = host_obj.content_size;
+    let inset = host_obj.content_inset;
+    let min = CGPoint {
+        x: -inset.left,
+        y: -inset.top,
+    };
+    let max = CGPoint {
+        x: (content_size.width + inset.right - bounds.size.width).max(min.x),
+        y: (content_size.height + inset.bottom - bounds.size.height).max(min.y),
+    };
+    (min, max)
+}
+
+fn clamp_offset(env: &mut Environment, this: id, offset: CGPoint) -> CGPoint {
+    let (min, max) = offset_limits(env, this);
+    CGPoint {
+        x: offset.x.clamp(min.x, max.x),
+        y: offset.y.clamp(min.y, max.y),
+    }
+}
+
+fn set_offset(env: &mut Environment, this: id, offset: CGPoint) {
+    let mut bounds: CGRect = msg![env; this bounds];
+    if bounds.origin == offset {
+        return;
+    }
+    bounds.origin = offset;
+    () = msg![env; this setBounds:bounds];
+    tell_delegate(env, this, "scrollViewDidScroll:");
+}
+
+fn start_motion(env: &mut Environment, this: id, motion: Motion) {
+    let host_obj = env.objc.borrow_mut::<UIScrollViewHostObject>(this);
+    host_obj.motion = motion;
+    host_obj.last_step = Instant::now();
+    if host_obj.timer != nil {
+        return;
+    }
+    let selector = env.objc.lookup_selector("_touchHLE_step:").unwrap();
+    let timer: id = msg_class![env; NSTimer scheduledTimerWithTimeInterval:STEP_INTERVAL
+                                                                    target:this
+                                                                  selector:selector
+                                                                  userInfo:nil
+                                                                   repeats:true];
+    env.objc.borrow_mut::<UIScrollViewHostObject>(this).timer = timer;
+}
+
+fn stop_motion(env: &mut Environment, this: id) {
+    let host_obj = env.objc.borrow_mut::<UIScrollViewHostObject>(this);
+    host_obj.motion = Motion::None;
+    let timer = std::mem::replace(&mut host_obj.timer, nil);
+    if timer != nil {
+        () = msg![env; timer invalidate];
+    }
+}
+
+/// Where a paging scroll view should come to rest after a drag.
+fn page_target(env: &mut Environment, this: id, velocity: CGPoint) -> CGPoint {
+    let bounds: CGRect = msg![env; this bounds];
+    let snap = |offset: CGFloat, page_size: CGFloat, velocity: CGFloat| {
+        if page_size <= 0.0 {
+            return offset;
+        }
+        let page = offset / page_size;
+        let page = if velocity > MIN_VELOCITY * 10.0 {
+            page.ceil()
+        } else if velocity < -MIN_VELOCITY * 10.0 {
+            page.floor()
+        } else {
+            page.round()
+        };
+        page * page_size
+    };
+    let target = CGPoint {
+        x: snap(bounds.origin.x, bounds.size.width, velocity.x),
+        y: snap(bounds.origin.y, bounds.size.height, velocity.y),
+    };
+    clamp_offset(env, this, target)
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UIScrollView: UIView
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::<UIScrollViewHostObject>::default();
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+// TODO: initWithCoder: should decode the scroll view properties
+
+- (())dealloc {
+    let tracked_touch = env.objc.borrow::<UIScrollViewHostObject>(this).tracked_touch;
+    release(env, tracked_touch);
+    msg_super![env; this dealloc]
+}
+
+- (id)delegate {
+    env.objc.borrow::<UIScrollViewHostObject>(this).delegate
+}
+- (())setDelegate:(id)delegate { // something implementing UIScrollViewDelegate
+    // Weak reference
+    env.objc.borrow_mut::<UIScrollViewHostObject>(this).delegate = delegate;
+}
+
+- (CGSize)contentSize {
+    env.objc.borrow::<UIScrollViewHostObject>(this).content_size
+}
+- (())setContentSize:(CGSize)size {
+    env.objc.borrow_mut::<UIScrollViewHostObject>(this).content_size = size;
+}
+
+- (UIEdgeInsets)contentInset {
+    env.objc.borrow::<UIScrollViewHostObject>(this).content_inset
+}
+- (())setContentInset:(UIEdgeInsets)inset {
+    env.objc.borrow_mut::<UIScrollViewHostObject>(this).content_inset = inset;
+}
+
+- (CGPoint)contentOffset {
+    let bounds: CGRect = msg![env; this bounds];
+    bounds.origin
+}
+- (())setContentOffset:(CGPoint)offset {
+    // Unlike scrolling by touch, this isn't limited to the content's bounds.
+    set_offset(env, this, offset);
+}
+- (())setContentOffset:(CGPoint)offset
+              animated:(bool)animated {
+    if !animated {
+        stop_motion(env, this);
+        () = msg![env; this setContentOffset:offset];
+        return;
+    }
+    let from: CGPoint = msg![env; this contentOffset];
+    start_motion(env, this, Motion::Animating {
+        from,
+        to: offset,
+        started: Instant::now(),
+        is_deceleration: false,
+    });
+}
+
+- (())scrollRectToVisible:(CGRect)rect
+                 animated:(bool)animated {
+    let bounds: CGRect = msg![env; this bounds];
+    let mut offset = bounds.origin;
+    if rect.origin.x < offset.x {
+        offset.x = rect.origin.x;
+    } else if rect.origin.x + rect.size.width > offset.x + bounds.size.width {
+        offset.x = rect.origin.x + rect.size.width - bounds.size.width;
+    }
+    if rect.origin.y < offset.y {
+        offset.y = rect.origin.y;
+    } else if rect.origin.y + rect.size.height > offset.y + bounds.size.height {
+        offset.y = rect.origin.y + rect.size.height - bounds.size.height;
+    }
+    let offset = clamp_offset(env, this, offset);
+    msg![env; this setContentOffset:offset animated:animated]
+}
+
+- (bool)isScrollEnabled {
+    env.objc.borrow::<UIScrollViewHostObject>(this).scroll_enabled
+}
+- (())setScrollEnabled:(bool)enabled {
+    env.objc.borrow_mut::<UIScrollViewHostObject>(this).scroll_enabled = enabled;
+}
+
+- (bool)isPagingEnabled {
+    env.objc.borrow::<UIScrollViewHostObject>(this).paging_enabled
+}
+- (())setPagingEnabled:(bool)enabled {
+    env.objc.borrow_mut::<UIScrollViewHostObject>(this).paging_enabled = enabled;
+}
+
+- (bool)bounces {
+    env.objc.borrow::<UIScrollViewHostObject>(this).bounces
+}
+- (())setBounces:(bool)bounces {
+    // TODO: actually bounce
+    env.objc.borrow_mut::<UIScrollViewHostObject>(this).bounces = bounces;
+}
+
+- (CGFloat)decelerationRate {
+    env.objc.borrow::<UIScrollViewHostObject>(this).deceleration_rate
+}
+- (())setDecelerationRate:(CGFloat)rate {
+    env.objc.borrow_mut::<UIScrollViewHostObject>(this).deceleration_rate = rate;
+}
+
+- (())setShowsHorizontalScrollIndicator:(bool)_shows {
+    // TODO: scroll indicators
+}
+- (())setShowsVerticalScrollIndicator:(bool)_shows {
+    // TODO: scroll indicators
+}
+- (())flashScrollIndicators {
+    // TODO: scroll indicators
+}
+
+- (bool)isTracking {
+    env.objc.borrow::<UIScrollViewHostObject>(this).tracked_touch != nil
+}
+- (bool)isDragging {
+    env.objc.borrow::<UIScrollViewHostObject>(this).dragging
+}
+- (bool)isDecelerating {
+    matches!(
+        env.objc.borrow::<UIScrollViewHostObject>(this).motion,
+        Motion::Decelerating { .. } | Motion::Animating { is_deceleration: true, .. }
+    )
+}
+
+// UIResponder implementation
+
+- (())touchesBegan:(id)touches // NSSet* of UITouch*
+         withEvent:(id)event { // UIEvent*
+    let host_obj = env.objc.borrow::<UIScrollViewHostObject>(this);
+    if !host_obj.scroll_enabled || host_obj.tracked_touch != nil {
+        return msg_super![env; this touchesBegan:touches withEvent:event];
+    }
+    // Touching the content stops it moving.
+    stop_motion(env, this);
+
+    let touch: id = msg![env; touches anyObject];
+    retain(env, touch);
+    let location: CGPoint = msg![env; touch locationInView:nil];
+    let host_obj = env.objc.borrow_mut::<UIScrollViewHostObject>(this);
+    host_obj.tracked_touch = touch;
+    host_obj.last_touch_location = location;
+    host_obj.last_touch_time = Instant::now();
+    host_obj.touch_velocity = CGPoint::default();
+    host_obj.dragging = false;
+}
+
+- (())touchesMoved:(id)touches // NSSet* of UITouch*
+         withEvent:(id)event { // UIEvent*
+    let touch: id = msg![env; touches anyObject];
+    if touch != env.objc.borrow::<UIScrollViewHostObject>(this).tracked_touch {
+        return msg_super![env; this touchesMoved:touches withEvent:event];
+    }
+    let location: CGPoint = msg![env; touch locationInView:nil];
+
+    let host_obj = env.objc.borrow_mut::<UIScrollViewHostObject>(this);
+    let delta = CGPoint {
+        x: host_obj.last_touch_location.x - location.x,
+        y: host_obj.last_touch_location.y - location.y,
+    };
+    let now = Instant::now();
+    let dt = now.duration_since(host_obj.last_touch_time).as_secs_f32().max(0.001);
+    // Smooth the velocity since touch events are unevenly spaced.
+    host_obj.touch_velocity = CGPoint {
+        x: host_obj.touch_velocity.x * 0.2 + (delta.x / dt) * 0.8,
+        y: host_obj.touch_velocity.y * 0.2 + (delta.y / dt) * 0.8,
+    };
+    host_obj.last_touch_location = location;
+    host_obj.last_touch_time = now;
+    let was_dragging = std::mem::replace(&mut host_obj.dragging, true);
+
+    if !was_dragging {
+        tell_delegate(env, this, "scrollViewWillBeginDragging:");
+    }
+
+    let offset: CGPoint = msg![env; this contentOffset];
+    let offset = clamp_offset(env, this, CGPoint {
+        x: offset.x + delta.x,
+        y: offset.y + delta.y,
+    });
+    set_offset(env, this, offset);
+}
+
+- (())touchesEnded:(id)touches // NSSet* of UITouch*
+         withEvent:(id)event { // UIEvent*
+    let touch: id = msg![env; touches anyObject];
+    let host_obj = env.objc.borrow_mut::<UIScrollViewHostObject>(this);
+    if touch != host_obj.tracked_touch {
+        return msg_super![env; this touchesEnded:touches withEvent:event];
+    }
+    host_obj.tracked_touch = nil;
+    let was_dragging = std::mem::replace(&mut host_obj.dragging, false);
+    let paging_enabled = host_obj.paging_enabled;
+    // If the finger stopped before it was lifted, there's no momentum.
+    let velocity = if host_obj.last_touch_time.elapsed() > Duration::from_millis(100) {
+        CGPoint::default()
+    } else {
+        host_obj.touch_velocity
+    };
+    release(env, touch);
+
+    if !was_dragging {
+        return;
+    }
+
+    let will_decelerate = if paging_enabled {
+        let from: CGPoint = msg![env; this contentOffset];
+        let to = page_target(env, this, velocity);
+        start_motion(env, this, Motion::Animating {
+            from,
+            to,
+            started: Instant::now(),
+            is_deceleration: true,
+        });
+        true
+    } else if velocity.x.hypot(velocity.y) > MIN_VELOCITY {
+        start_motion(env, this, Motion::Decelerating { velocity });
+        true
+    } else {
+        false
+    };
+
+    let delegate = env.objc.borrow::<UIScrollViewHostObject>(this).delegate;
+    if delegate != nil
+        && env.objc.object_has_method_named(
+            &env.mem,
+            delegate,
+            "scrollViewDidEndDragging:willDecelerate:",
+        )
+    {
+        () = msg![env; delegate scrollViewDidEndDragging:this willDecelerate:will_decelerate];
+    }
+}
+
+- (())touchesCancelled:(id)touches // NSSet* of UITouch*
+             withEvent:(id)event { // UIEvent*
+    let touch: id = msg![env; touches anyObject];
+    let host_obj = env.objc.borrow_mut::<UIScrollViewHostObject>(this);
+    if touch != host_obj.tracked_touch {
+        return msg_super![env; this touchesCancelled:touches withEvent:event];
+    }
+    host_obj.tracked_touch = nil;
+    let was_dragging = std::mem::replace(&mut host_obj.dragging, false);
+    release(env, touch);
+
+    // The content stays where it is, no momentum.
+    if was_dragging {
+        let delegate = env.objc.borrow::<UIScrollViewHostObject>(this).delegate;
+        if delegate != nil
+            && env.objc.object_has_method_named(
+                &env.mem,
+                delegate,
+                "scrollViewDidEndDragging:willDecelerate:",
+            )
+        {
+            () = msg![env; delegate scrollViewDidEndDragging:this willDecelerate:false];
+        }
+    }
+}
+
+- (())_touchHLE_step:(id)_timer { // NSTimer*
+    let now = Instant::now();
+    let host_obj = env.objc.borrow_mut::<UIScrollViewHostObject>(this);
+    let dt = now.duration_since(host_obj.last_step);
+    host_obj.last_step = now;
+    let deceleration_rate = host_obj.deceleration_rate;
+    let motion = host_obj.motion;
+
+    match motion {
+        Motion::None => stop_motion(env, this),
+        Motion::Decelerating { velocity } => {
+            let factor = deceleration_rate.powf(dt.as_secs_f32() * 1000.0);
+            let mut velocity = CGPoint {
+                x: velocity.x * factor,
+                y: velocity.y * factor,
+            };
+            let offset: CGPoint = msg![env; this contentOffset];
+            let new_offset = CGPoint {
+                x: offset.x + velocity.x * dt.as_secs_f32(),
+                y: offset.y + velocity.y * dt.as_secs_f32(),
+            };
+            let clamped_offset = clamp_offset(env, this, new_offset);
+            // Hitting the edge stops movement in that direction.
+            if clamped_offset.x != new_offset.x {
+                velocity.x = 0.0;
+            }
+            if clamped_offset.y != new_offset.y {
+                velocity.y = 0.0;
+            }
+            set_offset(env, this, clamped_offset);
+
+            if velocity.x.hypot(velocity.y) < MIN_VELOCITY {
+                stop_motion(env, this);
+                tell_delegate(env, this, "scrollViewDidEndDecelerating:");
+            } else {
+                env.objc.borrow_mut::<UIScrollViewHostObject>(this).motion =
+                    Motion::Decelerating { velocity };
+            }
+        }
+        Motion::Animating { from, to, started, is_deceleration } => {
+            let t = (now.duration_since(started).as_secs_f32()
+                / ANIMATION_DURATION.as_secs_f32()).min(1.0);
+            // Ease out (cubic).
+            let t_eased = 1.0 - (1.0 - t).powi(3);
+            let offset = CGPoint {
+                x: from.x + (to.x - from.x) * t_eased,
+                y: from.y + (to.y - from.y) * t_eased,
+            };
+            set_offset(env, this, offset);
+
+            if t >= 1.0 {
+                stop_motion(env, this);
+                if is_deceleration {
+                    tell_delegate(env, this, "scrollViewDidEndDecelerating:");
+                } else {
+                    tell_delegate(env, this, "scrollViewDidEndScrollingAnimation:");
+                }
+            }
+        }
+    }
+}
+
+@end
+
+};
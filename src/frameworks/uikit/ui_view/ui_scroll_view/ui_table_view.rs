@@ -0,0 +1,743 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UITableView`.
+//!
+//! Only the cells for visible rows are requested from the data source. Cells
+//! that scroll out of view are kept for `dequeueReusableCellWithIdentifier:`.
+//!
+//! Missing features include separators, footers, editing, index titles and
+//! row animations.
+
+use super::{clamp_offset, UIScrollViewHostObject};
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::ns_index_path::{index_path_for_row, section_and_row};
+use crate::frameworks::foundation::{ns_array, ns_string, NSInteger, NSUInteger};
+use crate::frameworks::uikit::ui_font::UITextAlignmentLeft;
+use crate::objc::{
+    autorelease, id, impl_HostObject_with_superclass, msg, msg_class, msg_super, nil, objc_classes,
+    release, retain, ClassExports, NSZonePtr,
+};
+use crate::Environment;
+use std::collections::{BTreeMap, HashMap};
+
+pub type UITableViewStyle = NSInteger;
+pub const UITableViewStylePlain: UITableViewStyle = 0;
+pub const UITableViewStyleGrouped: UITableViewStyle = 1;
+
+pub type UITableViewScrollPosition = NSInteger;
+pub const UITableViewScrollPositionNone: UITableViewScrollPosition = 0;
+pub const UITableViewScrollPositionTop: UITableViewScrollPosition = 1;
+pub const UITableViewScrollPositionMiddle: UITableViewScrollPosition = 2;
+pub const UITableViewScrollPositionBottom: UITableViewScrollPosition = 3;
+
+const DEFAULT_ROW_HEIGHT: CGFloat = 44.0;
+const PLAIN_HEADER_HEIGHT: CGFloat = 22.0;
+const GROUPED_HEADER_HEIGHT: CGFloat = 36.0;
+/// Space around sections and horizontal inset of rows in grouped tables.
+const GROUPED_MARGIN: CGFloat = 10.0;
+
+/// (section, row)
+type RowIndex = (NSUInteger, NSUInteger);
+
+struct SectionGeometry {
+    header_height: CGFloat,
+    /// Vertical position and height of each row.
+    rows: Vec<(CGFloat, CGFloat)>,
+}
+
+struct UITableViewHostObject {
+    superclass: UIScrollViewHostObject,
+    /// Weak reference.
+    data_source: id,
+    style: UITableViewStyle,
+    row_height: CGFloat,
+    /// Set when the data needs to be fetched from the data source again.
+    needs_reload: bool,
+    sections: Vec<SectionGeometry>,
+    /// `UILabel*`s for section headers. Weak references (they are subviews).
+    header_views: Vec<id>,
+    /// `UITableViewCell*`s for visible rows, strong references.
+    visible_cells: BTreeMap<RowIndex, id>,
+    /// `UITableViewCell*`s that can be reused, by reuse identifier. Strong
+    /// references.
+    reusable_cells: HashMap<String, Vec<id>>,
+    selected_row: Option<RowIndex>,
+    /// Row that is highlighted because a touch began on it.
+    touched_row: Option<RowIndex>,
+    allows_selection: bool,
+}
+impl_HostObject_with_superclass!(UITableViewHostObject);
+impl Default for UITableViewHostObject {
+    fn default() -> Self {
+        UITableViewHostObject {
+            superclass: Default::default(),
+            data_source: nil,
+            style: UITableViewStylePlain,
+            row_height: DEFAULT_ROW_HEIGHT,
+            needs_reload: true,
+            sections: Vec::new(),
+            header_views: Vec::new(),
+            visible_cells: BTreeMap::new(),
+            reusable_cells: HashMap::new(),
+            selected_row: None,
+            touched_row: None,
+            allows_selection: true,
+        }
+    }
+}
+
+fn delegate_responds_to(env: &mut Environment, this: id, selector: &str) -> Option<id> {
+    let delegate = env.objc.borrow::<UIScrollViewHostObject>(this).delegate;
+    if delegate != nil
+        && env
+            .objc
+            .object_has_method_named(&env.mem, delegate, selector)
+    {
+        Some(delegate)
+    } else {
+        None
+    }
+}
+
+fn row_rect(env: &mut Environment, this: id, (section, row): RowIndex) -> Option<CGRect> {
+    let bounds: CGRect = msg![env; this bounds];
+    let host_obj = env.objc.borrow::<UITableViewHostObject>(this);
+    let &(y, height) = host_obj
+        .sections
+        .get(section as usize)?
+        .rows
+        .get(row as usize)?;
+    let inset = if host_obj.style == UITableViewStyleGrouped {
+        GROUPED_MARGIN
+    } else {
+        0.0
+    };
+    Some(CGRect {
+        origin: CGPoint { x: inset, y },
+        size: CGSize {
+            width: bounds.size.width - inset * 2.0,
+            height,
+        },
+    })
+}
+
+fn row_at_y(env: &Environment, this: id, point_y: CGFloat) -> Option<RowIndex> {
+    let host_obj = env.objc.borrow::<UITableViewHostObject>(this);
+    for (section_idx, section) in host_obj.sections.iter().enumerate() {
+        for (row_idx, &(y, height)) in section.rows.iter().enumerate() {
+            if point_y >= y && point_y < y + height {
+                return Some((section_idx as NSUInteger, row_idx as NSUInteger));
+            }
+        }
+    }
+    None
+}
+
+/// Give up ownership of a cell that is no longer visible, keeping it for reuse
+/// if possible.
+fn enqueue_cell(env: &mut Environment, this: id, cell: id) {
+    () = msg![env; cell removeFromSuperview];
+    let reuse_identifier: id = msg![env; cell reuseIdentifier];
+    if reuse_identifier == nil {
+        release(env, cell);
+        return;
+    }
+    let reuse_identifier = ns_string::to_rust_string(env, reuse_identifier).into_owned();
+    env.objc
+        .borrow_mut::<UITableViewHostObject>(this)
+        .reusable_cells
+        .entry(reuse_identifier)
+        .or_default()
+        .push(cell);
+}
+
+/// Fetch the number of sections and rows, and the sizes of everything, from the
+/// data source and delegate.
+fn reload(env: &mut Environment, this: id) {
+    let host_obj = env.objc.borrow_mut::<UITableViewHostObject>(this);
+    host_obj.needs_reload = false;
+    let old_cells = std::mem::take(&mut host_obj.visible_cells);
+    let old_headers = std::mem::take(&mut host_obj.header_views);
+    let data_source = host_obj.data_source;
+    let style = host_obj.style;
+    let default_row_height = host_obj.row_height;
+    for (_, cell) in old_cells {
+        enqueue_cell(env, this, cell);
+    }
+    for header in old_headers {
+        () = msg![env; header removeFromSuperview];
+    }
+
+    let section_count: NSInteger = if data_source == nil {
+        0
+    } else if env.objc.object_has_method_named(
+        &env.mem,
+        data_source,
+        "numberOfSectionsInTableView:",
+    ) {
+        msg![env; data_source numberOfSectionsInTableView:this]
+    } else {
+        1
+    };
+    let has_titles = data_source != nil
+        && env.objc.object_has_method_named(
+            &env.mem,
+            data_source,
+            "tableView:titleForHeaderInSection:",
+        );
+    let height_delegate = delegate_responds_to(env, this, "tableView:heightForRowAtIndexPath:");
+
+    let bounds: CGRect = msg![env; this bounds];
+    let margin = if style == UITableViewStyleGrouped {
+        GROUPED_MARGIN
+    } else {
+        0.0
+    };
+    let mut sections = Vec::new();
+    let mut header_views = Vec::new();
+    let mut y = 0.0;
+    for section in 0..section_count {
+        y += margin;
+        let title: id = if has_titles {
+            msg![env; data_source tableView:this titleForHeaderInSection:section]
+        } else {
+            nil
+        };
+        let header_height = if title == nil {
+            0.0
+        } else {
+            let height = if style == UITableViewStyleGrouped {
+                GROUPED_HEADER_HEIGHT
+            } else {
+                PLAIN_HEADER_HEIGHT
+            };
+            let frame = CGRect {
+                origin: CGPoint { x: margin * 2.0, y },
+                size: CGSize {
+                    width: bounds.size.width - margin * 4.0,
+                    height,
+                },
+            };
+            let label: id = msg_class![env; UILabel alloc];
+            let label: id = msg![env; label initWithFrame:frame];
+            () = msg![env; label setText:title];
+            () = msg![env; label setTextAlignment:UITextAlignmentLeft];
+            let font: id = msg_class![env; UIFont boldSystemFontOfSize:(17.0 as CGFloat)];
+            () = msg![env; label setFont:font];
+            let bg_color: id = if style == UITableViewStyleGrouped {
+                msg_class![env; UIColor clearColor]
+            } else {
+                msg_class![env; UIColor colorWithRed:(0.6 as CGFloat)
+                                               green:(0.65 as CGFloat)
+                                                blue:(0.7 as CGFloat)
+                                               alpha:(1.0 as CGFloat)]
+            };
+            () = msg![env; label setBackgroundColor:bg_color];
+            () = msg![env; this addSubview:label];
+            release(env, label);
+            header_views.push(label);
+            height
+        };
+        y += header_height;
+
+        let row_count: NSInteger = msg![env; data_source tableView:this
+                                               numberOfRowsInSection:section];
+        let mut rows = Vec::new();
+        for row in 0..row_count {
+            let height = if let Some(delegate) = height_delegate {
+                let index_path =
+                    index_path_for_row(env, section.try_into().unwrap(), row.try_into().unwrap());
+                msg![env; delegate tableView:this heightForRowAtIndexPath:index_path]
+            } else {
+                default_row_height
+            };
+            rows.push((y, height));
+            y += height;
+        }
+        y += margin;
+
+        sections.push(SectionGeometry {
+            header_height,
+            rows,
+        });
+    }
+
+    let host_obj = env.objc.borrow_mut::<UITableViewHostObject>(this);
+    host_obj.sections = sections;
+    host_obj.header_views = header_views;
+    if let Some((section, row)) = host_obj.selected_row {
+        let still_exists = host_obj
+            .sections
+            .get(section as usize)
+            .map_or(false, |s| (row as usize) < s.rows.len());
+        if !still_exists {
+            host_obj.selected_row = None;
+        }
+    }
+    host_obj.touched_row = None;
+
+    let content_size = CGSize {
+        width: bounds.size.width,
+        height: y,
+    };
+    () = msg![env; this setContentSize:content_size];
+
+    lay_out_cells(env, this);
+}
+
+/// Make sure there are cells for the visible rows, and only those rows.
+fn lay_out_cells(env: &mut Environment, this: id) {
+    if env.objc.borrow::<UITableViewHostObject>(this).needs_reload {
+        // This calls lay_out_cells() again.
+        return reload(env, this);
+    }
+
+    let bounds: CGRect = msg![env; this bounds];
+    let top = bounds.origin.y;
+    let bottom = top + bounds.size.height;
+
+    let host_obj = env.objc.borrow::<UITableViewHostObject>(this);
+    let mut visible_rows = Vec::new();
+    for (section_idx, section) in host_obj.sections.iter().enumerate() {
+        for (row_idx, &(y, height)) in section.rows.iter().enumerate() {
+            if y < bottom && y + height > top {
+                visible_rows.push((section_idx as NSUInteger, row_idx as NSUInteger));
+            }
+        }
+    }
+
+    let no_longer_visible: Vec<RowIndex> = host_obj
+        .visible_cells
+        .keys()
+        .filter(|row| !visible_rows.contains(row))
+        .copied()
+        .collect();
+    for row in no_longer_visible {
+        let cell = env
+            .objc
+            .borrow_mut::<UITableViewHostObject>(this)
+            .visible_cells
+            .remove(&row)
+            .unwrap();
+        enqueue_cell(env, this, cell);
+    }
+
+    for row in visible_rows {
+        let host_obj = env.objc.borrow::<UITableViewHostObject>(this);
+        if host_obj.visible_cells.contains_key(&row) {
+            continue;
+        }
+        let data_source = host_obj.data_source;
+        let selected = host_obj.selected_row == Some(row);
+
+        let index_path = index_path_for_row(env, row.0, row.1);
+        let cell: id = msg![env; data_source tableView:this cellForRowAtIndexPath:index_path];
+        assert!(cell != nil);
+        retain(env, cell);
+        let frame = row_rect(env, this, row).unwrap();
+        () = msg![env; cell setFrame:frame];
+        () = msg![env; cell setSelected:selected];
+        () = msg![env; this addSubview:cell];
+        env.objc
+            .borrow_mut::<UITableViewHostObject>(this)
+            .visible_cells
+            .insert(row, cell);
+    }
+}
+
+fn set_touched_row(env: &mut Environment, this: id, row: Option<RowIndex>) {
+    let host_obj = env.objc.borrow_mut::<UITableViewHostObject>(this);
+    let old_row = std::mem::replace(&mut host_obj.touched_row, row);
+    for (row, highlighted) in [(old_row, false), (row, true)] {
+        let Some(row) = row else {
+            continue;
+        };
+        let cell = env
+            .objc
+            .borrow::<UITableViewHostObject>(this)
+            .visible_cells
+            .get(&row)
+            .copied();
+        if let Some(cell) = cell {
+            () = msg![env; cell setHighlighted:highlighted];
+        }
+    }
+}
+
+fn set_selected_row(env: &mut Environment, this: id, row: Option<RowIndex>) {
+    let host_obj = env.objc.borrow_mut::<UITableViewHostObject>(this);
+    let old_row = std::mem::replace(&mut host_obj.selected_row, row);
+    for (row, selected) in [(old_row, false), (row, true)] {
+        let Some(row) = row else {
+            continue;
+        };
+        let cell = env
+            .objc
+            .borrow::<UITableViewHostObject>(this)
+            .visible_cells
+            .get(&row)
+            .copied();
+        if let Some(cell) = cell {
+            () = msg![env; cell setSelected:selected];
+        }
+    }
+}
+
+/// Handle the user tapping a row.
+fn user_selected_row(env: &mut Environment, this: id, row: RowIndex) {
+    let mut index_path = index_path_for_row(env, row.0, row.1);
+    if let Some(delegate) = delegate_responds_to(env, this, "tableView:willSelectRowAtIndexPath:") {
+        index_path = msg![env; delegate tableView:this willSelectRowAtIndexPath:index_path];
+        if index_path == nil {
+            return;
+        }
+    }
+    let row = section_and_row(env, index_path);
+
+    let old_row = env.objc.borrow::<UITableViewHostObject>(this).selected_row;
+    set_selected_row(env, this, Some(row));
+    if let Some(old_row) = old_row.filter(|&old_row| old_row != row) {
+        if let Some(delegate) =
+            delegate_responds_to(env, this, "tableView:didDeselectRowAtIndexPath:")
+        {
+            let old_index_path = index_path_for_row(env, old_row.0, old_row.1);
+            () = msg![env; delegate tableView:this didDeselectRowAtIndexPath:old_index_path];
+        }
+    }
+    if let Some(delegate) = delegate_responds_to(env, this, "tableView:didSelectRowAtIndexPath:") {
+        () = msg![env; delegate tableView:this didSelectRowAtIndexPath:index_path];
+    }
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UITableView: UIScrollView
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::<UITableViewHostObject>::default();
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (id)initWithFrame:(CGRect)frame {
+    msg![env; this initWithFrame:frame style:UITableViewStylePlain]
+}
+
+- (id)initWithFrame:(CGRect)frame
+              style:(UITableViewStyle)style {
+    let this: id = msg_super![env; this initWithFrame:frame];
+    env.objc.borrow_mut::<UITableViewHostObject>(this).style = style;
+    let bg_color: id = if style == UITableViewStyleGrouped {
+        msg_class![env; UIColor colorWithRed:(0.77 as CGFloat)
+                                       green:(0.8 as CGFloat)
+                                        blue:(0.85 as CGFloat)
+                                       alpha:(1.0 as CGFloat)]
+    } else {
+        msg_class![env; UIColor whiteColor]
+    };
+    () = msg![env; this setBackgroundColor:bg_color];
+    () = msg![env; this setNeedsLayout];
+    this
+}
+
+// TODO: initWithCoder: should decode the table view properties
+- (id)initWithCoder:(id)coder {
+    let this: id = msg_super![env; this initWithCoder:coder];
+    () = msg![env; this setNeedsLayout];
+    this
+}
+
+- (())dealloc {
+    let host_obj = env.objc.borrow_mut::<UITableViewHostObject>(this);
+    let visible_cells = std::mem::take(&mut host_obj.visible_cells);
+    let reusable_cells = std::mem::take(&mut host_obj.reusable_cells);
+    for (_, cell) in visible_cells {
+        release(env, cell);
+    }
+    for cell in reusable_cells.into_values().flatten() {
+        release(env, cell);
+    }
+    msg_super![env; this dealloc]
+}
+
+- (UITableViewStyle)style {
+    env.objc.borrow::<UITableViewHostObject>(this).style
+}
+
+- (id)dataSource {
+    env.objc.borrow::<UITableViewHostObject>(this).data_source
+}
+- (())setDataSource:(id)data_source { // something implementing UITableViewDataSource
+    // Weak reference
+    let host_obj = env.objc.borrow_mut::<UITableViewHostObject>(this);
+    host_obj.data_source = data_source;
+    host_obj.needs_reload = true;
+    () = msg![env; this setNeedsLayout];
+}
+- (())setDelegate:(id)delegate { // something implementing UITableViewDelegate
+    () = msg_super![env; this setDelegate:delegate];
+    // The delegate can provide row heights.
+    env.objc.borrow_mut::<UITableViewHostObject>(this).needs_reload = true;
+    () = msg![env; this setNeedsLayout];
+}
+
+- (CGFloat)rowHeight {
+    env.objc.borrow::<UITableViewHostObject>(this).row_height
+}
+- (())setRowHeight:(CGFloat)row_height {
+    let host_obj = env.objc.borrow_mut::<UITableViewHostObject>(this);
+    host_obj.row_height = row_height;
+    host_obj.needs_reload = true;
+    () = msg![env; this setNeedsLayout];
+}
+
+- (bool)allowsSelection {
+    env.objc.borrow::<UITableViewHostObject>(this).allows_selection
+}
+- (())setAllowsSelection:(bool)allows_selection {
+    env.objc.borrow_mut::<UITableViewHostObject>(this).allows_selection = allows_selection;
+}
+
+- (())setSeparatorStyle:(NSInteger)_style { // UITableViewCellSeparatorStyle
+    // TODO: separators
+}
+- (())setSeparatorColor:(id)_color { // UIColor*
+    // TODO: separators
+}
+
+- (())reloadData {
+    env.objc.borrow_mut::<UITableViewHostObject>(this).needs_reload = true;
+    lay_out_cells(env, this);
+}
+
+- (())beginUpdates {
+    // Row animations aren't supported, so there's nothing to do here.
+}
+- (())endUpdates {
+    // TODO: insertion/deletion of rows and sections
+    () = msg![env; this reloadData];
+}
+
+- (NSInteger)numberOfSections {
+    let host_obj = env.objc.borrow::<UITableViewHostObject>(this);
+    host_obj.sections.len().try_into().unwrap()
+}
+- (NSInteger)numberOfRowsInSection:(NSInteger)section {
+    let host_obj = env.objc.borrow::<UITableViewHostObject>(this);
+    host_obj.sections[section as usize].rows.len().try_into().unwrap()
+}
+
+- (CGRect)rectForRowAtIndexPath:(id)index_path { // NSIndexPath*
+    let row = section_and_row(env, index_path);
+    row_rect(env, this, row).unwrap_or_default()
+}
+- (CGRect)rectForSection:(NSInteger)section {
+    let bounds: CGRect = msg![env; this bounds];
+    let host_obj = env.objc.borrow::<UITableViewHostObject>(this);
+    let Some(section) = host_obj.sections.get(section as usize) else {
+        return CGRect::default();
+    };
+    let (start, end) = match (section.rows.first(), section.rows.last()) {
+        (Some(&(first_y, _)), Some(&(last_y, last_height))) => (first_y, last_y + last_height),
+        _ => return CGRect::default(),
+    };
+    let start = start - section.header_height;
+    CGRect {
+        origin: CGPoint { x: 0.0, y: start },
+        size: CGSize {
+            width: bounds.size.width,
+            height: end - start,
+        },
+    }
+}
+
+- (id)indexPathForRowAtPoint:(CGPoint)point {
+    match row_at_y(env, this, point.y) {
+        Some((section, row)) => index_path_for_row(env, section, row),
+        None => nil,
+    }
+}
+
+- (id)cellForRowAtIndexPath:(id)index_path { // NSIndexPath*
+    let row = section_and_row(env, index_path);
+    let host_obj = env.objc.borrow::<UITableViewHostObject>(this);
+    host_obj.visible_cells.get(&row).copied().unwrap_or(nil)
+}
+
+- (id)visibleCells {
+    let cells: Vec<id> = env
+        .objc
+        .borrow::<UITableViewHostObject>(this)
+        .visible_cells
+        .values()
+        .copied()
+        .collect();
+    for &cell in &cells {
+        retain(env, cell);
+    }
+    let array = ns_array::from_vec(env, cells);
+    autorelease(env, array)
+}
+
+- (id)indexPathsForVisibleRows {
+    let rows: Vec<RowIndex> = env
+        .objc
+        .borrow::<UITableViewHostObject>(this)
+        .visible_cells
+        .keys()
+        .copied()
+        .collect();
+    let index_paths = rows
+        .into_iter()
+        .map(|(section, row)| {
+            let index_path = index_path_for_row(env, section, row);
+            retain(env, index_path)
+        })
+        .collect();
+    let array = ns_array::from_vec(env, index_paths);
+    autorelease(env, array)
+}
+
+- (id)dequeueReusableCellWithIdentifier:(id)identifier { // NSString*
+    let identifier = ns_string::to_rust_string(env, identifier);
+    let cell = env
+        .objc
+        .borrow_mut::<UITableViewHostObject>(this)
+        .reusable_cells
+        .get_mut(identifier.as_ref())
+        .and_then(|cells| cells.pop());
+    let Some(cell) = cell else {
+        return nil;
+    };
+    () = msg![env; cell prepareForReuse];
+    autorelease(env, cell)
+}
+
+- (id)indexPathForSelectedRow {
+    match env.objc.borrow::<UITableViewHostObject>(this).selected_row {
+        Some((section, row)) => index_path_for_row(env, section, row),
+        None => nil,
+    }
+}
+- (())selectRowAtIndexPath:(id)index_path // NSIndexPath*
+                  animated:(bool)animated
+            scrollPosition:(UITableViewScrollPosition)scroll_position {
+    if index_path == nil {
+        return set_selected_row(env, this, None);
+    }
+    let row = section_and_row(env, index_path);
+    set_selected_row(env, this, Some(row));
+    if scroll_position != UITableViewScrollPositionNone {
+        () = msg![env; this scrollToRowAtIndexPath:index_path
+                                  atScrollPosition:scroll_position
+                                          animated:animated];
+    }
+}
+- (())deselectRowAtIndexPath:(id)index_path // NSIndexPath*
+                    animated:(bool)_animated {
+    let row = section_and_row(env, index_path);
+    if env.objc.borrow::<UITableViewHostObject>(this).selected_row == Some(row) {
+        set_selected_row(env, this, None);
+    }
+}
+
+- (())scrollToRowAtIndexPath:(id)index_path // NSIndexPath*
+            atScrollPosition:(UITableViewScrollPosition)scroll_position
+                    animated:(bool)animated {
+    let row = section_and_row(env, index_path);
+    let Some(rect) = row_rect(env, this, row) else {
+        return;
+    };
+    let bounds: CGRect = msg![env; this bounds];
+    let y = match scroll_position {
+        UITableViewScrollPositionTop => rect.origin.y,
+        UITableViewScrollPositionMiddle => {
+            rect.origin.y + rect.size.height / 2.0 - bounds.size.height / 2.0
+        }
+        UITableViewScrollPositionBottom => {
+            rect.origin.y + rect.size.height - bounds.size.height
+        }
+        _ => return msg![env; this scrollRectToVisible:rect animated:animated],
+    };
+    let offset = clamp_offset(env, this, CGPoint { x: bounds.origin.x, y });
+    msg![env; this setContentOffset:offset animated:animated]
+}
+
+// UIView implementation
+
+- (())setBounds:(CGRect)bounds {
+    () = msg_super![env; this setBounds:bounds];
+    // Scrolling changes the bounds.
+    lay_out_cells(env, this);
+}
+- (())setFrame:(CGRect)frame {
+    () = msg_super![env; this setFrame:frame];
+    env.objc.borrow_mut::<UITableViewHostObject>(this).needs_reload = true;
+    () = msg![env; this setNeedsLayout];
+}
+
+- (())layoutSubviews {
+    lay_out_cells(env, this);
+}
+
+// UIResponder implementation
+
+- (())touchesBegan:(id)touches // NSSet* of UITouch*
+         withEvent:(id)event { // UIEvent*
+    let was_tracking: bool = msg![env; this isTracking];
+    () = msg_super![env; this touchesBegan:touches withEvent:event];
+    let is_tracking: bool = msg![env; this isTracking];
+    if was_tracking
+        || !is_tracking
+        || !env.objc.borrow::<UITableViewHostObject>(this).allows_selection
+    {
+        return;
+    }
+    let touch: id = msg![env; touches anyObject];
+    let location: CGPoint = msg![env; touch locationInView:this];
+    let row = row_at_y(env, this, location.y);
+    set_touched_row(env, this, row);
+}
+
+- (())touchesMoved:(id)touches // NSSet* of UITouch*
+         withEvent:(id)event { // UIEvent*
+    () = msg_super![env; this touchesMoved:touches withEvent:event];
+    let dragging: bool = msg![env; this isDragging];
+    if dragging {
+        set_touched_row(env, this, None);
+    }
+}
+
+- (())touchesEnded:(id)touches // NSSet* of UITouch*
+         withEvent:(id)event { // UIEvent*
+    let was_tracking: bool = msg![env; this isTracking];
+    let was_dragging: bool = msg![env; this isDragging];
+    () = msg_super![env; this touchesEnded:touches withEvent:event];
+    let is_tracking: bool = msg![env; this isTracking];
+    // Only handle the end of the touch the scroll view was tracking.
+    if !was_tracking || is_tracking {
+        return;
+    }
+    let touched_row = env.objc.borrow::<UITableViewHostObject>(this).touched_row;
+    set_touched_row(env, this, None);
+    if was_dragging {
+        return;
+    }
+    if let Some(row) = touched_row {
+        user_selected_row(env, this, row);
+    }
+}
+
+- (())touchesCancelled:(id)touches // NSSet* of UITouch*
+             withEvent:(id)event { // UIEvent*
+    () = msg_super![env; this touchesCancelled:touches withEvent:event];
+    set_touched_row(env, this, None);
+}
+
+@end
+
+};
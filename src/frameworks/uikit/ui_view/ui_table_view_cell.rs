@@ -0,0 +1,387 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UITableViewCell`.
+//!
+//! Accessory views (disclosure indicators etc) aren't drawn yet, but space is
+//! reserved for them.
+
+use super::UIViewHostObject;
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::NSInteger;
+use crate::frameworks::uikit::ui_font::{UITextAlignmentLeft, UITextAlignmentRight};
+use crate::objc::{
+    id, impl_HostObject_with_superclass, msg, msg_class, msg_super, nil, objc_classes, release,
+    ClassExports, NSZonePtr,
+};
+use crate::Environment;
+
+pub type UITableViewCellStyle = NSInteger;
+pub const UITableViewCellStyleDefault: UITableViewCellStyle = 0;
+pub const UITableViewCellStyleValue1: UITableViewCellStyle = 1;
+pub const UITableViewCellStyleValue2: UITableViewCellStyle = 2;
+pub const UITableViewCellStyleSubtitle: UITableViewCellStyle = 3;
+
+pub type UITableViewCellSelectionStyle = NSInteger;
+pub const UITableViewCellSelectionStyleNone: UITableViewCellSelectionStyle = 0;
+pub const UITableViewCellSelectionStyleBlue: UITableViewCellSelectionStyle = 1;
+pub const UITableViewCellSelectionStyleGray: UITableViewCellSelectionStyle = 2;
+
+pub type UITableViewCellAccessoryType = NSInteger;
+pub const UITableViewCellAccessoryNone: UITableViewCellAccessoryType = 0;
+
+/// Horizontal padding around the cell's contents.
+const PADDING: CGFloat = 10.0;
+/// Width reserved for an accessory.
+const ACCESSORY_WIDTH: CGFloat = 20.0;
+
+#[derive(Default)]
+struct UITableViewCellHostObject {
+    superclass: UIViewHostObject,
+    style: UITableViewCellStyle,
+    /// `NSString*`, strong reference.
+    reuse_identifier: id,
+    /// Weak references (these are subviews of the cell).
+    content_view: id,
+    selected_background_view: id,
+    text_label: id,
+    /// `nil` for [UITableViewCellStyleDefault].
+    detail_text_label: id,
+    image_view: id,
+    selected: bool,
+    highlighted: bool,
+    selection_style: UITableViewCellSelectionStyle,
+    accessory_type: UITableViewCellAccessoryType,
+}
+impl_HostObject_with_superclass!(UITableViewCellHostObject);
+
+fn new_label(env: &mut Environment, parent: id, font: id, text_color: id) -> id {
+    let label: id = msg_class![env; UILabel new];
+    () = msg![env; label setFont:font];
+    () = msg![env; label setTextColor:text_color];
+    let bg_color: id = msg_class![env; UIColor clearColor];
+    () = msg![env; label setBackgroundColor:bg_color];
+    () = msg![env; parent addSubview:label];
+    release(env, label);
+    label
+}
+
+/// Shared parts of the initializers.
+fn init_common(env: &mut Environment, this: id, style: UITableViewCellStyle, reuse_identifier: id) {
+    let reuse_identifier: id = msg![env; reuse_identifier copy];
+
+    let white: id = msg_class![env; UIColor whiteColor];
+    () = msg![env; this setBackgroundColor:white];
+
+    let selected_background_view: id = msg_class![env; UIView new];
+    let blue: id = msg_class![env; UIColor colorWithRed:(0.02 as CGFloat)
+                                                   green:(0.47 as CGFloat)
+                                                    blue:(0.93 as CGFloat)
+                                                   alpha:(1.0 as CGFloat)];
+    () = msg![env; selected_background_view setBackgroundColor:blue];
+    () = msg![env; selected_background_view setHidden:true];
+    () = msg![env; this addSubview:selected_background_view];
+    release(env, selected_background_view);
+
+    let content_view: id = msg_class![env; UIView new];
+    let clear: id = msg_class![env; UIColor clearColor];
+    () = msg![env; content_view setBackgroundColor:clear];
+    () = msg![env; this addSubview:content_view];
+    release(env, content_view);
+
+    let image_view: id = msg_class![env; UIImageView new];
+    () = msg![env; content_view addSubview:image_view];
+    release(env, image_view);
+
+    // Fonts and colours approximate those of iPhone OS 3.
+    let black: id = msg_class![env; UIColor blackColor];
+    let (text_font_size, detail_font_size) = match style {
+        UITableViewCellStyleValue1 => (17.0, 17.0),
+        UITableViewCellStyleValue2 => (12.0, 15.0),
+        UITableViewCellStyleSubtitle => (18.0, 14.0),
+        _ => (20.0, 0.0),
+    };
+    let text_font: id = msg_class![env; UIFont boldSystemFontOfSize:(text_font_size as CGFloat)];
+    let text_color = if style == UITableViewCellStyleValue2 {
+        msg_class![env; UIColor colorWithRed:(0.32 as CGFloat)
+                                       green:(0.4 as CGFloat)
+                                        blue:(0.57 as CGFloat)
+                                       alpha:(1.0 as CGFloat)]
+    } else {
+        black
+    };
+    let text_label = new_label(env, content_view, text_font, text_color);
+
+    let detail_text_label = if style == UITableViewCellStyleDefault {
+        nil
+    } else {
+        let detail_font: id = if style == UITableViewCellStyleValue2 {
+            msg_class![env; UIFont boldSystemFontOfSize:(detail_font_size as CGFloat)]
+        } else {
+            msg_class![env; UIFont systemFontOfSize:(detail_font_size as CGFloat)]
+        };
+        let detail_color: id = match style {
+            UITableViewCellStyleValue1 => msg_class![env; UIColor colorWithRed:(0.22 as CGFloat)
+                                                                          green:(0.33 as CGFloat)
+                                                                           blue:(0.53 as CGFloat)
+                                                                          alpha:(1.0 as CGFloat)],
+            UITableViewCellStyleSubtitle => msg_class![env; UIColor grayColor],
+            _ => black,
+        };
+        let label = new_label(env, content_view, detail_font, detail_color);
+        if style == UITableViewCellStyleValue1 {
+            () = msg![env; label setTextAlignment:UITextAlignmentRight];
+        }
+        label
+    };
+    if style == UITableViewCellStyleValue2 {
+        () = msg![env; text_label setTextAlignment:UITextAlignmentRight];
+    } else {
+        () = msg![env; text_label setTextAlignment:UITextAlignmentLeft];
+    }
+
+    let host_obj = env.objc.borrow_mut::<UITableViewCellHostObject>(this);
+    host_obj.style = style;
+    host_obj.reuse_identifier = reuse_identifier;
+    host_obj.content_view = content_view;
+    host_obj.selected_background_view = selected_background_view;
+    host_obj.text_label = text_label;
+    host_obj.detail_text_label = detail_text_label;
+    host_obj.image_view = image_view;
+    host_obj.selection_style = UITableViewCellSelectionStyleBlue;
+    host_obj.accessory_type = UITableViewCellAccessoryNone;
+}
+
+fn update_selection_appearance(env: &mut Environment, this: id) {
+    let host_obj = env.objc.borrow::<UITableViewCellHostObject>(this);
+    let show = (host_obj.selected || host_obj.highlighted)
+        && host_obj.selection_style != UITableViewCellSelectionStyleNone;
+    let selection_style = host_obj.selection_style;
+    let view = host_obj.selected_background_view;
+    if selection_style == UITableViewCellSelectionStyleGray {
+        let gray: id = msg_class![env; UIColor grayColor];
+        () = msg![env; view setBackgroundColor:gray];
+    }
+    () = msg![env; view setHidden:(!show)];
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UITableViewCell: UIView
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::<UITableViewCellHostObject>::default();
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (id)initWithStyle:(UITableViewCellStyle)style
+    reuseIdentifier:(id)reuse_identifier { // NSString*
+    let frame = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: CGSize { width: 320.0, height: 44.0 },
+    };
+    let this: id = msg_super![env; this initWithFrame:frame];
+    init_common(env, this, style, reuse_identifier);
+    this
+}
+
+// iPhone OS 2.x initializer
+- (id)initWithFrame:(CGRect)frame
+    reuseIdentifier:(id)reuse_identifier { // NSString*
+    let this: id = msg_super![env; this initWithFrame:frame];
+    init_common(env, this, UITableViewCellStyleDefault, reuse_identifier);
+    this
+}
+
+- (id)initWithFrame:(CGRect)frame {
+    msg![env; this initWithFrame:frame reuseIdentifier:nil]
+}
+
+- (())dealloc {
+    let reuse_identifier = env.objc.borrow::<UITableViewCellHostObject>(this).reuse_identifier;
+    release(env, reuse_identifier);
+    msg_super![env; this dealloc]
+}
+
+- (id)reuseIdentifier {
+    env.objc.borrow::<UITableViewCellHostObject>(this).reuse_identifier
+}
+
+- (())prepareForReuse {
+    let host_obj = env.objc.borrow_mut::<UITableViewCellHostObject>(this);
+    host_obj.selected = false;
+    host_obj.highlighted = false;
+    update_selection_appearance(env, this);
+}
+
+- (id)contentView {
+    env.objc.borrow::<UITableViewCellHostObject>(this).content_view
+}
+- (id)textLabel {
+    env.objc.borrow::<UITableViewCellHostObject>(this).text_label
+}
+- (id)detailTextLabel {
+    env.objc.borrow::<UITableViewCellHostObject>(this).detail_text_label
+}
+- (id)imageView {
+    env.objc.borrow::<UITableViewCellHostObject>(this).image_view
+}
+- (id)selectedBackgroundView {
+    env.objc.borrow::<UITableViewCellHostObject>(this).selected_background_view
+}
+
+// iPhone OS 2.x properties, deprecated in favour of the labels and image view.
+- (id)text {
+    let label = env.objc.borrow::<UITableViewCellHostObject>(this).text_label;
+    msg![env; label text]
+}
+- (())setText:(id)text { // NSString*
+    let label = env.objc.borrow::<UITableViewCellHostObject>(this).text_label;
+    msg![env; label setText:text]
+}
+- (id)font {
+    let label = env.objc.borrow::<UITableViewCellHostObject>(this).text_label;
+    msg![env; label font]
+}
+- (())setFont:(id)font { // UIFont*
+    let label = env.objc.borrow::<UITableViewCellHostObject>(this).text_label;
+    msg![env; label setFont:font]
+}
+- (id)textColor {
+    let label = env.objc.borrow::<UITableViewCellHostObject>(this).text_label;
+    msg![env; label textColor]
+}
+- (())setTextColor:(id)color { // UIColor*
+    let label = env.objc.borrow::<UITableViewCellHostObject>(this).text_label;
+    msg![env; label setTextColor:color]
+}
+- (id)image {
+    let image_view = env.objc.borrow::<UITableViewCellHostObject>(this).image_view;
+    msg![env; image_view image]
+}
+- (())setImage:(id)image { // UIImage*
+    let image_view = env.objc.borrow::<UITableViewCellHostObject>(this).image_view;
+    () = msg![env; image_view setImage:image];
+    () = msg![env; this setNeedsLayout];
+}
+
+- (bool)isSelected {
+    env.objc.borrow::<UITableViewCellHostObject>(this).selected
+}
+- (())setSelected:(bool)selected {
+    msg![env; this setSelected:selected animated:false]
+}
+- (())setSelected:(bool)selected
+         animated:(bool)_animated {
+    env.objc.borrow_mut::<UITableViewCellHostObject>(this).selected = selected;
+    update_selection_appearance(env, this);
+}
+
+- (bool)isHighlighted {
+    env.objc.borrow::<UITableViewCellHostObject>(this).highlighted
+}
+- (())setHighlighted:(bool)highlighted {
+    msg![env; this setHighlighted:highlighted animated:false]
+}
+- (())setHighlighted:(bool)highlighted
+            animated:(bool)_animated {
+    env.objc.borrow_mut::<UITableViewCellHostObject>(this).highlighted = highlighted;
+    update_selection_appearance(env, this);
+}
+
+- (UITableViewCellSelectionStyle)selectionStyle {
+    env.objc.borrow::<UITableViewCellHostObject>(this).selection_style
+}
+- (())setSelectionStyle:(UITableViewCellSelectionStyle)style {
+    env.objc.borrow_mut::<UITableViewCellHostObject>(this).selection_style = style;
+    update_selection_appearance(env, this);
+}
+
+- (UITableViewCellAccessoryType)accessoryType {
+    env.objc.borrow::<UITableViewCellHostObject>(this).accessory_type
+}
+- (())setAccessoryType:(UITableViewCellAccessoryType)accessory_type {
+    // TODO: draw accessories
+    env.objc.borrow_mut::<UITableViewCellHostObject>(this).accessory_type = accessory_type;
+    () = msg![env; this setNeedsLayout];
+}
+
+- (())setFrame:(CGRect)frame {
+    () = msg_super![env; this setFrame:frame];
+    () = msg![env; this setNeedsLayout];
+}
+
+- (())layoutSubviews {
+    let bounds: CGRect = msg![env; this bounds];
+    let &UITableViewCellHostObject {
+        style,
+        content_view,
+        selected_background_view,
+        text_label,
+        detail_text_label,
+        image_view,
+        accessory_type,
+        ..
+    } = env.objc.borrow(this);
+
+    () = msg![env; selected_background_view setFrame:bounds];
+    let mut content_frame = bounds;
+    if accessory_type != UITableViewCellAccessoryNone {
+        content_frame.size.width -= ACCESSORY_WIDTH;
+    }
+    () = msg![env; content_view setFrame:content_frame];
+
+    let height = content_frame.size.height;
+    let mut x = PADDING;
+    let image: id = msg![env; image_view image];
+    if image != nil {
+        let size: CGSize = msg![env; image size];
+        let size = CGSize {
+            width: size.width.min(height),
+            height: size.height.min(height),
+        };
+        let frame = CGRect {
+            origin: CGPoint { x, y: (height - size.height) / 2.0 },
+            size,
+        };
+        () = msg![env; image_view setFrame:frame];
+        x += size.width + PADDING;
+    }
+    let width = (content_frame.size.width - x - PADDING).max(0.0);
+    let rect = |x: CGFloat, y: CGFloat, width: CGFloat, height: CGFloat| CGRect {
+        origin: CGPoint { x, y },
+        size: CGSize { width, height },
+    };
+
+    let (text_frame, detail_frame) = match style {
+        UITableViewCellStyleValue1 => (
+            rect(x, 0.0, width / 2.0, height),
+            rect(x + width / 2.0, 0.0, width / 2.0, height),
+        ),
+        UITableViewCellStyleValue2 => {
+            let label_width = width / 4.0;
+            let detail_x = x + label_width + PADDING / 2.0;
+            (
+                rect(x, 0.0, label_width, height),
+                rect(detail_x, 0.0, x + width - detail_x, height),
+            )
+        }
+        UITableViewCellStyleSubtitle => (
+            rect(x, height * 0.08, width, height * 0.5),
+            rect(x, height * 0.55, width, height * 0.37),
+        ),
+        _ => (rect(x, 0.0, width, height), CGRect::default()),
+    };
+    () = msg![env; text_label setFrame:text_frame];
+    if detail_text_label != nil {
+        () = msg![env; detail_text_label setFrame:detail_frame];
+    }
+}
+
+@end
+
+};
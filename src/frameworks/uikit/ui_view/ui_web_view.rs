@@ -0,0 +1,947 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! `UIWebView`.
+//!
+//! This is not a browser! It's just enough to show the local HTML help and
+//! credits pages that some apps have: see [html] for what is understood. Pages
+//! are laid out once, drawn into a single view which is put in a scroll view,
+//! and links to other local pages can be followed. There is no JavaScript
+//! engine and nothing can be loaded from the network.
+
+mod html;
+
+use super::UIViewHostObject;
+use crate::frameworks::core_graphics::cg_context::{CGContextFillRect, CGContextSetRGBFillColor};
+use crate::frameworks::core_graphics::{CGFloat, CGPoint, CGRect, CGSize};
+use crate::frameworks::foundation::ns_url_request::request_info;
+use crate::frameworks::foundation::{ns_data, ns_error, ns_string, NSInteger, NSUInteger};
+use crate::frameworks::uikit::ui_font;
+use crate::frameworks::uikit::ui_graphics::UIGraphicsGetCurrentContext;
+use crate::fs::GuestPath;
+use crate::objc::{
+    autorelease, id, impl_HostObject_with_superclass, msg, msg_class, msg_send, msg_super, nil,
+    objc_classes, release, retain, ClassExports, NSZonePtr,
+};
+use crate::Environment;
+use html::{Alignment, BlockKind, Color, Inline};
+
+pub type UIWebViewNavigationType = NSInteger;
+pub const UIWebViewNavigationTypeLinkClicked: UIWebViewNavigationType = 0;
+pub const UIWebViewNavigationTypeBackForward: UIWebViewNavigationType = 2;
+pub const UIWebViewNavigationTypeReload: UIWebViewNavigationType = 3;
+pub const UIWebViewNavigationTypeOther: UIWebViewNavigationType = 5;
+
+/// Space around the page's contents.
+const PAGE_MARGIN: CGFloat = 8.0;
+
+#[derive(Default)]
+struct UIWebViewHostObject {
+    superclass: UIViewHostObject,
+    /// Weak reference.
+    delegate: id,
+    /// Weak references (these are subviews).
+    scroll_view: id,
+    document_view: id,
+    document: Option<html::Document>,
+    /// Guest directory that relative URLs in the page are resolved against.
+    base_path: Option<String>,
+    /// `NSURLRequest*` for the current page, if it was loaded from one. Strong
+    /// reference.
+    request: id,
+    /// `NSURLRequest*`s, strong references.
+    back_list: Vec<id>,
+    forward_list: Vec<id>,
+    /// Error message if the current load failed, reported to the delegate
+    /// when the load finishes.
+    load_error: Option<String>,
+    loading: bool,
+    scales_page_to_fit: bool,
+    /// Width the document was last laid out for.
+    layout_width: Option<CGFloat>,
+}
+impl_HostObject_with_superclass!(UIWebViewHostObject);
+
+#[derive(Clone)]
+enum DrawItem {
+    Text {
+        origin: CGPoint,
+        text: String,
+        /// Index into [DocumentViewHostObject::fonts].
+        font: usize,
+        color: Color,
+    },
+    Rect {
+        rect: CGRect,
+        color: Color,
+    },
+}
+
+#[derive(Default)]
+struct DocumentViewHostObject {
+    superclass: UIViewHostObject,
+    /// `UIWebView*`, weak reference.
+    web_view: id,
+    items: Vec<DrawItem>,
+    /// `UIFont*`s, strong references.
+    fonts: Vec<id>,
+    /// Areas that can be tapped to follow a link, with the link's `href`.
+    links: Vec<(CGRect, String)>,
+}
+impl_HostObject_with_superclass!(DocumentViewHostObject);
+
+fn tell_delegate(env: &mut Environment, this: id, selector: &str) {
+    let delegate = env.objc.borrow::<UIWebViewHostObject>(this).delegate;
+    if delegate != nil
+        && env
+            .objc
+            .object_has_method_named(&env.mem, delegate, selector)
+    {
+        let selector = env.objc.lookup_selector(selector).unwrap();
+        () = msg_send(env, (delegate, selector, this));
+    }
+}
+
+fn should_start_load(
+    env: &mut Environment,
+    this: id,
+    request: id,
+    navigation_type: UIWebViewNavigationType,
+) -> bool {
+    let delegate = env.objc.borrow::<UIWebViewHostObject>(this).delegate;
+    if delegate != nil
+        && env.objc.object_has_method_named(
+            &env.mem,
+            delegate,
+            "webView:shouldStartLoadWithRequest:navigationType:",
+        )
+    {
+        msg![env; delegate webView:this
+            shouldStartLoadWithRequest:request
+                        navigationType:navigation_type]
+    } else {
+        true
+    }
+}
+
+/// Resolve a URL found in a page to a guest path, if it refers to a local
+/// file.
+fn resolve_local_url(base_path: Option<&str>, url: &str) -> Option<String> {
+    let url = url.split(['#', '?']).next().unwrap();
+    if let Some(path) = url.strip_prefix("file://") {
+        return Some(path.to_string());
+    }
+    if url.contains(':') {
+        return None; // Some other scheme, e.g. http: or mailto:
+    }
+    if url.starts_with('/') {
+        return Some(url.to_string());
+    }
+    let mut components = vec![""];
+    components.extend(base_path.unwrap_or("").split('/').filter(|c| !c.is_empty()));
+    for component in url.split('/') {
+        match component {
+            "" | "." => (),
+            ".." => {
+                if components.len() > 1 {
+                    components.pop();
+                }
+            }
+            _ => components.push(component),
+        }
+    }
+    Some(components.join("/"))
+}
+
+/// Start loading a page. The delegate will be told when the load finishes.
+fn start_load(
+    env: &mut Environment,
+    this: id,
+    html: Result<String, String>,
+    base_path: Option<String>,
+) {
+    let (document, load_error) = match html {
+        Ok(html) => (Some(html::parse(&html)), None),
+        Err(error) => (None, Some(error)),
+    };
+    let host_obj = env.objc.borrow_mut::<UIWebViewHostObject>(this);
+    // Keep showing the old page if the new one failed to load.
+    if document.is_some() {
+        host_obj.document = document;
+        host_obj.layout_width = None;
+    }
+    host_obj.base_path = base_path;
+    host_obj.load_error = load_error;
+    let was_loading = std::mem::replace(&mut host_obj.loading, true);
+    if document_changed(env, this) {
+        () = msg![env; this setNeedsLayout];
+    }
+
+    // Real UIWebView loads asynchronously, and apps may rely on the delegate
+    // not being called until the run loop runs.
+    if !was_loading {
+        let selector = env.objc.lookup_selector("_touchHLE_finishLoad:").unwrap();
+        let _: id = msg_class![env; NSTimer scheduledTimerWithTimeInterval:0.0
+                                                                    target:this
+                                                                  selector:selector
+                                                                  userInfo:nil
+                                                                   repeats:false];
+    }
+}
+
+fn document_changed(env: &Environment, this: id) -> bool {
+    env.objc
+        .borrow::<UIWebViewHostObject>(this)
+        .layout_width
+        .is_none()
+}
+
+/// Load a page from an `NSURLRequest*`. Returns [false] if the delegate
+/// didn't allow it.
+fn load_request(
+    env: &mut Environment,
+    this: id,
+    request: id,
+    navigation_type: UIWebViewNavigationType,
+) -> bool {
+    if !should_start_load(env, this, request, navigation_type) {
+        return false;
+    }
+    retain(env, request);
+    let host_obj = env.objc.borrow_mut::<UIWebViewHostObject>(this);
+    let old_request = std::mem::replace(&mut host_obj.request, request);
+    release(env, old_request);
+
+    let url = request_info(env, request).url;
+    let Some(path) = resolve_local_url(None, &url) else {
+        log!("TODO: UIWebView can't load non-local URL {:?}", url);
+        start_load(env, this, Err(format!("Can't load {}", url)), None);
+        return true;
+    };
+    log_dbg!("UIWebView {:?} loading {:?}", this, path);
+    let base_path = GuestPath::new(&path)
+        .parent()
+        .map(|parent| parent.as_str().to_string());
+    let html = env
+        .fs
+        .read(GuestPath::new(&path))
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+        .map_err(|()| format!("Couldn't read {}", path));
+    start_load(env, this, html, base_path);
+    true
+}
+
+/// Handle the user tapping a link.
+fn follow_link(env: &mut Environment, this: id, href: &str) {
+    if href.starts_with('#') {
+        log!("TODO: UIWebView link to anchor {:?}", href);
+        return;
+    }
+    let base_path = env
+        .objc
+        .borrow::<UIWebViewHostObject>(this)
+        .base_path
+        .clone();
+    let url: id = match resolve_local_url(base_path.as_deref(), href) {
+        Some(path) => {
+            let path = ns_string::from_rust_string(env, path);
+            let url: id = msg_class![env; NSURL fileURLWithPath:path];
+            release(env, path);
+            url
+        }
+        None => {
+            let href = ns_string::from_rust_string(env, href.to_string());
+            let url: id = msg_class![env; NSURL URLWithString:href];
+            release(env, href);
+            url
+        }
+    };
+    let request: id = msg_class![env; NSURLRequest requestWithURL:url];
+    let old_request = env.objc.borrow::<UIWebViewHostObject>(this).request;
+    if old_request != nil {
+        retain(env, old_request);
+    }
+    if load_request(env, this, request, UIWebViewNavigationTypeLinkClicked) {
+        if old_request != nil {
+            let host_obj = env.objc.borrow_mut::<UIWebViewHostObject>(this);
+            host_obj.back_list.push(old_request);
+            let forward_list = std::mem::take(&mut host_obj.forward_list);
+            for request in forward_list {
+                release(env, request);
+            }
+        }
+    } else {
+        release(env, old_request);
+    }
+}
+
+enum PieceKind {
+    Text {
+        text: String,
+        font: usize,
+        color: Color,
+        link: Option<usize>,
+    },
+    /// `UIImage*`
+    Image(id),
+}
+struct Piece {
+    kind: PieceKind,
+    x: CGFloat,
+    size: CGSize,
+}
+
+/// State while laying out a document.
+struct Layout<'a> {
+    document: &'a html::Document,
+    /// `_touchHLE_UIWebDocumentView*`
+    document_view: id,
+    /// Font style and size, and the `UIFont*` (strong reference).
+    fonts: Vec<(bool, bool, CGFloat, id)>,
+    items: Vec<DrawItem>,
+    links: Vec<(CGRect, String)>,
+    /// Pieces on the line currently being laid out.
+    line: Vec<Piece>,
+    /// Horizontal position on the current line, relative to the block.
+    x: CGFloat,
+    /// Top of the current line.
+    y: CGFloat,
+}
+
+impl Layout<'_> {
+    fn get_font(&mut self, env: &mut Environment, style: &html::TextStyle) -> usize {
+        let key = (style.bold, style.italic, style.size);
+        if let Some(idx) = self
+            .fonts
+            .iter()
+            .position(|&(bold, italic, size, _)| (bold, italic, size) == key)
+        {
+            return idx;
+        }
+        // There is no bold italic font.
+        let font: id = if style.bold {
+            msg_class![env; UIFont boldSystemFontOfSize:(style.size)]
+        } else if style.italic {
+            msg_class![env; UIFont italicSystemFontOfSize:(style.size)]
+        } else {
+            msg_class![env; UIFont systemFontOfSize:(style.size)]
+        };
+        retain(env, font);
+        self.fonts
+            .push((style.bold, style.italic, style.size, font));
+        self.fonts.len() - 1
+    }
+
+    /// Add something to the current line, starting a new line first if it
+    /// wouldn't fit.
+    fn add_piece(
+        &mut self,
+        env: &mut Environment,
+        block: &html::Block,
+        kind: PieceKind,
+        size: CGSize,
+        width: CGFloat,
+    ) {
+        if !self.line.is_empty() && !block.preformatted && self.x + size.width > width {
+            self.finish_line(env, block, width, 0.0);
+        }
+        self.line.push(Piece {
+            kind,
+            x: self.x,
+            size,
+        });
+        self.x += size.width;
+    }
+
+    fn finish_line(
+        &mut self,
+        env: &mut Environment,
+        block: &html::Block,
+        width: CGFloat,
+        min_height: CGFloat,
+    ) {
+        let line_width = self.x;
+        let line_height = self
+            .line
+            .iter()
+            .map(|piece| piece.size.height)
+            .fold(min_height, CGFloat::max);
+        let x_offset = PAGE_MARGIN
+            + block.indent
+            + match block.alignment {
+                Alignment::Left => 0.0,
+                Alignment::Center => ((width - line_width) / 2.0).max(0.0),
+                Alignment::Right => (width - line_width).max(0.0),
+            };
+        for piece in std::mem::take(&mut self.line) {
+            // Align everything to the bottom of the line, which is close
+            // enough to aligning text baselines.
+            let rect = CGRect {
+                origin: CGPoint {
+                    x: x_offset + piece.x,
+                    y: self.y + line_height - piece.size.height,
+                },
+                size: piece.size,
+            };
+            match piece.kind {
+                PieceKind::Text {
+                    text,
+                    font,
+                    color,
+                    link,
+                } => {
+                    if let Some(link) = link {
+                        self.links.push((rect, self.document.links[link].clone()));
+                    }
+                    self.items.push(DrawItem::Text {
+                        origin: rect.origin,
+                        text,
+                        font,
+                        color,
+                    });
+                }
+                PieceKind::Image(image) => {
+                    let image_view: id = msg_class![env; UIImageView alloc];
+                    let image_view: id = msg![env; image_view initWithImage:image];
+                    () = msg![env; image_view setFrame:rect];
+                    () = msg![env; (self.document_view) addSubview:image_view];
+                    release(env, image_view);
+                }
+            }
+        }
+        self.x = 0.0;
+        self.y += line_height;
+    }
+
+    fn lay_out_text(
+        &mut self,
+        env: &mut Environment,
+        block: &html::Block,
+        text: &str,
+        style: &html::TextStyle,
+        width: CGFloat,
+    ) -> CGFloat {
+        let font_idx = self.get_font(env, style);
+        let font = self.fonts[font_idx].3;
+        let line_height = ui_font::size_with_font(env, font, " ", None).height;
+        let words: Vec<&str> = if block.preformatted {
+            text.split_inclusive('\n').collect()
+        } else {
+            text.split_inclusive(' ').collect()
+        };
+        for word in words {
+            let (word, newline) = match word.strip_suffix('\n') {
+                Some(word) => (word, true),
+                None => (word, false),
+            };
+            // Spaces at the start of a line aren't shown.
+            let word = if self.line.is_empty() && !block.preformatted {
+                word.trim_start()
+            } else {
+                word
+            };
+            if !word.is_empty() {
+                let size = ui_font::size_with_font(env, font, word, None);
+                let kind = PieceKind::Text {
+                    text: word.to_string(),
+                    font: font_idx,
+                    color: style.color,
+                    link: style.link,
+                };
+                self.add_piece(env, block, kind, size, width);
+            }
+            if newline {
+                self.finish_line(env, block, width, line_height);
+            }
+        }
+        line_height
+    }
+
+    fn lay_out_image(
+        &mut self,
+        env: &mut Environment,
+        block: &html::Block,
+        base_path: Option<&str>,
+        (src, width_attr, height_attr): (&str, Option<CGFloat>, Option<CGFloat>),
+        width: CGFloat,
+    ) {
+        let Some(path) = resolve_local_url(base_path, src) else {
+            log!("TODO: UIWebView can't load non-local image {:?}", src);
+            return;
+        };
+        let path = ns_string::from_rust_string(env, path);
+        let image: id = msg_class![env; UIImage imageWithContentsOfFile:path];
+        release(env, path);
+        if image == nil {
+            log!("Warning: UIWebView couldn't load image {:?}", src);
+            return;
+        }
+        let natural: CGSize = msg![env; image size];
+        let mut size = match (width_attr, height_attr) {
+            (Some(width), Some(height)) => CGSize { width, height },
+            (Some(width), None) => CGSize {
+                width,
+                height: natural.height * width / natural.width,
+            },
+            (None, Some(height)) => CGSize {
+                width: natural.width * height / natural.height,
+                height,
+            },
+            (None, None) => natural,
+        };
+        if size.width > width {
+            size = CGSize {
+                width,
+                height: size.height * width / size.width,
+            };
+        }
+        self.add_piece(env, block, PieceKind::Image(image), size, width);
+    }
+}
+
+/// Lay out the current document for the view's width, replacing the contents
+/// of the document view.
+fn lay_out_document(env: &mut Environment, this: id) {
+    let bounds: CGRect = msg![env; this bounds];
+    let host_obj = env.objc.borrow_mut::<UIWebViewHostObject>(this);
+    host_obj.layout_width = Some(bounds.size.width);
+    let scroll_view = host_obj.scroll_view;
+    let document_view = host_obj.document_view;
+    let base_path = host_obj.base_path.clone();
+    let Some(document) = host_obj.document.take() else {
+        return;
+    };
+
+    // Clear out the old layout.
+    let old_host_obj = env.objc.borrow_mut::<DocumentViewHostObject>(document_view);
+    old_host_obj.items.clear();
+    old_host_obj.links.clear();
+    let old_fonts = std::mem::take(&mut old_host_obj.fonts);
+    for font in old_fonts {
+        release(env, font);
+    }
+    let old_subviews: id = msg![env; document_view subviews];
+    let old_subview_count: NSUInteger = msg![env; old_subviews count];
+    for i in 0..old_subview_count {
+        let subview: id = msg![env; old_subviews objectAtIndex:i];
+        () = msg![env; subview removeFromSuperview];
+    }
+
+    let available_width = bounds.size.width - PAGE_MARGIN * 2.0;
+    let mut layout = Layout {
+        document: &document,
+        document_view,
+        fonts: Vec::new(),
+        items: Vec::new(),
+        links: Vec::new(),
+        line: Vec::new(),
+        x: 0.0,
+        y: PAGE_MARGIN,
+    };
+    for block in &document.blocks {
+        layout.y += block.margin;
+        let width = (available_width - block.indent).max(0.0);
+
+        if block.kind == BlockKind::Rule {
+            layout.items.push(DrawItem::Rect {
+                rect: CGRect {
+                    origin: CGPoint {
+                        x: PAGE_MARGIN + block.indent,
+                        y: layout.y,
+                    },
+                    size: CGSize { width, height: 1.0 },
+                },
+                color: (0.5, 0.5, 0.5, 1.0),
+            });
+            layout.y += 1.0;
+            continue;
+        }
+
+        // Height of an empty line, for <br><br>.
+        let mut line_height = html::DEFAULT_FONT_SIZE;
+        for inline in &block.inlines {
+            match inline {
+                Inline::Text(text, style) => {
+                    line_height = layout.lay_out_text(env, block, text, style, width);
+                }
+                &Inline::Image {
+                    ref src,
+                    width: width_attr,
+                    height: height_attr,
+                } => {
+                    let image = (src.as_str(), width_attr, height_attr);
+                    layout.lay_out_image(env, block, base_path.as_deref(), image, width);
+                }
+                Inline::LineBreak => layout.finish_line(env, block, width, line_height),
+            }
+        }
+        if !layout.line.is_empty() {
+            layout.finish_line(env, block, width, 0.0);
+        }
+    }
+    let Layout {
+        fonts,
+        items,
+        links,
+        y,
+        ..
+    } = layout;
+    let height = y + PAGE_MARGIN;
+
+    let background_color = document.background_color;
+    env.objc.borrow_mut::<UIWebViewHostObject>(this).document = Some(document);
+
+    let host_obj = env.objc.borrow_mut::<DocumentViewHostObject>(document_view);
+    host_obj.items = items;
+    host_obj.links = links;
+    host_obj.fonts = fonts.into_iter().map(|(_, _, _, font)| font).collect();
+
+    let content_size = CGSize {
+        width: bounds.size.width,
+        height: height.max(bounds.size.height),
+    };
+    let document_frame = CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: content_size,
+    };
+    () = msg![env; document_view setFrame:document_frame];
+    if let Some((r, g, b, a)) = background_color {
+        let color: id = msg_class![env; UIColor colorWithRed:r green:g blue:b alpha:a];
+        () = msg![env; document_view setBackgroundColor:color];
+    }
+    () = msg![env; document_view setNeedsDisplay];
+    () = msg![env; scroll_view setContentSize:content_size];
+    let top = CGPoint { x: 0.0, y: 0.0 };
+    () = msg![env; scroll_view setContentOffset:top];
+}
+
+fn init_common(env: &mut Environment, this: id) {
+    let bounds: CGRect = msg![env; this bounds];
+
+    let scroll_view: id = msg_class![env; UIScrollView alloc];
+    let scroll_view: id = msg![env; scroll_view initWithFrame:bounds];
+    () = msg![env; this addSubview:scroll_view];
+    release(env, scroll_view);
+
+    let document_view: id = msg_class![env; _touchHLE_UIWebDocumentView alloc];
+    let document_view: id = msg![env; document_view initWithFrame:bounds];
+    env.objc
+        .borrow_mut::<DocumentViewHostObject>(document_view)
+        .web_view = this;
+    let white: id = msg_class![env; UIColor whiteColor];
+    () = msg![env; document_view setBackgroundColor:white];
+    () = msg![env; scroll_view addSubview:document_view];
+    release(env, document_view);
+
+    let host_obj = env.objc.borrow_mut::<UIWebViewHostObject>(this);
+    host_obj.scroll_view = scroll_view;
+    host_obj.document_view = document_view;
+}
+
+pub const CLASSES: ClassExports = objc_classes! {
+
+(env, this, _cmd);
+
+@implementation UIWebView: UIView
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::<UIWebViewHostObject>::default();
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (id)initWithFrame:(CGRect)frame {
+    let this: id = msg_super![env; this initWithFrame:frame];
+    init_common(env, this);
+    this
+}
+
+- (id)initWithCoder:(id)coder {
+    let this: id = msg_super![env; this initWithCoder:coder];
+    // TODO: decode properties like scalesPageToFit
+    init_common(env, this);
+    this
+}
+
+- (())dealloc {
+    let host_obj = env.objc.borrow_mut::<UIWebViewHostObject>(this);
+    let request = host_obj.request;
+    let back_list = std::mem::take(&mut host_obj.back_list);
+    let forward_list = std::mem::take(&mut host_obj.forward_list);
+    release(env, request);
+    for request in back_list.into_iter().chain(forward_list) {
+        release(env, request);
+    }
+    msg_super![env; this dealloc]
+}
+
+- (id)delegate {
+    env.objc.borrow::<UIWebViewHostObject>(this).delegate
+}
+- (())setDelegate:(id)delegate { // something implementing UIWebViewDelegate
+    // Weak reference
+    env.objc.borrow_mut::<UIWebViewHostObject>(this).delegate = delegate;
+}
+
+- (id)scrollView {
+    env.objc.borrow::<UIWebViewHostObject>(this).scroll_view
+}
+
+- (id)request {
+    env.objc.borrow::<UIWebViewHostObject>(this).request
+}
+
+- (bool)isLoading {
+    env.objc.borrow::<UIWebViewHostObject>(this).loading
+}
+
+- (bool)scalesPageToFit {
+    env.objc.borrow::<UIWebViewHostObject>(this).scales_page_to_fit
+}
+- (())setScalesPageToFit:(bool)scales {
+    // Pages are always laid out for the view's width, so there is nothing to
+    // scale.
+    env.objc.borrow_mut::<UIWebViewHostObject>(this).scales_page_to_fit = scales;
+}
+
+- (())setDataDetectorTypes:(NSInteger)_types { // UIDataDetectorTypes
+    // TODO: data detectors
+}
+- (())setDetectsPhoneNumbers:(bool)_detects {
+    // TODO: data detectors
+}
+
+- (())loadRequest:(id)request { // NSURLRequest*
+    load_request(env, this, request, UIWebViewNavigationTypeOther);
+}
+
+- (())loadHTMLString:(id)string // NSString*
+             baseURL:(id)base_url { // NSURL*
+    let html = ns_string::to_rust_string(env, string).into_owned();
+    let base_path = if base_url == nil {
+        None
+    } else {
+        let path: id = msg![env; base_url path];
+        Some(ns_string::to_rust_string(env, path).into_owned())
+    };
+    let host_obj = env.objc.borrow_mut::<UIWebViewHostObject>(this);
+    let old_request = std::mem::replace(&mut host_obj.request, nil);
+    release(env, old_request);
+    start_load(env, this, Ok(html), base_path);
+}
+
+- (())loadData:(id)data // NSData*
+      MIMEType:(id)mime_type // NSString*
+textEncodingName:(id)_encoding_name // NSString*
+       baseURL:(id)base_url { // NSURL*
+    let mime_type = ns_string::to_rust_string(env, mime_type);
+    // TODO: respect the encoding name
+    let text = String::from_utf8_lossy(ns_data::to_rust_slice(env, data)).into_owned();
+    let html = if mime_type == "text/html" {
+        text
+    } else {
+        log!("TODO: UIWebView loadData: with MIME type {:?}, showing as text", mime_type);
+        let text = text.replace('&', "&amp;").replace('<', "&lt;");
+        format!("<pre>{}</pre>", text)
+    };
+    let html = ns_string::from_rust_string(env, html);
+    () = msg![env; this loadHTMLString:html baseURL:base_url];
+    release(env, html);
+}
+
+- (())_touchHLE_finishLoad:(id)_timer { // NSTimer*
+    tell_delegate(env, this, "webViewDidStartLoad:");
+    let host_obj = env.objc.borrow_mut::<UIWebViewHostObject>(this);
+    host_obj.loading = false;
+    let load_error = host_obj.load_error.take();
+    let delegate = host_obj.delegate;
+    if document_changed(env, this) {
+        // Make sure the page has been laid out before the delegate is told.
+        () = msg![env; this layoutIfNeeded];
+    }
+    if let Some(error) = load_error {
+        log!("UIWebView {:?} failed to load: {}", this, error);
+        if delegate != nil
+            && env.objc.object_has_method_named(
+                &env.mem,
+                delegate,
+                "webView:didFailLoadWithError:",
+            )
+        {
+            // NSURLErrorDomain, NSURLErrorFileDoesNotExist
+            let error = ns_error::new_error(env, "NSURLErrorDomain", -1100, error);
+            () = msg![env; delegate webView:this didFailLoadWithError:error];
+        }
+    } else {
+        tell_delegate(env, this, "webViewDidFinishLoad:");
+    }
+}
+
+- (())stopLoading {
+    // Loading is instant.
+}
+
+- (())reload {
+    let request = env.objc.borrow::<UIWebViewHostObject>(this).request;
+    if request != nil {
+        retain(env, request);
+        load_request(env, this, request, UIWebViewNavigationTypeReload);
+        release(env, request);
+    }
+}
+
+- (bool)canGoBack {
+    !env.objc.borrow::<UIWebViewHostObject>(this).back_list.is_empty()
+}
+- (bool)canGoForward {
+    !env.objc.borrow::<UIWebViewHostObject>(this).forward_list.is_empty()
+}
+- (())goBack {
+    let Some(request) = env.objc.borrow_mut::<UIWebViewHostObject>(this).back_list.pop() else {
+        return;
+    };
+    let current = env.objc.borrow::<UIWebViewHostObject>(this).request;
+    retain(env, current);
+    if load_request(env, this, request, UIWebViewNavigationTypeBackForward) {
+        release(env, request);
+        if current != nil {
+            env.objc.borrow_mut::<UIWebViewHostObject>(this).forward_list.push(current);
+        }
+    } else {
+        env.objc.borrow_mut::<UIWebViewHostObject>(this).back_list.push(request);
+        release(env, current);
+    }
+}
+- (())goForward {
+    let Some(request) = env.objc.borrow_mut::<UIWebViewHostObject>(this).forward_list.pop() else {
+        return;
+    };
+    let current = env.objc.borrow::<UIWebViewHostObject>(this).request;
+    retain(env, current);
+    if load_request(env, this, request, UIWebViewNavigationTypeBackForward) {
+        release(env, request);
+        if current != nil {
+            env.objc.borrow_mut::<UIWebViewHostObject>(this).back_list.push(current);
+        }
+    } else {
+        env.objc.borrow_mut::<UIWebViewHostObject>(this).forward_list.push(request);
+        release(env, current);
+    }
+}
+
+- (id)stringByEvaluatingJavaScriptFromString:(id)script { // NSString*
+    let script = ns_string::to_rust_string(env, script);
+    let script = script.trim().trim_end_matches(';').trim();
+    let result = match script {
+        "document.title" => env
+            .objc
+            .borrow::<UIWebViewHostObject>(this)
+            .document
+            .as_ref()
+            .and_then(|document| document.title.clone())
+            .unwrap_or_default(),
+        "document.location.href" | "window.location.href" | "location.href" => {
+            let request = env.objc.borrow::<UIWebViewHostObject>(this).request;
+            if request == nil {
+                "about:blank".to_string()
+            } else {
+                request_info(env, request).url
+            }
+        }
+        _ => {
+            log!("TODO: UIWebView can't evaluate JavaScript {:?}, returning empty string", script);
+            String::new()
+        }
+    };
+    let result = ns_string::from_rust_string(env, result);
+    autorelease(env, result)
+}
+
+// UIView implementation
+
+- (())setFrame:(CGRect)frame {
+    () = msg_super![env; this setFrame:frame];
+    () = msg![env; this setNeedsLayout];
+}
+
+- (())layoutSubviews {
+    let bounds: CGRect = msg![env; this bounds];
+    let host_obj = env.objc.borrow::<UIWebViewHostObject>(this);
+    let scroll_view = host_obj.scroll_view;
+    let needs_layout = host_obj.layout_width != Some(bounds.size.width);
+    () = msg![env; scroll_view setFrame:bounds];
+    if needs_layout {
+        lay_out_document(env, this);
+    }
+}
+
+@end
+
+// The view the page is drawn into.
+@implementation _touchHLE_UIWebDocumentView: UIView
+
++ (id)allocWithZone:(NSZonePtr)_zone {
+    let host_object = Box::<DocumentViewHostObject>::default();
+    env.objc.alloc_object(this, host_object, &mut env.mem)
+}
+
+- (())dealloc {
+    let fonts = std::mem::take(&mut env.objc.borrow_mut::<DocumentViewHostObject>(this).fonts);
+    for font in fonts {
+        release(env, font);
+    }
+    msg_super![env; this dealloc]
+}
+
+- (())drawRect:(CGRect)_rect {
+    let context = UIGraphicsGetCurrentContext(env);
+    let host_obj = env.objc.borrow::<DocumentViewHostObject>(this);
+    let items = host_obj.items.clone();
+    let fonts = host_obj.fonts.clone();
+    for item in items {
+        match item {
+            DrawItem::Text { origin, text, font, color: (r, g, b, a) } => {
+                CGContextSetRGBFillColor(env, context, r, g, b, a);
+                ui_font::draw_at_point(env, fonts[font], &text, origin, None);
+            }
+            DrawItem::Rect { rect, color: (r, g, b, a) } => {
+                CGContextSetRGBFillColor(env, context, r, g, b, a);
+                CGContextFillRect(env, context, rect);
+            }
+        }
+    }
+}
+
+- (())touchesEnded:(id)touches // NSSet* of UITouch*
+         withEvent:(id)event { // UIEvent*
+    let web_view = env.objc.borrow::<DocumentViewHostObject>(this).web_view;
+    let scroll_view = env.objc.borrow::<UIWebViewHostObject>(web_view).scroll_view;
+    let was_dragging: bool = msg![env; scroll_view isDragging];
+    // The scroll view handles scrolling.
+    () = msg_super![env; this touchesEnded:touches withEvent:event];
+    if was_dragging {
+        return;
+    }
+    let touch: id = msg![env; touches anyObject];
+    let location: CGPoint = msg![env; touch locationInView:this];
+    let href = env
+        .objc
+        .borrow::<DocumentViewHostObject>(this)
+        .links
+        .iter()
+        .find(|(rect, _)| {
+            location.x >= rect.origin.x
+                && location.x < rect.origin.x + rect.size.width
+                && location.y >= rect.origin.y
+                && location.y < rect.origin.y + rect.size.height
+        })
+        .map(|(_, href)| href.clone());
+    if let Some(href) = href {
+        follow_link(env, web_view, &href);
+    }
+}
+
+@end
+
+};
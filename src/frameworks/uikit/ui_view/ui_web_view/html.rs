@@ -0,0 +1,724 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! A very forgiving parser for the simple HTML documents apps show in
+//! `UIWebView`, such as help and credits screens.
+//!
+//! The document is turned into a list of blocks containing styled runs of text,
+//! which is all the renderer needs. There is no DOM, and only inline styles
+//! (`style="..."` and old-fashioned attributes like `bgcolor`) are understood;
+//! `<style>` and `<script>` elements are ignored.
+
+/// RGBA
+pub type Color = (f32, f32, f32, f32);
+
+pub const DEFAULT_FONT_SIZE: f32 = 16.0;
+const LINK_COLOR: Color = (0.0, 0.0, 0.93, 1.0);
+const LIST_INDENT: f32 = 24.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub size: f32,
+    pub color: Color,
+    /// Index into [Document::links].
+    pub link: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Inline {
+    Text(String, TextStyle),
+    Image {
+        src: String,
+        width: Option<f32>,
+        height: Option<f32>,
+    },
+    LineBreak,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BlockKind {
+    Text,
+    /// `<hr>`
+    Rule,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Block {
+    pub kind: BlockKind,
+    pub alignment: Alignment,
+    pub indent: f32,
+    /// Space above the block, in points.
+    pub margin: f32,
+    /// `<pre>` blocks keep their whitespace.
+    pub preformatted: bool,
+    pub inlines: Vec<Inline>,
+}
+
+#[derive(Debug, Default)]
+pub struct Document {
+    pub title: Option<String>,
+    pub background_color: Option<Color>,
+    pub blocks: Vec<Block>,
+    /// `href`s of links.
+    pub links: Vec<String>,
+}
+
+enum Token {
+    StartTag {
+        name: String,
+        attributes: Vec<(String, String)>,
+    },
+    EndTag {
+        name: String,
+    },
+    Text(String),
+}
+
+fn tokenize(html: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.split_once("-->").map_or("", |(_, after)| after);
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.split_once('>').map_or("", |(_, after)| after);
+            continue;
+        }
+        let is_tag = rest.starts_with('<')
+            && rest[1..]
+                .chars()
+                .next()
+                .map_or(false, |c| c.is_ascii_alphabetic() || c == '/');
+        if is_tag {
+            let (tag, after) = rest[1..].split_once('>').unwrap_or((&rest[1..], ""));
+            rest = after;
+            let tag = tag.strip_suffix('/').unwrap_or(tag);
+            if let Some(name) = tag.strip_prefix('/') {
+                tokens.push(Token::EndTag {
+                    name: name.trim().to_ascii_lowercase(),
+                });
+                continue;
+            }
+            let (name, attributes) = parse_tag(tag);
+            // The contents of these elements aren't HTML, so they must be
+            // skipped without tokenizing.
+            if name == "script" || name == "style" {
+                let end_tag = format!("</{}", name);
+                let lowercase_rest = rest.to_ascii_lowercase();
+                rest = match lowercase_rest.find(&end_tag) {
+                    Some(idx) => rest[idx..].split_once('>').map_or("", |(_, after)| after),
+                    None => "",
+                };
+                continue;
+            }
+            tokens.push(Token::StartTag { name, attributes });
+            continue;
+        }
+        let first_len = rest.chars().next().unwrap().len_utf8();
+        let text_end = rest[first_len..]
+            .find('<')
+            .map_or(rest.len(), |idx| idx + first_len);
+        tokens.push(Token::Text(decode_entities(&rest[..text_end])));
+        rest = &rest[text_end..];
+    }
+    tokens
+}
+
+fn parse_tag(tag: &str) -> (String, Vec<(String, String)>) {
+    let name_end = tag
+        .find(|c: char| c.is_ascii_whitespace())
+        .unwrap_or(tag.len());
+    let name = tag[..name_end].to_ascii_lowercase();
+    let mut rest = &tag[name_end..];
+    let mut attributes = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        let attr_name_end = rest
+            .find(|c: char| c.is_ascii_whitespace() || c == '=')
+            .unwrap_or(rest.len());
+        let attr_name = rest[..attr_name_end].to_ascii_lowercase();
+        rest = rest[attr_name_end..].trim_start();
+        let value = if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (value, after) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    after[1..].split_once(quote).unwrap_or((&after[1..], ""))
+                }
+                _ => {
+                    let value_end = after
+                        .find(|c: char| c.is_ascii_whitespace())
+                        .unwrap_or(after.len());
+                    (&after[..value_end], &after[value_end..])
+                }
+            };
+            rest = after;
+            decode_entities(value)
+        } else {
+            String::new()
+        };
+        if !attr_name.is_empty() {
+            attributes.push((attr_name, value));
+        } else {
+            // Avoid looping forever on junk like a stray `=`.
+            rest = rest.get(1..).unwrap_or("");
+        }
+    }
+    (name, attributes)
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(idx) = rest.find('&') {
+        decoded.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&len| len <= 10)
+            .map(|len| &rest[1..len + 1]);
+        let character = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            "copy" => Some('©'),
+            "reg" => Some('®'),
+            "trade" => Some('™'),
+            "hellip" => Some('…'),
+            "mdash" => Some('—'),
+            "ndash" => Some('–'),
+            "bull" => Some('•'),
+            _ => {
+                let number = entity.strip_prefix('#')?;
+                let code = if let Some(hex) = number.strip_prefix(['x', 'X']) {
+                    u32::from_str_radix(hex, 16).ok()?
+                } else {
+                    number.parse().ok()?
+                };
+                char::from_u32(code)
+            }
+        });
+        if let Some(character) = character {
+            decoded.push(character);
+            rest = &rest[entity.unwrap().len() + 2..];
+        } else {
+            decoded.push('&');
+            rest = &rest[1..];
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+pub fn parse_color(color: &str) -> Option<Color> {
+    let color = color.trim().to_ascii_lowercase();
+    if let Some(hex) = color.strip_prefix('#') {
+        let digits: Vec<u32> = hex.chars().map(|c| c.to_digit(16)).collect::<Option<_>>()?;
+        let (r, g, b) = match digits[..] {
+            [r, g, b] => (r * 17, g * 17, b * 17),
+            [r1, r2, g1, g2, b1, b2] => (r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2),
+            _ => return None,
+        };
+        return Some((r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0));
+    }
+    let (r, g, b) = match color.as_str() {
+        "black" => (0, 0, 0),
+        "white" => (255, 255, 255),
+        "red" => (255, 0, 0),
+        "lime" => (0, 255, 0),
+        "green" => (0, 128, 0),
+        "blue" => (0, 0, 255),
+        "yellow" => (255, 255, 0),
+        "cyan" | "aqua" => (0, 255, 255),
+        "magenta" | "fuchsia" => (255, 0, 255),
+        "gray" | "grey" => (128, 128, 128),
+        "silver" => (192, 192, 192),
+        "maroon" => (128, 0, 0),
+        "olive" => (128, 128, 0),
+        "navy" => (0, 0, 128),
+        "purple" => (128, 0, 128),
+        "teal" => (0, 128, 128),
+        "orange" => (255, 165, 0),
+        "transparent" => return Some((0.0, 0.0, 0.0, 0.0)),
+        _ => return None,
+    };
+    Some((r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0))
+}
+
+fn parse_length(length: &str) -> Option<f32> {
+    let length = length.trim();
+    let length = length.strip_suffix("px").unwrap_or(length).trim();
+    length.parse().ok().filter(|&length: &f32| length >= 0.0)
+}
+
+/// Properties from `style` and presentational attributes.
+#[derive(Default)]
+struct Declarations {
+    color: Option<Color>,
+    background_color: Option<Color>,
+    alignment: Option<Alignment>,
+    font_size: Option<f32>,
+    bold: Option<bool>,
+    italic: Option<bool>,
+}
+
+fn parse_declarations(attributes: &[(String, String)]) -> Declarations {
+    let mut decls = Declarations::default();
+    for (name, value) in attributes {
+        match name.as_str() {
+            "color" | "text" => decls.color = parse_color(value).or(decls.color),
+            "bgcolor" => decls.background_color = parse_color(value).or(decls.background_color),
+            "align" => decls.alignment = parse_alignment(value).or(decls.alignment),
+            "style" => {
+                for declaration in value.split(';') {
+                    let Some((property, value)) = declaration.split_once(':') else {
+                        continue;
+                    };
+                    let value = value.trim();
+                    match property.trim().to_ascii_lowercase().as_str() {
+                        "color" => decls.color = parse_color(value).or(decls.color),
+                        "background-color" | "background" => {
+                            decls.background_color = parse_color(value).or(decls.background_color)
+                        }
+                        "text-align" => {
+                            decls.alignment = parse_alignment(value).or(decls.alignment)
+                        }
+                        "font-size" => decls.font_size = parse_length(value).or(decls.font_size),
+                        "font-weight" => {
+                            let weight = value.parse::<u32>().ok();
+                            decls.bold =
+                                Some(value == "bold" || value == "bolder" || weight >= Some(600));
+                        }
+                        "font-style" => decls.italic = Some(value == "italic"),
+                        _ => (),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    decls
+}
+
+fn parse_alignment(value: &str) -> Option<Alignment> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "left" => Some(Alignment::Left),
+        "center" => Some(Alignment::Center),
+        "right" => Some(Alignment::Right),
+        _ => None,
+    }
+}
+
+fn is_void_element(name: &str) -> bool {
+    matches!(
+        name,
+        "br" | "hr" | "img" | "meta" | "link" | "input" | "area" | "base" | "col" | "param"
+    )
+}
+
+/// Elements that start a new block, and the space above them relative to the
+/// font size.
+fn block_margin(name: &str) -> Option<f32> {
+    match name {
+        "p" | "ul" | "ol" | "blockquote" | "pre" | "dl" | "table" => Some(1.0),
+        "h1" => Some(0.67),
+        "h2" => Some(0.83),
+        "h3" | "h4" | "h5" | "h6" => Some(1.0),
+        "div" | "li" | "center" | "tr" | "dt" | "dd" | "body" | "html" | "form" | "address" => {
+            Some(0.0)
+        }
+        _ => None,
+    }
+}
+
+struct OpenElement {
+    name: String,
+    style: TextStyle,
+    alignment: Alignment,
+    indent: f32,
+    preformatted: bool,
+    /// For `<ol>`: the next item number.
+    list_counter: Option<u32>,
+}
+
+struct Parser {
+    document: Document,
+    stack: Vec<OpenElement>,
+    in_title: bool,
+    /// Whether the last text in the current block ended with whitespace, for
+    /// whitespace collapsing.
+    after_space: bool,
+}
+
+impl Parser {
+    fn current(&self) -> &OpenElement {
+        self.stack.last().unwrap()
+    }
+
+    fn start_block(&mut self, margin: f32) {
+        if let Some(block) = self.document.blocks.last_mut() {
+            if block.kind == BlockKind::Text && block.inlines.is_empty() {
+                // Reuse empty blocks, collapsing their margins.
+                block.margin = block.margin.max(margin);
+                block.alignment = self.stack.last().unwrap().alignment;
+                block.indent = self.stack.last().unwrap().indent;
+                block.preformatted = self.stack.last().unwrap().preformatted;
+                return;
+            }
+        }
+        let current = self.current();
+        let block = Block {
+            kind: BlockKind::Text,
+            alignment: current.alignment,
+            indent: current.indent,
+            margin,
+            preformatted: current.preformatted,
+            inlines: Vec::new(),
+        };
+        self.document.blocks.push(block);
+        self.after_space = true;
+    }
+
+    fn push_inline(&mut self, inline: Inline) {
+        if self.document.blocks.is_empty() {
+            self.start_block(0.0);
+        }
+        self.document
+            .blocks
+            .last_mut()
+            .unwrap()
+            .inlines
+            .push(inline);
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.in_title {
+            self.document
+                .title
+                .get_or_insert_with(String::new)
+                .push_str(text.trim());
+            return;
+        }
+        // The contents of <head> (other than the title) aren't shown.
+        if self.stack.iter().any(|e| e.name == "head") {
+            return;
+        }
+        let current = self.current();
+        let style = current.style.clone();
+        let text = if current.preformatted {
+            text.to_string()
+        } else {
+            let mut collapsed = String::with_capacity(text.len());
+            for c in text.chars() {
+                if c.is_ascii_whitespace() {
+                    if !self.after_space {
+                        collapsed.push(' ');
+                    }
+                    self.after_space = true;
+                } else {
+                    collapsed.push(c);
+                    self.after_space = false;
+                }
+            }
+            collapsed
+        };
+        if !text.is_empty() {
+            self.push_inline(Inline::Text(text, style));
+        }
+    }
+
+    fn start_tag(&mut self, name: String, attributes: Vec<(String, String)>) {
+        let decls = parse_declarations(&attributes);
+        let attribute = |wanted: &str| {
+            attributes
+                .iter()
+                .find(|(name, _)| name == wanted)
+                .map(|(_, value)| value.as_str())
+        };
+
+        match name.as_str() {
+            "title" => {
+                self.in_title = true;
+                return;
+            }
+            "br" => {
+                self.push_inline(Inline::LineBreak);
+                self.after_space = true;
+                return;
+            }
+            "hr" => {
+                let indent = self.current().indent;
+                let margin = self.current().style.size / 2.0;
+                self.document.blocks.push(Block {
+                    kind: BlockKind::Rule,
+                    alignment: Alignment::Left,
+                    indent,
+                    margin,
+                    preformatted: false,
+                    inlines: Vec::new(),
+                });
+                return;
+            }
+            "img" => {
+                if let Some(src) = attribute("src") {
+                    let image = Inline::Image {
+                        src: src.to_string(),
+                        width: attribute("width").and_then(parse_length),
+                        height: attribute("height").and_then(parse_length),
+                    };
+                    self.push_inline(image);
+                    self.after_space = false;
+                }
+                return;
+            }
+            "td" | "th" => {
+                // Good enough for simple layout tables.
+                if !self.after_space {
+                    let style = self.current().style.clone();
+                    self.push_inline(Inline::Text(" ".to_string(), style));
+                    self.after_space = true;
+                }
+            }
+            _ => (),
+        }
+        if is_void_element(&name) {
+            return;
+        }
+
+        let parent = self.current();
+        let mut element = OpenElement {
+            name: name.clone(),
+            style: parent.style.clone(),
+            alignment: parent.alignment,
+            indent: parent.indent,
+            preformatted: parent.preformatted,
+            list_counter: None,
+        };
+        let style = &mut element.style;
+        match name.as_str() {
+            "b" | "strong" | "th" | "dt" => style.bold = true,
+            "i" | "em" | "cite" | "var" | "address" => style.italic = true,
+            "big" => style.size *= 1.2,
+            "small" => style.size /= 1.2,
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse::<usize>().unwrap();
+                style.size = DEFAULT_FONT_SIZE * [2.0, 1.5, 1.17, 1.0, 0.83, 0.67][level - 1];
+                style.bold = true;
+            }
+            "a" => {
+                if let Some(href) = attribute("href") {
+                    self.document.links.push(href.to_string());
+                    style.link = Some(self.document.links.len() - 1);
+                    style.color = LINK_COLOR;
+                }
+            }
+            "font" => {
+                let size = attribute("size").map(str::trim);
+                if let Some(number) = size.and_then(|size| size.parse::<i32>().ok()) {
+                    // Sizes like "+1" are relative to the default size, 3.
+                    let relative = size.unwrap().starts_with(['+', '-']);
+                    let size = if relative { 3 + number } else { number }.clamp(1, 7);
+                    style.size = [10.0, 13.0, 16.0, 18.0, 24.0, 32.0, 48.0][size as usize - 1];
+                }
+            }
+            "center" => element.alignment = Alignment::Center,
+            "pre" => element.preformatted = true,
+            "ul" | "blockquote" | "dd" => element.indent += LIST_INDENT,
+            "ol" => {
+                element.indent += LIST_INDENT;
+                element.list_counter = Some(1);
+            }
+            _ => (),
+        }
+        if let Some(color) = decls.color {
+            style.color = color;
+        }
+        if let Some(size) = decls.font_size {
+            style.size = size;
+        }
+        if let Some(bold) = decls.bold {
+            style.bold = bold;
+        }
+        if let Some(italic) = decls.italic {
+            style.italic = italic;
+        }
+        if let Some(alignment) = decls.alignment {
+            element.alignment = alignment;
+        }
+        if name == "body" {
+            if let Some(color) = decls.background_color {
+                self.document.background_color = Some(color);
+            }
+        }
+
+        let margin = block_margin(&name).map(|margin| margin * element.style.size);
+        self.stack.push(element);
+        if let Some(margin) = margin {
+            self.start_block(margin);
+        }
+
+        if name == "li" {
+            // Find the list this item belongs to.
+            let list = self
+                .stack
+                .iter_mut()
+                .rev()
+                .find(|e| e.name == "ul" || e.name == "ol");
+            let marker = match list {
+                Some(OpenElement {
+                    list_counter: Some(counter),
+                    ..
+                }) => {
+                    *counter += 1;
+                    format!("{}. ", *counter - 1)
+                }
+                _ => "• ".to_string(),
+            };
+            let style = self.current().style.clone();
+            self.push_inline(Inline::Text(marker, style));
+            self.after_space = true;
+        }
+    }
+
+    fn end_tag(&mut self, name: &str) {
+        if name == "title" {
+            self.in_title = false;
+            return;
+        }
+        // The root element can't be closed, so text after </html> still works.
+        let Some(idx) = self.stack.iter().rposition(|e| e.name == name) else {
+            return;
+        };
+        if idx == 0 {
+            return;
+        }
+        let is_block = self.stack[idx..]
+            .iter()
+            .any(|e| block_margin(&e.name).is_some());
+        let margin = block_margin(name).unwrap_or(0.0) * self.stack[idx].style.size;
+        self.stack.truncate(idx);
+        if is_block {
+            // Text after the end of a block goes into a new block.
+            self.start_block(margin);
+        }
+    }
+}
+
+pub fn parse(html: &str) -> Document {
+    let mut parser = Parser {
+        document: Document::default(),
+        stack: vec![OpenElement {
+            name: String::new(),
+            style: TextStyle {
+                bold: false,
+                italic: false,
+                size: DEFAULT_FONT_SIZE,
+                color: (0.0, 0.0, 0.0, 1.0),
+                link: None,
+            },
+            alignment: Alignment::Left,
+            indent: 0.0,
+            preformatted: false,
+            list_counter: None,
+        }],
+        in_title: false,
+        after_space: true,
+    };
+    for token in tokenize(html) {
+        match token {
+            Token::StartTag { name, attributes } => parser.start_tag(name, attributes),
+            Token::EndTag { name } => parser.end_tag(&name),
+            Token::Text(text) => parser.text(&text),
+        }
+    }
+    let mut document = parser.document;
+    document
+        .blocks
+        .retain(|block| block.kind == BlockKind::Rule || !block.inlines.is_empty());
+    document
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_text(block: &Block) -> String {
+        block
+            .inlines
+            .iter()
+            .map(|inline| match inline {
+                Inline::Text(text, _) => text.as_str(),
+                Inline::Image { .. } => "[img]",
+                Inline::LineBreak => "\n",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_entities() {
+        assert_eq!(decode_entities("a &amp; b"), "a & b");
+        assert_eq!(decode_entities("&lt;&#65;&#x42;&gt;"), "<AB>");
+        assert_eq!(decode_entities("AT&T"), "AT&T");
+        assert_eq!(decode_entities("&bogus; &"), "&bogus; &");
+    }
+
+    #[test]
+    fn test_colors() {
+        assert_eq!(parse_color("#fff"), Some((1.0, 1.0, 1.0, 1.0)));
+        assert_eq!(parse_color("#FF0000"), Some((1.0, 0.0, 0.0, 1.0)));
+        assert_eq!(parse_color("Black"), Some((0.0, 0.0, 0.0, 1.0)));
+        assert_eq!(parse_color("#12"), None);
+        assert_eq!(parse_color("rainbow"), None);
+    }
+
+    #[test]
+    fn test_blocks_and_whitespace() {
+        let document = parse(
+            "<!DOCTYPE html><html><head><title> Help </title>\
+             <style>p { color: red; }</style></head>\
+             <body bgcolor=\"#000\"><h1>Heading</h1>\n  <p>Some   <b>bold</b>\n text.</p>\
+             <!-- comment --><p align=center>Line<br>break</p></body></html>",
+        );
+        assert_eq!(document.title.as_deref(), Some("Help"));
+        assert_eq!(document.background_color, Some((0.0, 0.0, 0.0, 1.0)));
+        let texts: Vec<String> = document.blocks.iter().map(block_text).collect();
+        assert_eq!(texts, ["Heading", "Some bold text.", "Line\nbreak"]);
+        assert_eq!(document.blocks[2].alignment, Alignment::Center);
+        let Inline::Text(_, ref style) = document.blocks[1].inlines[1] else {
+            panic!();
+        };
+        assert!(style.bold);
+    }
+
+    #[test]
+    fn test_lists_and_links() {
+        let document =
+            parse("<ol><li>One<li>Two</ol><ul><li><a href='more.html'>More</a></li></ul><hr>");
+        let texts: Vec<String> = document.blocks.iter().map(block_text).collect();
+        assert_eq!(texts, ["1. One", "2. Two", "• More", ""]);
+        assert_eq!(document.blocks[3].kind, BlockKind::Rule);
+        assert_eq!(document.links, ["more.html"]);
+        let Inline::Text(_, ref style) = document.blocks[2].inlines[1] else {
+            panic!();
+        };
+        assert_eq!(style.link, Some(0));
+    }
+}
@@ -5,14 +5,20 @@
  */
 //! `UIViewController`.
 
+use crate::frameworks::core_graphics::CGRect;
 use crate::frameworks::foundation::ns_string::get_static_str;
 use crate::objc::{
     id, msg, msg_class, nil, objc_classes, release, retain, ClassExports, HostObject, NSZonePtr,
 };
 
 #[derive(Default)]
-struct UIViewControllerHostObject {
+pub(super) struct UIViewControllerHostObject {
     view: id,
+    /// The view controller presented by this one with
+    /// `presentModalViewController:animated:`, if any. Strong reference.
+    modal_view_controller: id,
+    /// The view controller that presented this one, if any. Weak reference.
+    parent_view_controller: id,
 }
 impl HostObject for UIViewControllerHostObject {}
 
@@ -27,6 +33,23 @@ pub const CLASSES: ClassExports = objc_classes! {
     env.objc.alloc_object(this, host_object, &mut env.mem)
 }
 
+- (id)init {
+    msg![env; this initWithNibName:nil bundle:nil]
+}
+
+- (id)initWithNibName:(id)nib_name // NSString*
+               bundle:(id)_bundle { // NSBundle*
+    if nib_name != nil {
+        // TODO: load the view from the nib
+        log!(
+            "TODO: [(UIViewController*){:?} initWithNibName:{:?} bundle:...] ignoring nib",
+            this,
+            nib_name,
+        );
+    }
+    this
+}
+
 - (id)initWithCoder:(id)coder {
     let key_ns_string = get_static_str(env, "UIView");
     let view: id = msg![env; coder decodeObjectForKey:key_ns_string];
@@ -37,9 +60,14 @@ pub const CLASSES: ClassExports = objc_classes! {
 }
 
 - (())dealloc {
-    let &UIViewControllerHostObject { view } = env.objc.borrow(this);
+    let &UIViewControllerHostObject {
+        view,
+        modal_view_controller,
+        ..
+    } = env.objc.borrow(this);
 
     release(env, view);
+    release(env, modal_view_controller);
 
     env.objc.dealloc_object(this, &mut env.mem);
 }
@@ -68,6 +96,68 @@ pub const CLASSES: ClassExports = objc_classes! {
     }
 }
 
+- (id)modalViewController {
+    env.objc.borrow::<UIViewControllerHostObject>(this).modal_view_controller
+}
+- (id)parentViewController {
+    env.objc.borrow::<UIViewControllerHostObject>(this).parent_view_controller
+}
+
+- (())presentModalViewController:(id)modal_view_controller // UIViewController*
+                        animated:(bool)_animated {
+    // TODO: animation
+    let host_obj = env.objc.borrow::<UIViewControllerHostObject>(this);
+    assert!(host_obj.modal_view_controller == nil); // TODO: what happens here?
+
+    // TODO: Use the window of this view controller's view. For now, use the
+    // frontmost window, which is usually the only one.
+    let &window = env
+        .framework_state
+        .uikit
+        .ui_view
+        .ui_window
+        .visible_windows
+        .last()
+        .expect("presentModalViewController:animated: requires a visible window");
+
+    retain(env, modal_view_controller);
+    env.objc.borrow_mut::<UIViewControllerHostObject>(this).modal_view_controller =
+        modal_view_controller;
+    env.objc
+        .borrow_mut::<UIViewControllerHostObject>(modal_view_controller)
+        .parent_view_controller = this;
+
+    let screen: id = msg_class![env; UIScreen mainScreen];
+    let frame: CGRect = msg![env; screen applicationFrame];
+    let view: id = msg![env; modal_view_controller view];
+    () = msg![env; view setFrame:frame];
+    () = msg![env; window addSubview:view];
+}
+
+- (())dismissModalViewControllerAnimated:(bool)animated {
+    // TODO: animation
+    let &UIViewControllerHostObject {
+        modal_view_controller,
+        parent_view_controller,
+        ..
+    } = env.objc.borrow(this);
+    if modal_view_controller == nil {
+        // The presented view controller can ask to be dismissed itself.
+        if parent_view_controller != nil {
+            () = msg![env; parent_view_controller dismissModalViewControllerAnimated:animated];
+        }
+        return;
+    }
+
+    let view: id = msg![env; modal_view_controller view];
+    () = msg![env; view removeFromSuperview];
+    env.objc
+        .borrow_mut::<UIViewControllerHostObject>(modal_view_controller)
+        .parent_view_controller = nil;
+    env.objc.borrow_mut::<UIViewControllerHostObject>(this).modal_view_controller = nil;
+    release(env, modal_view_controller);
+}
+
 - (())setEditing:(bool)editing {
     log!("TODO: [(UIViewController*){:?} setEditing:{}]", this, editing); // TODO
 }
@@ -34,6 +34,7 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io::{Read, Seek, Write};
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 /// The actual location of a file outside the virtual filesystem, e.g. a host
 /// file path.
@@ -341,6 +342,16 @@ fn handle_open_err<T, E: std::fmt::Display, P: std::fmt::Debug>(
     }
 }
 
+/// Result of [Fs::metadata].
+#[derive(Debug)]
+pub struct GuestMetadata {
+    pub is_dir: bool,
+    /// Size in bytes (zero for directories).
+    pub len: u64,
+    /// Last modification time, if known.
+    pub modified: Option<SystemTime>,
+}
+
 /// Like [File] but for the guest filesystem.
 #[derive(Debug)]
 pub enum GuestFile {
@@ -436,8 +447,8 @@ pub struct Fs {
 }
 impl Fs {
     /// Construct a filesystem containing a home directory for the app, its
-    /// bundle and documents, and the bundled shared libraries. Returns the new
-    /// filesystem and the guest path of the bundle.
+    /// bundle, documents and library, and the bundled shared libraries. Returns
+    /// the new filesystem and the guest path of the bundle.
     ///
     /// The `bundle_dir_name` argument will be used as the name of the bundle
     /// directory in the guest filesystem, and must end in `.app`.
@@ -447,8 +458,8 @@ impl Fs {
     ///
     /// The `bundle_id` argument should be some value that uniquely identifies
     /// the app. This will be used to construct the host path for the app's
-    /// sandbox directory, where documents can be stored. A directory will be
-    /// created at that path if it does not already exist.
+    /// sandbox directory, where documents and preferences can be stored. A
+    /// directory will be created at that path if it does not already exist.
     ///
     /// `read_only_mode` can be used when the app won't actually be run, just
     /// just inspected (e.g. to retrieve display name and icon), so no user data
@@ -467,18 +478,22 @@ impl Fs {
 
         let bundle_guest_path = home_directory.join(&bundle_dir_name);
 
-        let documents_host_path = if !read_only_mode {
-            let path = paths::user_data_base_path()
+        let sandbox_host_paths = if !read_only_mode {
+            let sandbox = paths::user_data_base_path()
                 .join(paths::SANDBOX_DIR)
-                .join(bundle_id)
-                .join("Documents");
-            if let Err(e) = std::fs::create_dir_all(&path) {
-                panic!(
-                    "Could not create documents directory for app at {:?}: {:?}",
-                    path, e
-                );
+                .join(bundle_id);
+            let documents = sandbox.join("Documents");
+            let library = sandbox.join("Library");
+            // Preferences is where NSUserDefaults stores its data.
+            for path in [&documents, &library.join("Preferences")] {
+                if let Err(e) = std::fs::create_dir_all(path) {
+                    panic!(
+                        "Could not create sandbox directory for app at {:?}: {:?}",
+                        path, e
+                    );
+                }
             }
-            Some(path)
+            Some((documents, library))
         } else {
             None
         };
@@ -502,11 +517,15 @@ impl Fs {
 
         let mut app_dir_children = HashMap::new();
         app_dir_children.insert(bundle_dir_name, app_bundle.into_fs_node());
-        if let Some(documents_host_path) = documents_host_path {
+        if let Some((documents_host_path, library_host_path)) = sandbox_host_paths {
             app_dir_children.insert(
                 "Documents".to_string(),
                 FsNode::from_host_dir(&documents_host_path, /* writeable: */ true),
             );
+            app_dir_children.insert(
+                "Library".to_string(),
+                FsNode::from_host_dir(&library_host_path, /* writeable: */ true),
+            );
         }
 
         let root = FsNode::dir()
@@ -659,6 +678,46 @@ impl Fs {
         matches!(self.lookup_node(path), Some(FsNode::Directory { .. }))
     }
 
+    /// Like [std::fs::metadata] but for the guest filesystem. Only a few
+    /// properties are available.
+    pub fn metadata<P: AsRef<GuestPath>>(&self, path: P) -> Result<GuestMetadata, ()> {
+        match self.lookup_node(path.as_ref()).ok_or(())? {
+            FsNode::Directory { writeable, .. } => {
+                let modified = writeable
+                    .as_ref()
+                    .and_then(|host_path| std::fs::metadata(host_path).ok())
+                    .and_then(|metadata| metadata.modified().ok());
+                Ok(GuestMetadata {
+                    is_dir: true,
+                    len: 0,
+                    modified,
+                })
+            }
+            FsNode::File {
+                location: FileLocation::Path(host_path),
+                ..
+            } => {
+                let metadata = handle_open_err(std::fs::metadata(host_path), host_path);
+                Ok(GuestMetadata {
+                    is_dir: false,
+                    len: metadata.len(),
+                    modified: metadata.modified().ok(),
+                })
+            }
+            FsNode::File { .. } => {
+                // Files inside the app bundle and bundled resources don't have
+                // a meaningful modification time.
+                let mut file = self.open(path.as_ref())?;
+                let len = file.seek(std::io::SeekFrom::End(0)).map_err(|_| ())?;
+                Ok(GuestMetadata {
+                    is_dir: false,
+                    len,
+                    modified: None,
+                })
+            }
+        }
+    }
+
     /// Get an iterator over the names of files/directories in a directory.
     pub fn enumerate<P: AsRef<GuestPath>>(
         &self,
@@ -987,4 +1046,89 @@ impl Fs {
         );
         Ok(())
     }
+
+    /// Like [std::fs::rename] but for the guest filesystem. Only files can be
+    /// renamed currently. If there's already a file at the new path, it is
+    /// replaced.
+    pub fn rename<P: AsRef<GuestPath>, Q: AsRef<GuestPath>>(
+        &mut self,
+        from: P,
+        to: Q,
+    ) -> Result<(), ()> {
+        let (from, to) = (from.as_ref(), to.as_ref());
+
+        let (to_parent_node, to_name) = self.lookup_parent_node(to).ok_or(())?;
+        let FsNode::Directory {
+            children: to_children,
+            writeable: to_dir_host_path,
+        } = to_parent_node
+        else {
+            return Err(());
+        };
+        let Some(to_dir_host_path) = to_dir_host_path else {
+            log!(
+                "Warning: attempt to rename file to path {:?}, but directory is read-only",
+                to
+            );
+            return Err(());
+        };
+        match to_children.get(&to_name) {
+            None => (),
+            Some(FsNode::File {
+                location: FileLocation::Path(_),
+                writeable: true,
+            }) => (),
+            // TODO: replacing directories
+            Some(_) => return Err(()),
+        }
+        for c in to_name.chars() {
+            if std::path::is_separator(c) {
+                panic!("Attempt to rename file to path {:?}, but filename contains path separator character {:?}!", to, c);
+            }
+        }
+        let to_host_path = to_dir_host_path.join(&to_name);
+
+        let (from_parent_node, from_name) = self.lookup_parent_node(from).ok_or(())?;
+        let FsNode::Directory {
+            children: from_children,
+            writeable: Some(_),
+        } = from_parent_node
+        else {
+            return Err(());
+        };
+        let Some(FsNode::File {
+            location: FileLocation::Path(from_host_path),
+            writeable: true,
+        }) = from_children.get(&from_name)
+        else {
+            // TODO: renaming directories
+            return Err(());
+        };
+
+        handle_open_err(
+            std::fs::rename(from_host_path, &to_host_path),
+            &to_host_path,
+        );
+        log_dbg!(
+            "Renamed file at path {:?} to {:?} (host path: {:?} to {:?})",
+            from,
+            to,
+            from_host_path,
+            to_host_path,
+        );
+        from_children.remove(&from_name).unwrap();
+
+        let (FsNode::Directory { children, .. }, to_name) = self.lookup_parent_node(to).unwrap()
+        else {
+            unreachable!();
+        };
+        children.insert(
+            to_name,
+            FsNode::File {
+                location: FileLocation::Path(to_host_path),
+                writeable: true,
+            },
+        );
+        Ok(())
+    }
 }
@@ -23,13 +23,15 @@
 //! - Various modules provide implementations:
 //!   - [gles1_native] passes through native OpenGL ES 1.1.
 //!   - [gles1_on_gl2] provides an implementation of OpenGL ES 1.1 using OpenGL
-//!     2.1 compatibility profile.
+//!     2.1 compatibility profile. It also provides OpenGL ES 2.0.
 //!   - There might be more in future.
 //! - [gles11_raw] provides raw bindings for OpenGL ES 1.1 generated from the
 //!   Khronos API headers. **The function bindings are only for use within this
 //!   module.** The constants and types can be used outside it, however.
 //!   - [gl21compat_raw] is the same thing, but for OpenGL 2.1 compatibility
 //!     profile, which can't be used outside this module at all.
+//!   - [gles20_raw] is the same thing, but for OpenGL ES 2.0. Only its
+//!     constants are used, even within this module.
 //! - [present] provides utilities for presenting frames to the window using an
 //!   abstract OpenGL ES implementation.
 //!
@@ -42,9 +44,16 @@
 //! - [Specification](https://registry.khronos.org/OpenGL/specs/es/1.1/es_full_spec_1.1.pdf)
 //! - Extensions:
 //!   - [OES_framebuffer_object](https://registry.khronos.org/OpenGL/extensions/OES/OES_framebuffer_object.txt)
+//!   - [OES_mapbuffer](https://registry.khronos.org/OpenGL/extensions/OES/OES_mapbuffer.txt)
 //!   - [IMG_texture_compression_pvrtc](https://registry.khronos.org/OpenGL/extensions/IMG/IMG_texture_compression_pvrtc.txt)
+//!   - [OES_compressed_ETC1_RGB8_texture](https://registry.khronos.org/OpenGL/extensions/OES/OES_compressed_ETC1_RGB8_texture.txt)
 //!   - [OES_compressed_paletted_texture](https://registry.khronos.org/OpenGL/extensions/OES/OES_compressed_paletted_texture.txt) (also incorporated into the main spec)
 //!
+//! Useful resources for OpenGL ES 2.0:
+//! - [Reference pages](https://registry.khronos.org/OpenGL-Refpages/es2.0/)
+//! - [Specification](https://registry.khronos.org/OpenGL/specs/es/2.0/es_full_spec_2.0.pdf)
+//! - [Shading language specification](https://registry.khronos.org/OpenGL/specs/es/2.0/GLSL_ES_Specification_1.00.pdf)
+//!
 //! Useful resources for OpenGL 2.1:
 //! - [Reference pages](https://registry.khronos.org/OpenGL-Refpages/gl2.1/)
 //! - [Specification](https://registry.khronos.org/OpenGL/specs/gl/glspec21.pdf)
@@ -64,13 +73,14 @@ mod util;
 
 use touchHLE_gl_bindings::gl21compat as gl21compat_raw;
 pub use touchHLE_gl_bindings::gles11 as gles11_raw;
+pub use touchHLE_gl_bindings::gles20 as gles20_raw;
 
 use gles1_native::GLES1Native;
 use gles1_on_gl2::GLES1OnGL2;
 pub use gles_generic::GLES;
 
 /// Labels for [GLES] implementations and an abstraction for constructing them.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq)]
 pub enum GLESImplementation {
     /// [GLES1Native].
     GLES1Native,
@@ -80,6 +90,9 @@ pub enum GLESImplementation {
 impl GLESImplementation {
     /// List of OpenGL ES 1.1 implementations in order of preference.
     pub const GLES1_IMPLEMENTATIONS: &'static [Self] = &[Self::GLES1Native, Self::GLES1OnGL2];
+    /// List of implementations that also support OpenGL ES 2.0, in order of
+    /// preference.
+    pub const GLES2_IMPLEMENTATIONS: &'static [Self] = &[Self::GLES1OnGL2];
     /// Convert from short name used for command-line arguments. Returns [Err]
     /// if name is not recognized..
     pub fn from_short_name(name: &str) -> Result<Self, ()> {
@@ -136,3 +149,34 @@ pub fn create_gles1_ctx(
     }
     gles1_ctx.expect("Couldn't create OpenGL ES 1.1 context!")
 }
+
+/// Try to create a context that supports OpenGL ES 2.0, panicking on failure.
+///
+/// The configured OpenGL ES 1.1 strategy is ignored if it can't do this.
+pub fn create_gles2_ctx(
+    window: &mut crate::window::Window,
+    options: &crate::options::Options,
+) -> Box<dyn GLES> {
+    log!("Creating an OpenGL ES 2.0 context:");
+    let list = match options.gles1_implementation {
+        Some(ref preference) if GLESImplementation::GLES2_IMPLEMENTATIONS.contains(preference) => {
+            std::slice::from_ref(preference)
+        }
+        _ => GLESImplementation::GLES2_IMPLEMENTATIONS,
+    };
+    let mut gles2_ctx = None;
+    for implementation in list {
+        log!("Trying: {}", implementation.description());
+        match implementation.construct(window) {
+            Ok(ctx) => {
+                log!("=> Success!");
+                gles2_ctx = Some(ctx);
+                break;
+            }
+            Err(err) => {
+                log!("=> Failed: {}.", err);
+            }
+        }
+    }
+    gles2_ctx.expect("Couldn't create OpenGL ES 2.0 context!")
+}
@@ -33,9 +33,11 @@ fn main() {
         Fallbacks::None,
         [
             "GL_OES_framebuffer_object",
+            "GL_OES_mapbuffer",
             "GL_OES_rgb8_rgba8",
             "GL_EXT_texture_filter_anisotropic",
             "GL_IMG_texture_compression_pvrtc",
+            "GL_OES_compressed_ETC1_RGB8_texture",
             "GL_EXT_texture_lod_bias",
             "GL_OES_draw_texture",
             // Part of the OpenGL ES 1.1 common profile.
@@ -44,4 +46,10 @@ fn main() {
     )
     .write_bindings(GlobalGenerator, &mut file)
     .unwrap();
+    // Only the constants are used. The functions are provided by
+    // gl21compat instead.
+    let mut file = File::create(out_dir.join("gles20.rs")).unwrap();
+    Registry::new(Api::Gles2, (2, 0), Profile::Core, Fallbacks::None, [])
+        .write_bindings(GlobalGenerator, &mut file)
+        .unwrap();
 }
@@ -14,13 +14,28 @@
 
 use super::gles11_raw as gles11;
 use super::gles11_raw::types::*;
-use super::util::{try_decode_pvrtc, PalettedTextureFormat};
+use super::util::{
+    try_decode_compressed_texture, try_get_compressed_texture_formats, PalettedTextureFormat,
+};
 use super::GLES;
 use crate::window::{GLContext, GLVersion, Window};
 use std::ffi::CStr;
 
+/// A native OpenGL ES 1.1 context can't provide OpenGL ES 2.0, so this
+/// implementation is never used for OpenGL ES 2.0 contexts and those functions
+/// are unreachable.
+fn no_gles2() -> ! {
+    panic!(
+        "OpenGL ES 2.0 is not supported by {}",
+        GLES1Native::description()
+    )
+}
+
 pub struct GLES1Native {
     gl_ctx: GLContext,
+    /// GL error generated by our own validation, which should be reported by
+    /// the next `glGetError` call before any host error. `0` if none.
+    emulated_error: GLenum,
 }
 impl GLES for GLES1Native {
     fn description() -> &'static str {
@@ -30,6 +45,7 @@ impl GLES for GLES1Native {
     fn new(window: &mut Window) -> Result<Self, String> {
         Ok(Self {
             gl_ctx: window.create_gl_context(GLVersion::GLES11)?,
+            emulated_error: 0,
         })
     }
 
@@ -54,7 +70,10 @@ impl GLES for GLES1Native {
 
     // Generic state manipulation
     unsafe fn GetError(&mut self) -> GLenum {
-        gles11::GetError()
+        match std::mem::take(&mut self.emulated_error) {
+            0 => gles11::GetError(),
+            error => error,
+        }
     }
     unsafe fn Enable(&mut self, cap: GLenum) {
         gles11::Enable(cap)
@@ -81,6 +100,10 @@ impl GLES for GLES1Native {
         gles11::GetFloatv(pname, params)
     }
     unsafe fn GetIntegerv(&mut self, pname: GLenum, params: *mut GLint) {
+        // The host might not support some formats that we decode ourselves.
+        if try_get_compressed_texture_formats(pname, params) {
+            return;
+        }
         gles11::GetIntegerv(pname, params)
     }
     unsafe fn GetPointerv(&mut self, pname: GLenum, params: *mut *const GLvoid) {
@@ -228,6 +251,13 @@ impl GLES for GLES1Native {
         assert!(target == gles11::ARRAY_BUFFER || target == gles11::ELEMENT_ARRAY_BUFFER);
         gles11::BufferSubData(target, offset, size, data)
     }
+    unsafe fn IsBuffer(&mut self, buffer: GLuint) -> GLboolean {
+        gles11::IsBuffer(buffer)
+    }
+    unsafe fn GetBufferParameteriv(&mut self, target: GLenum, pname: GLenum, params: *mut GLint) {
+        assert!(target == gles11::ARRAY_BUFFER || target == gles11::ELEMENT_ARRAY_BUFFER);
+        gles11::GetBufferParameteriv(target, pname, params)
+    }
 
     // Non-pointers
     unsafe fn Color4f(&mut self, red: GLfloat, green: GLfloat, blue: GLfloat, alpha: GLfloat) {
@@ -338,7 +368,40 @@ impl GLES for GLES1Native {
         type_: GLenum,
         pixels: *mut GLvoid,
     ) {
-        gles11::ReadPixels(x, y, width, height, format, type_, pixels)
+        // OpenGL ES only guarantees GL_RGBA with GL_UNSIGNED_BYTE, but apps
+        // may rely on the iPhone OS driver also supporting GL_RGB.
+        if (format, type_) != (gles11::RGB, gles11::UNSIGNED_BYTE) || width <= 0 || height <= 0 {
+            gles11::ReadPixels(x, y, width, height, format, type_, pixels);
+            return;
+        }
+
+        let mut alignment = 0;
+        gles11::GetIntegerv(gles11::PACK_ALIGNMENT, &mut alignment);
+        let (width_usize, height_usize) = (width as usize, height as usize);
+        let alignment = alignment as usize;
+
+        // RGBA rows never need padding with an alignment of 4.
+        let mut rgba8_pixels = vec![0u8; width_usize * height_usize * 4];
+        gles11::PixelStorei(gles11::PACK_ALIGNMENT, 4);
+        gles11::ReadPixels(
+            x,
+            y,
+            width,
+            height,
+            gles11::RGBA,
+            gles11::UNSIGNED_BYTE,
+            rgba8_pixels.as_mut_ptr() as *mut _,
+        );
+        gles11::PixelStorei(gles11::PACK_ALIGNMENT, alignment as _);
+
+        let row_stride = (width_usize * 3).next_multiple_of(alignment);
+        let pixels = pixels.cast::<u8>();
+        for (row_index, row) in rgba8_pixels.chunks_exact(width_usize * 4).enumerate() {
+            let out_row = pixels.add(row_index * row_stride);
+            for (i, rgba) in row.chunks_exact(4).enumerate() {
+                std::ptr::copy_nonoverlapping(rgba.as_ptr(), out_row.add(i * 3), 3);
+            }
+        }
     }
     unsafe fn GenTextures(&mut self, n: GLsizei, textures: *mut GLuint) {
         gles11::GenTextures(n, textures)
@@ -422,10 +485,11 @@ impl GLES for GLES1Native {
         data: *const GLvoid,
     ) {
         let data = unsafe { std::slice::from_raw_parts(data.cast::<u8>(), image_size as usize) };
-        // IMG_texture_compression_pvrtc (only on Imagination/Apple GPUs)
+        // IMG_texture_compression_pvrtc (only on Imagination/Apple GPUs) and
+        // OES_compressed_ETC1_RGB8_texture (not supported by all GPUs)
         // TODO: It would be more efficient to use hardware decoding where
         // available (I just don't have a suitable device to try this on)
-        if try_decode_pvrtc(
+        match try_decode_compressed_texture(
             self,
             target,
             level,
@@ -435,8 +499,17 @@ impl GLES for GLES1Native {
             border,
             data,
         ) {
-            log_dbg!("Decoded PVRTC");
-            return;
+            Ok(true) => {
+                log_dbg!("Decoded PVRTC or ETC1");
+                return;
+            }
+            Ok(false) => (),
+            Err(error) => {
+                if self.emulated_error == 0 {
+                    self.emulated_error = error;
+                }
+                return;
+            }
         }
 
         // OES_compressed_paletted_texture is in the common profile of OpenGL ES
@@ -650,4 +723,311 @@ impl GLES for GLES1Native {
     unsafe fn GenerateMipmapOES(&mut self, target: GLenum) {
         gles11::GenerateMipmapOES(target)
     }
+
+    // OES_mapbuffer
+    unsafe fn MapBufferOES(&mut self, target: GLenum, access: GLenum) -> *mut GLvoid {
+        assert!(target == gles11::ARRAY_BUFFER || target == gles11::ELEMENT_ARRAY_BUFFER);
+        // TODO: The mapping is write-only, so reading it is undefined, though
+        // it works with many drivers.
+        gles11::MapBufferOES(target, access)
+    }
+    unsafe fn UnmapBufferOES(&mut self, target: GLenum) -> GLboolean {
+        assert!(target == gles11::ARRAY_BUFFER || target == gles11::ELEMENT_ARRAY_BUFFER);
+        gles11::UnmapBufferOES(target)
+    }
+
+    // Shaders (OpenGL ES 2.0)
+    unsafe fn CreateShader(&mut self, _type_: GLenum) -> GLuint {
+        no_gles2()
+    }
+    unsafe fn DeleteShader(&mut self, _shader: GLuint) {
+        no_gles2()
+    }
+    unsafe fn IsShader(&mut self, _shader: GLuint) -> GLboolean {
+        no_gles2()
+    }
+    unsafe fn ShaderSource(
+        &mut self,
+        _shader: GLuint,
+        _count: GLsizei,
+        _string: *const *const GLchar,
+        _length: *const GLint,
+    ) {
+        no_gles2()
+    }
+    unsafe fn CompileShader(&mut self, _shader: GLuint) {
+        no_gles2()
+    }
+    unsafe fn GetShaderiv(&mut self, _shader: GLuint, _pname: GLenum, _params: *mut GLint) {
+        no_gles2()
+    }
+    unsafe fn GetShaderInfoLog(
+        &mut self,
+        _shader: GLuint,
+        _bufsize: GLsizei,
+        _length: *mut GLsizei,
+        _infolog: *mut GLchar,
+    ) {
+        no_gles2()
+    }
+    unsafe fn GetShaderSource(
+        &mut self,
+        _shader: GLuint,
+        _bufsize: GLsizei,
+        _length: *mut GLsizei,
+        _source: *mut GLchar,
+    ) {
+        no_gles2()
+    }
+    unsafe fn GetShaderPrecisionFormat(
+        &mut self,
+        _shadertype: GLenum,
+        _precisiontype: GLenum,
+        _range: *mut GLint,
+        _precision: *mut GLint,
+    ) {
+        no_gles2()
+    }
+    unsafe fn ReleaseShaderCompiler(&mut self) {
+        no_gles2()
+    }
+
+    // Programs (OpenGL ES 2.0)
+    unsafe fn CreateProgram(&mut self) -> GLuint {
+        no_gles2()
+    }
+    unsafe fn DeleteProgram(&mut self, _program: GLuint) {
+        no_gles2()
+    }
+    unsafe fn IsProgram(&mut self, _program: GLuint) -> GLboolean {
+        no_gles2()
+    }
+    unsafe fn AttachShader(&mut self, _program: GLuint, _shader: GLuint) {
+        no_gles2()
+    }
+    unsafe fn DetachShader(&mut self, _program: GLuint, _shader: GLuint) {
+        no_gles2()
+    }
+    unsafe fn GetAttachedShaders(
+        &mut self,
+        _program: GLuint,
+        _maxcount: GLsizei,
+        _count: *mut GLsizei,
+        _shaders: *mut GLuint,
+    ) {
+        no_gles2()
+    }
+    unsafe fn LinkProgram(&mut self, _program: GLuint) {
+        no_gles2()
+    }
+    unsafe fn ValidateProgram(&mut self, _program: GLuint) {
+        no_gles2()
+    }
+    unsafe fn UseProgram(&mut self, _program: GLuint) {
+        no_gles2()
+    }
+    unsafe fn GetProgramiv(&mut self, _program: GLuint, _pname: GLenum, _params: *mut GLint) {
+        no_gles2()
+    }
+    unsafe fn GetProgramInfoLog(
+        &mut self,
+        _program: GLuint,
+        _bufsize: GLsizei,
+        _length: *mut GLsizei,
+        _infolog: *mut GLchar,
+    ) {
+        no_gles2()
+    }
+
+    // Vertex attributes (OpenGL ES 2.0)
+    unsafe fn BindAttribLocation(
+        &mut self,
+        _program: GLuint,
+        _index: GLuint,
+        _name: *const GLchar,
+    ) {
+        no_gles2()
+    }
+    unsafe fn GetAttribLocation(&mut self, _program: GLuint, _name: *const GLchar) -> GLint {
+        no_gles2()
+    }
+    unsafe fn GetActiveAttrib(
+        &mut self,
+        _program: GLuint,
+        _index: GLuint,
+        _bufsize: GLsizei,
+        _length: *mut GLsizei,
+        _size: *mut GLint,
+        _type_: *mut GLenum,
+        _name: *mut GLchar,
+    ) {
+        no_gles2()
+    }
+    unsafe fn EnableVertexAttribArray(&mut self, _index: GLuint) {
+        no_gles2()
+    }
+    unsafe fn DisableVertexAttribArray(&mut self, _index: GLuint) {
+        no_gles2()
+    }
+    unsafe fn GetVertexAttribfv(&mut self, _index: GLuint, _pname: GLenum, _params: *mut GLfloat) {
+        no_gles2()
+    }
+    unsafe fn GetVertexAttribiv(&mut self, _index: GLuint, _pname: GLenum, _params: *mut GLint) {
+        no_gles2()
+    }
+    unsafe fn GetVertexAttribPointerv(
+        &mut self,
+        _index: GLuint,
+        _pname: GLenum,
+        _pointer: *mut *mut GLvoid,
+    ) {
+        no_gles2()
+    }
+    unsafe fn VertexAttribPointer(
+        &mut self,
+        _index: GLuint,
+        _size: GLint,
+        _type_: GLenum,
+        _normalized: GLboolean,
+        _stride: GLsizei,
+        _pointer: *const GLvoid,
+    ) {
+        no_gles2()
+    }
+    unsafe fn VertexAttrib1f(&mut self, _index: GLuint, _x: GLfloat) {
+        no_gles2()
+    }
+    unsafe fn VertexAttrib2f(&mut self, _index: GLuint, _x: GLfloat, _y: GLfloat) {
+        no_gles2()
+    }
+    unsafe fn VertexAttrib3f(&mut self, _index: GLuint, _x: GLfloat, _y: GLfloat, _z: GLfloat) {
+        no_gles2()
+    }
+    unsafe fn VertexAttrib4f(
+        &mut self,
+        _index: GLuint,
+        _x: GLfloat,
+        _y: GLfloat,
+        _z: GLfloat,
+        _w: GLfloat,
+    ) {
+        no_gles2()
+    }
+    unsafe fn VertexAttrib1fv(&mut self, _index: GLuint, _values: *const GLfloat) {
+        no_gles2()
+    }
+    unsafe fn VertexAttrib2fv(&mut self, _index: GLuint, _values: *const GLfloat) {
+        no_gles2()
+    }
+    unsafe fn VertexAttrib3fv(&mut self, _index: GLuint, _values: *const GLfloat) {
+        no_gles2()
+    }
+    unsafe fn VertexAttrib4fv(&mut self, _index: GLuint, _values: *const GLfloat) {
+        no_gles2()
+    }
+
+    // Uniforms (OpenGL ES 2.0)
+    unsafe fn GetUniformLocation(&mut self, _program: GLuint, _name: *const GLchar) -> GLint {
+        no_gles2()
+    }
+    unsafe fn GetActiveUniform(
+        &mut self,
+        _program: GLuint,
+        _index: GLuint,
+        _bufsize: GLsizei,
+        _length: *mut GLsizei,
+        _size: *mut GLint,
+        _type_: *mut GLenum,
+        _name: *mut GLchar,
+    ) {
+        no_gles2()
+    }
+    unsafe fn GetUniformfv(&mut self, _program: GLuint, _location: GLint, _params: *mut GLfloat) {
+        no_gles2()
+    }
+    unsafe fn GetUniformiv(&mut self, _program: GLuint, _location: GLint, _params: *mut GLint) {
+        no_gles2()
+    }
+    unsafe fn Uniform1f(&mut self, _location: GLint, _x: GLfloat) {
+        no_gles2()
+    }
+    unsafe fn Uniform2f(&mut self, _location: GLint, _x: GLfloat, _y: GLfloat) {
+        no_gles2()
+    }
+    unsafe fn Uniform3f(&mut self, _location: GLint, _x: GLfloat, _y: GLfloat, _z: GLfloat) {
+        no_gles2()
+    }
+    unsafe fn Uniform4f(
+        &mut self,
+        _location: GLint,
+        _x: GLfloat,
+        _y: GLfloat,
+        _z: GLfloat,
+        _w: GLfloat,
+    ) {
+        no_gles2()
+    }
+    unsafe fn Uniform1i(&mut self, _location: GLint, _x: GLint) {
+        no_gles2()
+    }
+    unsafe fn Uniform2i(&mut self, _location: GLint, _x: GLint, _y: GLint) {
+        no_gles2()
+    }
+    unsafe fn Uniform3i(&mut self, _location: GLint, _x: GLint, _y: GLint, _z: GLint) {
+        no_gles2()
+    }
+    unsafe fn Uniform4i(&mut self, _location: GLint, _x: GLint, _y: GLint, _z: GLint, _w: GLint) {
+        no_gles2()
+    }
+    unsafe fn Uniform1fv(&mut self, _location: GLint, _count: GLsizei, _values: *const GLfloat) {
+        no_gles2()
+    }
+    unsafe fn Uniform2fv(&mut self, _location: GLint, _count: GLsizei, _values: *const GLfloat) {
+        no_gles2()
+    }
+    unsafe fn Uniform3fv(&mut self, _location: GLint, _count: GLsizei, _values: *const GLfloat) {
+        no_gles2()
+    }
+    unsafe fn Uniform4fv(&mut self, _location: GLint, _count: GLsizei, _values: *const GLfloat) {
+        no_gles2()
+    }
+    unsafe fn Uniform1iv(&mut self, _location: GLint, _count: GLsizei, _values: *const GLint) {
+        no_gles2()
+    }
+    unsafe fn Uniform2iv(&mut self, _location: GLint, _count: GLsizei, _values: *const GLint) {
+        no_gles2()
+    }
+    unsafe fn Uniform3iv(&mut self, _location: GLint, _count: GLsizei, _values: *const GLint) {
+        no_gles2()
+    }
+    unsafe fn Uniform4iv(&mut self, _location: GLint, _count: GLsizei, _values: *const GLint) {
+        no_gles2()
+    }
+    unsafe fn UniformMatrix2fv(
+        &mut self,
+        _location: GLint,
+        _count: GLsizei,
+        _transpose: GLboolean,
+        _value: *const GLfloat,
+    ) {
+        no_gles2()
+    }
+    unsafe fn UniformMatrix3fv(
+        &mut self,
+        _location: GLint,
+        _count: GLsizei,
+        _transpose: GLboolean,
+        _value: *const GLfloat,
+    ) {
+        no_gles2()
+    }
+    unsafe fn UniformMatrix4fv(
+        &mut self,
+        _location: GLint,
+        _count: GLsizei,
+        _transpose: GLboolean,
+        _value: *const GLfloat,
+    ) {
+        no_gles2()
+    }
 }
@@ -17,13 +17,18 @@
 //! OpenGL 2.1 is the latest version that has a compatibility profile available
 //! on macOS. It's also a version supported on various other OSes.
 //! It is therefore a convenient target for our implementation.
+//!
+//! OpenGL 2.1 also has GLSL, so this implementation provides the OpenGL ES 2.0
+//! parts of [GLES] too. OpenGL ES 2.0's shading language is translated to the
+//! desktop one by [translate_shader_source].
 
 use super::gl21compat_raw as gl21;
 use super::gl21compat_raw::types::*;
 use super::gles11_raw as gles11; // constants only
+use super::gles20_raw as gles20; // constants only
 use super::util::{
-    fixed_to_float, matrix_fixed_to_float, try_decode_pvrtc, PalettedTextureFormat, ParamTable,
-    ParamType,
+    fixed_to_float, matrix_fixed_to_float, translate_shader_source, try_decode_compressed_texture,
+    try_get_compressed_texture_formats, PalettedTextureFormat, ParamTable, ParamType,
 };
 use super::GLES;
 use crate::window::{GLContext, GLVersion, Window};
@@ -87,6 +92,8 @@ pub struct ArrayInfo {
 struct ArrayStateBackup {
     size: Option<GLint>,
     stride: GLsizei,
+    /// Buffer the pointer is an offset into, or 0 for a client-side array.
+    buffer_binding: GLuint,
     pointer: *const GLvoid,
 }
 
@@ -150,7 +157,7 @@ const GET_PARAMS: ParamTable = ParamTable(&[
     (gl21::COLOR_LOGIC_OP, ParamType::Boolean, 1),
     (gl21::COLOR_MATERIAL, ParamType::Boolean, 1),
     (gl21::COLOR_WRITEMASK, ParamType::Boolean, 4),
-    // TODO: COMPRESSED_TEXTURE_FORMATS (need to support PVRTC etc)
+    // COMPRESSED_TEXTURE_FORMATS is special-cased in GetIntegerv
     (gl21::CULL_FACE, ParamType::Boolean, 1),
     (gl21::CULL_FACE_MODE, ParamType::Int, 1),
     (gl21::CURRENT_COLOR, ParamType::FloatSpecial, 4), // TODO correct type
@@ -208,7 +215,7 @@ const GET_PARAMS: ParamTable = ParamTable(&[
     (gl21::NORMAL_ARRAY_STRIDE, ParamType::Int, 1),
     (gl21::NORMAL_ARRAY_TYPE, ParamType::Int, 1),
     (gl21::NORMALIZE, ParamType::Boolean, 1),
-    // TODO: NUM_COMPRESSED_TEXTURE_FORMATS (need to support PVRTC etc)
+    // NUM_COMPRESSED_TEXTURE_FORMATS is special-cased in GetIntegerv
     (gl21::PACK_ALIGNMENT, ParamType::Int, 1),
     (gl21::PERSPECTIVE_CORRECTION_HINT, ParamType::Int, 1),
     (gl21::POINT_DISTANCE_ATTENUATION, ParamType::Float, 3),
@@ -269,10 +276,33 @@ const GET_PARAMS: ParamTable = ParamTable(&[
     // OES_framebuffer_object -> EXT_framebuffer_object
     (gl21::FRAMEBUFFER_BINDING_EXT, ParamType::Int, 1),
     (gl21::RENDERBUFFER_BINDING_EXT, ParamType::Int, 1),
+    (gl21::MAX_RENDERBUFFER_SIZE_EXT, ParamType::Int, 1),
+    // EXT_texture_filter_anisotropic
+    (gl21::MAX_TEXTURE_MAX_ANISOTROPY_EXT, ParamType::Float, 1),
     // EXT_texture_lod_bias
     (gl21::MAX_TEXTURE_LOD_BIAS_EXT, ParamType::Float, 1),
+    // OpenGL ES 2.0
+    (gl21::CURRENT_PROGRAM, ParamType::Int, 1),
+    (gl21::MAX_COMBINED_TEXTURE_IMAGE_UNITS, ParamType::Int, 1),
+    (gl21::MAX_TEXTURE_IMAGE_UNITS, ParamType::Int, 1),
+    (gl21::MAX_VERTEX_ATTRIBS, ParamType::Int, 1),
+    (gl21::MAX_VERTEX_TEXTURE_IMAGE_UNITS, ParamType::Int, 1),
 ]);
 
+/// OpenGL ES 2.0 limits that OpenGL 2.1 expresses as a number of components
+/// rather than a number of vectors, and the equivalent OpenGL 2.1 parameters.
+const VECTOR_LIMITS: &[(GLenum, GLenum)] = &[
+    (
+        gles20::MAX_VERTEX_UNIFORM_VECTORS,
+        gl21::MAX_VERTEX_UNIFORM_COMPONENTS,
+    ),
+    (gles20::MAX_VARYING_VECTORS, gl21::MAX_VARYING_FLOATS),
+    (
+        gles20::MAX_FRAGMENT_UNIFORM_VECTORS,
+        gl21::MAX_FRAGMENT_UNIFORM_COMPONENTS,
+    ),
+];
+
 /// Table of `glFog` parameters shared by OpenGL ES 1.1 and OpenGL 2.1.
 const FOG_PARAMS: ParamTable = ParamTable(&[
     // Despite only having f, fv, x and xv setters in OpenGL ES 1.1, this is
@@ -350,8 +380,47 @@ pub struct GLES1OnGL2 {
     pointer_is_fixed_point: [bool; ARRAYS.len()],
     fixed_point_texture_units: HashSet<GLenum>,
     fixed_point_translation_buffers: [Vec<GLfloat>; ARRAYS.len()],
+    /// GL error generated by our own validation, which should be reported by
+    /// the next `glGetError` call before any host error. `0` if none.
+    emulated_error: GLenum,
 }
 impl GLES1OnGL2 {
+    /// Call a function that defines or modifies levels of the texture bound
+    /// to `target`, then limit its mipmap levels to those which have been
+    /// uploaded or generated.
+    ///
+    /// Without this, a texture with a mipmapping minification filter, but
+    /// only some of its levels (often just the base level), would be
+    /// incomplete and be rendered as black. Apps get away with this on real
+    /// devices surprisingly often, so it's better to fall back to the levels
+    /// that are there. `GL_TEXTURE_MAX_LEVEL` isn't in OpenGL ES 1.1, so the
+    /// app can't observe this. The limit is lifted while the function runs,
+    /// since it would otherwise stop `GL_GENERATE_MIPMAP` from working.
+    unsafe fn define_texture_levels<F: FnOnce()>(&mut self, target: GLenum, f: F) {
+        // Initial value from the spec.
+        gl21::TexParameteri(target, gl21::TEXTURE_MAX_LEVEL, 1000);
+        f();
+
+        let mut max_level = -1;
+        loop {
+            let level = max_level + 1;
+            let mut width = 0;
+            let mut height = 0;
+            gl21::GetTexLevelParameteriv(target, level, gl21::TEXTURE_WIDTH, &mut width);
+            gl21::GetTexLevelParameteriv(target, level, gl21::TEXTURE_HEIGHT, &mut height);
+            if width == 0 || height == 0 {
+                break;
+            }
+            max_level = level;
+            if width == 1 && height == 1 {
+                // The mip chain is complete, so there can't be more levels.
+                break;
+            }
+        }
+        // If even the base level is missing, there's nothing to fall back to.
+        gl21::TexParameteri(target, gl21::TEXTURE_MAX_LEVEL, max_level.max(0));
+    }
+
     /// If any arrays with fixed-point data are in use at the time of a draw
     /// call, this function will convert the data to floating-point and
     /// replace the pointers. [Self::restore_fixed_point_arrays] can be called
@@ -402,8 +471,7 @@ impl GLES1OnGL2 {
 
             let mut buffer_binding = 0;
             gl21::GetIntegerv(array_info.buffer_binding, &mut buffer_binding);
-            // TODO: translation for bound array buffers
-            assert!(buffer_binding == 0);
+            let buffer_binding = buffer_binding as GLuint;
 
             // Get and back up data
 
@@ -425,9 +493,24 @@ impl GLES1OnGL2 {
             backups[i] = Some(ArrayStateBackup {
                 size,
                 stride,
+                buffer_binding,
                 pointer,
             });
 
+            // If a buffer is bound, the pointer is an offset into it, so the
+            // buffer must be mapped to read the data.
+            let (data, old_array_buffer) = if buffer_binding != 0 {
+                let mut old_array_buffer = 0;
+                gl21::GetIntegerv(gl21::ARRAY_BUFFER_BINDING, &mut old_array_buffer);
+                gl21::BindBuffer(gl21::ARRAY_BUFFER, buffer_binding);
+                let mapping = gl21::MapBuffer(gl21::ARRAY_BUFFER, gl21::READ_ONLY);
+                assert!(!mapping.is_null());
+                let data: *const GLvoid = mapping.cast_const().add(pointer as usize);
+                (data, Some(old_array_buffer as GLuint))
+            } else {
+                (pointer, None)
+            };
+
             // Create translated array and substitute pointer
 
             let size = size.unwrap_or_else(|| {
@@ -452,7 +535,7 @@ impl GLES1OnGL2 {
                 let size = size as usize;
                 let stride = stride as usize;
                 for j in first..(first + count) {
-                    let vector_ptr: *const GLvoid = pointer.add(j * stride);
+                    let vector_ptr: *const GLvoid = data.add(j * stride);
                     let vector_ptr: *const GLfixed = vector_ptr.cast();
                     for k in 0..size {
                         buffer[j * size + k] = fixed_to_float(vector_ptr.add(k).read_unaligned());
@@ -460,6 +543,12 @@ impl GLES1OnGL2 {
                 }
             }
 
+            if old_array_buffer.is_some() {
+                gl21::UnmapBuffer(gl21::ARRAY_BUFFER);
+                // The translated array is client-side.
+                gl21::BindBuffer(gl21::ARRAY_BUFFER, 0);
+            }
+
             let buffer_ptr: *const GLfloat = buffer.as_ptr();
             let buffer_ptr: *const GLvoid = buffer_ptr.cast();
             match array_info.name {
@@ -475,6 +564,9 @@ impl GLES1OnGL2 {
                 _ => unreachable!(),
             }
 
+            if let Some(old_array_buffer) = old_array_buffer {
+                gl21::BindBuffer(gl21::ARRAY_BUFFER, old_array_buffer);
+            }
             if let Some(old_client_active_texture) = old_client_active_texture {
                 gl21::ClientActiveTexture(old_client_active_texture);
             }
@@ -490,12 +582,18 @@ impl GLES1OnGL2 {
             let Some(ArrayStateBackup {
                 size,
                 stride,
+                buffer_binding,
                 pointer,
             }) = backup
             else {
                 continue;
             };
 
+            // The buffer binding is captured by the pointer functions.
+            let mut old_array_buffer = 0;
+            gl21::GetIntegerv(gl21::ARRAY_BUFFER_BINDING, &mut old_array_buffer);
+            gl21::BindBuffer(gl21::ARRAY_BUFFER, buffer_binding);
+
             match array_info.name {
                 gl21::COLOR_ARRAY => {
                     gl21::ColorPointer(size.unwrap(), gl21::FLOAT, stride, pointer)
@@ -525,6 +623,8 @@ impl GLES1OnGL2 {
                 }
                 _ => unreachable!(),
             }
+
+            gl21::BindBuffer(gl21::ARRAY_BUFFER, old_array_buffer as GLuint);
         }
     }
 
@@ -557,6 +657,33 @@ impl GLES1OnGL2 {
             gl21::Fogf(gl21::FOG_END, fogEnd);
         }
     }
+
+    /// OpenGL ES has no `glDrawBuffer` or `glReadBuffer`, so a framebuffer
+    /// object without a color attachment (e.g. for a shadow map) is complete.
+    /// In OpenGL 2.1, it is only complete if those buffers are set to
+    /// `GL_NONE`, which this function takes care of for the bound framebuffer.
+    /// It must be called whenever its color attachment changes.
+    unsafe fn update_draw_and_read_buffers(&mut self, target: GLenum) {
+        let mut framebuffer = 0;
+        gl21::GetIntegerv(gl21::FRAMEBUFFER_BINDING_EXT, &mut framebuffer);
+        if framebuffer == 0 {
+            return;
+        }
+        let mut color_type = 0;
+        gl21::GetFramebufferAttachmentParameterivEXT(
+            target,
+            gl21::COLOR_ATTACHMENT0_EXT,
+            gl21::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE_EXT,
+            &mut color_type,
+        );
+        let buffer = if color_type as GLenum == gl21::NONE {
+            gl21::NONE
+        } else {
+            gl21::COLOR_ATTACHMENT0_EXT
+        };
+        gl21::DrawBuffer(buffer);
+        gl21::ReadBuffer(buffer);
+    }
 }
 impl GLES for GLES1OnGL2 {
     fn description() -> &'static str {
@@ -569,6 +696,7 @@ impl GLES for GLES1OnGL2 {
             pointer_is_fixed_point: [false; ARRAYS.len()],
             fixed_point_texture_units: HashSet::new(),
             fixed_point_translation_buffers: [Vec::new(), Vec::new(), Vec::new(), Vec::new()],
+            emulated_error: 0,
         })
     }
 
@@ -592,7 +720,10 @@ impl GLES for GLES1OnGL2 {
 
     // Generic state manipulation
     unsafe fn GetError(&mut self) -> GLenum {
-        gl21::GetError()
+        match std::mem::take(&mut self.emulated_error) {
+            0 => gl21::GetError(),
+            error => error,
+        }
     }
     unsafe fn Enable(&mut self, cap: GLenum) {
         if ARRAYS.iter().any(|&ArrayInfo { name, .. }| name == cap) {
@@ -639,6 +770,14 @@ impl GLES for GLES1OnGL2 {
         gl21::GetFloatv(pname, params);
     }
     unsafe fn GetIntegerv(&mut self, pname: GLenum, params: *mut GLint) {
+        if let Some(&(_, components_pname)) = VECTOR_LIMITS.iter().find(|&&(p, _)| p == pname) {
+            gl21::GetIntegerv(components_pname, params);
+            params.write_unaligned(params.read_unaligned() / 4);
+            return;
+        }
+        if try_get_compressed_texture_formats(pname, params) {
+            return;
+        }
         let (type_, _count) = GET_PARAMS.get_type_info(pname);
         // TODO: type conversion
         assert!(type_ == ParamType::Int);
@@ -896,6 +1035,14 @@ impl GLES for GLES1OnGL2 {
         assert!(target == gl21::ARRAY_BUFFER || target == gl21::ELEMENT_ARRAY_BUFFER);
         gl21::BufferSubData(target, offset, size, data)
     }
+    unsafe fn IsBuffer(&mut self, buffer: GLuint) -> GLboolean {
+        gl21::IsBuffer(buffer)
+    }
+    unsafe fn GetBufferParameteriv(&mut self, target: GLenum, pname: GLenum, params: *mut GLint) {
+        assert!(target == gl21::ARRAY_BUFFER || target == gl21::ELEMENT_ARRAY_BUFFER);
+        assert!(pname == gl21::BUFFER_SIZE || pname == gl21::BUFFER_USAGE);
+        gl21::GetBufferParameteriv(target, pname, params)
+    }
 
     // Non-pointers
     unsafe fn Color4f(&mut self, red: GLfloat, green: GLfloat, blue: GLfloat, alpha: GLfloat) {
@@ -1051,8 +1198,15 @@ impl GLES for GLES1OnGL2 {
                     gl21::ELEMENT_ARRAY_BUFFER_BINDING,
                     &mut index_buffer_binding,
                 );
-                // TODO: handling of bound index array buffers
-                assert!(index_buffer_binding == 0);
+                // If a buffer is bound, the pointer is an offset into it, so
+                // the buffer must be mapped to read the indices.
+                let indices = if index_buffer_binding != 0 {
+                    let mapping = gl21::MapBuffer(gl21::ELEMENT_ARRAY_BUFFER, gl21::READ_ONLY);
+                    assert!(!mapping.is_null());
+                    mapping.cast_const().add(indices as usize)
+                } else {
+                    indices
+                };
 
                 let mut first = usize::MAX;
                 let mut last = usize::MIN;
@@ -1076,6 +1230,9 @@ impl GLES for GLES1OnGL2 {
                     }
                     _ => unreachable!(),
                 }
+                if index_buffer_binding != 0 {
+                    gl21::UnmapBuffer(gl21::ELEMENT_ARRAY_BUFFER);
+                }
 
                 let (first, count) = if first == usize::MAX && last == usize::MIN {
                     assert!(count == 0);
@@ -1245,17 +1402,19 @@ impl GLES for GLES1OnGL2 {
                 || type_ == gl21::UNSIGNED_SHORT_4_4_4_4
                 || type_ == gl21::UNSIGNED_SHORT_5_5_5_1
         );
-        gl21::TexImage2D(
-            target,
-            level,
-            internalformat,
-            width,
-            height,
-            border,
-            format,
-            type_,
-            pixels,
-        )
+        self.define_texture_levels(target, || {
+            gl21::TexImage2D(
+                target,
+                level,
+                internalformat,
+                width,
+                height,
+                border,
+                format,
+                type_,
+                pixels,
+            )
+        });
     }
     unsafe fn TexSubImage2D(
         &mut self,
@@ -1284,9 +1443,12 @@ impl GLES for GLES1OnGL2 {
                 || type_ == gl21::UNSIGNED_SHORT_4_4_4_4
                 || type_ == gl21::UNSIGNED_SHORT_5_5_5_1
         );
-        gl21::TexSubImage2D(
-            target, level, xoffset, yoffset, width, height, format, type_, pixels,
-        )
+        // With GL_GENERATE_MIPMAP, this can also define levels.
+        self.define_texture_levels(target, || {
+            gl21::TexSubImage2D(
+                target, level, xoffset, yoffset, width, height, format, type_, pixels,
+            )
+        });
     }
     unsafe fn CompressedTexImage2D(
         &mut self,
@@ -1300,10 +1462,11 @@ impl GLES for GLES1OnGL2 {
         data: *const GLvoid,
     ) {
         let data = unsafe { std::slice::from_raw_parts(data.cast::<u8>(), image_size as usize) };
-        // IMG_texture_compression_pvrtc (only on Imagination/Apple GPUs)
+        // IMG_texture_compression_pvrtc (only on Imagination/Apple GPUs) and
+        // OES_compressed_ETC1_RGB8_texture (only in OpenGL ES)
         // TODO: It would be more efficient to use hardware decoding where
         // available (I just don't have a suitable device to try this on)
-        if try_decode_pvrtc(
+        let decoded = try_decode_compressed_texture(
             self,
             target,
             level,
@@ -1312,8 +1475,13 @@ impl GLES for GLES1OnGL2 {
             height,
             border,
             data,
-        ) {
-            log_dbg!("Decoded PVRTC");
+        );
+        if let Err(error) = decoded {
+            if self.emulated_error == 0 {
+                self.emulated_error = error;
+            }
+        } else if decoded == Ok(true) {
+            log_dbg!("Decoded PVRTC or ETC1");
         // OES_compressed_paletted_texture is only in OpenGL ES, so we'll need
         // to decompress those formats.
         } else if let Some(PalettedTextureFormat {
@@ -1367,17 +1535,19 @@ impl GLES for GLES1OnGL2 {
             assert!(decoded.len() == palette_entry_size * index_count);
 
             log_dbg!("Decoded paletted texture");
-            gl21::TexImage2D(
-                target,
-                level,
-                palette_entry_format as _,
-                width,
-                height,
-                border,
-                palette_entry_format,
-                palette_entry_type,
-                decoded.as_ptr() as *const _,
-            )
+            self.define_texture_levels(target, || {
+                gl21::TexImage2D(
+                    target,
+                    level,
+                    palette_entry_format as _,
+                    width,
+                    height,
+                    border,
+                    palette_entry_format,
+                    palette_entry_type,
+                    decoded.as_ptr() as *const _,
+                )
+            });
         } else {
             unimplemented!("CompressedTexImage2D internalformat: {:#x}", internalformat);
         }
@@ -1403,7 +1573,9 @@ impl GLES for GLES1OnGL2 {
                 || internalformat as GLenum == gl21::LUMINANCE_ALPHA
         );
         assert!(border == 0);
-        gl21::CopyTexImage2D(target, level, internalformat, x, y, width, height, border)
+        self.define_texture_levels(target, || {
+            gl21::CopyTexImage2D(target, level, internalformat, x, y, width, height, border)
+        });
     }
     unsafe fn CopyTexSubImage2D(
         &mut self,
@@ -1418,7 +1590,10 @@ impl GLES for GLES1OnGL2 {
     ) {
         assert!(target == gl21::TEXTURE_2D);
         assert!(level >= 0);
-        gl21::CopyTexSubImage2D(target, level, xoffset, yoffset, x, y, width, height)
+        // With GL_GENERATE_MIPMAP, this can also define levels.
+        self.define_texture_levels(target, || {
+            gl21::CopyTexSubImage2D(target, level, xoffset, yoffset, x, y, width, height)
+        });
     }
     unsafe fn TexEnvf(&mut self, target: GLenum, pname: GLenum, param: GLfloat) {
         // TODO: GL_POINT_SPRITE_OES
@@ -1654,6 +1829,12 @@ impl GLES for GLES1OnGL2 {
         width: GLsizei,
         height: GLsizei,
     ) {
+        // OpenGL 2.1 has no 16-bit RGB format, so use one with at least as
+        // much precision.
+        let internalformat = match internalformat {
+            gles11::RGB565_OES => gl21::RGB8,
+            _ => internalformat,
+        };
         gl21::RenderbufferStorageEXT(target, internalformat, width, height)
     }
     unsafe fn FramebufferRenderbufferOES(
@@ -1663,7 +1844,8 @@ impl GLES for GLES1OnGL2 {
         renderbuffertarget: GLenum,
         renderbuffer: GLuint,
     ) {
-        gl21::FramebufferRenderbufferEXT(target, attachment, renderbuffertarget, renderbuffer)
+        gl21::FramebufferRenderbufferEXT(target, attachment, renderbuffertarget, renderbuffer);
+        self.update_draw_and_read_buffers(target);
     }
     unsafe fn FramebufferTexture2DOES(
         &mut self,
@@ -1673,7 +1855,8 @@ impl GLES for GLES1OnGL2 {
         texture: GLuint,
         level: i32,
     ) {
-        gl21::FramebufferTexture2DEXT(target, attachment, textarget, texture, level)
+        gl21::FramebufferTexture2DEXT(target, attachment, textarget, texture, level);
+        self.update_draw_and_read_buffers(target);
     }
     unsafe fn GetRenderbufferParameterivOES(
         &mut self,
@@ -1684,7 +1867,13 @@ impl GLES for GLES1OnGL2 {
         gl21::GetRenderbufferParameterivEXT(target, pname, params)
     }
     unsafe fn CheckFramebufferStatusOES(&mut self, target: GLenum) -> GLenum {
-        gl21::CheckFramebufferStatusEXT(target)
+        match gl21::CheckFramebufferStatusEXT(target) {
+            // These have no OpenGL ES equivalent, and shouldn't happen anyway
+            // thanks to update_draw_and_read_buffers.
+            gl21::FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER_EXT
+            | gl21::FRAMEBUFFER_INCOMPLETE_READ_BUFFER_EXT => gles11::FRAMEBUFFER_UNSUPPORTED_OES,
+            status => status,
+        }
     }
     unsafe fn DeleteFramebuffersOES(&mut self, n: GLsizei, framebuffers: *const GLuint) {
         gl21::DeleteFramebuffersEXT(n, framebuffers)
@@ -1693,6 +1882,340 @@ impl GLES for GLES1OnGL2 {
         gl21::DeleteRenderbuffersEXT(n, renderbuffers)
     }
     unsafe fn GenerateMipmapOES(&mut self, target: GLenum) {
-        gl21::GenerateMipmapEXT(target)
+        self.define_texture_levels(target, || gl21::GenerateMipmapEXT(target));
+    }
+
+    // OES_mapbuffer
+    unsafe fn MapBufferOES(&mut self, target: GLenum, access: GLenum) -> *mut GLvoid {
+        assert!(target == gl21::ARRAY_BUFFER || target == gl21::ELEMENT_ARRAY_BUFFER);
+        assert!(access == gles11::WRITE_ONLY_OES);
+        // OpenGL ES only allows write-only mappings, but reading the
+        // buffer's contents is also allowed here, since the guest wrapper
+        // relies on that.
+        gl21::MapBuffer(target, gl21::READ_WRITE)
+    }
+    unsafe fn UnmapBufferOES(&mut self, target: GLenum) -> GLboolean {
+        assert!(target == gl21::ARRAY_BUFFER || target == gl21::ELEMENT_ARRAY_BUFFER);
+        gl21::UnmapBuffer(target)
+    }
+
+    // Shaders (OpenGL ES 2.0)
+    unsafe fn CreateShader(&mut self, type_: GLenum) -> GLuint {
+        gl21::CreateShader(type_)
+    }
+    unsafe fn DeleteShader(&mut self, shader: GLuint) {
+        gl21::DeleteShader(shader)
+    }
+    unsafe fn IsShader(&mut self, shader: GLuint) -> GLboolean {
+        gl21::IsShader(shader)
+    }
+    unsafe fn ShaderSource(
+        &mut self,
+        shader: GLuint,
+        count: GLsizei,
+        string: *const *const GLchar,
+        length: *const GLint,
+    ) {
+        // The strings are concatenated so the source can be translated as a
+        // whole.
+        let mut source = Vec::new();
+        for i in 0..(count as usize) {
+            let string = *string.add(i);
+            let len = if length.is_null() || length.add(i).read_unaligned() < 0 {
+                CStr::from_ptr(string).to_bytes().len()
+            } else {
+                length.add(i).read_unaligned() as usize
+            };
+            source.extend_from_slice(std::slice::from_raw_parts(string as *const u8, len));
+        }
+        let source = translate_shader_source(&String::from_utf8_lossy(&source));
+        log_dbg!("Translated shader {} source: {:?}", shader, source);
+        let string = source.as_ptr() as *const GLchar;
+        let length = source.len() as GLint;
+        gl21::ShaderSource(shader, 1, &string, &length);
+    }
+    unsafe fn CompileShader(&mut self, shader: GLuint) {
+        gl21::CompileShader(shader)
+    }
+    unsafe fn GetShaderiv(&mut self, shader: GLuint, pname: GLenum, params: *mut GLint) {
+        gl21::GetShaderiv(shader, pname, params)
+    }
+    unsafe fn GetShaderInfoLog(
+        &mut self,
+        shader: GLuint,
+        bufsize: GLsizei,
+        length: *mut GLsizei,
+        infolog: *mut GLchar,
+    ) {
+        gl21::GetShaderInfoLog(shader, bufsize, length, infolog)
+    }
+    unsafe fn GetShaderSource(
+        &mut self,
+        shader: GLuint,
+        bufsize: GLsizei,
+        length: *mut GLsizei,
+        source: *mut GLchar,
+    ) {
+        gl21::GetShaderSource(shader, bufsize, length, source)
+    }
+    unsafe fn GetShaderPrecisionFormat(
+        &mut self,
+        shadertype: GLenum,
+        precisiontype: GLenum,
+        range: *mut GLint,
+        precision: *mut GLint,
+    ) {
+        // OpenGL 2.1 has no equivalent of this. Report the precision a typical
+        // desktop GPU would have for all precision qualifiers, since they are
+        // ignored by translate_shader_source anyway.
+        assert!([gl21::VERTEX_SHADER, gl21::FRAGMENT_SHADER].contains(&shadertype));
+        let (range_min, range_max, bits) = match precisiontype {
+            gles20::LOW_FLOAT | gles20::MEDIUM_FLOAT | gles20::HIGH_FLOAT => (127, 127, 23),
+            gles20::LOW_INT | gles20::MEDIUM_INT | gles20::HIGH_INT => (31, 30, 0),
+            _ => panic!("Unexpected precision type {:#x}", precisiontype),
+        };
+        range.write_unaligned(range_min);
+        range.add(1).write_unaligned(range_max);
+        precision.write_unaligned(bits);
+    }
+    unsafe fn ReleaseShaderCompiler(&mut self) {
+        // This is only a hint.
+    }
+
+    // Programs (OpenGL ES 2.0)
+    unsafe fn CreateProgram(&mut self) -> GLuint {
+        gl21::CreateProgram()
+    }
+    unsafe fn DeleteProgram(&mut self, program: GLuint) {
+        gl21::DeleteProgram(program)
+    }
+    unsafe fn IsProgram(&mut self, program: GLuint) -> GLboolean {
+        gl21::IsProgram(program)
+    }
+    unsafe fn AttachShader(&mut self, program: GLuint, shader: GLuint) {
+        gl21::AttachShader(program, shader)
+    }
+    unsafe fn DetachShader(&mut self, program: GLuint, shader: GLuint) {
+        gl21::DetachShader(program, shader)
+    }
+    unsafe fn GetAttachedShaders(
+        &mut self,
+        program: GLuint,
+        maxcount: GLsizei,
+        count: *mut GLsizei,
+        shaders: *mut GLuint,
+    ) {
+        gl21::GetAttachedShaders(program, maxcount, count, shaders)
+    }
+    unsafe fn LinkProgram(&mut self, program: GLuint) {
+        gl21::LinkProgram(program)
+    }
+    unsafe fn ValidateProgram(&mut self, program: GLuint) {
+        gl21::ValidateProgram(program)
+    }
+    unsafe fn UseProgram(&mut self, program: GLuint) {
+        gl21::UseProgram(program)
+    }
+    unsafe fn GetProgramiv(&mut self, program: GLuint, pname: GLenum, params: *mut GLint) {
+        gl21::GetProgramiv(program, pname, params)
+    }
+    unsafe fn GetProgramInfoLog(
+        &mut self,
+        program: GLuint,
+        bufsize: GLsizei,
+        length: *mut GLsizei,
+        infolog: *mut GLchar,
+    ) {
+        gl21::GetProgramInfoLog(program, bufsize, length, infolog)
+    }
+
+    // Vertex attributes (OpenGL ES 2.0)
+    unsafe fn BindAttribLocation(&mut self, program: GLuint, index: GLuint, name: *const GLchar) {
+        gl21::BindAttribLocation(program, index, name)
+    }
+    unsafe fn GetAttribLocation(&mut self, program: GLuint, name: *const GLchar) -> GLint {
+        gl21::GetAttribLocation(program, name)
+    }
+    unsafe fn GetActiveAttrib(
+        &mut self,
+        program: GLuint,
+        index: GLuint,
+        bufsize: GLsizei,
+        length: *mut GLsizei,
+        size: *mut GLint,
+        type_: *mut GLenum,
+        name: *mut GLchar,
+    ) {
+        gl21::GetActiveAttrib(program, index, bufsize, length, size, type_, name)
+    }
+    unsafe fn EnableVertexAttribArray(&mut self, index: GLuint) {
+        gl21::EnableVertexAttribArray(index)
+    }
+    unsafe fn DisableVertexAttribArray(&mut self, index: GLuint) {
+        gl21::DisableVertexAttribArray(index)
+    }
+    unsafe fn GetVertexAttribfv(&mut self, index: GLuint, pname: GLenum, params: *mut GLfloat) {
+        gl21::GetVertexAttribfv(index, pname, params)
+    }
+    unsafe fn GetVertexAttribiv(&mut self, index: GLuint, pname: GLenum, params: *mut GLint) {
+        gl21::GetVertexAttribiv(index, pname, params)
+    }
+    unsafe fn GetVertexAttribPointerv(
+        &mut self,
+        index: GLuint,
+        pname: GLenum,
+        pointer: *mut *mut GLvoid,
+    ) {
+        // See GetPointerv for why this cast is needed.
+        gl21::GetVertexAttribPointerv(index, pname, pointer as *mut _ as *const _);
+    }
+    unsafe fn VertexAttribPointer(
+        &mut self,
+        index: GLuint,
+        size: GLint,
+        type_: GLenum,
+        normalized: GLboolean,
+        stride: GLsizei,
+        pointer: *const GLvoid,
+    ) {
+        // TODO: Translate fixed-point data like for the OpenGL ES 1.1 arrays.
+        assert!(type_ != gles11::FIXED);
+        gl21::VertexAttribPointer(index, size, type_, normalized, stride, pointer);
+    }
+    unsafe fn VertexAttrib1f(&mut self, index: GLuint, x: GLfloat) {
+        gl21::VertexAttrib1f(index, x)
+    }
+    unsafe fn VertexAttrib2f(&mut self, index: GLuint, x: GLfloat, y: GLfloat) {
+        gl21::VertexAttrib2f(index, x, y)
+    }
+    unsafe fn VertexAttrib3f(&mut self, index: GLuint, x: GLfloat, y: GLfloat, z: GLfloat) {
+        gl21::VertexAttrib3f(index, x, y, z)
+    }
+    unsafe fn VertexAttrib4f(
+        &mut self,
+        index: GLuint,
+        x: GLfloat,
+        y: GLfloat,
+        z: GLfloat,
+        w: GLfloat,
+    ) {
+        gl21::VertexAttrib4f(index, x, y, z, w)
+    }
+    unsafe fn VertexAttrib1fv(&mut self, index: GLuint, values: *const GLfloat) {
+        gl21::VertexAttrib1fv(index, values)
+    }
+    unsafe fn VertexAttrib2fv(&mut self, index: GLuint, values: *const GLfloat) {
+        gl21::VertexAttrib2fv(index, values)
+    }
+    unsafe fn VertexAttrib3fv(&mut self, index: GLuint, values: *const GLfloat) {
+        gl21::VertexAttrib3fv(index, values)
+    }
+    unsafe fn VertexAttrib4fv(&mut self, index: GLuint, values: *const GLfloat) {
+        gl21::VertexAttrib4fv(index, values)
+    }
+
+    // Uniforms (OpenGL ES 2.0)
+    unsafe fn GetUniformLocation(&mut self, program: GLuint, name: *const GLchar) -> GLint {
+        gl21::GetUniformLocation(program, name)
+    }
+    unsafe fn GetActiveUniform(
+        &mut self,
+        program: GLuint,
+        index: GLuint,
+        bufsize: GLsizei,
+        length: *mut GLsizei,
+        size: *mut GLint,
+        type_: *mut GLenum,
+        name: *mut GLchar,
+    ) {
+        gl21::GetActiveUniform(program, index, bufsize, length, size, type_, name)
+    }
+    unsafe fn GetUniformfv(&mut self, program: GLuint, location: GLint, params: *mut GLfloat) {
+        gl21::GetUniformfv(program, location, params)
+    }
+    unsafe fn GetUniformiv(&mut self, program: GLuint, location: GLint, params: *mut GLint) {
+        gl21::GetUniformiv(program, location, params)
+    }
+    unsafe fn Uniform1f(&mut self, location: GLint, x: GLfloat) {
+        gl21::Uniform1f(location, x)
+    }
+    unsafe fn Uniform2f(&mut self, location: GLint, x: GLfloat, y: GLfloat) {
+        gl21::Uniform2f(location, x, y)
+    }
+    unsafe fn Uniform3f(&mut self, location: GLint, x: GLfloat, y: GLfloat, z: GLfloat) {
+        gl21::Uniform3f(location, x, y, z)
+    }
+    unsafe fn Uniform4f(
+        &mut self,
+        location: GLint,
+        x: GLfloat,
+        y: GLfloat,
+        z: GLfloat,
+        w: GLfloat,
+    ) {
+        gl21::Uniform4f(location, x, y, z, w)
+    }
+    unsafe fn Uniform1i(&mut self, location: GLint, x: GLint) {
+        gl21::Uniform1i(location, x)
+    }
+    unsafe fn Uniform2i(&mut self, location: GLint, x: GLint, y: GLint) {
+        gl21::Uniform2i(location, x, y)
+    }
+    unsafe fn Uniform3i(&mut self, location: GLint, x: GLint, y: GLint, z: GLint) {
+        gl21::Uniform3i(location, x, y, z)
+    }
+    unsafe fn Uniform4i(&mut self, location: GLint, x: GLint, y: GLint, z: GLint, w: GLint) {
+        gl21::Uniform4i(location, x, y, z, w)
+    }
+    unsafe fn Uniform1fv(&mut self, location: GLint, count: GLsizei, values: *const GLfloat) {
+        gl21::Uniform1fv(location, count, values)
+    }
+    unsafe fn Uniform2fv(&mut self, location: GLint, count: GLsizei, values: *const GLfloat) {
+        gl21::Uniform2fv(location, count, values)
+    }
+    unsafe fn Uniform3fv(&mut self, location: GLint, count: GLsizei, values: *const GLfloat) {
+        gl21::Uniform3fv(location, count, values)
+    }
+    unsafe fn Uniform4fv(&mut self, location: GLint, count: GLsizei, values: *const GLfloat) {
+        gl21::Uniform4fv(location, count, values)
+    }
+    unsafe fn Uniform1iv(&mut self, location: GLint, count: GLsizei, values: *const GLint) {
+        gl21::Uniform1iv(location, count, values)
+    }
+    unsafe fn Uniform2iv(&mut self, location: GLint, count: GLsizei, values: *const GLint) {
+        gl21::Uniform2iv(location, count, values)
+    }
+    unsafe fn Uniform3iv(&mut self, location: GLint, count: GLsizei, values: *const GLint) {
+        gl21::Uniform3iv(location, count, values)
+    }
+    unsafe fn Uniform4iv(&mut self, location: GLint, count: GLsizei, values: *const GLint) {
+        gl21::Uniform4iv(location, count, values)
+    }
+    unsafe fn UniformMatrix2fv(
+        &mut self,
+        location: GLint,
+        count: GLsizei,
+        transpose: GLboolean,
+        value: *const GLfloat,
+    ) {
+        gl21::UniformMatrix2fv(location, count, transpose, value)
+    }
+    unsafe fn UniformMatrix3fv(
+        &mut self,
+        location: GLint,
+        count: GLsizei,
+        transpose: GLboolean,
+        value: *const GLfloat,
+    ) {
+        gl21::UniformMatrix3fv(location, count, transpose, value)
+    }
+    unsafe fn UniformMatrix4fv(
+        &mut self,
+        location: GLint,
+        count: GLsizei,
+        transpose: GLboolean,
+        value: *const GLfloat,
+    ) {
+        gl21::UniformMatrix4fv(location, count, transpose, value)
     }
 }
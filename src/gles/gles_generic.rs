@@ -3,7 +3,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/.
  */
-//! Generic OpenGL ES 1.1 interface.
+//! Generic OpenGL ES 1.1 interface, with optional OpenGL ES 2.0 parts.
 //!
 //! Unfortunately this does not provide the types and constants, so the correct
 //! usage is to import `GLES` and `types` from this module, but get the
@@ -112,6 +112,8 @@ pub trait GLES {
         size: GLsizeiptr,
         data: *const GLvoid,
     );
+    unsafe fn IsBuffer(&mut self, buffer: GLuint) -> GLboolean;
+    unsafe fn GetBufferParameteriv(&mut self, target: GLenum, pname: GLenum, params: *mut GLint);
 
     // Non-pointers
     unsafe fn Color4f(&mut self, red: GLfloat, green: GLfloat, blue: GLfloat, alpha: GLfloat);
@@ -349,4 +351,174 @@ pub trait GLES {
     unsafe fn DeleteFramebuffersOES(&mut self, n: GLsizei, framebuffers: *const GLuint);
     unsafe fn DeleteRenderbuffersOES(&mut self, n: GLsizei, renderbuffers: *const GLuint);
     unsafe fn GenerateMipmapOES(&mut self, target: GLenum);
+
+    // OES_mapbuffer
+    /// Unlike in OpenGL ES, the mapped memory should also be readable, so its
+    /// existing contents can be copied to guest memory.
+    unsafe fn MapBufferOES(&mut self, target: GLenum, access: GLenum) -> *mut GLvoid;
+    unsafe fn UnmapBufferOES(&mut self, target: GLenum) -> GLboolean;
+
+    // OpenGL ES 2.0. Only implementations listed in
+    // [super::GLESImplementation::GLES2_IMPLEMENTATIONS] support these.
+
+    // Shaders (OpenGL ES 2.0)
+    unsafe fn CreateShader(&mut self, type_: GLenum) -> GLuint;
+    unsafe fn DeleteShader(&mut self, shader: GLuint);
+    unsafe fn IsShader(&mut self, shader: GLuint) -> GLboolean;
+    unsafe fn ShaderSource(
+        &mut self,
+        shader: GLuint,
+        count: GLsizei,
+        string: *const *const GLchar,
+        length: *const GLint,
+    );
+    unsafe fn CompileShader(&mut self, shader: GLuint);
+    unsafe fn GetShaderiv(&mut self, shader: GLuint, pname: GLenum, params: *mut GLint);
+    unsafe fn GetShaderInfoLog(
+        &mut self,
+        shader: GLuint,
+        bufsize: GLsizei,
+        length: *mut GLsizei,
+        infolog: *mut GLchar,
+    );
+    unsafe fn GetShaderSource(
+        &mut self,
+        shader: GLuint,
+        bufsize: GLsizei,
+        length: *mut GLsizei,
+        source: *mut GLchar,
+    );
+    unsafe fn GetShaderPrecisionFormat(
+        &mut self,
+        shadertype: GLenum,
+        precisiontype: GLenum,
+        range: *mut GLint,
+        precision: *mut GLint,
+    );
+    unsafe fn ReleaseShaderCompiler(&mut self);
+
+    // Programs (OpenGL ES 2.0)
+    unsafe fn CreateProgram(&mut self) -> GLuint;
+    unsafe fn DeleteProgram(&mut self, program: GLuint);
+    unsafe fn IsProgram(&mut self, program: GLuint) -> GLboolean;
+    unsafe fn AttachShader(&mut self, program: GLuint, shader: GLuint);
+    unsafe fn DetachShader(&mut self, program: GLuint, shader: GLuint);
+    unsafe fn GetAttachedShaders(
+        &mut self,
+        program: GLuint,
+        maxcount: GLsizei,
+        count: *mut GLsizei,
+        shaders: *mut GLuint,
+    );
+    unsafe fn LinkProgram(&mut self, program: GLuint);
+    unsafe fn ValidateProgram(&mut self, program: GLuint);
+    unsafe fn UseProgram(&mut self, program: GLuint);
+    unsafe fn GetProgramiv(&mut self, program: GLuint, pname: GLenum, params: *mut GLint);
+    unsafe fn GetProgramInfoLog(
+        &mut self,
+        program: GLuint,
+        bufsize: GLsizei,
+        length: *mut GLsizei,
+        infolog: *mut GLchar,
+    );
+
+    // Vertex attributes (OpenGL ES 2.0)
+    unsafe fn BindAttribLocation(&mut self, program: GLuint, index: GLuint, name: *const GLchar);
+    unsafe fn GetAttribLocation(&mut self, program: GLuint, name: *const GLchar) -> GLint;
+    unsafe fn GetActiveAttrib(
+        &mut self,
+        program: GLuint,
+        index: GLuint,
+        bufsize: GLsizei,
+        length: *mut GLsizei,
+        size: *mut GLint,
+        type_: *mut GLenum,
+        name: *mut GLchar,
+    );
+    unsafe fn EnableVertexAttribArray(&mut self, index: GLuint);
+    unsafe fn DisableVertexAttribArray(&mut self, index: GLuint);
+    unsafe fn GetVertexAttribfv(&mut self, index: GLuint, pname: GLenum, params: *mut GLfloat);
+    unsafe fn GetVertexAttribiv(&mut self, index: GLuint, pname: GLenum, params: *mut GLint);
+    unsafe fn GetVertexAttribPointerv(
+        &mut self,
+        index: GLuint,
+        pname: GLenum,
+        pointer: *mut *mut GLvoid,
+    );
+    unsafe fn VertexAttribPointer(
+        &mut self,
+        index: GLuint,
+        size: GLint,
+        type_: GLenum,
+        normalized: GLboolean,
+        stride: GLsizei,
+        pointer: *const GLvoid,
+    );
+    unsafe fn VertexAttrib1f(&mut self, index: GLuint, x: GLfloat);
+    unsafe fn VertexAttrib2f(&mut self, index: GLuint, x: GLfloat, y: GLfloat);
+    unsafe fn VertexAttrib3f(&mut self, index: GLuint, x: GLfloat, y: GLfloat, z: GLfloat);
+    unsafe fn VertexAttrib4f(
+        &mut self,
+        index: GLuint,
+        x: GLfloat,
+        y: GLfloat,
+        z: GLfloat,
+        w: GLfloat,
+    );
+    unsafe fn VertexAttrib1fv(&mut self, index: GLuint, values: *const GLfloat);
+    unsafe fn VertexAttrib2fv(&mut self, index: GLuint, values: *const GLfloat);
+    unsafe fn VertexAttrib3fv(&mut self, index: GLuint, values: *const GLfloat);
+    unsafe fn VertexAttrib4fv(&mut self, index: GLuint, values: *const GLfloat);
+
+    // Uniforms (OpenGL ES 2.0)
+    unsafe fn GetUniformLocation(&mut self, program: GLuint, name: *const GLchar) -> GLint;
+    unsafe fn GetActiveUniform(
+        &mut self,
+        program: GLuint,
+        index: GLuint,
+        bufsize: GLsizei,
+        length: *mut GLsizei,
+        size: *mut GLint,
+        type_: *mut GLenum,
+        name: *mut GLchar,
+    );
+    unsafe fn GetUniformfv(&mut self, program: GLuint, location: GLint, params: *mut GLfloat);
+    unsafe fn GetUniformiv(&mut self, program: GLuint, location: GLint, params: *mut GLint);
+    unsafe fn Uniform1f(&mut self, location: GLint, x: GLfloat);
+    unsafe fn Uniform2f(&mut self, location: GLint, x: GLfloat, y: GLfloat);
+    unsafe fn Uniform3f(&mut self, location: GLint, x: GLfloat, y: GLfloat, z: GLfloat);
+    unsafe fn Uniform4f(&mut self, location: GLint, x: GLfloat, y: GLfloat, z: GLfloat, w: GLfloat);
+    unsafe fn Uniform1i(&mut self, location: GLint, x: GLint);
+    unsafe fn Uniform2i(&mut self, location: GLint, x: GLint, y: GLint);
+    unsafe fn Uniform3i(&mut self, location: GLint, x: GLint, y: GLint, z: GLint);
+    unsafe fn Uniform4i(&mut self, location: GLint, x: GLint, y: GLint, z: GLint, w: GLint);
+    unsafe fn Uniform1fv(&mut self, location: GLint, count: GLsizei, values: *const GLfloat);
+    unsafe fn Uniform2fv(&mut self, location: GLint, count: GLsizei, values: *const GLfloat);
+    unsafe fn Uniform3fv(&mut self, location: GLint, count: GLsizei, values: *const GLfloat);
+    unsafe fn Uniform4fv(&mut self, location: GLint, count: GLsizei, values: *const GLfloat);
+    unsafe fn Uniform1iv(&mut self, location: GLint, count: GLsizei, values: *const GLint);
+    unsafe fn Uniform2iv(&mut self, location: GLint, count: GLsizei, values: *const GLint);
+    unsafe fn Uniform3iv(&mut self, location: GLint, count: GLsizei, values: *const GLint);
+    unsafe fn Uniform4iv(&mut self, location: GLint, count: GLsizei, values: *const GLint);
+    unsafe fn UniformMatrix2fv(
+        &mut self,
+        location: GLint,
+        count: GLsizei,
+        transpose: GLboolean,
+        value: *const GLfloat,
+    );
+    unsafe fn UniformMatrix3fv(
+        &mut self,
+        location: GLint,
+        count: GLsizei,
+        transpose: GLboolean,
+        value: *const GLfloat,
+    );
+    unsafe fn UniformMatrix4fv(
+        &mut self,
+        location: GLint,
+        count: GLsizei,
+        transpose: GLboolean,
+        value: *const GLfloat,
+    );
 }
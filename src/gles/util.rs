@@ -137,12 +137,17 @@ impl ParamTable {
 }
 
 /// Helper for implementing `glCompressedTexImage2D`: if `internalformat` is
-/// one of the `IMG_texture_compression_pvrtc` formats, decode it and call
-/// `glTexImage2D`. Returns `true` if this is done.
+/// one of the `IMG_texture_compression_pvrtc` formats or the
+/// `OES_compressed_ETC1_RGB8_texture` format, decode it and call
+/// `glTexImage2D`. Returns `Ok(true)` if this is done.
 ///
-/// Note that this panics rather than create GL errors for invalid use (TODO?)
+/// If the size of the data doesn't match the format, the GL error that should
+/// be reported is returned, and nothing is done.
+///
+/// Note that this panics rather than create GL errors for some other kinds of
+/// invalid use (TODO?)
 #[allow(clippy::too_many_arguments)]
-pub fn try_decode_pvrtc(
+pub fn try_decode_compressed_texture(
     gles: &mut dyn GLES,
     target: GLenum,
     level: GLint,
@@ -150,21 +155,43 @@ pub fn try_decode_pvrtc(
     width: GLsizei,
     height: GLsizei,
     border: GLint,
-    pvrtc_data: &[u8],
-) -> bool {
-    let is_2bit = match internalformat {
-        gles11::COMPRESSED_RGB_PVRTC_4BPPV1_IMG | gles11::COMPRESSED_RGBA_PVRTC_4BPPV1_IMG => false,
-        gles11::COMPRESSED_RGB_PVRTC_2BPPV1_IMG | gles11::COMPRESSED_RGBA_PVRTC_2BPPV1_IMG => true,
-        _ => return false,
+    data: &[u8],
+) -> Result<bool, GLenum> {
+    let pvrtc_is_2bit = match internalformat {
+        gles11::COMPRESSED_RGB_PVRTC_4BPPV1_IMG | gles11::COMPRESSED_RGBA_PVRTC_4BPPV1_IMG => {
+            Some(false)
+        }
+        gles11::COMPRESSED_RGB_PVRTC_2BPPV1_IMG | gles11::COMPRESSED_RGBA_PVRTC_2BPPV1_IMG => {
+            Some(true)
+        }
+        gles11::ETC1_RGB8_OES => None,
+        _ => return Ok(false),
     };
 
     assert!(border == 0);
-    let pixels = crate::image::decode_pvrtc(
-        pvrtc_data,
-        is_2bit,
-        width.try_into().unwrap(),
-        height.try_into().unwrap(),
-    );
+    let (Ok(width_u32), Ok(height_u32)) = (u32::try_from(width), u32::try_from(height)) else {
+        return Err(gles11::INVALID_VALUE);
+    };
+    let expected_size = match pvrtc_is_2bit {
+        Some(is_2bit) => crate::image::pvrtc_size(is_2bit, width_u32, height_u32),
+        None => crate::image::etc1_size(width_u32, height_u32),
+    };
+    if data.len() != expected_size {
+        log!(
+            "Warning: glCompressedTexImage2D data size for format {:#x} at {}x{} should be {}, but is {}",
+            internalformat,
+            width,
+            height,
+            expected_size,
+            data.len()
+        );
+        return Err(gles11::INVALID_VALUE);
+    }
+
+    let pixels = match pvrtc_is_2bit {
+        Some(is_2bit) => crate::image::decode_pvrtc(data, is_2bit, width_u32, height_u32),
+        None => crate::image::decode_etc1(data, width_u32, height_u32),
+    };
     unsafe {
         gles.TexImage2D(
             target,
@@ -178,6 +205,48 @@ pub fn try_decode_pvrtc(
             pixels.as_ptr() as *const _,
         )
     };
+    Ok(true)
+}
+
+/// The formats accepted by `glCompressedTexImage2D`, regardless of what the
+/// host supports, since they're all decoded by us if necessary.
+const COMPRESSED_TEXTURE_FORMATS: &[GLenum] = &[
+    gles11::COMPRESSED_RGB_PVRTC_4BPPV1_IMG,
+    gles11::COMPRESSED_RGBA_PVRTC_4BPPV1_IMG,
+    gles11::COMPRESSED_RGB_PVRTC_2BPPV1_IMG,
+    gles11::COMPRESSED_RGBA_PVRTC_2BPPV1_IMG,
+    gles11::ETC1_RGB8_OES,
+    gles11::PALETTE4_RGB8_OES,
+    gles11::PALETTE4_RGBA8_OES,
+    gles11::PALETTE4_R5_G6_B5_OES,
+    gles11::PALETTE4_RGBA4_OES,
+    gles11::PALETTE4_RGB5_A1_OES,
+    gles11::PALETTE8_RGB8_OES,
+    gles11::PALETTE8_RGBA8_OES,
+    gles11::PALETTE8_R5_G6_B5_OES,
+    gles11::PALETTE8_RGBA4_OES,
+    gles11::PALETTE8_RGB5_A1_OES,
+];
+
+/// Helper for implementing `glGetIntegerv`: if `pname` is
+/// `GL_NUM_COMPRESSED_TEXTURE_FORMATS` or `GL_COMPRESSED_TEXTURE_FORMATS`,
+/// write the formats we support rather than the host's. Returns `true` if this
+/// is done.
+///
+/// # Safety
+/// `params` must be valid for writing as many values as `pname` requires.
+pub unsafe fn try_get_compressed_texture_formats(pname: GLenum, params: *mut GLint) -> bool {
+    match pname {
+        gles11::NUM_COMPRESSED_TEXTURE_FORMATS => {
+            params.write_unaligned(COMPRESSED_TEXTURE_FORMATS.len() as GLint);
+        }
+        gles11::COMPRESSED_TEXTURE_FORMATS => {
+            for (i, &format) in COMPRESSED_TEXTURE_FORMATS.iter().enumerate() {
+                params.add(i).write_unaligned(format as GLint);
+            }
+        }
+        _ => return false,
+    }
     true
 }
 
@@ -250,3 +319,147 @@ impl PalettedTextureFormat {
         }
     }
 }
+
+/// Translate OpenGL ES Shading Language 1.00 (used by OpenGL ES 2.0) source
+/// code to OpenGL Shading Language 1.20 (used by OpenGL 2.1).
+///
+/// The languages are very similar. The main differences dealt with here are
+/// that GLSL ES has precision qualifiers and default precision statements,
+/// which GLSL 1.20 doesn't know about, and a different `#version`. Line
+/// numbers are preserved so that compiler errors still match the app's source.
+///
+/// `GL_ES` is deliberately left undefined (names starting with `GL_` are
+/// reserved, and some compilers reject defining them), so `#ifdef GL_ES`
+/// blocks in the app's source are skipped like on any desktop GL.
+pub fn translate_shader_source(source: &str) -> String {
+    let mut translated = String::with_capacity(source.len() + 128);
+    translated.push_str(concat!(
+        "#version 120\n",
+        "#define lowp\n",
+        "#define mediump\n",
+        "#define highp\n",
+        "#line 1\n",
+    ));
+
+    let mut chars = source.chars().peekable();
+    let mut at_line_start = true;
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => {
+                translated.push(c);
+                while let Some(&next) = chars.peek() {
+                    if next == '\n' {
+                        break;
+                    }
+                    translated.push(next);
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                translated.push(c);
+                translated.push(chars.next().unwrap());
+                let mut prev = '\0';
+                for next in chars.by_ref() {
+                    translated.push(next);
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+            }
+            '#' if at_line_start => {
+                let mut directive = String::from(c);
+                while let Some(&next) = chars.peek() {
+                    if next == '\n' {
+                        break;
+                    }
+                    directive.push(next);
+                    chars.next();
+                }
+                let words: Vec<&str> = directive[1..].split_whitespace().collect();
+                let remove = match words[..] {
+                    // Replaced by the version above.
+                    ["version", ..] => true,
+                    // Derivatives are a core feature of GLSL 1.20.
+                    ["extension", name, ..] => name.starts_with("GL_OES_standard_derivatives"),
+                    _ => false,
+                };
+                if !remove {
+                    translated.push_str(&directive);
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut word = String::from(c);
+                while let Some(&next) = chars.peek() {
+                    if !(next.is_ascii_alphanumeric() || next == '_') {
+                        break;
+                    }
+                    word.push(next);
+                    chars.next();
+                }
+                if word == "precision" {
+                    // Skip the statement, e.g. `precision mediump float;`,
+                    // but keep its line breaks.
+                    for next in chars.by_ref() {
+                        if next == '\n' {
+                            translated.push(next);
+                        } else if next == ';' {
+                            break;
+                        }
+                    }
+                } else {
+                    translated.push_str(&word);
+                }
+            }
+            c => translated.push(c),
+        }
+        at_line_start = match c {
+            '\n' => true,
+            ' ' | '\t' | '\r' => at_line_start,
+            _ => false,
+        };
+    }
+    translated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translate_body(source: &str) -> String {
+        let translated = translate_shader_source(source);
+        let (_, body) = translated.split_once("#line 1\n").unwrap();
+        body.to_string()
+    }
+
+    #[test]
+    fn test_translate_shader_source() {
+        assert_eq!(
+            translate_shader_source("#ifdef GL_ES\nprecision highp float;\n#endif\n"),
+            concat!(
+                "#version 120\n",
+                "#define lowp\n",
+                "#define mediump\n",
+                "#define highp\n",
+                "#line 1\n",
+                "#ifdef GL_ES\n\n#endif\n",
+            )
+        );
+        assert_eq!(
+            translate_body("#version 100\nprecision mediump float;\nvoid main() {}\n"),
+            "\n\nvoid main() {}\n"
+        );
+        assert_eq!(
+            translate_body("precision\n  highp float; // precision\nuniform lowp vec4 c;"),
+            "\n // precision\nuniform lowp vec4 c;"
+        );
+        assert_eq!(
+            translate_body(concat!(
+                "/* precision; */ #define A 1\n",
+                "  #extension GL_OES_standard_derivatives : enable\n",
+                "int precisionx;",
+            )),
+            "/* precision; */ #define A 1\n  \nint precisionx;"
+        );
+    }
+}
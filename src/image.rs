@@ -10,7 +10,7 @@
 //!
 //! This module also exposes decompression for Imagination Technologies' PVRTC
 //! format, implementing as a wrapper around their decoder from the PowerVR
-//! SDK.
+//! SDK, and for Ericsson's ETC1 format, which is simple enough to decode here.
 
 use std::ffi::{c_int, c_uchar, CStr};
 
@@ -196,15 +196,21 @@ pub fn gamma_decode(intensity: f32) -> f32 {
     intensity.powf(2.2)
 }
 
-/// Decodes Imagination Technologies' PVRTC texture compression format to
-/// RGBA (8 bits per channel).
-pub fn decode_pvrtc(pvrtc_data: &[u8], is_2bit: bool, width: u32, height: u32) -> Vec<u32> {
+/// Size in bytes of an image in Imagination Technologies' PVRTC texture
+/// compression format.
+pub fn pvrtc_size(is_2bit: bool, width: u32, height: u32) -> usize {
     // This formula is from the IMG_texture_compression_pvrtc extension spec.
-    let expected_size = if is_2bit {
+    if is_2bit {
         (width.max(16) as usize * height.max(8) as usize * 2 + 7) / 8
     } else {
         (width.max(8) as usize * height.max(8) as usize * 4 + 7) / 8
-    };
+    }
+}
+
+/// Decodes Imagination Technologies' PVRTC texture compression format to
+/// RGBA (8 bits per channel).
+pub fn decode_pvrtc(pvrtc_data: &[u8], is_2bit: bool, width: u32, height: u32) -> Vec<u32> {
+    let expected_size = pvrtc_size(is_2bit, width, height);
     assert!(pvrtc_data.len() == expected_size);
 
     let rgba8_word_count = width as usize * height as usize;
@@ -224,3 +230,104 @@ pub fn decode_pvrtc(pvrtc_data: &[u8], is_2bit: bool, width: u32, height: u32) -
     };
     rgba8_data
 }
+
+/// Size in bytes of an image in the ETC1 texture compression format.
+pub fn etc1_size(width: u32, height: u32) -> usize {
+    // Each 4x4 block of pixels is 64 bits, and partial blocks are padded.
+    width.div_ceil(4) as usize * height.div_ceil(4) as usize * 8
+}
+
+/// Decodes the ETC1 texture compression format (as in the
+/// OES_compressed_ETC1_RGB8_texture extension) to RGBA (8 bits per channel).
+pub fn decode_etc1(etc1_data: &[u8], width: u32, height: u32) -> Vec<u32> {
+    // Modifiers for each of the 8 tables. The pixel indices select from these.
+    const MODIFIERS: [[i32; 4]; 8] = [
+        [2, 8, -2, -8],
+        [5, 17, -5, -17],
+        [9, 29, -9, -29],
+        [13, 42, -13, -42],
+        [18, 60, -18, -60],
+        [24, 80, -24, -80],
+        [33, 106, -33, -106],
+        [47, 183, -47, -183],
+    ];
+
+    assert!(etc1_data.len() == etc1_size(width, height));
+
+    let (width, height) = (width as usize, height as usize);
+    let mut rgba8_data = vec![0u32; width * height];
+    let blocks_per_row = width.div_ceil(4);
+    for (block_index, block) in etc1_data.chunks_exact(8).enumerate() {
+        let block = u64::from_be_bytes(block.try_into().unwrap());
+        let bits = |shift: u32, count: u32| ((block >> shift) & ((1 << count) - 1)) as i32;
+
+        let diff_mode = bits(33, 1) == 1;
+        let flip = bits(32, 1) == 1;
+        // Base colors of the two sub-blocks
+        let bases: [[i32; 3]; 2] = if diff_mode {
+            let extend = |c: i32| (c << 3) | (c >> 2);
+            let mut bases = [[0; 3]; 2];
+            for (channel, shift) in [63, 55, 47].into_iter().enumerate() {
+                let base = bits(shift - 4, 5);
+                // 3-bit two's complement
+                let delta = (bits(shift - 7, 3) << 29) >> 29;
+                bases[0][channel] = extend(base);
+                bases[1][channel] = extend((base + delta) & 31);
+            }
+            bases
+        } else {
+            let extend = |c: i32| (c << 4) | c;
+            let mut bases = [[0; 3]; 2];
+            for (channel, shift) in [63, 55, 47].into_iter().enumerate() {
+                bases[0][channel] = extend(bits(shift - 3, 4));
+                bases[1][channel] = extend(bits(shift - 7, 4));
+            }
+            bases
+        };
+        let tables = [bits(37, 3) as usize, bits(34, 3) as usize];
+
+        let block_x = (block_index % blocks_per_row) * 4;
+        let block_y = (block_index / blocks_per_row) * 4;
+        for x in 0..4 {
+            for y in 0..4 {
+                let (pixel_x, pixel_y) = (block_x + x, block_y + y);
+                if pixel_x >= width || pixel_y >= height {
+                    continue;
+                }
+                // The sub-blocks are 2x4 side-by-side, or 4x2 if flipped.
+                let sub_block = if flip { y / 2 } else { x / 2 };
+                // Pixels are in column-major order, and the most and least
+                // significant bits of their indices are stored separately.
+                let i = (x * 4 + y) as u32;
+                let index = (bits(16 + i, 1) << 1 | bits(i, 1)) as usize;
+                let modifier = MODIFIERS[tables[sub_block]][index];
+                let [r, g, b] = bases[sub_block].map(|c| (c + modifier).clamp(0, 255) as u8);
+                rgba8_data[pixel_y * width + pixel_x] = u32::from_le_bytes([r, g, b, 255]);
+            }
+        }
+    }
+    rgba8_data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_etc1() {
+        // Individual mode, one color per sub-block, table 0, all indices 0.
+        let block = [0xA3, 0x5C, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let pixels = decode_etc1(&block, 4, 4);
+        let left = u32::from_le_bytes([0xAA + 2, 0x55 + 2, 0x00 + 2, 255]);
+        let right = u32::from_le_bytes([0x33 + 2, 0xCC + 2, 0x77 + 2, 255]);
+        for y in 0..4 {
+            assert_eq!(pixels[y * 4..y * 4 + 4], [left, left, right, right]);
+        }
+
+        // Differential mode, flipped, table 7 for the bottom, all indices 3.
+        let block = [0x80, 0x00, 0x00, 0x1F, 0xFF, 0xFF, 0xFF, 0xFF];
+        let pixels = decode_etc1(&block, 2, 2);
+        let top = u32::from_le_bytes([(132 - 8) as u8, 0, 0, 255]);
+        assert_eq!(pixels, [top, top, top, top]);
+    }
+}
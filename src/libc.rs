@@ -42,6 +42,7 @@ pub struct State {
     pthread: pthread::State,
     pub semaphore: semaphore::State,
     stdlib: stdlib::State,
+    stdio: stdio::State,
     string: string::State,
     time: time::State,
     errno: errno::State,
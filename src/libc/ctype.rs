@@ -142,7 +142,7 @@ fn get_default_rune_locale(mem: &mut Mem) -> ConstVoidPtr {
 
 pub const CONSTANTS: ConstantExports = &[(
     "__DefaultRuneLocale",
-    HostConstant::Custom(get_default_rune_locale),
+    HostConstant::Custom(|env| get_default_rune_locale(&mut env.mem)),
 )];
 
 pub const FUNCTIONS: FunctionExports =
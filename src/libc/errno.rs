@@ -12,9 +12,13 @@ use crate::Environment;
 use std::io::Write;
 
 pub const EPERM: i32 = 1;
+pub const EIO: i32 = 5;
+pub const EBADF: i32 = 9;
 pub const EDEADLK: i32 = 11;
+pub const ENOMEM: i32 = 12;
 pub const EBUSY: i32 = 16;
 pub const EINVAL: i32 = 22;
+pub const ERANGE: i32 = 34;
 
 #[derive(Default)]
 pub struct State {
@@ -27,15 +31,22 @@ impl State {
         thread: crate::ThreadId,
     ) -> MutPtr<i32> {
         *self.errnos.entry(thread).or_insert_with(|| {
-            log!(
-                "TODO: errno accessed on thread {} (will always be 0)",
-                thread
-            );
+            log_dbg!("Allocating errno for thread {}", thread);
             mem.alloc_and_write(0i32)
         })
     }
 }
 
+/// Set the current thread's `errno`, for use by host implementations of
+/// functions that report errors this way.
+pub fn set_errno(env: &mut Environment, value: i32) {
+    let ptr = env
+        .libc_state
+        .errno
+        .errno_for_thread(&mut env.mem, env.current_thread);
+    env.mem.write(ptr, value);
+}
+
 fn __error(env: &mut Environment) -> MutPtr<i32> {
     env.libc_state
         .errno
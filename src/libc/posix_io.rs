@@ -10,6 +10,7 @@ pub mod stat;
 use crate::abi::DotDotDot;
 use crate::dyld::{export_c_func, FunctionExports};
 use crate::fs::{GuestFile, GuestOpenOptions, GuestPath};
+use crate::libc::errno::{set_errno, EBADF, EIO};
 use crate::mem::{ConstPtr, ConstVoidPtr, GuestISize, GuestUSize, MutPtr, MutVoidPtr, Ptr};
 use crate::Environment;
 use std::io::{Read, Seek, SeekFrom, Write};
@@ -21,14 +22,17 @@ pub struct State {
 }
 impl State {
     fn file_for_fd(&mut self, fd: FileDescriptor) -> Option<&mut PosixFileHostObject> {
+        let idx = usize::try_from(fd.checked_sub(NORMAL_FILENO_BASE)?).ok()?;
         self.files
-            .get_mut(fd_to_file_idx(fd))
+            .get_mut(idx)
             .and_then(|file_or_none| file_or_none.as_mut())
     }
 }
 
 struct PosixFileHostObject {
     file: GuestFile,
+    /// Whether the file was opened with [O_WRONLY] or [O_RDWR].
+    writable: bool,
     reached_eof: bool,
 }
 
@@ -134,6 +138,7 @@ pub fn open_direct(env: &mut Environment, path: ConstPtr<u8>, flags: i32) -> Fil
         Ok(file) => {
             let host_object = PosixFileHostObject {
                 file,
+                writable: flags & O_ACCMODE != O_RDONLY,
                 reached_eof: false,
             };
 
@@ -178,8 +183,16 @@ pub fn read(
     buffer: MutVoidPtr,
     size: GuestUSize,
 ) -> GuestISize {
-    // TODO: error handling for unknown fd?
-    let file = env.libc_state.posix_io.file_for_fd(fd).unwrap();
+    let Some(file) = env.libc_state.posix_io.file_for_fd(fd) else {
+        log!(
+            "Warning: read({:?}, {:?}, {:#x}) on unknown file descriptor, returning -1",
+            fd,
+            buffer,
+            size,
+        );
+        set_errno(env, EBADF);
+        return -1;
+    };
 
     let buffer_slice = env.mem.bytes_at_mut(buffer.cast(), size);
     match file.file.read(buffer_slice) {
@@ -223,7 +236,9 @@ pub fn read(
 
 /// Helper for C `feof()`.
 pub(super) fn eof(env: &mut Environment, fd: FileDescriptor) -> i32 {
-    let file = env.libc_state.posix_io.file_for_fd(fd).unwrap();
+    let Some(file) = env.libc_state.posix_io.file_for_fd(fd) else {
+        return 0;
+    };
     if file.reached_eof {
         1
     } else {
@@ -237,8 +252,21 @@ pub fn write(
     buffer: ConstVoidPtr,
     size: GuestUSize,
 ) -> GuestISize {
-    // TODO: error handling for unknown fd?
-    let file = env.libc_state.posix_io.file_for_fd(fd).unwrap();
+    let Some(file) = env
+        .libc_state
+        .posix_io
+        .file_for_fd(fd)
+        .filter(|file| file.writable)
+    else {
+        log!(
+            "Warning: write({:?}, {:?}, {:#x}) on unknown or read-only file descriptor, returning -1",
+            fd,
+            buffer,
+            size,
+        );
+        set_errno(env, EBADF);
+        return -1;
+    };
 
     let buffer_slice = env.mem.bytes_at(buffer.cast(), size);
     match file.file.write(buffer_slice) {
@@ -263,7 +291,6 @@ pub fn write(
             bytes_written.try_into().unwrap()
         }
         Err(e) => {
-            // TODO: set errno
             log!(
                 "Warning: write({:?}, {:?}, {:#x}) encountered error {:?}, returning -1",
                 fd,
@@ -271,6 +298,7 @@ pub fn write(
                 size,
                 e,
             );
+            set_errno(env, EIO);
             -1
         }
     }
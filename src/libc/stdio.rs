@@ -12,7 +12,9 @@ use super::posix_io::{
 use crate::dyld::{export_c_func, ConstantExports, FunctionExports, HostConstant};
 use crate::fs::GuestPath;
 use crate::libc::string::strlen;
-use crate::mem::{ConstPtr, ConstVoidPtr, GuestUSize, Mem, MutPtr, MutVoidPtr, Ptr, SafeRead};
+use crate::mem::{
+    ConstPtr, ConstVoidPtr, GuestISize, GuestUSize, MutPtr, MutVoidPtr, Ptr, SafeRead,
+};
 use crate::Environment;
 use std::io::Write;
 
@@ -22,13 +24,87 @@ pub mod printf;
 
 const EOF: i32 = -1;
 
+#[derive(Default)]
+pub struct State {
+    /// The `FILE` for stdin. This is shared by `___stdinp` and `scanf()`, so
+    /// that input pushed back by one is seen by the other.
+    stdin: Option<MutPtr<FILE>>,
+}
+
 #[allow(clippy::upper_case_acronyms)]
 /// C `FILE` struct. This is an opaque type in C, so the definition here is our
 /// own.
 struct FILE {
     fd: posix_io::FileDescriptor,
+    /// Error indicator, see `ferror()`.
+    error: bool,
+    /// A byte that was pushed back onto the stream, or [EOF] if there is none.
+    /// The next read returns this before reading anything from `fd`.
+    unget: i32,
 }
 unsafe impl SafeRead for FILE {}
+impl FILE {
+    fn new(fd: posix_io::FileDescriptor) -> FILE {
+        FILE {
+            fd,
+            error: false,
+            unget: EOF,
+        }
+    }
+}
+
+fn stdin_file(env: &mut Environment) -> MutPtr<FILE> {
+    if let Some(file_ptr) = env.libc_state.stdio.stdin {
+        return file_ptr;
+    }
+    let file_ptr = env.mem.alloc_and_write(FILE::new(STDIN_FILENO));
+    env.libc_state.stdio.stdin = Some(file_ptr);
+    file_ptr
+}
+
+/// Take the byte that was pushed back onto the stream, if there is one.
+fn take_unget(env: &mut Environment, file_ptr: MutPtr<FILE>) -> Option<u8> {
+    let mut file = env.mem.read(file_ptr);
+    if file.unget == EOF {
+        return None;
+    }
+    let byte = file.unget as u8;
+    file.unget = EOF;
+    env.mem.write(file_ptr, file);
+    Some(byte)
+}
+
+/// Push a byte back onto the stream, so that it's returned by the next read.
+fn set_unget(env: &mut Environment, file_ptr: MutPtr<FILE>, byte: u8) {
+    let mut file = env.mem.read(file_ptr);
+    file.unget = byte.into();
+    env.mem.write(file_ptr, file);
+}
+
+/// Like [posix_io::read], but the byte pushed back onto the stream, if there
+/// is one, is read first.
+fn read_stream(
+    env: &mut Environment,
+    file_ptr: MutPtr<FILE>,
+    buffer: MutPtr<u8>,
+    size: GuestUSize,
+) -> GuestISize {
+    let FILE { fd, .. } = env.mem.read(file_ptr);
+    let unget = if size > 0 {
+        take_unget(env, file_ptr)
+    } else {
+        None
+    };
+    let Some(byte) = unget else {
+        return posix_io::read(env, fd, buffer.cast(), size);
+    };
+    env.mem.write(buffer, byte);
+    match posix_io::read(env, fd, (buffer + 1).cast(), size - 1) {
+        // The pushed-back byte has still been read.
+        -1 => 1,
+        bytes_read => bytes_read + 1,
+    }
+}
 
 #[allow(non_camel_case_types)]
 type fpos_t = off_t;
@@ -71,7 +147,7 @@ fn fopen(env: &mut Environment, filename: ConstPtr<u8>, mode: ConstPtr<u8>) -> M
 
     match posix_io::open_direct(env, filename, flags) {
         -1 => Ptr::null(),
-        fd => env.mem.alloc_and_write(FILE { fd }),
+        fd => env.mem.alloc_and_write(FILE::new(fd)),
     }
 }
 
@@ -86,13 +162,11 @@ fn fread(
         return 0;
     }
 
-    let FILE { fd } = env.mem.read(file_ptr);
-
     // Yes, the item_size/n_items split doesn't mean anything. The C standard
     // really does expect you to just multiply and divide like this, with no
     // attempt being made to ensure a whole number are read or written!
     let total_size = item_size.checked_mul(n_items).unwrap();
-    match posix_io::read(env, fd, buffer, total_size) {
+    match read_stream(env, file_ptr, buffer.cast(), total_size) {
         // TODO: ferror() support.
         -1 => 0,
         bytes_read => {
@@ -103,10 +177,9 @@ fn fread(
 }
 
 fn fgetc(env: &mut Environment, file_ptr: MutPtr<FILE>) -> i32 {
-    let FILE { fd } = env.mem.read(file_ptr);
     let buffer = env.mem.alloc(1);
 
-    match posix_io::read(env, fd, buffer, 1) {
+    match read_stream(env, file_ptr, buffer.cast(), 1) {
         -1 => EOF,
         bytes_read => {
             let bytes_read: GuestUSize = bytes_read.try_into().unwrap();
@@ -163,7 +236,7 @@ fn fwrite(
         return 0;
     }
 
-    let FILE { fd } = env.mem.read(file_ptr);
+    let FILE { fd, .. } = env.mem.read(file_ptr);
 
     let total_size = item_size.checked_mul(n_items).unwrap();
 
@@ -202,7 +275,7 @@ const SEEK_SET: i32 = posix_io::SEEK_SET;
 const SEEK_CUR: i32 = posix_io::SEEK_CUR;
 const SEEK_END: i32 = posix_io::SEEK_END;
 fn fseek(env: &mut Environment, file_ptr: MutPtr<FILE>, offset: i32, whence: i32) -> i32 {
-    let FILE { fd } = env.mem.read(file_ptr);
+    let FILE { fd, .. } = env.mem.read(file_ptr);
 
     assert!([SEEK_SET, SEEK_CUR, SEEK_END].contains(&whence));
     match posix_io::lseek(env, fd, offset.into(), whence) {
@@ -212,7 +285,7 @@ fn fseek(env: &mut Environment, file_ptr: MutPtr<FILE>, offset: i32, whence: i32
 }
 
 fn ftell(env: &mut Environment, file_ptr: MutPtr<FILE>) -> i32 {
-    let FILE { fd } = env.mem.read(file_ptr);
+    let FILE { fd, .. } = env.mem.read(file_ptr);
 
     match posix_io::lseek(env, fd, 0, posix_io::SEEK_CUR) {
         -1 => -1,
@@ -222,7 +295,7 @@ fn ftell(env: &mut Environment, file_ptr: MutPtr<FILE>) -> i32 {
 }
 
 fn fclose(env: &mut Environment, file_ptr: MutPtr<FILE>) -> i32 {
-    let FILE { fd } = env.mem.read(file_ptr);
+    let FILE { fd, .. } = env.mem.read(file_ptr);
 
     env.mem.free(file_ptr.cast());
 
@@ -234,7 +307,7 @@ fn fclose(env: &mut Environment, file_ptr: MutPtr<FILE>) -> i32 {
 }
 
 fn fsetpos(env: &mut Environment, file_ptr: MutPtr<FILE>, pos: ConstPtr<fpos_t>) -> i32 {
-    let FILE { fd } = env.mem.read(file_ptr);
+    let FILE { fd, .. } = env.mem.read(file_ptr);
 
     let res = posix_io::lseek(env, fd, env.mem.read(pos), SEEK_SET);
     if res == -1 {
@@ -245,7 +318,7 @@ fn fsetpos(env: &mut Environment, file_ptr: MutPtr<FILE>, pos: ConstPtr<fpos_t>)
 }
 
 fn fgetpos(env: &mut Environment, file_ptr: MutPtr<FILE>, pos: MutPtr<fpos_t>) -> i32 {
-    let FILE { fd } = env.mem.read(file_ptr);
+    let FILE { fd, .. } = env.mem.read(file_ptr);
 
     let res = posix_io::lseek(env, fd, 0, posix_io::SEEK_CUR);
     if res == -1 {
@@ -256,10 +329,22 @@ fn fgetpos(env: &mut Environment, file_ptr: MutPtr<FILE>, pos: MutPtr<fpos_t>) -
 }
 
 fn feof(env: &mut Environment, file_ptr: MutPtr<FILE>) -> i32 {
-    let FILE { fd } = env.mem.read(file_ptr);
+    let FILE { fd, .. } = env.mem.read(file_ptr);
     posix_io::eof(env, fd)
 }
 
+fn ferror(env: &mut Environment, file_ptr: MutPtr<FILE>) -> i32 {
+    // TODO: set the error indicator in functions other than fprintf()
+    env.mem.read(file_ptr).error.into()
+}
+
+fn clearerr(env: &mut Environment, file_ptr: MutPtr<FILE>) {
+    // TODO: also clear the end-of-file indicator
+    let mut file = env.mem.read(file_ptr);
+    file.error = false;
+    env.mem.write(file_ptr, file);
+}
+
 fn puts(env: &mut Environment, s: ConstPtr<u8>) -> i32 {
     let _ = std::io::stdout().write_all(env.mem.cstr_at(s));
     let _ = std::io::stdout().write_all(b"\n");
@@ -307,30 +392,30 @@ fn setbuf(_env: &mut Environment, stream: MutPtr<FILE>, buf: ConstPtr<u8>) {
 // POSIX-specific functions
 
 fn fileno(env: &mut Environment, file_ptr: MutPtr<FILE>) -> posix_io::FileDescriptor {
-    let FILE { fd } = env.mem.read(file_ptr);
+    let FILE { fd, .. } = env.mem.read(file_ptr);
     fd
 }
 
 pub const CONSTANTS: ConstantExports = &[
     (
         "___stdinp",
-        HostConstant::Custom(|mem: &mut Mem| -> ConstVoidPtr {
-            let ptr = mem.alloc_and_write(FILE { fd: STDIN_FILENO });
-            mem.alloc_and_write(ptr).cast().cast_const()
+        HostConstant::Custom(|env: &mut Environment| -> ConstVoidPtr {
+            let ptr = stdin_file(env);
+            env.mem.alloc_and_write(ptr).cast().cast_const()
         }),
     ),
     (
         "___stdoutp",
-        HostConstant::Custom(|mem: &mut Mem| -> ConstVoidPtr {
-            let ptr = mem.alloc_and_write(FILE { fd: STDOUT_FILENO });
-            mem.alloc_and_write(ptr).cast().cast_const()
+        HostConstant::Custom(|env: &mut Environment| -> ConstVoidPtr {
+            let ptr = env.mem.alloc_and_write(FILE::new(STDOUT_FILENO));
+            env.mem.alloc_and_write(ptr).cast().cast_const()
         }),
     ),
     (
         "___stderrp",
-        HostConstant::Custom(|mem: &mut Mem| -> ConstVoidPtr {
-            let ptr = mem.alloc_and_write(FILE { fd: STDERR_FILENO });
-            mem.alloc_and_write(ptr).cast().cast_const()
+        HostConstant::Custom(|env: &mut Environment| -> ConstVoidPtr {
+            let ptr = env.mem.alloc_and_write(FILE::new(STDERR_FILENO));
+            env.mem.alloc_and_write(ptr).cast().cast_const()
         }),
     ),
 ];
@@ -348,6 +433,8 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(fsetpos(_, _)),
     export_c_func!(fgetpos(_, _)),
     export_c_func!(feof(_)),
+    export_c_func!(ferror(_)),
+    export_c_func!(clearerr(_)),
     export_c_func!(fclose(_)),
     export_c_func!(puts(_)),
     export_c_func!(putchar(_)),
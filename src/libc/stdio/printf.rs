@@ -5,19 +5,164 @@
  */
 //! `printf` function family. The implementation is also used by `NSLog` etc.
 
-use crate::abi::{DotDotDot, VaList};
+use crate::abi::{DotDotDot, GuestArg, VaList};
 use crate::dyld::{export_c_func, FunctionExports};
 use crate::frameworks::foundation::{ns_string, unichar};
-use crate::libc::posix_io::{STDERR_FILENO, STDOUT_FILENO};
-use crate::libc::stdio::FILE;
-use crate::mem::{ConstPtr, GuestUSize, Mem, MutPtr, MutVoidPtr};
+use crate::libc::errno::{set_errno, EIO};
+use crate::libc::posix_io::{
+    self, off_t, FileDescriptor, STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO,
+};
+use crate::libc::stdio::{read_stream, set_unget, stdin_file, take_unget, EOF, FILE};
+use crate::libc::wchar::wchar_t;
+use crate::mem::{ConstPtr, GuestISize, GuestUSize, Mem, MutPtr, MutVoidPtr, Ptr};
 use crate::objc::{id, msg};
 use crate::Environment;
 use std::collections::HashSet;
-use std::io::Write;
+use std::io::{Read, Write};
 
 const INTEGER_SPECIFIERS: [u8; 6] = [b'd', b'i', b'o', b'u', b'x', b'X'];
-const FLOAT_SPECIFIERS: [u8; 1] = [b'f'];
+const FLOAT_SPECIFIERS: [u8; 5] = [b'e', b'E', b'f', b'g', b'G'];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum LengthModifier {
+    /// `hh`
+    Char,
+    /// `h`
+    Short,
+    /// `l`
+    Long,
+    /// `ll` or `q`
+    LongLong,
+}
+
+/// Arguments for [printf_inner]. These are normally read from the [VaList] in
+/// order, but if the format string uses positional arguments (`%1$d`), they
+/// must all be read in advance so they can be accessed in any order.
+enum FormatArgs {
+    Sequential(VaList),
+    Positional {
+        /// Register-sized words for each argument, in positional order.
+        values: Vec<[u32; 2]>,
+        /// Index of the argument for the current conversion.
+        current: usize,
+    },
+}
+impl FormatArgs {
+    fn next<T: GuestArg>(&mut self, env: &mut Environment) -> T {
+        match self {
+            FormatArgs::Sequential(va_list) => va_list.next(env),
+            FormatArgs::Positional { values, current } => {
+                T::from_regs(&values[*current][..T::REG_COUNT])
+            }
+        }
+    }
+}
+
+/// Parse a positional argument specifier (e.g. `1$`) if there is one at
+/// `format_char_idx`, and return its (one-based) position.
+fn parse_position<F: Fn(&Mem, GuestUSize) -> u8>(
+    mem: &Mem,
+    get_format_char: &F,
+    format_char_idx: &mut GuestUSize,
+) -> Option<usize> {
+    let mut idx = *format_char_idx;
+    let mut position = 0;
+    while let c @ b'0'..=b'9' = get_format_char(mem, idx) {
+        position = position * 10 + (c - b'0') as usize;
+        idx += 1;
+    }
+    // Positions start at 1, so a leading zero must be the `0` flag instead.
+    if position == 0 || get_format_char(mem, idx) != b'$' {
+        return None;
+    }
+    *format_char_idx = idx + 1;
+    Some(position)
+}
+
+/// If the format string uses positional arguments (`%1$d`), read all of the
+/// arguments from `args` in advance. The type of each argument has to be
+/// worked out from the conversion specifications first, because the size and
+/// alignment of arguments in the [VaList] depends on it.
+fn read_positional_args<F: Fn(&Mem, GuestUSize) -> u8>(
+    env: &mut Environment,
+    get_format_char: &F,
+    mut args: VaList,
+) -> Option<Vec<[u32; 2]>> {
+    // Whether each argument is two words in size (long long or double).
+    let mut arg_is_wide: Vec<Option<bool>> = Vec::new();
+
+    let mut format_char_idx = 0;
+    loop {
+        let c = get_format_char(&env.mem, format_char_idx);
+        format_char_idx += 1;
+
+        if c == b'\0' {
+            break;
+        }
+        if c != b'%' {
+            continue;
+        }
+        if get_format_char(&env.mem, format_char_idx) == b'%' {
+            format_char_idx += 1;
+            continue;
+        }
+
+        // Mixing positional and non-positional arguments isn't allowed, so
+        // it's enough to give up at the first non-positional one.
+        let position = parse_position(&env.mem, get_format_char, &mut format_char_idx)?;
+
+        let mut l_count = 0;
+        let specifier = loop {
+            let c = get_format_char(&env.mem, format_char_idx);
+            format_char_idx += 1;
+            match c {
+                // flags, width and precision
+                b'-' | b'+' | b' ' | b'#' | b'.' | b'0'..=b'9' | b'h' => (),
+                b'l' => l_count += 1,
+                b'q' => l_count = 2,
+                // `*m$` takes the width or precision from argument `m`, which
+                // is an `int`.
+                b'*' => {
+                    let width_position =
+                        parse_position(&env.mem, get_format_char, &mut format_char_idx)?;
+                    set_arg_is_wide(&mut arg_is_wide, width_position, false);
+                }
+                _ => break c,
+            }
+        };
+        assert!(specifier != b'\0');
+
+        let is_wide = FLOAT_SPECIFIERS.contains(&specifier)
+            || (l_count >= 2 && (INTEGER_SPECIFIERS.contains(&specifier) || specifier == b'n'));
+        set_arg_is_wide(&mut arg_is_wide, position, is_wide);
+    }
+
+    if arg_is_wide.is_empty() {
+        return None;
+    }
+
+    Some(
+        arg_is_wide
+            .into_iter()
+            .map(|is_wide| {
+                // Unused arguments are assumed to be word-sized.
+                if is_wide == Some(true) {
+                    let value: u64 = args.next(env);
+                    [value as u32, (value >> 32) as u32]
+                } else {
+                    [args.next(env), 0]
+                }
+            })
+            .collect(),
+    )
+}
+
+fn set_arg_is_wide(arg_is_wide: &mut Vec<Option<bool>>, position: usize, is_wide: bool) {
+    if arg_is_wide.len() < position {
+        arg_is_wide.resize(position, None);
+    }
+    arg_is_wide[position - 1] = Some(is_wide);
+}
 
 /// String formatting implementation for `printf` and `NSLog` function families.
 ///
@@ -29,12 +174,21 @@ const FLOAT_SPECIFIERS: [u8; 1] = [b'f'];
 pub fn printf_inner<const NS_LOG: bool, F: Fn(&Mem, GuestUSize) -> u8>(
     env: &mut Environment,
     get_format_char: F,
-    mut args: VaList,
+    args: VaList,
 ) -> Vec<u8> {
     let mut res = Vec::<u8>::new();
 
+    let mut args = match read_positional_args(env, &get_format_char, args) {
+        Some(values) => FormatArgs::Positional { values, current: 0 },
+        None => FormatArgs::Sequential(args),
+    };
+
     let mut format_char_idx = 0;
 
+    // End position in `res` and value of the last high surrogate written by
+    // `%C`, in case it's followed by a low surrogate.
+    let mut high_surrogate: Option<(usize, unichar)> = None;
+
     loop {
         let c = get_format_char(&env.mem, format_char_idx);
         format_char_idx += 1;
@@ -47,22 +201,59 @@ pub fn printf_inner<const NS_LOG: bool, F: Fn(&Mem, GuestUSize) -> u8>(
             continue;
         }
 
-        let pad_char = if get_format_char(&env.mem, format_char_idx) == b'0' {
+        if let Some(position) = parse_position(&env.mem, &get_format_char, &mut format_char_idx) {
+            if let FormatArgs::Positional { current, .. } = &mut args {
+                *current = position - 1;
+            }
+        }
+
+        let mut pad_char = ' ';
+        let mut left_justify = false;
+        let mut force_sign = false;
+        let mut space_sign = false;
+        let mut alternate_form = false;
+        loop {
+            match get_format_char(&env.mem, format_char_idx) {
+                b'0' => pad_char = '0',
+                b'-' => left_justify = true,
+                b'+' => force_sign = true,
+                b' ' => space_sign = true,
+                b'#' => alternate_form = true,
+                _ => break,
+            }
             format_char_idx += 1;
-            '0'
+        }
+
+        // Prefix for non-negative numbers in signed conversions. If both flags
+        // are given, `+` wins.
+        let positive_sign: &[u8] = if force_sign {
+            b"+"
+        } else if space_sign {
+            b" "
         } else {
-            ' '
+            b""
         };
 
         let pad_width = if get_format_char(&env.mem, format_char_idx) == b'*' {
-            let pad_width = args.next::<i32>(env);
-            assert!(pad_width >= 0); // TODO: Implement right-padding
             format_char_idx += 1;
-            pad_width
+            if let FormatArgs::Positional { .. } = args {
+                // TODO: support `*m$` in positional mode
+                parse_position(&env.mem, &get_format_char, &mut format_char_idx);
+                log!("TODO: `*m$` width in positional printf format string, ignoring");
+                0
+            } else {
+                let pad_width = args.next::<i32>(env);
+                // A negative width argument is treated as a `-` flag followed
+                // by a positive width.
+                if pad_width < 0 {
+                    left_justify = true;
+                }
+                pad_width.unsigned_abs() as usize
+            }
         } else {
-            let mut pad_width: i32 = 0;
+            let mut pad_width: usize = 0;
             while let c @ b'0'..=b'9' = get_format_char(&env.mem, format_char_idx) {
-                pad_width = pad_width * 10 + (c - b'0') as i32;
+                pad_width = pad_width * 10 + (c - b'0') as usize;
                 format_char_idx += 1;
             }
             pad_width
@@ -70,21 +261,53 @@ pub fn printf_inner<const NS_LOG: bool, F: Fn(&Mem, GuestUSize) -> u8>(
 
         let precision = if get_format_char(&env.mem, format_char_idx) == b'.' {
             format_char_idx += 1;
-            let mut precision = 0;
-            while let c @ b'0'..=b'9' = get_format_char(&env.mem, format_char_idx) {
-                precision = precision * 10 + (c - b'0') as usize;
+            if get_format_char(&env.mem, format_char_idx) == b'*' {
                 format_char_idx += 1;
+                if let FormatArgs::Positional { .. } = args {
+                    // TODO: support `.*m$` in positional mode
+                    parse_position(&env.mem, &get_format_char, &mut format_char_idx);
+                    log!("TODO: `.*m$` precision in positional printf format string, ignoring");
+                    None
+                } else {
+                    // A negative precision argument is treated as if the
+                    // precision were omitted.
+                    usize::try_from(args.next::<i32>(env)).ok()
+                }
+            } else {
+                let mut precision = 0;
+                while let c @ b'0'..=b'9' = get_format_char(&env.mem, format_char_idx) {
+                    precision = precision * 10 + (c - b'0') as usize;
+                    format_char_idx += 1;
+                }
+                Some(precision)
             }
-            Some(precision)
         } else {
             None
         };
 
-        let length_modifier = if get_format_char(&env.mem, format_char_idx) == b'l' {
-            format_char_idx += 1;
-            Some(b'l')
-        } else {
-            None
+        let length_modifier = match get_format_char(&env.mem, format_char_idx) {
+            b'h' if get_format_char(&env.mem, format_char_idx + 1) == b'h' => {
+                format_char_idx += 2;
+                Some(LengthModifier::Char)
+            }
+            b'h' => {
+                format_char_idx += 1;
+                Some(LengthModifier::Short)
+            }
+            b'l' if get_format_char(&env.mem, format_char_idx + 1) == b'l' => {
+                format_char_idx += 2;
+                Some(LengthModifier::LongLong)
+            }
+            b'l' => {
+                format_char_idx += 1;
+                Some(LengthModifier::Long)
+            }
+            // BSD synonym for `ll`
+            b'q' => {
+                format_char_idx += 1;
+                Some(LengthModifier::LongLong)
+            }
+            _ => None,
         };
 
         let specifier = get_format_char(&env.mem, format_char_idx);
@@ -98,17 +321,29 @@ pub fn printf_inner<const NS_LOG: bool, F: Fn(&Mem, GuestUSize) -> u8>(
 
         if precision.is_some() {
             assert!(
-                INTEGER_SPECIFIERS.contains(&specifier) || FLOAT_SPECIFIERS.contains(&specifier)
+                INTEGER_SPECIFIERS.contains(&specifier)
+                    || FLOAT_SPECIFIERS.contains(&specifier)
+                    || specifier == b's'
+                    // Undefined for `%c`, but harmless to ignore.
+                    || specifier == b'c'
             )
         }
 
         match specifier {
+            b'c' if length_modifier == Some(LengthModifier::Long) => {
+                // Wide character (`wint_t`), written as UTF-8
+                let c: wchar_t = args.next(env);
+                let c = char::from_u32(c as u32).unwrap_or(char::REPLACEMENT_CHARACTER);
+                let mut buf = [0u8; 4];
+                let c = c.encode_utf8(&mut buf).as_bytes();
+                write_padded(&mut res, b"", c, pad_width, ' ', left_justify);
+            }
             b'c' => {
-                // TODO: support length modifier
+                // TODO: support other length modifiers
                 assert!(length_modifier.is_none());
                 let c: u8 = args.next(env);
-                assert!(pad_char == ' ' && pad_width == 0); // TODO
-                res.push(c);
+                // The `0` flag is undefined for characters, so it's ignored.
+                write_padded(&mut res, b"", &[c], pad_width, ' ', left_justify);
             }
             // Apple extension? Seemingly works in both NSLog and printf.
             b'C' => {
@@ -116,65 +351,137 @@ pub fn printf_inner<const NS_LOG: bool, F: Fn(&Mem, GuestUSize) -> u8>(
                 let c: unichar = args.next(env);
                 // TODO
                 assert!(pad_char == ' ' && pad_width == 0);
-                // This will panic if it's a surrogate! This isn't good if
-                // targeting UTF-16 ([NSString stringWithFormat:] etc).
-                let c = char::from_u32(c.into()).unwrap();
-                write!(&mut res, "{}", c).unwrap();
+                // Surrogate pairs may be split across two `%C` conversions.
+                // An unpaired surrogate is written as U+FFFD, but if the other
+                // half immediately follows, the replacement character is
+                // swapped for the decoded pair.
+                match high_surrogate.take() {
+                    Some((end, high)) if end == res.len() && (0xDC00..=0xDFFF).contains(&c) => {
+                        res.truncate(end - char::REPLACEMENT_CHARACTER.len_utf8());
+                        let pair = char::decode_utf16([high, c]).next().unwrap().unwrap();
+                        write!(&mut res, "{}", pair).unwrap();
+                    }
+                    _ => {
+                        let c_decoded = char::decode_utf16([c])
+                            .next()
+                            .unwrap()
+                            .unwrap_or(char::REPLACEMENT_CHARACTER);
+                        write!(&mut res, "{}", c_decoded).unwrap();
+                        if (0xD800..=0xDBFF).contains(&c) {
+                            high_surrogate = Some((res.len(), c));
+                        }
+                    }
+                }
+            }
+            b's' if length_modifier == Some(LengthModifier::Long) => {
+                // Wide string, written as UTF-8. The precision is treated as
+                // a number of wide characters rather than bytes.
+                let w_string: ConstPtr<wchar_t> = args.next(env);
+                let string = if w_string.is_null() {
+                    "(null)".to_string()
+                } else {
+                    wide_cstr_to_string(&env.mem, w_string, precision)
+                };
+                write_padded(
+                    &mut res,
+                    b"",
+                    string.as_bytes(),
+                    pad_width,
+                    ' ',
+                    left_justify,
+                );
             }
             b's' => {
-                // TODO: support length modifier
+                // TODO: support other length modifiers
                 assert!(length_modifier.is_none());
                 let c_string: ConstPtr<u8> = args.next(env);
-                assert!(pad_char == ' ' && pad_width == 0); // TODO
-                if !c_string.is_null() {
-                    res.extend_from_slice(env.mem.cstr_at(c_string));
+                let string = if c_string.is_null() {
+                    "(null)".as_bytes()
+                } else if let Some(precision) = precision {
+                    // The precision is the maximum number of bytes to use, and
+                    // the string need not be null-terminated if it's shorter.
+                    let mut len = 0;
+                    while len < precision && env.mem.read(c_string + len as GuestUSize) != b'\0' {
+                        len += 1;
+                    }
+                    env.mem.bytes_at(c_string, len as GuestUSize)
                 } else {
-                    res.extend_from_slice("(null)".as_bytes());
-                }
+                    env.mem.cstr_at(c_string)
+                };
+                // The `0` flag is undefined for strings, so it's ignored.
+                write_padded(&mut res, b"", string, pad_width, ' ', left_justify);
             }
             b'd' | b'i' | b'u' => {
-                // Note: on 32-bit system int and long are i32,
-                // so length_modifier is ignored
-                let int: i64 = if specifier == b'u' {
-                    let uint: u32 = args.next(env);
-                    uint.into()
-                } else {
-                    let int: i32 = args.next(env);
-                    int.into()
+                // Note: on 32-bit system int and long are i32, so only
+                // long long needs special handling. char and short arguments
+                // are promoted to int when passed, so they must be truncated.
+                let int: i128 = match (specifier, length_modifier) {
+                    (b'u', Some(LengthModifier::Char)) => (args.next::<u32>(env) as u8).into(),
+                    (b'u', Some(LengthModifier::Short)) => (args.next::<u32>(env) as u16).into(),
+                    (b'u', Some(LengthModifier::LongLong)) => args.next::<u64>(env).into(),
+                    (b'u', _) => args.next::<u32>(env).into(),
+                    (_, Some(LengthModifier::Char)) => (args.next::<i32>(env) as i8).into(),
+                    (_, Some(LengthModifier::Short)) => (args.next::<i32>(env) as i16).into(),
+                    (_, Some(LengthModifier::LongLong)) => args.next::<i64>(env).into(),
+                    (_, _) => args.next::<i32>(env).into(),
                 };
 
-                let int_with_precision = if precision.is_some_and(|value| value > 0) {
-                    format!("{:01$}", int, precision.unwrap())
+                let sign: &[u8] = if int < 0 {
+                    b"-"
+                } else if specifier != b'u' {
+                    positive_sign
                 } else {
-                    format!("{}", int)
+                    b""
                 };
-
-                if pad_width > 0 {
-                    let pad_width = pad_width as usize;
-                    if pad_char == '0' && precision.is_none() {
-                        write!(&mut res, "{:0>1$}", int_with_precision, pad_width).unwrap();
-                    } else {
-                        write!(&mut res, "{:>1$}", int_with_precision, pad_width).unwrap();
-                    }
-                } else {
-                    res.extend_from_slice(int_with_precision.as_bytes());
-                }
+                let digits = format!("{:01$}", int.unsigned_abs(), precision.unwrap_or(0));
+                // The `0` flag is ignored if a precision is given.
+                let pad_char = if precision.is_none() { pad_char } else { ' ' };
+                write_padded(
+                    &mut res,
+                    sign,
+                    digits.as_bytes(),
+                    pad_width,
+                    pad_char,
+                    left_justify,
+                );
             }
-            b'f' => {
+            b'e' | b'E' | b'f' | b'g' | b'G' => {
                 // TODO: support length modifier
                 assert!(length_modifier.is_none());
                 let float: f64 = args.next(env);
                 let precision_value = precision.unwrap_or(6);
-                if pad_width > 0 {
-                    let pad_width = pad_width as usize;
-                    if pad_char == '0' {
-                        write!(&mut res, "{:01$.2$}", float, pad_width, precision_value).unwrap();
-                    } else {
-                        write!(&mut res, "{:1$.2$}", float, pad_width, precision_value).unwrap();
-                    }
+                let sign: &[u8] = if float.is_sign_negative() {
+                    b"-"
                 } else {
-                    write!(&mut res, "{:.1$}", float, precision_value).unwrap();
+                    positive_sign
+                };
+                let mut digits = match specifier {
+                    b'f' => format!("{:.1$}", float.abs(), precision_value),
+                    b'e' | b'E' => {
+                        format_exponential(float.abs(), precision_value, specifier == b'E')
+                    }
+                    b'g' | b'G' => format_general(
+                        float.abs(),
+                        precision_value,
+                        specifier == b'G',
+                        alternate_form,
+                    ),
+                    _ => unreachable!(),
+                };
+                // The `#` flag forces a decimal point even with no digits
+                // after it.
+                if alternate_form && float.is_finite() && !digits.contains('.') {
+                    let point_idx = digits.find(['e', 'E']).unwrap_or(digits.len());
+                    digits.insert(point_idx, '.');
                 }
+                write_padded(
+                    &mut res,
+                    sign,
+                    digits.as_bytes(),
+                    pad_width,
+                    pad_char,
+                    left_justify,
+                );
             }
             b'@' if NS_LOG => {
                 assert!(length_modifier.is_none());
@@ -182,21 +489,71 @@ pub fn printf_inner<const NS_LOG: bool, F: Fn(&Mem, GuestUSize) -> u8>(
                 // TODO: use localized description if available?
                 let description: id = msg![env; object description];
                 // TODO: avoid copy
-                // TODO: what if the description isn't valid UTF-16?
-                let description = ns_string::to_rust_string(env, description);
-                write!(&mut res, "{}", description).unwrap();
+                // The description might not be valid UTF-16, e.g. if it was
+                // truncated in the middle of a surrogate pair.
+                let mut code_units = Vec::new();
+                ns_string::for_each_code_unit(env, description, |_, c| code_units.push(c));
+                write!(&mut res, "{}", String::from_utf16_lossy(&code_units)).unwrap();
             }
-            b'x' => {
+            b'o' | b'x' | b'X' => {
                 // Note: on 32-bit system unsigned int and unsigned long
-                // are u32, so length_modifier is ignored
-                let uint: u32 = args.next(env);
-                res.extend_from_slice(format!("{:x}", uint).as_bytes());
+                // are u32, so only unsigned long long needs special handling
+                let uint: u64 = match length_modifier {
+                    Some(LengthModifier::Char) => (args.next::<u32>(env) as u8).into(),
+                    Some(LengthModifier::Short) => (args.next::<u32>(env) as u16).into(),
+                    Some(LengthModifier::LongLong) => args.next(env),
+                    _ => args.next::<u32>(env).into(),
+                };
+                let precision_value = precision.unwrap_or(0);
+                let mut digits = match specifier {
+                    b'o' => format!("{:01$o}", uint, precision_value),
+                    b'x' => format!("{:01$x}", uint, precision_value),
+                    b'X' => format!("{:01$X}", uint, precision_value),
+                    _ => unreachable!(),
+                };
+                // The `#` flag adds a `0x` prefix to non-zero hexadecimal
+                // numbers, and makes sure octal numbers start with `0`.
+                let prefix: &[u8] = match specifier {
+                    b'x' if alternate_form && uint != 0 => b"0x",
+                    b'X' if alternate_form && uint != 0 => b"0X",
+                    _ => b"",
+                };
+                if specifier == b'o' && alternate_form && !digits.starts_with('0') {
+                    digits.insert(0, '0');
+                }
+                let pad_char = if precision.is_none() { pad_char } else { ' ' };
+                write_padded(
+                    &mut res,
+                    prefix,
+                    digits.as_bytes(),
+                    pad_width,
+                    pad_char,
+                    left_justify,
+                );
             }
-            b'X' => {
-                // Note: on 32-bit system unsigned int and unsigned long
-                // are u32, so length_modifier is ignored
-                let uint: u32 = args.next(env);
-                res.extend_from_slice(format!("{:X}", uint).as_bytes());
+            b'n' => {
+                // Stores the number of bytes written so far, rather than
+                // formatting anything.
+                let count = res.len();
+                match length_modifier {
+                    Some(LengthModifier::Char) => {
+                        let ptr: MutPtr<i8> = args.next(env);
+                        env.mem.write(ptr, count as i8);
+                    }
+                    Some(LengthModifier::Short) => {
+                        let ptr: MutPtr<i16> = args.next(env);
+                        env.mem.write(ptr, count as i16);
+                    }
+                    Some(LengthModifier::LongLong) => {
+                        let ptr: MutPtr<i64> = args.next(env);
+                        env.mem.write(ptr, count as i64);
+                    }
+                    // Note: on 32-bit system int and long are i32
+                    Some(LengthModifier::Long) | None => {
+                        let ptr: MutPtr<i32> = args.next(env);
+                        env.mem.write(ptr, count as i32);
+                    }
+                }
             }
             b'p' => {
                 assert!(length_modifier.is_none());
@@ -217,6 +574,101 @@ pub fn printf_inner<const NS_LOG: bool, F: Fn(&Mem, GuestUSize) -> u8>(
     res
 }
 
+/// Read a null-terminated wide string, up to `max_len` characters if given, and
+/// convert it to a Rust string. Invalid characters are replaced with U+FFFD.
+fn wide_cstr_to_string(mem: &Mem, w_string: ConstPtr<wchar_t>, max_len: Option<usize>) -> String {
+    let mut string = String::new();
+    let mut len: GuestUSize = 0;
+    while max_len.map_or(true, |max_len| (len as usize) < max_len) {
+        let c = mem.read(w_string + len);
+        if c == 0 {
+            break;
+        }
+        string.push(char::from_u32(c as u32).unwrap_or(char::REPLACEMENT_CHARACTER));
+        len += 1;
+    }
+    string
+}
+
+/// Format a non-negative float in the style of C's `%e`, e.g. `1.500000e+01`.
+fn format_exponential(float: f64, precision: usize, upper_case: bool) -> String {
+    if !float.is_finite() {
+        return format!("{}", float);
+    }
+    // Rust formats the exponent like `e1` or `e-1`, but C always includes a
+    // sign and at least two digits.
+    let formatted = format!("{:.1$e}", float, precision);
+    let (mantissa, exponent) = formatted.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    format!(
+        "{}{}{}{:02}",
+        mantissa,
+        if upper_case { 'E' } else { 'e' },
+        if exponent < 0 { '-' } else { '+' },
+        exponent.unsigned_abs()
+    )
+}
+
+/// Format a non-negative float in the style of C's `%g`, which picks whichever
+/// of `%e` and `%f` is more appropriate for the exponent and precision.
+/// Trailing zeros are removed unless `alternate_form` (the `#` flag) is set.
+fn format_general(float: f64, precision: usize, upper_case: bool, alternate_form: bool) -> String {
+    if !float.is_finite() {
+        return format!("{}", float);
+    }
+    // A precision of 0 is treated as 1.
+    let precision = precision.max(1);
+    // The choice depends on the exponent after rounding to the precision.
+    let exponent: i32 = format!("{:.1$e}", float, precision - 1)
+        .split_once('e')
+        .unwrap()
+        .1
+        .parse()
+        .unwrap();
+    let (mut mantissa, exponent) = if exponent < -4 || exponent >= precision as i32 {
+        let formatted = format_exponential(float, precision - 1, upper_case);
+        let exponent_idx = formatted.find(['e', 'E']).unwrap();
+        let (mantissa, exponent) = formatted.split_at(exponent_idx);
+        (mantissa.to_string(), exponent.to_string())
+    } else {
+        let precision = (precision as i32 - 1 - exponent) as usize;
+        (format!("{:.1$}", float, precision), String::new())
+    };
+    if !alternate_form && mantissa.contains('.') {
+        mantissa.truncate(mantissa.trim_end_matches('0').trim_end_matches('.').len());
+    }
+    mantissa + &exponent
+}
+
+/// Append `prefix` (e.g. a sign) and `body` to `res`, padded to `pad_width`.
+///
+/// Padding with spaces goes before the prefix, unless `left_justify` is set, in
+/// which case it goes after the body. Padding with zeros goes between the
+/// prefix and the body, so that e.g. `-5` becomes `-005` and not `00-5`.
+fn write_padded(
+    res: &mut Vec<u8>,
+    prefix: &[u8],
+    body: &[u8],
+    pad_width: usize,
+    pad_char: char,
+    left_justify: bool,
+) {
+    let padding = pad_width.saturating_sub(prefix.len() + body.len());
+    if left_justify {
+        res.extend_from_slice(prefix);
+        res.extend_from_slice(body);
+        res.resize(res.len() + padding, b' ');
+    } else if pad_char == '0' {
+        res.extend_from_slice(prefix);
+        res.resize(res.len() + padding, b'0');
+        res.extend_from_slice(body);
+    } else {
+        res.resize(res.len() + padding, b' ');
+        res.extend_from_slice(prefix);
+        res.extend_from_slice(body);
+    }
+}
+
 fn snprintf(
     env: &mut Environment,
     dest: MutPtr<u8>,
@@ -227,6 +679,67 @@ fn snprintf(
     vsnprintf(env, dest, n, format, args.start())
 }
 
+fn vswprintf(
+    env: &mut Environment,
+    dest: MutPtr<wchar_t>,
+    n: GuestUSize,
+    format: ConstPtr<wchar_t>,
+    arg: VaList,
+) -> i32 {
+    // The format string is converted to UTF-8 so it can be handled by
+    // printf_inner(), and the result is converted back again.
+    let format_string = wide_cstr_to_string(&env.mem, format, None);
+
+    log_dbg!(
+        "vswprintf({:?}, {:#x}, {:?} ({:?}), ...)",
+        dest,
+        n,
+        format,
+        format_string
+    );
+
+    let res = printf_inner::<false, _>(
+        env,
+        |_, idx| {
+            if idx as usize == format_string.len() {
+                b'\0'
+            } else {
+                format_string.as_bytes()[idx as usize]
+            }
+        },
+        arg,
+    );
+    let res: Vec<wchar_t> = String::from_utf8_lossy(&res)
+        .chars()
+        .map(|c| c as wchar_t)
+        .collect();
+
+    if n == 0 {
+        return -1;
+    }
+    let middle = &res[..res.len().min((n - 1) as usize)];
+    for (i, &c) in middle.iter().chain([0].iter()).enumerate() {
+        env.mem.write(dest + i as GuestUSize, c);
+    }
+
+    // Unlike snprintf(), truncation is an error.
+    if middle.len() < res.len() {
+        -1
+    } else {
+        res.len().try_into().unwrap()
+    }
+}
+
+fn swprintf(
+    env: &mut Environment,
+    dest: MutPtr<wchar_t>,
+    n: GuestUSize,
+    format: ConstPtr<wchar_t>,
+    args: DotDotDot,
+) -> i32 {
+    vswprintf(env, dest, n, format, args.start())
+}
+
 fn vprintf(env: &mut Environment, format: ConstPtr<u8>, arg: VaList) -> i32 {
     log_dbg!(
         "vprintf({:?} ({:?}), ...)",
@@ -235,9 +748,7 @@ fn vprintf(env: &mut Environment, format: ConstPtr<u8>, arg: VaList) -> i32 {
     );
 
     let res = printf_inner::<false, _>(env, |mem, idx| mem.read(format + idx), arg);
-    // TODO: I/O error handling
-    let _ = std::io::stdout().write_all(&res);
-    res.len().try_into().unwrap()
+    write_formatted(env, STDOUT_FILENO, &res)
 }
 
 fn vsnprintf(
@@ -317,28 +828,65 @@ fn printf(env: &mut Environment, format: ConstPtr<u8>, args: DotDotDot) -> i32 {
     );
 
     let res = printf_inner::<false, _>(env, |mem, idx| mem.read(format + idx), args.start());
-    // TODO: I/O error handling
-    let _ = std::io::stdout().write_all(&res);
-    res.len().try_into().unwrap()
+    write_formatted(env, STDOUT_FILENO, &res)
 }
 
-// TODO: more printf variants
-
-fn sscanf(env: &mut Environment, src: ConstPtr<u8>, format: ConstPtr<u8>, args: DotDotDot) -> i32 {
+fn vasprintf(
+    env: &mut Environment,
+    ret: MutPtr<MutPtr<u8>>,
+    format: ConstPtr<u8>,
+    arg: VaList,
+) -> i32 {
     log_dbg!(
-        "sscanf({:?} ({:?}), {:?} ({:?}), ...)",
-        src,
-        env.mem.cstr_at_utf8(src),
+        "vasprintf({:?}, {:?} ({:?}), ...)",
+        ret,
         format,
         env.mem.cstr_at_utf8(format)
     );
 
-    let mut args = args.start();
+    let res = printf_inner::<false, _>(env, |mem, idx| mem.read(format + idx), arg);
+
+    // touchHLE's allocator panics rather than returning NULL, so the only
+    // failure we can report is a result that's too long.
+    let Ok(len) = i32::try_from(res.len()) else {
+        env.mem.write(ret, Ptr::null());
+        return -1;
+    };
+    // This is the same allocator used by malloc(), so the guest can free() it.
+    let buffer = env.mem.alloc_and_write_cstr(&res);
+    env.mem.write(ret, buffer);
+    len
+}
 
-    let mut src_ptr = src.cast_mut();
+fn asprintf(
+    env: &mut Environment,
+    ret: MutPtr<MutPtr<u8>>,
+    format: ConstPtr<u8>,
+    args: DotDotDot,
+) -> i32 {
+    vasprintf(env, ret, format, args.start())
+}
+
+// TODO: more printf variants
+
+/// Formatted input implementation for the `scanf` function family.
+///
+/// `get_src_char` is a callback that returns the byte at a given index in the
+/// input, or `'\0'` if the index is at or past the end of the input.
+///
+/// Returns the number of matched arguments, or [EOF] if the input ended before
+/// the first conversion, along with the number of input bytes consumed.
+fn scanf_inner<F: FnMut(&mut Environment, GuestUSize) -> u8>(
+    env: &mut Environment,
+    format: ConstPtr<u8>,
+    mut get_src_char: F,
+    mut args: VaList,
+) -> (i32, GuestUSize) {
+    let mut src_idx = 0;
     let mut format_char_idx = 0;
 
     let mut matched_args = 0;
+    let mut failed = false;
 
     loop {
         let c = env.mem.read(format + format_char_idx);
@@ -347,90 +895,171 @@ fn sscanf(env: &mut Environment, src: ConstPtr<u8>, format: ConstPtr<u8>, args:
         if c == b'\0' {
             break;
         }
+        if is_space(c) {
+            // Whitespace in the format string matches any amount of whitespace
+            // in the input, including none.
+            while is_space(get_src_char(env, src_idx)) {
+                src_idx += 1;
+            }
+            continue;
+        }
         if c != b'%' {
-            let cc = env.mem.read(src_ptr);
+            let cc = get_src_char(env, src_idx);
             if c != cc {
-                return matched_args - 1;
+                failed = true;
+                break;
             }
-            src_ptr += 1;
+            src_idx += 1;
             continue;
         }
 
-        let length_modifier = if env.mem.read(format + format_char_idx) == b'h' {
+        let mut max_width: Option<GuestUSize> = None;
+        while let c @ b'0'..=b'9' = env.mem.read(format + format_char_idx) {
+            max_width = Some(max_width.unwrap_or(0) * 10 + (c - b'0') as GuestUSize);
             format_char_idx += 1;
-            Some(b'h')
-        } else {
-            None
+        }
+
+        let length_modifier = match env.mem.read(format + format_char_idx) {
+            lm @ (b'h' | b'l') => {
+                format_char_idx += 1;
+                Some(lm)
+            }
+            _ => None,
         };
 
         let specifier = env.mem.read(format + format_char_idx);
         format_char_idx += 1;
 
+        // Most conversions skip leading whitespace.
+        if !matches!(specifier, b'[' | b'c' | b'n') {
+            while is_space(get_src_char(env, src_idx)) {
+                src_idx += 1;
+            }
+        }
+
+        // The maximum field width, if any, is applied by making the input seem
+        // to end early. Skipped whitespace isn't counted.
+        let field_end = max_width.map(|max_width| src_idx + max_width);
+        let mut get_field_char = |env: &mut Environment, idx: GuestUSize| -> u8 {
+            if field_end.is_some_and(|field_end| idx >= field_end) {
+                b'\0'
+            } else {
+                get_src_char(env, idx)
+            }
+        };
+
         match specifier {
             b'd' | b'i' => {
-                if specifier == b'i' {
-                    // TODO: hexs and octals
-                    assert_ne!(env.mem.read(src_ptr), b'0');
-                }
+                // `%i` detects the base from the prefix, like `strtol()`.
+                let base = if specifier == b'i' { None } else { Some(10) };
+                let Some(val) = scan_integer(env, &mut get_field_char, &mut src_idx, base) else {
+                    failed = true;
+                    break;
+                };
+                // Overflow is undefined behavior, so truncation is fine.
+                let val = val as i32;
 
                 match length_modifier {
-                    Some(lm) => {
-                        match lm {
-                            b'h' => {
-                                // signed short* or unsigned short*
-                                let mut val: i16 = 0;
-                                while let c @ b'0'..=b'9' = env.mem.read(src_ptr) {
-                                    val = val * 10 + (c - b'0') as i16;
-                                    src_ptr += 1;
-                                }
-                                let c_short_ptr: ConstPtr<i16> = args.next(env);
-                                env.mem.write(c_short_ptr.cast_mut(), val);
-                            }
-                            _ => unimplemented!(),
-                        }
+                    Some(b'h') => {
+                        // signed short* or unsigned short*
+                        let c_short_ptr: ConstPtr<i16> = args.next(env);
+                        env.mem.write(c_short_ptr.cast_mut(), val as i16);
                     }
+                    // Note: on 32-bit system int and long are i32,
+                    // so `l` can be ignored
                     _ => {
-                        let mut val: i32 = 0;
-                        while let c @ b'0'..=b'9' = env.mem.read(src_ptr) {
-                            val = val * 10 + (c - b'0') as i32;
-                            src_ptr += 1;
-                        }
                         let c_int_ptr: ConstPtr<i32> = args.next(env);
                         env.mem.write(c_int_ptr.cast_mut(), val);
                     }
                 }
             }
+            b'e' | b'E' | b'f' | b'g' | b'G' => {
+                let Some(val) = scan_float(env, &mut get_field_char, &mut src_idx) else {
+                    failed = true;
+                    break;
+                };
+
+                match length_modifier {
+                    Some(b'l') => {
+                        let c_double_ptr: MutPtr<f64> = args.next(env);
+                        env.mem.write(c_double_ptr, val);
+                    }
+                    _ => {
+                        let c_float_ptr: MutPtr<f32> = args.next(env);
+                        env.mem.write(c_float_ptr, val as f32);
+                    }
+                }
+            }
+            b's' => {
+                assert!(length_modifier.is_none());
+                // Note that the destination needs room for the null
+                // terminator in addition to the maximum field width.
+                let mut dst_ptr: MutPtr<u8> = args.next(env);
+                let start_idx = src_idx;
+                loop {
+                    let cc = get_field_char(env, src_idx);
+                    if cc == b'\0' || is_space(cc) {
+                        break;
+                    }
+                    env.mem.write(dst_ptr, cc);
+                    dst_ptr += 1;
+                    src_idx += 1;
+                }
+                if src_idx == start_idx {
+                    failed = true;
+                    break;
+                }
+                env.mem.write(dst_ptr, b'\0');
+            }
             b'[' => {
                 assert!(length_modifier.is_none());
-                // TODO: support ranges like [0-9]
-                // [set] case
-                let mut c = env.mem.read(format + format_char_idx);
-                format_char_idx += 1;
-                // TODO: only `not in the set` for a moment
-                assert_eq!(c, b'^');
-                // Build set
+                // [set] or [^set] case
+                let negated = env.mem.read(format + format_char_idx) == b'^';
+                if negated {
+                    format_char_idx += 1;
+                }
+                // Build set. A `]` at the very start is a member rather than
+                // the end of the set.
                 let mut set: HashSet<u8> = HashSet::new();
-                // TODO: set can contain ']' as well
-                c = env.mem.read(format + format_char_idx);
+                let mut c = env.mem.read(format + format_char_idx);
                 format_char_idx += 1;
-                while c != b']' {
-                    set.insert(c);
+                loop {
+                    assert_ne!(c, b'\0');
+                    // `-` is a range, unless it's at the start or end.
+                    let range_end = env.mem.read(format + format_char_idx + 1);
+                    if env.mem.read(format + format_char_idx) == b'-'
+                        && !matches!(range_end, b']' | b'\0')
+                    {
+                        set.extend(c..=range_end);
+                        format_char_idx += 2;
+                    } else {
+                        set.insert(c);
+                    }
                     c = env.mem.read(format + format_char_idx);
                     format_char_idx += 1;
+                    if c == b']' {
+                        break;
+                    }
                 }
                 let mut dst_ptr: MutPtr<u8> = args.next(env);
-                // Consume `src` while chars are not in the set
-                let mut cc = env.mem.read(src_ptr);
-                src_ptr += 1;
-                // TODO: handle end of src string
-                while !set.contains(&cc) {
+                // Consume `src` while chars are matched by the set, stopping
+                // at the end of the input even if the set would match it.
+                let start_idx = src_idx;
+                loop {
+                    let cc = get_field_char(env, src_idx);
+                    if cc == b'\0' || set.contains(&cc) == negated {
+                        break;
+                    }
                     env.mem.write(dst_ptr, cc);
                     dst_ptr += 1;
-                    cc = env.mem.read(src_ptr);
-                    src_ptr += 1;
+                    src_idx += 1;
+                }
+                // The conversion fails if nothing matched, and in that case
+                // the destination is left untouched.
+                if src_idx == start_idx {
+                    failed = true;
+                    break;
                 }
-                // we need to backtrack one position
-                src_ptr -= 1;
                 env.mem.write(dst_ptr, b'\0');
             }
             // TODO: more specifiers
@@ -440,9 +1069,245 @@ fn sscanf(env: &mut Environment, src: ConstPtr<u8>, format: ConstPtr<u8>, args:
         matched_args += 1;
     }
 
+    // Running out of input before the first conversion is an input failure,
+    // rather than a matching failure.
+    if failed && matched_args == 0 && get_src_char(env, src_idx) == b'\0' {
+        return (EOF, src_idx);
+    }
+
+    (matched_args, src_idx)
+}
+
+/// Helper for [scanf_inner]: C's definition of whitespace.
+fn is_space(c: u8) -> bool {
+    // Rust's definition of whitespace excludes vertical tab, unlike C's
+    c.is_ascii_whitespace() || c == b'\x0b'
+}
+
+/// Helper for [scanf_inner]: scan an integer with an optional sign, either in
+/// the given `base` or, if that is [None], in the base indicated by its prefix
+/// (`0x` for hexadecimal, `0` for octal, otherwise decimal).
+///
+/// Returns [None] without consuming anything if there are no digits.
+fn scan_integer<F: FnMut(&mut Environment, GuestUSize) -> u8>(
+    env: &mut Environment,
+    get_src_char: &mut F,
+    src_idx: &mut GuestUSize,
+    base: Option<u32>,
+) -> Option<i64> {
+    let mut idx = *src_idx;
+
+    let negative = match get_src_char(env, idx) {
+        b'-' => {
+            idx += 1;
+            true
+        }
+        b'+' => {
+            idx += 1;
+            false
+        }
+        _ => false,
+    };
+
+    let base = base.unwrap_or_else(|| {
+        if get_src_char(env, idx) != b'0' {
+            10
+        } else if matches!(get_src_char(env, idx + 1), b'x' | b'X')
+            && (get_src_char(env, idx + 2) as char).is_ascii_hexdigit()
+        {
+            idx += 2;
+            16
+        } else {
+            // The leading zero is itself an octal digit.
+            8
+        }
+    });
+
+    let digits_start = idx;
+    let mut val: i64 = 0;
+    while let Some(digit) = (get_src_char(env, idx) as char).to_digit(base) {
+        val = val.wrapping_mul(base.into()).wrapping_add(digit.into());
+        idx += 1;
+    }
+    if idx == digits_start {
+        return None;
+    }
+
+    *src_idx = idx;
+    Some(if negative { val.wrapping_neg() } else { val })
+}
+
+/// Helper for [scanf_inner]: scan a decimal floating-point number with an
+/// optional sign, fractional part and exponent.
+///
+/// Returns [None] without consuming anything if there are no digits.
+fn scan_float<F: FnMut(&mut Environment, GuestUSize) -> u8>(
+    env: &mut Environment,
+    get_src_char: &mut F,
+    src_idx: &mut GuestUSize,
+) -> Option<f64> {
+    let mut idx = *src_idx;
+    let mut number = String::new();
+
+    if let c @ (b'-' | b'+') = get_src_char(env, idx) {
+        number.push(c as char);
+        idx += 1;
+    }
+
+    let mut have_digits = false;
+    while let c @ b'0'..=b'9' = get_src_char(env, idx) {
+        number.push(c as char);
+        have_digits = true;
+        idx += 1;
+    }
+    if get_src_char(env, idx) == b'.' {
+        number.push('.');
+        idx += 1;
+        while let c @ b'0'..=b'9' = get_src_char(env, idx) {
+            number.push(c as char);
+            have_digits = true;
+            idx += 1;
+        }
+    }
+    if !have_digits {
+        return None;
+    }
+
+    // The exponent is only consumed if it is complete, so that e.g. `1e` is
+    // scanned as just `1`.
+    if let b'e' | b'E' = get_src_char(env, idx) {
+        let mut exponent_idx = idx + 1;
+        let mut exponent = String::from("e");
+        if let c @ (b'-' | b'+') = get_src_char(env, exponent_idx) {
+            exponent.push(c as char);
+            exponent_idx += 1;
+        }
+        if get_src_char(env, exponent_idx).is_ascii_digit() {
+            while let c @ b'0'..=b'9' = get_src_char(env, exponent_idx) {
+                exponent.push(c as char);
+                exponent_idx += 1;
+            }
+            number.push_str(&exponent);
+            idx = exponent_idx;
+        }
+    }
+
+    *src_idx = idx;
+    Some(number.parse().unwrap())
+}
+
+fn sscanf(env: &mut Environment, src: ConstPtr<u8>, format: ConstPtr<u8>, args: DotDotDot) -> i32 {
+    log_dbg!(
+        "sscanf({:?} ({:?}), {:?} ({:?}), ...)",
+        src,
+        env.mem.cstr_at_utf8(src),
+        format,
+        env.mem.cstr_at_utf8(format)
+    );
+
+    scanf_inner(
+        env,
+        format,
+        |env, idx| env.mem.read(src + idx),
+        args.start(),
+    )
+    .0
+}
+
+/// Shared implementation of `scanf` and `fscanf`. The input is read from the
+/// stream one byte at a time, as it's needed by [scanf_inner].
+fn scanf_stream(
+    env: &mut Environment,
+    stream: MutPtr<FILE>,
+    format: ConstPtr<u8>,
+    args: VaList,
+) -> i32 {
+    let fd = env.mem.read(stream).fd;
+    let byte_buffer: MutPtr<u8> = env.mem.alloc(1).cast();
+    let mut src_bytes = Vec::new();
+    let mut reached_end = false;
+
+    let (matched_args, consumed) = scanf_inner(
+        env,
+        format,
+        |env, idx| {
+            while !reached_end && src_bytes.len() <= idx as usize {
+                let mut byte = 0u8;
+                let bytes_read = if fd == STDIN_FILENO {
+                    if let Some(unget) = take_unget(env, stream) {
+                        byte = unget;
+                        1
+                    } else {
+                        std::io::stdin()
+                            .read(std::slice::from_mut(&mut byte))
+                            .map_or(-1, |bytes_read| bytes_read as GuestISize)
+                    }
+                } else {
+                    let bytes_read = read_stream(env, stream, byte_buffer, 1);
+                    byte = env.mem.read(byte_buffer);
+                    bytes_read
+                };
+                if bytes_read == 1 {
+                    src_bytes.push(byte);
+                } else {
+                    reached_end = true;
+                }
+            }
+            src_bytes.get(idx as usize).copied().unwrap_or(b'\0')
+        },
+        args,
+    );
+
+    env.mem.free(byte_buffer.cast());
+
+    // Give back any bytes that were looked at but not consumed.
+    let unconsumed = &src_bytes[(consumed as usize).min(src_bytes.len())..];
+    if let Some(&first_unconsumed) = unconsumed.first() {
+        if fd == STDIN_FILENO {
+            // stdin can't be seeked, so the lookahead is pushed back onto the
+            // stream instead. Only one byte of that is possible.
+            set_unget(env, stream, first_unconsumed);
+            if unconsumed.len() > 1 {
+                log!(
+                    "Warning: scanf() discarded {} byte(s) of lookahead from stdin",
+                    unconsumed.len() - 1
+                );
+            }
+        } else {
+            posix_io::lseek(env, fd, -(unconsumed.len() as off_t), posix_io::SEEK_CUR);
+        }
+    }
+
     matched_args
 }
 
+fn scanf(env: &mut Environment, format: ConstPtr<u8>, args: DotDotDot) -> i32 {
+    log_dbg!(
+        "scanf({:?} ({:?}), ...)",
+        format,
+        env.mem.cstr_at_utf8(format)
+    );
+
+    let stdin = stdin_file(env);
+    scanf_stream(env, stdin, format, args.start())
+}
+
+fn fscanf(
+    env: &mut Environment,
+    stream: MutPtr<FILE>,
+    format: ConstPtr<u8>,
+    args: DotDotDot,
+) -> i32 {
+    log_dbg!(
+        "fscanf({:?}, {:?} ({:?}), ...)",
+        stream,
+        format,
+        env.mem.cstr_at_utf8(format)
+    );
+
+    scanf_stream(env, stream, format, args.start())
+}
+
 fn fprintf(
     env: &mut Environment,
     stream: MutPtr<FILE>,
@@ -457,22 +1322,71 @@ fn fprintf(
     );
 
     let res = printf_inner::<false, _>(env, |mem, idx| mem.read(format + idx), args.start());
-    // TODO: I/O error handling
-    match env.mem.read(stream).fd {
-        STDOUT_FILENO => _ = std::io::stdout().write_all(&res),
-        STDERR_FILENO => _ = std::io::stderr().write_all(&res),
-        _ => unimplemented!(),
+    let mut file = env.mem.read(stream);
+    let result = write_formatted(env, file.fd, &res);
+    if result < 0 {
+        file.error = true;
+        env.mem.write(stream, file);
+    }
+    result
+}
+
+/// Write the output of [printf_inner] to a file descriptor, and return the
+/// appropriate result for a `printf`-like function: the number of bytes
+/// written, or a negative value (with `errno` set) if there was an error.
+fn write_formatted(env: &mut Environment, fd: FileDescriptor, res: &[u8]) -> i32 {
+    let success = match fd {
+        STDOUT_FILENO => std::io::stdout().write_all(res).is_ok(),
+        STDERR_FILENO => std::io::stderr().write_all(res).is_ok(),
+        fd => {
+            // TODO: avoid copying through guest memory
+            let len: GuestUSize = res.len().try_into().unwrap();
+            let buffer: MutPtr<u8> = env.mem.alloc(len).cast();
+            env.mem.bytes_at_mut(buffer, len).copy_from_slice(res);
+            // Writes may be short, so keep going until everything is written
+            // or there's an error.
+            let mut written = 0;
+            while written < len {
+                let remaining = (buffer + written).cast().cast_const();
+                match posix_io::write(env, fd, remaining, len - written) {
+                    // write() has already set errno, e.g. to EBADF for a file
+                    // that isn't open for writing.
+                    -1 => break,
+                    0 => {
+                        set_errno(env, EIO);
+                        break;
+                    }
+                    bytes_written => written += GuestUSize::try_from(bytes_written).unwrap(),
+                }
+            }
+            env.mem.free(buffer.cast());
+            if written != len {
+                return -1;
+            }
+            true
+        }
+    };
+
+    if !success {
+        set_errno(env, EIO);
+        return -1;
     }
     res.len().try_into().unwrap()
 }
 
 pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(sscanf(_, _, _)),
+    export_c_func!(scanf(_, _)),
+    export_c_func!(fscanf(_, _, _)),
     export_c_func!(snprintf(_, _, _, _)),
     export_c_func!(vprintf(_, _)),
     export_c_func!(vsnprintf(_, _, _, _)),
     export_c_func!(vsprintf(_, _, _)),
     export_c_func!(sprintf(_, _, _)),
     export_c_func!(printf(_, _)),
+    export_c_func!(vasprintf(_, _, _)),
+    export_c_func!(asprintf(_, _, _)),
+    export_c_func!(vswprintf(_, _, _, _)),
+    export_c_func!(swprintf(_, _, _, _)),
     export_c_func!(fprintf(_, _, _)),
 ];
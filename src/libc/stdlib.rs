@@ -7,19 +7,33 @@
 
 use crate::abi::{CallFromHost, GuestFunction};
 use crate::dyld::{export_c_func, FunctionExports};
+use crate::libc::errno::{set_errno, EINVAL, ENOMEM, ERANGE};
 use crate::mem::{ConstPtr, ConstVoidPtr, GuestUSize, MutPtr, MutVoidPtr, Ptr};
 use crate::Environment;
 use std::collections::HashMap;
-use std::str::FromStr;
 
 pub mod qsort;
 
 #[derive(Default)]
 pub struct State {
+    /// State for `rand()`, `random()` and `arc4random()` respectively. These
+    /// never use host randomness: the generated sequences only depend on the
+    /// seed (or lack of one), so they are the same on every run. This matters
+    /// for reproducing bugs and for replaying recorded input.
     rand: u32,
     random: u32,
     arc4random: u32,
-    env: HashMap<Vec<u8>, MutPtr<u8>>,
+    /// Environment variables, see [env_vars]. Initialized on first use.
+    env: Option<HashMap<Vec<u8>, EnvValue>>,
+}
+
+/// The value of an environment variable.
+struct EnvValue {
+    value: MutPtr<u8>,
+    /// If [true], `value` is owned by us and must be freed if the variable is
+    /// changed. If [false], it's part of a string passed to `putenv()` which
+    /// the app owns.
+    owned: bool,
 }
 
 // Sizes of zero are implementation-defined. macOS will happily give you back
@@ -31,14 +45,27 @@ fn malloc(env: &mut Environment, size: GuestUSize) -> MutVoidPtr {
 }
 
 fn calloc(env: &mut Environment, count: GuestUSize, size: GuestUSize) -> MutVoidPtr {
-    let total = size.checked_mul(count).unwrap();
-    env.mem.alloc(total)
+    let Some(total) = size.checked_mul(count) else {
+        log!(
+            "Warning: calloc({:#x}, {:#x}) overflows, returning NULL",
+            count,
+            size
+        );
+        set_errno(env, ENOMEM);
+        return Ptr::null();
+    };
+    let ptr = env.mem.alloc(total);
+    // Freed memory is zeroed by the allocator, but calloc() guarantees this,
+    // so don't rely on it.
+    env.mem.bytes_at_mut(ptr.cast(), total).fill(0);
+    ptr
 }
 
 fn realloc(env: &mut Environment, ptr: MutVoidPtr, size: GuestUSize) -> MutVoidPtr {
     if ptr.is_null() {
         return malloc(env, size);
     }
+    // A size of zero gives a minimum-size allocation, like malloc(0) does.
     env.mem.realloc(ptr, size)
 }
 
@@ -50,6 +77,13 @@ fn free(env: &mut Environment, ptr: MutVoidPtr) {
     env.mem.free(ptr);
 }
 
+fn malloc_size(env: &mut Environment, ptr: ConstVoidPtr) -> GuestUSize {
+    if ptr.is_null() {
+        return 0;
+    }
+    env.mem.allocated_size(ptr)
+}
+
 fn atexit(
     _env: &mut Environment,
     func: GuestFunction, // void (*func)(void)
@@ -139,45 +173,116 @@ fn arc4random(env: &mut Environment) -> u32 {
     env.libc_state.stdlib.arc4random
 }
 
+/// Get the environment variables, first setting up the default ones if this
+/// hasn't been done yet.
+fn env_vars(env: &mut Environment) -> &mut HashMap<Vec<u8>, EnvValue> {
+    if env.libc_state.stdlib.env.is_none() {
+        let home = env.fs.home_directory().as_str().to_string();
+        let defaults = [
+            ("HOME", home.clone()),
+            ("TMPDIR", format!("{}/tmp/", home)),
+            ("USER", "mobile".to_string()),
+            ("LOGNAME", "mobile".to_string()),
+            ("SHELL", "/bin/sh".to_string()),
+            ("PATH", "/usr/bin:/bin:/usr/sbin:/sbin".to_string()),
+        ];
+        let mut vars = HashMap::new();
+        for (name, value) in defaults {
+            let value = env.mem.alloc_and_write_cstr(value.as_bytes());
+            vars.insert(name.as_bytes().to_vec(), EnvValue { value, owned: true });
+        }
+        env.libc_state.stdlib.env = Some(vars);
+    }
+    env.libc_state.stdlib.env.as_mut().unwrap()
+}
+
+/// Names must be non-empty and can't contain `=`.
+fn is_valid_env_var_name(name: &[u8]) -> bool {
+    !name.is_empty() && !name.contains(&b'=')
+}
+
+/// Remove an environment variable, freeing its value if we own it.
+fn remove_env_var(env: &mut Environment, name: &[u8]) {
+    if let Some(EnvValue { value, owned }) = env_vars(env).remove(name) {
+        if owned {
+            env.mem.free(value.cast());
+        }
+    }
+}
+
 fn getenv(env: &mut Environment, name: ConstPtr<u8>) -> MutPtr<u8> {
-    let name_cstr = env.mem.cstr_at(name);
-    // TODO: Provide all the system environment variables an app might expect to
-    // find. Currently the only environment variables that can be found are
-    // those put there by the app (Crash Bandicoot Nitro Kart 3D uses this).
-    let Some(&value) = env.libc_state.stdlib.env.get(name_cstr) else {
+    let name_cstr = env.mem.cstr_at(name).to_vec();
+    let Some(&EnvValue { value, .. }) = env_vars(env).get(&name_cstr) else {
         log!(
             "Warning: getenv() for {:?} ({:?}) unhandled",
             name,
-            std::str::from_utf8(name_cstr)
+            std::str::from_utf8(&name_cstr)
         );
         return Ptr::null();
     };
     log_dbg!(
         "getenv({:?} ({:?})) => {:?} ({:?})",
         name,
-        name_cstr,
+        std::str::from_utf8(&name_cstr),
         value,
         env.mem.cstr_at_utf8(value),
     );
-    // Caller should not modify the result
+    // Caller should not modify the result. It stays valid until the variable
+    // is changed.
     value
 }
 fn setenv(env: &mut Environment, name: ConstPtr<u8>, value: ConstPtr<u8>, overwrite: i32) -> i32 {
-    let name_cstr = env.mem.cstr_at(name);
-    if let Some(&existing) = env.libc_state.stdlib.env.get(name_cstr) {
+    let name_cstr = env.mem.cstr_at(name).to_vec();
+    if !is_valid_env_var_name(&name_cstr) {
+        set_errno(env, EINVAL);
+        return -1;
+    }
+    if env_vars(env).contains_key(&name_cstr) {
         if overwrite == 0 {
             return 0; // success
         }
-        env.mem.free(existing.cast());
-    };
+        remove_env_var(env, &name_cstr);
+    }
     let value = super::string::strdup(env, value);
-    let name_cstr = env.mem.cstr_at(name); // reborrow
-    env.libc_state.stdlib.env.insert(name_cstr.to_vec(), value);
     log_dbg!(
         "Stored new value {:?} ({:?}) for environment variable {:?}",
         value,
         env.mem.cstr_at_utf8(value),
-        std::str::from_utf8(name_cstr),
+        std::str::from_utf8(&name_cstr),
+    );
+    env_vars(env).insert(name_cstr, EnvValue { value, owned: true });
+    0 // success
+}
+fn unsetenv(env: &mut Environment, name: ConstPtr<u8>) -> i32 {
+    let name_cstr = env.mem.cstr_at(name).to_vec();
+    if !is_valid_env_var_name(&name_cstr) {
+        set_errno(env, EINVAL);
+        return -1;
+    }
+    remove_env_var(env, &name_cstr);
+    0 // success
+}
+fn putenv(env: &mut Environment, string: MutPtr<u8>) -> i32 {
+    // The string has the form "name=value" and becomes part of the
+    // environment: later changes to it by the app will be visible.
+    let string_cstr = env.mem.cstr_at(string);
+    let Some(equals_idx) = string_cstr.iter().position(|&c| c == b'=') else {
+        set_errno(env, EINVAL);
+        return -1;
+    };
+    let name = string_cstr[..equals_idx].to_vec();
+    if name.is_empty() {
+        set_errno(env, EINVAL);
+        return -1;
+    }
+    let value = string + GuestUSize::try_from(equals_idx).unwrap() + 1;
+    remove_env_var(env, &name);
+    env_vars(env).insert(
+        name,
+        EnvValue {
+            value,
+            owned: false,
+        },
     );
     0 // success
 }
@@ -223,12 +328,63 @@ fn bsearch(
     Ptr::null()
 }
 
+fn strtod(env: &mut Environment, nptr: ConstPtr<u8>, endptr: MutPtr<ConstPtr<u8>>) -> f64 {
+    let (number, length, out_of_range) = atof_inner(env, nptr).unwrap_or((0.0, 0, false));
+    if !endptr.is_null() {
+        env.mem.write(endptr, nptr + length);
+    }
+    if out_of_range {
+        set_errno(env, ERANGE);
+    }
+    number
+}
+
 fn strtof(env: &mut Environment, nptr: ConstPtr<u8>, endptr: MutPtr<ConstPtr<u8>>) -> f32 {
-    let (number, length) = atof_inner(env, nptr).unwrap_or((0.0, 0));
+    let (number, length, out_of_range) = atof_inner(env, nptr).unwrap_or((0.0, 0, false));
     if !endptr.is_null() {
         env.mem.write(endptr, nptr + length);
     }
-    number as f32
+    let number_f32 = number as f32;
+    if out_of_range
+        || (number_f32.is_infinite() && number.is_finite())
+        || (number_f32 == 0.0 && number != 0.0)
+    {
+        set_errno(env, ERANGE);
+    }
+    number_f32
+}
+
+fn strtol(
+    env: &mut Environment,
+    nptr: ConstPtr<u8>,
+    endptr: MutPtr<ConstPtr<u8>>,
+    base: i32,
+) -> i32 {
+    strtol_inner(env, nptr, endptr, base, i32::MIN.into(), i32::MAX.into()) as i32
+}
+fn strtoul(
+    env: &mut Environment,
+    nptr: ConstPtr<u8>,
+    endptr: MutPtr<ConstPtr<u8>>,
+    base: i32,
+) -> u32 {
+    strtol_inner(env, nptr, endptr, base, 0, u32::MAX.into()) as u32
+}
+fn strtoll(
+    env: &mut Environment,
+    nptr: ConstPtr<u8>,
+    endptr: MutPtr<ConstPtr<u8>>,
+    base: i32,
+) -> i64 {
+    strtol_inner(env, nptr, endptr, base, i64::MIN.into(), i64::MAX.into()) as i64
+}
+fn strtoull(
+    env: &mut Environment,
+    nptr: ConstPtr<u8>,
+    endptr: MutPtr<ConstPtr<u8>>,
+    base: i32,
+) -> u64 {
+    strtol_inner(env, nptr, endptr, base, 0, u64::MAX.into()) as u64
 }
 
 pub const FUNCTIONS: FunctionExports = &[
@@ -236,6 +392,7 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(calloc(_, _)),
     export_c_func!(realloc(_, _)),
     export_c_func!(free(_)),
+    export_c_func!(malloc_size(_)),
     export_c_func!(atexit(_)),
     export_c_func!(atoi(_)),
     export_c_func!(atol(_)),
@@ -247,43 +404,291 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(arc4random()),
     export_c_func!(getenv(_)),
     export_c_func!(setenv(_, _, _)),
+    export_c_func!(unsetenv(_)),
+    export_c_func!(putenv(_)),
     export_c_func!(exit(_)),
     export_c_func!(bsearch(_, _, _, _, _)),
+    export_c_func!(strtod(_, _)),
     export_c_func!(strtof(_, _)),
+    export_c_func!(strtol(_, _, _)),
+    export_c_func!(strtoul(_, _, _)),
+    export_c_func!(strtoll(_, _, _)),
+    export_c_func!(strtoull(_, _, _)),
 ];
 
-/// Returns a tuple containing the parsed number and the length of the number in
-/// the string
-fn atof_inner(env: &mut Environment, s: ConstPtr<u8>) -> Result<(f64, u32), <f64 as FromStr>::Err> {
-    // atof() is similar to atoi().
-    // FIXME: no C99 hexfloat, INF, NAN support
+/// Parse a floating-point number in any of the forms accepted by `strtod()`:
+/// decimal, hexadecimal (`0x1.8p3`), infinity or NaN.
+///
+/// Returns a tuple containing the parsed number, the length of the number in
+/// the string (including leading whitespace), and whether the number was out of
+/// range (overflow or underflow). Returns [None] if there is no number.
+fn atof_inner(env: &mut Environment, s: ConstPtr<u8>) -> Option<(f64, u32, bool)> {
     let start = skip_whitespace(env, s);
     let whitespace_len = Ptr::to_bits(start) - Ptr::to_bits(s);
     let mut len = 0;
-    let maybe_sign = env.mem.read(start + len);
-    if maybe_sign == b'+' || maybe_sign == b'-' || maybe_sign.is_ascii_digit() {
-        len += 1;
+
+    let sign = match env.mem.read(start) {
+        b'-' => {
+            len += 1;
+            -1.0
+        }
+        b'+' => {
+            len += 1;
+            1.0
+        }
+        _ => 1.0,
+    };
+
+    // Case-insensitive check for a word in the string
+    let matches_word = |env: &mut Environment, at: GuestUSize, word: &[u8]| {
+        word.iter().enumerate().all(|(i, &c)| {
+            let i: GuestUSize = i.try_into().unwrap();
+            env.mem.read(start + at + i).to_ascii_lowercase() == c
+        })
+    };
+
+    if matches_word(env, len, b"inf") {
+        len += if matches_word(env, len, b"infinity") {
+            8
+        } else {
+            3
+        };
+        return Some((sign * f64::INFINITY, whitespace_len + len, false));
     }
-    while env.mem.read(start + len).is_ascii_digit() {
-        len += 1;
+    if matches_word(env, len, b"nan") {
+        len += 3;
+        // Optional implementation-defined "n-char-sequence" in brackets
+        if env.mem.read(start + len) == b'(' {
+            let mut seq_len = 1;
+            loop {
+                let c = env.mem.read(start + len + seq_len);
+                if !(c.is_ascii_alphanumeric() || c == b'_') {
+                    break;
+                }
+                seq_len += 1;
+            }
+            if env.mem.read(start + len + seq_len) == b')' {
+                len += seq_len + 1;
+            }
+        }
+        return Some((f64::NAN.copysign(sign), whitespace_len + len, false));
     }
-    if env.mem.read(start + len) == b'.' {
-        len += 1;
-        while env.mem.read(start + len).is_ascii_digit() {
+
+    let is_hex = env.mem.read(start + len) == b'0'
+        && env.mem.read(start + len + 1).to_ascii_lowercase() == b'x';
+    let (number, has_non_zero_digit) = if is_hex {
+        // If no digits follow, the "0" alone is the number.
+        let zero_len = len + 1;
+        len += 2;
+        let mut mantissa: u64 = 0;
+        let mut exponent: i32 = 0;
+        let mut has_digits = false;
+        let mut seen_point = false;
+        loop {
+            let c = env.mem.read(start + len);
+            if c == b'.' && !seen_point {
+                seen_point = true;
+            } else if let Some(digit) = (c as char).to_digit(16) {
+                has_digits = true;
+                // Digits that don't fit in the mantissa only affect the
+                // exponent (and the rounding, which is ignored here).
+                if mantissa >> 60 == 0 {
+                    mantissa = (mantissa << 4) | u64::from(digit);
+                    if seen_point {
+                        exponent -= 4;
+                    }
+                } else if !seen_point {
+                    exponent += 4;
+                }
+            } else {
+                break;
+            }
             len += 1;
         }
-    }
-    if env.mem.read(start + len).to_ascii_lowercase() == b'e' {
-        len += 1;
-        let maybe_sign = env.mem.read(start + len);
-        if maybe_sign == b'+' || maybe_sign == b'-' || maybe_sign.is_ascii_digit() {
+        if !has_digits {
+            return Some((sign * 0.0, whitespace_len + zero_len, false));
+        }
+        if env.mem.read(start + len).to_ascii_lowercase() == b'p' {
+            if let Some((exponent_value, exponent_len)) = parse_exponent(env, start + len + 1) {
+                exponent = exponent.saturating_add(exponent_value);
+                len += 1 + exponent_len;
+            }
+        }
+        let number = if mantissa == 0 {
+            0.0
+        } else {
+            // The scaling is split in two so that the intermediate result
+            // doesn't overflow or underflow unnecessarily.
+            let half_exponent = exponent / 2;
+            mantissa as f64 * 2f64.powi(half_exponent) * 2f64.powi(exponent - half_exponent)
+        };
+        (number, mantissa != 0)
+    } else {
+        let digits_start = len;
+        let mut has_digits = false;
+        let mut has_non_zero_digit = false;
+        while let c @ b'0'..=b'9' = env.mem.read(start + len) {
+            has_digits = true;
+            has_non_zero_digit |= c != b'0';
             len += 1;
         }
-        while env.mem.read(start + len).is_ascii_digit() {
+        if env.mem.read(start + len) == b'.' {
             len += 1;
+            while let c @ b'0'..=b'9' = env.mem.read(start + len) {
+                has_digits = true;
+                has_non_zero_digit |= c != b'0';
+                len += 1;
+            }
+        }
+        if !has_digits {
+            return None;
+        }
+        let mantissa_len = len;
+        let mut exponent = 0;
+        if env.mem.read(start + len).to_ascii_lowercase() == b'e' {
+            if let Some((exponent_value, exponent_len)) = parse_exponent(env, start + len + 1) {
+                exponent = exponent_value;
+                len += 1 + exponent_len;
+            }
         }
+        // Rust's parser does correct rounding, but it can't take the exponent
+        // if it was clamped, so that's appended separately.
+        let mantissa = std::str::from_utf8(
+            env.mem
+                .bytes_at(start + digits_start, mantissa_len - digits_start),
+        )
+        .unwrap();
+        let number: f64 = format!("{}e{}", mantissa, exponent).parse().unwrap();
+        (number, has_non_zero_digit)
+    };
+
+    let out_of_range = number.is_infinite() || (number == 0.0 && has_non_zero_digit);
+    Some((sign * number, whitespace_len + len, out_of_range))
+}
+
+/// Helper for [atof_inner]: parse a decimal exponent with an optional sign.
+/// Returns the exponent (clamped to a reasonable range) and its length, or
+/// [None] if there are no digits.
+fn parse_exponent(env: &mut Environment, s: ConstPtr<u8>) -> Option<(i32, u32)> {
+    let mut len = 0;
+    let negative = match env.mem.read(s) {
+        b'-' => {
+            len += 1;
+            true
+        }
+        b'+' => {
+            len += 1;
+            false
+        }
+        _ => false,
+    };
+    let digits_start = len;
+    let mut exponent: i32 = 0;
+    while let c @ b'0'..=b'9' = env.mem.read(s + len) {
+        // Anything this large will overflow or underflow anyway.
+        exponent = (exponent * 10 + i32::from(c - b'0')).min(100_000);
+        len += 1;
+    }
+    if len == digits_start {
+        return None;
     }
+    Some((if negative { -exponent } else { exponent }, len))
+}
 
-    let s = std::str::from_utf8(env.mem.bytes_at(start, len)).unwrap();
-    s.parse().map(|result| (result, whitespace_len + len))
+/// Shared implementation of the `strtol()` family. The result is clamped to
+/// `min..=max`, which also determines whether the conversion is signed (`min`
+/// is negative) or unsigned (`min` is zero).
+fn strtol_inner(
+    env: &mut Environment,
+    nptr: ConstPtr<u8>,
+    endptr: MutPtr<ConstPtr<u8>>,
+    base: i32,
+    min: i128,
+    max: i128,
+) -> i128 {
+    if base != 0 && !(2..=36).contains(&base) {
+        set_errno(env, EINVAL);
+        if !endptr.is_null() {
+            env.mem.write(endptr, nptr);
+        }
+        return 0;
+    }
+
+    let mut ptr = skip_whitespace(env, nptr);
+    let negative = match env.mem.read(ptr) {
+        b'-' => {
+            ptr += 1;
+            true
+        }
+        b'+' => {
+            ptr += 1;
+            false
+        }
+        _ => false,
+    };
+
+    // A `0x` prefix is only consumed if a hex digit follows it, so that e.g.
+    // "0xz" is parsed as just "0".
+    let has_hex_prefix = env.mem.read(ptr) == b'0'
+        && matches!(env.mem.read(ptr + 1), b'x' | b'X')
+        && env.mem.read(ptr + 2).is_ascii_hexdigit();
+    let base: u32 = match base {
+        0 | 16 if has_hex_prefix => {
+            ptr += 2;
+            16
+        }
+        0 if env.mem.read(ptr) == b'0' => 8,
+        0 => 10,
+        _ => base as u32,
+    };
+
+    let digits_start = ptr;
+    let mut magnitude: u128 = 0;
+    while let Some(digit) = (env.mem.read(ptr) as char).to_digit(base) {
+        magnitude = magnitude
+            .saturating_mul(base.into())
+            .saturating_add(digit.into());
+        ptr += 1;
+    }
+
+    if ptr == digits_start {
+        // No conversion could be performed
+        if !endptr.is_null() {
+            env.mem.write(endptr, nptr);
+        }
+        return 0;
+    }
+    if !endptr.is_null() {
+        env.mem.write(endptr, ptr);
+    }
+
+    // Any value that doesn't fit in an i128 is certainly out of range.
+    let magnitude: i128 = magnitude.try_into().unwrap_or(i128::MAX);
+    let value = if min == 0 && magnitude <= max {
+        // Unsigned conversions negate the value within the unsigned type, so
+        // "-1" becomes the maximum value.
+        if negative && magnitude != 0 {
+            max + 1 - magnitude
+        } else {
+            magnitude
+        }
+    } else if negative {
+        -magnitude
+    } else {
+        magnitude
+    };
+
+    if value > max {
+        set_errno(env, ERANGE);
+        max
+    } else if value < min {
+        set_errno(env, ERANGE);
+        if min == 0 {
+            max
+        } else {
+            min
+        }
+    } else {
+        value
+    }
 }
@@ -23,32 +23,65 @@ fn qsort(
     qsort_rec(env, base, nitems, size, compar, 0, nitems - 1);
 }
 
+/// Sorts the items from `low` to `hi` inclusive.
+///
+/// Note that `compar` runs guest code, which may itself call into the host, so
+/// no references into guest memory can be held across a comparison.
 fn qsort_rec(
     env: &mut Environment,
     base: MutPtr<u8>,
     nitems: u32,
     size: u32,
     compar: GuestFunction,
-    low: u32,
-    hi: u32,
+    mut low: u32,
+    mut hi: u32,
 ) {
-    if low >= hi {
-        return;
-    }
-    // TODO: use median selection
-    let pivot = low;
-    let mut separator = low + 1;
-    for i in low + 1..=hi {
-        if compare(env, base, size, compar, i, pivot) < 0 {
-            swap_slices(env, base, nitems, size, i, separator);
-            separator += 1;
+    while low < hi {
+        // Median-of-three pivot selection avoids quadratic behavior on already
+        // sorted input, which is common in practice. The pivot is moved to
+        // the start of the range.
+        let mid = low + (hi - low) / 2;
+        if compare(env, base, size, compar, mid, low) < 0 {
+            swap_slices(env, base, nitems, size, mid, low);
+        }
+        if compare(env, base, size, compar, hi, low) < 0 {
+            swap_slices(env, base, nitems, size, hi, low);
+        }
+        if compare(env, base, size, compar, hi, mid) < 0 {
+            swap_slices(env, base, nitems, size, hi, mid);
+        }
+        swap_slices(env, base, nitems, size, low, mid);
+
+        let pivot = low;
+        let mut separator = low + 1;
+        for i in low + 1..=hi {
+            if compare(env, base, size, compar, i, pivot) < 0 {
+                swap_slices(env, base, nitems, size, i, separator);
+                separator += 1;
+            }
+        }
+        swap_slices(env, base, nitems, size, pivot, separator - 1);
+
+        // The pivot is now at separator - 1. Recurse into the smaller
+        // partition and loop on the larger one, so the host stack depth is
+        // bounded by log2(nitems).
+        let left_len = separator - 1 - low;
+        let right_len = hi + 1 - separator;
+        if left_len < right_len {
+            if left_len > 1 {
+                qsort_rec(env, base, nitems, size, compar, low, separator - 2);
+            }
+            low = separator;
+        } else {
+            if right_len > 1 {
+                qsort_rec(env, base, nitems, size, compar, separator, hi);
+            }
+            if separator < low + 2 {
+                return;
+            }
+            hi = separator - 2;
         }
     }
-    swap_slices(env, base, nitems, size, pivot, separator - 1);
-    if separator > 1 {
-        qsort_rec(env, base, nitems, size, compar, low, separator - 2);
-    }
-    qsort_rec(env, base, nitems, size, compar, separator, hi);
 }
 
 fn compare(
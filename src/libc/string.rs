@@ -17,26 +17,25 @@ pub struct State {
     strtok: Option<MutPtr<u8>>,
 }
 
-fn strtok(env: &mut Environment, s: MutPtr<u8>, sep: ConstPtr<u8>) -> MutPtr<u8> {
-    let s = if s.is_null() {
-        let state = env.libc_state.string.strtok.unwrap();
-        if state.is_null() {
-            env.libc_state.string.strtok = None;
-            return Ptr::null();
-        }
-        state
-    } else {
-        s
-    };
+/// Shared implementation of `strtok()` and `strtok_r()`. Returns the next token
+/// (or NULL if there are none left) and the position to continue from.
+fn strtok_inner(
+    env: &mut Environment,
+    s: MutPtr<u8>,
+    sep: ConstPtr<u8>,
+) -> (MutPtr<u8>, MutPtr<u8>) {
+    if s.is_null() {
+        return (Ptr::null(), Ptr::null());
+    }
 
     let sep = env.mem.cstr_at(sep);
 
+    // Consecutive separators are skipped, so there can't be empty tokens.
     let mut token_start = s;
     loop {
         let c = env.mem.read(token_start);
         if c == b'\0' {
-            env.libc_state.string.strtok = None;
-            return Ptr::null();
+            return (Ptr::null(), Ptr::null());
         } else if sep.contains(&c) {
             token_start += 1;
         } else {
@@ -57,9 +56,32 @@ fn strtok(env: &mut Environment, s: MutPtr<u8>, sep: ConstPtr<u8>) -> MutPtr<u8>
         }
     };
 
+    (token_start, next_token)
+}
+
+fn strtok(env: &mut Environment, s: MutPtr<u8>, sep: ConstPtr<u8>) -> MutPtr<u8> {
+    let s = if s.is_null() {
+        env.libc_state.string.strtok.unwrap_or(Ptr::null())
+    } else {
+        s
+    };
+    let (token, next_token) = strtok_inner(env, s, sep);
     env.libc_state.string.strtok = Some(next_token);
+    token
+}
 
-    token_start
+fn strtok_r(
+    env: &mut Environment,
+    s: MutPtr<u8>,
+    sep: ConstPtr<u8>,
+    lasts: MutPtr<MutPtr<u8>>,
+) -> MutPtr<u8> {
+    // Unlike strtok(), the position is stored by the caller, so separate
+    // tokenizations can be interleaved.
+    let s = if s.is_null() { env.mem.read(lasts) } else { s };
+    let (token, next_token) = strtok_inner(env, s, sep);
+    env.mem.write(lasts, next_token);
+    token
 }
 
 // Functions shared with wchar.rs
@@ -213,6 +235,47 @@ fn strncat(env: &mut Environment, s1: MutPtr<u8>, s2: ConstPtr<u8>, n: GuestUSiz
 fn strstr(env: &mut Environment, string: ConstPtr<u8>, substring: ConstPtr<u8>) -> ConstPtr<u8> {
     GenericChar::<u8>::strstr(env, string, substring)
 }
+fn strcasestr(
+    env: &mut Environment,
+    string: ConstPtr<u8>,
+    substring: ConstPtr<u8>,
+) -> ConstPtr<u8> {
+    // TODO: generalize to wide chars
+    let substring_len = strlen(env, substring);
+    let mut offset = 0;
+    loop {
+        if strncasecmp(env, string + offset, substring, substring_len) == 0 {
+            return string + offset;
+        } else if env.mem.read(string + offset) == b'\0' {
+            return Ptr::null();
+        }
+        offset += 1;
+    }
+}
+fn memmem(
+    env: &mut Environment,
+    big: ConstVoidPtr,
+    big_len: GuestUSize,
+    little: ConstVoidPtr,
+    little_len: GuestUSize,
+) -> ConstVoidPtr {
+    // Unlike strstr(), null bytes are not special.
+    if little_len == 0 {
+        return big;
+    }
+    if little_len > big_len {
+        return Ptr::null();
+    }
+    let big_bytes = env.mem.bytes_at(big.cast(), big_len);
+    let little_bytes = env.mem.bytes_at(little.cast(), little_len);
+    match big_bytes
+        .windows(little_bytes.len())
+        .position(|window| window == little_bytes)
+    {
+        Some(offset) => (big.cast::<u8>() + GuestUSize::try_from(offset).unwrap()).cast(),
+        None => Ptr::null(),
+    }
+}
 fn strchr(env: &mut Environment, path: ConstPtr<u8>, c: u8) -> ConstPtr<u8> {
     GenericChar::<u8>::strchr(env, path, c)
 }
@@ -222,6 +285,7 @@ fn strrchr(env: &mut Environment, path: ConstPtr<u8>, c: u8) -> ConstPtr<u8> {
 
 pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(strtok(_, _)),
+    export_c_func!(strtok_r(_, _, _)),
     // Functions shared with wchar.rs
     export_c_func!(memset(_, _, _)),
     export_c_func!(memcpy(_, _, _)),
@@ -242,6 +306,8 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(strncasecmp(_, _, _)),
     export_c_func!(strncat(_, _, _)),
     export_c_func!(strstr(_, _)),
+    export_c_func!(strcasestr(_, _)),
+    export_c_func!(memmem(_, _, _, _)),
     export_c_func!(strchr(_, _)),
     export_c_func!(strrchr(_, _)),
 ];
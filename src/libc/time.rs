@@ -6,9 +6,11 @@
 //! `time.h` (C) and `sys/time.h` (POSIX)
 
 use crate::dyld::{export_c_func, FunctionExports};
-use crate::mem::{guest_size_of, ConstPtr, MutPtr, Ptr, SafeRead};
+use crate::frameworks::foundation::ns_locale;
+use crate::libc::errno::{set_errno, EINVAL};
+use crate::mem::{guest_size_of, ConstPtr, GuestUSize, MutPtr, Ptr, SafeRead};
 use crate::Environment;
-use std::time::{Duration, Instant, SystemTime};
+use std::time::{Duration, SystemTime};
 
 #[derive(Default)]
 pub struct State {
@@ -30,22 +32,28 @@ type clock_t = u64;
 const CLOCKS_PER_SEC: clock_t = 1000000;
 
 fn clock(env: &mut Environment) -> clock_t {
-    Instant::now()
-        .duration_since(env.startup_time)
-        .as_secs()
-        .wrapping_mul(CLOCKS_PER_SEC)
+    env.clock.elapsed().as_secs().wrapping_mul(CLOCKS_PER_SEC)
 }
 
-fn time(env: &mut Environment, out: MutPtr<time_t>) -> time_t {
-    let time64 = SystemTime::now()
+/// Get the emulated wall-clock time as a [time_t] and the sub-second part.
+/// Shared by `time()`, `gettimeofday()` and `clock_gettime()` so they agree.
+fn unix_time(env: &mut Environment) -> (time_t, Duration) {
+    let time = env
+        .clock
+        .system_time()
         .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    let time = time64 as time_t;
-    if !env.libc_state.time.y2k38_warned && time64 != time as u64 {
+        .unwrap();
+    let time64 = time.as_secs();
+    let time_s = time64 as time_t;
+    if !env.libc_state.time.y2k38_warned && time64 != time_s as u64 {
         env.libc_state.time.y2k38_warned = true;
         log!("Warning: system clock is beyond Y2K38 and might confuse the app");
     }
+    (time_s, Duration::from_nanos(time.subsec_nanos().into()))
+}
+
+fn time(env: &mut Environment, out: MutPtr<time_t>) -> time_t {
+    let (time, _) = unix_time(env);
     if !out.is_null() {
         env.mem.write(out, time);
     }
@@ -70,7 +78,7 @@ pub struct tm {
     /// year with 1900 subtracted from it
     pub tm_year: i32,
     /// day of the week (where Sunday is the first day)
-    tm_wday: i32,
+    pub tm_wday: i32,
     /// day of the year
     tm_yday: i32,
     /// 1 if daylight saving time is in effect
@@ -187,6 +195,49 @@ pub fn timestamp_to_calendar_date(timestamp: time_t) -> tm {
         tm_zone: Ptr::null(),
     }
 }
+/// The inverse of [timestamp_to_calendar_date]. Out-of-range fields are
+/// normalized (e.g. 61 minutes is 1 hour and 1 minute), and `tm_wday`,
+/// `tm_yday` and the timezone fields are ignored. Returns [None] if the result
+/// doesn't fit in a [time_t].
+pub fn calendar_date_to_timestamp(date: tm) -> Option<time_t> {
+    let tm {
+        tm_sec,
+        tm_min,
+        tm_hour,
+        tm_mday,
+        tm_mon,
+        tm_year,
+        ..
+    } = date;
+
+    // Months are the only field whose length depends on the others, so they
+    // are normalized first. Everything else can just be added up as days or
+    // seconds.
+    let year = i64::from(tm_year) + 1900 + i64::from(tm_mon).div_euclid(12);
+    let month_in_year = tm_mon.rem_euclid(12) as usize;
+
+    let years_since_y2k = year - 2000;
+    let cycles_since_y2k = years_since_y2k.div_euclid(CYCLE_YEARS.into());
+    let year_in_cycle = years_since_y2k.rem_euclid(CYCLE_YEARS.into()) as usize;
+    let month_to_day = if is_leap_year(year_in_cycle as i32) {
+        &MONTH_TO_DAY_LEAP
+    } else {
+        &MONTH_TO_DAY_NONLEAP
+    };
+    let days_since_y2k = cycles_since_y2k * i64::from(CYCLE_DAYS)
+        + i64::from(YEAR_TO_DAY[year_in_cycle])
+        + i64::from(month_to_day[month_in_year])
+        + i64::from(tm_mday)
+        - 1;
+    let days_since_unix_epoch = days_since_y2k + 10957;
+
+    let timestamp = days_since_unix_epoch * 24 * 60 * 60
+        + i64::from(tm_hour) * 60 * 60
+        + i64::from(tm_min) * 60
+        + i64::from(tm_sec);
+    timestamp.try_into().ok()
+}
+
 #[cfg(test)]
 #[test]
 fn test_timestamp_to_calendar_date() {
@@ -240,6 +291,35 @@ fn test_timestamp_to_calendar_date() {
     do_test("Fri, 2005-05-27T19:45:47", 1117223147);
     do_test("Sat, 1955-03-26T20:47:45", -466053135);
 }
+#[cfg(test)]
+#[test]
+fn test_calendar_date_to_timestamp() {
+    for timestamp in [
+        1140398872,
+        2113022454,
+        -1509557849,
+        648910963,
+        -1799896627,
+        0,
+        951782400,
+    ] {
+        assert_eq!(
+            calendar_date_to_timestamp(timestamp_to_calendar_date(timestamp)),
+            Some(timestamp)
+        );
+    }
+    // Normalization: 2005-14-20T00:-60:3600 is 2006-02-20T00:00:00
+    let mut date = timestamp_to_calendar_date(1140398872);
+    date.tm_mon = 13;
+    date.tm_year = 105;
+    date.tm_hour = 0;
+    date.tm_min = -60;
+    date.tm_sec = 3600;
+    assert_eq!(calendar_date_to_timestamp(date), Some(1140393600));
+    // Too late for a 32-bit time_t
+    date.tm_year = 200;
+    assert_eq!(calendar_date_to_timestamp(date), None);
+}
 
 fn gmtime_r(env: &mut Environment, timestamp: ConstPtr<time_t>, res: MutPtr<tm>) -> MutPtr<tm> {
     let timestamp = env.mem.read(timestamp);
@@ -267,6 +347,330 @@ fn localtime(env: &mut Environment, timestamp: ConstPtr<time_t>) -> MutPtr<tm> {
     gmtime(env, timestamp)
 }
 
+fn mktime(env: &mut Environment, timeptr: MutPtr<tm>) -> time_t {
+    // TODO: don't assume local time is UTC?
+    let date = env.mem.read(timeptr);
+    let Some(timestamp) = calendar_date_to_timestamp(date) else {
+        log!("Warning: mktime() result out of range, returning -1");
+        return -1;
+    };
+    // The fields of the struct must be normalized and the day of the week and
+    // year filled in.
+    env.mem
+        .write(timeptr, timestamp_to_calendar_date(timestamp));
+    timestamp
+}
+
+/// Localized names of days and months for `strftime()`. Also used by
+/// `NSDateFormatter`.
+pub struct TimeNames {
+    pub weekdays: [&'static str; 7],
+    pub weekdays_abbr: [&'static str; 7],
+    pub months: [&'static str; 12],
+    pub months_abbr: [&'static str; 12],
+}
+
+const ENGLISH_TIME_NAMES: TimeNames = TimeNames {
+    weekdays: [
+        "Sunday",
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+        "Thursday",
+        "Friday",
+        "Saturday",
+    ],
+    weekdays_abbr: ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"],
+    months: [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ],
+    months_abbr: [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ],
+};
+const FRENCH_TIME_NAMES: TimeNames = TimeNames {
+    weekdays: [
+        "dimanche", "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi",
+    ],
+    weekdays_abbr: ["dim.", "lun.", "mar.", "mer.", "jeu.", "ven.", "sam."],
+    months: [
+        "janvier",
+        "février",
+        "mars",
+        "avril",
+        "mai",
+        "juin",
+        "juillet",
+        "août",
+        "septembre",
+        "octobre",
+        "novembre",
+        "décembre",
+    ],
+    months_abbr: [
+        "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.", "nov.",
+        "déc.",
+    ],
+};
+const GERMAN_TIME_NAMES: TimeNames = TimeNames {
+    weekdays: [
+        "Sonntag",
+        "Montag",
+        "Dienstag",
+        "Mittwoch",
+        "Donnerstag",
+        "Freitag",
+        "Samstag",
+    ],
+    weekdays_abbr: ["So", "Mo", "Di", "Mi", "Do", "Fr", "Sa"],
+    months: [
+        "Januar",
+        "Februar",
+        "März",
+        "April",
+        "Mai",
+        "Juni",
+        "Juli",
+        "August",
+        "September",
+        "Oktober",
+        "November",
+        "Dezember",
+    ],
+    months_abbr: [
+        "Jan", "Feb", "Mär", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez",
+    ],
+};
+const SPANISH_TIME_NAMES: TimeNames = TimeNames {
+    weekdays: [
+        "domingo",
+        "lunes",
+        "martes",
+        "miércoles",
+        "jueves",
+        "viernes",
+        "sábado",
+    ],
+    weekdays_abbr: ["dom", "lun", "mar", "mié", "jue", "vie", "sáb"],
+    months: [
+        "enero",
+        "febrero",
+        "marzo",
+        "abril",
+        "mayo",
+        "junio",
+        "julio",
+        "agosto",
+        "septiembre",
+        "octubre",
+        "noviembre",
+        "diciembre",
+    ],
+    months_abbr: [
+        "ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sep", "oct", "nov", "dic",
+    ],
+};
+const ITALIAN_TIME_NAMES: TimeNames = TimeNames {
+    weekdays: [
+        "domenica",
+        "lunedì",
+        "martedì",
+        "mercoledì",
+        "giovedì",
+        "venerdì",
+        "sabato",
+    ],
+    weekdays_abbr: ["dom", "lun", "mar", "mer", "gio", "ven", "sab"],
+    months: [
+        "gennaio",
+        "febbraio",
+        "marzo",
+        "aprile",
+        "maggio",
+        "giugno",
+        "luglio",
+        "agosto",
+        "settembre",
+        "ottobre",
+        "novembre",
+        "dicembre",
+    ],
+    months_abbr: [
+        "gen", "feb", "mar", "apr", "mag", "giu", "lug", "ago", "set", "ott", "nov", "dic",
+    ],
+};
+const JAPANESE_TIME_NAMES: TimeNames = TimeNames {
+    weekdays: [
+        "日曜日",
+        "月曜日",
+        "火曜日",
+        "水曜日",
+        "木曜日",
+        "金曜日",
+        "土曜日",
+    ],
+    weekdays_abbr: ["日", "月", "火", "水", "木", "金", "土"],
+    months: [
+        "1月", "2月", "3月", "4月", "5月", "6月", "7月", "8月", "9月", "10月", "11月", "12月",
+    ],
+    months_abbr: [
+        "1月", "2月", "3月", "4月", "5月", "6月", "7月", "8月", "9月", "10月", "11月", "12月",
+    ],
+};
+
+/// Get the day and month names for a language code, falling back to English.
+pub fn time_names_for_language(language_code: &str) -> &'static TimeNames {
+    match language_code {
+        "fr" => &FRENCH_TIME_NAMES,
+        "de" => &GERMAN_TIME_NAMES,
+        "es" => &SPANISH_TIME_NAMES,
+        "it" => &ITALIAN_TIME_NAMES,
+        "ja" => &JAPANESE_TIME_NAMES,
+        _ => &ENGLISH_TIME_NAMES,
+    }
+}
+
+/// Format a calendar date like `strftime()`, using the given names for days
+/// and months. Returns the formatted bytes (without a null terminator).
+fn strftime_inner(format: &[u8], time: tm, names: &TimeNames) -> Vec<u8> {
+    let tm {
+        tm_sec,
+        tm_min,
+        tm_hour,
+        tm_mday,
+        tm_mon,
+        tm_year,
+        tm_wday,
+        tm_yday,
+        ..
+    } = time;
+    // Out-of-range values are undefined behavior, but let's not crash.
+    let weekday_idx = tm_wday.rem_euclid(7) as usize;
+    let month_idx = tm_mon.rem_euclid(12) as usize;
+    let hour_12 = (tm_hour + 11).rem_euclid(12) + 1;
+
+    let mut res = Vec::new();
+    let mut format_iter = format.iter();
+    while let Some(&c) = format_iter.next() {
+        if c != b'%' {
+            res.push(c);
+            continue;
+        }
+        let Some(&specifier) = format_iter.next() else {
+            // A trailing % is undefined behavior, just print it.
+            res.push(b'%');
+            break;
+        };
+        let formatted = match specifier {
+            b'a' => names.weekdays_abbr[weekday_idx].to_string(),
+            b'A' => names.weekdays[weekday_idx].to_string(),
+            b'b' | b'h' => names.months_abbr[month_idx].to_string(),
+            b'B' => names.months[month_idx].to_string(),
+            b'C' => format!("{:02}", (tm_year + 1900).div_euclid(100)),
+            b'd' => format!("{:02}", tm_mday),
+            b'D' => format!(
+                "{:02}/{:02}/{:02}",
+                tm_mon + 1,
+                tm_mday,
+                tm_year.rem_euclid(100)
+            ),
+            b'e' => format!("{:2}", tm_mday),
+            b'F' => format!("{}-{:02}-{:02}", tm_year + 1900, tm_mon + 1, tm_mday),
+            b'H' => format!("{:02}", tm_hour),
+            b'I' => format!("{:02}", hour_12),
+            b'j' => format!("{:03}", tm_yday + 1),
+            b'm' => format!("{:02}", tm_mon + 1),
+            b'M' => format!("{:02}", tm_min),
+            b'n' => "\n".to_string(),
+            b'p' => (if tm_hour < 12 { "AM" } else { "PM" }).to_string(),
+            b'r' => format!(
+                "{:02}:{:02}:{:02} {}",
+                hour_12,
+                tm_min,
+                tm_sec,
+                if tm_hour < 12 { "AM" } else { "PM" }
+            ),
+            b'R' => format!("{:02}:{:02}", tm_hour, tm_min),
+            b'S' => format!("{:02}", tm_sec),
+            b't' => "\t".to_string(),
+            b'T' => format!("{:02}:{:02}:{:02}", tm_hour, tm_min, tm_sec),
+            b'u' => format!("{}", if tm_wday == 0 { 7 } else { tm_wday }),
+            b'w' => format!("{}", tm_wday),
+            b'y' => format!("{:02}", tm_year.rem_euclid(100)),
+            b'Y' => format!("{}", tm_year + 1900),
+            // The timezone is always UTC for now, see localtime_r().
+            b'z' => "+0000".to_string(),
+            b'Z' => "UTC".to_string(),
+            b'%' => "%".to_string(),
+            _ => {
+                log!(
+                    "TODO: strftime() format specifier {:?}, ignoring",
+                    specifier as char
+                );
+                format!("%{}", specifier as char)
+            }
+        };
+        res.extend_from_slice(formatted.as_bytes());
+    }
+    res
+}
+
+#[cfg(test)]
+#[test]
+fn test_strftime() {
+    let time = timestamp_to_calendar_date(1140398872); // Mon, 2006-02-20T01:27:52
+    let do_test = |expected: &str, format: &str| {
+        let res = strftime_inner(format.as_bytes(), time, &ENGLISH_TIME_NAMES);
+        assert_eq!(expected.as_bytes(), res);
+    };
+    do_test("2006-02-20 01:27:52", "%Y-%m-%d %H:%M:%S");
+    do_test("Monday, February 20", "%A, %B %e");
+    do_test("Mon Feb 06 051", "%a %b %y %j");
+    do_test("01:27 AM 100%", "%I:%M %p 100%%");
+    let time = timestamp_to_calendar_date(1641764511); // Sun, 2022-01-09T21:41:51
+    let res = strftime_inner(b"%r, %u day %w", time, &ENGLISH_TIME_NAMES);
+    assert_eq!(b"09:41:51 PM, 7 day 0", &res[..]);
+    // Localized names, falling back to English
+    let res = strftime_inner(b"%A %e %B (%a %b)", time, time_names_for_language("fr"));
+    assert_eq!("dimanche  9 janvier (dim. janv.)".as_bytes(), res);
+    let res = strftime_inner(b"%a %b", time, time_names_for_language("xx"));
+    assert_eq!(b"Sun Jan", &res[..]);
+}
+
+fn strftime(
+    env: &mut Environment,
+    s: MutPtr<u8>,
+    maxsize: GuestUSize,
+    format: ConstPtr<u8>,
+    timeptr: ConstPtr<tm>,
+) -> GuestUSize {
+    let time = env.mem.read(timeptr);
+    // TODO: This should really depend on the C locale (`setlocale()`), which
+    // is "C" by default, but apps that display dates probably expect them to
+    // match the system language.
+    let names = time_names_for_language(&ns_locale::current_language_code(env));
+    let res = strftime_inner(env.mem.cstr_at(format), time, names);
+    // The null terminator must fit too, otherwise nothing is written.
+    let len: GuestUSize = res.len().try_into().unwrap();
+    if len >= maxsize {
+        return 0;
+    }
+    env.mem.bytes_at_mut(s, len).copy_from_slice(&res);
+    env.mem.write(s + len, b'\0');
+    len
+}
+
 // sys/time.h (POSIX)
 
 #[allow(non_camel_case_types)]
@@ -315,31 +719,80 @@ fn gettimeofday(
         return 0; // success
     }
 
-    let time = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap();
-
-    let time_s_64: u64 = time.as_secs();
-    let tv_sec = time_s_64 as time_t;
-    if !env.libc_state.time.y2k38_warned && time_s_64 != tv_sec as u64 {
-        env.libc_state.time.y2k38_warned = true;
-        log!("Warning: system clock is beyond Y2K38 and might confuse the app");
-    }
-    let tv_usec: suseconds_t = time.subsec_micros().try_into().unwrap();
+    let (tv_sec, subsec) = unix_time(env);
+    let tv_usec: suseconds_t = subsec.subsec_micros().try_into().unwrap();
 
     env.mem.write(timeval_ptr, timeval { tv_sec, tv_usec });
 
     0 // success
 }
 
+#[allow(non_camel_case_types)]
+type clockid_t = u32;
+
+const CLOCK_REALTIME: clockid_t = 0;
+const CLOCK_MONOTONIC_RAW: clockid_t = 4;
+const CLOCK_MONOTONIC_RAW_APPROX: clockid_t = 5;
+const CLOCK_MONOTONIC: clockid_t = 6;
+const CLOCK_UPTIME_RAW: clockid_t = 8;
+const CLOCK_UPTIME_RAW_APPROX: clockid_t = 9;
+const CLOCK_PROCESS_CPUTIME_ID: clockid_t = 12;
+const CLOCK_THREAD_CPUTIME_ID: clockid_t = 16;
+
+fn clock_gettime(env: &mut Environment, clock_id: clockid_t, tp: MutPtr<timespec>) -> i32 {
+    let (tv_sec, tv_nsec) = match clock_id {
+        CLOCK_REALTIME => {
+            let (tv_sec, subsec) = unix_time(env);
+            (tv_sec, subsec.subsec_nanos())
+        }
+        // touchHLE doesn't track CPU time, but the time since startup is an
+        // upper bound on it, so it's a reasonable substitute.
+        CLOCK_MONOTONIC
+        | CLOCK_MONOTONIC_RAW
+        | CLOCK_MONOTONIC_RAW_APPROX
+        | CLOCK_UPTIME_RAW
+        | CLOCK_UPTIME_RAW_APPROX
+        | CLOCK_PROCESS_CPUTIME_ID
+        | CLOCK_THREAD_CPUTIME_ID => {
+            // The emulated clock is monotonic, so this never goes backwards.
+            let elapsed = env.clock.elapsed();
+            (
+                elapsed.as_secs().try_into().unwrap(),
+                elapsed.subsec_nanos(),
+            )
+        }
+        _ => {
+            log!("Warning: clock_gettime() with unknown clock {}", clock_id);
+            set_errno(env, EINVAL);
+            return -1;
+        }
+    };
+    if tp.is_null() {
+        set_errno(env, EINVAL);
+        return -1;
+    }
+    let tv_nsec = tv_nsec.try_into().unwrap();
+    env.mem.write(tp, timespec { tv_sec, tv_nsec });
+    0 // success
+}
+
 fn nanosleep(env: &mut Environment, rqtp: ConstPtr<timespec>, _rmtp: MutPtr<timespec>) -> i32 {
     let t = env.mem.read(rqtp);
     let tv_sec = t.tv_sec;
     let tv_nsec = t.tv_nsec;
     log_dbg!("nanosleep {} {}", tv_sec, tv_nsec);
+    if tv_sec < 0 || !(0..1_000_000_000).contains(&tv_nsec) {
+        set_errno(env, EINVAL);
+        return -1;
+    }
     let total_sleep = Duration::from_secs(tv_sec.try_into().unwrap())
         + Duration::from_nanos(tv_nsec.try_into().unwrap());
+    // This blocks only the current guest thread: other threads keep running,
+    // and the host sleeps if there's nothing else to do.
     env.sleep(total_sleep, true);
+    // The remaining time is only written if the sleep was interrupted by a
+    // signal (EINTR). touchHLE never does that currently, so the sleep always
+    // completes and `rmtp` is left alone.
     0 // success
 }
 
@@ -350,6 +803,9 @@ pub const FUNCTIONS: FunctionExports = &[
     export_c_func!(gmtime(_)),
     export_c_func!(localtime_r(_, _)),
     export_c_func!(localtime(_)),
+    export_c_func!(mktime(_)),
+    export_c_func!(strftime(_, _, _, _)),
     export_c_func!(gettimeofday(_, _)),
+    export_c_func!(clock_gettime(_, _)),
     export_c_func!(nanosleep(_, _)),
 ];
@@ -27,6 +27,8 @@ fn sleep(env: &mut Environment, seconds: u32) -> u32 {
 }
 
 fn usleep(env: &mut Environment, useconds: useconds_t) -> i32 {
+    // Like with sleep(), only the current guest thread is blocked, and it is
+    // never woken up early by a signal.
     env.sleep(Duration::from_micros(useconds.into()), true);
     0 // success
 }
@@ -427,6 +427,15 @@ impl Mem {
         self.bytes_at_mut(ptr.cast(), size).as_mut_ptr().cast()
     }
 
+    /// The inverse of [Self::ptr_at]: get the guest pointer corresponding to a
+    /// host pointer into guest memory, or [None] if it points elsewhere. This
+    /// is for pointers that host APIs hand back, e.g. OpenGL ES client-side
+    /// array pointers.
+    pub fn guest_ptr_for_host_ptr<T>(&self, host_ptr: *const T) -> Option<ConstPtr<T>> {
+        let offset = (host_ptr as usize).checked_sub(self.bytes as usize)?;
+        Some(Ptr::from_bits(offset.try_into().ok()?))
+    }
+
     /// Read a value for memory. This is the preferred way to read memory in
     /// most cases.
     pub fn read<T, const MUT: bool>(&self, ptr: Ptr<T, MUT>) -> T
@@ -454,7 +463,8 @@ impl Mem {
         unsafe { ptr.write_unaligned(value) }
     }
 
-    /// C-style `memmove`.
+    /// C-style `memmove`. The regions may overlap: [slice::copy_within] copies
+    /// as if through an intermediate buffer.
     pub fn memmove(&mut self, dest: MutVoidPtr, src: ConstVoidPtr, size: GuestUSize) {
         let src = src.to_bits() as usize;
         let dest = dest.to_bits() as usize;
@@ -470,6 +480,8 @@ impl Mem {
         ptr
     }
 
+    /// Resize an allocation made with one of the `alloc` methods on this type.
+    /// The contents are preserved up to the smaller of the old and new sizes.
     pub fn realloc(&mut self, old_ptr: MutVoidPtr, size: GuestUSize) -> MutVoidPtr {
         // TODO: for a moment we always assume that we do not have enough size
         //       to realloc inplace
@@ -477,13 +489,18 @@ impl Mem {
         if old_size == size {
             return old_ptr;
         }
-        assert!(size > old_size);
         let new_ptr = self.alloc(size);
-        self.memmove(new_ptr, old_ptr.cast_const(), old_size);
+        self.memmove(new_ptr, old_ptr.cast_const(), old_size.min(size));
         self.free(old_ptr);
         new_ptr
     }
 
+    /// Get the usable size of an allocation made with one of the `alloc`
+    /// methods on this type. This may be larger than the requested size.
+    pub fn allocated_size(&mut self, ptr: ConstVoidPtr) -> GuestUSize {
+        self.allocator.find_allocated_size(ptr.to_bits())
+    }
+
     /// Free an allocation made with one of the `alloc` methods on this type.
     pub fn free(&mut self, ptr: MutVoidPtr) {
         let size = self.allocator.free(ptr.to_bits());
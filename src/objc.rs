@@ -22,6 +22,7 @@ use crate::dyld::{export_c_func, FunctionExports};
 use crate::MutexId;
 use std::collections::HashMap;
 
+mod blocks;
 mod classes;
 mod messages;
 mod methods;
@@ -30,6 +31,7 @@ mod properties;
 mod selectors;
 mod synchronization;
 
+pub use blocks::{copy_block, get_block_invoke, release_block};
 pub use classes::{objc_classes, Class, ClassExports, ClassTemplate};
 pub use messages::{
     autorelease, msg, msg_class, msg_send, msg_send_super2, msg_super, objc_super, release, retain,
@@ -0,0 +1,82 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+//! Blocks, Apple's closure extension to C and Objective-C.
+//!
+//! Only calling guest blocks from host code, and copying them so host code can
+//! keep them, is supported so far. The block runtime functions exported to the
+//! guest (`_Block_copy` etc) are not yet implemented.
+//!
+//! Resources:
+//! - Clang's [Block Implementation Specification](https://clang.llvm.org/docs/Block-ABI-Apple.html)
+
+use super::{id, nil};
+use crate::abi::GuestFunction;
+use crate::mem::{ConstPtr, Mem, MutPtr};
+
+/// Offset in words of the `flags` field within a block literal. It is
+/// preceded by the `isa` field.
+const FLAGS_FIELD_OFFSET: u32 = 1;
+/// Offset in words of the `invoke` field within a block literal. It is
+/// preceded by the `isa`, `flags` and `reserved` fields.
+const INVOKE_FIELD_OFFSET: u32 = 3;
+/// Offset in words of the `descriptor` field within a block literal.
+const DESCRIPTOR_FIELD_OFFSET: u32 = 4;
+/// Offset in words of the `size` field within a block descriptor. It is
+/// preceded by the `reserved` field.
+const DESCRIPTOR_SIZE_FIELD_OFFSET: u32 = 1;
+
+const BLOCK_HAS_COPY_DISPOSE: u32 = 1 << 25;
+const BLOCK_IS_GLOBAL: u32 = 1 << 28;
+
+/// Get the function that implements a guest block.
+///
+/// The function must be called with the block itself as the first argument,
+/// followed by the block's parameters, e.g.:
+///
+/// ```ignore
+/// let invoke = get_block_invoke(&env.mem, block);
+/// let result: NSComparisonResult = invoke.call_from_host(env, (block, a, b));
+/// ```
+pub fn get_block_invoke(mem: &Mem, block: id) -> GuestFunction {
+    assert!(block != nil);
+    let field: ConstPtr<u32> = block.cast().cast_const() + INVOKE_FIELD_OFFSET;
+    GuestFunction::from_addr_with_thumb_bit(mem.read(field))
+}
+
+/// Copy a guest block so that host code can use it after the function that
+/// created it returns, like `Block_copy()`. The result must eventually be
+/// passed to [release_block].
+///
+/// TODO: Blocks with copy and dispose helpers (i.e. that capture objects or
+/// `__block` variables) are copied without calling the helpers, since they
+/// depend on block runtime functions we don't have yet.
+pub fn copy_block(mem: &mut Mem, block: id) -> id {
+    assert!(block != nil);
+    let words: MutPtr<u32> = block.cast();
+    let flags = mem.read(words + FLAGS_FIELD_OFFSET);
+    if flags & BLOCK_IS_GLOBAL != 0 {
+        return block;
+    }
+    if flags & BLOCK_HAS_COPY_DISPOSE != 0 {
+        log!(
+            "TODO: Copying block {:?} without calling its copy helper",
+            block
+        );
+    }
+    let descriptor: ConstPtr<u32> = mem.read((words + DESCRIPTOR_FIELD_OFFSET).cast());
+    let size = mem.read(descriptor + DESCRIPTOR_SIZE_FIELD_OFFSET);
+    let new_block = mem.alloc(size);
+    mem.memmove(new_block, block.cast().cast_const(), size);
+    new_block.cast()
+}
+
+/// Free a block copied with [copy_block].
+pub fn release_block(mem: &mut Mem, block: id) {
+    let flags = mem.read(block.cast::<u32>() + FLAGS_FIELD_OFFSET);
+    if flags & BLOCK_IS_GLOBAL == 0 {
+        mem.free(block.cast());
+    }
+}
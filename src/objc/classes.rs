@@ -702,6 +702,17 @@ impl ObjC {
         }
     }
 
+    /// Get the superclass of a class, or `nil` for a root class. Classes that
+    /// we don't have a real implementation of have no superclass.
+    pub fn get_superclass(&self, class: Class) -> Class {
+        let host_object = self.get_host_object(class).unwrap();
+        if let Some(&ClassHostObject { superclass, .. }) = host_object.as_any().downcast_ref() {
+            superclass
+        } else {
+            nil
+        }
+    }
+
     pub fn get_class_name(&self, class: Class) -> &str {
         let host_object = self.get_host_object(class).unwrap();
         if let Some(ClassHostObject { name, .. }) = host_object.as_any().downcast_ref() {
@@ -11,6 +11,7 @@ use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Read};
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::num::NonZeroU32;
+use std::path::PathBuf;
 
 pub const OPTIONS_HELP: &str =
     include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/OPTIONS_HELP.txt"));
@@ -39,15 +40,22 @@ pub struct Options {
     pub y_tilt_range: f32,
     pub x_tilt_offset: f32,
     pub y_tilt_offset: f32,
+    /// SDL key names for tilting up, left, down and right.
+    pub tilt_keys: Option<[String; 4]>,
     pub button_to_touch: HashMap<Button, (f32, f32)>,
     pub stabilize_virtual_cursor: Option<(f32, f32)>,
     pub gles1_implementation: Option<GLESImplementation>,
     pub direct_memory_access: bool,
     pub gdb_listen_addrs: Option<Vec<SocketAddr>>,
     pub preferred_languages: Option<Vec<String>>,
+    pub system_version: String,
     pub headless: bool,
     pub print_fps: bool,
     pub fps_limit: Option<f64>,
+    pub network_access: bool,
+    pub photo_library_dir: Option<PathBuf>,
+    pub camera_image: Option<PathBuf>,
+    pub background_on_focus_loss: bool,
 }
 
 impl Default for Options {
@@ -61,15 +69,21 @@ impl Default for Options {
             y_tilt_range: 60.0,
             x_tilt_offset: 0.0,
             y_tilt_offset: 0.0,
+            tilt_keys: None,
             button_to_touch: HashMap::new(),
             stabilize_virtual_cursor: None,
             gles1_implementation: None,
             direct_memory_access: true,
             gdb_listen_addrs: None,
             preferred_languages: None,
+            system_version: "2.0".to_string(),
             headless: false,
             print_fps: false,
             fps_limit: Some(60.0), // Original iPhone is 60Hz and uses v-sync
+            network_access: false,
+            photo_library_dir: None,
+            camera_image: None,
+            background_on_focus_loss: true,
         }
     }
 }
@@ -109,6 +123,15 @@ impl Options {
             self.x_tilt_offset = parse_degrees(value, "X tilt offset")?;
         } else if let Some(value) = arg.strip_prefix("--y-tilt-offset=") {
             self.y_tilt_offset = parse_degrees(value, "Y tilt offset")?;
+        } else if let Some(value) = arg.strip_prefix("--tilt-keys=") {
+            let keys: Vec<String> = value.split(',').map(|key| key.trim().to_owned()).collect();
+            if keys.iter().any(|key| key.is_empty()) {
+                return Err("Empty key name in --tilt-keys=".to_string());
+            }
+            let keys: [String; 4] = keys
+                .try_into()
+                .map_err(|_| "--tilt-keys= requires four key names".to_string())?;
+            self.tilt_keys = Some(keys);
         } else if let Some(values) = arg.strip_prefix("--button-to-touch=") {
             let (button, coords) = values
                 .split_once(',')
@@ -169,6 +192,15 @@ impl Options {
             self.gdb_listen_addrs = Some(addrs);
         } else if let Some(value) = arg.strip_prefix("--preferred-languages=") {
             self.preferred_languages = Some(value.split(',').map(ToOwned::to_owned).collect());
+        } else if let Some(value) = arg.strip_prefix("--system-version=") {
+            if value.is_empty()
+                || !value
+                    .split('.')
+                    .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+            {
+                return Err("Invalid value for --system-version=".to_string());
+            }
+            self.system_version = value.to_string();
         } else if arg == "--headless" {
             self.headless = true;
         } else if arg == "--print-fps" {
@@ -184,6 +216,14 @@ impl Options {
                     .ok_or_else(|| "Invalid value for --fps-limit=".to_string())?;
                 self.fps_limit = Some(limit);
             }
+        } else if arg == "--network-access" {
+            self.network_access = true;
+        } else if let Some(value) = arg.strip_prefix("--photo-library=") {
+            self.photo_library_dir = Some(PathBuf::from(value));
+        } else if let Some(value) = arg.strip_prefix("--camera-image=") {
+            self.camera_image = Some(PathBuf::from(value));
+        } else if arg == "--no-background-on-focus-loss" {
+            self.background_on_focus_loss = false;
         } else {
             return Ok(false);
         };
@@ -73,6 +73,10 @@ pub const RESOURCES_ARE_EXTERNAL_FILES: bool = cfg!(not(target_os = "android"));
 /// appear in the app picker.
 pub const APPS_DIR: &str = "touchHLE_apps";
 
+/// Name of the directory where the user can put images for apps to pick from
+/// the simulated photo library.
+pub const PHOTOS_DIR: &str = "touchHLE_photos";
+
 /// Name of the file intended for the user's own options.
 pub const USER_OPTIONS_FILE: &str = "touchHLE_options.txt";
 
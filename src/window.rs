@@ -17,6 +17,7 @@ use crate::gles::{create_gles1_ctx, GLES};
 use crate::image::Image;
 use crate::matrix::Matrix;
 use crate::options::Options;
+use sdl2::event::WindowEvent;
 use sdl2::mouse::MouseButton;
 use sdl2::pixels::PixelFormatEnum;
 use sdl2::surface::Surface;
@@ -90,9 +91,31 @@ pub enum Event {
     TouchesDown(HashMap<FingerId, Coords>),
     TouchesMove(HashMap<FingerId, Coords>),
     TouchesUp(HashMap<FingerId, Coords>),
+    /// User pressed Escape (or the Back button on Android), e.g. to dismiss an
+    /// alert.
+    Escape,
+    /// User typed something while text input was active (see
+    /// [Window::start_text_input]).
+    TextInput(TextInputEvent),
     /// User pressed F12, requesting that execution be paused and the debugger
     /// take over.
     EnterDebugger,
+    /// The window lost input focus, e.g. because the user switched to another
+    /// window.
+    FocusLost,
+    /// The window regained input focus.
+    FocusGained,
+    /// User pressed F11, requesting that the app be paused, or resumed if it is
+    /// already paused.
+    TogglePause,
+}
+
+#[derive(Debug)]
+pub enum TextInputEvent {
+    /// Text was typed or pasted.
+    Text(String),
+    Backspace,
+    Return,
 }
 
 pub enum GLVersion {
@@ -152,6 +175,9 @@ pub struct Window {
     controllers: Vec<sdl2::controller::GameController>,
     _sensor_ctx: sdl2::SensorSubsystem,
     accelerometer: Option<sdl2::sensor::Sensor>,
+    /// Keys for tilting up, left, down and right, see `--tilt-keys=`.
+    tilt_keys: Option<[sdl2::keyboard::Keycode; 4]>,
+    tilt_keys_pressed: [bool; 4],
     virtual_cursor_last: Option<(f32, f32, bool, bool)>,
     virtual_cursor_last_unsticky: Option<(f32, f32, Instant)>,
 }
@@ -265,9 +291,25 @@ impl Window {
             }
         }
 
+        let tilt_keys = options.tilt_keys.as_ref().and_then(|names| {
+            let keys: Option<Vec<_>> = names
+                .iter()
+                .map(|name| sdl2::keyboard::Keycode::from_name(name))
+                .collect();
+            if keys.is_none() {
+                log!("Warning: Unrecognized key name in --tilt-keys=, ignoring it.");
+            }
+            keys.map(|keys| keys.try_into().unwrap())
+        });
+
         #[cfg(target_os = "macos")]
         let max_height = window.size().1;
 
+        // SDL2 enables text input by default on desktop platforms, but it
+        // should only be enabled while a text field is being edited, since on
+        // some platforms it shows the on-screen keyboard.
+        video_ctx.text_input().stop();
+
         let mut window = Window {
             _sdl_ctx: sdl_ctx,
             video_ctx,
@@ -291,6 +333,8 @@ impl Window {
             controllers: Vec::new(),
             _sensor_ctx: sensor_ctx,
             accelerometer,
+            tilt_keys,
+            tilt_keys_pressed: [false; 4],
             virtual_cursor_last: None,
             virtual_cursor_last_unsticky: None,
         };
@@ -563,6 +607,43 @@ impl Window {
                         _ => unreachable!(),
                     }
                 }
+                E::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } if !self.video_ctx.text_input().is_active()
+                    && self.tilt_key_index(keycode).is_some() =>
+                {
+                    let index = self.tilt_key_index(keycode).unwrap();
+                    self.tilt_keys_pressed[index] = true;
+                    continue;
+                }
+                E::KeyUp {
+                    keycode: Some(keycode),
+                    ..
+                } if self.tilt_key_index(keycode).is_some() => {
+                    let index = self.tilt_key_index(keycode).unwrap();
+                    self.tilt_keys_pressed[index] = false;
+                    continue;
+                }
+                E::KeyDown {
+                    keycode:
+                        Some(sdl2::keyboard::Keycode::Escape | sdl2::keyboard::Keycode::AcBack),
+                    ..
+                } => Event::Escape,
+                E::TextInput { text, .. } => Event::TextInput(TextInputEvent::Text(text)),
+                E::KeyDown {
+                    keycode: Some(sdl2::keyboard::Keycode::Backspace),
+                    ..
+                } if self.video_ctx.text_input().is_active() => {
+                    Event::TextInput(TextInputEvent::Backspace)
+                }
+                E::KeyDown {
+                    keycode:
+                        Some(sdl2::keyboard::Keycode::Return | sdl2::keyboard::Keycode::KpEnter),
+                    ..
+                } if self.video_ctx.text_input().is_active() => {
+                    Event::TextInput(TextInputEvent::Return)
+                }
                 E::KeyDown {
                     keycode: Some(sdl2::keyboard::Keycode::F12),
                     ..
@@ -572,6 +653,19 @@ impl Window {
                     echo!("F12 pressed, EnterDebugger event queued.");
                     Event::EnterDebugger
                 }
+                E::KeyDown {
+                    keycode: Some(sdl2::keyboard::Keycode::F11),
+                    repeat: false,
+                    ..
+                } => Event::TogglePause,
+                E::Window {
+                    win_event: WindowEvent::FocusLost,
+                    ..
+                } => Event::FocusLost,
+                E::Window {
+                    win_event: WindowEvent::FocusGained,
+                    ..
+                } => Event::FocusGained,
                 _ => continue,
             })
         }
@@ -628,6 +722,30 @@ impl Window {
         let controller = self.controllers.remove(idx);
         log!("Warning: Controller disconnected: {}", controller.name());
     }
+
+    /// Make connected controllers vibrate for a while, e.g. to simulate the
+    /// device's vibration motor. Controllers without rumble support are
+    /// ignored.
+    pub fn rumble(&mut self, duration: Duration) {
+        let duration_ms = duration.as_millis().try_into().unwrap_or(u32::MAX);
+        for controller in &mut self.controllers {
+            if let Err(e) = controller.set_rumble(0xFFFF, 0xFFFF, duration_ms) {
+                log_dbg!("Couldn't rumble controller {}: {}", controller.name(), e);
+            }
+        }
+    }
+
+    fn tilt_key_index(&self, keycode: sdl2::keyboard::Keycode) -> Option<usize> {
+        self.tilt_keys?.iter().position(|&key| key == keycode)
+    }
+
+    /// Get the simulated analog stick input from the `--tilt-keys=` keys. The
+    /// range is [-1, 1] on each axis.
+    fn get_tilt_keys_stick(&self) -> (f32, f32) {
+        let [up, left, down, right] = self.tilt_keys_pressed.map(|pressed| pressed as u8 as f32);
+        (right - left, down - up)
+    }
+
     pub fn print_accelerometer_notice(&self) {
         log!("This app uses the accelerometer.");
         if !self.controllers.is_empty() {
@@ -638,15 +756,20 @@ impl Window {
         } else if self.accelerometer.is_some() {
             log!("Your device's accelerometer will be used for accelerometer simulation.");
             log!("Connect a controller if you would prefer to use an analog stick.");
+        } else if self.tilt_keys.is_some() {
+            log!("The keys set with --tilt-keys= will be used for accelerometer simulation.");
+            log!("Connect a controller if you would prefer to use an analog stick.");
         } else if self.controllers.is_empty() {
-            log!("Connect a controller to get accelerometer simulation.");
+            log!("Connect a controller, or use --tilt-keys=, to get accelerometer simulation.");
         }
     }
 
     /// Get the real or simulated accelerometer output.
     /// See also [crate::frameworks::uikit::ui_accelerometer].
     pub fn get_acceleration(&self, options: &Options) -> (f32, f32, f32) {
-        if self.controllers.is_empty() {
+        // The tilt keys take precedence over the device's accelerometer only
+        // while they are held, so that both can be used.
+        if self.controllers.is_empty() && !self.tilt_keys_pressed.contains(&true) {
             if let Some(ref accelerometer) = self.accelerometer {
                 let data = accelerometer.get_data().unwrap();
                 let sdl2::sensor::SensorData::Accel(data) = data else {
@@ -666,6 +789,8 @@ impl Window {
 
         // Get left analog stick input. The range is [-1, 1] on each axis.
         let (x, y, _) = self.get_controller_stick(options, true);
+        let (key_x, key_y) = self.get_tilt_keys_stick();
+        let (x, y) = (x + key_x, y + key_y);
 
         // Correct for window rotation
         let [x, y] = self.rotation_matrix().transform([x, y]);
@@ -1101,6 +1226,37 @@ impl Window {
         }
     }
 
+    /// Start accepting text input from the keyboard, for example because a text
+    /// field has been tapped. This shows the on-screen keyboard on devices
+    /// that have one.
+    pub fn start_text_input(&mut self) {
+        self.video_ctx.text_input().start();
+    }
+    pub fn stop_text_input(&mut self) {
+        self.video_ctx.text_input().stop();
+    }
+
+    /// Get the text on the host's clipboard, if there is any.
+    pub fn get_clipboard_text(&self) -> Option<String> {
+        let clipboard = self.video_ctx.clipboard();
+        if !clipboard.has_clipboard_text() {
+            return None;
+        }
+        match clipboard.clipboard_text() {
+            Ok(text) => Some(text),
+            Err(e) => {
+                log!("Warning: Couldn't get clipboard text: {}", e);
+                None
+            }
+        }
+    }
+    /// Replace the contents of the host's clipboard with some text.
+    pub fn set_clipboard_text(&mut self, text: &str) {
+        if let Err(e) = self.video_ctx.clipboard().set_clipboard_text(text) {
+            log!("Warning: Couldn't set clipboard text: {}", e);
+        }
+    }
+
     pub fn is_screen_saver_enabled(&self) -> bool {
         self.video_ctx.is_screen_saver_enabled()
     }
@@ -22,10 +22,14 @@
 // <stddef.h>
 #define NULL ((void *)0)
 typedef unsigned long size_t;
+#define SIZE_MAX 4294967295UL
 
 // <errno.h>
 int *__error(void);
 #define errno (*__error())
+#define EBADF 9
+#define EINVAL 22
+#define ERANGE 34
 
 // <stdarg.h>
 typedef __builtin_va_list va_list;
@@ -37,9 +41,20 @@ typedef __builtin_va_list va_list;
 typedef struct FILE FILE;
 FILE *fopen(const char *, const char *);
 int fclose(FILE *);
+size_t fread(void *, size_t, size_t, FILE *);
+int ferror(FILE *);
+void clearerr(FILE *);
+int remove(const char *);
 int sscanf(const char *, const char *, ...);
+int fscanf(FILE *, const char *, ...);
 int printf(const char *, ...);
+int fprintf(FILE *, const char *, ...);
 int vsnprintf(char *, size_t, const char *, va_list);
+int asprintf(char **, const char *, ...);
+
+// <wchar.h>
+typedef int wchar_t;
+int swprintf(wchar_t *, size_t, const wchar_t *, ...);
 
 // <stdlib.h>
 #define EXIT_SUCCESS 0
@@ -48,9 +63,27 @@ void exit(int);
 void free(void *);
 void *malloc(size_t);
 void qsort(void *, size_t, size_t, int (*)(const void *, const void *));
+void *bsearch(const void *, const void *, size_t, size_t,
+              int (*)(const void *, const void *));
+void *calloc(size_t, size_t);
+#define RAND_MAX 0x7fffffff
+int rand(void);
+void srand(unsigned);
+long random(void);
+void srandom(unsigned);
 void *realloc(void *, size_t);
+size_t malloc_size(const void *);
+char *getenv(const char *);
+int setenv(const char *, const char *, int);
+int unsetenv(const char *);
+int putenv(char *);
 double atof(const char *);
 float strtof(const char *, char **);
+double strtod(const char *, char **);
+long strtol(const char *, char **, int);
+unsigned long strtoul(const char *, char **, int);
+long long strtoll(const char *, char **, int);
+unsigned long long strtoull(const char *, char **, int);
 
 // <string.h>
 void *memset(void *, int, size_t);
@@ -59,6 +92,11 @@ void *memmove(void *, const void *, size_t);
 int strcmp(const char *, const char *);
 char *strncpy(char *, const char *, size_t);
 char *strncat(char *, const char *, size_t);
+char *strtok(char *, const char *);
+char *strtok_r(char *, const char *, char **);
+char *strstr(const char *, const char *);
+char *strcasestr(const char *, const char *);
+void *memmem(const void *, size_t, const void *, size_t);
 
 // <unistd.h>
 typedef unsigned int __uint32_t;
@@ -70,6 +108,42 @@ int usleep(useconds_t);
 // <fcntl.h>
 #define O_CREAT 0x00000200
 
+// <time.h>
+typedef long time_t;
+struct tm {
+  int tm_sec;
+  int tm_min;
+  int tm_hour;
+  int tm_mday;
+  int tm_mon;
+  int tm_year;
+  int tm_wday;
+  int tm_yday;
+  int tm_isdst;
+  long tm_gmtoff;
+  char *tm_zone;
+};
+time_t mktime(struct tm *);
+size_t strftime(char *, size_t, const char *, const struct tm *);
+struct tm *gmtime_r(const time_t *, struct tm *);
+time_t time(time_t *);
+struct timespec {
+  time_t tv_sec;
+  long tv_nsec;
+};
+typedef unsigned int clockid_t;
+#define CLOCK_REALTIME 0
+#define CLOCK_MONOTONIC 6
+int clock_gettime(clockid_t, struct timespec *);
+int nanosleep(const struct timespec *, struct timespec *);
+
+// <sys/time.h>
+struct timeval {
+  time_t tv_sec;
+  int tv_usec;
+};
+int gettimeofday(struct timeval *, void *);
+
 // <pthread.h>
 typedef struct opaque_pthread_t opaque_pthread_t;
 typedef struct opaque_pthread_t *__pthread_t;
@@ -94,6 +168,10 @@ int sem_wait(sem_t *);
 
 int int_compar(const void *a, const void *b) { return *(int *)a - *(int *)b; }
 
+int str3_compar(const void *a, const void *b) {
+  return memcmp(a, b, 3);
+}
+
 int sort_and_check(int nel, int *arr, int *expected_arr) {
   qsort(arr, nel, sizeof(int), &int_compar);
   return memcmp(arr, expected_arr, nel * sizeof(int));
@@ -117,6 +195,45 @@ int test_qsort() {
       sort_and_check(5, (int[]){1, -1, 2, 1024, 4}, (int[]){-1, 1, 2, 4, 1024});
   if (res != 0)
     return -1;
+  // duplicates
+  res = sort_and_check(6, (int[]){3, 1, 3, 2, 1, 3}, (int[]){1, 1, 2, 3, 3, 3});
+  if (res != 0)
+    return -1;
+  // already sorted and reverse sorted, larger size
+  int sorted[100], reversed[100];
+  for (int i = 0; i < 100; i++) {
+    sorted[i] = i;
+    reversed[i] = 99 - i;
+  }
+  if (sort_and_check(100, sorted, sorted) != 0)
+    return -1;
+  res = sort_and_check(100, reversed, sorted);
+  if (res != 0)
+    return -1;
+  // element size that isn't a multiple of the word size
+  char strs[][3] = {"dd", "bb", "cc", "aa", "ee"};
+  qsort(strs, 5, 3, &str3_compar);
+  if (memcmp(strs, "aa\0bb\0cc\0dd\0ee\0", 15) != 0)
+    return -1;
+  return 0;
+}
+
+int test_bsearch() {
+  int arr[] = {-5, 0, 1, 3, 8, 13, 21};
+  int nel = sizeof(arr) / sizeof(arr[0]);
+  for (int i = 0; i < nel; i++) {
+    int *found = bsearch(&arr[i], arr, nel, sizeof(int), &int_compar);
+    if (found != &arr[i])
+      return -1;
+  }
+  int missing[] = {-6, 2, 9, 22};
+  for (int i = 0; i < 4; i++) {
+    if (bsearch(&missing[i], arr, nel, sizeof(int), &int_compar) != NULL)
+      return -2;
+  }
+  // empty array
+  if (bsearch(&arr[0], arr, 0, sizeof(int), &int_compar) != NULL)
+    return -3;
   return 0;
 }
 
@@ -140,6 +257,19 @@ int test_vsnprintf() {
   str = str_format("%s", "test");
   res += !!strcmp(str, "test");
   free(str);
+  // Test %s with width and precision
+  str = str_format("%.3s|%5s|%-5s|%5.2s|%.10s", "hello", "ab", "ab", "xyz",
+                   "short");
+  res += !!strcmp(str, "hel|   ab|ab   |   xy|short");
+  free(str);
+  // Test %c with width
+  str = str_format("%c|%3c|%-3c|%.5c", 'a', 'b', 'c', 'd');
+  res += !!strcmp(str, "a|  b|c  |d");
+  free(str);
+  // Test %C with surrogate pairs
+  str = str_format("%C|%C%C|%C|", 0xE9, 0xD83D, 0xDE00, 0xD83D);
+  res += !!strcmp(str, "\xC3\xA9|\xF0\x9F\x98\x80|\xEF\xBF\xBD|");
+  free(str);
   // Test %s NULL
   str = str_format("%s", NULL);
   res += !!strcmp(str, "(null)");
@@ -162,10 +292,140 @@ int test_vsnprintf() {
   res += !!strcmp(str, "10.123450|10.123450|10.123450|10|      10|10.123|  "
                        "10.123|0010.123|10.123450|10.123450");
   free(str);
+  // Test left-justify flag
+  str = str_format("%-5d|%-05d|%-*d|%-6s|%-8.3f|%-4x", -5, 5, 3, 5, "ab",
+                   10.12345, 255);
+  res += !!strcmp(str, "-5   |5    |5  |ab    |10.123  |ff  ");
+  free(str);
+  // Test sign flags
+  str = str_format("%+d|% d|%+ d|%+d|%+4d|%+.1f|% 06.1f", 5, 5, 5, -5, 5, 1.25,
+                   1.25);
+  res += !!strcmp(str, "+5| 5|+5|-5|  +5|+1.2| 001.2");
+  free(str);
+  // Test alternate form flag
+  str = str_format("%#x|%#X|%#08x|%#x|%o|%#o|%#.f", 255, 255, 255, 0, 8, 8, 3.0);
+  res += !!strcmp(str, "0xff|0XFF|0x0000ff|0|10|010|3.");
+  free(str);
+  // Test %e
+  str = str_format("%e|%E|%.2e|%e|%12.3e|%-+10.1e|%#.0e", 0.0, 1234.5, -0.00123,
+                   1e100, 15.0, 2.0, 3.0);
+  res += !!strcmp(str, "0.000000e+00|1.234500E+03|-1.23e-03|1.000000e+100|"
+                       "   1.500e+01|+2.0e+00  |3.e+00");
+  free(str);
+  // Test %g
+  str = str_format("%g|%g|%g|%G|%g|%.0g|%.3g|%#g|%8g", 100000.0, 0.0001,
+                   0.00001, 1e20, 0.0, 25.0, 3.14159, 1.5, -2.5);
+  res += !!strcmp(str, "100000|0.0001|1e-05|1E+20|0|2e+01|3.14|1.50000|    -2.5");
+  free(str);
+  // Test 64-bit length modifiers
+  str = str_format("%lld|%llu|%qd|%llx|%d", -5000000000LL,
+                   18446744073709551615ULL, 1LL, 0x123456789abcdefULL, 7);
+  res += !!strcmp(str, "-5000000000|18446744073709551615|1|123456789abcdef|7");
+  free(str);
+  // Test char and short length modifiers
+  str = str_format("%hhu|%hhd|%hu|%hd|%hhx|%hX", 300, 255, 65537, 40000, 0x1ff,
+                   0x12345);
+  res += !!strcmp(str, "44|-1|1|-25536|ff|2345");
+  free(str);
+  // Test %n
+  int written = 0;
+  long long written_ll = 0;
+  str = str_format("abc%n|%5d%lln", &written, 1, &written_ll);
+  res += !!strcmp(str, "abc|    1");
+  res += written != 3;
+  res += written_ll != 9;
+  free(str);
+  // Test positional arguments
+  str = str_format("%2$s|%1$d|%3$.1f|%2$s|%4$lld", 7, "ab", 2.5, 10000000000LL);
+  res += !!strcmp(str, "ab|7|2.5|ab|10000000000");
+  free(str);
+  // Test variable precision
+  str = str_format("%.*f|%.*s|%.*d|%*.*f", 2, 1.5, 1, "ab", -1, 5, 6, 1, 2.5);
+  res += !!strcmp(str, "1.50|a|5|   2.5");
+  free(str);
+  // Test variable width and precision with positional arguments
+  str = str_format("%2$*1$d|%3$.*1$s|%4$.1f", 1, 42, "x", 2.5);
+  res += !!strcmp(str, "42|x|2.5");
+  free(str);
+  // Test asprintf
+  str = NULL;
+  res += asprintf(&str, "%d-%s", 42, "abc") != 6;
+  res += str == NULL || !!strcmp(str, "42-abc");
+  free(str);
 
   return res;
 }
 
+int test_swprintf() {
+  wchar_t buf[16];
+  int res = swprintf(buf, 16, L"%d|%ls|%lc|%s|%3ls", 42, L"ab", L'c', "de",
+                     L"f");
+  if (res != 14 || memcmp(buf, L"42|ab|c|de|  f", 15 * sizeof(wchar_t)))
+    return -1;
+  res = swprintf(buf, 4, L"abcdef");
+  if (res != -1 || memcmp(buf, L"abc", 4 * sizeof(wchar_t)))
+    return -2;
+  return 0;
+}
+
+#define DOCUMENTS_DIR                                                          \
+  "/var/mobile/Applications/00000000-0000-0000-0000-000000000000/Documents"
+
+int test_fprintf() {
+  const char *path = DOCUMENTS_DIR "/test_fprintf.txt";
+  FILE *file = fopen(path, "w");
+  if (!file)
+    return -1;
+  int res = fprintf(file, "%d-%s\n", 42, "abc");
+  fclose(file);
+  if (res != 7)
+    return -2;
+
+  char buf[16];
+  file = fopen(path, "r");
+  if (!file)
+    return -3;
+  size_t n_read = fread(buf, 1, sizeof buf, file);
+  if (n_read != 7 || memcmp(buf, "42-abc\n", 7)) {
+    fclose(file);
+    return -4;
+  }
+
+  // Writing to a file that was opened for reading fails.
+  errno = 0;
+  res = fprintf(file, "%d", 42);
+  int error = errno;
+  fclose(file);
+  remove(path);
+  if (res >= 0 || error != EBADF)
+    return -5;
+  return 0;
+}
+
+int test_ferror() {
+  const char *path = DOCUMENTS_DIR "/test_ferror.txt";
+  FILE *file = fopen(path, "w");
+  if (!file)
+    return -1;
+  fclose(file);
+
+  file = fopen(path, "r");
+  if (!file)
+    return -2;
+  int res = 0;
+  res += !!ferror(file);
+  // Writing to a file that was opened for reading sets the error indicator,
+  // which stays set until it's cleared.
+  res += fprintf(file, "%d", 42) >= 0;
+  res += !ferror(file);
+  res += !ferror(file);
+  clearerr(file);
+  res += !!ferror(file);
+  fclose(file);
+  remove(path);
+  return res;
+}
+
 int test_sscanf() {
   int a, b;
   short c;
@@ -185,9 +445,92 @@ int test_sscanf() {
   matched = sscanf("9,10", "%hi,%i", &c, &a);
   if (!(c == 9 && a == 10))
     return -1;
+  matched = sscanf("010 0x1F -0X1f +12 -7", "%i %i %i %i %d", &a, &b, &a, &b,
+                   &a);
+  if (!(matched == 5 && a == -7 && b == 12))
+    return -1;
+  matched = sscanf("010,0x1F", "%i,%i", &a, &b);
+  if (!(matched == 2 && a == 8 && b == 31))
+    return -1;
+  float x, y, z;
+  double w;
+  matched = sscanf("1.5 -2 .25e2 3e-1x", "%f %f %f %lf", &x, &y, &z, &w);
+  if (!(matched == 4 && x == 1.5 && y == -2.0 && z == 25.0 && w == 0.3))
+    return -1;
+  matched = sscanf("7e", "%f", &x);
+  if (!(matched == 1 && x == 7.0))
+    return -1;
+  char word1[8], word2[8];
+  matched = sscanf("  hello \t world!  42", "%s%s%d", word1, word2, &a);
+  if (!(matched == 3 && strcmp(word1, "hello") == 0 &&
+        strcmp(word2, "world!") == 0 && a == 42))
+    return -1;
+  matched = sscanf("1   ,\n2", "%d , %d", &a, &b);
+  if (!(matched == 2 && a == 1 && b == 2))
+    return -1;
+  int year, month, day;
+  matched = sscanf("20240115", "%4d%2d%2d", &year, &month, &day);
+  if (!(matched == 3 && year == 2024 && month == 1 && day == 15))
+    return -1;
+  matched = sscanf("  abcdefg 1.2345", "%3s%s%3f", word1, word2, &x);
+  if (!(matched == 3 && strcmp(word1, "abc") == 0 &&
+        strcmp(word2, "defg") == 0 && x == 1.2f))
+    return -1;
+  char token[16];
+  matched = sscanf("Foo_42-bar", "%[A-Za-z0-9_]-%3[a-z]", token, word1);
+  if (!(matched == 2 && strcmp(token, "Foo_42") == 0 &&
+        strcmp(word1, "bar") == 0))
+    return -1;
+  matched = sscanf("]-x]", "%[]-]", token);
+  if (!(matched == 1 && strcmp(token, "]-") == 0))
+    return -1;
+  matched = sscanf("abc", "%[^]]", token);
+  if (!(matched == 1 && strcmp(token, "abc") == 0))
+    return -1;
+  matched = sscanf("abc", "%[^,],%d", token, &b);
+  if (!(matched == 1 && strcmp(token, "abc") == 0))
+    return -1;
+  matched = sscanf(",5", "%[^,],%d", token, &b);
+  if (matched != 0)
+    return -1;
+  matched = sscanf("", "%[^,]", token);
+  if (matched != -1)
+    return -1;
+  matched = sscanf("", "%d", &a);
+  if (matched != -1)
+    return -1;
+  matched = sscanf("5", "%d,%d", &a, &b);
+  if (!(matched == 1 && a == 5))
+    return -1;
   return 0;
 }
 
+int test_fscanf() {
+  const char *path = DOCUMENTS_DIR "/test_fscanf.txt";
+  FILE *file = fopen(path, "w");
+  if (!file)
+    return -1;
+  fprintf(file, "12 34x5");
+  fclose(file);
+
+  file = fopen(path, "r");
+  if (!file)
+    return -2;
+  int res = 0;
+  int a, b;
+  // The byte after each number has to be looked at, but isn't consumed, so
+  // the next read must still see it.
+  res += fscanf(file, "%d", &a) != 1 || a != 12;
+  res += fscanf(file, "%d", &b) != 1 || b != 34;
+  res += fscanf(file, "%d", &a) != 0;
+  char buf[4];
+  res += fread(buf, 1, sizeof buf, file) != 2 || memcmp(buf, "x5", 2);
+  res += fscanf(file, "%d", &a) != -1;
+  fclose(file);
+  remove(path);
+  return res;
+}
+
 int test_errno() { return (errno == 0) ? 0 : -1; }
 
 int test_realloc() {
@@ -196,7 +539,78 @@ int test_realloc() {
   ptr = realloc(ptr, 64);
   int res = memcmp(ptr, "abcd", 4);
   free(ptr);
-  return res == 0 ? 0 : -1;
+  if (res != 0)
+    return -1;
+
+  // Grow, then shrink, checking the preserved bytes each time
+  char *buf = malloc(20);
+  for (int i = 0; i < 20; i++)
+    buf[i] = i;
+  buf = realloc(buf, 100);
+  if (buf == NULL || malloc_size(buf) < 100)
+    return -2;
+  for (int i = 0; i < 20; i++) {
+    if (buf[i] != i)
+      return -3;
+  }
+  buf = realloc(buf, 8);
+  if (buf == NULL || malloc_size(buf) < 8)
+    return -4;
+  for (int i = 0; i < 8; i++) {
+    if (buf[i] != i)
+      return -5;
+  }
+  // Zero size gives a minimal allocation that can be freed
+  buf = realloc(buf, 0);
+  free(buf);
+  return 0;
+}
+
+int test_calloc() {
+  if (calloc(SIZE_MAX, 2) != NULL)
+    return -1;
+  if (calloc(2, SIZE_MAX / 2 + 1) != NULL)
+    return -2;
+  unsigned char *ptr = calloc(16, 4);
+  if (ptr == NULL)
+    return -3;
+  for (int i = 0; i < 64; i++) {
+    if (ptr[i] != 0) {
+      free(ptr);
+      return -4;
+    }
+  }
+  free(ptr);
+  return 0;
+}
+
+int test_rand() {
+  int first[10];
+  srand(1234);
+  for (int i = 0; i < 10; i++) {
+    first[i] = rand();
+    if (first[i] < 0 || first[i] > RAND_MAX)
+      return -1;
+  }
+  // Same seed, same sequence
+  srand(1234);
+  for (int i = 0; i < 10; i++) {
+    if (rand() != first[i])
+      return -2;
+  }
+  // Different seed, different sequence
+  srand(4321);
+  if (rand() == first[0] && rand() == first[1])
+    return -3;
+  // random() has its own state
+  srandom(1234);
+  long r = random();
+  srand(1);
+  rand();
+  srandom(1234);
+  if (random() != r || r < 0 || r > RAND_MAX)
+    return -4;
+  return 0;
 }
 
 int test_atof() {
@@ -341,6 +755,257 @@ int test_strtof() {
   return 0;
 }
 
+int test_strtol() {
+  char *text = "  -123abc";
+  char *endptr;
+  if (strtol(text, &endptr, 10) != -123 || endptr != text + 6)
+    return -1;
+  text = "0x1fg";
+  if (strtol(text, &endptr, 0) != 31 || endptr != text + 4)
+    return -2;
+  text = "017";
+  if (strtol(text, &endptr, 0) != 15 || endptr != text + 3)
+    return -3;
+  text = "0xz";
+  if (strtol(text, &endptr, 16) != 0 || endptr != text + 1)
+    return -4;
+  text = "zzz";
+  if (strtol(text, &endptr, 36) != 46655 || endptr != text + 3)
+    return -5;
+  text = "  +";
+  if (strtol(text, &endptr, 10) != 0 || endptr != text)
+    return -6;
+  errno = 0;
+  if (strtol("99999999999", NULL, 10) != 2147483647 || errno != ERANGE)
+    return -7;
+  errno = 0;
+  if (strtol("-99999999999", NULL, 10) != -2147483647 - 1 || errno != ERANGE)
+    return -8;
+  errno = 0;
+  if (strtoul("-1", NULL, 10) != 4294967295UL || errno != 0)
+    return -9;
+  if (strtoul("4294967296", NULL, 10) != 4294967295UL || errno != ERANGE)
+    return -10;
+  errno = 0;
+  if (strtoll("-9223372036854775808", NULL, 10) != -9223372036854775807LL - 1 ||
+      errno != 0)
+    return -11;
+  if (strtoull("ffffffffffffffff", NULL, 16) != 18446744073709551615ULL)
+    return -12;
+  return 0;
+}
+
+int test_strtod() {
+  char *text = "  -0x1.8p1xyz";
+  char *endptr;
+  if (strtod(text, &endptr) != -3.0 || endptr != text + 10)
+    return -1;
+  text = "0xg";
+  if (strtod(text, &endptr) != 0.0 || endptr != text + 1)
+    return -2;
+  text = "INFINITY";
+  if (strtod(text, &endptr) != 1.0 / 0.0 || endptr != text + 8)
+    return -3;
+  text = "-infinite";
+  if (strtod(text, &endptr) != -1.0 / 0.0 || endptr != text + 4)
+    return -4;
+  text = "nan(123)";
+  double nan = strtod(text, &endptr);
+  if (nan == nan || endptr != text + 8)
+    return -5;
+  text = "1.5e+2e";
+  if (strtod(text, &endptr) != 150.0 || endptr != text + 6)
+    return -6;
+  text = "2e";
+  if (strtod(text, &endptr) != 2.0 || endptr != text + 1)
+    return -7;
+  text = ".";
+  if (strtod(text, &endptr) != 0.0 || endptr != text)
+    return -8;
+  errno = 0;
+  if (strtod("1e999", NULL) != 1.0 / 0.0 || errno != ERANGE)
+    return -9;
+  errno = 0;
+  if (strtod("1e-999", NULL) != 0.0 || errno != ERANGE)
+    return -10;
+  errno = 0;
+  if (strtod("0e-999", NULL) != 0.0 || errno != 0)
+    return -11;
+  if (strtof("1e100", NULL) != 1.0f / 0.0f || errno != ERANGE)
+    return -12;
+  return 0;
+}
+
+int test_mktime() {
+  // 2005-14-20T00:-60:3600 is 2006-02-20T00:00:00 after normalization
+  struct tm date = {.tm_sec = 3600,
+                    .tm_min = -60,
+                    .tm_hour = 0,
+                    .tm_mday = 20,
+                    .tm_mon = 13,
+                    .tm_year = 105};
+  if (mktime(&date) != 1140393600)
+    return -1;
+  if (date.tm_year != 106 || date.tm_mon != 1 || date.tm_mday != 20 ||
+      date.tm_hour != 0 || date.tm_min != 0 || date.tm_sec != 0 ||
+      date.tm_wday != 1 || date.tm_yday != 50)
+    return -2;
+  char buf[32];
+  if (strftime(buf, sizeof buf, "%a %Y-%m-%d %H:%M:%S", &date) != 23 ||
+      strcmp(buf, "Mon 2006-02-20 00:00:00") != 0)
+    return -3;
+  // Doesn't fit (including the null terminator)
+  if (strftime(buf, 23, "%a %Y-%m-%d %H:%M:%S", &date) != 0)
+    return -4;
+  // Round trip
+  time_t timestamp = 648910963;
+  struct tm date2;
+  gmtime_r(&timestamp, &date2);
+  if (mktime(&date2) != timestamp)
+    return -5;
+  return 0;
+}
+
+int test_clock_gettime() {
+  struct timespec a, b;
+  if (clock_gettime(CLOCK_MONOTONIC, &a) != 0)
+    return -1;
+  if (clock_gettime(CLOCK_MONOTONIC, &b) != 0)
+    return -2;
+  if (b.tv_sec < a.tv_sec || (b.tv_sec == a.tv_sec && b.tv_nsec < a.tv_nsec))
+    return -3;
+  if (a.tv_nsec < 0 || a.tv_nsec >= 1000000000)
+    return -4;
+  // The realtime clocks should all agree (allowing for a second ticking over)
+  if (clock_gettime(CLOCK_REALTIME, &a) != 0)
+    return -5;
+  struct timeval tv;
+  if (gettimeofday(&tv, NULL) != 0)
+    return -6;
+  time_t t = time(NULL);
+  if (tv.tv_sec < a.tv_sec || tv.tv_sec > a.tv_sec + 1 || t < tv.tv_sec ||
+      t > tv.tv_sec + 1)
+    return -7;
+  if (tv.tv_usec < 0 || tv.tv_usec >= 1000000)
+    return -8;
+  // Unknown clock
+  errno = 0;
+  if (clock_gettime(12345, &a) != -1 || errno != EINVAL)
+    return -9;
+  return 0;
+}
+
+int test_sleep() {
+  struct timespec before, after;
+  clock_gettime(CLOCK_MONOTONIC, &before);
+  struct timespec duration = {.tv_sec = 0, .tv_nsec = 2000000};
+  if (nanosleep(&duration, NULL) != 0)
+    return -1;
+  if (usleep(2000) != 0)
+    return -2;
+  clock_gettime(CLOCK_MONOTONIC, &after);
+  long long elapsed_ns = (after.tv_sec - before.tv_sec) * 1000000000LL +
+                         (after.tv_nsec - before.tv_nsec);
+  if (elapsed_ns < 4000000)
+    return -3;
+  struct timespec invalid = {.tv_sec = 0, .tv_nsec = 1000000000};
+  errno = 0;
+  if (nanosleep(&invalid, NULL) != -1 || errno != EINVAL)
+    return -4;
+  return 0;
+}
+
+int test_getenv_setenv() {
+  char *home = getenv("HOME");
+  if (home == NULL || home[0] != '/')
+    return -1;
+  if (getenv("TOUCHHLE_TEST_VAR") != NULL)
+    return -2;
+  if (setenv("TOUCHHLE_TEST_VAR", "a", 0) != 0 ||
+      strcmp(getenv("TOUCHHLE_TEST_VAR"), "a") != 0)
+    return -3;
+  // No overwrite
+  if (setenv("TOUCHHLE_TEST_VAR", "b", 0) != 0 ||
+      strcmp(getenv("TOUCHHLE_TEST_VAR"), "a") != 0)
+    return -4;
+  if (setenv("TOUCHHLE_TEST_VAR", "b", 1) != 0 ||
+      strcmp(getenv("TOUCHHLE_TEST_VAR"), "b") != 0)
+    return -5;
+  if (unsetenv("TOUCHHLE_TEST_VAR") != 0 ||
+      getenv("TOUCHHLE_TEST_VAR") != NULL)
+    return -6;
+  // putenv() uses the string itself
+  char str[] = "TOUCHHLE_TEST_VAR=c";
+  if (putenv(str) != 0 || strcmp(getenv("TOUCHHLE_TEST_VAR"), "c") != 0)
+    return -7;
+  str[18] = 'd';
+  if (strcmp(getenv("TOUCHHLE_TEST_VAR"), "d") != 0)
+    return -8;
+  unsetenv("TOUCHHLE_TEST_VAR");
+  errno = 0;
+  if (setenv("A=B", "c", 1) != -1 || errno != EINVAL)
+    return -9;
+  return 0;
+}
+
+int test_strtok() {
+  char str[] = ",,a,bb;;c,";
+  char *token = strtok(str, ",;");
+  if (token == NULL || strcmp(token, "a") != 0)
+    return -1;
+  token = strtok(NULL, ",;");
+  if (token == NULL || strcmp(token, "bb") != 0)
+    return -2;
+  token = strtok(NULL, ",;");
+  if (token == NULL || strcmp(token, "c") != 0)
+    return -3;
+  if (strtok(NULL, ",;") != NULL || strtok(NULL, ",;") != NULL)
+    return -4;
+
+  // Interleaved tokenizations with strtok_r
+  char str1[] = "1 2 3";
+  char str2[] = "x-y";
+  char *last1, *last2;
+  char *a = strtok_r(str1, " ", &last1);
+  char *b = strtok_r(str2, "-", &last2);
+  char *c = strtok_r(NULL, " ", &last1);
+  char *d = strtok_r(NULL, "-", &last2);
+  char *e = strtok_r(NULL, " ", &last1);
+  if (strcmp(a, "1") != 0 || strcmp(b, "x") != 0 || strcmp(c, "2") != 0 ||
+      strcmp(d, "y") != 0 || strcmp(e, "3") != 0)
+    return -5;
+  if (strtok_r(NULL, "-", &last2) != NULL ||
+      strtok_r(NULL, " ", &last1) != NULL)
+    return -6;
+  // Only separators
+  char str3[] = "---";
+  if (strtok_r(str3, "-", &last1) != NULL)
+    return -7;
+  return 0;
+}
+
+int test_strstr() {
+  const char *str = "Hello, world! Hello!";
+  if (strstr(str, "Hello") != str || strstr(str, "lo!") != str + 17)
+    return -1;
+  if (strstr(str, "") != str || strstr(str, "hello") != NULL ||
+      strstr("", "a") != NULL)
+    return -2;
+  if (strcasestr(str, "WORLD") != str + 7 || strcasestr(str, "") != str ||
+      strcasestr(str, "worlds") != NULL)
+    return -3;
+  // memmem() is not stopped by null bytes, and respects lengths
+  const char data[] = {'a', '\0', 'b', 'c', '\0', 'b', 'c', 'd'};
+  if (memmem(data, 8, "\0bc", 3) != data + 1 ||
+      memmem(data, 8, "bcd", 3) != data + 5)
+    return -4;
+  if (memmem(data, 7, "bcd", 3) != NULL || memmem(data, 2, "a\0b", 3) != NULL)
+    return -5;
+  if (memmem(data, 8, "xyz", 0) != data)
+    return -6;
+  return 0;
+}
+
 int test_getcwd_chdir() {
   char buf[256];
   char *buf2 = getcwd(buf, sizeof buf);
@@ -431,6 +1096,22 @@ int test_sem() {
   return shared_int == 1 ? 0 : -1;
 }
 
+int test_memmove() {
+  char buf[] = "abcdefgh";
+  // Overlapping, dest > src: a naive forward copy would repeat "a"
+  memmove(buf + 1, buf, 7);
+  if (memcmp(buf, "aabcdefg", 9) != 0)
+    return -1;
+  // Overlapping, dest < src
+  memmove(buf, buf + 2, 6);
+  if (memcmp(buf, "bcdefgfg", 9) != 0)
+    return -2;
+  // Result is dest
+  if (memmove(buf, buf, 8) != buf)
+    return -3;
+  return 0;
+}
+
 int test_strncpy() {
   char *src = "test\0abcd";
   char dst[10];
@@ -497,12 +1178,20 @@ struct {
   int (*func)();
   const char *name;
 } test_func_array[] = {
-    FUNC_DEF(test_qsort),   FUNC_DEF(test_vsnprintf),
-    FUNC_DEF(test_sscanf),  FUNC_DEF(test_errno),
-    FUNC_DEF(test_realloc), FUNC_DEF(test_atof),
-    FUNC_DEF(test_strtof),  FUNC_DEF(test_getcwd_chdir),
-    FUNC_DEF(test_sem),     FUNC_DEF(test_CGAffineTransform),
-    FUNC_DEF(test_strncpy), FUNC_DEF(test_strncat),
+    FUNC_DEF(test_qsort),         FUNC_DEF(test_vsnprintf),
+    FUNC_DEF(test_sscanf),        FUNC_DEF(test_errno),
+    FUNC_DEF(test_realloc),       FUNC_DEF(test_atof),
+    FUNC_DEF(test_strtof),        FUNC_DEF(test_getcwd_chdir),
+    FUNC_DEF(test_sem),           FUNC_DEF(test_CGAffineTransform),
+    FUNC_DEF(test_strncpy),       FUNC_DEF(test_strncat),
+    FUNC_DEF(test_fprintf),       FUNC_DEF(test_ferror),
+    FUNC_DEF(test_swprintf),      FUNC_DEF(test_strtol),
+    FUNC_DEF(test_strtod),        FUNC_DEF(test_bsearch),
+    FUNC_DEF(test_memmove),       FUNC_DEF(test_calloc),
+    FUNC_DEF(test_rand),          FUNC_DEF(test_mktime),
+    FUNC_DEF(test_clock_gettime), FUNC_DEF(test_sleep),
+    FUNC_DEF(test_getenv_setenv), FUNC_DEF(test_strtok),
+    FUNC_DEF(test_strstr),        FUNC_DEF(test_fscanf),
 };
 
 // Because no libc is linked into this executable, there is no libc entry point